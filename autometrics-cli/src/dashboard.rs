@@ -0,0 +1,100 @@
+use crate::sloth::MetricNaming;
+use autometrics::dashboards::Dashboard;
+use autometrics::objectives::{self, Objective};
+use autometrics::settings::{self, AutometricsSettings};
+use clap::Parser;
+use std::{
+    fs::{read_to_string, write},
+    path::PathBuf,
+};
+
+#[derive(Parser)]
+#[clap(group = clap::ArgGroup::new("rows").required(true).multiple(true))]
+pub struct Arguments {
+    /// The functions to add panels for.
+    ///
+    /// The names are the values of the `function` label, so they include the type name
+    /// for methods (like `Database::get_user`).
+    #[clap(long = "function", group = "rows")]
+    functions: Vec<String>,
+
+    /// A YAML file with the objectives to add panels for, in the format loaded by
+    /// `autometrics::objectives::from_yaml_str`.
+    #[clap(long, group = "rows")]
+    objectives: Option<PathBuf>,
+
+    /// The names of the objectives from the objectives file to add panels for.
+    ///
+    /// If not specified, all of the objectives in the file are added.
+    #[clap(long = "objective", requires = "objectives")]
+    objective_names: Vec<String>,
+
+    /// The title of the dashboard.
+    #[clap(long, default_value = "Autometrics")]
+    title: String,
+
+    /// The naming convention of the metrics.
+    ///
+    /// This must match the `metric_naming` setting of the instrumented code.
+    #[clap(long, value_enum, default_value_t = MetricNaming::Prometheus)]
+    metric_naming: MetricNaming,
+
+    /// Output path where the dashboard should be written.
+    ///
+    /// If not specified, the dashboard will be printed to stdout.
+    #[clap(short, long)]
+    output: Option<PathBuf>,
+}
+
+impl Arguments {
+    pub fn run(&self) {
+        // The queries of the dashboard use the metric names from the settings
+        AutometricsSettings::builder()
+            .metric_naming(match self.metric_naming {
+                MetricNaming::Prometheus => settings::MetricNaming::Prometheus,
+                MetricNaming::OpenTelemetry => settings::MetricNaming::OpenTelemetry,
+            })
+            .init();
+
+        let dashboard = self
+            .load_objectives()
+            .into_iter()
+            .fold(Dashboard::new(&self.title), Dashboard::objective);
+        let dashboard = self
+            .functions
+            .iter()
+            .fold(dashboard, |dashboard, function| {
+                dashboard.function(function)
+            });
+
+        if let Some(output_path) = &self.output {
+            write(output_path, dashboard.to_string())
+                .unwrap_or_else(|err| panic!("Error writing dashboard to {output_path:?}: {err}"));
+        } else {
+            println!("{}", dashboard);
+        }
+    }
+
+    fn load_objectives(&self) -> Vec<Objective> {
+        let Some(path) = &self.objectives else {
+            return Vec::new();
+        };
+        let yaml = read_to_string(path)
+            .unwrap_or_else(|err| panic!("Error reading objectives from {path:?}: {err}"));
+        let objectives = objectives::from_yaml_str(&yaml)
+            .unwrap_or_else(|err| panic!("Invalid objectives file {path:?}: {err}"));
+
+        if self.objective_names.is_empty() {
+            objectives.iter().copied().collect()
+        } else {
+            self.objective_names
+                .iter()
+                .map(|name| {
+                    objectives
+                        .get(name)
+                        .unwrap_or_else(|| panic!("Objective `{name}` is not defined in {path:?}"))
+                })
+                .collect()
+        }
+    }
+}
@@ -1,16 +1,28 @@
 use clap::Parser;
 
+mod dashboard;
+mod relabel;
 mod sloth;
 
 #[derive(Parser)]
 #[command(name = "autometrics", about)]
+// The variants are named after the subcommands
+#[allow(clippy::enum_variant_names)]
 enum Cli {
     /// Generate an SLO definition file for use with <https://sloth.dev>
     GenerateSlothFile(sloth::Arguments),
+    /// Generate the Prometheus relabeling rules that rename the series of functions
+    /// renamed with the `alias` argument of the `autometrics` macro
+    GenerateRelabelConfig(relabel::Arguments),
+    /// Generate a Grafana dashboard with the request rate, error ratio, and latency of functions
+    /// and the burn rates of objectives
+    GenerateDashboard(dashboard::Arguments),
 }
 
 fn main() {
     match Cli::parse() {
         Cli::GenerateSlothFile(command) => command.run(),
+        Cli::GenerateRelabelConfig(command) => command.run(),
+        Cli::GenerateDashboard(command) => command.run(),
     }
 }
@@ -0,0 +1,82 @@
+use clap::Parser;
+use std::{fs::write, path::PathBuf};
+
+#[derive(Parser)]
+pub struct Arguments {
+    /// The functions to rename, as `old_name=new_name`.
+    ///
+    /// The names are the values of the `function` label, so they include the type name
+    /// for methods (like `Database::get_user=Database::load_user`).
+    #[clap(long = "alias", value_parser = parse_alias, required = true)]
+    aliases: Vec<(String, String)>,
+
+    /// The key of the `function` label.
+    ///
+    /// This must match the `label_key_overrides` setting of the instrumented code.
+    #[clap(long, default_value = "function")]
+    function_label: String,
+
+    /// Output path where the relabeling rules should be written.
+    ///
+    /// If not specified, the rules will be printed to stdout.
+    #[clap(short, long)]
+    output: Option<PathBuf>,
+}
+
+fn parse_alias(alias: &str) -> Result<(String, String), String> {
+    match alias.split_once('=') {
+        Some((old_name, new_name)) if !old_name.is_empty() && !new_name.is_empty() => {
+            Ok((old_name.to_string(), new_name.to_string()))
+        }
+        _ => Err(format!("expected `old_name=new_name`, got `{alias}`")),
+    }
+}
+
+impl Arguments {
+    pub fn run(&self) {
+        let relabel_config = generate_relabel_config(&self.aliases, &self.function_label);
+        if let Some(output_path) = &self.output {
+            write(output_path, relabel_config).unwrap_or_else(|err| {
+                panic!("Error writing relabeling rules to {output_path:?}: {err}")
+            });
+        } else {
+            println!("{}", relabel_config);
+        }
+    }
+}
+
+/// Generate the `metric_relabel_configs` section of a Prometheus scrape config,
+/// which renames the old functions in the series to their new names when they are scraped.
+fn generate_relabel_config(aliases: &[(String, String)], function_label: &str) -> String {
+    let mut relabel_config = "metric_relabel_configs:
+"
+    .to_string();
+
+    // The function is also renamed where it shows up as the caller of other functions
+    for (old_name, new_name) in aliases {
+        let regex = escape_regex(old_name);
+        for label in [function_label, "caller_function"] {
+            relabel_config.push_str(&format!(
+                "  - source_labels: [{label}]
+    regex: '{regex}'
+    target_label: {label}
+    replacement: '{new_name}'
+"
+            ));
+        }
+    }
+
+    relabel_config
+}
+
+/// Escape the characters that have a special meaning in the regular expressions used by Prometheus
+fn escape_regex(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for c in name.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
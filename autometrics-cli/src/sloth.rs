@@ -1,4 +1,4 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use std::{fs::write, path::PathBuf};
 
 #[derive(Parser)]
@@ -29,12 +29,40 @@ pub struct Arguments {
     /// If not specified, the SLO file will be printed to stdout.
     #[clap(short, long)]
     output: Option<PathBuf>,
+
+    /// The naming convention of the metrics.
+    ///
+    /// This must match the `metric_naming` setting of the instrumented code.
+    #[clap(long, value_enum, default_value_t = MetricNaming::Prometheus)]
+    metric_naming: MetricNaming,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum MetricNaming {
+    /// The names from the Autometrics spec, like `function_calls_duration_seconds`
+    Prometheus,
+    /// The OpenTelemetry semantic conventions, like `function_duration_seconds`
+    #[value(name = "opentelemetry")]
+    OpenTelemetry,
+}
+
+impl MetricNaming {
+    /// The name of the latency histogram without its unit
+    fn histogram_name(self) -> &'static str {
+        match self {
+            MetricNaming::Prometheus => "function_calls_duration",
+            MetricNaming::OpenTelemetry => "function_duration",
+        }
+    }
 }
 
 impl Arguments {
     pub fn run(&self) {
-        let sloth_file =
-            generate_sloth_file(&self.objectives, self.alerting_traffic_threshold / 60.0);
+        let sloth_file = generate_sloth_file(
+            &self.objectives,
+            self.alerting_traffic_threshold / 60.0,
+            self.metric_naming,
+        );
         if let Some(output_path) = &self.output {
             write(output_path, sloth_file)
                 .unwrap_or_else(|err| panic!("Error writing SLO file to {output_path:?}: {err}"));
@@ -44,7 +72,11 @@ impl Arguments {
     }
 }
 
-fn generate_sloth_file(objectives: &[impl AsRef<str>], min_calls_per_second: f64) -> String {
+fn generate_sloth_file(
+    objectives: &[impl AsRef<str>],
+    min_calls_per_second: f64,
+    metric_naming: MetricNaming,
+) -> String {
     let mut sloth_file = "version: prometheus/v1
 service: autometrics
 slos:
@@ -61,6 +93,7 @@ slos:
         sloth_file.push_str(&generate_latency_slo(
             objective.as_ref(),
             min_calls_per_second,
+            metric_naming,
         ));
     }
 
@@ -92,8 +125,13 @@ fn generate_success_rate_slo(objective_percentile: &str, min_calls_per_second: f
 ")
 }
 
-fn generate_latency_slo(objective_percentile: &str, min_calls_per_second: f64) -> String {
+fn generate_latency_slo(
+    objective_percentile: &str,
+    min_calls_per_second: f64,
+    metric_naming: MetricNaming,
+) -> String {
     let objective_percentile_no_decimal = objective_percentile.replace('.', "_");
+    let histogram_name = metric_naming.histogram_name();
 
     format!("  - name: latency-{objective_percentile_no_decimal}
     objective: {objective_percentile}
@@ -101,14 +139,14 @@ fn generate_latency_slo(objective_percentile: &str, min_calls_per_second: f64) -
     sli:
       events:
         error_query: >
-          sum by (objective_name, objective_percentile, service_name) (rate({{__name__=~\"function_calls_duration(_seconds)?_count\", objective_percentile=\"{objective_percentile}\"}}[{{{{.window}}}}]))
+          sum by (objective_name, objective_percentile, service_name) (rate({{__name__=~\"{histogram_name}(_seconds)?_count\", objective_percentile=\"{objective_percentile}\"}}[{{{{.window}}}}]))
           -
           (sum by (objective_name, objective_percentile, service_name) (
-            label_join(rate({{__name__=~\"function_calls_duration(_seconds)?_bucket\", objective_percentile=\"{objective_percentile}\"}}[{{{{.window}}}}]), \"autometrics_check_label_equality\", \"\", \"objective_latency_threshold\")
+            label_join(rate({{__name__=~\"{histogram_name}(_seconds)?_bucket\", objective_percentile=\"{objective_percentile}\"}}[{{{{.window}}}}]), \"autometrics_check_label_equality\", \"\", \"objective_latency_threshold\")
             and
-            label_join(rate({{__name__=~\"function_calls_duration(_seconds)?_bucket\", objective_percentile=\"{objective_percentile}\"}}[{{{{.window}}}}]), \"autometrics_check_label_equality\", \"\", \"le\")
+            label_join(rate({{__name__=~\"{histogram_name}(_seconds)?_bucket\", objective_percentile=\"{objective_percentile}\"}}[{{{{.window}}}}]), \"autometrics_check_label_equality\", \"\", \"le\")
           ))
-        total_query: sum by (objective_name, objective_percentile, service_name) (rate({{__name__=~\"function_calls_duration(_seconds)?_count\", objective_percentile=\"{objective_percentile}\"}}[{{{{.window}}}}])) >= {min_calls_per_second}
+        total_query: sum by (objective_name, objective_percentile, service_name) (rate({{__name__=~\"{histogram_name}(_seconds)?_count\", objective_percentile=\"{objective_percentile}\"}}[{{{{.window}}}}])) >= {min_calls_per_second}
     alerting:
       name: High Latency SLO - {objective_percentile}%
       labels:
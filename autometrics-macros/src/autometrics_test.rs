@@ -0,0 +1,75 @@
+//! The definition of the autometrics_test attribute, see
+//! autometrics::autometrics_test for more information.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Error, FnArg, ItemFn, Result};
+
+/// Entry point of the autometrics_test attribute
+pub(crate) fn expand(args: TokenStream, item: ItemFn) -> Result<TokenStream> {
+    if !args.is_empty() {
+        return Err(Error::new_spanned(
+            args,
+            "the `autometrics_test` attribute does not take any arguments",
+        ));
+    }
+
+    let ItemFn {
+        attrs,
+        vis,
+        mut sig,
+        block,
+    } = item;
+
+    if sig.inputs.len() > 1 {
+        return Err(Error::new_spanned(
+            &sig.inputs,
+            "expected the test to take at most one argument, the `TestMetrics`",
+        ));
+    }
+    let start = quote!(::autometrics::testing::TestMetrics::start());
+    let metrics = match sig.inputs.pop().map(|input| input.into_value()) {
+        Some(FnArg::Typed(input)) => {
+            let pat = input.pat;
+            let ty = input.ty;
+            quote!(let #pat: #ty = #start;)
+        }
+        Some(FnArg::Receiver(receiver)) => {
+            return Err(Error::new_spanned(
+                receiver,
+                "the `autometrics_test` attribute cannot be used on methods",
+            ))
+        }
+        None => quote!(#start;),
+    };
+
+    // Keep the attribute of the async runtime, like `#[tokio::test]`, if there is one
+    let has_test_attr = attrs.iter().any(|attr| {
+        attr.path()
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "test")
+    });
+    let test_attr = (!has_test_attr).then(|| quote!(#[test]));
+
+    let body = if sig.asyncness.is_some() && !has_test_attr {
+        sig.asyncness = None;
+        quote! {
+            #metrics
+            ::autometrics::testing::block_on(async move #block)
+        }
+    } else {
+        quote! {
+            #metrics
+            #block
+        }
+    };
+
+    Ok(quote! {
+        #test_attr
+        #(#attrs)*
+        #vis #sig {
+            #body
+        }
+    })
+}
@@ -1,21 +1,20 @@
-use crate::parse::{AutometricsArgs, Item};
+use crate::parse::{AutometricsArgs, Item, Recursive};
 use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
 use proc_macro2::TokenStream;
-use quote::{quote, ToTokens};
-use regex::Regex;
+use quote::{format_ident, quote, quote_spanned, ToTokens};
 use std::env;
-use std::str::FromStr;
+use syn::punctuated::Punctuated;
+use syn::visit_mut::{self, VisitMut};
 use syn::{
-    parse_macro_input, GenericArgument, ImplItem, ItemFn, ItemImpl, PathArguments, Result,
-    ReturnType, Type,
+    parse_macro_input, Attribute, GenericArgument, ImplItem, ItemFn, ItemImpl, ItemTrait, LitStr,
+    PathArguments, PathSegment, Result, ReturnType, Token, TraitItem, Type, TypeInfer,
+    TypeParamBound, Visibility,
 };
 
+mod autometrics_test;
 mod parse;
 mod result_labels;
 
-const ADD_BUILD_INFO_LABELS: &str =
-    "* on (instance, job) group_left(version, commit) last_over_time(build_info[1s])";
-
 const DEFAULT_PROMETHEUS_URL: &str = "http://localhost:9090";
 
 #[proc_macro_attribute]
@@ -25,12 +24,24 @@ pub fn autometrics(
 ) -> proc_macro::TokenStream {
     let args = parse_macro_input!(args as AutometricsArgs);
 
-    let async_trait = check_async_trait(&item);
     let item = parse_macro_input!(item as Item);
 
+    // With the `noop` feature or the `AUTOMETRICS_NOOP` compile-time environment variable, the items
+    // are left unchanged. The arguments are still parsed so that they stay valid when it is turned off
+    if cfg!(feature = "noop") || env_flag("AUTOMETRICS_NOOP") {
+        return uninstrumented(item).into();
+    }
+
     let result = match item {
-        Item::Function(item) => instrument_function(&args, item, args.struct_name.as_deref()),
-        Item::Impl(item) => instrument_impl_block(&args, item, &async_trait),
+        Item::Function(item) => {
+            let name = match args.struct_name.as_deref() {
+                Some(struct_name) => FunctionName::Method(struct_name),
+                None => FunctionName::Function,
+            };
+            instrument_function(&args, item, name)
+        }
+        Item::Impl(item) => instrument_impl_block(&args, item),
+        Item::Trait(item) => instrument_trait(&args, item),
     };
 
     let output = match result {
@@ -41,15 +52,34 @@ pub fn autometrics(
     output.into()
 }
 
-/// returns the `async_trait` attributes that have to be re-added after our instrumentation magic has been added
-fn check_async_trait(input: &proc_macro::TokenStream) -> String {
-    let regex = Regex::new(r#"#\[[^\]]*async_trait\]"#)
-        .expect("The regex is hardcoded and thus guaranteed to be successfully parseable");
-
-    let original = input.to_string();
-    let attributes: Vec<_> = regex.find_iter(&original).map(|m| m.as_str()).collect();
+#[proc_macro_attribute]
+pub fn autometrics_test(
+    args: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let item = parse_macro_input!(item as ItemFn);
+    autometrics_test::expand(args.into(), item)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
-    attributes.join("\n")
+/// The queries that the `autometrics` macro adds to the documentation of the function with the given name,
+/// as an array of the request rate, error ratio, latency, concurrent calls, callee request rate, and
+/// callee error ratio queries. This is used to test that they match the ones built by `autometrics::queries`
+#[doc(hidden)]
+#[proc_macro]
+pub fn doc_queries(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let function = parse_macro_input!(input as LitStr).value();
+    let function_key = label_key("function");
+    let queries = [
+        request_rate_query(&function_key, &function),
+        error_ratio_query(&function_key, &function),
+        latency_query(&function_key, &function),
+        concurrent_calls_query(&function_key, &function),
+        request_rate_query("caller_function", &function),
+        error_ratio_query("caller_function", &function),
+    ];
+    quote! { [#(#queries),*] }.into()
 }
 
 #[proc_macro_derive(ResultLabels, attributes(label))]
@@ -60,34 +90,129 @@ pub fn result_labels(input: proc_macro::TokenStream) -> proc_macro::TokenStream
         .into()
 }
 
+/// Whether the compile-time environment variable is set to `1` or `true` (ignoring case),
+/// so that setting it to `0`, `false`, or an empty string does not turn the flag on
+fn env_flag(name: &str) -> bool {
+    env::var(name).is_ok_and(|value| {
+        let value = value.trim();
+        value == "1" || value.eq_ignore_ascii_case("true")
+    })
+}
+
+/// The original item, without the `#[skip_autometrics]` attributes of its methods
+fn uninstrumented(item: Item) -> TokenStream {
+    match item {
+        Item::Function(item) => quote! { #item },
+        Item::Impl(mut item) => {
+            for item in &mut item.items {
+                if let ImplItem::Fn(method) = item {
+                    remove_skip_attribute(&mut method.attrs);
+                }
+            }
+            quote! { #item }
+        }
+        Item::Trait(mut item) => {
+            for item in &mut item.items {
+                if let TraitItem::Fn(method) = item {
+                    remove_skip_attribute(&mut method.attrs);
+                }
+            }
+            quote! { #item }
+        }
+    }
+}
+
+fn remove_skip_attribute(attrs: &mut Vec<Attribute>) {
+    attrs.retain(|attr| !attr.path().is_ident("skip_autometrics"));
+}
+
+/// How the `function` label of an instrumented function is determined
+#[derive(Clone, Copy)]
+enum FunctionName<'a> {
+    Function,
+    /// Methods are identified as Struct::method
+    Method(&'a str),
+    /// Default methods of traits are identified as Implementor::method,
+    /// using the name of the type that the trait is implemented for
+    TraitMethod,
+}
+
 /// Add autometrics instrumentation to a single function
 fn instrument_function(
     args: &AutometricsArgs,
     item: ItemFn,
-    struct_name: Option<&str>,
+    name: FunctionName,
 ) -> Result<TokenStream> {
+    // The metrics are recorded at runtime, which is not possible in const functions
+    if let Some(constness) = &item.sig.constness {
+        return Err(syn::Error::new_spanned(
+            constness,
+            "autometrics cannot be applied to const functions",
+        ));
+    }
+
     let sig = item.sig;
     let block = item.block;
     let vis = item.vis;
     let attrs = item.attrs;
 
-    // Methods are identified as Struct::method
-    let function_name = match struct_name {
-        Some(struct_name) => format!("{}::{}", struct_name, sig.ident),
-        None => sig.ident.to_string(),
+    let function_name = match name {
+        FunctionName::Function | FunctionName::TraitMethod => sig.ident.to_string(),
+        FunctionName::Method(struct_name) => format!("{}::{}", struct_name, sig.ident),
+    };
+
+    // The implementor of a trait is only known once the trait is monomorphized,
+    // so the name of trait methods is determined at runtime
+    let (function_label, trait_method_name) = if let FunctionName::TraitMethod = name {
+        (
+            quote! { __autometrics_function },
+            quote! {
+                let __autometrics_function = autometrics::__private::trait_method_name::<Self>(#function_name);
+            },
+        )
+    } else {
+        (quote! { #function_name }, quote! {})
     };
 
     // The PROMETHEUS_URL can be configured by passing the environment variable during build time
+    // (the links built at runtime by `autometrics::queries` use the URL from the settings instead)
     let prometheus_url =
         env::var("PROMETHEUS_URL").unwrap_or_else(|_| DEFAULT_PROMETHEUS_URL.to_string());
 
-    // Build the documentation we'll add to the function's RustDocs, unless it is disabled by the environment variable
-    let metrics_docs = if env::var("AUTOMETRICS_DISABLE_DOCS").is_ok() {
-        String::new()
+    // Build the documentation we'll add to the function's RustDocs, unless it is disabled by the environment variable,
+    // the `no_docs` argument, or the `no-doc-links` or `debug-expansion` features
+    // Trait methods are not added to the docs because their metrics are split up by implementor
+    let metrics_docs = if env::var("AUTOMETRICS_DISABLE_DOCS").is_ok()
+        || args.no_docs
+        || cfg!(feature = "no-doc-links")
+        || cfg!(feature = "debug-expansion")
+        || matches!(name, FunctionName::TraitMethod)
+    {
+        quote! {}
+    } else {
+        let metrics_docs = create_metrics_docs(
+            &prometheus_url,
+            &function_name,
+            args.track_concurrency,
+            args.count_only.is_none(),
+        );
+        // Append the metrics documentation to the end of the function's documentation
+        quote! { #[doc = #metrics_docs] }
+    };
+
+    // Functions that return `Pin<Box<dyn Future<Output = T>>>` (such as the methods generated by `async_trait`)
+    // or `impl Future<Output = T>` are instrumented like async functions, so that the metrics track the execution
+    // of the returned future rather than the call that creates it. This is independent of the order of the attributes.
+    let returned_future = if sig.asyncness.is_none() {
+        returned_future(&sig.output)
     } else {
-        create_metrics_docs(&prometheus_url, &function_name, args.track_concurrency)
+        None
     };
 
+    // Functions that return `!` never produce a result, so only their panics are recorded
+    let diverges = sig.asyncness.is_none()
+        && matches!(&sig.output, ReturnType::Type(_, t) if matches!(**t, Type::Never(_)));
+
     // Type annotation to allow type inference to work on return expressions (such as `.collect()`), as
     // well as prevent compiler type-inference from selecting the wrong branch in the `spez` macro later.
     //
@@ -112,165 +237,316 @@ fn instrument_function(
     //
     // specifying the return type makes the compiler select the (correct) fallback case of `ApiError` not being a
     // `GetLabels` implementor.
-    let return_type = match sig.output {
-        ReturnType::Default => quote! { : () },
-        ReturnType::Type(_, ref t) => match t.as_ref() {
-            Type::ImplTrait(_) => quote! {},
-            Type::Path(path) => {
-                let mut ts = vec![];
-                let mut first = true;
-
-                for segment in &path.path.segments {
-                    let ident = &segment.ident;
-                    let args = &segment.arguments;
-
-                    // special handling in case the type is angle bracket with a `impl` trait
-                    // in such a case, we would run into the following error
-                    //
-                    // ```
-                    // error[E0562]: `impl Trait` only allowed in function and inherent method return types, not in variable bindings
-                    //   --> src/main.rs:11:28
-                    //    |
-                    // 11 | async fn hello() -> Result<impl ToString, std::io::Error> {
-                    //    |                            ^^^^^^^^^^^^^
-                    // ```
-                    //
-                    // this whole block just re-creates the angle bracketed `<impl ToString, std::io::Error>`
-                    // manually but the trait `impl` replaced with an infer `_`, which fixes this issue
-                    let suffix = match args {
-                        PathArguments::AngleBracketed(brackets) => {
-                            let mut ts = vec![];
-
-                            for args in &brackets.args {
-                                ts.push(match args {
-                                    GenericArgument::Type(Type::ImplTrait(_)) => {
-                                        quote! { _ }
-                                    }
-                                    generic_arg => quote! { #generic_arg },
-                                });
-                            }
-
-                            quote! { ::<#(#ts),*> }
-                        }
-                        _ => quote! {},
-                    };
-
-                    // primitive way to check whenever this is the first iteration or not
-                    // as on the first iteration, we don't want to prepend `::`,
-                    // as types may be local and/or imported and then couldn't be found
-                    if !first {
-                        ts.push(quote! { :: });
-                    } else {
-                        first = false;
-                    }
-
-                    ts.push(quote! { #ident });
-                    ts.push(quote! { #suffix });
-                }
+    let return_type = match (&returned_future, &sig.output) {
+        (Some(future), _) => future.output.map(type_annotation).unwrap_or_default(),
+        (None, ReturnType::Default) => quote! { : () },
+        (None, ReturnType::Type(_, t)) => type_annotation(t),
+    };
 
-                quote! { : #(#ts)* }
-            }
-            _ => quote! { : #t },
-        },
+    // Run the body of the original function directly, without tracking the caller of the functions it calls
+    let run_function = if returned_future.is_some() {
+        quote! { __autometrics_future.await }
+    } else if sig.asyncness.is_some() {
+        quote! { async move { #block }.await }
+    } else if diverges {
+        quote! { #block }
+    } else {
+        quote! { (move || #block)() }
     };
 
     // Track the name and module of the current function as a task-local variable
     // so that any functions it calls know which function they were called by
+    // (unless caller tracking is disabled in the settings)
     let caller_info = quote! {
-        use autometrics::__private::{CALLER, CallerInfo};
+        use autometrics::__private::CallerInfo;
         let caller = CallerInfo {
-            caller_function: #function_name,
+            caller_function: #function_label,
             caller_module: module_path!(),
         };
     };
 
     // Wrap the body of the original function, using a slightly different approach based on whether the function is async
-    let call_function = if sig.asyncness.is_some() {
+    let call_function = if args.no_caller {
+        run_function.clone()
+    } else if returned_future.is_some() {
         quote! {
             {
                 #caller_info
-                CALLER.scope(caller, async move {
+                caller.scope(__autometrics_settings, __autometrics_future).await
+            }
+        }
+    } else if sig.asyncness.is_some() {
+        quote! {
+            {
+                #caller_info
+                caller.scope(__autometrics_settings, async move {
                     #block
                 }).await
             }
         }
+    } else if diverges {
+        quote! {
+            {
+                #caller_info
+                caller.sync_scope(__autometrics_settings, move || -> ! #block)
+            }
+        }
     } else {
         quote! {
             {
                 #caller_info
-                CALLER.sync_scope(caller, move || {
+                caller.sync_scope(__autometrics_settings, move || {
                     #block
                 })
             }
         }
     };
 
+    // Functions with the `no_caller` argument leave their caller labels empty
+    let current_caller = if args.no_caller {
+        quote! { CallerInfo::EMPTY }
+    } else {
+        quote! { CallerInfo::current(__autometrics_settings) }
+    };
+
+    // The objective may be loaded at runtime (for example, from a YAML file), so it is resolved
+    // on the first call and kept by the call site, instead of every time the labels are created
     let objective = if let Some(objective) = &args.objective {
-        quote! { Some(#objective) }
+        quote! { __AUTOMETRICS_CALL_SITE.objective(|| Some(#objective)) }
     } else {
         quote! { None }
     };
+    let (resolve_objective, objective_label) = if args.objective.is_some() {
+        (
+            quote! { let __autometrics_objective = #objective; },
+            quote! { __autometrics_objective },
+        )
+    } else {
+        (quote! {}, quote! { None })
+    };
+
+    // Detect whether the function was called by itself, so that recursive calls
+    // do not show up as self-edges in the call graph or inflate the concurrency gauge.
+    // Without the caller, functions with `no_caller` count every call towards the gauge
+    let detect_recursion = args.recursive.is_some() || args.track_concurrency;
+    let recursive = if detect_recursion && args.no_caller {
+        quote! { let __autometrics_recursive = false; }
+    } else if detect_recursion {
+        quote! {
+            let __autometrics_recursive = {
+                use autometrics::__private::CallerInfo;
+                #current_caller.is_function(#function_label, module_path!())
+            };
+        }
+    } else {
+        quote! {}
+    };
+
+    let (caller_function, caller_module) = if let Some(Recursive::SkipCaller) = args.recursive {
+        (
+            quote! { if __autometrics_recursive { "" } else { caller.caller_function } },
+            quote! { if __autometrics_recursive { "" } else { caller.caller_module } },
+        )
+    } else {
+        (
+            quote! { caller.caller_function },
+            quote! { caller.caller_module },
+        )
+    };
+
+    let recursive_label = if let Some(Recursive::Label) = args.recursive {
+        quote! { .with_recursive(__autometrics_recursive) }
+    } else {
+        quote! {}
+    };
+
+    let error_type_label = if args.error_details {
+        quote! { .with_error_type(autometrics::get_error_type_for_value!(&result)) }
+    } else {
+        quote! {}
+    };
+
+    // The type parameters are only known once the function is monomorphized,
+    // so the label is determined at runtime
+    let type_params: Vec<_> = sig
+        .generics
+        .type_params()
+        .map(|param| &param.ident)
+        .collect();
+    let (type_params, type_params_label) = if args.generic_params && !type_params.is_empty() {
+        let ty = if let [param] = type_params.as_slice() {
+            quote! { #param }
+        } else {
+            quote! { (#(#type_params),*) }
+        };
+        (
+            quote! {
+                let __autometrics_type_params = autometrics::__private::type_params::<#ty>();
+            },
+            quote! { .with_type_params(__autometrics_type_params) },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+
+    // The values of the custom labels are evaluated before the body runs, because it may move
+    // the arguments (like `self`) that they are computed from
+    let label_values: Vec<_> = (0..args.labels.len())
+        .map(|index| format_ident!("__autometrics_label_{index}"))
+        .collect();
+    let evaluate_labels = args.labels.iter().zip(&label_values).map(|(label, ident)| {
+        let value = &label.value;
+        quote! { let #ident = autometrics::__private::label_value(&(#value)); }
+    });
+    let evaluate_labels = quote! { #(#evaluate_labels)* };
+    let label_keys = args.labels.iter().map(|label| &label.key);
+    let alias_label = args
+        .alias
+        .as_ref()
+        .map(|alias| quote! { .with_alias(#alias) });
+    let custom_labels = quote! { #(.with_label(#label_keys, #label_values))* #alias_label };
+
+    let histogram_buckets = if let Some(buckets) = &args.buckets {
+        quote! { .with_buckets(&[#(#buckets),*]) }
+    } else if args.fine_grained_latency {
+        quote! { .with_buckets(autometrics::__private::fine_grained_latency_buckets()) }
+    } else {
+        quote! {}
+    };
 
     let counter_labels = if args.ok_if.is_some() || args.error_if.is_some() {
-        // Apply the predicate to determine whether to consider the result as "ok" or "error"
+        // Apply the predicate to determine whether to consider the result as "ok" or "error".
+        // The predicate is passed to a function rather than called directly so that the
+        // argument type of closures can be inferred from the return type
         let result_label = if let Some(ok_if) = &args.ok_if {
-            quote! { if #ok_if (&result) { "ok" } else { "error" } }
+            quote! { if autometrics::__private::check_predicate(&result, #ok_if) { "ok" } else { "error" } }
         } else if let Some(error_if) = &args.error_if {
-            quote! { if #error_if (&result) { "error" } else { "ok" } }
+            quote! { if autometrics::__private::check_predicate(&result, #error_if) { "error" } else { "ok" } }
         } else {
             unreachable!()
         };
         quote! {
             {
-                use autometrics::__private::{CALLER, CounterLabels, GetStaticStrFromIntoStaticStr, GetStaticStr};
+                use autometrics::__private::{CallerInfo, CounterLabels, GetStaticStrFromIntoStaticStr, GetStaticStr};
                 let result_label = #result_label;
                 // If the return type implements Into<&'static str>, attach that as a label
                 let value_type = (&result).__autometrics_static_str();
-                let caller = CALLER.get();
+                let caller = #current_caller;
                 CounterLabels::new(
-                    #function_name,
+                    __autometrics_settings,
+                    #function_label,
                     module_path!(),
-                    caller.caller_function,
-                    caller.caller_module,
+                    #caller_function,
+                    #caller_module,
                     Some((result_label, value_type)),
-                    #objective,
-                )
+                    #objective_label,
+                )#recursive_label #error_type_label #type_params_label #custom_labels
+                .with_error_class(autometrics::get_error_class_for_value!(&result))
+                .with_call_site(&__AUTOMETRICS_CALL_SITE)
             }
         }
     } else {
         quote! {
             {
-                use autometrics::__private::{CALLER, CounterLabels, GetLabels};
+                use autometrics::__private::{CallerInfo, CounterLabels, GetLabels};
                 let result_labels = autometrics::get_result_labels_for_value!(&result);
-                let caller = CALLER.get();
+                let caller = #current_caller;
                 CounterLabels::new(
-                    #function_name,
+                    __autometrics_settings,
+                    #function_label,
                     module_path!(),
-                    caller.caller_function,
-                    caller.caller_module,
+                    #caller_function,
+                    #caller_module,
                     result_labels,
-                    #objective,
-                )
+                    #objective_label,
+                )#recursive_label #error_type_label #type_params_label #custom_labels
+                .with_error_class(autometrics::get_error_class_for_value!(&result))
+                .with_call_site(&__AUTOMETRICS_CALL_SITE)
             }
         }
     };
 
+    // Only the top-level call of a recursive function counts towards the concurrency gauge
+    let max_concurrency = if args.max_concurrency {
+        quote! { .with_max_concurrency() }
+    } else {
+        quote! {}
+    };
     let gauge_labels = if args.track_concurrency {
         quote! { {
             use autometrics::__private::GaugeLabels;
             Some(&GaugeLabels::new(
-                #function_name,
+                __autometrics_settings,
+                #function_label,
                 module_path!(),
-            )) }
+            )#max_concurrency).filter(|_| !__autometrics_recursive) }
         }
     } else {
         quote! { None }
     };
 
+    // Sampled calls are counted as multiple calls, and skipped calls are not recorded at all
+    let sample_weight = match (&args.skip_if, &args.sample_rate) {
+        (Some(skip_if), Some(sample_rate)) => Some(quote! {
+            if (#skip_if)() { 0 } else { autometrics::__private::sample_weight(#sample_rate) }
+        }),
+        (Some(skip_if), None) => Some(quote! { if (#skip_if)() { 0 } else { 1 } }),
+        (None, Some(sample_rate)) => {
+            Some(quote! { autometrics::__private::sample_weight(#sample_rate) })
+        }
+        (None, None) => None,
+    };
+    let span_events = if args.span_events {
+        quote! { .with_span_events() }
+    } else {
+        quote! {}
+    };
+    let start_tracker = if let Some(sample_weight) = sample_weight {
+        quote! { AutometricsTracker::start_sampled(#gauge_labels, #sample_weight)#span_events }
+    } else {
+        quote! { AutometricsTracker::start(#gauge_labels)#span_events }
+    };
+
+    // The closure is passed to a function (rather than called directly) so that
+    // its argument type can be inferred from the return type
+    let record_size = if let Some(track_size) = &args.track_size {
+        quote! {
+            __autometrics_tracker.record_size(
+                &histogram_labels,
+                autometrics::__private::measure_size(&result, #track_size),
+            );
+        }
+    } else {
+        quote! {}
+    };
+
+    // The identifier of the function is created at compile time and added to the registry
+    // the first time the function is called (trait methods do not have one, because their
+    // name is only known at runtime)
+    let (function_id, observe_function) = if matches!(name, FunctionName::TraitMethod) {
+        (quote! {}, quote! {})
+    } else {
+        (
+            quote! {
+                const __AUTOMETRICS_FUNCTION_ID: autometrics::__private::FunctionId = autometrics::__private::FunctionId::new(
+                    #function_name,
+                    module_path!(),
+                    autometrics::__private::crate_name(option_env!("CARGO_CRATE_NAME")),
+                );
+            },
+            quote! { autometrics::__private::observe_function(&__AUTOMETRICS_FUNCTION_ID); },
+        )
+    };
+
     // This is a little nuts.
-    // In debug mode, we're using the `linkme` crate to collect all the function descriptions into a static slice.
+    // In debug mode (or with the `function-registry` feature), we're using the `linkme` crate to collect
+    // all the function descriptions into a static slice.
     // We're then using that to start all the function counters at zero, even before the function is called.
-    let collect_function_descriptions = if cfg!(debug_assertions) {
+    // This is skipped for trait methods, because the implementors of the trait are not known here.
+    let collect_function_descriptions = if (cfg!(debug_assertions)
+        || cfg!(feature = "function-registry"))
+        && !matches!(name, FunctionName::TraitMethod)
+    {
         quote! {
             {
                 use autometrics::__private::{linkme::distributed_slice, FUNCTION_DESCRIPTIONS, FunctionDescription};
@@ -278,9 +554,10 @@ fn instrument_function(
                 // Point the distributed_slice macro to the linkme crate re-exported from autometrics
                 #[linkme(crate = autometrics::__private::linkme)]
                 static FUNCTION_DESCRIPTION: FunctionDescription = FunctionDescription {
-                    name: #function_name,
-                    module: module_path!(),
-                    objective: #objective,
+                    id: __AUTOMETRICS_FUNCTION_ID,
+                    // The objective is wrapped in a function so that it does not need to be a constant expression
+                    // (for example, if it is loaded from a file via `autometrics::objectives::from_yaml_str`)
+                    objective: || #objective,
                 };
             }
         }
@@ -288,50 +565,373 @@ fn instrument_function(
         quote! {}
     };
 
-    Ok(quote! {
-        #(#attrs)*
+    // The previous name of the function is reported the first time it is called
+    let register_alias = if let Some(alias) = &args.alias {
+        if alias.value() == function_name {
+            return Err(syn::Error::new_spanned(
+                alias,
+                "the alias must be different from the name of the function",
+            ));
+        }
+        quote! {
+            autometrics::__private::register_alias(settings(), #function_name, module_path!(), #alias);
+        }
+    } else {
+        quote! {}
+    };
 
-        // Append the metrics documentation to the end of the function's documentation
-        #[doc=#metrics_docs]
+    // The `opentelemetry` and `metrics` backends configure the buckets per metric rather than per function,
+    // so the `buckets` argument is rejected at compile time if only those backends are enabled
+    let check_buckets = if let Some(span) = args.buckets_span {
+        quote_spanned! {span=>
+            const _: () = autometrics::__private::check_per_function_buckets::<()>();
+        }
+    } else {
+        quote! {}
+    };
 
-        #vis #sig {
-            #collect_function_descriptions
+    let set_build_info = quote! {
+        {
+            use autometrics::__private::{set_build_timestamp, AutometricsTracker, BuildInfoLabels, TrackMetrics};
+            set_build_timestamp(option_env!("AUTOMETRICS_BUILD_TIMESTAMP"));
+            AutometricsTracker::set_build_info(&BuildInfoLabels::new(
+                __autometrics_settings,
+                option_env!("AUTOMETRICS_VERSION").or(option_env!("CARGO_PKG_VERSION")).unwrap_or_default(),
+                option_env!("AUTOMETRICS_COMMIT").or(option_env!("VERGEN_GIT_SHA")).unwrap_or_default(),
+                option_env!("AUTOMETRICS_BRANCH").or(option_env!("VERGEN_GIT_BRANCH")).unwrap_or_default(),
+            ).with_toolchain(
+                option_env!("AUTOMETRICS_BUILD_TIMESTAMP").or(option_env!("VERGEN_BUILD_TIMESTAMP")),
+                option_env!("AUTOMETRICS_RUSTC_VERSION").or(option_env!("VERGEN_RUSTC_SEMVER")),
+                option_env!("AUTOMETRICS_TARGET").or(option_env!("VERGEN_CARGO_TARGET_TRIPLE")),
+            ));
+        }
+    };
 
-            let __autometrics_tracker = {
-                use autometrics::__private::{AutometricsTracker, BuildInfoLabels, TrackMetrics};
-                AutometricsTracker::set_build_info(&BuildInfoLabels::new(
-                    option_env!("AUTOMETRICS_VERSION").or(option_env!("CARGO_PKG_VERSION")).unwrap_or_default(),
-                    option_env!("AUTOMETRICS_COMMIT").or(option_env!("VERGEN_GIT_SHA")).unwrap_or_default(),
-                    option_env!("AUTOMETRICS_BRANCH").or(option_env!("VERGEN_GIT_BRANCH")).unwrap_or_default(),
-                ));
-                AutometricsTracker::start(#gauge_labels)
-            };
+    // Functions that are only counted are usually very hot, so their build info is set
+    // when the settings are first loaded rather than checked on every call
+    let init_settings = if args.count_only.is_some() {
+        quote! {
+            let __autometrics_settings = settings();
+            #set_build_info
+            __autometrics_settings
+        }
+    } else {
+        quote! { settings() }
+    };
+
+    // Look up the settings once per call site rather than every time the labels are created
+    // (which is also when the function is added to the registry)
+    let load_settings = quote! {
+        #check_buckets
+        let __autometrics_settings = {
+            use autometrics::__private::{settings, AutometricsSettings, OnceCell};
+            static SETTINGS: OnceCell<&'static AutometricsSettings> = OnceCell::new();
+            *SETTINGS.get_or_init(|| {
+                #observe_function
+                #register_alias
+                #init_settings
+            })
+        };
+        #resolve_objective
+    };
+
+    // If the function panics, the call is recorded as an error when the guard is dropped
+    let start_tracker_with_guard = quote! {
+        {
+            use autometrics::__private::{AutometricsTracker, PanicGuard, TrackMetrics};
+            #set_build_info
+            let tracker = #start_tracker;
+            PanicGuard::new(tracker, move || {
+                use autometrics::__private::{CallerInfo, CounterLabels, HistogramLabels};
+                let caller = #current_caller;
+                let counter_labels = CounterLabels::new(
+                    __autometrics_settings,
+                    #function_label,
+                    module_path!(),
+                    #caller_function,
+                    #caller_module,
+                    None,
+                    #objective_label,
+                )#recursive_label #type_params_label #custom_labels;
+                let histogram_labels = HistogramLabels::new(
+                    __autometrics_settings,
+                    #function_label,
+                    module_path!(),
+                    #objective_label,
+                )#histogram_buckets #type_params_label #custom_labels;
+                (counter_labels, histogram_labels)
+            })
+        }
+    };
+
+    // The labels for a panic are only created if the function panics
+    let start_count_guard = quote! {
+        autometrics::__private::CountGuard::new(move || {
+            use autometrics::__private::{CallerInfo, CounterLabels};
+            let caller = #current_caller;
+            CounterLabels::new(
+                __autometrics_settings,
+                #function_label,
+                module_path!(),
+                #caller_function,
+                #caller_module,
+                None,
+                #objective_label,
+            )#type_params_label #custom_labels
+            .with_call_site(&__AUTOMETRICS_CALL_SITE)
+        })
+    };
+
+    // Functions that are only counted run their body directly, without starting a timer
+    // or tracking the caller of the functions they call
+    let instrumented = if diverges && args.count_only.is_some() {
+        // There is no result to count if the function never returns, but it may still panic
+        quote! {
+            #load_settings
+
+            #type_params
+
+            let __autometrics_guard = #start_count_guard;
+
+            #block
+        }
+    } else if args.count_only.is_some() {
+        quote! {
+            #load_settings
+
+            #type_params
+
+            let __autometrics_guard = #start_count_guard;
+
+            let result #return_type = #run_function;
+
+            {
+                let counter_labels = #counter_labels;
+                __autometrics_guard.finish(&counter_labels);
+            }
+
+            result
+        }
+    } else if diverges {
+        quote! {
+            #load_settings
+
+            #recursive
+
+            #type_params
+
+            let __autometrics_tracker = #start_tracker_with_guard;
+
+            #call_function
+        }
+    } else {
+        quote! {
+            #load_settings
+
+            #recursive
+
+            #type_params
+
+            let __autometrics_tracker = #start_tracker_with_guard;
 
             let result #return_type = #call_function;
 
             {
-                use autometrics::__private::{HistogramLabels, TrackMetrics};
+                use autometrics::__private::HistogramLabels;
                 let counter_labels = #counter_labels;
                 let histogram_labels = HistogramLabels::new(
-                    #function_name,
-                     module_path!(),
-                     #objective,
-                );
+                    __autometrics_settings,
+                    #function_label,
+                    module_path!(),
+                    #objective_label,
+                )#histogram_buckets #type_params_label #custom_labels
+                .with_call_site(&__AUTOMETRICS_CALL_SITE);
+                #record_size
                 __autometrics_tracker.finish(&counter_labels, &histogram_labels);
             }
 
             result
         }
+    };
+
+    // The returned future is created by the original body and then awaited inside of the instrumentation.
+    // The instrumented future is boxed if the original one was, or if the `impl Future` must be `Unpin`
+    let body = match returned_future {
+        Some(future) if future.boxed || future.unpin => quote! {
+            let __autometrics_future = #block;
+            ::std::boxed::Box::pin(async move {
+                #instrumented
+            })
+        },
+        Some(_) => quote! {
+            let __autometrics_future = #block;
+            async move {
+                #instrumented
+            }
+        },
+        None => instrumented,
+    };
+
+    let inline = if args.inline_hint {
+        quote! { #[inline] }
+    } else {
+        quote! {}
+    };
+
+    Ok(quote! {
+        #(#attrs)*
+
+        #inline
+
+        #metrics_docs
+
+        #vis #sig {
+            #function_id
+
+            // The call site keeps the function's objective and the handles to its series,
+            // for the backends that support it
+            static __AUTOMETRICS_CALL_SITE: autometrics::__private::CallSite = autometrics::__private::CallSite::new();
+
+            #collect_function_descriptions
+
+            #trait_method_name
+
+            #evaluate_labels
+
+            #body
+        }
     })
 }
 
+/// Create the type annotation for the `result` of the instrumented function
+fn type_annotation(t: &Type) -> TokenStream {
+    // `impl Trait` types are only allowed in the return type of the function, so using them in
+    // the annotation of the variable would lead to the following error:
+    //
+    // ```
+    // error[E0562]: `impl Trait` only allowed in function and inherent method return types, not in variable bindings
+    //   --> src/main.rs:11:28
+    //    |
+    // 11 | async fn hello() -> Result<impl ToString, std::io::Error> {
+    //    |                            ^^^^^^^^^^^^^
+    // ```
+    //
+    // They are replaced with `_` wherever they appear in the type (for example, in `Option<Vec<impl Display>>`,
+    // `(impl Display, usize)`, or `&'a impl Display`) so the compiler infers them from the body instead.
+    // The rest of the type, including any lifetimes and qualified paths, is used as written.
+    let mut t = t.clone();
+    InferImplTrait.visit_type_mut(&mut t);
+    quote! { : #t }
+}
+
+/// Replaces the `impl Trait` types within a type with `_`
+struct InferImplTrait;
+
+impl VisitMut for InferImplTrait {
+    fn visit_type_mut(&mut self, t: &mut Type) {
+        if let Type::ImplTrait(impl_trait) = t {
+            *t = Type::Infer(TypeInfer {
+                underscore_token: Token![_](impl_trait.impl_token.span),
+            });
+        } else {
+            visit_mut::visit_type_mut(self, t);
+        }
+    }
+}
+
+/// A future returned by a function that is not declared as `async`
+struct ReturnedFuture<'a> {
+    /// The `Output` of the future, unless it is left unspecified in an `impl Future`
+    output: Option<&'a Type>,
+    /// Whether the function returns `Pin<Box<dyn Future<Output = T>>>` rather than `impl Future<Output = T>`
+    boxed: bool,
+    /// Whether the `impl Future` is also `Unpin`
+    unpin: bool,
+}
+
+/// If the return type is `Pin<Box<dyn Future<Output = T>>>` or `impl Future<Output = T>`, describe the future
+fn returned_future(output: &ReturnType) -> Option<ReturnedFuture<'_>> {
+    let ReturnType::Type(_, t) = output else {
+        return None;
+    };
+    if let Type::ImplTrait(impl_trait) = &**t {
+        let future = trait_bound(&impl_trait.bounds, "Future")?;
+        return Some(ReturnedFuture {
+            output: future_output(future),
+            boxed: false,
+            unpin: trait_bound(&impl_trait.bounds, "Unpin").is_some(),
+        });
+    }
+
+    let boxed = single_type_argument(t, "Pin")?;
+    let Type::TraitObject(future) = single_type_argument(boxed, "Box")? else {
+        return None;
+    };
+    Some(ReturnedFuture {
+        output: Some(future_output(trait_bound(&future.bounds, "Future")?)?),
+        boxed: true,
+        unpin: false,
+    })
+}
+
+/// Find the bound on the given trait, such as `Future<Output = T>` in `impl Future<Output = T> + Send`
+fn trait_bound<'a>(
+    bounds: &'a Punctuated<TypeParamBound, Token![+]>,
+    name: &str,
+) -> Option<&'a PathSegment> {
+    bounds.iter().find_map(|bound| {
+        let TypeParamBound::Trait(bound) = bound else {
+            return None;
+        };
+        let segment = bound.path.segments.last()?;
+        (segment.ident == name).then_some(segment)
+    })
+}
+
+/// If the bound is `Future<Output = T>`, return the `T`
+fn future_output(future: &PathSegment) -> Option<&Type> {
+    let PathArguments::AngleBracketed(args) = &future.arguments else {
+        return None;
+    };
+    args.args.iter().find_map(|arg| match arg {
+        GenericArgument::AssocType(assoc) if assoc.ident == "Output" => Some(&assoc.ty),
+        _ => None,
+    })
+}
+
+/// If the type is `Name<T>`, return the `T`
+fn single_type_argument<'a>(t: &'a Type, name: &str) -> Option<&'a Type> {
+    let Type::Path(path) = t else {
+        return None;
+    };
+    let segment = path.path.segments.last()?;
+    if segment.ident != name {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else {
+        return None;
+    };
+    match args.args.first()? {
+        GenericArgument::Type(t) => Some(t),
+        _ => None,
+    }
+}
+
+/// Some arguments only make sense for a single function, so they cannot be applied to all methods at once
+fn check_method_args(args: &AutometricsArgs) -> Result<()> {
+    if let Some(alias) = &args.alias {
+        return Err(syn::Error::new_spanned(
+            alias,
+            "`alias` can only be used on individual functions, not on impl blocks or traits",
+        ));
+    }
+    Ok(())
+}
+
 /// Add autometrics instrumentation to an entire impl block
-fn instrument_impl_block(
-    args: &AutometricsArgs,
-    mut item: ItemImpl,
-    attributes_to_re_add: &str,
-) -> Result<TokenStream> {
-    let struct_name = Some(item.self_ty.to_token_stream().to_string());
+fn instrument_impl_block(args: &AutometricsArgs, mut item: ItemImpl) -> Result<TokenStream> {
+    check_method_args(args)?;
+    let struct_name = item.self_ty.to_token_stream().to_string();
 
     // Replace all of the method items in place
     item.items = item
@@ -357,28 +957,76 @@ fn instrument_impl_block(
                     sig: method.sig,
                     block: Box::new(method.block),
                 };
-                let tokens = match instrument_function(args, item_fn, struct_name.as_deref()) {
+                let tokens =
+                    match instrument_function(args, item_fn, FunctionName::Method(&struct_name)) {
+                        Ok(tokens) => tokens,
+                        Err(err) => err.to_compile_error(),
+                    };
+                ImplItem::Verbatim(tokens)
+            }
+            _ => item,
+        })
+        .collect();
+
+    Ok(quote! { #item })
+}
+
+/// Add autometrics instrumentation to the default methods of a trait definition
+fn instrument_trait(args: &AutometricsArgs, mut item: ItemTrait) -> Result<TokenStream> {
+    check_method_args(args)?;
+    // Replace all of the default methods in place
+    item.items = item
+        .items
+        .into_iter()
+        .map(|item| match item {
+            TraitItem::Fn(mut method) => {
+                // Skip any methods that have the #[skip_autometrics] attribute
+                if method
+                    .attrs
+                    .iter()
+                    .any(|attr| attr.path().is_ident("skip_autometrics"))
+                {
+                    method
+                        .attrs
+                        .retain(|attr| !attr.path().is_ident("skip_autometrics"));
+                    return TraitItem::Fn(method);
+                }
+
+                // Methods without a default implementation are instrumented
+                // (or not) in the impl blocks of the implementors
+                let Some(block) = method.default else {
+                    return TraitItem::Fn(method);
+                };
+
+                let item_fn = ItemFn {
+                    attrs: method.attrs,
+                    vis: Visibility::Inherited,
+                    sig: method.sig,
+                    block: Box::new(block),
+                };
+                let tokens = match instrument_function(args, item_fn, FunctionName::TraitMethod) {
                     Ok(tokens) => tokens,
                     Err(err) => err.to_compile_error(),
                 };
-                ImplItem::Verbatim(tokens)
+                TraitItem::Verbatim(tokens)
             }
             _ => item,
         })
         .collect();
 
-    let ts = TokenStream::from_str(attributes_to_re_add)?;
-
-    Ok(quote! {
-        #ts
-        #item
-    })
+    Ok(quote! { #item })
 }
 
 /// Create Prometheus queries for the generated metric and
 /// package them up into a RustDoc string
-fn create_metrics_docs(prometheus_url: &str, function: &str, track_concurrency: bool) -> String {
-    let request_rate = request_rate_query("function", function);
+fn create_metrics_docs(
+    prometheus_url: &str,
+    function: &str,
+    track_concurrency: bool,
+    track_latency: bool,
+) -> String {
+    let function_key = label_key("function");
+    let request_rate = request_rate_query(&function_key, function);
     let request_rate_url = make_prometheus_url(
         prometheus_url,
         &request_rate,
@@ -389,21 +1037,30 @@ fn create_metrics_docs(prometheus_url: &str, function: &str, track_concurrency:
     let callee_request_rate = request_rate_query("caller_function", function);
     let callee_request_rate_url = make_prometheus_url(prometheus_url, &callee_request_rate, &format!("Rate of calls to functions called by `{function}` per second, averaged over 5 minute windows"));
 
-    let error_ratio = &error_ratio_query("function", function);
+    let error_ratio = &error_ratio_query(&function_key, function);
     let error_ratio_url = make_prometheus_url(prometheus_url, error_ratio, &format!("Percentage of calls to the `{function}` function that return errors, averaged over 5 minute windows"));
     let callee_error_ratio = &error_ratio_query("caller_function", function);
     let callee_error_ratio_url = make_prometheus_url(prometheus_url, callee_error_ratio, &format!("Percentage of calls to functions called by `{function}` that return errors, averaged over 5 minute windows"));
 
-    let latency = latency_query("function", function);
-    let latency_url = make_prometheus_url(
-        prometheus_url,
-        &latency,
-        &format!("95th and 99th percentile latencies (in seconds) for the `{function}` function"),
-    );
+    // Only include the latency query if the function is timed
+    let latency_doc = if track_latency {
+        let latency = latency_query(&function_key, function);
+        let latency_url = make_prometheus_url(
+            prometheus_url,
+            &latency,
+            &format!(
+                "95th and 99th percentile latencies (in {}) for the `{function}` function",
+                duration_unit()
+            ),
+        );
+        format!("\n- [Latency (95th and 99th percentiles)]({latency_url})")
+    } else {
+        String::new()
+    };
 
     // Only include the concurrent calls query if the user has enabled it for this function
     let concurrent_calls_doc = if track_concurrency {
-        let concurrent_calls = concurrent_calls_query("function", function);
+        let concurrent_calls = concurrent_calls_query(&function_key, function);
         let concurrent_calls_url = make_prometheus_url(
             prometheus_url,
             &concurrent_calls,
@@ -421,8 +1078,7 @@ fn create_metrics_docs(prometheus_url: &str, function: &str, track_concurrency:
 
 View the live metrics for the `{function}` function:
 - [Request Rate]({request_rate_url})
-- [Error Ratio]({error_ratio_url})
-- [Latency (95th and 99th percentiles)]({latency_url}){concurrent_calls_doc}
+- [Error Ratio]({error_ratio_url}){latency_doc}{concurrent_calls_doc}
 
 Or, dig into the metrics of *functions called by* `{function}`:
 - [Request Rate]({callee_request_rate_url})
@@ -446,20 +1102,93 @@ fn make_prometheus_url(url: &str, query: &str, comment: &str) -> String {
     url
 }
 
+/// The prefix of the metric names, which can be configured by passing the
+/// `AUTOMETRICS_METRIC_NAME_PREFIX` environment variable during build time
+/// (this needs to match `AutometricsSettingsBuilder::metric_name_prefix`)
+fn metric_name_prefix() -> String {
+    match env::var("AUTOMETRICS_METRIC_NAME_PREFIX") {
+        Ok(prefix) if !prefix.is_empty() => format!("{prefix}_"),
+        _ => String::new(),
+    }
+}
+
+/// The unit of the latency histogram (`seconds` or `milliseconds`), which can be configured by passing the
+/// `AUTOMETRICS_DURATION_UNIT` environment variable during build time
+/// (this needs to match `AutometricsSettingsBuilder::duration_unit`)
+fn duration_unit() -> &'static str {
+    match env::var("AUTOMETRICS_DURATION_UNIT").as_deref() {
+        Ok("milliseconds" | "ms") => "milliseconds",
+        _ => "seconds",
+    }
+}
+
+/// The name of the latency histogram without its unit, which follows the OpenTelemetry semantic conventions
+/// if `AUTOMETRICS_METRIC_NAMING=opentelemetry` is passed during build time
+/// (this needs to match `AutometricsSettingsBuilder::metric_naming`)
+fn histogram_name() -> &'static str {
+    match env::var("AUTOMETRICS_METRIC_NAMING").as_deref() {
+        Ok("opentelemetry" | "otel") => "function_duration",
+        _ => "function_calls_duration",
+    }
+}
+
+/// The key of the `function` or `module` label, which can be renamed by passing the
+/// `AUTOMETRICS_LABEL_KEY_OVERRIDES` environment variable (like `function=fn,module=mod`) during build time
+/// (this needs to match `AutometricsSettingsBuilder::label_key_overrides`)
+fn label_key(key: &str) -> String {
+    env::var("AUTOMETRICS_LABEL_KEY_OVERRIDES")
+        .ok()
+        .and_then(|overrides| {
+            overrides.split(',').find_map(|key_override| {
+                let (from, to) = key_override.split_once('=')?;
+                (from.trim() == key).then(|| to.trim().to_string())
+            })
+        })
+        .unwrap_or_else(|| key.to_string())
+}
+
+/// The labels that the queries are aggregated by
+fn group_by_labels() -> String {
+    format!(
+        "{}, {}, service_name, commit, version",
+        label_key("function"),
+        label_key("module")
+    )
+}
+
+fn add_build_info_labels(prefix: &str) -> String {
+    format!(
+        "* on (instance, job) group_left(version, commit) last_over_time({prefix}build_info[1s])"
+    )
+}
+
+// The queries need to match the ones built at runtime by `autometrics::queries`,
+// which is checked by comparing them with the output of `doc_queries!` in the tests
 fn request_rate_query(label_key: &str, label_value: &str) -> String {
-    format!("sum by (function, module, service_name, commit, version) (rate({{__name__=~\"function_calls(_count)?(_total)?\",{label_key}=\"{label_value}\"}}[5m]) {ADD_BUILD_INFO_LABELS})")
+    let prefix = metric_name_prefix();
+    let add_build_info_labels = add_build_info_labels(&prefix);
+    let group_by_labels = group_by_labels();
+    format!("sum by ({group_by_labels}) (rate({{__name__=~\"{prefix}function_calls(_count)?(_total)?\",{label_key}=\"{label_value}\"}}[5m]) {add_build_info_labels})")
 }
 
 fn error_ratio_query(label_key: &str, label_value: &str) -> String {
+    let prefix = metric_name_prefix();
+    let add_build_info_labels = add_build_info_labels(&prefix);
     let request_rate = request_rate_query(label_key, label_value);
-    format!("(sum by (function, module, service_name, commit, version) (rate({{__name__=~\"function_calls(_count)?(_total)?\",{label_key}=\"{label_value}\",result=\"error\"}}[5m]) {ADD_BUILD_INFO_LABELS}))
+    let group_by_labels = group_by_labels();
+    format!("(sum by ({group_by_labels}) (rate({{__name__=~\"{prefix}function_calls(_count)?(_total)?\",{label_key}=\"{label_value}\",result=\"error\"}}[5m]) {add_build_info_labels}))
 /
 ({request_rate})",)
 }
 
 fn latency_query(label_key: &str, label_value: &str) -> String {
+    let prefix = metric_name_prefix();
+    let add_build_info_labels = add_build_info_labels(&prefix);
+    let group_by_labels = group_by_labels();
+    let duration_unit = duration_unit();
+    let histogram_name = histogram_name();
     let latency = format!(
-        "sum by (le, function, module, service_name, commit, version) (rate({{__name__=~\"function_calls_duration(_seconds)?_bucket\",{label_key}=\"{label_value}\"}}[5m]) {ADD_BUILD_INFO_LABELS})"
+        "sum by (le, {group_by_labels}) (rate({{__name__=~\"{prefix}{histogram_name}(_{duration_unit})?_bucket\",{label_key}=\"{label_value}\"}}[5m]) {add_build_info_labels})"
     );
     format!(
         "label_replace(histogram_quantile(0.99, {latency}), \"percentile_latency\", \"99\", \"\", \"\")
@@ -469,5 +1198,8 @@ label_replace(histogram_quantile(0.95, {latency}), \"percentile_latency\", \"95\
 }
 
 fn concurrent_calls_query(label_key: &str, label_value: &str) -> String {
-    format!("sum by (function, module, service_name, commit, version) (function_calls_concurrent{{{label_key}=\"{label_value}\"}} {ADD_BUILD_INFO_LABELS})")
+    let prefix = metric_name_prefix();
+    let add_build_info_labels = add_build_info_labels(&prefix);
+    let group_by_labels = group_by_labels();
+    format!("sum by ({group_by_labels}) ({prefix}function_calls_concurrent{{{label_key}=\"{label_value}\"}} {add_build_info_labels})")
 }
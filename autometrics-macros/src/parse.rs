@@ -1,5 +1,9 @@
+use proc_macro2::{Span, TokenStream};
+use quote::{quote, quote_spanned, ToTokens};
 use syn::parse::{Parse, ParseStream};
-use syn::{Expr, ItemFn, ItemImpl, LitStr, Result, Token};
+use syn::punctuated::Punctuated;
+use syn::{bracketed, parenthesized, token, Expr, Ident, ItemFn, ItemImpl, ItemTrait, Lit, LitStr};
+use syn::{Result, Token};
 
 mod kw {
     syn::custom_keyword!(track_concurrency);
@@ -8,31 +12,138 @@ mod kw {
     syn::custom_keyword!(latency);
     syn::custom_keyword!(ok_if);
     syn::custom_keyword!(error_if);
+    syn::custom_keyword!(ok_if_status);
+    syn::custom_keyword!(error_if_status);
     syn::custom_keyword!(struct_name);
+    syn::custom_keyword!(recursive);
+    syn::custom_keyword!(buckets);
+    syn::custom_keyword!(error_details);
+    syn::custom_keyword!(generic_params);
+    syn::custom_keyword!(fine_grained_latency);
+    syn::custom_keyword!(sample_rate);
+    syn::custom_keyword!(skip_if);
+    syn::custom_keyword!(track_size);
+    syn::custom_keyword!(span_events);
+    syn::custom_keyword!(count_only);
+    syn::custom_keyword!(inline_hint);
+    syn::custom_keyword!(no_caller);
+    syn::custom_keyword!(no_docs);
+    syn::custom_keyword!(max);
+    syn::custom_keyword!(label);
+    syn::custom_keyword!(alias);
+
+    // Fields of custom labels
+    syn::custom_keyword!(key);
+    syn::custom_keyword!(value);
+
+    // Fields of inline objectives
+    syn::custom_keyword!(name);
+    syn::custom_keyword!(threshold);
+    syn::custom_keyword!(percentile);
 }
 
-/// Autometrics can be applied to individual functions or to
-/// (all of the methods within) impl blocks.
+/// The maximum number of `label` arguments, which must match autometrics::__private::MAX_CUSTOM_LABELS
+const MAX_CUSTOM_LABELS: usize = 2;
+
+/// The keys of the labels that autometrics sets itself, which cannot be used as custom label keys
+const RESERVED_LABEL_KEYS: [&str; 19] = [
+    "function",
+    "module",
+    "service_name",
+    "caller_function",
+    "caller_module",
+    "result",
+    "ok",
+    "error",
+    "objective_name",
+    "objective_percentile",
+    "objective_latency_threshold",
+    "recursive",
+    "error_type",
+    "type_params",
+    "panic",
+    "cold",
+    "previous_function",
+    "le",
+    "quantile",
+];
+
+/// The percentiles that have a named `ObjectivePercentile` variant
+const PERCENTILES: [(f64, &str); 4] =
+    [(90.0, "P90"), (95.0, "P95"), (99.0, "P99"), (99.9, "P99_9")];
+
+/// The latency thresholds, in seconds, that have a named `ObjectiveLatency` variant
+const LATENCIES: [(f64, &str); 14] = [
+    (0.005, "Ms5"),
+    (0.01, "Ms10"),
+    (0.025, "Ms25"),
+    (0.05, "Ms50"),
+    (0.075, "Ms75"),
+    (0.1, "Ms100"),
+    (0.25, "Ms250"),
+    (0.5, "Ms500"),
+    (0.75, "Ms750"),
+    (1.0, "Ms1000"),
+    (2.5, "Ms2500"),
+    (5.0, "Ms5000"),
+    (7.5, "Ms7500"),
+    (10.0, "Ms10000"),
+];
+
+/// Autometrics can be applied to individual functions, to
+/// (all of the methods within) impl blocks, or to (the default methods of) traits.
 pub(crate) enum Item {
     Function(ItemFn),
     Impl(ItemImpl),
+    Trait(ItemTrait),
 }
 
 impl Parse for Item {
     fn parse(input: ParseStream) -> Result<Self> {
-        input
-            .parse()
-            .map(Item::Function)
-            .or_else(|_| input.parse().map(Item::Impl))
+        match input.parse()? {
+            syn::Item::Fn(item) => Ok(Item::Function(item)),
+            syn::Item::Impl(item) => Ok(Item::Impl(item)),
+            syn::Item::Trait(item) => Ok(Item::Trait(item)),
+            item => Err(syn::Error::new_spanned(
+                item,
+                "autometrics can only be applied to functions, impl blocks, and traits",
+            )),
+        }
     }
 }
 
 #[derive(Default)]
 pub(crate) struct AutometricsArgs {
     pub track_concurrency: bool,
+    /// Whether the maximum number of concurrent calls is recorded too, with `track_concurrency(max)`
+    pub max_concurrency: bool,
+    pub error_details: bool,
+    pub generic_params: bool,
+    pub fine_grained_latency: bool,
     pub ok_if: Option<Expr>,
     pub error_if: Option<Expr>,
-    pub objective: Option<Expr>,
+    pub objective: Option<ObjectiveArg>,
+    pub recursive: Option<Recursive>,
+    pub buckets: Option<Vec<f64>>,
+    /// The span of the `buckets` argument, for reporting backends that cannot use the buckets
+    pub buckets_span: Option<Span>,
+    pub sample_rate: Option<Expr>,
+    pub skip_if: Option<Expr>,
+    /// The function that measures the size of the return value, in bytes
+    pub track_size: Option<Expr>,
+    /// Whether events are attached to the current span for slow or failed calls
+    pub span_events: bool,
+    /// The span of the `count_only` argument, if it is set
+    pub count_only: Option<Span>,
+    pub inline_hint: bool,
+    /// Whether the function skips reading and setting the task-local caller
+    pub no_caller: bool,
+    /// Whether the Prometheus query links are left out of the function's documentation
+    pub no_docs: bool,
+    /// The labels added with `label(key = "...", value = ...)`
+    pub labels: Vec<CustomLabel>,
+    /// The previous name of the function, set with `alias = "old_name"`
+    pub alias: Option<LitStr>,
 
     // Fix for https://github.com/autometrics-dev/autometrics-rs/issues/139.
     pub struct_name: Option<String>,
@@ -41,64 +152,818 @@ pub(crate) struct AutometricsArgs {
 impl Parse for AutometricsArgs {
     fn parse(input: ParseStream) -> Result<Self> {
         let mut args = AutometricsArgs::default();
-        while !input.is_empty() {
-            let lookahead = input.lookahead1();
-            if lookahead.peek(kw::track_concurrency) {
-                let _ = input.parse::<kw::track_concurrency>()?;
-                args.track_concurrency = true;
-            } else if lookahead.peek(kw::ok_if) {
-                if args.ok_if.is_some() {
-                    return Err(input.error("expected only a single `ok_if` argument"));
-                }
-                if args.error_if.is_some() {
-                    return Err(input.error("cannot use both `ok_if` and `error_if`"));
-                }
-                let ok_if = input.parse::<ExprArg<kw::ok_if>>()?;
-                args.ok_if = Some(ok_if.value);
-            } else if lookahead.peek(kw::error_if) {
-                if args.error_if.is_some() {
-                    return Err(input.error("expected only a single `error_if` argument"));
-                }
-                if args.ok_if.is_some() {
-                    return Err(input.error("cannot use both `ok_if` and `error_if`"));
-                }
-                let error_if = input.parse::<ExprArg<kw::error_if>>()?;
-                args.error_if = Some(error_if.value);
-            } else if lookahead.peek(kw::objective) {
-                let _ = input.parse::<kw::objective>()?;
-                let _ = input.parse::<Token![=]>()?;
-                if args.objective.is_some() {
-                    return Err(input.error("expected only a single `objective` argument"));
-                }
-                args.objective = Some(input.parse()?);
-            } else if lookahead.peek(kw::struct_name) {
-                let _ = input.parse::<kw::struct_name>()?;
-                let _ = input.parse::<Token![=]>()?;
-                let struct_name = input.parse::<LitStr>()?.value();
-                args.struct_name = Some(struct_name);
-            } else if lookahead.peek(Token![,]) {
-                let _ = input.parse::<Token![,]>()?;
-            } else {
-                return Err(lookahead.error());
-            }
+        // The arguments are separated by commas, with an optional trailing comma
+        for arg in Punctuated::<Arg, Token![,]>::parse_terminated(input)? {
+            args.add(arg)?;
         }
+        args.check_count_only()?;
         Ok(args)
     }
 }
 
-struct ExprArg<T> {
-    value: Expr,
-    _p: std::marker::PhantomData<T>,
+impl AutometricsArgs {
+    fn add(&mut self, arg: Arg) -> Result<()> {
+        let span = arg.span;
+        let duplicate = |name: &str| syn::Error::new(span, format!("duplicate `{name}` argument"));
+
+        match arg.value {
+            ArgValue::TrackConcurrency { max } => {
+                if self.track_concurrency {
+                    return Err(duplicate("track_concurrency"));
+                }
+                self.track_concurrency = true;
+                self.max_concurrency = max;
+            }
+            ArgValue::ErrorDetails => {
+                if self.error_details {
+                    return Err(duplicate("error_details"));
+                }
+                self.error_details = true;
+            }
+            ArgValue::GenericParams => {
+                if self.generic_params {
+                    return Err(duplicate("generic_params"));
+                }
+                self.generic_params = true;
+            }
+            ArgValue::FineGrainedLatency => {
+                if self.fine_grained_latency {
+                    return Err(duplicate("fine_grained_latency"));
+                }
+                if self.buckets.is_some() {
+                    return Err(syn::Error::new(
+                        span,
+                        "cannot use both `buckets` and `fine_grained_latency`",
+                    ));
+                }
+                self.fine_grained_latency = true;
+            }
+            ArgValue::OkIf(ok_if) => {
+                if self.ok_if.is_some() {
+                    return Err(duplicate("ok_if"));
+                }
+                if self.error_if.is_some() {
+                    return Err(syn::Error::new(
+                        span,
+                        "cannot use both `ok_if` and `error_if`",
+                    ));
+                }
+                self.ok_if = Some(ok_if);
+            }
+            ArgValue::ErrorIf(error_if) => {
+                if self.error_if.is_some() {
+                    return Err(duplicate("error_if"));
+                }
+                if self.ok_if.is_some() {
+                    return Err(syn::Error::new(
+                        span,
+                        "cannot use both `ok_if` and `error_if`",
+                    ));
+                }
+                self.error_if = Some(error_if);
+            }
+            ArgValue::Objective(objective) => {
+                if self.objective.is_some() {
+                    return Err(duplicate("objective"));
+                }
+                self.objective = Some(objective);
+            }
+            ArgValue::Recursive(recursive) => {
+                if self.recursive.is_some() {
+                    return Err(duplicate("recursive"));
+                }
+                if self.no_caller {
+                    return Err(syn::Error::new(
+                        span,
+                        "cannot use both `recursive` and `no_caller`",
+                    ));
+                }
+                self.recursive = Some(recursive);
+            }
+            ArgValue::Buckets(buckets) => {
+                if self.buckets.is_some() {
+                    return Err(duplicate("buckets"));
+                }
+                if self.fine_grained_latency {
+                    return Err(syn::Error::new(
+                        span,
+                        "cannot use both `buckets` and `fine_grained_latency`",
+                    ));
+                }
+                self.buckets = Some(buckets);
+                self.buckets_span = Some(span);
+            }
+            ArgValue::SampleRate(sample_rate) => {
+                if self.sample_rate.is_some() {
+                    return Err(duplicate("sample_rate"));
+                }
+                self.sample_rate = Some(sample_rate);
+            }
+            ArgValue::SkipIf(skip_if) => {
+                if self.skip_if.is_some() {
+                    return Err(duplicate("skip_if"));
+                }
+                self.skip_if = Some(skip_if);
+            }
+            ArgValue::TrackSize(track_size) => {
+                if self.track_size.is_some() {
+                    return Err(duplicate("track_size"));
+                }
+                self.track_size = Some(track_size);
+            }
+            ArgValue::SpanEvents => {
+                if self.span_events {
+                    return Err(duplicate("span_events"));
+                }
+                self.span_events = true;
+            }
+            ArgValue::CountOnly => {
+                if self.count_only.is_some() {
+                    return Err(duplicate("count_only"));
+                }
+                self.count_only = Some(span);
+            }
+            ArgValue::InlineHint => {
+                if self.inline_hint {
+                    return Err(duplicate("inline_hint"));
+                }
+                self.inline_hint = true;
+            }
+            ArgValue::NoCaller => {
+                if self.no_caller {
+                    return Err(duplicate("no_caller"));
+                }
+                if self.recursive.is_some() {
+                    return Err(syn::Error::new(
+                        span,
+                        "cannot use both `recursive` and `no_caller`",
+                    ));
+                }
+                self.no_caller = true;
+            }
+            ArgValue::NoDocs => {
+                if self.no_docs {
+                    return Err(duplicate("no_docs"));
+                }
+                self.no_docs = true;
+            }
+            ArgValue::Label(label) => {
+                if self.labels.len() == MAX_CUSTOM_LABELS {
+                    return Err(syn::Error::new(
+                        span,
+                        format!("at most {MAX_CUSTOM_LABELS} `label` arguments are allowed"),
+                    ));
+                }
+                if self
+                    .labels
+                    .iter()
+                    .any(|existing| existing.key.value() == label.key.value())
+                {
+                    return Err(syn::Error::new_spanned(
+                        &label.key,
+                        format!("duplicate `{}` label", label.key.value()),
+                    ));
+                }
+                self.labels.push(label);
+            }
+            ArgValue::Alias(alias) => {
+                if self.alias.is_some() {
+                    return Err(duplicate("alias"));
+                }
+                if alias.value().is_empty() {
+                    return Err(syn::Error::new_spanned(
+                        alias,
+                        "expected the alias to be the previous name of the function",
+                    ));
+                }
+                self.alias = Some(alias);
+            }
+            ArgValue::StructName(struct_name) => {
+                if self.struct_name.is_some() {
+                    return Err(duplicate("struct_name"));
+                }
+                self.struct_name = Some(struct_name);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl AutometricsArgs {
+    /// Functions with the `count_only` argument are not timed and do not track their callees,
+    /// so the arguments that depend on either of those cannot be used with it
+    fn check_count_only(&self) -> Result<()> {
+        let Some(span) = self.count_only else {
+            return Ok(());
+        };
+        let conflicting = [
+            ("track_concurrency", self.track_concurrency),
+            ("fine_grained_latency", self.fine_grained_latency),
+            ("buckets", self.buckets.is_some()),
+            ("recursive", self.recursive.is_some()),
+            ("sample_rate", self.sample_rate.is_some()),
+            ("skip_if", self.skip_if.is_some()),
+            ("track_size", self.track_size.is_some()),
+            ("span_events", self.span_events),
+        ];
+        match conflicting.iter().find(|(_, is_set)| *is_set) {
+            Some((name, _)) => Err(syn::Error::new(
+                span,
+                format!("cannot use both `count_only` and `{name}`"),
+            )),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A single argument, with the span of its name for reporting errors
+struct Arg {
+    span: Span,
+    value: ArgValue,
+}
+
+enum ArgValue {
+    TrackConcurrency { max: bool },
+    ErrorDetails,
+    GenericParams,
+    FineGrainedLatency,
+    OkIf(Expr),
+    ErrorIf(Expr),
+    Objective(ObjectiveArg),
+    Recursive(Recursive),
+    Buckets(Vec<f64>),
+    SampleRate(Expr),
+    SkipIf(Expr),
+    TrackSize(Expr),
+    SpanEvents,
+    CountOnly,
+    InlineHint,
+    NoCaller,
+    NoDocs,
+    Label(CustomLabel),
+    Alias(LitStr),
+    StructName(String),
+}
+
+impl Parse for Arg {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let span = input.span();
+        let lookahead = input.lookahead1();
+        let value = if lookahead.peek(kw::track_concurrency) {
+            input.parse::<kw::track_concurrency>()?;
+            ArgValue::TrackConcurrency {
+                max: parse_max_concurrency(input)?,
+            }
+        } else if lookahead.peek(kw::error_details) {
+            input.parse::<kw::error_details>()?;
+            ArgValue::ErrorDetails
+        } else if lookahead.peek(kw::generic_params) {
+            input.parse::<kw::generic_params>()?;
+            ArgValue::GenericParams
+        } else if lookahead.peek(kw::fine_grained_latency) {
+            input.parse::<kw::fine_grained_latency>()?;
+            ArgValue::FineGrainedLatency
+        } else if lookahead.peek(kw::ok_if) {
+            input.parse::<kw::ok_if>()?;
+            input.parse::<Token![=]>()?;
+            ArgValue::OkIf(parse_predicate(input, "ok_if")?)
+        } else if lookahead.peek(kw::error_if) {
+            input.parse::<kw::error_if>()?;
+            input.parse::<Token![=]>()?;
+            ArgValue::ErrorIf(parse_predicate(input, "error_if")?)
+        } else if lookahead.peek(kw::ok_if_status) {
+            input.parse::<kw::ok_if_status>()?;
+            ArgValue::OkIf(parse_status_predicate(input)?)
+        } else if lookahead.peek(kw::error_if_status) {
+            input.parse::<kw::error_if_status>()?;
+            ArgValue::ErrorIf(parse_status_predicate(input)?)
+        } else if lookahead.peek(kw::objective) {
+            input.parse::<kw::objective>()?;
+            ArgValue::Objective(input.parse()?)
+        } else if lookahead.peek(kw::recursive) {
+            input.parse::<kw::recursive>()?;
+            input.parse::<Token![=]>()?;
+            ArgValue::Recursive(input.parse()?)
+        } else if lookahead.peek(kw::buckets) {
+            input.parse::<kw::buckets>()?;
+            input.parse::<Token![=]>()?;
+            ArgValue::Buckets(parse_buckets(input)?)
+        } else if lookahead.peek(kw::sample_rate) {
+            input.parse::<kw::sample_rate>()?;
+            input.parse::<Token![=]>()?;
+            ArgValue::SampleRate(parse_sample_rate(input)?)
+        } else if lookahead.peek(kw::skip_if) {
+            input.parse::<kw::skip_if>()?;
+            input.parse::<Token![=]>()?;
+            ArgValue::SkipIf(input.parse()?)
+        } else if lookahead.peek(kw::track_size) {
+            input.parse::<kw::track_size>()?;
+            input.parse::<Token![=]>()?;
+            ArgValue::TrackSize(parse_predicate(input, "track_size")?)
+        } else if lookahead.peek(kw::span_events) {
+            input.parse::<kw::span_events>()?;
+            ArgValue::SpanEvents
+        } else if lookahead.peek(kw::count_only) {
+            input.parse::<kw::count_only>()?;
+            ArgValue::CountOnly
+        } else if lookahead.peek(kw::inline_hint) {
+            input.parse::<kw::inline_hint>()?;
+            ArgValue::InlineHint
+        } else if lookahead.peek(kw::no_caller) {
+            input.parse::<kw::no_caller>()?;
+            ArgValue::NoCaller
+        } else if lookahead.peek(kw::no_docs) {
+            input.parse::<kw::no_docs>()?;
+            ArgValue::NoDocs
+        } else if lookahead.peek(kw::label) {
+            input.parse::<kw::label>()?;
+            ArgValue::Label(input.parse()?)
+        } else if lookahead.peek(kw::alias) {
+            input.parse::<kw::alias>()?;
+            input.parse::<Token![=]>()?;
+            ArgValue::Alias(input.parse()?)
+        } else if lookahead.peek(kw::struct_name) {
+            input.parse::<kw::struct_name>()?;
+            input.parse::<Token![=]>()?;
+            ArgValue::StructName(input.parse::<LitStr>()?.value())
+        } else {
+            return Err(lookahead.error());
+        };
+        Ok(Arg { span, value })
+    }
+}
+
+/// Parse the optional `(max)` after `track_concurrency`
+fn parse_max_concurrency(input: ParseStream) -> Result<bool> {
+    if !input.peek(token::Paren) {
+        return Ok(false);
+    }
+    let content;
+    parenthesized!(content in input);
+    content.parse::<kw::max>()?;
+    Ok(true)
+}
+
+/// A label added with `label(key = "shard", value = self.shard())`, where the value
+/// is evaluated when the function is called and implements `autometrics::LabelValue`
+pub(crate) struct CustomLabel {
+    pub key: LitStr,
+    pub value: Expr,
+}
+
+impl Parse for CustomLabel {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let content;
+        let parens = parenthesized!(content in input);
+
+        content.parse::<kw::key>()?;
+        content.parse::<Token![=]>()?;
+        let key: LitStr = content.parse()?;
+        check_label_key(&key)?;
+        content.parse::<Token![,]>()?;
+        content.parse::<kw::value>()?;
+        content.parse::<Token![=]>()?;
+        let value = content.parse()?;
+        content.parse::<Option<Token![,]>>()?;
+        if !content.is_empty() {
+            return Err(syn::Error::new(
+                parens.span.join(),
+                "expected the label to only have a `key` and a `value`",
+            ));
+        }
+
+        Ok(CustomLabel { key, value })
+    }
+}
+
+/// The key must be a valid Prometheus label name that is not used by autometrics itself
+fn check_label_key(key: &LitStr) -> Result<()> {
+    let name = key.value();
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !name.starts_with("__");
+    if !valid {
+        return Err(syn::Error::new_spanned(
+            key,
+            "expected the label key to only contain letters, digits, and underscores, and to not start with a digit or `__`",
+        ));
+    }
+    if RESERVED_LABEL_KEYS.contains(&name.as_str()) {
+        return Err(syn::Error::new_spanned(
+            key,
+            format!("the `{name}` label is already set by autometrics"),
+        ));
+    }
+    Ok(())
+}
+
+/// The objective is either an expression that evaluates to an `Objective`
+/// (`objective = API_SLO`), or defined inline (`objective(name = "api", success_rate = 99.9)`).
+pub(crate) enum ObjectiveArg {
+    Expr(Expr),
+    Inline(InlineObjective),
+}
+
+impl Parse for ObjectiveArg {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let lookahead = input.lookahead1();
+        if lookahead.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            Ok(ObjectiveArg::Expr(input.parse()?))
+        } else if lookahead.peek(token::Paren) {
+            Ok(ObjectiveArg::Inline(input.parse()?))
+        } else {
+            Err(lookahead.error())
+        }
+    }
+}
+
+impl ToTokens for ObjectiveArg {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            ObjectiveArg::Expr(expr) => expr.to_tokens(tokens),
+            ObjectiveArg::Inline(objective) => objective.to_tokens(tokens),
+        }
+    }
+}
+
+/// An objective defined in the macro arguments, using the same fields as the objectives YAML file:
+///
+/// `objective(name = "api", success_rate = 99.9, latency(threshold = 0.25, percentile = 99))`
+pub(crate) struct InlineObjective {
+    name: LitStr,
+    success_rate: Option<TokenStream>,
+    latency: Option<(TokenStream, TokenStream)>,
 }
 
-impl<T: Parse> Parse for ExprArg<T> {
-    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
-        let _ = input.parse::<T>()?;
-        let _ = input.parse::<Token![=]>()?;
-        let value = input.parse()?;
-        Ok(Self {
-            value,
-            _p: std::marker::PhantomData,
+impl Parse for InlineObjective {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let content;
+        let parens = parenthesized!(content in input);
+
+        let mut name = None;
+        let mut success_rate = None;
+        let mut latency = None;
+        let fields = Punctuated::<ObjectiveField, Token![,]>::parse_terminated(&content)?;
+        for field in fields {
+            let duplicate = |name: &str| {
+                syn::Error::new(field.span, format!("duplicate `{name}` in the objective"))
+            };
+            match field.value {
+                ObjectiveFieldValue::Name(value) => {
+                    if name.replace(value).is_some() {
+                        return Err(duplicate("name"));
+                    }
+                }
+                ObjectiveFieldValue::SuccessRate(value) => {
+                    if success_rate.replace(value).is_some() {
+                        return Err(duplicate("success_rate"));
+                    }
+                }
+                ObjectiveFieldValue::Latency(value) => {
+                    if latency.replace(value).is_some() {
+                        return Err(duplicate("latency"));
+                    }
+                }
+            }
+        }
+
+        let Some(name) = name else {
+            return Err(syn::Error::new(
+                parens.span.join(),
+                "expected the objective to have a `name`",
+            ));
+        };
+        if success_rate.is_none() && latency.is_none() {
+            return Err(syn::Error::new(
+                parens.span.join(),
+                "expected the objective to have a `success_rate` or a `latency`",
+            ));
+        }
+
+        Ok(InlineObjective {
+            name,
+            success_rate,
+            latency,
         })
     }
 }
+
+impl ToTokens for InlineObjective {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let name = &self.name;
+        let success_rate = self.success_rate.iter();
+        let latency = self
+            .latency
+            .iter()
+            .map(|(threshold, percentile)| quote! { #threshold, #percentile });
+        tokens.extend(quote! {
+            autometrics::objectives::Objective::new(#name)
+                #(.success_rate(#success_rate))*
+                #(.latency(#latency))*
+        });
+    }
+}
+
+struct ObjectiveField {
+    span: Span,
+    value: ObjectiveFieldValue,
+}
+
+enum ObjectiveFieldValue {
+    Name(LitStr),
+    SuccessRate(TokenStream),
+    Latency((TokenStream, TokenStream)),
+}
+
+impl Parse for ObjectiveField {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let span = input.span();
+        let lookahead = input.lookahead1();
+        let value = if lookahead.peek(kw::name) {
+            input.parse::<kw::name>()?;
+            input.parse::<Token![=]>()?;
+            ObjectiveFieldValue::Name(input.parse()?)
+        } else if lookahead.peek(kw::success_rate) {
+            input.parse::<kw::success_rate>()?;
+            input.parse::<Token![=]>()?;
+            ObjectiveFieldValue::SuccessRate(parse_percentile(input)?)
+        } else if lookahead.peek(kw::latency) {
+            input.parse::<kw::latency>()?;
+            ObjectiveFieldValue::Latency(parse_latency(input)?)
+        } else {
+            return Err(lookahead.error());
+        };
+        Ok(ObjectiveField { span, value })
+    }
+}
+
+/// Parse `(threshold = 0.25, percentile = 99)` into the `ObjectiveLatency` and `ObjectivePercentile`
+fn parse_latency(input: ParseStream) -> Result<(TokenStream, TokenStream)> {
+    let content;
+    let parens = parenthesized!(content in input);
+
+    let mut threshold = None;
+    let mut percentile = None;
+    while !content.is_empty() {
+        let span = content.span();
+        let lookahead = content.lookahead1();
+        if lookahead.peek(kw::threshold) {
+            content.parse::<kw::threshold>()?;
+            content.parse::<Token![=]>()?;
+            if threshold.replace(parse_threshold(&content)?).is_some() {
+                return Err(syn::Error::new(
+                    span,
+                    "duplicate `threshold` in the latency",
+                ));
+            }
+        } else if lookahead.peek(kw::percentile) {
+            content.parse::<kw::percentile>()?;
+            content.parse::<Token![=]>()?;
+            if percentile.replace(parse_percentile(&content)?).is_some() {
+                return Err(syn::Error::new(
+                    span,
+                    "duplicate `percentile` in the latency",
+                ));
+            }
+        } else {
+            return Err(lookahead.error());
+        }
+
+        if !content.is_empty() {
+            content.parse::<Token![,]>()?;
+        }
+    }
+
+    match (threshold, percentile) {
+        (Some(threshold), Some(percentile)) => Ok((threshold, percentile)),
+        _ => Err(syn::Error::new(
+            parens.span.join(),
+            "expected the latency to have a `threshold` and a `percentile`",
+        )),
+    }
+}
+
+/// Parse a percentage between 0 and 100 into an `ObjectivePercentile`
+fn parse_percentile(input: ParseStream) -> Result<TokenStream> {
+    let (value, number, span) = parse_number(input)?;
+    if !(number > 0.0 && number <= 100.0) {
+        return Err(syn::Error::new(
+            span,
+            "expected the percentile to be a number between 0 and 100",
+        ));
+    }
+
+    Ok(objective_value(
+        "ObjectivePercentile",
+        &PERCENTILES,
+        &value,
+        number,
+        span,
+    ))
+}
+
+/// Parse a latency threshold in seconds into an `ObjectiveLatency`
+fn parse_threshold(input: ParseStream) -> Result<TokenStream> {
+    let (value, number, span) = parse_number(input)?;
+    if number <= 0.0 {
+        return Err(syn::Error::new(
+            span,
+            "expected the latency threshold to be a positive number of seconds",
+        ));
+    }
+
+    Ok(objective_value(
+        "ObjectiveLatency",
+        &LATENCIES,
+        &value,
+        number,
+        span,
+    ))
+}
+
+/// Use the named variant for the value if there is one, or the `Custom` variant otherwise
+/// (which requires the corresponding `custom-objective-*` feature).
+fn objective_value(
+    enum_name: &str,
+    variants: &[(f64, &str)],
+    value: &str,
+    number: f64,
+    span: Span,
+) -> TokenStream {
+    let enum_name = Ident::new(enum_name, span);
+    match variants.iter().find(|(variant, _)| *variant == number) {
+        Some((_, variant)) => {
+            let variant = Ident::new(variant, span);
+            quote_spanned! {span=> autometrics::objectives::#enum_name::#variant }
+        }
+        None => {
+            quote_spanned! {span=> autometrics::objectives::#enum_name::Custom(#value) }
+        }
+    }
+}
+
+/// Parse a number, which can be written as a number or a string literal,
+/// into the way it is written and its value
+fn parse_number(input: ParseStream) -> Result<(String, f64, Span)> {
+    let literal = input.parse::<Lit>()?;
+    let value = match &literal {
+        Lit::Float(float) => float.base10_digits().to_string(),
+        Lit::Int(int) => int.base10_digits().to_string(),
+        Lit::Str(string) => string.value().trim().to_string(),
+        _ => String::new(),
+    };
+    match value.parse::<f64>() {
+        Ok(number) if number.is_finite() => Ok((value, number, literal.span())),
+        _ => Err(syn::Error::new_spanned(literal, "expected a number")),
+    }
+}
+
+/// How to label the calls a recursive function makes to itself
+#[derive(Clone, Copy)]
+pub(crate) enum Recursive {
+    /// Leave the caller labels empty
+    SkipCaller,
+    /// Add the `recursive="true"` label
+    Label,
+}
+
+impl Parse for Recursive {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let ident = input.parse::<Ident>()?;
+        match ident.to_string().as_str() {
+            "skip_caller" => Ok(Recursive::SkipCaller),
+            "label" => Ok(Recursive::Label),
+            _ => Err(syn::Error::new(
+                ident.span(),
+                "expected `recursive = skip_caller` or `recursive = label`",
+            )),
+        }
+    }
+}
+
+/// Parse the histogram buckets, which must be a non-empty list of increasing numbers
+fn parse_buckets(input: ParseStream) -> Result<Vec<f64>> {
+    let content;
+    let brackets = bracketed!(content in input);
+    let literals = Punctuated::<Lit, Token![,]>::parse_terminated(&content)?;
+    if literals.is_empty() {
+        return Err(syn::Error::new(
+            brackets.span.join(),
+            "expected at least one histogram bucket",
+        ));
+    }
+
+    let mut buckets: Vec<f64> = Vec::with_capacity(literals.len());
+    for literal in literals {
+        let bucket: f64 = match &literal {
+            Lit::Float(float) => float.base10_parse()?,
+            Lit::Int(int) => int.base10_parse()?,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    literal,
+                    "expected the histogram bucket to be a number",
+                ))
+            }
+        };
+        if !bucket.is_finite() {
+            return Err(syn::Error::new_spanned(
+                literal,
+                "expected the histogram bucket to be a finite number",
+            ));
+        }
+        if buckets.last().is_some_and(|last| *last >= bucket) {
+            return Err(syn::Error::new_spanned(
+                literal,
+                "expected the histogram buckets to be in increasing order",
+            ));
+        }
+        buckets.push(bucket);
+    }
+
+    Ok(buckets)
+}
+
+/// The `ok_if` and `error_if` predicates can be any expression that is callable as `f(&T) -> bool`,
+/// such as a path to a function or method, or a closure that takes a single argument
+fn parse_predicate(input: ParseStream, arg: &str) -> Result<Expr> {
+    let expr: Expr = input.parse()?;
+    match &expr {
+        Expr::Lit(_) => Err(syn::Error::new_spanned(
+            &expr,
+            format!("expected `{arg}` to be a function, a method, or a closure"),
+        )),
+        Expr::Closure(closure) if closure.inputs.len() != 1 => Err(syn::Error::new_spanned(
+            &closure.inputs,
+            format!("expected the `{arg}` closure to take a single argument"),
+        )),
+        _ => Ok(expr),
+    }
+}
+
+/// The `ok_if_status` and `error_if_status` arguments compare the status code of the return value
+/// (`ok_if_status < 500`) or check whether it is in a list (`error_if_status in [500, 503]`),
+/// and are turned into a closure that is used like the `ok_if` and `error_if` predicates
+fn parse_status_predicate(input: ParseStream) -> Result<Expr> {
+    let status = quote!(::autometrics::ResultStatus::status_code(
+        __autometrics_result
+    ));
+    if input.peek(Token![in]) {
+        input.parse::<Token![in]>()?;
+        let codes: syn::ExprArray = input.parse()?;
+        return Ok(syn::parse_quote!(|__autometrics_result| #codes.contains(&#status)));
+    }
+
+    let op: syn::BinOp = input.parse().map_err(|err| {
+        syn::Error::new(
+            err.span(),
+            "expected a comparison (`<`, `<=`, `>`, `>=`, `==`, or `!=`) or `in` followed by a list of status codes",
+        )
+    })?;
+    if !matches!(
+        op,
+        syn::BinOp::Lt(_)
+            | syn::BinOp::Le(_)
+            | syn::BinOp::Gt(_)
+            | syn::BinOp::Ge(_)
+            | syn::BinOp::Eq(_)
+            | syn::BinOp::Ne(_)
+    ) {
+        return Err(syn::Error::new_spanned(
+            op,
+            "expected the status code to be compared with `<`, `<=`, `>`, `>=`, `==`, or `!=`",
+        ));
+    }
+    let code: Expr = input.parse()?;
+    Ok(syn::parse_quote!(|__autometrics_result| #status #op #code))
+}
+
+/// The sample rate can be any expression that evaluates to an `f64`,
+/// but literals are checked to be between 0 (exclusive) and 1 (inclusive)
+fn parse_sample_rate(input: ParseStream) -> Result<Expr> {
+    let expr: Expr = input.parse()?;
+    if let Expr::Lit(syn::ExprLit { lit, .. }) = &expr {
+        let sample_rate: f64 = match lit {
+            Lit::Float(float) => float.base10_parse()?,
+            Lit::Int(int) => int.base10_parse()?,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    lit,
+                    "expected the sample rate to be a number",
+                ))
+            }
+        };
+        if !(sample_rate > 0.0 && sample_rate <= 1.0) {
+            return Err(syn::Error::new_spanned(
+                lit,
+                "expected the sample rate to be greater than 0 and at most 1",
+            ));
+        }
+        // Integer literals are turned into floats
+        return Ok(syn::parse_quote_spanned!(lit.span()=> #sample_rate));
+    }
+    Ok(expr)
+}
@@ -5,7 +5,7 @@ use proc_macro2::TokenStream;
 use quote::quote;
 use syn::{
     punctuated::Punctuated, token::Comma, Attribute, Data, DataEnum, DeriveInput, Error, Expr,
-    ExprLit, Ident, Lit, LitStr, Result, Variant,
+    ExprLit, ExprPath, Fields, Lit, LitStr, Path, Result, Variant,
 };
 
 // These labels must match autometrics::ERROR_KEY and autometrics::OK_KEY,
@@ -13,85 +13,112 @@ use syn::{
 const OK_KEY: &str = "ok";
 const ERROR_KEY: &str = "error";
 const RESULT_KEY: &str = "result";
+const RESULT_FN_KEY: &str = "result_fn";
 const ATTR_LABEL: &str = "label";
 const ACCEPTED_LABELS: [&str; 2] = [ERROR_KEY, OK_KEY];
 
+/// The value of a `#[label(...)]` attribute
+enum LabelAttribute {
+    /// `#[label(result = "ok")]`, which always uses the given label
+    Result(LitStr),
+    /// `#[label(result_fn = path)]`, which calls the function to compute the label
+    ResultFn(Path),
+}
+
 /// Entry point of the ResultLabels macro
 pub(crate) fn expand(input: DeriveInput) -> Result<TokenStream> {
-    let variants = match &input.data {
-        Data::Enum(DataEnum { variants, .. }) => variants,
-        _ => {
+    let labels = match &input.data {
+        Data::Enum(DataEnum { variants, .. }) => enum_label_clauses(variants)?,
+        Data::Struct(_) => struct_label(&input.attrs)?,
+        Data::Union(_) => {
             return Err(Error::new_spanned(
                 input,
-                "ResultLabels only works with 'Enum's.",
+                "ResultLabels only works with 'Enum's and 'Struct's.",
             ))
         }
     };
-    let enum_name = &input.ident;
+    let type_name = &input.ident;
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
-    let conditional_clauses_for_labels = conditional_label_clauses(variants, enum_name)?;
 
     Ok(quote! {
         #[automatically_derived]
-        impl #impl_generics ::autometrics::__private::GetLabels for #enum_name #ty_generics #where_clause {
+        impl #impl_generics ::autometrics::__private::GetLabels for #type_name #ty_generics #where_clause {
+            #[allow(unreachable_patterns)]
             fn __autometrics_get_labels(&self) -> Option<&'static str> {
-                #conditional_clauses_for_labels
+                #labels
             }
         }
     })
 }
 
-/// Build the list of match clauses for the generated code.
-fn conditional_label_clauses(
-    variants: &Punctuated<Variant, Comma>,
-    enum_name: &Ident,
-) -> Result<TokenStream> {
+/// Build the label of a struct, which is set by an attribute on the struct itself.
+fn struct_label(attrs: &[Attribute]) -> Result<TokenStream> {
+    Ok(match extract_label_attribute(attrs)? {
+        Some(LabelAttribute::Result(key)) => quote! { Some(#key) },
+        Some(LabelAttribute::ResultFn(path)) => quote! { #path(self) },
+        None => quote! { None },
+    })
+}
+
+/// Build the match over the variants of an enum for the generated code.
+fn enum_label_clauses(variants: &Punctuated<Variant, Comma>) -> Result<TokenStream> {
     let clauses: Vec<TokenStream> = variants
         .iter()
         .map(|variant| {
             let variant_name = &variant.ident;
-            let variant_matcher: TokenStream = match variant.fields {
-                syn::Fields::Named(_) => quote! { #variant_name {..} },
-                syn::Fields::Unnamed(_) => quote! { #variant_name (_) },
-                syn::Fields::Unit => quote! { #variant_name },
-            };
-            if let Some(key) = extract_label_attribute(&variant.attrs)? {
-                Ok(quote! [
-                    else if ::std::matches!(self, & #enum_name :: #variant_matcher) {
-                       Some(#key)
-                    }
-                ])
-            } else {
+            match extract_label_attribute(&variant.attrs)? {
+                Some(LabelAttribute::Result(key)) => Ok(quote! {
+                    Self::#variant_name { .. } => Some(#key),
+                }),
+                Some(LabelAttribute::ResultFn(path)) => {
+                    // The function is called with the value inside of the variant
+                    let inner_matcher = match &variant.fields {
+                        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                            quote! { (inner) }
+                        }
+                        Fields::Named(fields) if fields.named.len() == 1 => {
+                            let field = &fields.named[0].ident;
+                            quote! { { #field: inner } }
+                        }
+                        _ => {
+                            return Err(Error::new_spanned(
+                                variant,
+                                format!("`{RESULT_FN_KEY}` can only be used on variants with a single field"),
+                            ))
+                        }
+                    };
+                    Ok(quote! {
+                        Self::#variant_name #inner_matcher => #path(inner),
+                    })
+                }
                 // Let the code flow through the last value
-                Ok(quote! {})
+                None => Ok(quote! {}),
             }
         })
         .collect::<Result<Vec<_>>>()?;
 
-    Ok(quote! [
-        if false {
-            None
-        }
-        #(#clauses)*
-        else {
-            None
+    Ok(quote! {
+        match self {
+            #(#clauses)*
+            _ => None,
         }
-    ])
+    })
 }
 
-/// Extract the wanted label from the annotation in the variant, if present.
-/// The function looks for `#[label(result = "ok")]` kind of labels.
+/// Extract the wanted label from the annotation in the variant or struct, if present.
+/// The function looks for `#[label(result = "ok")]` and `#[label(result_fn = path)]` kind of labels.
 ///
 /// ## Error cases
 ///
 /// The function will error out with the smallest possible span when:
 ///
 /// - The attribute on a variant is not a "list" type (so `#[label]` is not allowed),
-/// - The key in the key value pair is not "result", as it's the only supported keyword
+/// - The key in the key value pair is not "result" or "result_fn", as they are the only supported keywords
 ///   for now (so `#[label(non_existing_label = "ok")]` is not allowed),
 /// - The value for the "result" label is not in the autometrics supported set (so
 ///   `#[label(result = "random label that will break queries")]` is not allowed)
-fn extract_label_attribute(attrs: &[Attribute]) -> Result<Option<LitStr>> {
+/// - The value for the "result_fn" label is not a path to a function
+fn extract_label_attribute(attrs: &[Attribute]) -> Result<Option<LabelAttribute>> {
     attrs
             .iter()
             .find_map(|att| match &att.meta {
@@ -108,17 +135,28 @@ fn extract_label_attribute(attrs: &[Attribute]) -> Result<Option<LitStr>> {
                                 Err(
                                     Error::new_spanned(
                                         &att.meta,
-                                        format!("Only `{ATTR_LABEL}({RESULT_KEY} = \"RES\")` (RES can be {OK_KEY:?} or {ERROR_KEY:?}) is supported"),
+                                        format!("Only `{ATTR_LABEL}({RESULT_KEY} = \"RES\")` (RES can be {OK_KEY:?} or {ERROR_KEY:?}) or `{ATTR_LABEL}({RESULT_FN_KEY} = path)` is supported"),
                                     ),
                                 ),
                             ),
                         };
 
-                        // Inside list, only 'result = ...' are allowed
+                        // Inside 'result_fn = path', 'path' must be the path to a function
+                        if pair.path.segments.len() == 1 && pair.path.segments[0].ident == RESULT_FN_KEY {
+                            return Some(match pair.value {
+                                Expr::Path(ExprPath { path, .. }) => Ok(LabelAttribute::ResultFn(path)),
+                                _ => Err(Error::new_spanned(
+                                    &pair.value,
+                                    format!("Only the path to a function is accepted as the {RESULT_FN_KEY} value"),
+                                )),
+                            });
+                        }
+
+                        // Inside list, only 'result = ...' and 'result_fn = ...' are allowed
                         if pair.path.segments.len() != 1 || pair.path.segments[0].ident != RESULT_KEY {
                             return Some(Err(Error::new_spanned(
                                 pair.path.clone(),
-                            format!("Only `{RESULT_KEY} = \"RES\"` (RES can be {OK_KEY:?} or {ERROR_KEY:?}) is supported"),
+                            format!("Only `{RESULT_KEY} = \"RES\"` (RES can be {OK_KEY:?} or {ERROR_KEY:?}) or `{RESULT_FN_KEY} = path` is supported"),
                             )));
                         }
 
@@ -141,18 +179,18 @@ fn extract_label_attribute(attrs: &[Attribute]) -> Result<Option<LitStr>> {
                             )));
                         }
 
-                        Some(Ok(lit_str.clone()))
+                        Some(Ok(LabelAttribute::Result(lit_str.clone())))
                     },
                     syn::Meta::NameValue(nv) if nv.path.segments.len() == 1 && nv.path.segments[0].ident == ATTR_LABEL => {
                         Some(Err(Error::new_spanned(
                             nv,
-                            format!("Only `{ATTR_LABEL}({RESULT_KEY} = \"RES\")` (RES can be {OK_KEY:?} or {ERROR_KEY:?}) is supported"),
+                            format!("Only `{ATTR_LABEL}({RESULT_KEY} = \"RES\")` (RES can be {OK_KEY:?} or {ERROR_KEY:?}) or `{ATTR_LABEL}({RESULT_FN_KEY} = path)` is supported"),
                         )))
                     },
                     syn::Meta::Path(p) if p.segments.len() == 1 && p.segments[0].ident == ATTR_LABEL => {
                         Some(Err(Error::new_spanned(
                             p,
-                            format!("Only `{ATTR_LABEL}({RESULT_KEY} = \"RES\")` (RES can be {OK_KEY:?} or {ERROR_KEY:?}) is supported"),
+                            format!("Only `{ATTR_LABEL}({RESULT_KEY} = \"RES\")` (RES can be {OK_KEY:?} or {ERROR_KEY:?}) or `{ATTR_LABEL}({RESULT_FN_KEY} = path)` is supported"),
                         )))
                     },
                     _ => None,
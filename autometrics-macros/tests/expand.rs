@@ -0,0 +1,20 @@
+//! Snapshots of the code generated by the `autometrics` macro, so that changes to the
+//! expansion show up in reviews.
+//!
+//! These require [`cargo expand`](https://github.com/dtolnay/cargo-expand) and run with the `debug-expansion`
+//! feature, which leaves out the generated documentation: `cargo test --features debug-expansion --test expand`.
+//! To update the snapshots after changing the macro, run the tests with `MACROTEST=overwrite`.
+//! The snapshots are generated on Linux, because the function registry uses platform-specific link sections.
+#![cfg(feature = "debug-expansion")]
+
+#[test]
+fn expansion_snapshots() {
+    // The files are expanded one at a time, because the module path in the expansion
+    // depends on the position of the file among the expanded files
+    for file in ["basic", "async_fn", "methods", "arguments"] {
+        macrotest::expand_args(
+            format!("tests/expand/{file}.rs"),
+            &["--features", "debug-expansion"],
+        );
+    }
+}
@@ -0,0 +1,2054 @@
+use autometrics::autometrics;
+struct Response {
+    body: Vec<u8>,
+}
+fn predicate(id: u32) -> Option<u32> {
+    const __AUTOMETRICS_FUNCTION_ID: autometrics::__private::FunctionId = autometrics::__private::FunctionId::new(
+        "predicate",
+        "arguments",
+        autometrics::__private::crate_name(::core::option::Option::Some("arguments")),
+    );
+    static __AUTOMETRICS_CALL_SITE: autometrics::__private::CallSite = autometrics::__private::CallSite::new();
+    {
+        use autometrics::__private::{
+            linkme::distributed_slice, FUNCTION_DESCRIPTIONS, FunctionDescription,
+        };
+        #[used]
+        #[unsafe(link_section = "linkme_FUNCTION_DESCRIPTIONS")]
+        static FUNCTION_DESCRIPTION: FunctionDescription = {
+            #[allow(clippy::no_effect_underscore_binding)]
+            unsafe fn __typecheck(_: autometrics::__private::linkme::__private37::Void) {
+                #[allow(clippy::ref_option_ref)]
+                let __new = || -> fn() -> &'static FunctionDescription {
+                    || &FUNCTION_DESCRIPTION
+                };
+                unsafe {
+                    autometrics::__private::linkme::DistributedSlice::private_typecheck(
+                        FUNCTION_DESCRIPTIONS,
+                        __new(),
+                    );
+                }
+            }
+            FunctionDescription {
+                id: __AUTOMETRICS_FUNCTION_ID,
+                objective: || None,
+            }
+        };
+    }
+    const _: () = autometrics::__private::check_per_function_buckets::<()>();
+    let __autometrics_settings = {
+        use autometrics::__private::{settings, AutometricsSettings, OnceCell};
+        static SETTINGS: OnceCell<&'static AutometricsSettings> = OnceCell::new();
+        *SETTINGS
+            .get_or_init(|| {
+                autometrics::__private::observe_function(&__AUTOMETRICS_FUNCTION_ID);
+                settings()
+            })
+    };
+    let __autometrics_tracker = {
+        use autometrics::__private::{AutometricsTracker, PanicGuard, TrackMetrics};
+        {
+            use autometrics::__private::{
+                set_build_timestamp, AutometricsTracker, BuildInfoLabels, TrackMetrics,
+            };
+            set_build_timestamp(::core::option::Option::None::<&'static str>);
+            AutometricsTracker::set_build_info(
+                &BuildInfoLabels::new(
+                        __autometrics_settings,
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::Some("0.0.0"))
+                            .unwrap_or_default(),
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>)
+                            .unwrap_or_default(),
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>)
+                            .unwrap_or_default(),
+                    )
+                    .with_toolchain(
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>),
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>),
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>),
+                    ),
+            );
+        }
+        let tracker = AutometricsTracker::start(None);
+        PanicGuard::new(
+            tracker,
+            move || {
+                use autometrics::__private::{CallerInfo, CounterLabels, HistogramLabels};
+                let caller = CallerInfo::current(__autometrics_settings);
+                let counter_labels = CounterLabels::new(
+                    __autometrics_settings,
+                    "predicate",
+                    "arguments",
+                    caller.caller_function,
+                    caller.caller_module,
+                    None,
+                    None,
+                );
+                let histogram_labels = HistogramLabels::new(
+                        __autometrics_settings,
+                        "predicate",
+                        "arguments",
+                        None,
+                    )
+                    .with_buckets(&[0.1f64, 1f64]);
+                (counter_labels, histogram_labels)
+            },
+        )
+    };
+    let result: Option<u32> = {
+        use autometrics::__private::CallerInfo;
+        let caller = CallerInfo {
+            caller_function: "predicate",
+            caller_module: "arguments",
+        };
+        caller.sync_scope(__autometrics_settings, move || { { Some(id) } })
+    };
+    {
+        use autometrics::__private::HistogramLabels;
+        let counter_labels = {
+            use autometrics::__private::{
+                CallerInfo, CounterLabels, GetStaticStrFromIntoStaticStr, GetStaticStr,
+            };
+            let result_label = if autometrics::__private::check_predicate(
+                &result,
+                Option::is_some,
+            ) {
+                "ok"
+            } else {
+                "error"
+            };
+            let value_type = (&result).__autometrics_static_str();
+            let caller = CallerInfo::current(__autometrics_settings);
+            CounterLabels::new(
+                    __autometrics_settings,
+                    "predicate",
+                    "arguments",
+                    caller.caller_function,
+                    caller.caller_module,
+                    Some((result_label, value_type)),
+                    None,
+                )
+                .with_error_type({
+                    {
+                        struct Match<T>(core::cell::Cell<Option<T>>);
+                        trait Match1 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T, E> Match1 for &Match<&::std::result::Result<T, E>>
+                        where
+                            E: ::std::error::Error,
+                        {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(_) => None,
+                                        Err(err) => Some(::autometrics::__private::error_type(err)),
+                                    }
+                                }
+                            }
+                        }
+                        trait Match2 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match2 for Match<T> {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                { None }
+                            }
+                        }
+                        (&&Match(core::cell::Cell::new(Some(&result)))).spez()
+                    }
+                })
+                .with_error_class({
+                    {
+                        struct Match<T>(core::cell::Cell<Option<T>>);
+                        trait Match1 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T, E> Match1 for &&&Match<&::std::result::Result<T, E>>
+                        where
+                            E: ::std::error::Error + 'static,
+                        {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(_) => None,
+                                        Err(err) => ::autometrics::__private::classify_error(err),
+                                    }
+                                }
+                            }
+                        }
+                        trait Match2 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match2
+                        for &&Match<
+                            &::std::result::Result<
+                                T,
+                                ::std::boxed::Box<dyn ::std::error::Error + Send + Sync>,
+                            >,
+                        > {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(_) => None,
+                                        Err(err) => ::autometrics::__private::classify_error(&**err),
+                                    }
+                                }
+                            }
+                        }
+                        trait Match3 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match3
+                        for &Match<
+                            &::std::result::Result<
+                                T,
+                                ::std::boxed::Box<dyn ::std::error::Error>,
+                            >,
+                        > {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(_) => None,
+                                        Err(err) => ::autometrics::__private::classify_error(&**err),
+                                    }
+                                }
+                            }
+                        }
+                        trait Match4 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match4 for Match<T> {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                { None }
+                            }
+                        }
+                        (&&&&Match(core::cell::Cell::new(Some(&result)))).spez()
+                    }
+                })
+                .with_call_site(&__AUTOMETRICS_CALL_SITE)
+        };
+        let histogram_labels = HistogramLabels::new(
+                __autometrics_settings,
+                "predicate",
+                "arguments",
+                None,
+            )
+            .with_buckets(&[0.1f64, 1f64])
+            .with_call_site(&__AUTOMETRICS_CALL_SITE);
+        __autometrics_tracker.finish(&counter_labels, &histogram_labels);
+    }
+    result
+}
+fn count_only() {
+    const __AUTOMETRICS_FUNCTION_ID: autometrics::__private::FunctionId = autometrics::__private::FunctionId::new(
+        "count_only",
+        "arguments",
+        autometrics::__private::crate_name(::core::option::Option::Some("arguments")),
+    );
+    static __AUTOMETRICS_CALL_SITE: autometrics::__private::CallSite = autometrics::__private::CallSite::new();
+    {
+        use autometrics::__private::{
+            linkme::distributed_slice, FUNCTION_DESCRIPTIONS, FunctionDescription,
+        };
+        #[used]
+        #[unsafe(link_section = "linkme_FUNCTION_DESCRIPTIONS")]
+        static FUNCTION_DESCRIPTION: FunctionDescription = {
+            #[allow(clippy::no_effect_underscore_binding)]
+            unsafe fn __typecheck(_: autometrics::__private::linkme::__private37::Void) {
+                #[allow(clippy::ref_option_ref)]
+                let __new = || -> fn() -> &'static FunctionDescription {
+                    || &FUNCTION_DESCRIPTION
+                };
+                unsafe {
+                    autometrics::__private::linkme::DistributedSlice::private_typecheck(
+                        FUNCTION_DESCRIPTIONS,
+                        __new(),
+                    );
+                }
+            }
+            FunctionDescription {
+                id: __AUTOMETRICS_FUNCTION_ID,
+                objective: || None,
+            }
+        };
+    }
+    let __autometrics_settings = {
+        use autometrics::__private::{settings, AutometricsSettings, OnceCell};
+        static SETTINGS: OnceCell<&'static AutometricsSettings> = OnceCell::new();
+        *SETTINGS
+            .get_or_init(|| {
+                autometrics::__private::observe_function(&__AUTOMETRICS_FUNCTION_ID);
+                let __autometrics_settings = settings();
+                {
+                    use autometrics::__private::{
+                        set_build_timestamp, AutometricsTracker, BuildInfoLabels, TrackMetrics,
+                    };
+                    set_build_timestamp(::core::option::Option::None::<&'static str>);
+                    AutometricsTracker::set_build_info(
+                        &BuildInfoLabels::new(
+                                __autometrics_settings,
+                                ::core::option::Option::None::<&'static str>
+                                    .or(::core::option::Option::Some("0.0.0"))
+                                    .unwrap_or_default(),
+                                ::core::option::Option::None::<&'static str>
+                                    .or(::core::option::Option::None::<&'static str>)
+                                    .unwrap_or_default(),
+                                ::core::option::Option::None::<&'static str>
+                                    .or(::core::option::Option::None::<&'static str>)
+                                    .unwrap_or_default(),
+                            )
+                            .with_toolchain(
+                                ::core::option::Option::None::<&'static str>
+                                    .or(::core::option::Option::None::<&'static str>),
+                                ::core::option::Option::None::<&'static str>
+                                    .or(::core::option::Option::None::<&'static str>),
+                                ::core::option::Option::None::<&'static str>
+                                    .or(::core::option::Option::None::<&'static str>),
+                            ),
+                    );
+                }
+                __autometrics_settings
+            })
+    };
+    let __autometrics_guard = autometrics::__private::CountGuard::new(move || {
+        use autometrics::__private::{CallerInfo, CounterLabels};
+        let caller = CallerInfo::current(__autometrics_settings);
+        CounterLabels::new(
+                __autometrics_settings,
+                "count_only",
+                "arguments",
+                caller.caller_function,
+                caller.caller_module,
+                None,
+                None,
+            )
+            .with_call_site(&__AUTOMETRICS_CALL_SITE)
+    });
+    let result: () = (move || {})();
+    {
+        let counter_labels = {
+            use autometrics::__private::{CallerInfo, CounterLabels, GetLabels};
+            let result_labels = {
+                use ::autometrics::__private::{
+                    GetLabels, GetStaticStr, ResultAndReturnTypeLabels, ERROR_KEY, OK_KEY,
+                };
+                {
+                    struct Match<T>(core::cell::Cell<Option<T>>);
+                    trait Match1 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T, E> Match1 for &&&&&Match<&::std::result::Result<T, E>>
+                    where
+                        T: GetLabels,
+                        E: GetLabels,
+                    {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                match val {
+                                    Ok(ok) => {
+                                        Some((
+                                            ok.__autometrics_get_labels().unwrap_or(OK_KEY),
+                                            ok.__autometrics_static_str(),
+                                        ))
+                                    }
+                                    Err(err) => {
+                                        Some((
+                                            err.__autometrics_get_labels().unwrap_or(ERROR_KEY),
+                                            err.__autometrics_static_str(),
+                                        ))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    trait Match2 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T, E> Match2 for &&&&Match<&::std::result::Result<T, E>>
+                    where
+                        E: GetLabels,
+                    {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                match val {
+                                    Ok(ok) => Some((OK_KEY, ok.__autometrics_static_str())),
+                                    Err(err) => {
+                                        Some((
+                                            err.__autometrics_get_labels().unwrap_or(ERROR_KEY),
+                                            err.__autometrics_static_str(),
+                                        ))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    trait Match3 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T, E> Match3 for &&&Match<&::std::result::Result<T, E>>
+                    where
+                        T: GetLabels,
+                    {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                match val {
+                                    Ok(ok) => {
+                                        Some((
+                                            ok.__autometrics_get_labels().unwrap_or(OK_KEY),
+                                            ok.__autometrics_static_str(),
+                                        ))
+                                    }
+                                    Err(err) => {
+                                        Some((ERROR_KEY, err.__autometrics_static_str()))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    trait Match4 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T, E> Match4 for &&Match<&::std::result::Result<T, E>> {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                match val {
+                                    Ok(ok) => Some((OK_KEY, ok.__autometrics_static_str())),
+                                    Err(err) => {
+                                        Some((ERROR_KEY, err.__autometrics_static_str()))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    trait Match5 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T> Match5 for &Match<&T>
+                    where
+                        T: GetLabels,
+                    {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                val.__autometrics_get_labels()
+                                    .map(|label| (label, val.__autometrics_static_str()))
+                            }
+                        }
+                    }
+                    trait Match6 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T> Match6 for Match<T> {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            { None }
+                        }
+                    }
+                    (&&&&&&Match(core::cell::Cell::new(Some(&result)))).spez()
+                }
+            };
+            let caller = CallerInfo::current(__autometrics_settings);
+            CounterLabels::new(
+                    __autometrics_settings,
+                    "count_only",
+                    "arguments",
+                    caller.caller_function,
+                    caller.caller_module,
+                    result_labels,
+                    None,
+                )
+                .with_error_class({
+                    {
+                        struct Match<T>(core::cell::Cell<Option<T>>);
+                        trait Match1 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T, E> Match1 for &&&Match<&::std::result::Result<T, E>>
+                        where
+                            E: ::std::error::Error + 'static,
+                        {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(_) => None,
+                                        Err(err) => ::autometrics::__private::classify_error(err),
+                                    }
+                                }
+                            }
+                        }
+                        trait Match2 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match2
+                        for &&Match<
+                            &::std::result::Result<
+                                T,
+                                ::std::boxed::Box<dyn ::std::error::Error + Send + Sync>,
+                            >,
+                        > {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(_) => None,
+                                        Err(err) => ::autometrics::__private::classify_error(&**err),
+                                    }
+                                }
+                            }
+                        }
+                        trait Match3 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match3
+                        for &Match<
+                            &::std::result::Result<
+                                T,
+                                ::std::boxed::Box<dyn ::std::error::Error>,
+                            >,
+                        > {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(_) => None,
+                                        Err(err) => ::autometrics::__private::classify_error(&**err),
+                                    }
+                                }
+                            }
+                        }
+                        trait Match4 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match4 for Match<T> {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                { None }
+                            }
+                        }
+                        (&&&&Match(core::cell::Cell::new(Some(&result)))).spez()
+                    }
+                })
+                .with_call_site(&__AUTOMETRICS_CALL_SITE)
+        };
+        __autometrics_guard.finish(&counter_labels);
+    }
+    result
+}
+fn sampled() {
+    const __AUTOMETRICS_FUNCTION_ID: autometrics::__private::FunctionId = autometrics::__private::FunctionId::new(
+        "sampled",
+        "arguments",
+        autometrics::__private::crate_name(::core::option::Option::Some("arguments")),
+    );
+    static __AUTOMETRICS_CALL_SITE: autometrics::__private::CallSite = autometrics::__private::CallSite::new();
+    {
+        use autometrics::__private::{
+            linkme::distributed_slice, FUNCTION_DESCRIPTIONS, FunctionDescription,
+        };
+        #[used]
+        #[unsafe(link_section = "linkme_FUNCTION_DESCRIPTIONS")]
+        static FUNCTION_DESCRIPTION: FunctionDescription = {
+            #[allow(clippy::no_effect_underscore_binding)]
+            unsafe fn __typecheck(_: autometrics::__private::linkme::__private37::Void) {
+                #[allow(clippy::ref_option_ref)]
+                let __new = || -> fn() -> &'static FunctionDescription {
+                    || &FUNCTION_DESCRIPTION
+                };
+                unsafe {
+                    autometrics::__private::linkme::DistributedSlice::private_typecheck(
+                        FUNCTION_DESCRIPTIONS,
+                        __new(),
+                    );
+                }
+            }
+            FunctionDescription {
+                id: __AUTOMETRICS_FUNCTION_ID,
+                objective: || None,
+            }
+        };
+    }
+    let __autometrics_settings = {
+        use autometrics::__private::{settings, AutometricsSettings, OnceCell};
+        static SETTINGS: OnceCell<&'static AutometricsSettings> = OnceCell::new();
+        *SETTINGS
+            .get_or_init(|| {
+                autometrics::__private::observe_function(&__AUTOMETRICS_FUNCTION_ID);
+                settings()
+            })
+    };
+    let __autometrics_recursive = {
+        use autometrics::__private::CallerInfo;
+        CallerInfo::current(__autometrics_settings).is_function("sampled", "arguments")
+    };
+    let __autometrics_tracker = {
+        use autometrics::__private::{AutometricsTracker, PanicGuard, TrackMetrics};
+        {
+            use autometrics::__private::{
+                set_build_timestamp, AutometricsTracker, BuildInfoLabels, TrackMetrics,
+            };
+            set_build_timestamp(::core::option::Option::None::<&'static str>);
+            AutometricsTracker::set_build_info(
+                &BuildInfoLabels::new(
+                        __autometrics_settings,
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::Some("0.0.0"))
+                            .unwrap_or_default(),
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>)
+                            .unwrap_or_default(),
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>)
+                            .unwrap_or_default(),
+                    )
+                    .with_toolchain(
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>),
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>),
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>),
+                    ),
+            );
+        }
+        let tracker = AutometricsTracker::start_sampled(
+            None,
+            if (|| false)() { 0 } else { autometrics::__private::sample_weight(0.1f64) },
+        );
+        PanicGuard::new(
+            tracker,
+            move || {
+                use autometrics::__private::{CallerInfo, CounterLabels, HistogramLabels};
+                let caller = CallerInfo::current(__autometrics_settings);
+                let counter_labels = CounterLabels::new(
+                        __autometrics_settings,
+                        "sampled",
+                        "arguments",
+                        caller.caller_function,
+                        caller.caller_module,
+                        None,
+                        None,
+                    )
+                    .with_recursive(__autometrics_recursive);
+                let histogram_labels = HistogramLabels::new(
+                    __autometrics_settings,
+                    "sampled",
+                    "arguments",
+                    None,
+                );
+                (counter_labels, histogram_labels)
+            },
+        )
+    };
+    let result: () = {
+        use autometrics::__private::CallerInfo;
+        let caller = CallerInfo {
+            caller_function: "sampled",
+            caller_module: "arguments",
+        };
+        caller.sync_scope(__autometrics_settings, move || { {} })
+    };
+    {
+        use autometrics::__private::HistogramLabels;
+        let counter_labels = {
+            use autometrics::__private::{CallerInfo, CounterLabels, GetLabels};
+            let result_labels = {
+                use ::autometrics::__private::{
+                    GetLabels, GetStaticStr, ResultAndReturnTypeLabels, ERROR_KEY, OK_KEY,
+                };
+                {
+                    struct Match<T>(core::cell::Cell<Option<T>>);
+                    trait Match1 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T, E> Match1 for &&&&&Match<&::std::result::Result<T, E>>
+                    where
+                        T: GetLabels,
+                        E: GetLabels,
+                    {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                match val {
+                                    Ok(ok) => {
+                                        Some((
+                                            ok.__autometrics_get_labels().unwrap_or(OK_KEY),
+                                            ok.__autometrics_static_str(),
+                                        ))
+                                    }
+                                    Err(err) => {
+                                        Some((
+                                            err.__autometrics_get_labels().unwrap_or(ERROR_KEY),
+                                            err.__autometrics_static_str(),
+                                        ))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    trait Match2 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T, E> Match2 for &&&&Match<&::std::result::Result<T, E>>
+                    where
+                        E: GetLabels,
+                    {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                match val {
+                                    Ok(ok) => Some((OK_KEY, ok.__autometrics_static_str())),
+                                    Err(err) => {
+                                        Some((
+                                            err.__autometrics_get_labels().unwrap_or(ERROR_KEY),
+                                            err.__autometrics_static_str(),
+                                        ))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    trait Match3 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T, E> Match3 for &&&Match<&::std::result::Result<T, E>>
+                    where
+                        T: GetLabels,
+                    {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                match val {
+                                    Ok(ok) => {
+                                        Some((
+                                            ok.__autometrics_get_labels().unwrap_or(OK_KEY),
+                                            ok.__autometrics_static_str(),
+                                        ))
+                                    }
+                                    Err(err) => {
+                                        Some((ERROR_KEY, err.__autometrics_static_str()))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    trait Match4 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T, E> Match4 for &&Match<&::std::result::Result<T, E>> {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                match val {
+                                    Ok(ok) => Some((OK_KEY, ok.__autometrics_static_str())),
+                                    Err(err) => {
+                                        Some((ERROR_KEY, err.__autometrics_static_str()))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    trait Match5 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T> Match5 for &Match<&T>
+                    where
+                        T: GetLabels,
+                    {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                val.__autometrics_get_labels()
+                                    .map(|label| (label, val.__autometrics_static_str()))
+                            }
+                        }
+                    }
+                    trait Match6 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T> Match6 for Match<T> {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            { None }
+                        }
+                    }
+                    (&&&&&&Match(core::cell::Cell::new(Some(&result)))).spez()
+                }
+            };
+            let caller = CallerInfo::current(__autometrics_settings);
+            CounterLabels::new(
+                    __autometrics_settings,
+                    "sampled",
+                    "arguments",
+                    caller.caller_function,
+                    caller.caller_module,
+                    result_labels,
+                    None,
+                )
+                .with_recursive(__autometrics_recursive)
+                .with_error_class({
+                    {
+                        struct Match<T>(core::cell::Cell<Option<T>>);
+                        trait Match1 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T, E> Match1 for &&&Match<&::std::result::Result<T, E>>
+                        where
+                            E: ::std::error::Error + 'static,
+                        {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(_) => None,
+                                        Err(err) => ::autometrics::__private::classify_error(err),
+                                    }
+                                }
+                            }
+                        }
+                        trait Match2 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match2
+                        for &&Match<
+                            &::std::result::Result<
+                                T,
+                                ::std::boxed::Box<dyn ::std::error::Error + Send + Sync>,
+                            >,
+                        > {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(_) => None,
+                                        Err(err) => ::autometrics::__private::classify_error(&**err),
+                                    }
+                                }
+                            }
+                        }
+                        trait Match3 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match3
+                        for &Match<
+                            &::std::result::Result<
+                                T,
+                                ::std::boxed::Box<dyn ::std::error::Error>,
+                            >,
+                        > {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(_) => None,
+                                        Err(err) => ::autometrics::__private::classify_error(&**err),
+                                    }
+                                }
+                            }
+                        }
+                        trait Match4 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match4 for Match<T> {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                { None }
+                            }
+                        }
+                        (&&&&Match(core::cell::Cell::new(Some(&result)))).spez()
+                    }
+                })
+                .with_call_site(&__AUTOMETRICS_CALL_SITE)
+        };
+        let histogram_labels = HistogramLabels::new(
+                __autometrics_settings,
+                "sampled",
+                "arguments",
+                None,
+            )
+            .with_call_site(&__AUTOMETRICS_CALL_SITE);
+        __autometrics_tracker.finish(&counter_labels, &histogram_labels);
+    }
+    result
+}
+fn generic<T: Default>() -> T {
+    const __AUTOMETRICS_FUNCTION_ID: autometrics::__private::FunctionId = autometrics::__private::FunctionId::new(
+        "generic",
+        "arguments",
+        autometrics::__private::crate_name(::core::option::Option::Some("arguments")),
+    );
+    static __AUTOMETRICS_CALL_SITE: autometrics::__private::CallSite = autometrics::__private::CallSite::new();
+    {
+        use autometrics::__private::{
+            linkme::distributed_slice, FUNCTION_DESCRIPTIONS, FunctionDescription,
+        };
+        #[used]
+        #[unsafe(link_section = "linkme_FUNCTION_DESCRIPTIONS")]
+        static FUNCTION_DESCRIPTION: FunctionDescription = {
+            #[allow(clippy::no_effect_underscore_binding)]
+            unsafe fn __typecheck(_: autometrics::__private::linkme::__private37::Void) {
+                #[allow(clippy::ref_option_ref)]
+                let __new = || -> fn() -> &'static FunctionDescription {
+                    || &FUNCTION_DESCRIPTION
+                };
+                unsafe {
+                    autometrics::__private::linkme::DistributedSlice::private_typecheck(
+                        FUNCTION_DESCRIPTIONS,
+                        __new(),
+                    );
+                }
+            }
+            FunctionDescription {
+                id: __AUTOMETRICS_FUNCTION_ID,
+                objective: || None,
+            }
+        };
+    }
+    let __autometrics_settings = {
+        use autometrics::__private::{settings, AutometricsSettings, OnceCell};
+        static SETTINGS: OnceCell<&'static AutometricsSettings> = OnceCell::new();
+        *SETTINGS
+            .get_or_init(|| {
+                autometrics::__private::observe_function(&__AUTOMETRICS_FUNCTION_ID);
+                settings()
+            })
+    };
+    let __autometrics_type_params = autometrics::__private::type_params::<T>();
+    let __autometrics_tracker = {
+        use autometrics::__private::{AutometricsTracker, PanicGuard, TrackMetrics};
+        {
+            use autometrics::__private::{
+                set_build_timestamp, AutometricsTracker, BuildInfoLabels, TrackMetrics,
+            };
+            set_build_timestamp(::core::option::Option::None::<&'static str>);
+            AutometricsTracker::set_build_info(
+                &BuildInfoLabels::new(
+                        __autometrics_settings,
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::Some("0.0.0"))
+                            .unwrap_or_default(),
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>)
+                            .unwrap_or_default(),
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>)
+                            .unwrap_or_default(),
+                    )
+                    .with_toolchain(
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>),
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>),
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>),
+                    ),
+            );
+        }
+        let tracker = AutometricsTracker::start(None);
+        PanicGuard::new(
+            tracker,
+            move || {
+                use autometrics::__private::{CallerInfo, CounterLabels, HistogramLabels};
+                let caller = CallerInfo::current(__autometrics_settings);
+                let counter_labels = CounterLabels::new(
+                        __autometrics_settings,
+                        "generic",
+                        "arguments",
+                        caller.caller_function,
+                        caller.caller_module,
+                        None,
+                        None,
+                    )
+                    .with_type_params(__autometrics_type_params);
+                let histogram_labels = HistogramLabels::new(
+                        __autometrics_settings,
+                        "generic",
+                        "arguments",
+                        None,
+                    )
+                    .with_type_params(__autometrics_type_params);
+                (counter_labels, histogram_labels)
+            },
+        )
+    };
+    let result: T = {
+        use autometrics::__private::CallerInfo;
+        let caller = CallerInfo {
+            caller_function: "generic",
+            caller_module: "arguments",
+        };
+        caller.sync_scope(__autometrics_settings, move || { { T::default() } })
+    };
+    {
+        use autometrics::__private::HistogramLabels;
+        let counter_labels = {
+            use autometrics::__private::{CallerInfo, CounterLabels, GetLabels};
+            let result_labels = {
+                use ::autometrics::__private::{
+                    GetLabels, GetStaticStr, ResultAndReturnTypeLabels, ERROR_KEY, OK_KEY,
+                };
+                {
+                    struct Match<T>(core::cell::Cell<Option<T>>);
+                    trait Match1 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T, E> Match1 for &&&&&Match<&::std::result::Result<T, E>>
+                    where
+                        T: GetLabels,
+                        E: GetLabels,
+                    {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                match val {
+                                    Ok(ok) => {
+                                        Some((
+                                            ok.__autometrics_get_labels().unwrap_or(OK_KEY),
+                                            ok.__autometrics_static_str(),
+                                        ))
+                                    }
+                                    Err(err) => {
+                                        Some((
+                                            err.__autometrics_get_labels().unwrap_or(ERROR_KEY),
+                                            err.__autometrics_static_str(),
+                                        ))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    trait Match2 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T, E> Match2 for &&&&Match<&::std::result::Result<T, E>>
+                    where
+                        E: GetLabels,
+                    {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                match val {
+                                    Ok(ok) => Some((OK_KEY, ok.__autometrics_static_str())),
+                                    Err(err) => {
+                                        Some((
+                                            err.__autometrics_get_labels().unwrap_or(ERROR_KEY),
+                                            err.__autometrics_static_str(),
+                                        ))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    trait Match3 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T, E> Match3 for &&&Match<&::std::result::Result<T, E>>
+                    where
+                        T: GetLabels,
+                    {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                match val {
+                                    Ok(ok) => {
+                                        Some((
+                                            ok.__autometrics_get_labels().unwrap_or(OK_KEY),
+                                            ok.__autometrics_static_str(),
+                                        ))
+                                    }
+                                    Err(err) => {
+                                        Some((ERROR_KEY, err.__autometrics_static_str()))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    trait Match4 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T, E> Match4 for &&Match<&::std::result::Result<T, E>> {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                match val {
+                                    Ok(ok) => Some((OK_KEY, ok.__autometrics_static_str())),
+                                    Err(err) => {
+                                        Some((ERROR_KEY, err.__autometrics_static_str()))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    trait Match5 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T> Match5 for &Match<&T>
+                    where
+                        T: GetLabels,
+                    {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                val.__autometrics_get_labels()
+                                    .map(|label| (label, val.__autometrics_static_str()))
+                            }
+                        }
+                    }
+                    trait Match6 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T> Match6 for Match<T> {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            { None }
+                        }
+                    }
+                    (&&&&&&Match(core::cell::Cell::new(Some(&result)))).spez()
+                }
+            };
+            let caller = CallerInfo::current(__autometrics_settings);
+            CounterLabels::new(
+                    __autometrics_settings,
+                    "generic",
+                    "arguments",
+                    caller.caller_function,
+                    caller.caller_module,
+                    result_labels,
+                    None,
+                )
+                .with_type_params(__autometrics_type_params)
+                .with_error_class({
+                    {
+                        struct Match<T>(core::cell::Cell<Option<T>>);
+                        trait Match1 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T, E> Match1 for &&&Match<&::std::result::Result<T, E>>
+                        where
+                            E: ::std::error::Error + 'static,
+                        {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(_) => None,
+                                        Err(err) => ::autometrics::__private::classify_error(err),
+                                    }
+                                }
+                            }
+                        }
+                        trait Match2 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match2
+                        for &&Match<
+                            &::std::result::Result<
+                                T,
+                                ::std::boxed::Box<dyn ::std::error::Error + Send + Sync>,
+                            >,
+                        > {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(_) => None,
+                                        Err(err) => ::autometrics::__private::classify_error(&**err),
+                                    }
+                                }
+                            }
+                        }
+                        trait Match3 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match3
+                        for &Match<
+                            &::std::result::Result<
+                                T,
+                                ::std::boxed::Box<dyn ::std::error::Error>,
+                            >,
+                        > {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(_) => None,
+                                        Err(err) => ::autometrics::__private::classify_error(&**err),
+                                    }
+                                }
+                            }
+                        }
+                        trait Match4 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match4 for Match<T> {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                { None }
+                            }
+                        }
+                        (&&&&Match(core::cell::Cell::new(Some(&result)))).spez()
+                    }
+                })
+                .with_call_site(&__AUTOMETRICS_CALL_SITE)
+        };
+        let histogram_labels = HistogramLabels::new(
+                __autometrics_settings,
+                "generic",
+                "arguments",
+                None,
+            )
+            .with_type_params(__autometrics_type_params)
+            .with_call_site(&__AUTOMETRICS_CALL_SITE);
+        __autometrics_tracker.finish(&counter_labels, &histogram_labels);
+    }
+    result
+}
+fn sized() -> Response {
+    const __AUTOMETRICS_FUNCTION_ID: autometrics::__private::FunctionId = autometrics::__private::FunctionId::new(
+        "sized",
+        "arguments",
+        autometrics::__private::crate_name(::core::option::Option::Some("arguments")),
+    );
+    static __AUTOMETRICS_CALL_SITE: autometrics::__private::CallSite = autometrics::__private::CallSite::new();
+    {
+        use autometrics::__private::{
+            linkme::distributed_slice, FUNCTION_DESCRIPTIONS, FunctionDescription,
+        };
+        #[used]
+        #[unsafe(link_section = "linkme_FUNCTION_DESCRIPTIONS")]
+        static FUNCTION_DESCRIPTION: FunctionDescription = {
+            #[allow(clippy::no_effect_underscore_binding)]
+            unsafe fn __typecheck(_: autometrics::__private::linkme::__private37::Void) {
+                #[allow(clippy::ref_option_ref)]
+                let __new = || -> fn() -> &'static FunctionDescription {
+                    || &FUNCTION_DESCRIPTION
+                };
+                unsafe {
+                    autometrics::__private::linkme::DistributedSlice::private_typecheck(
+                        FUNCTION_DESCRIPTIONS,
+                        __new(),
+                    );
+                }
+            }
+            FunctionDescription {
+                id: __AUTOMETRICS_FUNCTION_ID,
+                objective: || None,
+            }
+        };
+    }
+    let __autometrics_settings = {
+        use autometrics::__private::{settings, AutometricsSettings, OnceCell};
+        static SETTINGS: OnceCell<&'static AutometricsSettings> = OnceCell::new();
+        *SETTINGS
+            .get_or_init(|| {
+                autometrics::__private::observe_function(&__AUTOMETRICS_FUNCTION_ID);
+                settings()
+            })
+    };
+    let __autometrics_tracker = {
+        use autometrics::__private::{AutometricsTracker, PanicGuard, TrackMetrics};
+        {
+            use autometrics::__private::{
+                set_build_timestamp, AutometricsTracker, BuildInfoLabels, TrackMetrics,
+            };
+            set_build_timestamp(::core::option::Option::None::<&'static str>);
+            AutometricsTracker::set_build_info(
+                &BuildInfoLabels::new(
+                        __autometrics_settings,
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::Some("0.0.0"))
+                            .unwrap_or_default(),
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>)
+                            .unwrap_or_default(),
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>)
+                            .unwrap_or_default(),
+                    )
+                    .with_toolchain(
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>),
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>),
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>),
+                    ),
+            );
+        }
+        let tracker = AutometricsTracker::start(None).with_span_events();
+        PanicGuard::new(
+            tracker,
+            move || {
+                use autometrics::__private::{CallerInfo, CounterLabels, HistogramLabels};
+                let caller = CallerInfo::current(__autometrics_settings);
+                let counter_labels = CounterLabels::new(
+                    __autometrics_settings,
+                    "sized",
+                    "arguments",
+                    caller.caller_function,
+                    caller.caller_module,
+                    None,
+                    None,
+                );
+                let histogram_labels = HistogramLabels::new(
+                    __autometrics_settings,
+                    "sized",
+                    "arguments",
+                    None,
+                );
+                (counter_labels, histogram_labels)
+            },
+        )
+    };
+    let result: Response = {
+        use autometrics::__private::CallerInfo;
+        let caller = CallerInfo {
+            caller_function: "sized",
+            caller_module: "arguments",
+        };
+        caller
+            .sync_scope(
+                __autometrics_settings,
+                move || { { Response { body: Vec::new() } } },
+            )
+    };
+    {
+        use autometrics::__private::HistogramLabels;
+        let counter_labels = {
+            use autometrics::__private::{CallerInfo, CounterLabels, GetLabels};
+            let result_labels = {
+                use ::autometrics::__private::{
+                    GetLabels, GetStaticStr, ResultAndReturnTypeLabels, ERROR_KEY, OK_KEY,
+                };
+                {
+                    struct Match<T>(core::cell::Cell<Option<T>>);
+                    trait Match1 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T, E> Match1 for &&&&&Match<&::std::result::Result<T, E>>
+                    where
+                        T: GetLabels,
+                        E: GetLabels,
+                    {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                match val {
+                                    Ok(ok) => {
+                                        Some((
+                                            ok.__autometrics_get_labels().unwrap_or(OK_KEY),
+                                            ok.__autometrics_static_str(),
+                                        ))
+                                    }
+                                    Err(err) => {
+                                        Some((
+                                            err.__autometrics_get_labels().unwrap_or(ERROR_KEY),
+                                            err.__autometrics_static_str(),
+                                        ))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    trait Match2 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T, E> Match2 for &&&&Match<&::std::result::Result<T, E>>
+                    where
+                        E: GetLabels,
+                    {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                match val {
+                                    Ok(ok) => Some((OK_KEY, ok.__autometrics_static_str())),
+                                    Err(err) => {
+                                        Some((
+                                            err.__autometrics_get_labels().unwrap_or(ERROR_KEY),
+                                            err.__autometrics_static_str(),
+                                        ))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    trait Match3 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T, E> Match3 for &&&Match<&::std::result::Result<T, E>>
+                    where
+                        T: GetLabels,
+                    {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                match val {
+                                    Ok(ok) => {
+                                        Some((
+                                            ok.__autometrics_get_labels().unwrap_or(OK_KEY),
+                                            ok.__autometrics_static_str(),
+                                        ))
+                                    }
+                                    Err(err) => {
+                                        Some((ERROR_KEY, err.__autometrics_static_str()))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    trait Match4 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T, E> Match4 for &&Match<&::std::result::Result<T, E>> {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                match val {
+                                    Ok(ok) => Some((OK_KEY, ok.__autometrics_static_str())),
+                                    Err(err) => {
+                                        Some((ERROR_KEY, err.__autometrics_static_str()))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    trait Match5 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T> Match5 for &Match<&T>
+                    where
+                        T: GetLabels,
+                    {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                val.__autometrics_get_labels()
+                                    .map(|label| (label, val.__autometrics_static_str()))
+                            }
+                        }
+                    }
+                    trait Match6 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T> Match6 for Match<T> {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            { None }
+                        }
+                    }
+                    (&&&&&&Match(core::cell::Cell::new(Some(&result)))).spez()
+                }
+            };
+            let caller = CallerInfo::current(__autometrics_settings);
+            CounterLabels::new(
+                    __autometrics_settings,
+                    "sized",
+                    "arguments",
+                    caller.caller_function,
+                    caller.caller_module,
+                    result_labels,
+                    None,
+                )
+                .with_error_class({
+                    {
+                        struct Match<T>(core::cell::Cell<Option<T>>);
+                        trait Match1 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T, E> Match1 for &&&Match<&::std::result::Result<T, E>>
+                        where
+                            E: ::std::error::Error + 'static,
+                        {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(_) => None,
+                                        Err(err) => ::autometrics::__private::classify_error(err),
+                                    }
+                                }
+                            }
+                        }
+                        trait Match2 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match2
+                        for &&Match<
+                            &::std::result::Result<
+                                T,
+                                ::std::boxed::Box<dyn ::std::error::Error + Send + Sync>,
+                            >,
+                        > {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(_) => None,
+                                        Err(err) => ::autometrics::__private::classify_error(&**err),
+                                    }
+                                }
+                            }
+                        }
+                        trait Match3 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match3
+                        for &Match<
+                            &::std::result::Result<
+                                T,
+                                ::std::boxed::Box<dyn ::std::error::Error>,
+                            >,
+                        > {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(_) => None,
+                                        Err(err) => ::autometrics::__private::classify_error(&**err),
+                                    }
+                                }
+                            }
+                        }
+                        trait Match4 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match4 for Match<T> {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                { None }
+                            }
+                        }
+                        (&&&&Match(core::cell::Cell::new(Some(&result)))).spez()
+                    }
+                })
+                .with_call_site(&__AUTOMETRICS_CALL_SITE)
+        };
+        let histogram_labels = HistogramLabels::new(
+                __autometrics_settings,
+                "sized",
+                "arguments",
+                None,
+            )
+            .with_call_site(&__AUTOMETRICS_CALL_SITE);
+        __autometrics_tracker
+            .record_size(
+                &histogram_labels,
+                autometrics::__private::measure_size(
+                    &result,
+                    |response: &Response| response.body.len(),
+                ),
+            );
+        __autometrics_tracker.finish(&counter_labels, &histogram_labels);
+    }
+    result
+}
+fn no_caller() {
+    const __AUTOMETRICS_FUNCTION_ID: autometrics::__private::FunctionId = autometrics::__private::FunctionId::new(
+        "no_caller",
+        "arguments",
+        autometrics::__private::crate_name(::core::option::Option::Some("arguments")),
+    );
+    static __AUTOMETRICS_CALL_SITE: autometrics::__private::CallSite = autometrics::__private::CallSite::new();
+    {
+        use autometrics::__private::{
+            linkme::distributed_slice, FUNCTION_DESCRIPTIONS, FunctionDescription,
+        };
+        #[used]
+        #[unsafe(link_section = "linkme_FUNCTION_DESCRIPTIONS")]
+        static FUNCTION_DESCRIPTION: FunctionDescription = {
+            #[allow(clippy::no_effect_underscore_binding)]
+            unsafe fn __typecheck(_: autometrics::__private::linkme::__private37::Void) {
+                #[allow(clippy::ref_option_ref)]
+                let __new = || -> fn() -> &'static FunctionDescription {
+                    || &FUNCTION_DESCRIPTION
+                };
+                unsafe {
+                    autometrics::__private::linkme::DistributedSlice::private_typecheck(
+                        FUNCTION_DESCRIPTIONS,
+                        __new(),
+                    );
+                }
+            }
+            FunctionDescription {
+                id: __AUTOMETRICS_FUNCTION_ID,
+                objective: || None,
+            }
+        };
+    }
+    let __autometrics_settings = {
+        use autometrics::__private::{settings, AutometricsSettings, OnceCell};
+        static SETTINGS: OnceCell<&'static AutometricsSettings> = OnceCell::new();
+        *SETTINGS
+            .get_or_init(|| {
+                autometrics::__private::observe_function(&__AUTOMETRICS_FUNCTION_ID);
+                settings()
+            })
+    };
+    let __autometrics_recursive = false;
+    let __autometrics_tracker = {
+        use autometrics::__private::{AutometricsTracker, PanicGuard, TrackMetrics};
+        {
+            use autometrics::__private::{
+                set_build_timestamp, AutometricsTracker, BuildInfoLabels, TrackMetrics,
+            };
+            set_build_timestamp(::core::option::Option::None::<&'static str>);
+            AutometricsTracker::set_build_info(
+                &BuildInfoLabels::new(
+                        __autometrics_settings,
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::Some("0.0.0"))
+                            .unwrap_or_default(),
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>)
+                            .unwrap_or_default(),
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>)
+                            .unwrap_or_default(),
+                    )
+                    .with_toolchain(
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>),
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>),
+                        ::core::option::Option::None::<&'static str>
+                            .or(::core::option::Option::None::<&'static str>),
+                    ),
+            );
+        }
+        let tracker = AutometricsTracker::start({
+            use autometrics::__private::GaugeLabels;
+            Some(&GaugeLabels::new(__autometrics_settings, "no_caller", "arguments"))
+                .filter(|_| !__autometrics_recursive)
+        });
+        PanicGuard::new(
+            tracker,
+            move || {
+                use autometrics::__private::{CallerInfo, CounterLabels, HistogramLabels};
+                let caller = CallerInfo::EMPTY;
+                let counter_labels = CounterLabels::new(
+                    __autometrics_settings,
+                    "no_caller",
+                    "arguments",
+                    caller.caller_function,
+                    caller.caller_module,
+                    None,
+                    None,
+                );
+                let histogram_labels = HistogramLabels::new(
+                    __autometrics_settings,
+                    "no_caller",
+                    "arguments",
+                    None,
+                );
+                (counter_labels, histogram_labels)
+            },
+        )
+    };
+    let result: () = (move || {})();
+    {
+        use autometrics::__private::HistogramLabels;
+        let counter_labels = {
+            use autometrics::__private::{CallerInfo, CounterLabels, GetLabels};
+            let result_labels = {
+                use ::autometrics::__private::{
+                    GetLabels, GetStaticStr, ResultAndReturnTypeLabels, ERROR_KEY, OK_KEY,
+                };
+                {
+                    struct Match<T>(core::cell::Cell<Option<T>>);
+                    trait Match1 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T, E> Match1 for &&&&&Match<&::std::result::Result<T, E>>
+                    where
+                        T: GetLabels,
+                        E: GetLabels,
+                    {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                match val {
+                                    Ok(ok) => {
+                                        Some((
+                                            ok.__autometrics_get_labels().unwrap_or(OK_KEY),
+                                            ok.__autometrics_static_str(),
+                                        ))
+                                    }
+                                    Err(err) => {
+                                        Some((
+                                            err.__autometrics_get_labels().unwrap_or(ERROR_KEY),
+                                            err.__autometrics_static_str(),
+                                        ))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    trait Match2 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T, E> Match2 for &&&&Match<&::std::result::Result<T, E>>
+                    where
+                        E: GetLabels,
+                    {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                match val {
+                                    Ok(ok) => Some((OK_KEY, ok.__autometrics_static_str())),
+                                    Err(err) => {
+                                        Some((
+                                            err.__autometrics_get_labels().unwrap_or(ERROR_KEY),
+                                            err.__autometrics_static_str(),
+                                        ))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    trait Match3 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T, E> Match3 for &&&Match<&::std::result::Result<T, E>>
+                    where
+                        T: GetLabels,
+                    {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                match val {
+                                    Ok(ok) => {
+                                        Some((
+                                            ok.__autometrics_get_labels().unwrap_or(OK_KEY),
+                                            ok.__autometrics_static_str(),
+                                        ))
+                                    }
+                                    Err(err) => {
+                                        Some((ERROR_KEY, err.__autometrics_static_str()))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    trait Match4 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T, E> Match4 for &&Match<&::std::result::Result<T, E>> {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                match val {
+                                    Ok(ok) => Some((OK_KEY, ok.__autometrics_static_str())),
+                                    Err(err) => {
+                                        Some((ERROR_KEY, err.__autometrics_static_str()))
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    trait Match5 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T> Match5 for &Match<&T>
+                    where
+                        T: GetLabels,
+                    {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            {
+                                val.__autometrics_get_labels()
+                                    .map(|label| (label, val.__autometrics_static_str()))
+                            }
+                        }
+                    }
+                    trait Match6 {
+                        type Return;
+                        fn spez(&self) -> Self::Return;
+                    }
+                    impl<T> Match6 for Match<T> {
+                        type Return = ::std::option::Option<ResultAndReturnTypeLabels>;
+                        fn spez(&self) -> Self::Return {
+                            let val = self.0.take().unwrap();
+                            let _ = val;
+                            { None }
+                        }
+                    }
+                    (&&&&&&Match(core::cell::Cell::new(Some(&result)))).spez()
+                }
+            };
+            let caller = CallerInfo::EMPTY;
+            CounterLabels::new(
+                    __autometrics_settings,
+                    "no_caller",
+                    "arguments",
+                    caller.caller_function,
+                    caller.caller_module,
+                    result_labels,
+                    None,
+                )
+                .with_error_class({
+                    {
+                        struct Match<T>(core::cell::Cell<Option<T>>);
+                        trait Match1 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T, E> Match1 for &&&Match<&::std::result::Result<T, E>>
+                        where
+                            E: ::std::error::Error + 'static,
+                        {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(_) => None,
+                                        Err(err) => ::autometrics::__private::classify_error(err),
+                                    }
+                                }
+                            }
+                        }
+                        trait Match2 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match2
+                        for &&Match<
+                            &::std::result::Result<
+                                T,
+                                ::std::boxed::Box<dyn ::std::error::Error + Send + Sync>,
+                            >,
+                        > {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(_) => None,
+                                        Err(err) => ::autometrics::__private::classify_error(&**err),
+                                    }
+                                }
+                            }
+                        }
+                        trait Match3 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match3
+                        for &Match<
+                            &::std::result::Result<
+                                T,
+                                ::std::boxed::Box<dyn ::std::error::Error>,
+                            >,
+                        > {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(_) => None,
+                                        Err(err) => ::autometrics::__private::classify_error(&**err),
+                                    }
+                                }
+                            }
+                        }
+                        trait Match4 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match4 for Match<T> {
+                            type Return = ::std::option::Option<&'static str>;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                { None }
+                            }
+                        }
+                        (&&&&Match(core::cell::Cell::new(Some(&result)))).spez()
+                    }
+                })
+                .with_call_site(&__AUTOMETRICS_CALL_SITE)
+        };
+        let histogram_labels = HistogramLabels::new(
+                __autometrics_settings,
+                "no_caller",
+                "arguments",
+                None,
+            )
+            .with_call_site(&__AUTOMETRICS_CALL_SITE);
+        __autometrics_tracker.finish(&counter_labels, &histogram_labels);
+    }
+    result
+}
@@ -0,0 +1,29 @@
+use autometrics::autometrics;
+
+struct Response {
+    body: Vec<u8>,
+}
+
+#[autometrics(ok_if = Option::is_some, error_details, buckets = [0.1, 1])]
+fn predicate(id: u32) -> Option<u32> {
+    Some(id)
+}
+
+#[autometrics(count_only)]
+fn count_only() {}
+
+#[autometrics(sample_rate = 0.1, skip_if = || false, recursive = label)]
+fn sampled() {}
+
+#[autometrics(generic_params)]
+fn generic<T: Default>() -> T {
+    T::default()
+}
+
+#[autometrics(track_size = |response: &Response| response.body.len(), span_events)]
+fn sized() -> Response {
+    Response { body: Vec::new() }
+}
+
+#[autometrics(no_caller, track_concurrency)]
+fn no_caller() {}
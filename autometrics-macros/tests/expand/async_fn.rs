@@ -0,0 +1,6 @@
+use autometrics::autometrics;
+
+#[autometrics]
+async fn fetch(url: &str) -> usize {
+    url.len()
+}
@@ -0,0 +1,6 @@
+use autometrics::autometrics;
+
+#[autometrics]
+fn get_user(id: u32) -> Result<String, String> {
+    Ok(id.to_string())
+}
@@ -0,0 +1,747 @@
+use autometrics::autometrics;
+use autometrics::objectives::{Objective, ObjectivePercentile};
+const API_SLO: Objective = Objective::new("api")
+    .success_rate(ObjectivePercentile::P99_9);
+struct Database;
+impl Database {
+    fn load(&self, key: &str) -> Option<String> {
+        const __AUTOMETRICS_FUNCTION_ID: autometrics::__private::FunctionId = autometrics::__private::FunctionId::new(
+            "Database::load",
+            "methods",
+            autometrics::__private::crate_name(::core::option::Option::Some("methods")),
+        );
+        static __AUTOMETRICS_CALL_SITE: autometrics::__private::CallSite = autometrics::__private::CallSite::new();
+        {
+            use autometrics::__private::{
+                linkme::distributed_slice, FUNCTION_DESCRIPTIONS, FunctionDescription,
+            };
+            #[used]
+            #[unsafe(link_section = "linkme_FUNCTION_DESCRIPTIONS")]
+            static FUNCTION_DESCRIPTION: FunctionDescription = {
+                #[allow(clippy::no_effect_underscore_binding)]
+                unsafe fn __typecheck(
+                    _: autometrics::__private::linkme::__private37::Void,
+                ) {
+                    #[allow(clippy::ref_option_ref)]
+                    let __new = || -> fn() -> &'static FunctionDescription {
+                        || &FUNCTION_DESCRIPTION
+                    };
+                    unsafe {
+                        autometrics::__private::linkme::DistributedSlice::private_typecheck(
+                            FUNCTION_DESCRIPTIONS,
+                            __new(),
+                        );
+                    }
+                }
+                FunctionDescription {
+                    id: __AUTOMETRICS_FUNCTION_ID,
+                    objective: || __AUTOMETRICS_CALL_SITE.objective(|| Some(API_SLO)),
+                }
+            };
+        }
+        let __autometrics_settings = {
+            use autometrics::__private::{settings, AutometricsSettings, OnceCell};
+            static SETTINGS: OnceCell<&'static AutometricsSettings> = OnceCell::new();
+            *SETTINGS
+                .get_or_init(|| {
+                    autometrics::__private::observe_function(&__AUTOMETRICS_FUNCTION_ID);
+                    settings()
+                })
+        };
+        let __autometrics_objective = __AUTOMETRICS_CALL_SITE.objective(|| Some(API_SLO));
+        let __autometrics_recursive = {
+            use autometrics::__private::CallerInfo;
+            CallerInfo::current(__autometrics_settings)
+                .is_function("Database::load", "methods")
+        };
+        let __autometrics_tracker = {
+            use autometrics::__private::{AutometricsTracker, PanicGuard, TrackMetrics};
+            {
+                use autometrics::__private::{
+                    set_build_timestamp, AutometricsTracker, BuildInfoLabels,
+                    TrackMetrics,
+                };
+                set_build_timestamp(::core::option::Option::None::<&'static str>);
+                AutometricsTracker::set_build_info(
+                    &BuildInfoLabels::new(
+                            __autometrics_settings,
+                            ::core::option::Option::None::<&'static str>
+                                .or(::core::option::Option::Some("0.0.0"))
+                                .unwrap_or_default(),
+                            ::core::option::Option::None::<&'static str>
+                                .or(::core::option::Option::None::<&'static str>)
+                                .unwrap_or_default(),
+                            ::core::option::Option::None::<&'static str>
+                                .or(::core::option::Option::None::<&'static str>)
+                                .unwrap_or_default(),
+                        )
+                        .with_toolchain(
+                            ::core::option::Option::None::<&'static str>
+                                .or(::core::option::Option::None::<&'static str>),
+                            ::core::option::Option::None::<&'static str>
+                                .or(::core::option::Option::None::<&'static str>),
+                            ::core::option::Option::None::<&'static str>
+                                .or(::core::option::Option::None::<&'static str>),
+                        ),
+                );
+            }
+            let tracker = AutometricsTracker::start({
+                use autometrics::__private::GaugeLabels;
+                Some(
+                        &GaugeLabels::new(
+                                __autometrics_settings,
+                                "Database::load",
+                                "methods",
+                            )
+                            .with_max_concurrency(),
+                    )
+                    .filter(|_| !__autometrics_recursive)
+            });
+            PanicGuard::new(
+                tracker,
+                move || {
+                    use autometrics::__private::{
+                        CallerInfo, CounterLabels, HistogramLabels,
+                    };
+                    let caller = CallerInfo::current(__autometrics_settings);
+                    let counter_labels = CounterLabels::new(
+                        __autometrics_settings,
+                        "Database::load",
+                        "methods",
+                        caller.caller_function,
+                        caller.caller_module,
+                        None,
+                        __autometrics_objective,
+                    );
+                    let histogram_labels = HistogramLabels::new(
+                        __autometrics_settings,
+                        "Database::load",
+                        "methods",
+                        __autometrics_objective,
+                    );
+                    (counter_labels, histogram_labels)
+                },
+            )
+        };
+        let result: Option<String> = {
+            use autometrics::__private::CallerInfo;
+            let caller = CallerInfo {
+                caller_function: "Database::load",
+                caller_module: "methods",
+            };
+            caller
+                .sync_scope(
+                    __autometrics_settings,
+                    move || { { Some(key.to_string()) } },
+                )
+        };
+        {
+            use autometrics::__private::HistogramLabels;
+            let counter_labels = {
+                use autometrics::__private::{CallerInfo, CounterLabels, GetLabels};
+                let result_labels = {
+                    use ::autometrics::__private::{
+                        GetLabels, GetStaticStr, ResultAndReturnTypeLabels, ERROR_KEY,
+                        OK_KEY,
+                    };
+                    {
+                        struct Match<T>(core::cell::Cell<Option<T>>);
+                        trait Match1 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T, E> Match1 for &&&&&Match<&::std::result::Result<T, E>>
+                        where
+                            T: GetLabels,
+                            E: GetLabels,
+                        {
+                            type Return = ::std::option::Option<
+                                ResultAndReturnTypeLabels,
+                            >;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(ok) => {
+                                            Some((
+                                                ok.__autometrics_get_labels().unwrap_or(OK_KEY),
+                                                ok.__autometrics_static_str(),
+                                            ))
+                                        }
+                                        Err(err) => {
+                                            Some((
+                                                err.__autometrics_get_labels().unwrap_or(ERROR_KEY),
+                                                err.__autometrics_static_str(),
+                                            ))
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        trait Match2 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T, E> Match2 for &&&&Match<&::std::result::Result<T, E>>
+                        where
+                            E: GetLabels,
+                        {
+                            type Return = ::std::option::Option<
+                                ResultAndReturnTypeLabels,
+                            >;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(ok) => Some((OK_KEY, ok.__autometrics_static_str())),
+                                        Err(err) => {
+                                            Some((
+                                                err.__autometrics_get_labels().unwrap_or(ERROR_KEY),
+                                                err.__autometrics_static_str(),
+                                            ))
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        trait Match3 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T, E> Match3 for &&&Match<&::std::result::Result<T, E>>
+                        where
+                            T: GetLabels,
+                        {
+                            type Return = ::std::option::Option<
+                                ResultAndReturnTypeLabels,
+                            >;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(ok) => {
+                                            Some((
+                                                ok.__autometrics_get_labels().unwrap_or(OK_KEY),
+                                                ok.__autometrics_static_str(),
+                                            ))
+                                        }
+                                        Err(err) => {
+                                            Some((ERROR_KEY, err.__autometrics_static_str()))
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        trait Match4 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T, E> Match4 for &&Match<&::std::result::Result<T, E>> {
+                            type Return = ::std::option::Option<
+                                ResultAndReturnTypeLabels,
+                            >;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(ok) => Some((OK_KEY, ok.__autometrics_static_str())),
+                                        Err(err) => {
+                                            Some((ERROR_KEY, err.__autometrics_static_str()))
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        trait Match5 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match5 for &Match<&T>
+                        where
+                            T: GetLabels,
+                        {
+                            type Return = ::std::option::Option<
+                                ResultAndReturnTypeLabels,
+                            >;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    val.__autometrics_get_labels()
+                                        .map(|label| (label, val.__autometrics_static_str()))
+                                }
+                            }
+                        }
+                        trait Match6 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match6 for Match<T> {
+                            type Return = ::std::option::Option<
+                                ResultAndReturnTypeLabels,
+                            >;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                { None }
+                            }
+                        }
+                        (&&&&&&Match(core::cell::Cell::new(Some(&result)))).spez()
+                    }
+                };
+                let caller = CallerInfo::current(__autometrics_settings);
+                CounterLabels::new(
+                        __autometrics_settings,
+                        "Database::load",
+                        "methods",
+                        caller.caller_function,
+                        caller.caller_module,
+                        result_labels,
+                        __autometrics_objective,
+                    )
+                    .with_error_class({
+                        {
+                            struct Match<T>(core::cell::Cell<Option<T>>);
+                            trait Match1 {
+                                type Return;
+                                fn spez(&self) -> Self::Return;
+                            }
+                            impl<T, E> Match1 for &&&Match<&::std::result::Result<T, E>>
+                            where
+                                E: ::std::error::Error + 'static,
+                            {
+                                type Return = ::std::option::Option<&'static str>;
+                                fn spez(&self) -> Self::Return {
+                                    let val = self.0.take().unwrap();
+                                    let _ = val;
+                                    {
+                                        match val {
+                                            Ok(_) => None,
+                                            Err(err) => ::autometrics::__private::classify_error(err),
+                                        }
+                                    }
+                                }
+                            }
+                            trait Match2 {
+                                type Return;
+                                fn spez(&self) -> Self::Return;
+                            }
+                            impl<T> Match2
+                            for &&Match<
+                                &::std::result::Result<
+                                    T,
+                                    ::std::boxed::Box<dyn ::std::error::Error + Send + Sync>,
+                                >,
+                            > {
+                                type Return = ::std::option::Option<&'static str>;
+                                fn spez(&self) -> Self::Return {
+                                    let val = self.0.take().unwrap();
+                                    let _ = val;
+                                    {
+                                        match val {
+                                            Ok(_) => None,
+                                            Err(err) => ::autometrics::__private::classify_error(&**err),
+                                        }
+                                    }
+                                }
+                            }
+                            trait Match3 {
+                                type Return;
+                                fn spez(&self) -> Self::Return;
+                            }
+                            impl<T> Match3
+                            for &Match<
+                                &::std::result::Result<
+                                    T,
+                                    ::std::boxed::Box<dyn ::std::error::Error>,
+                                >,
+                            > {
+                                type Return = ::std::option::Option<&'static str>;
+                                fn spez(&self) -> Self::Return {
+                                    let val = self.0.take().unwrap();
+                                    let _ = val;
+                                    {
+                                        match val {
+                                            Ok(_) => None,
+                                            Err(err) => ::autometrics::__private::classify_error(&**err),
+                                        }
+                                    }
+                                }
+                            }
+                            trait Match4 {
+                                type Return;
+                                fn spez(&self) -> Self::Return;
+                            }
+                            impl<T> Match4 for Match<T> {
+                                type Return = ::std::option::Option<&'static str>;
+                                fn spez(&self) -> Self::Return {
+                                    let val = self.0.take().unwrap();
+                                    let _ = val;
+                                    { None }
+                                }
+                            }
+                            (&&&&Match(core::cell::Cell::new(Some(&result)))).spez()
+                        }
+                    })
+                    .with_call_site(&__AUTOMETRICS_CALL_SITE)
+            };
+            let histogram_labels = HistogramLabels::new(
+                    __autometrics_settings,
+                    "Database::load",
+                    "methods",
+                    __autometrics_objective,
+                )
+                .with_call_site(&__AUTOMETRICS_CALL_SITE);
+            __autometrics_tracker.finish(&counter_labels, &histogram_labels);
+        }
+        result
+    }
+    fn skipped(&self) {}
+}
+trait Cache {
+    fn get(&self, key: &str) -> Option<String> {
+        static __AUTOMETRICS_CALL_SITE: autometrics::__private::CallSite = autometrics::__private::CallSite::new();
+        let __autometrics_function = autometrics::__private::trait_method_name::<
+            Self,
+        >("get");
+        let __autometrics_settings = {
+            use autometrics::__private::{settings, AutometricsSettings, OnceCell};
+            static SETTINGS: OnceCell<&'static AutometricsSettings> = OnceCell::new();
+            *SETTINGS.get_or_init(|| { settings() })
+        };
+        let __autometrics_tracker = {
+            use autometrics::__private::{AutometricsTracker, PanicGuard, TrackMetrics};
+            {
+                use autometrics::__private::{
+                    set_build_timestamp, AutometricsTracker, BuildInfoLabels,
+                    TrackMetrics,
+                };
+                set_build_timestamp(::core::option::Option::None::<&'static str>);
+                AutometricsTracker::set_build_info(
+                    &BuildInfoLabels::new(
+                            __autometrics_settings,
+                            ::core::option::Option::None::<&'static str>
+                                .or(::core::option::Option::Some("0.0.0"))
+                                .unwrap_or_default(),
+                            ::core::option::Option::None::<&'static str>
+                                .or(::core::option::Option::None::<&'static str>)
+                                .unwrap_or_default(),
+                            ::core::option::Option::None::<&'static str>
+                                .or(::core::option::Option::None::<&'static str>)
+                                .unwrap_or_default(),
+                        )
+                        .with_toolchain(
+                            ::core::option::Option::None::<&'static str>
+                                .or(::core::option::Option::None::<&'static str>),
+                            ::core::option::Option::None::<&'static str>
+                                .or(::core::option::Option::None::<&'static str>),
+                            ::core::option::Option::None::<&'static str>
+                                .or(::core::option::Option::None::<&'static str>),
+                        ),
+                );
+            }
+            let tracker = AutometricsTracker::start(None);
+            PanicGuard::new(
+                tracker,
+                move || {
+                    use autometrics::__private::{
+                        CallerInfo, CounterLabels, HistogramLabels,
+                    };
+                    let caller = CallerInfo::current(__autometrics_settings);
+                    let counter_labels = CounterLabels::new(
+                        __autometrics_settings,
+                        __autometrics_function,
+                        "methods",
+                        caller.caller_function,
+                        caller.caller_module,
+                        None,
+                        None,
+                    );
+                    let histogram_labels = HistogramLabels::new(
+                        __autometrics_settings,
+                        __autometrics_function,
+                        "methods",
+                        None,
+                    );
+                    (counter_labels, histogram_labels)
+                },
+            )
+        };
+        let result: Option<String> = {
+            use autometrics::__private::CallerInfo;
+            let caller = CallerInfo {
+                caller_function: __autometrics_function,
+                caller_module: "methods",
+            };
+            caller.sync_scope(__autometrics_settings, move || { { None } })
+        };
+        {
+            use autometrics::__private::HistogramLabels;
+            let counter_labels = {
+                use autometrics::__private::{CallerInfo, CounterLabels, GetLabels};
+                let result_labels = {
+                    use ::autometrics::__private::{
+                        GetLabels, GetStaticStr, ResultAndReturnTypeLabels, ERROR_KEY,
+                        OK_KEY,
+                    };
+                    {
+                        struct Match<T>(core::cell::Cell<Option<T>>);
+                        trait Match1 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T, E> Match1 for &&&&&Match<&::std::result::Result<T, E>>
+                        where
+                            T: GetLabels,
+                            E: GetLabels,
+                        {
+                            type Return = ::std::option::Option<
+                                ResultAndReturnTypeLabels,
+                            >;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(ok) => {
+                                            Some((
+                                                ok.__autometrics_get_labels().unwrap_or(OK_KEY),
+                                                ok.__autometrics_static_str(),
+                                            ))
+                                        }
+                                        Err(err) => {
+                                            Some((
+                                                err.__autometrics_get_labels().unwrap_or(ERROR_KEY),
+                                                err.__autometrics_static_str(),
+                                            ))
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        trait Match2 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T, E> Match2 for &&&&Match<&::std::result::Result<T, E>>
+                        where
+                            E: GetLabels,
+                        {
+                            type Return = ::std::option::Option<
+                                ResultAndReturnTypeLabels,
+                            >;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(ok) => Some((OK_KEY, ok.__autometrics_static_str())),
+                                        Err(err) => {
+                                            Some((
+                                                err.__autometrics_get_labels().unwrap_or(ERROR_KEY),
+                                                err.__autometrics_static_str(),
+                                            ))
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        trait Match3 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T, E> Match3 for &&&Match<&::std::result::Result<T, E>>
+                        where
+                            T: GetLabels,
+                        {
+                            type Return = ::std::option::Option<
+                                ResultAndReturnTypeLabels,
+                            >;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(ok) => {
+                                            Some((
+                                                ok.__autometrics_get_labels().unwrap_or(OK_KEY),
+                                                ok.__autometrics_static_str(),
+                                            ))
+                                        }
+                                        Err(err) => {
+                                            Some((ERROR_KEY, err.__autometrics_static_str()))
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        trait Match4 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T, E> Match4 for &&Match<&::std::result::Result<T, E>> {
+                            type Return = ::std::option::Option<
+                                ResultAndReturnTypeLabels,
+                            >;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    match val {
+                                        Ok(ok) => Some((OK_KEY, ok.__autometrics_static_str())),
+                                        Err(err) => {
+                                            Some((ERROR_KEY, err.__autometrics_static_str()))
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                        trait Match5 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match5 for &Match<&T>
+                        where
+                            T: GetLabels,
+                        {
+                            type Return = ::std::option::Option<
+                                ResultAndReturnTypeLabels,
+                            >;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                {
+                                    val.__autometrics_get_labels()
+                                        .map(|label| (label, val.__autometrics_static_str()))
+                                }
+                            }
+                        }
+                        trait Match6 {
+                            type Return;
+                            fn spez(&self) -> Self::Return;
+                        }
+                        impl<T> Match6 for Match<T> {
+                            type Return = ::std::option::Option<
+                                ResultAndReturnTypeLabels,
+                            >;
+                            fn spez(&self) -> Self::Return {
+                                let val = self.0.take().unwrap();
+                                let _ = val;
+                                { None }
+                            }
+                        }
+                        (&&&&&&Match(core::cell::Cell::new(Some(&result)))).spez()
+                    }
+                };
+                let caller = CallerInfo::current(__autometrics_settings);
+                CounterLabels::new(
+                        __autometrics_settings,
+                        __autometrics_function,
+                        "methods",
+                        caller.caller_function,
+                        caller.caller_module,
+                        result_labels,
+                        None,
+                    )
+                    .with_error_class({
+                        {
+                            struct Match<T>(core::cell::Cell<Option<T>>);
+                            trait Match1 {
+                                type Return;
+                                fn spez(&self) -> Self::Return;
+                            }
+                            impl<T, E> Match1 for &&&Match<&::std::result::Result<T, E>>
+                            where
+                                E: ::std::error::Error + 'static,
+                            {
+                                type Return = ::std::option::Option<&'static str>;
+                                fn spez(&self) -> Self::Return {
+                                    let val = self.0.take().unwrap();
+                                    let _ = val;
+                                    {
+                                        match val {
+                                            Ok(_) => None,
+                                            Err(err) => ::autometrics::__private::classify_error(err),
+                                        }
+                                    }
+                                }
+                            }
+                            trait Match2 {
+                                type Return;
+                                fn spez(&self) -> Self::Return;
+                            }
+                            impl<T> Match2
+                            for &&Match<
+                                &::std::result::Result<
+                                    T,
+                                    ::std::boxed::Box<dyn ::std::error::Error + Send + Sync>,
+                                >,
+                            > {
+                                type Return = ::std::option::Option<&'static str>;
+                                fn spez(&self) -> Self::Return {
+                                    let val = self.0.take().unwrap();
+                                    let _ = val;
+                                    {
+                                        match val {
+                                            Ok(_) => None,
+                                            Err(err) => ::autometrics::__private::classify_error(&**err),
+                                        }
+                                    }
+                                }
+                            }
+                            trait Match3 {
+                                type Return;
+                                fn spez(&self) -> Self::Return;
+                            }
+                            impl<T> Match3
+                            for &Match<
+                                &::std::result::Result<
+                                    T,
+                                    ::std::boxed::Box<dyn ::std::error::Error>,
+                                >,
+                            > {
+                                type Return = ::std::option::Option<&'static str>;
+                                fn spez(&self) -> Self::Return {
+                                    let val = self.0.take().unwrap();
+                                    let _ = val;
+                                    {
+                                        match val {
+                                            Ok(_) => None,
+                                            Err(err) => ::autometrics::__private::classify_error(&**err),
+                                        }
+                                    }
+                                }
+                            }
+                            trait Match4 {
+                                type Return;
+                                fn spez(&self) -> Self::Return;
+                            }
+                            impl<T> Match4 for Match<T> {
+                                type Return = ::std::option::Option<&'static str>;
+                                fn spez(&self) -> Self::Return {
+                                    let val = self.0.take().unwrap();
+                                    let _ = val;
+                                    { None }
+                                }
+                            }
+                            (&&&&Match(core::cell::Cell::new(Some(&result)))).spez()
+                        }
+                    })
+                    .with_call_site(&__AUTOMETRICS_CALL_SITE)
+            };
+            let histogram_labels = HistogramLabels::new(
+                    __autometrics_settings,
+                    __autometrics_function,
+                    "methods",
+                    None,
+                )
+                .with_call_site(&__AUTOMETRICS_CALL_SITE);
+            __autometrics_tracker.finish(&counter_labels, &histogram_labels);
+        }
+        result
+    }
+}
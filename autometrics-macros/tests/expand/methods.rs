@@ -0,0 +1,23 @@
+use autometrics::autometrics;
+use autometrics::objectives::{Objective, ObjectivePercentile};
+
+const API_SLO: Objective = Objective::new("api").success_rate(ObjectivePercentile::P99_9);
+
+struct Database;
+
+#[autometrics(objective = API_SLO, track_concurrency(max))]
+impl Database {
+    fn load(&self, key: &str) -> Option<String> {
+        Some(key.to_string())
+    }
+
+    #[skip_autometrics]
+    fn skipped(&self) {}
+}
+
+#[autometrics]
+trait Cache {
+    fn get(&self, key: &str) -> Option<String> {
+        None
+    }
+}
@@ -0,0 +1,58 @@
+//! Compares recording a call through the handles kept by a call site of an instrumented function
+//! with looking up the series by their labels on every call.
+//!
+//! Only the `prometheus` backend keeps the handles so far, so run this once for each backend to compare them:
+//! `cargo bench --bench call_site_benchmark --features prometheus-exporter,BACKEND`
+use autometrics::__private::{
+    settings, AutometricsTracker, CallSite, CounterLabels, HistogramLabels, TrackMetrics,
+};
+use autometrics::prometheus_exporter;
+use criterion::{criterion_group, criterion_main, Criterion};
+
+static CALL_SITE: CallSite = CallSite::new();
+
+fn record_call(call_site: Option<&'static CallSite>) {
+    let settings = settings();
+    let mut counter_labels = CounterLabels::new(
+        settings,
+        "bench_function",
+        module_path!(),
+        "",
+        "",
+        Some(("ok", None)),
+        None,
+    );
+    let mut histogram_labels =
+        HistogramLabels::new(settings, "bench_function", module_path!(), None);
+    if let Some(call_site) = call_site {
+        counter_labels = counter_labels.with_call_site(call_site);
+        histogram_labels = histogram_labels.with_call_site(call_site);
+    }
+    AutometricsTracker::start(None).finish(&counter_labels, &histogram_labels);
+}
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    prometheus_exporter::init();
+
+    let backend = if cfg!(prometheus) {
+        "prometheus"
+    } else if cfg!(prometheus_client) {
+        "prometheus-client"
+    } else if cfg!(metrics) {
+        "metrics"
+    } else if cfg!(opentelemetry) {
+        "opentelemetry"
+    } else {
+        "unknown"
+    };
+
+    let mut group = c.benchmark_group(format!("Record a call with {backend}"));
+    group.bench_function("label lookup", |b| b.iter(|| record_call(None)));
+    group.bench_function("call site handles", |b| {
+        b.iter(|| record_call(Some(&CALL_SITE)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -17,7 +17,10 @@ pub fn main() {
     cfg_aliases! {
       // Backends
       metrics: { any(feature = "metrics", feature = "metrics-0_24") },
-      opentelemetry: { any(feature = "opentelemetry", feature = "opentelemetry-0_24") },
+      opentelemetry: { any(opentelemetry_0_24, opentelemetry_0_33) },
+      opentelemetry_0_24: { any(feature = "opentelemetry", feature = "opentelemetry-0_24") },
+      // (the docs are built with all features, so prefer 0.24 if both are enabled)
+      opentelemetry_0_33: { all(feature = "opentelemetry-0_33", not(opentelemetry_0_24)) },
       prometheus: { any(feature = "prometheus", feature = "prometheus-0_13") },
       prometheus_client_feature: { any(feature = "prometheus-client", feature = "prometheus-client-0_22") },
       default_backend: { all(
@@ -28,14 +31,61 @@ pub fn main() {
 
       // Misc
       prometheus_exporter: { feature = "prometheus-exporter" },
+      push_gateway: { feature = "prometheus-push-gateway" },
+      exporter_server: { feature = "prometheus-exporter-server" },
+      function_registry: { any(debug_assertions, feature = "function-registry") },
+
+      // OpenTelemetry push exporter
+      otel_push_exporter_transport: { any(feature = "otel-push-exporter-http", feature = "otel-push-exporter-grpc") },
+      otel_push_exporter_tokio: { feature = "otel-push-exporter-tokio" },
+      otel_push_exporter_tokio_current_thread: { feature = "otel-push-exporter-tokio-current-thread" },
+      otel_push_exporter_async_std: { feature = "otel-push-exporter-async-std" },
 
       // Exemplars
-      exemplars: { any(exemplars_tracing, exemplars_tracing_opentelemetry) },
+      exemplars: { any(exemplars_tracing, exemplars_tracing_opentelemetry, exemplars_custom, exemplars_testing) },
+      exemplars_custom: { feature = "exemplars-custom" },
+      exemplars_testing: { feature = "exemplars-testing" },
       exemplars_tracing: { feature = "exemplars-tracing" },
       exemplars_tracing_opentelemetry: { any(feature = "exemplars-tracing-opentelemetry-0_25", feature = "exemplars-tracing-opentelemetry") },
 
       // Custom objectives
       custom_objective_percentile: { feature = "custom-objective-percentile" },
       custom_objective_latency: { feature = "custom-objective-latency" },
+      objectives_yaml: { feature = "objectives-yaml" },
+
+      // Summaries
+      summaries: { any(summaries_metrics_util, summaries_hdrhistogram) },
+      summaries_metrics_util: { feature = "summaries-metrics-util" },
+      summaries_hdrhistogram: { feature = "summaries-hdrhistogram" },
+
+      // Wide events
+      wide_events: { feature = "wide-events" },
+
+      // Span events
+      span_events: { feature = "span-events" },
+
+      // Span traces
+      span_traces: { feature = "span-traces" },
+
+      // Task names from tokio-console
+      tokio_console: { feature = "tokio-console" },
+
+      // Snapshots
+      snapshots: { feature = "snapshots" },
+
+      // Calls recorded by the C interface
+      ffi: { feature = "ffi" },
+
+      // Calls that were measured elsewhere, for snapshots and the C interface
+      replayed_calls: { any(snapshots, ffi) },
+
+      // Streams of deltas
+      stream: { feature = "stream" },
+
+      // Debugging
+      debug_metrics: { feature = "debug-metrics" },
+
+      // Targets without `std::time::Instant`, where the JavaScript clock is used instead
+      wasm32_unknown: { all(target_arch = "wasm32", target_os = "unknown") },
     }
 }
@@ -0,0 +1,291 @@
+//! Generate the Prometheus recording and alerting rules for your objectives.
+//!
+//! The rules compute the error rates of every [`Objective`] used by the instrumented functions,
+//! and alert when they burn through the error budget too quickly, using the
+//! [multi-window, multi-burn-rate alerts](https://sre.google/workbook/alerting-on-slos/#6-multiwindow-multi-burn-rate-alerts)
+//! recommended by the Google SRE workbook:
+//!
+//! - a `page` alert if 2% of the monthly error budget is spent in 1 hour, or 5% in 6 hours
+//! - a `ticket` alert if 10% of the monthly error budget is spent in 1 day, or 10% in 3 days
+//!
+//! Unlike the Sloth file generated by the [autometrics-cli](https://github.com/autometrics-dev/autometrics-rs/tree/main/autometrics-cli),
+//! the rules only cover the objectives that are actually used, including those with custom percentiles
+//! and [composite](crate::objectives::Objective::composite) objectives (whose rules have the `composite` category),
+//! and can be loaded by Prometheus directly:
+//!
+//! ```rust,no_run
+//! use autometrics::{alerts, autometrics, objectives::*};
+//!
+//! const API_SLO: Objective = Objective::new("api")
+//!     .success_rate(ObjectivePercentile::P99_9)
+//!     .latency(ObjectiveLatency::Ms250, ObjectivePercentile::P99);
+//!
+//! #[autometrics(objective = API_SLO)]
+//! pub fn api_handler() {
+//!    // ...
+//! }
+//!
+//! fn main() {
+//!     // Only needed in release builds without the `function-registry` feature, see below
+//!     alerts::register_objective(API_SLO);
+//!
+//!     std::fs::write("autometrics.rules.yml", alerts::generate_rules()).unwrap();
+//! }
+//! ```
+//!
+//! # Finding the objectives
+//!
+//! In debug builds, or if the `function-registry` feature is enabled, the objectives of all of the
+//! instrumented functions are found automatically. Otherwise, the instrumented functions are not
+//! collected, so the objectives need to be registered with [`register_objective`]. (Objectives that
+//! are registered anyway are only included once.)
+//!
+//! The metric names include the prefix configured with
+//! [`AutometricsSettingsBuilder::metric_name_prefix`](crate::settings::AutometricsSettingsBuilder::metric_name_prefix),
+//! so the rules should be generated after the settings are initialized.
+//!
+//! # Caller graph
+//!
+//! Queries over the calls between every pair of functions, like the ones used to draw a service's
+//! call graph, can be slow on large installations, because they aggregate every series of the
+//! `function.calls` counter. [`generate_caller_graph_rules`] generates recording rules that precompute
+//! them over 5 minutes, 1 hour, and 1 day, aggregated by the caller, the function, and the service:
+//!
+//! - `autometrics:caller_graph:calls_rate{window}` is the rate of the calls
+//! - `autometrics:caller_graph:error_ratio_rate{window}` is the ratio of the calls that returned errors
+//!
+//! When the instrumented functions are known (see above), the rules only include the series of those functions.
+//! Use [`generate_all_rules`] to write the rules for the objectives and the caller graph to a single file.
+
+#[cfg(function_registry)]
+use crate::__private::FUNCTION_DESCRIPTIONS;
+use crate::constants::*;
+use crate::labels::label_key;
+use crate::objectives::Objective;
+use crate::queries::{
+    burn_rate_condition, error_budget, escape, slo_error_ratio_query, Sli, BURN_RATE_ALERTS,
+};
+use crate::settings::get_settings;
+use once_cell::sync::Lazy;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write;
+use std::sync::RwLock;
+
+/// The objectives registered with [`register_objective`]
+static OBJECTIVES: Lazy<RwLock<Vec<Objective>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+/// The windows over which the call rates and error ratios of the caller graph are recorded
+const CALLER_GRAPH_WINDOWS: [&str; 3] = ["5m", "1h", "1d"];
+
+/// The windows over which the error ratios are recorded
+const WINDOWS: [&str; 7] = ["5m", "30m", "1h", "2h", "6h", "1d", "3d"];
+
+/// Include the given objective in the rules, even if no instrumented function was found that uses it.
+pub fn register_objective(objective: Objective) {
+    OBJECTIVES
+        .write()
+        .unwrap_or_else(|err| err.into_inner())
+        .push(objective);
+}
+
+/// Generate the Prometheus rules file with the recording and alerting rules for all of the objectives.
+///
+/// See the [module docs](self) for details.
+pub fn generate_rules() -> String {
+    let mut rules = String::from("groups:\n");
+    write_objective_rules(&mut rules);
+    finish_rules(rules)
+}
+
+/// Generate the Prometheus rules file with the recording rules for the caller graph.
+///
+/// See the [module docs](self#caller-graph) for details.
+pub fn generate_caller_graph_rules() -> String {
+    let mut rules = String::from("groups:\n");
+    write_caller_graph_rules(&mut rules);
+    finish_rules(rules)
+}
+
+/// Generate a single Prometheus rules file with both the rules for the objectives
+/// (from [`generate_rules`]) and the rules for the caller graph (from [`generate_caller_graph_rules`]).
+pub fn generate_all_rules() -> String {
+    let mut rules = String::from("groups:\n");
+    write_objective_rules(&mut rules);
+    write_caller_graph_rules(&mut rules);
+    finish_rules(rules)
+}
+
+/// Prometheus does not accept a `groups` key without any groups
+fn finish_rules(rules: String) -> String {
+    if rules.ends_with("groups:\n") {
+        String::from("groups: []\n")
+    } else {
+        rules
+    }
+}
+
+fn write_objective_rules(rules: &mut String) {
+    let mut objectives = OBJECTIVES
+        .read()
+        .unwrap_or_else(|err| err.into_inner())
+        .clone();
+    #[cfg(function_registry)]
+    objectives.extend(
+        FUNCTION_DESCRIPTIONS
+            .iter()
+            .filter_map(|function| (function.objective)()),
+    );
+
+    // Functions that are part of the same objective share the rules
+    let mut slos = BTreeMap::new();
+    for objective in objectives {
+        if let Some(percentile) = objective.success_rate {
+            slos.insert(
+                (
+                    objective.name,
+                    Sli::SuccessRate.category(),
+                    percentile.as_str(),
+                ),
+                Sli::SuccessRate,
+            );
+        }
+        if let Some((_, percentile)) = objective.latency {
+            slos.insert(
+                (objective.name, Sli::Latency.category(), percentile.as_str()),
+                Sli::Latency,
+            );
+        }
+        if let Some((_, percentile)) = objective.composite {
+            slos.insert(
+                (
+                    objective.name,
+                    Sli::Composite.category(),
+                    percentile.as_str(),
+                ),
+                Sli::Composite,
+            );
+        }
+    }
+
+    for ((name, _, percentile), sli) in slos {
+        write_slo_rules(rules, name, sli, percentile);
+    }
+}
+
+fn write_caller_graph_rules(rules: &mut String) {
+    // Only the series of the instrumented functions are included when they are known
+    #[cfg(function_registry)]
+    let selector = {
+        let functions: BTreeSet<&str> = FUNCTION_DESCRIPTIONS
+            .iter()
+            .map(|function| function.id.name())
+            .collect();
+        if functions.is_empty() {
+            return;
+        }
+        let pattern = functions
+            .into_iter()
+            .map(escape_regex)
+            .collect::<Vec<_>>()
+            .join("|");
+        format!(r#"{}=~"{}""#, label_key(FUNCTION_KEY), escape(&pattern))
+    };
+    #[cfg(not(function_registry))]
+    let selector = String::new();
+    let error_selector = if selector.is_empty() {
+        format!(r#"{RESULT_KEY}="{ERROR_KEY}""#)
+    } else {
+        format!(r#"{selector},{RESULT_KEY}="{ERROR_KEY}""#)
+    };
+
+    let counter = get_settings().prometheus_metric_name(COUNTER_NAME_PROMETHEUS);
+    let by = format!(
+        "sum by ({CALLER_FUNCTION_PROMETHEUS}, {CALLER_MODULE_PROMETHEUS}, {}, {}, {SERVICE_NAME_KEY_PROMETHEUS})",
+        label_key(FUNCTION_KEY),
+        label_key(MODULE_KEY)
+    );
+
+    let _ = writeln!(rules, "  - name: \"autometrics-caller-graph\"");
+    let _ = writeln!(rules, "    rules:");
+    for window in CALLER_GRAPH_WINDOWS {
+        let calls = format!("{by} (rate({counter}{{{selector}}}[{window}]))");
+        let errors = format!("{by} (rate({counter}{{{error_selector}}}[{window}]))");
+        let _ = writeln!(
+            rules,
+            "      - record: autometrics:caller_graph:calls_rate{window}"
+        );
+        let _ = writeln!(rules, "        expr: |-\n          {calls}");
+        let _ = writeln!(
+            rules,
+            "      - record: autometrics:caller_graph:error_ratio_rate{window}"
+        );
+        let _ = writeln!(rules, "        expr: |-\n          {errors} / {calls}");
+    }
+}
+
+fn write_slo_rules(rules: &mut String, name: &str, sli: Sli, percentile: &str) {
+    let Some(error_budget) = error_budget(percentile) else {
+        return;
+    };
+    let category = sli.category();
+    let series = format!(
+        r#"category="{category}",objective_name="{}",objective_percentile="{percentile}""#,
+        escape(name)
+    );
+
+    let _ = writeln!(
+        rules,
+        "  - name: \"autometrics-{}-{category}-{}\"",
+        escape(name),
+        percentile.replace('.', "_")
+    );
+    let _ = writeln!(rules, "    rules:");
+
+    for window in WINDOWS {
+        let expr = slo_error_ratio_query(sli, name, percentile, window);
+        let _ = writeln!(
+            rules,
+            "      - record: autometrics:slo_errors:ratio_rate{window}"
+        );
+        let _ = writeln!(rules, "        expr: |-\n          {expr}");
+        let _ = writeln!(rules, "        labels:\n          category: {category}");
+    }
+
+    let title = match sli {
+        Sli::SuccessRate => "High error rate",
+        Sli::Latency => "High latency",
+        Sli::Composite => "High error rate or latency",
+    };
+    for (severity, windows) in BURN_RATE_ALERTS {
+        let expr = burn_rate_condition(&windows, error_budget, |window| {
+            format!("autometrics:slo_errors:ratio_rate{window}{{{series}}}")
+        });
+        let _ = writeln!(
+            rules,
+            "      - alert: \"{title} SLO - {}%\"",
+            escape(percentile)
+        );
+        let _ = writeln!(rules, "        expr: |-\n          {expr}");
+        let _ = writeln!(
+            rules,
+            "        labels:\n          category: {category}\n          severity: {severity}"
+        );
+        let _ = writeln!(
+            rules,
+            "        annotations:\n          summary: \"{title} on the `{{{{ $labels.objective_name }}}}` SLO for the `{{{{ $labels.service_name }}}}` service\""
+        );
+    }
+}
+
+/// Escape the characters that have a special meaning in a PromQL regular expression
+#[cfg(function_registry)]
+fn escape_regex(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if r"\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
@@ -0,0 +1,142 @@
+//! Instrument every resolver of an [`async-graphql`](https://crates.io/crates/async-graphql) schema without annotating them.
+//!
+//! # Example
+//! ```rust
+//! use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema};
+//! use autometrics::{async_graphql::Autometrics, objectives::*};
+//!
+//! const API_SLO: Objective = Objective::new("api")
+//!     .success_rate(ObjectivePercentile::P99_9);
+//!
+//! struct Query;
+//!
+//! #[Object]
+//! impl Query {
+//!     async fn user(&self, id: u64) -> String {
+//!         format!("User {id}")
+//!     }
+//! }
+//!
+//! // Every resolver is tracked as a function named after its field (here: "Query.user")
+//! let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+//!     .extension(Autometrics::new().objective(API_SLO))
+//!     .finish();
+//! ```
+//!
+//! Resolvers that return an error are counted as errors. Introspection fields are not tracked.
+//!
+//! Nested resolvers and functions instrumented with the `autometrics` macro
+//! are reported with the resolver as their caller.
+
+use crate::__private::{
+    AutometricsTracker, CallerInfo, CounterLabels, HistogramLabels, TrackMetrics, ERROR_KEY, OK_KEY,
+};
+use crate::objectives::Objective;
+use crate::poison;
+use crate::settings::get_settings;
+use ::async_graphql::extensions::{
+    Extension, ExtensionContext, ExtensionFactory, NextResolve, ResolveInfo,
+};
+use ::async_graphql::{ServerResult, Value};
+use once_cell::sync::Lazy;
+use std::{
+    collections::HashSet,
+    sync::{Arc, RwLock},
+};
+
+/// The value of the `module` label for the metrics of the resolvers
+const MODULE: &str = "async_graphql";
+
+/// The label values need to be `&'static str`s, so the field names are leaked once each.
+/// This is bounded by the number of fields in the schema.
+static FIELDS: Lazy<RwLock<HashSet<&'static str>>> = Lazy::new(Default::default);
+
+/// Schema extension that tracks the request rate, error rate, and latency of every resolver,
+/// using `Type.field` as the `function` label.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Autometrics {
+    objective: Option<Objective>,
+}
+
+impl Autometrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Include the metrics of all of the resolvers in the given [`Objective`].
+    pub fn objective(mut self, objective: Objective) -> Self {
+        self.objective = Some(objective);
+        self
+    }
+}
+
+impl ExtensionFactory for Autometrics {
+    fn create(&self) -> Arc<dyn Extension> {
+        Arc::new(AutometricsExtension {
+            objective: self.objective,
+        })
+    }
+}
+
+struct AutometricsExtension {
+    objective: Option<Objective>,
+}
+
+#[::async_graphql::async_trait::async_trait]
+impl Extension for AutometricsExtension {
+    async fn resolve(
+        &self,
+        ctx: &ExtensionContext<'_>,
+        info: ResolveInfo<'_>,
+        next: NextResolve<'_>,
+    ) -> ServerResult<Option<Value>> {
+        // The root introspection fields (such as `__schema`) are not flagged as introspection themselves,
+        // but the names starting with two underscores are reserved for introspection
+        if info.is_for_introspection || info.name.starts_with("__") {
+            return next.run(ctx, info).await;
+        }
+
+        let function = intern_field(info.parent_type, info.name);
+        let settings = get_settings();
+        let caller = CallerInfo::current(settings);
+
+        let tracker = AutometricsTracker::start(None);
+        let callee = CallerInfo {
+            caller_function: function,
+            caller_module: MODULE,
+        };
+        let result = callee.scope(settings, next.run(ctx, info)).await;
+
+        let result_label = if result.is_ok() { OK_KEY } else { ERROR_KEY };
+        let counter_labels = CounterLabels::new(
+            settings,
+            function,
+            MODULE,
+            caller.caller_function,
+            caller.caller_module,
+            Some((result_label, None)),
+            self.objective,
+        );
+        let histogram_labels = HistogramLabels::new(settings, function, MODULE, self.objective);
+        tracker.finish(&counter_labels, &histogram_labels);
+
+        result
+    }
+}
+
+fn intern_field(parent_type: &str, name: &str) -> &'static str {
+    let field = format!("{parent_type}.{name}");
+    if let Some(field) = poison::read(&FIELDS, "fields").get(field.as_str()).copied() {
+        return field;
+    }
+
+    let mut fields = poison::write(&FIELDS, "fields");
+    match fields.get(field.as_str()) {
+        Some(field) => field,
+        None => {
+            let field: &'static str = Box::leak(field.into_boxed_str());
+            fields.insert(field);
+            field
+        }
+    }
+}
@@ -0,0 +1,42 @@
+//! Helpers for the build scripts of crates that use autometrics.
+//!
+//! The `build_info` metric and the `build_age_seconds` gauge are populated from compile-time
+//! environment variables, which a build script can set with these functions.
+//! Add `autometrics` to the `[build-dependencies]` of the crate to use them.
+//!
+//! # Example
+//! ```rust,no_run
+//! // build.rs
+//! pub fn main() {
+//!     autometrics::build::emit_build_timestamp();
+//! }
+//! ```
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// The Unix timestamp (in seconds) of the build.
+///
+/// This is the value of the `SOURCE_DATE_EPOCH` environment variable if it is set
+/// (so that reproducible builds keep the same timestamp), or the current time otherwise.
+pub fn build_timestamp() -> u64 {
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|timestamp| timestamp.trim().parse().ok())
+        .unwrap_or_else(|| {
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("System clock is set before the Unix epoch")
+                .as_secs()
+        })
+}
+
+/// Set the `AUTOMETRICS_BUILD_TIMESTAMP` compile-time environment variable to the [`build_timestamp`],
+/// which adds the `build_timestamp` label to the `build_info` metric and produces the `build_age_seconds` gauge.
+///
+/// This must be called from the build script of the crate that uses the `autometrics` macro.
+pub fn emit_build_timestamp() {
+    println!(
+        "cargo:rustc-env=AUTOMETRICS_BUILD_TIMESTAMP={}",
+        build_timestamp()
+    );
+}
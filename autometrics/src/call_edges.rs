@@ -0,0 +1,68 @@
+//! The pairs of functions where one was seen calling the other, which are exported as the
+//! `function_call_edges_info` metric when it is enabled with
+//! [`AutometricsSettingsBuilder::call_edges_info`](crate::settings::AutometricsSettingsBuilder::call_edges_info).
+
+use crate::labels::{CallEdgeLabels, CounterLabels};
+use crate::poison;
+use crate::settings::get_settings;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Once, RwLock};
+
+/// The call edges, along with the tick at which each was last seen
+static EDGES: Lazy<RwLock<HashMap<CallEdgeLabels, AtomicU64>>> = Lazy::new(Default::default);
+
+/// Incremented for every observed call, so that the edges seen least recently can be evicted
+static TICK: AtomicU64 = AtomicU64::new(0);
+
+static REGISTER: Once = Once::new();
+
+/// Record that the function in the counter labels was called by its caller, if the metric is enabled.
+pub(crate) fn observe(counter_labels: &CounterLabels) {
+    let Some(max_edges) = get_settings().call_edges else {
+        return;
+    };
+    if max_edges == 0 || counter_labels.caller_function.is_empty() {
+        return;
+    }
+
+    let edge = CallEdgeLabels::from(counter_labels);
+    let tick = TICK.fetch_add(1, Ordering::Relaxed);
+
+    let edges = poison::read(&EDGES, "call_edges");
+    if let Some(last_seen) = edges.get(&edge) {
+        last_seen.store(tick, Ordering::Relaxed);
+        return;
+    }
+    drop(edges);
+
+    let mut edges = poison::write(&EDGES, "call_edges");
+    if edges.contains_key(&edge) {
+        return;
+    }
+
+    // Finding the edge to evict is linear in the number of edges, but this only happens
+    // the first time that an edge is seen once the limit is reached
+    let mut evicted = None;
+    if edges.len() >= max_edges {
+        evicted = edges
+            .iter()
+            .min_by_key(|(_, last_seen)| last_seen.load(Ordering::Relaxed))
+            .map(|(edge, _)| *edge);
+        if let Some(evicted) = &evicted {
+            edges.remove(evicted);
+        }
+    }
+    edges.insert(edge, AtomicU64::new(tick));
+    drop(edges);
+
+    REGISTER.call_once(crate::tracker::register_call_edges);
+    crate::tracker::update_call_edges(&edge, evicted.as_ref());
+}
+
+/// The call edges that are currently known
+#[allow(dead_code)]
+pub(crate) fn edges() -> Vec<CallEdgeLabels> {
+    poison::read(&EDGES, "call_edges").keys().copied().collect()
+}
@@ -0,0 +1,183 @@
+use std::fmt;
+
+/// The metrics library used to produce the metrics.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Backend {
+    /// The `metrics-0_24` feature.
+    Metrics,
+    /// The `opentelemetry-0_24` or `opentelemetry-0_33` feature.
+    OpenTelemetry,
+    /// The `prometheus-0_13` feature.
+    Prometheus,
+    /// The `prometheus-client-0_22` feature, which is also used by default with the `prometheus-exporter` feature.
+    PrometheusClient,
+}
+
+impl Backend {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Backend::Metrics => "metrics",
+            Backend::OpenTelemetry => "opentelemetry",
+            Backend::Prometheus => "prometheus",
+            Backend::PrometheusClient => "prometheus-client",
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Where exemplars are extracted from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ExemplarsSource {
+    /// The fields of the current `tracing::Span` (the `exemplars-tracing` feature).
+    Tracing,
+    /// The OpenTelemetry Context attached to the current `tracing::Span`
+    /// (the `exemplars-tracing-opentelemetry-0_25` feature).
+    TracingOpenTelemetry,
+    /// The `ExemplarProvider` set in the settings
+    /// (the `exemplars-custom` feature, if neither of the other exemplars features are enabled).
+    Custom,
+    /// Only the exemplars set with `exemplars::set_override`
+    /// (the `exemplars-testing` feature, if none of the other exemplars features are enabled).
+    Testing,
+}
+
+impl ExemplarsSource {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            ExemplarsSource::Tracing => "tracing",
+            ExemplarsSource::TracingOpenTelemetry => "tracing-opentelemetry",
+            ExemplarsSource::Custom => "custom",
+            ExemplarsSource::Testing => "testing",
+        }
+    }
+}
+
+impl fmt::Display for ExemplarsSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The backend, exporters, and features that autometrics was compiled with.
+///
+/// See [`capabilities`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Capabilities {
+    /// The metrics library used to produce the metrics, if any.
+    pub backend: Option<Backend>,
+    /// Where exemplars are extracted from, if exemplars are enabled.
+    pub exemplars: Option<ExemplarsSource>,
+    /// Whether the `prometheus_exporter` module is available.
+    pub prometheus_exporter: bool,
+    /// Whether metrics can be pushed to a Prometheus Pushgateway (the `prometheus-push-gateway` feature).
+    pub push_gateway: bool,
+    /// Whether the `otel_push_exporter` module is available.
+    pub otel_push_exporter: bool,
+    /// Whether the `buckets` argument of the `autometrics` macro is supported by the backend.
+    pub per_function_buckets: bool,
+    /// Whether `registry::remove_function` is supported by the backend.
+    pub remove_function: bool,
+    /// Whether `registry::instrumented_functions` is available
+    /// (in debug builds, or with the `function-registry` feature).
+    pub instrumented_functions: bool,
+    /// Whether in-process summaries are recorded (the `summaries-*` features).
+    pub summaries: bool,
+    /// Whether wide events are emitted (the `wide-events` feature).
+    pub wide_events: bool,
+    /// Whether the `span_events` argument of the `autometrics` macro is supported (the `span-events` feature).
+    pub span_events: bool,
+    /// Whether the `snapshot` module is available (the `snapshots` feature).
+    pub snapshots: bool,
+    /// Whether custom objective percentiles are allowed.
+    pub custom_objective_percentile: bool,
+    /// Whether custom objective latency thresholds are allowed.
+    pub custom_objective_latency: bool,
+    /// Whether objectives can be loaded from YAML.
+    pub objectives_yaml: bool,
+    /// Whether the `plugin` module is available.
+    pub plugin: bool,
+    /// Whether the `alerts` module is available.
+    pub alerts: bool,
+    /// Whether the `axum` integration is available.
+    pub axum: bool,
+    /// Whether the `async-graphql` integration is available.
+    pub async_graphql: bool,
+    /// Whether the `rdkafka` integration is available.
+    pub rdkafka: bool,
+    /// Whether the `tower` middleware is available.
+    pub tower: bool,
+    /// Whether `context::spawn` is available.
+    pub tokio: bool,
+}
+
+/// Describe the backend, exporters, and features that autometrics was compiled with.
+///
+/// Libraries and frameworks building on autometrics can use this to adapt their behavior,
+/// for example by skipping work to extract exemplars when they are not supported,
+/// or to show the configuration in a diagnostics endpoint.
+///
+/// ```rust
+/// let capabilities = autometrics::capabilities();
+/// if capabilities.exemplars.is_none() {
+///     // Don't bother attaching trace details to the spans
+/// }
+/// println!("{capabilities:?}");
+/// ```
+pub const fn capabilities() -> Capabilities {
+    let backend = if cfg!(metrics) {
+        Some(Backend::Metrics)
+    } else if cfg!(opentelemetry) {
+        Some(Backend::OpenTelemetry)
+    } else if cfg!(prometheus) {
+        Some(Backend::Prometheus)
+    } else if cfg!(prometheus_client) {
+        Some(Backend::PrometheusClient)
+    } else {
+        None
+    };
+
+    let exemplars = if cfg!(exemplars_tracing) {
+        Some(ExemplarsSource::Tracing)
+    } else if cfg!(exemplars_tracing_opentelemetry) {
+        Some(ExemplarsSource::TracingOpenTelemetry)
+    } else if cfg!(exemplars_custom) {
+        Some(ExemplarsSource::Custom)
+    } else if cfg!(exemplars_testing) {
+        Some(ExemplarsSource::Testing)
+    } else {
+        None
+    };
+
+    Capabilities {
+        backend,
+        exemplars,
+        prometheus_exporter: cfg!(prometheus_exporter),
+        push_gateway: cfg!(push_gateway),
+        otel_push_exporter: cfg!(feature = "otel-push-exporter"),
+        per_function_buckets: cfg!(any(prometheus, prometheus_client)),
+        remove_function: cfg!(any(prometheus, prometheus_client)),
+        instrumented_functions: cfg!(function_registry),
+        summaries: cfg!(summaries),
+        wide_events: cfg!(wide_events),
+        span_events: cfg!(span_events),
+        snapshots: cfg!(snapshots),
+        custom_objective_percentile: cfg!(custom_objective_percentile),
+        custom_objective_latency: cfg!(custom_objective_latency),
+        objectives_yaml: cfg!(objectives_yaml),
+        plugin: cfg!(feature = "plugin"),
+        alerts: cfg!(feature = "alerts"),
+        axum: cfg!(feature = "axum"),
+        async_graphql: cfg!(feature = "async-graphql"),
+        rdkafka: cfg!(feature = "rdkafka"),
+        tower: cfg!(feature = "tower"),
+        tokio: cfg!(feature = "tokio"),
+    }
+}
@@ -0,0 +1,227 @@
+//! Warn when an instrumented function creates too many series.
+//!
+//! Every distinct combination of label values creates a new series in the metrics backend and in Prometheus.
+//! Labels like `caller_function`, `error_type`, or `type_params` are usually bounded, but a function called
+//! from many places, or one that returns errors with dynamic variants, can create far more series than expected.
+//!
+//! The [`CardinalityWatchdog`] counts the label sets of the `function.calls` counter for each function.
+//! When a function exceeds the threshold, it reports a [`CardinalityWarning`] naming the function and
+//! the label with the most distinct values, and increments the `autometrics_cardinality_warnings_total`
+//! counter (with the `function`, `module`, and `label` labels). The warning is reported again every time
+//! the number of series doubles.
+//!
+//! # Example
+//! ```rust
+//! use autometrics::cardinality::CardinalityWatchdog;
+//! use autometrics::settings::AutometricsSettings;
+//!
+//! AutometricsSettings::builder()
+//!     .cardinality_watchdog(
+//!         CardinalityWatchdog::new(100)
+//!             // This function is expected to be called from many places
+//!             .function_threshold("get_config", 1000)
+//!             .on_warning(|warning| eprintln!("{warning}")),
+//!     )
+//!     .init();
+//! ```
+//!
+//! The watchdog computes the label set of every call, so it adds a small overhead to each call.
+
+use crate::constants::*;
+use crate::labels::{CardinalityWarningLabels, CounterLabels};
+use crate::poison;
+use crate::settings::get_settings;
+use once_cell::sync::Lazy;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::RwLock;
+
+/// The label sets of each function, keyed by the function and module
+static FUNCTIONS: Lazy<RwLock<HashMap<(&'static str, &'static str), FunctionCardinality>>> =
+    Lazy::new(Default::default);
+
+#[derive(Default)]
+struct FunctionCardinality {
+    /// The hashes of the label sets
+    series: HashSet<u64>,
+    /// The distinct values of each label
+    values: HashMap<&'static str, HashSet<&'static str>>,
+    /// The number of series at which the next warning is reported
+    next_warning: usize,
+}
+
+/// A function that has more series than its threshold.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct CardinalityWarning {
+    pub function: &'static str,
+    pub module: &'static str,
+    /// The label with the most distinct values, which most likely causes the high cardinality
+    pub label: &'static str,
+    /// The number of distinct values of the label
+    pub label_values: usize,
+    /// The number of series of the function
+    pub series: usize,
+    pub threshold: usize,
+}
+
+impl fmt::Display for CardinalityWarning {
+    /// Format the warning as a single line of `key=value` pairs.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "autometrics high cardinality function={:?} module={:?} label={:?} label_values={} series={} threshold={}",
+            self.function, self.module, self.label, self.label_values, self.series, self.threshold
+        )
+    }
+}
+
+/// The configuration of the cardinality watchdog, which is set with
+/// [`AutometricsSettingsBuilder::cardinality_watchdog`](crate::settings::AutometricsSettingsBuilder::cardinality_watchdog).
+pub struct CardinalityWatchdog {
+    threshold: usize,
+    function_thresholds: HashMap<String, usize>,
+    on_warning: Box<dyn Fn(&CardinalityWarning) + Send + Sync>,
+}
+
+impl CardinalityWatchdog {
+    /// Warn when a function has more than `threshold` series.
+    ///
+    /// By default, the warnings are logged as `tracing` events at the `WARN` level if the `tracing`
+    /// crate is enabled (for example, by the `exemplars-tracing` or `wide-events` features), and
+    /// are otherwise only counted in the `autometrics_cardinality_warnings_total` counter.
+    pub fn new(threshold: usize) -> Self {
+        Self {
+            threshold,
+            function_thresholds: HashMap::new(),
+            on_warning: Box::new(log_warning),
+        }
+    }
+
+    /// Use a different threshold for the function with the given name.
+    pub fn function_threshold(mut self, function: impl Into<String>, threshold: usize) -> Self {
+        self.function_thresholds.insert(function.into(), threshold);
+        self
+    }
+
+    /// Report the warnings to the given function instead of the default `tracing` event,
+    /// for example to log them with a different logger.
+    pub fn on_warning(
+        mut self,
+        on_warning: impl Fn(&CardinalityWarning) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_warning = Box::new(on_warning);
+        self
+    }
+
+    fn threshold(&self, function: &str) -> usize {
+        self.function_thresholds
+            .get(function)
+            .copied()
+            .unwrap_or(self.threshold)
+    }
+}
+
+/// The default handler of the warnings
+#[allow(unused_variables)]
+fn log_warning(warning: &CardinalityWarning) {
+    #[cfg(feature = "tracing")]
+    tracing::warn!(
+        target: "autometrics::cardinality",
+        function = warning.function,
+        module = warning.module,
+        label = warning.label,
+        label_values = warning.label_values,
+        series = warning.series,
+        threshold = warning.threshold,
+        "autometrics high cardinality"
+    );
+}
+
+impl fmt::Debug for CardinalityWatchdog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CardinalityWatchdog")
+            .field("threshold", &self.threshold)
+            .field("function_thresholds", &self.function_thresholds)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Count the label set of a finished call, if the watchdog is enabled
+pub(crate) fn observe(counter_labels: &CounterLabels) {
+    let Some(watchdog) = &get_settings().cardinality_watchdog else {
+        return;
+    };
+
+    let labels = counter_labels.to_vec();
+    let mut hasher = DefaultHasher::new();
+    labels.hash(&mut hasher);
+    let hash = hasher.finish();
+
+    let key = (counter_labels.function, counter_labels.module);
+    if poison::read(&FUNCTIONS, "cardinality")
+        .get(&key)
+        .is_some_and(|function| function.series.contains(&hash))
+    {
+        return;
+    }
+
+    let mut functions = poison::write(&FUNCTIONS, "cardinality");
+    let function = functions.entry(key).or_insert_with(|| FunctionCardinality {
+        next_warning: watchdog
+            .threshold(counter_labels.function)
+            .saturating_add(1),
+        ..Default::default()
+    });
+    if !function.series.insert(hash) {
+        return;
+    }
+    // The function, module, and service name are the same for all of the series
+    for (label, value) in labels {
+        if ![FUNCTION_KEY, MODULE_KEY, SERVICE_NAME_KEY].contains(&label) {
+            function.values.entry(label).or_default().insert(value);
+        }
+    }
+    if function.series.len() < function.next_warning {
+        return;
+    }
+    function.next_warning = function.next_warning.saturating_mul(2);
+
+    let (label, values) = function
+        .values
+        .iter()
+        .max_by_key(|(label, values)| (values.len(), *label))
+        .map(|(label, values)| (prometheus_label(label), values.len()))
+        .unwrap_or_default();
+    let warning = CardinalityWarning {
+        function: counter_labels.function,
+        module: counter_labels.module,
+        label,
+        label_values: values,
+        series: function.series.len(),
+        threshold: watchdog.threshold(counter_labels.function),
+    };
+    drop(functions);
+
+    crate::tracker::record_cardinality_warning(&CardinalityWarningLabels {
+        function: counter_labels.function,
+        module: counter_labels.module,
+        label,
+        service_name: counter_labels.service_name,
+    });
+    (watchdog.on_warning)(&warning);
+}
+
+/// The name of the label in Prometheus, which does not allow dots in label names
+fn prometheus_label(label: &'static str) -> &'static str {
+    match label {
+        CALLER_FUNCTION_KEY => CALLER_FUNCTION_PROMETHEUS,
+        CALLER_MODULE_KEY => CALLER_MODULE_PROMETHEUS,
+        ERROR_TYPE_KEY => ERROR_TYPE_PROMETHEUS,
+        OBJECTIVE_NAME => OBJECTIVE_NAME_PROMETHEUS,
+        OBJECTIVE_PERCENTILE => OBJECTIVE_PERCENTILE_PROMETHEUS,
+        label => label,
+    }
+}
@@ -0,0 +1,87 @@
+//! Track the maximum number of concurrent calls of the functions instrumented with `track_concurrency(max)`.
+//!
+//! The `function.calls.concurrent` gauge only shows the number of calls in progress at the time it is scraped,
+//! so short bursts between two scrapes are missed. For these functions, the highest number of concurrent calls
+//! is also recorded in the `function.calls.concurrent.max` gauge. The [`prometheus_exporter`](crate::prometheus_exporter)
+//! resets it to the current number of calls after every scrape, so that it shows the maximum since the previous scrape.
+//! With other exporters, it shows the maximum since the process started.
+
+use crate::labels::GaugeLabels;
+use crate::poison;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// The watermarks of each function, keyed by the function and module
+type Watermarks = HashMap<(&'static str, &'static str), Arc<Watermark>>;
+
+static WATERMARKS: Lazy<RwLock<Watermarks>> = Lazy::new(Default::default);
+
+struct Watermark {
+    labels: GaugeLabels,
+    current: AtomicI64,
+    max: AtomicI64,
+    /// Held while the gauge is set, so that a lower maximum is never recorded after a higher one
+    gauge: Mutex<()>,
+}
+
+impl Watermark {
+    fn set_gauge(&self) {
+        let _gauge = poison::lock(&self.gauge, "concurrency_watermarks");
+        crate::tracker::set_max_concurrency(&self.labels, self.max.load(Ordering::SeqCst));
+    }
+}
+
+/// Decrements the number of concurrent calls when the call finishes
+pub(crate) struct ConcurrencyGuard(Arc<Watermark>);
+
+impl Drop for ConcurrencyGuard {
+    fn drop(&mut self) {
+        self.0.current.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Count a call that started, and raise the maximum if it is the highest number of concurrent calls so far
+pub(crate) fn start(labels: &GaugeLabels) -> ConcurrencyGuard {
+    let key = (labels.function, labels.module);
+    let existing = poison::read(&WATERMARKS, "concurrency_watermarks")
+        .get(&key)
+        .cloned();
+    let watermark = existing.unwrap_or_else(|| {
+        poison::write(&WATERMARKS, "concurrency_watermarks")
+            .entry(key)
+            .or_insert_with(|| {
+                Arc::new(Watermark {
+                    labels: labels.clone(),
+                    current: AtomicI64::new(0),
+                    max: AtomicI64::new(0),
+                    gauge: Mutex::new(()),
+                })
+            })
+            .clone()
+    });
+
+    let current = watermark.current.fetch_add(1, Ordering::SeqCst) + 1;
+    if watermark.max.fetch_max(current, Ordering::SeqCst) < current {
+        watermark.set_gauge();
+    }
+    ConcurrencyGuard(watermark)
+}
+
+/// Lower the maximums to the current number of calls, after the metrics were exported
+#[cfg(prometheus_exporter)]
+pub(crate) fn reset() {
+    for watermark in poison::read(&WATERMARKS, "concurrency_watermarks").values() {
+        watermark
+            .max
+            .store(watermark.current.load(Ordering::SeqCst), Ordering::SeqCst);
+        watermark.set_gauge();
+    }
+}
+
+/// Stop tracking the function with the given name, in any module
+#[cfg(any(prometheus, prometheus_client))]
+pub(crate) fn remove_function(function: &str) {
+    poison::write(&WATERMARKS, "concurrency_watermarks").retain(|(name, _), _| *name != function);
+}
@@ -0,0 +1,210 @@
+//! Instrument long-lived connections, such as WebSockets, in phases.
+//!
+//! A connection can stay open for hours, so the latency of the whole connection says little about how
+//! it is doing. Instead, a [`Connection`] splits each connection into the [`Phase`]s it goes through,
+//! and records every phase as a call to a function named after the connection and the phase:
+//!
+//! - `function_calls_duration_seconds{function="chat.handshake"}` is the time it takes to accept a connection
+//! - `function_calls_duration_seconds{function="chat.active"}` is how long the connections stay open
+//! - `function_calls_duration_seconds{function="chat.shutdown"}` is the time it takes to close them
+//!
+//! The number of connections that are currently open is tracked in the
+//! `function_calls_concurrent{function="chat"}` gauge, and each connection is counted in
+//! `function_calls_total{function="chat"}` when it is closed.
+//!
+//! # Example
+//! ```rust
+//! use autometrics::connection::{Connection, Phase};
+//!
+//! const CHAT: Connection = Connection::new("chat", module_path!());
+//!
+//! # struct Socket;
+//! # impl Socket {
+//! #     async fn accept(&mut self) -> Result<(), std::io::Error> { Ok(()) }
+//! #     async fn recv(&mut self) -> Option<String> { None }
+//! #     async fn close(&mut self) -> Result<(), std::io::Error> { Ok(()) }
+//! # }
+//! async fn handle_socket(mut socket: Socket) -> Result<(), std::io::Error> {
+//!     let mut connection = CHAT.open();
+//!     socket.accept().await?;
+//!
+//!     connection.enter(Phase::Active);
+//!     while let Some(_message) = socket.recv().await {
+//!         // ...
+//!     }
+//!
+//!     connection.enter(Phase::Shutdown);
+//!     let result = socket.close().await;
+//!     connection.close(&result);
+//!     result
+//! }
+//! ```
+//!
+//! If the connection is dropped without being closed (for example, because the handler returned
+//! early with `?`), the current phase and the connection are recorded without a `result` label,
+//! or as errors with the `panic="true"` label if the thread is panicking.
+//! The functions instrumented with the `autometrics` macro that are called within
+//! [`ConnectionTracker::scope`] or [`ConnectionTracker::scope_async`] are reported with the current phase as their caller.
+
+use crate::instrument::{CallResult, Function, FunctionTimer};
+use crate::labels::intern;
+use std::fmt;
+use std::future::Future;
+
+/// The phases that a connection goes through, in order.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Phase {
+    /// The connection is being established (for example, the WebSocket upgrade or a TLS handshake).
+    Handshake,
+    /// The connection is open and exchanging messages.
+    Active,
+    /// The connection is being closed.
+    Shutdown,
+}
+
+impl Phase {
+    /// The suffix of the `function` label of the phase.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Phase::Handshake => "handshake",
+            Phase::Active => "active",
+            Phase::Shutdown => "shutdown",
+        }
+    }
+}
+
+impl fmt::Display for Phase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A kind of long-lived connection, whose connections are tracked in phases.
+///
+/// Like [`Function`], this can be defined as a constant and reused for every connection.
+#[derive(Clone, Copy, Debug)]
+pub struct Connection {
+    name: &'static str,
+    module: &'static str,
+}
+
+impl Connection {
+    /// Describe the connections with the given name and module.
+    ///
+    /// Use `module_path!()` for the module to report the connections in the module that handles them.
+    pub const fn new(name: &'static str, module: &'static str) -> Self {
+        Self { name, module }
+    }
+
+    /// Start tracking a new connection, beginning with the [`Phase::Handshake`].
+    pub fn open(&self) -> ConnectionTracker {
+        self.open_in(Phase::Handshake)
+    }
+
+    /// Start tracking a new connection in the given phase, for connections that are already
+    /// established when they are handed to the handler (the handshake is then not recorded).
+    pub fn open_in(&self, phase: Phase) -> ConnectionTracker {
+        let connection = Function::new(self.name, self.module)
+            .track_concurrency()
+            .start();
+        ConnectionTracker {
+            connection: *self,
+            phase,
+            phase_timer: Some(self.phase(phase).start()),
+            timer: Some(connection),
+        }
+    }
+
+    fn phase(&self, phase: Phase) -> Function {
+        let name = intern(&format!("{}.{}", self.name, phase));
+        Function::new(name, self.module)
+    }
+}
+
+/// Tracks a single connection, created with [`Connection::open`].
+///
+/// The connection counts towards the number of open connections until it is closed or dropped.
+#[must_use = "the connection is only tracked until the tracker is dropped"]
+pub struct ConnectionTracker {
+    connection: Connection,
+    phase: Phase,
+    phase_timer: Option<FunctionTimer>,
+    timer: Option<FunctionTimer>,
+}
+
+impl ConnectionTracker {
+    /// The phase that the connection is currently in.
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// Finish the current phase and start the given one.
+    ///
+    /// The current phase is recorded without a `result` label. Entering the phase that
+    /// the connection is already in does nothing.
+    pub fn enter(&mut self, phase: Phase) {
+        if phase == self.phase {
+            return;
+        }
+        if let Some(timer) = self.phase_timer.take() {
+            timer.finish(&());
+        }
+        self.phase = phase;
+        self.phase_timer = Some(self.connection.phase(phase).start());
+    }
+
+    /// Finish the current phase with the given result, and stop tracking the connection.
+    ///
+    /// The result determines the `result` label of both the current phase and the connection,
+    /// so a connection that fails during the handshake is counted as an error.
+    pub fn close(mut self, result: &impl CallResult) {
+        if let Some(timer) = self.phase_timer.take() {
+            timer.finish(result);
+        }
+        if let Some(timer) = self.timer.take() {
+            timer.finish(result);
+        }
+    }
+
+    /// Run the given closure as part of the current phase, so that the functions it calls
+    /// are reported with the phase as their caller.
+    pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        match &self.phase_timer {
+            Some(timer) => timer.scope(f),
+            None => f(),
+        }
+    }
+
+    /// Await the given future as part of the current phase, so that the functions it calls
+    /// are reported with the phase as their caller.
+    pub async fn scope_async<R>(&self, future: impl Future<Output = R>) -> R {
+        match &self.phase_timer {
+            Some(timer) => timer.scope_async(future).await,
+            None => future.await,
+        }
+    }
+}
+
+impl Drop for ConnectionTracker {
+    fn drop(&mut self) {
+        // The timers record the panic themselves when they are dropped
+        if std::thread::panicking() {
+            return;
+        }
+        if let Some(timer) = self.phase_timer.take() {
+            timer.finish(&());
+        }
+        if let Some(timer) = self.timer.take() {
+            timer.finish(&());
+        }
+    }
+}
+
+impl fmt::Debug for ConnectionTracker {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionTracker")
+            .field("connection", &self.connection)
+            .field("phase", &self.phase)
+            .finish_non_exhaustive()
+    }
+}
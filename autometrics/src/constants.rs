@@ -5,19 +5,62 @@ pub const AUTOMETRICS_SPEC_TARGET: &str = "1.0.0";
 pub const COUNTER_NAME: &str = "function.calls";
 pub const HISTOGRAM_NAME: &str = "function.calls.duration";
 pub const GAUGE_NAME: &str = "function.calls.concurrent";
+pub const MAX_CONCURRENCY_NAME: &str = "function.calls.concurrent.max";
+pub const RESPONSE_SIZE_NAME: &str = "function.response.size";
 pub const BUILD_INFO_NAME: &str = "build_info";
+pub const BUILD_AGE_NAME: &str = "build.age";
+pub const CONSUMER_LAG_NAME: &str = "kafka.consumer.lag";
+pub const CALL_EDGES_NAME: &str = "function.call.edges.info";
+pub const CARDINALITY_WARNINGS_NAME: &str = "autometrics.cardinality.warnings";
+pub const LOCK_RECOVERIES_NAME: &str = "autometrics.lock.recoveries";
+pub const FUNCTION_RENAMED_NAME: &str = "function.renamed.info";
+pub const CRATE_CALLS_NAME: &str = "crate.calls";
 
 // Prometheus-flavored metric names
 pub const COUNTER_NAME_PROMETHEUS: &str = "function_calls_total";
 pub const HISTOGRAM_NAME_PROMETHEUS: &str = "function_calls_duration_seconds";
 pub const GAUGE_NAME_PROMETHEUS: &str = "function_calls_concurrent";
+pub const MAX_CONCURRENCY_NAME_PROMETHEUS: &str = "function_calls_concurrent_max";
+pub const RESPONSE_SIZE_NAME_PROMETHEUS: &str = "function_response_size_bytes";
+pub const BUILD_AGE_NAME_PROMETHEUS: &str = "build_age_seconds";
+pub const CONSUMER_LAG_NAME_PROMETHEUS: &str = "kafka_consumer_lag";
+pub const CALL_EDGES_NAME_PROMETHEUS: &str = "function_call_edges_info";
+pub const CARDINALITY_WARNINGS_NAME_PROMETHEUS: &str = "autometrics_cardinality_warnings_total";
+pub const LOCK_RECOVERIES_NAME_PROMETHEUS: &str = "autometrics_lock_recoveries_total";
+pub const FUNCTION_RENAMED_NAME_PROMETHEUS: &str = "function_renamed_info";
+pub const LABEL_SET_EVICTIONS_NAME_PROMETHEUS: &str = "autometrics_label_set_evictions_total";
+pub const CRATE_CALLS_NAME_PROMETHEUS: &str = "crate_calls_total";
+
+// OpenTelemetry semantic convention metric names, used instead of the ones above with `MetricNaming::OpenTelemetry`
+pub const HISTOGRAM_NAME_SEMCONV: &str = "function.duration";
+pub const HISTOGRAM_NAME_SEMCONV_PROMETHEUS: &str = "function_duration_seconds";
 
 // Descriptions
 pub const COUNTER_DESCRIPTION: &str = "Autometrics counter for tracking function calls";
 pub const HISTOGRAM_DESCRIPTION: &str = "Autometrics histogram for tracking function call duration";
 pub const GAUGE_DESCRIPTION: &str = "Autometrics gauge for tracking concurrent function calls";
+pub const MAX_CONCURRENCY_DESCRIPTION: &str =
+    "Autometrics gauge for tracking the maximum number of concurrent function calls since the last scrape";
+pub const RESPONSE_SIZE_DESCRIPTION: &str =
+    "Autometrics histogram for tracking the size of function return values, in bytes";
 pub const BUILD_INFO_DESCRIPTION: &str =
     "Autometrics info metric for tracking software version and build details";
+pub const BUILD_AGE_DESCRIPTION: &str =
+    "Autometrics gauge for tracking the time elapsed since the software was built";
+pub const CONSUMER_LAG_DESCRIPTION: &str =
+    "Autometrics gauge for tracking how many messages a Kafka consumer is behind the end of each partition";
+pub const CALL_EDGES_DESCRIPTION: &str =
+    "Autometrics info metric for tracking which functions call each other";
+pub const CARDINALITY_WARNINGS_DESCRIPTION: &str =
+    "Autometrics counter for tracking the functions that created more series than the configured threshold";
+pub const LOCK_RECOVERIES_DESCRIPTION: &str =
+    "Autometrics counter for tracking the internal locks that were recovered after being poisoned by a panic";
+pub const LABEL_SET_EVICTIONS_DESCRIPTION: &str =
+    "Autometrics counter for tracking the label sets that were removed to keep the number of series bounded";
+pub const FUNCTION_RENAMED_DESCRIPTION: &str =
+    "Autometrics info metric for tracking the previous names of renamed functions";
+pub const CRATE_CALLS_DESCRIPTION: &str =
+    "Autometrics counter for tracking the calls to the instrumented functions of each crate";
 
 // Labels
 pub const FUNCTION_KEY: &str = "function";
@@ -27,8 +70,21 @@ pub const CALLER_FUNCTION_PROMETHEUS: &str = "caller_function";
 pub const CALLER_MODULE_KEY: &str = "caller.module";
 pub const CALLER_MODULE_PROMETHEUS: &str = "caller_module";
 pub const RESULT_KEY: &str = "result";
+pub const RECURSIVE_KEY: &str = "recursive";
 pub const OK_KEY: &str = "ok";
 pub const ERROR_KEY: &str = "error";
+pub const ERROR_TYPE_KEY: &str = "error.type";
+pub const ERROR_TYPE_PROMETHEUS: &str = "error_type";
+pub const LABEL_KEY: &str = "label";
+pub const TYPE_PARAMS_KEY: &str = "type_params";
+pub const PANIC_KEY: &str = "panic";
+pub const PREVIOUS_FUNCTION_KEY: &str = "previous_function";
+pub const COLD_KEY: &str = "cold";
+pub const TOPIC_KEY: &str = "topic";
+pub const LOCK_KEY: &str = "lock";
+pub const CRATE_KEY: &str = "crate";
+pub const TASK_KEY: &str = "task";
+pub const PARTITION_KEY: &str = "partition";
 pub const OBJECTIVE_NAME: &str = "objective.name";
 pub const OBJECTIVE_NAME_PROMETHEUS: &str = "objective_name";
 pub const OBJECTIVE_PERCENTILE: &str = "objective.percentile";
@@ -46,3 +102,10 @@ pub const REPO_PROVIDER_KEY: &str = "repository.provider";
 pub const REPO_PROVIDER_KEY_PROMETHEUS: &str = "repository_provider";
 pub const AUTOMETRICS_VERSION_KEY: &str = "autometrics.version";
 pub const AUTOMETRICS_VERSION_KEY_PROMETHEUS: &str = "autometrics_version";
+pub const BUILD_TIMESTAMP_KEY: &str = "build.timestamp";
+pub const BUILD_TIMESTAMP_KEY_PROMETHEUS: &str = "build_timestamp";
+pub const RUSTC_VERSION_KEY: &str = "rustc.version";
+pub const RUSTC_VERSION_KEY_PROMETHEUS: &str = "rustc_version";
+pub const TARGET_KEY: &str = "target";
+pub const RUNTIME_KEY: &str = "runtime";
+pub const OS_KEY: &str = "os";
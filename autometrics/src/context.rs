@@ -0,0 +1,92 @@
+//! Propagate the caller of instrumented functions into spawned tasks and threads.
+//!
+//! Functions instrumented with the `autometrics` macro are reported with the instrumented function
+//! that called them as their `caller_function` and `caller_module`. The caller is tracked per task
+//! (or per thread), so it is lost when work is moved to a new task, for example with `tokio::spawn`.
+//!
+//! Capture the [`current`] context before spawning the work, and run the work in its [`scope`](CallerContext::scope):
+//!
+//! ```rust
+//! use autometrics::{autometrics, context};
+//!
+//! #[autometrics]
+//! async fn handler() {
+//!     let context = context::current();
+//!     tokio::spawn(context.scope(async {
+//!         // This is reported as being called by `handler`
+//!         send_email().await;
+//!     }));
+//! }
+//!
+//! #[autometrics]
+//! async fn send_email() {}
+//! ```
+//!
+//! With the `tokio` feature, [`spawn`] does both in one step.
+
+use crate::__private::{CallerInfo, CALLER};
+use std::future::Future;
+
+/// The instrumented function that is currently running,
+/// which is reported as the caller of the instrumented functions it calls.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CallerContext {
+    function: &'static str,
+    module: &'static str,
+}
+
+/// The context of the instrumented function that is currently running.
+///
+/// Outside of instrumented functions, the function and module are empty.
+pub fn current() -> CallerContext {
+    let caller = CALLER.get();
+    CallerContext {
+        function: caller.caller_function,
+        module: caller.caller_module,
+    }
+}
+
+impl CallerContext {
+    /// The name of the function.
+    pub fn function(&self) -> &'static str {
+        self.function
+    }
+
+    /// The module of the function.
+    pub fn module(&self) -> &'static str {
+        self.module
+    }
+
+    /// Run the given future in this context, so that the instrumented functions
+    /// it calls are reported with this context's function as their caller.
+    pub fn scope<F: Future>(self, future: F) -> impl Future<Output = F::Output> {
+        CALLER.scope(self.into(), future)
+    }
+
+    /// Run the given closure in this context, for example in a spawned thread.
+    pub fn sync_scope<R>(self, f: impl FnOnce() -> R) -> R {
+        CALLER.sync_scope(self.into(), f)
+    }
+}
+
+impl From<CallerContext> for CallerInfo {
+    fn from(context: CallerContext) -> Self {
+        CallerInfo {
+            caller_function: context.function,
+            caller_module: context.module,
+        }
+    }
+}
+
+/// Spawn the given future on the current Tokio runtime, in the [`current`] context.
+///
+/// This works like [`tokio::spawn`], but the instrumented functions called by the spawned task
+/// are reported with the function that spawned it as their caller.
+#[cfg(feature = "tokio")]
+pub fn spawn<F>(future: F) -> tokio::task::JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    tokio::spawn(current().scope(future))
+}
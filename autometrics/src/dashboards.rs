@@ -0,0 +1,372 @@
+//! Generate [Grafana](https://grafana.com) dashboards for instrumented functions and objectives.
+//!
+//! The panels use the same queries as the function documentation and the [`queries`](crate::queries) module,
+//! so they match the links in the documentation and the alerts generated from the objectives. They are built
+//! when the dashboard is rendered, with the metric name prefix, duration unit, metric naming, and label keys
+//! from the [`settings`](crate::settings).
+//!
+//! A [`Dashboard`] renders as the JSON model of a Grafana dashboard, which can be imported in the Grafana UI
+//! or provisioned from a file. Its panels use a `datasource` variable, so the Prometheus data source
+//! is picked when the dashboard is opened.
+//!
+//! The same dashboards can be generated without running the instrumented code with the
+//! `autometrics generate-dashboard` command of the `autometrics-cli` crate.
+//!
+//! # Example
+//! ```rust
+//! use autometrics::dashboards::Dashboard;
+//! use autometrics::objectives::{Objective, ObjectivePercentile};
+//!
+//! const API_SLO: Objective = Objective::new("api").success_rate(ObjectivePercentile::P99_9);
+//!
+//! let dashboard = Dashboard::new("API")
+//!     .objective(API_SLO)
+//!     .function("create_user")
+//!     .function("get_user");
+//! std::fs::write("api-dashboard.json", dashboard.to_string()).unwrap();
+//! # std::fs::remove_file("api-dashboard.json").unwrap();
+//! ```
+
+use crate::json::JsonObject;
+use crate::objectives::Objective;
+use crate::queries::{self, Sli, BURN_RATE_ALERTS};
+use crate::settings::{get_settings, DurationUnit};
+use std::fmt;
+
+/// The version of the Grafana dashboard schema that the dashboards are generated for
+const SCHEMA_VERSION: u32 = 39;
+/// The width of the Grafana dashboard grid
+const GRID_WIDTH: u32 = 24;
+/// The height of the panels, in grid units
+const PANEL_HEIGHT: u32 = 8;
+
+/// A Grafana dashboard with a row of panels for each objective and function.
+///
+/// The [`Display`](fmt::Display) implementation renders the JSON model of the dashboard.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dashboard {
+    title: String,
+    objectives: Vec<Objective>,
+    functions: Vec<String>,
+}
+
+impl Dashboard {
+    /// An empty dashboard with the given title.
+    pub fn new(title: impl Into<String>) -> Self {
+        Dashboard {
+            title: title.into(),
+            objectives: Vec::new(),
+            functions: Vec::new(),
+        }
+    }
+
+    /// Add a row with the request rate, error ratio, and latency of the function with the given name
+    /// (as it appears in the `function` label, like `Database::load`).
+    pub fn function(mut self, name: impl Into<String>) -> Self {
+        self.functions.push(name.into());
+        self
+    }
+
+    /// Add a row with the burn rate of the error budget of the objective, with a panel for each of its targets.
+    ///
+    /// The objectives are shown above the functions.
+    pub fn objective(mut self, objective: Objective) -> Self {
+        self.objectives.push(objective);
+        self
+    }
+
+    fn rows(&self) -> Vec<Row> {
+        let objectives = self.objectives.iter().map(|objective| {
+            let queries = queries::objective(*objective);
+            let panels = [
+                (Sli::SuccessRate, "Success rate burn rate"),
+                (Sli::Latency, "Latency burn rate"),
+                (Sli::Composite, "Success rate and latency burn rate"),
+            ]
+            .into_iter()
+            .filter_map(|(sli, title)| {
+                // Show the burn rate over the long windows of the alerts
+                let targets = BURN_RATE_ALERTS
+                    .iter()
+                    .flat_map(|(_, windows)| windows.iter())
+                    .map(|(window, _, _)| {
+                        Some(Target {
+                            expr: queries.burn_rate(sli, window)?,
+                            legend: format!("{window} window"),
+                        })
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some(Panel {
+                    title,
+                    unit: "none",
+                    targets,
+                })
+            })
+            .collect();
+            Row {
+                title: format!("Objective: {}", objective.name),
+                panels,
+            }
+        });
+
+        let latency_unit = match get_settings().duration_unit {
+            DurationUnit::Seconds => "s",
+            DurationUnit::Milliseconds => "ms",
+        };
+        let functions = self.functions.iter().map(|function| {
+            let queries = queries::function(function.as_str());
+            Row {
+                title: format!("Function: {function}"),
+                panels: vec![
+                    Panel {
+                        title: "Request rate",
+                        unit: "reqps",
+                        targets: vec![Target::auto(queries.request_rate())],
+                    },
+                    Panel {
+                        title: "Error ratio",
+                        unit: "percentunit",
+                        targets: vec![Target::auto(queries.error_ratio())],
+                    },
+                    Panel {
+                        title: "Latency (95th and 99th percentile)",
+                        unit: latency_unit,
+                        targets: vec![Target {
+                            expr: queries.latency(),
+                            legend: "p{{percentile_latency}}".to_string(),
+                        }],
+                    },
+                ],
+            }
+        });
+
+        objectives.chain(functions).collect()
+    }
+}
+
+/// A dashboard with a row for each of the functions instrumented with the `autometrics` macro,
+/// listed by [`registry::instrumented_functions`](crate::registry::instrumented_functions).
+///
+/// This is available in debug builds, or in release builds with the `function-registry` feature.
+#[cfg(function_registry)]
+pub fn instrumented_functions(title: impl Into<String>) -> Dashboard {
+    let mut functions: Vec<_> = crate::registry::instrumented_functions()
+        .map(|function| function.name)
+        .collect();
+    functions.sort_unstable();
+    functions.dedup();
+    functions
+        .into_iter()
+        .fold(Dashboard::new(title), Dashboard::function)
+}
+
+/// A dashboard with the burn rates of the objective, titled after the objective.
+///
+/// In debug builds, or in release builds with the `function-registry` feature,
+/// the dashboard also has a row for each of the instrumented functions that are part of the objective.
+pub fn objective(objective: Objective) -> Dashboard {
+    let dashboard = Dashboard::new(format!("Objective: {}", objective.name)).objective(objective);
+
+    #[cfg(function_registry)]
+    let dashboard = {
+        let mut functions: Vec<_> = crate::registry::instrumented_functions()
+            .filter(|function| {
+                function
+                    .objective
+                    .is_some_and(|function_objective| function_objective.name == objective.name)
+            })
+            .map(|function| function.name)
+            .collect();
+        functions.sort_unstable();
+        functions.dedup();
+        functions.into_iter().fold(dashboard, Dashboard::function)
+    };
+
+    dashboard
+}
+
+impl fmt::Display for Dashboard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let rows = self.rows();
+        let mut dashboard = JsonObject::new(f)?;
+        dashboard.field("title", &self.title)?;
+        dashboard.raw_field("tags", "[\"autometrics\"]")?;
+        dashboard.raw_field("editable", true)?;
+        dashboard.raw_field("schemaVersion", SCHEMA_VERSION)?;
+        dashboard.raw_field(
+            "time",
+            json(|f| {
+                let mut time = JsonObject::new(f)?;
+                time.field("from", "now-6h")?;
+                time.field("to", "now")?;
+                time.finish()
+            }),
+        )?;
+        dashboard.raw_field(
+            "templating",
+            json(|f| {
+                let mut templating = JsonObject::new(f)?;
+                templating.raw_field(
+                    "list",
+                    json(|f| {
+                        f.write_str("[")?;
+                        let mut variable = JsonObject::new(f)?;
+                        variable.field("name", "datasource")?;
+                        variable.field("label", "Data source")?;
+                        variable.field("type", "datasource")?;
+                        variable.field("query", "prometheus")?;
+                        variable.finish()?;
+                        f.write_str("]")
+                    }),
+                )?;
+                templating.finish()
+            }),
+        )?;
+        dashboard.raw_field(
+            "panels",
+            json(|f| {
+                // Each row is a collapsible row panel followed by its panels, side by side
+                let mut id = 0;
+                let mut y = 0;
+                f.write_str("[")?;
+                for (index, row) in rows.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(",")?;
+                    }
+                    id += 1;
+                    let mut row_panel = JsonObject::new(f)?;
+                    row_panel.raw_field("id", id)?;
+                    row_panel.field("type", "row")?;
+                    row_panel.field("title", &row.title)?;
+                    row_panel.raw_field("collapsed", false)?;
+                    row_panel.raw_field("gridPos", GridPos(0, y, GRID_WIDTH, 1))?;
+                    row_panel.raw_field("panels", "[]")?;
+                    row_panel.finish()?;
+                    y += 1;
+
+                    let width = GRID_WIDTH / row.panels.len().max(1) as u32;
+                    for (column, panel) in row.panels.iter().enumerate() {
+                        id += 1;
+                        f.write_str(",")?;
+                        panel.write(
+                            f,
+                            id,
+                            GridPos(column as u32 * width, y, width, PANEL_HEIGHT),
+                        )?;
+                    }
+                    y += PANEL_HEIGHT;
+                }
+                f.write_str("]")
+            }),
+        )?;
+        dashboard.finish()
+    }
+}
+
+/// A row of panels
+struct Row {
+    title: String,
+    panels: Vec<Panel>,
+}
+
+/// A time series panel
+struct Panel {
+    title: &'static str,
+    unit: &'static str,
+    targets: Vec<Target>,
+}
+
+/// A query of a panel
+struct Target {
+    expr: String,
+    legend: String,
+}
+
+impl Target {
+    /// A query whose series are named after their labels
+    fn auto(expr: String) -> Self {
+        Target {
+            expr,
+            legend: "__auto".to_string(),
+        }
+    }
+}
+
+impl Panel {
+    fn write(&self, f: &mut fmt::Formatter<'_>, id: u32, grid_pos: GridPos) -> fmt::Result {
+        let mut panel = JsonObject::new(f)?;
+        panel.raw_field("id", id)?;
+        panel.field("type", "timeseries")?;
+        panel.field("title", self.title)?;
+        panel.raw_field("gridPos", grid_pos)?;
+        panel.raw_field("datasource", json(write_datasource))?;
+        panel.raw_field(
+            "fieldConfig",
+            json(|f| {
+                let mut field_config = JsonObject::new(f)?;
+                field_config.raw_field(
+                    "defaults",
+                    json(|f| {
+                        let mut defaults = JsonObject::new(f)?;
+                        defaults.field("unit", self.unit)?;
+                        defaults.finish()
+                    }),
+                )?;
+                field_config.raw_field("overrides", "[]")?;
+                field_config.finish()
+            }),
+        )?;
+        panel.raw_field(
+            "targets",
+            json(|f| {
+                f.write_str("[")?;
+                for (index, target) in self.targets.iter().enumerate() {
+                    if index > 0 {
+                        f.write_str(",")?;
+                    }
+                    // The queries are named A, B, C, and so on
+                    let ref_id = char::from(b'A' + index as u8).to_string();
+                    let mut query = JsonObject::new(f)?;
+                    query.field("refId", &ref_id)?;
+                    query.raw_field("datasource", json(write_datasource))?;
+                    query.field("expr", &target.expr)?;
+                    query.field("legendFormat", &target.legend)?;
+                    query.finish()?;
+                }
+                f.write_str("]")
+            }),
+        )?;
+        panel.finish()
+    }
+}
+
+/// The Prometheus data source picked with the `datasource` variable of the dashboard
+fn write_datasource(f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let mut datasource = JsonObject::new(f)?;
+    datasource.field("type", "prometheus")?;
+    datasource.field("uid", "${datasource}")?;
+    datasource.finish()
+}
+
+/// The position and size of a panel: x, y, width, and height
+struct GridPos(u32, u32, u32, u32);
+
+impl fmt::Display for GridPos {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let GridPos(x, y, w, h) = *self;
+        write!(f, "{{\"h\":{h},\"w\":{w},\"x\":{x},\"y\":{y}}}")
+    }
+}
+
+/// Writes the JSON value with the closure, so it can be nested in a [`JsonObject`]
+struct Json<F>(F);
+
+fn json<F: Fn(&mut fmt::Formatter<'_>) -> fmt::Result>(write: F) -> Json<F> {
+    Json(write)
+}
+
+impl<F: Fn(&mut fmt::Formatter<'_>) -> fmt::Result> fmt::Display for Json<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (self.0)(f)
+    }
+}
@@ -0,0 +1,98 @@
+//! Label errors by the class of their root cause.
+//!
+//! Functions often return errors that wrap other errors, like a `ServiceError::Internal` that was caused by
+//! an `io::Error` or a database timeout. The name of the wrapper says little about what went wrong,
+//! so the [`ErrorClassifier`] walks the chain of [`source`](std::error::Error::source)s of the error
+//! and uses the class of the first error that it recognizes, starting from the root cause,
+//! as the value of the `error` label of the `function.calls` counter.
+//!
+//! # Example
+//! ```rust
+//! use autometrics::error_classifier::ErrorClassifier;
+//! use autometrics::settings::AutometricsSettings;
+//!
+//! AutometricsSettings::builder()
+//!     .error_classifier(
+//!         ErrorClassifier::new()
+//!             .class::<std::io::Error>("io")
+//!             .class::<std::num::ParseIntError>("parse")
+//!             // Classify errors that cannot be recognized by their type
+//!             .with(|error| error.to_string().contains("timed out").then_some("timeout")),
+//!     )
+//!     .init();
+//! ```
+//!
+//! The classifier is applied to the errors returned by functions instrumented with the `autometrics` macro
+//! whose error type implements [`std::error::Error`] (or is a `Box<dyn Error>`). When none of the errors
+//! in the chain is recognized, the `error` label is the same as without a classifier.
+//!
+//! The classes should be a small, fixed set of values, because each one creates a new series.
+
+use crate::settings::get_settings;
+use std::any::type_name;
+use std::error::Error;
+use std::fmt;
+
+type Classify = Box<dyn Fn(&(dyn Error + 'static)) -> Option<&'static str> + Send + Sync>;
+
+/// The configuration of the error classification, which is set with
+/// [`AutometricsSettingsBuilder::error_classifier`](crate::settings::AutometricsSettingsBuilder::error_classifier).
+#[derive(Default)]
+pub struct ErrorClassifier {
+    classifiers: Vec<Classify>,
+    /// The names of the classes, for the `Debug` output
+    classes: Vec<String>,
+}
+
+impl ErrorClassifier {
+    /// A classifier that does not recognize any errors.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Classify the errors of type `E` as `class`.
+    pub fn class<E: Error + 'static>(mut self, class: &'static str) -> Self {
+        self.classifiers
+            .push(Box::new(move |error| error.is::<E>().then_some(class)));
+        self.classes.push(format!("{}: {class}", type_name::<E>()));
+        self
+    }
+
+    /// Classify the errors for which the given function returns a class.
+    ///
+    /// The classifiers are tried in the order they were added, so this can also
+    /// be used to classify the errors of a type by their contents.
+    pub fn with(
+        mut self,
+        classify: impl Fn(&(dyn Error + 'static)) -> Option<&'static str> + Send + Sync + 'static,
+    ) -> Self {
+        self.classifiers.push(Box::new(classify));
+        self.classes.push("<fn>".to_string());
+        self
+    }
+
+    /// The class of the root-most error in the chain of sources that any of the classifiers recognizes.
+    pub fn classify(&self, error: &(dyn Error + 'static)) -> Option<&'static str> {
+        let mut chain = vec![error];
+        while let Some(source) = chain[chain.len() - 1].source() {
+            chain.push(source);
+        }
+        chain
+            .into_iter()
+            .rev()
+            .find_map(|error| self.classifiers.iter().find_map(|classify| classify(error)))
+    }
+}
+
+impl fmt::Debug for ErrorClassifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ErrorClassifier")
+            .field("classes", &self.classes)
+            .finish()
+    }
+}
+
+/// Classify the error returned by an instrumented function, if a classifier is configured
+pub(crate) fn classify(error: &(dyn Error + 'static)) -> Option<&'static str> {
+    get_settings().error_classifier.as_ref()?.classify(error)
+}
@@ -17,13 +17,17 @@
 //! To enable Prometheus to scrape metrics with exemplars you must:
 //! 1. Run Prometheus with the [`--enable-feature=exemplar-storage`](https://prometheus.io/docs/prometheus/latest/feature_flags/#exemplars-storage) flag
 //! 2. Export the metrics to Prometheus using the provided [`prometheus_exporter::encode_http_response`] or
-//!   make sure to manually set the `Content-Type` header to indicate it is using the the OpenMetrics format,
-//!   rather than the default Prometheus format:
-//!   ```http
-//!   Content-Type: application/openmetrics-text; version=1.0.0; charset=utf-8
-//!   ```
+//!    make sure to manually set the `Content-Type` header to indicate it is using the the OpenMetrics format,
+//!    rather than the default Prometheus format:
+//!    ```http
+//!    Content-Type: application/openmetrics-text; version=1.0.0; charset=utf-8
+//!    ```
+//!
+//! With the `prometheus-exporter` feature, [`prometheus_exporter::openmetrics_with_exemplars_guard`] checks
+//! these settings at startup, along with the histogram buckets, and returns the content type to use.
 //!
 //! [`prometheus_exporter::encode_http_response`]: crate::prometheus_exporter::encode_http_response
+//! [`prometheus_exporter::openmetrics_with_exemplars_guard`]: crate::prometheus_exporter::openmetrics_with_exemplars_guard
 //!
 //! # Tracing libraries
 //!
@@ -43,12 +47,63 @@
 //!
 //! See the `exemplars-tracing-opentelemetry` example for usage details.
 //!
+//! ## Other tracing libraries
+//!
+//! With the `exemplars-custom` feature, you can implement the [`ExemplarProvider`] trait to attach the
+//! trace details from any other source, such as `minitrace`, `rustracing`, or a request ID set by your
+//! own middleware, and set it with
+//! [`AutometricsSettingsBuilder::exemplar_provider`](crate::settings::AutometricsSettingsBuilder::exemplar_provider):
+//!
+//! ```rust
+//! # #[cfg(feature = "exemplars-custom")] {
+//! use autometrics::settings::AutometricsSettings;
+//!
+//! thread_local! {
+//!     static REQUEST_ID: std::cell::RefCell<Option<String>> = Default::default();
+//! }
+//!
+//! AutometricsSettings::builder()
+//!     .exemplar_provider(|| {
+//!         REQUEST_ID.with(|id| id.borrow().clone().map(|id| vec![("trace_id", id)]))
+//!     })
+//!     .init();
+//! # }
+//! ```
+//!
+//! The provider takes precedence over the `exemplars-tracing` and `exemplars-tracing-opentelemetry` features,
+//! which are only used when it does not return an exemplar.
+//!
+//! # Testing exemplar pipelines
+//!
+//! With the `exemplars-testing` feature, [`set_override`] makes the next recordings carry the given exemplar,
+//! so that integration tests can check the exemplars in the OpenMetrics output without setting up a tracing subscriber:
+//!
+//! ```rust
+//! # #[cfg(feature = "exemplars-testing")] {
+//! use autometrics::{autometrics, exemplars, prometheus_exporter};
+//!
+//! #[autometrics]
+//! fn handler() {}
+//!
+//! prometheus_exporter::init();
+//! exemplars::set_override([("trace_id", "4bf92f3577b34da6a3ce929d0e0e4736")], 1);
+//! handler();
+//!
+//! let metrics = prometheus_exporter::encode_to_string().unwrap();
+//! assert!(metrics.contains(r#"# {trace_id="4bf92f3577b34da6a3ce929d0e0e4736"}"#));
+//! # }
+//! ```
+//!
 //! [`tracing_opentelemetry::OpenTelemetryLayer`]: https://docs.rs/tracing-opentelemetry/latest/tracing_opentelemetry/struct.OpenTelemetryLayer.html
 //! [`opentelemetry::Context`]: https://docs.rs/opentelemetry/latest/opentelemetry/struct.Context.html
 //! [`tracing::Span`]: https://docs.rs/tracing/latest/tracing/struct.Span.html
 //! [`tracing::instrument`]: https://docs.rs/tracing/latest/tracing/attr.instrument.html
 
+#[cfg(exemplars_testing)]
+use once_cell::sync::Lazy;
 use std::collections::HashMap;
+#[cfg(exemplars_testing)]
+use std::sync::Mutex;
 
 #[cfg(exemplars_tracing)]
 pub mod tracing;
@@ -61,8 +116,87 @@ compile_error!("Only one of the exemplars-tracing and exemplars-tracing-opentele
 #[cfg(not(prometheus_client))]
 compile_error!("Exemplars can only be used with the `prometheus-client` metrics library because that is the only one that currently supports producing metrics with exemplars");
 
+/// Provides the exemplar for the current call to an instrumented function.
+///
+/// This is implemented for closures that return the exemplar labels. See the [module docs](self) for an example.
+#[cfg(exemplars_custom)]
+pub trait ExemplarProvider: Send + Sync {
+    /// The labels of the exemplar, such as the `trace_id` and `span_id` of the current span,
+    /// or `None` if the call is not part of a trace.
+    fn exemplar(&self) -> Option<Vec<(&'static str, String)>>;
+}
+
+#[cfg(exemplars_custom)]
+impl<F> ExemplarProvider for F
+where
+    F: Fn() -> Option<Vec<(&'static str, String)>> + Send + Sync,
+{
+    fn exemplar(&self) -> Option<Vec<(&'static str, String)>> {
+        self()
+    }
+}
+
+/// The exemplar set with [`set_override`], and the number of recordings that still carry it
+#[cfg(exemplars_testing)]
+static OVERRIDE: Lazy<Mutex<Option<(TraceLabels, usize)>>> = Lazy::new(Default::default);
+
+/// Attach the given exemplar labels to the next `recordings` calls that are recorded, instead of the ones
+/// from the tracing libraries or the [`ExemplarProvider`].
+///
+/// This is meant for testing the exemplars in the exported metrics end-to-end. The override applies to the calls
+/// recorded on any thread, so tests that rely on it should not record calls concurrently. Setting another override
+/// replaces the previous one, and setting `recordings` to 0 removes it.
+///
+/// This requires the `exemplars-testing` feature.
+#[cfg(exemplars_testing)]
+pub fn set_override<V: Into<String>>(
+    labels: impl IntoIterator<Item = (&'static str, V)>,
+    recordings: usize,
+) {
+    let labels = labels
+        .into_iter()
+        .map(|(key, value)| (key, value.into()))
+        .collect();
+    *crate::poison::lock(&OVERRIDE, "exemplar_override") =
+        (recordings > 0).then_some((labels, recordings));
+}
+
+/// Remove the exemplar set with [`set_override`], if it was not used up yet.
+#[cfg(exemplars_testing)]
+pub fn clear_override() {
+    crate::poison::lock(&OVERRIDE, "exemplar_override").take();
+}
+
+/// The exemplar for recording the current call in the metrics, which uses up one of the recordings of the override
+pub(crate) fn take_exemplar() -> Option<TraceLabels> {
+    #[cfg(exemplars_testing)]
+    {
+        let mut exemplar_override = crate::poison::lock(&OVERRIDE, "exemplar_override");
+        if let Some((labels, recordings)) = exemplar_override.take() {
+            if recordings > 1 {
+                *exemplar_override = Some((labels.clone(), recordings - 1));
+            }
+            return Some(labels);
+        }
+    }
+
+    get_exemplar()
+}
+
 pub(crate) type TraceLabels = HashMap<&'static str, String>;
 pub(crate) fn get_exemplar() -> Option<TraceLabels> {
+    #[cfg(exemplars_testing)]
+    if let Some((labels, _)) = &*crate::poison::lock(&OVERRIDE, "exemplar_override") {
+        return Some(labels.clone());
+    }
+
+    #[cfg(exemplars_custom)]
+    if let Some(provider) = &crate::settings::get_settings().exemplar_provider {
+        if let Some(exemplar) = provider.0.exemplar() {
+            return Some(exemplar.into_iter().collect());
+        }
+    }
+
     #[cfg(exemplars_tracing_opentelemetry)]
     {
         tracing_opentelemetry::get_exemplar()
@@ -71,4 +205,8 @@ pub(crate) fn get_exemplar() -> Option<TraceLabels> {
     {
         tracing::get_exemplar()
     }
+    #[cfg(not(any(exemplars_tracing, exemplars_tracing_opentelemetry)))]
+    {
+        None
+    }
 }
@@ -0,0 +1,120 @@
+//! Record the calls of non-Rust code in the same process, through a C interface.
+//!
+//! Components written in C or C++ that are linked into a Rust application (or loaded by it as plugins)
+//! can record their calls with [`autometrics_record_call`]. The calls are recorded in the same registry as
+//! the instrumented Rust functions, with the same settings, and are exported on the same endpoint, so
+//! the same queries, dashboards, and alerts work for both.
+//!
+//! The functions in this module are exported with unmangled names, and only use C types, so a header
+//! can be generated for them with [cbindgen](https://github.com/mozilla/cbindgen) or written by hand:
+//!
+//! ```c
+//! #include <stdbool.h>
+//! #include <stdint.h>
+//!
+//! #define AUTOMETRICS_OUTCOME_NONE 0
+//! #define AUTOMETRICS_OUTCOME_OK 1
+//! #define AUTOMETRICS_OUTCOME_ERROR 2
+//!
+//! bool autometrics_record_call(const char *function, const char *module, uint64_t duration_ns, int outcome);
+//! ```
+//!
+//! The C code measures the duration of the call itself and records it once the call is finished:
+//!
+//! ```c
+//! struct timespec start, end;
+//! clock_gettime(CLOCK_MONOTONIC, &start);
+//! int status = decode_frame(buffer, length);
+//! clock_gettime(CLOCK_MONOTONIC, &end);
+//!
+//! uint64_t duration_ns = (end.tv_sec - start.tv_sec) * 1000000000 + (end.tv_nsec - start.tv_nsec);
+//! autometrics_record_call("decode_frame", "codec", duration_ns,
+//!                         status == 0 ? AUTOMETRICS_OUTCOME_OK : AUTOMETRICS_OUTCOME_ERROR);
+//! ```
+//!
+//! The settings and the exporter are initialized by the Rust application as usual, before the C code records any calls.
+//!
+//! # Limitations
+//!
+//! - The calls are only recorded in the function call counter and the latency histogram. They are not
+//!   reported with a `caller_function`, and they are not sent to the other sinks of the instrumented
+//!   functions (such as wide events, span traces, or streams).
+//! - The calls cannot be part of an objective, and the concurrent calls are not tracked.
+//! - The function and module names are copied the first time they are seen and kept for the rest of the program,
+//!   like the label values of [plugins](crate::plugin), so they should not contain unbounded values like IDs.
+
+use crate::__private::AutometricsTracker;
+use crate::constants::{ERROR_KEY, OK_KEY};
+use crate::labels::{intern, CounterLabels, HistogramLabels};
+use crate::settings::get_settings;
+use std::ffi::{c_char, c_int, CStr};
+use std::time::Duration;
+
+/// The call does not have a result, like an instrumented function that does not return a `Result`.
+pub const AUTOMETRICS_OUTCOME_NONE: c_int = 0;
+/// The call succeeded, and is recorded with `result="ok"`.
+pub const AUTOMETRICS_OUTCOME_OK: c_int = 1;
+/// The call failed, and is recorded with `result="error"`.
+pub const AUTOMETRICS_OUTCOME_ERROR: c_int = 2;
+
+/// Record a call to the function with the given name and module that took `duration_ns` nanoseconds,
+/// with one of the `AUTOMETRICS_OUTCOME_*` outcomes.
+///
+/// Returns `false` without recording anything if the function or module is null or not valid UTF-8,
+/// or if the outcome is not one of the known values.
+///
+/// # Safety
+///
+/// The function and module must either be null or point to null-terminated strings
+/// that are valid for the duration of the call.
+#[no_mangle]
+pub unsafe extern "C" fn autometrics_record_call(
+    function: *const c_char,
+    module: *const c_char,
+    duration_ns: u64,
+    outcome: c_int,
+) -> bool {
+    // SAFETY: the caller guarantees that the pointers are null or point to valid strings
+    let (Some(function), Some(module)) = (unsafe { to_str(function) }, unsafe { to_str(module) })
+    else {
+        return false;
+    };
+    let result = match outcome {
+        AUTOMETRICS_OUTCOME_NONE => None,
+        AUTOMETRICS_OUTCOME_OK => Some((OK_KEY, None)),
+        AUTOMETRICS_OUTCOME_ERROR => Some((ERROR_KEY, None)),
+        _ => return false,
+    };
+
+    // Like the calls of the instrumented functions, the calls are not recorded while recording is paused
+    if crate::tracker::is_paused() {
+        return true;
+    }
+
+    let settings = get_settings();
+    let function = intern(function);
+    let module = intern(module);
+    let counter_labels = CounterLabels::new(settings, function, module, "", "", result, None);
+    let histogram_labels = HistogramLabels::new(settings, function, module, None);
+    crate::cardinality::observe(&counter_labels);
+    AutometricsTracker::record_replayed(
+        &counter_labels,
+        &histogram_labels,
+        1,
+        Duration::from_nanos(duration_ns),
+    );
+    true
+}
+
+/// Borrow the null-terminated string, if the pointer is not null and the string is valid UTF-8
+///
+/// # Safety
+///
+/// The pointer must either be null or point to a null-terminated string that outlives the returned value.
+unsafe fn to_str<'a>(value: *const c_char) -> Option<&'a str> {
+    if value.is_null() {
+        return None;
+    }
+    // SAFETY: the pointer is not null, and the caller guarantees that it points to a valid string
+    unsafe { CStr::from_ptr(value) }.to_str().ok()
+}
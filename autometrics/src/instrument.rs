@@ -0,0 +1,302 @@
+//! Instrument functions without the `autometrics` macro.
+//!
+//! This is useful for code that the macro cannot be applied to, such as code generated by other tools
+//! or handlers that are dispatched dynamically. The metrics have the same names and labels as
+//! the ones produced by the macro, and the functions instrumented with the macro that are called
+//! within a tracked call are reported with it as their caller.
+//!
+//! # Example
+//! ```rust
+//! use autometrics::instrument::{track_call, Function};
+//! use autometrics::objectives::*;
+//!
+//! const API_SLO: Objective = Objective::new("api")
+//!     .success_rate(ObjectivePercentile::P99_9);
+//!
+//! // Describe the function once and reuse it for every call
+//! const GET_USER: Function = Function::new("get_user", module_path!())
+//!     .objective(API_SLO)
+//!     .track_concurrency();
+//!
+//! fn get_user(id: u64) -> Result<String, std::io::Error> {
+//!     GET_USER.call(|| Ok(format!("User {id}")))
+//! }
+//!
+//! // Or track a single call
+//! let user = track_call("load_user", module_path!(), || get_user(1));
+//! ```
+//!
+//! Futures and closures that are defined inline, like background jobs passed to `tokio::spawn`,
+//! can be wrapped with the [`wrap_future!`](crate::wrap_future) and [`wrap_closure!`](crate::wrap_closure)
+//! macros. They are tracked as calls to a function with the given name in the current module,
+//! and the function that wrapped them is reported as their caller:
+//! ```rust,ignore
+//! tokio::spawn(autometrics::wrap_future!("refresh_cache", async move {
+//!     cache.refresh().await
+//! }));
+//!
+//! std::thread::spawn(autometrics::wrap_closure!("compact_database", move || db.compact()));
+//! ```
+//!
+//! The `build_info` metric is only set by functions instrumented with the macro,
+//! because it is based on the compile-time environment of the instrumented crate.
+
+use crate::__private::{
+    AutometricsTracker, CallerInfo, CounterLabels, GaugeLabels, HistogramLabels,
+    ResultAndReturnTypeLabels, TrackMetrics, ERROR_KEY, OK_KEY,
+};
+use crate::objectives::Objective;
+use crate::settings::get_settings;
+use std::future::Future;
+
+/// Determines the `result` label of a tracked call from its return value.
+///
+/// This is implemented for [`Result`], which is counted as `ok` or `error` like in the `autometrics` macro,
+/// and for `()` and [`Option`], which do not have a `result` label.
+/// You can implement it for your own types, for example to count certain responses as errors.
+pub trait CallResult {
+    /// The value of the `result` label (`"ok"` or `"error"`) and, optionally, the value of the `ok` or `error` label.
+    fn result_labels(&self) -> Option<ResultAndReturnTypeLabels>;
+}
+
+impl<T, E> CallResult for Result<T, E> {
+    fn result_labels(&self) -> Option<ResultAndReturnTypeLabels> {
+        match self {
+            Ok(_) => Some((OK_KEY, None)),
+            Err(_) => Some((ERROR_KEY, None)),
+        }
+    }
+}
+
+impl<T> CallResult for Option<T> {
+    fn result_labels(&self) -> Option<ResultAndReturnTypeLabels> {
+        None
+    }
+}
+
+impl CallResult for () {
+    fn result_labels(&self) -> Option<ResultAndReturnTypeLabels> {
+        None
+    }
+}
+
+/// A function that is instrumented without the `autometrics` macro.
+///
+/// This takes the same options as the macro and can be defined as a constant.
+#[derive(Clone, Copy, Debug)]
+pub struct Function {
+    name: &'static str,
+    module: &'static str,
+    objective: Option<Objective>,
+    track_concurrency: bool,
+}
+
+impl Function {
+    /// Describe the function with the given name and module.
+    ///
+    /// Use `module_path!()` for the module to get the same labels as the `autometrics` macro.
+    pub const fn new(name: &'static str, module: &'static str) -> Self {
+        Self {
+            name,
+            module,
+            objective: None,
+            track_concurrency: false,
+        }
+    }
+
+    /// Include the metrics of the function in the given [`Objective`].
+    pub const fn objective(mut self, objective: Objective) -> Self {
+        self.objective = Some(objective);
+        self
+    }
+
+    /// Track the number of concurrent calls to the function.
+    pub const fn track_concurrency(mut self) -> Self {
+        self.track_concurrency = true;
+        self
+    }
+
+    /// Start timing a call to the function.
+    ///
+    /// The call is recorded when [`FunctionTimer::finish`] is called.
+    pub fn start(&self) -> FunctionTimer {
+        let settings = get_settings();
+        let caller = CallerInfo::current(settings);
+        // Like in the macro, only the top-level call of a recursive function counts towards the concurrency gauge
+        let gauge_labels = (self.track_concurrency && !caller.is_function(self.name, self.module))
+            .then(|| GaugeLabels::new(settings, self.name, self.module));
+
+        FunctionTimer {
+            function: *self,
+            caller,
+            tracker: Some(AutometricsTracker::start(gauge_labels.as_ref())),
+        }
+    }
+
+    /// Call the given closure and track it as a call to the function.
+    pub fn call<R: CallResult>(&self, f: impl FnOnce() -> R) -> R {
+        let timer = self.start();
+        let result = timer.scope(f);
+        timer.finish(&result);
+        result
+    }
+
+    /// Await the given future and track it as a call to the function.
+    pub async fn call_async<R: CallResult>(&self, future: impl Future<Output = R>) -> R {
+        let timer = self.start();
+        let result = timer.scope_async(future).await;
+        timer.finish(&result);
+        result
+    }
+
+    /// Wrap the given future so that awaiting it is tracked as a call to the function.
+    ///
+    /// The call is timed from when the future is first polled, and the function that is running
+    /// when the future is wrapped (not the one that awaits it) is reported as its caller.
+    /// This makes it possible to track futures that are spawned onto another task.
+    pub fn wrap_future<F>(self, future: F) -> impl Future<Output = F::Output>
+    where
+        F: Future,
+        F::Output: CallResult,
+    {
+        let settings = get_settings();
+        CallerInfo::current(settings).scope(settings, async move { self.call_async(future).await })
+    }
+
+    /// Wrap the given closure so that calling it is tracked as a call to the function.
+    ///
+    /// Like with [`Function::wrap_future`], the function that is running when the closure is wrapped
+    /// is reported as its caller, even if the closure is called on another thread.
+    pub fn wrap_closure<R: CallResult>(self, f: impl FnOnce() -> R) -> impl FnOnce() -> R {
+        let settings = get_settings();
+        let caller = CallerInfo::current(settings);
+        move || caller.sync_scope(settings, || self.call(f))
+    }
+}
+
+/// Times a single call to a [`Function`].
+///
+/// If the timer is dropped without being finished while the thread is panicking,
+/// the call is recorded as an error with the `panic="true"` label.
+#[must_use = "the call is only recorded when the timer is finished"]
+pub struct FunctionTimer {
+    function: Function,
+    caller: CallerInfo,
+    tracker: Option<AutometricsTracker>,
+}
+
+impl FunctionTimer {
+    /// Run the given closure as part of the call, so that the functions it calls
+    /// are reported with this function as their caller.
+    pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        self.callee().sync_scope(get_settings(), f)
+    }
+
+    /// Await the given future as part of the call, so that the functions it calls
+    /// are reported with this function as their caller.
+    pub async fn scope_async<R>(&self, future: impl Future<Output = R>) -> R {
+        self.callee().scope(get_settings(), future).await
+    }
+
+    /// Record the call, using the return value to determine the `result` label.
+    pub fn finish(mut self, result: &impl CallResult) {
+        self.record(result.result_labels(), false);
+    }
+
+    fn record(&mut self, result: Option<ResultAndReturnTypeLabels>, panicked: bool) {
+        let Some(tracker) = self.tracker.take() else {
+            return;
+        };
+        let Function {
+            name,
+            module,
+            objective,
+            ..
+        } = self.function;
+        let settings = get_settings();
+        let mut counter_labels = CounterLabels::new(
+            settings,
+            name,
+            module,
+            self.caller.caller_function,
+            self.caller.caller_module,
+            result,
+            objective,
+        );
+        if panicked {
+            counter_labels = counter_labels.with_panic();
+        }
+        let histogram_labels = HistogramLabels::new(settings, name, module, objective);
+        tracker.finish(&counter_labels, &histogram_labels);
+    }
+
+    fn callee(&self) -> CallerInfo {
+        CallerInfo {
+            caller_function: self.function.name,
+            caller_module: self.function.module,
+        }
+    }
+}
+
+impl Drop for FunctionTimer {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            self.record(None, true);
+        }
+    }
+}
+
+/// Call the given closure and track it as a call to the function with the given name and module.
+pub fn track_call<R: CallResult>(
+    function: &'static str,
+    module: &'static str,
+    f: impl FnOnce() -> R,
+) -> R {
+    Function::new(function, module).call(f)
+}
+
+/// Await the given future and track it as a call to the function with the given name and module.
+pub async fn track_call_async<R: CallResult>(
+    function: &'static str,
+    module: &'static str,
+    future: impl Future<Output = R>,
+) -> R {
+    Function::new(function, module).call_async(future).await
+}
+
+/// Wrap a future so that awaiting it is tracked as a call to the function with the given name
+/// in the current module.
+///
+/// This is a shorthand for [`Function::wrap_future`].
+///
+/// # Example
+/// ```rust
+/// # async fn example() {
+/// let job = autometrics::wrap_future!("send_report", async { Ok::<_, std::io::Error>(()) });
+/// // The call is recorded when the future completes, for example after `tokio::spawn(job)`
+/// job.await.ok();
+/// # }
+/// ```
+#[macro_export]
+macro_rules! wrap_future {
+    ($name:expr, $future:expr $(,)?) => {
+        $crate::instrument::Function::new($name, ::core::module_path!()).wrap_future($future)
+    };
+}
+
+/// Wrap a closure so that calling it is tracked as a call to the function with the given name
+/// in the current module.
+///
+/// This is a shorthand for [`Function::wrap_closure`].
+///
+/// # Example
+/// ```rust
+/// let job = autometrics::wrap_closure!("resize_images", || println!("Resizing"));
+/// std::thread::spawn(job).join().unwrap();
+/// ```
+#[macro_export]
+macro_rules! wrap_closure {
+    ($name:expr, $closure:expr $(,)?) => {
+        $crate::instrument::Function::new($name, ::core::module_path!()).wrap_closure($closure)
+    };
+}
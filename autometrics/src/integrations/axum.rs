@@ -0,0 +1,90 @@
+//! Instrument every route of an [`axum`](https://crates.io/crates/axum) router without annotating the handlers.
+//!
+//! # Example
+//! ```rust
+//! use autometrics::{integrations::axum::RouterExt, prometheus_exporter};
+//! use axum::{routing::get, Router};
+//!
+//! async fn get_user() -> &'static str {
+//!     "Hello world!"
+//! }
+//!
+//! pub fn main() {
+//!     prometheus_exporter::init();
+//!
+//!     // Every route is tracked as a function named after its path (here: "/users/:id")
+//!     // and the metrics are exported on /metrics
+//!     let app: Router = Router::new()
+//!         .route("/users/:id", get(get_user))
+//!         .with_autometrics();
+//! }
+//! ```
+//!
+//! Responses with a `5xx` status code are counted as errors.
+//!
+//! Handlers (and any other functions) instrumented with the `autometrics` macro
+//! are reported with the route as their caller.
+//!
+//! # Tracking the routes by method
+//!
+//! [`route_autometrics`] is a middleware that tracks each route as a function named after the
+//! HTTP method and the route (like `GET /users/:id`), so that the methods of a route are tracked separately.
+//! Unlike [`RouterExt::with_autometrics`], it can include the routes in an [`Objective`](crate::objectives::Objective)
+//! and leaves it to you to expose the metrics:
+//! ```rust
+//! use autometrics::integrations::axum::route_autometrics;
+//! use autometrics::objectives::{Objective, ObjectivePercentile};
+//! use axum::{routing::get, Router};
+//!
+//! const API_SLO: Objective = Objective::new("api").success_rate(ObjectivePercentile::P99_9);
+//!
+//! async fn get_user() -> &'static str {
+//!     "Hello world!"
+//! }
+//!
+//! let app: Router = Router::new()
+//!     .route("/users/:id", get(get_user).delete(get_user))
+//!     .route_layer(route_autometrics().objective(API_SLO));
+//! ```
+//!
+//! Both are built on the [`tower` middleware](crate::middleware::tower::AutometricsLayer),
+//! so the routes are recorded in the `http` module.
+
+use crate::middleware::tower::{AutometricsLayer, MethodAndMatchedPathExtractor};
+use crate::prometheus_exporter;
+use ::axum::{routing::get, Router};
+
+/// Extension trait for instrumenting an [`axum::Router`].
+pub trait RouterExt {
+    /// Track the request rate, error rate, and latency of every route that has been added to the router,
+    /// using the route path as the `function` label, and mount the Prometheus exporter on `/metrics`.
+    ///
+    /// This adds [`AutometricsLayer::matched_path`] as a route layer, so the routes are recorded in the
+    /// `http` module. Only the routes that were added before this is called are instrumented.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the router already has a `/metrics` route, like adding any other route twice.
+    /// Use [`route_autometrics`] or [`AutometricsLayer`] to expose the metrics on a different route.
+    fn with_autometrics(self) -> Self;
+}
+
+impl<S> RouterExt for Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    fn with_autometrics(self) -> Self {
+        self.route_layer(AutometricsLayer::matched_path()).route(
+            "/metrics",
+            get(|| async { prometheus_exporter::encode_http_response() }),
+        )
+    }
+}
+
+/// Middleware that tracks the request rate, error rate, and latency of every route,
+/// using the HTTP method and the route as the `function` label (like `GET /users/:id`).
+///
+/// See the [module documentation](self) for an example.
+pub fn route_autometrics() -> AutometricsLayer<MethodAndMatchedPathExtractor> {
+    AutometricsLayer::method_and_matched_path()
+}
@@ -0,0 +1,7 @@
+//! Integrations with web and RPC frameworks, which instrument every route or method of a server
+//! without annotating the handlers.
+
+#[cfg(feature = "axum")]
+pub mod axum;
+#[cfg(feature = "tonic")]
+pub mod tonic;
@@ -0,0 +1,193 @@
+//! Instrument every method of a [`tonic`](https://crates.io/crates/tonic) gRPC server
+//! without annotating the generated service implementations.
+//!
+//! The [`AutometricsLayer`] tracks every request as a call to a function named after the gRPC method,
+//! in a module named after the gRPC service. For example, a call to `/helloworld.Greeter/SayHello`
+//! is reported with the `function` label `SayHello` and the `module` label `helloworld.Greeter`.
+//! Requests whose path is not a gRPC method, and requests for methods that the server does not implement
+//! (which are answered with the status code `UNIMPLEMENTED`), are not tracked.
+//!
+//! Responses with the status code `OK` are counted as successful, and all others as errors.
+//! Status codes that are expected, like `NOT_FOUND` for a lookup, can be counted as successful
+//! with [`AutometricsLayer::ok_codes`]. Errors returned by the service itself are always counted as errors.
+//!
+//! Handlers (and any other functions) instrumented with the `autometrics` macro
+//! are reported with the gRPC method as their caller.
+//!
+//! # Example
+//! ```rust
+//! use autometrics::integrations::tonic::AutometricsLayer;
+//! use autometrics::objectives::{Objective, ObjectivePercentile};
+//! use tonic::Code;
+//!
+//! const API_SLO: Objective = Objective::new("grpc").success_rate(ObjectivePercentile::P99_9);
+//!
+//! // Looking up a user that does not exist is not a failure of the server
+//! let layer = AutometricsLayer::new()
+//!     .ok_codes([Code::NotFound])
+//!     .objective(API_SLO);
+//!
+//! // Add the layer to the server with `tonic::transport::Server::builder().layer(layer)`
+//! ```
+//!
+//! The status code is read from the `grpc-status` header of the response, which is where `tonic` puts it
+//! when a handler returns an error. Streaming responses that fail after the response has started send the
+//! status in the trailers instead, so those calls are counted as successful.
+//!
+//! The `function` and `module` label values need to be `&'static str`s, so each service and method name
+//! is leaked once. Clients can send requests for methods that do not exist, so the names of those are leaked
+//! too, but they do not create any time series.
+
+use crate::__private::{
+    AutometricsTracker, CallerInfo, CounterLabels, HistogramLabels, TrackMetrics, ERROR_KEY, OK_KEY,
+};
+use crate::middleware::tower::intern_route;
+use crate::objectives::Objective;
+use crate::settings::get_settings;
+use ::tonic::Code;
+use ::tower::{Layer, Service};
+use http::{Request, Response};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// The header that contains the status code of a gRPC response that failed before it started
+const GRPC_STATUS_HEADER: &str = "grpc-status";
+
+/// A [`Layer`] that tracks the request rate, error rate, and latency of every method of a gRPC server.
+///
+/// See the [module documentation](self) for details.
+#[derive(Clone, Copy, Debug)]
+pub struct AutometricsLayer {
+    /// The status codes that are counted as successful, as a bit set
+    ok_codes: u32,
+    objective: Option<Objective>,
+}
+
+impl AutometricsLayer {
+    /// Count the responses with the status code `OK` as successful, and all others as errors.
+    pub fn new() -> Self {
+        Self {
+            ok_codes: code_bit(Code::Ok),
+            objective: None,
+        }
+    }
+
+    /// Also count the responses with the given status codes as successful.
+    pub fn ok_codes(mut self, codes: impl IntoIterator<Item = Code>) -> Self {
+        for code in codes {
+            self.ok_codes |= code_bit(code);
+        }
+        self
+    }
+
+    /// Include the metrics of every method in the given [`Objective`].
+    pub fn objective(mut self, objective: Objective) -> Self {
+        self.objective = Some(objective);
+        self
+    }
+}
+
+impl Default for AutometricsLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> Layer<S> for AutometricsLayer {
+    type Service = AutometricsService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AutometricsService {
+            inner,
+            ok_codes: self.ok_codes,
+            objective: self.objective,
+        }
+    }
+}
+
+/// The [`Service`] produced by the [`AutometricsLayer`].
+#[derive(Clone, Debug)]
+pub struct AutometricsService<S> {
+    inner: S,
+    ok_codes: u32,
+    objective: Option<Objective>,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for AutometricsService<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        let Some((module, function)) = grpc_method(request.uri().path()) else {
+            return Box::pin(self.inner.call(request));
+        };
+
+        let ok_codes = self.ok_codes;
+        let objective = self.objective;
+        let tracker = AutometricsTracker::start(None);
+        let caller = CallerInfo {
+            caller_function: function,
+            caller_module: module,
+        };
+        let settings = get_settings();
+        let future = caller.sync_scope(settings, || self.inner.call(request));
+
+        Box::pin(async move {
+            let response = caller.scope(settings, future).await;
+
+            let result = match &response {
+                Ok(grpc_response) => match status_code(grpc_response) {
+                    Code::Unimplemented => return response,
+                    code if ok_codes & code_bit(code) != 0 => OK_KEY,
+                    _ => ERROR_KEY,
+                },
+                Err(_) => ERROR_KEY,
+            };
+            let counter_labels = CounterLabels::new(
+                settings,
+                function,
+                module,
+                "",
+                "",
+                Some((result, None)),
+                objective,
+            );
+            let histogram_labels = HistogramLabels::new(settings, function, module, objective);
+            tracker.finish(&counter_labels, &histogram_labels);
+
+            response
+        })
+    }
+}
+
+/// Split the path of a gRPC request (`/{service}/{method}`) into the service and the method
+fn grpc_method(path: &str) -> Option<(&'static str, &'static str)> {
+    let (service, method) = path.strip_prefix('/')?.split_once('/')?;
+    if service.is_empty() || method.is_empty() || method.contains('/') {
+        return None;
+    }
+    Some((intern_route(service), intern_route(method)))
+}
+
+/// The status code in the headers of the response, or `OK` if the status is sent in the trailers
+fn status_code<B>(response: &Response<B>) -> Code {
+    response
+        .headers()
+        .get(GRPC_STATUS_HEADER)
+        .map(|status| Code::from_bytes(status.as_bytes()))
+        .unwrap_or(Code::Ok)
+}
+
+fn code_bit(code: Code) -> u32 {
+    1 << code as u32
+}
@@ -0,0 +1,48 @@
+use std::fmt::{self, Write};
+
+/// Writes the fields of a JSON object, escaping the string values
+pub(crate) struct JsonObject<'a, 'b> {
+    f: &'a mut fmt::Formatter<'b>,
+    first: bool,
+}
+
+impl<'a, 'b> JsonObject<'a, 'b> {
+    pub(crate) fn new(f: &'a mut fmt::Formatter<'b>) -> Result<Self, fmt::Error> {
+        f.write_char('{')?;
+        Ok(Self { f, first: true })
+    }
+
+    fn key(&mut self, key: &str) -> fmt::Result {
+        if !self.first {
+            self.f.write_char(',')?;
+        }
+        self.first = false;
+        write!(self.f, "\"{key}\":")
+    }
+
+    pub(crate) fn raw_field(&mut self, key: &str, value: impl fmt::Display) -> fmt::Result {
+        self.key(key)?;
+        write!(self.f, "{value}")
+    }
+
+    pub(crate) fn field(&mut self, key: &str, value: &str) -> fmt::Result {
+        self.key(key)?;
+        self.f.write_char('"')?;
+        for c in value.chars() {
+            match c {
+                '"' => self.f.write_str("\\\"")?,
+                '\\' => self.f.write_str("\\\\")?,
+                '\n' => self.f.write_str("\\n")?,
+                '\r' => self.f.write_str("\\r")?,
+                '\t' => self.f.write_str("\\t")?,
+                c if c.is_control() => write!(self.f, "\\u{:04x}", c as u32)?,
+                c => self.f.write_char(c)?,
+            }
+        }
+        self.f.write_char('"')
+    }
+
+    pub(crate) fn finish(self) -> fmt::Result {
+        self.f.write_char('}')
+    }
+}
@@ -1,15 +1,118 @@
-use crate::{constants::*, objectives::*, settings::get_settings};
+use crate::registry::{fnv1a, FNV_OFFSET_BASIS};
+use crate::settings::{get_settings, AliasMode, AutometricsSettings};
+use crate::tracker::CallSite;
+use crate::{constants::*, objectives::*};
 #[cfg(prometheus_client)]
-use prometheus_client::encoding::{EncodeLabelSet, EncodeLabelValue, LabelValueEncoder};
+use prometheus_client::encoding::{
+    EncodeLabelKey, EncodeLabelSet, EncodeLabelValue, LabelSetEncoder, LabelValueEncoder,
+};
+use std::fmt;
+use std::hash::{Hash, Hasher};
 
 pub(crate) type Label = (&'static str, &'static str);
 pub type ResultAndReturnTypeLabels = (&'static str, Option<&'static str>);
 
+/// The labels configured via [`AutometricsSettingsBuilder::global_labels`](crate::settings::AutometricsSettingsBuilder::global_labels)
+/// that are attached to every metric.
+pub(crate) fn global_labels() -> impl Iterator<Item = Label> {
+    get_settings()
+        .global_labels
+        .iter()
+        .map(|(key, value)| (key.as_str(), value.as_str()))
+}
+
+/// Copy a label value that is only known at runtime (such as one received from a plugin or a snapshot),
+/// so that it lives until the end of the program. Each distinct value is only copied once.
+pub(crate) fn intern(value: &str) -> &'static str {
+    use once_cell::sync::Lazy;
+    use std::collections::HashSet;
+    use std::sync::RwLock;
+
+    static STRINGS: Lazy<RwLock<HashSet<&'static str>>> = Lazy::new(Default::default);
+
+    if let Some(value) = crate::poison::read(&STRINGS, "interned_labels")
+        .get(value)
+        .copied()
+    {
+        return value;
+    }
+
+    let mut strings = crate::poison::write(&STRINGS, "interned_labels");
+    match strings.get(value) {
+        Some(value) => value,
+        None => {
+            let value: &'static str = Box::leak(value.to_string().into_boxed_str());
+            strings.insert(value);
+            value
+        }
+    }
+}
+
+/// The length of the suffix that replaces the end of label values that are too long:
+/// a `~` followed by the 16 hex digits of the hash of the whole value.
+pub(crate) const TRUNCATION_SUFFIX_LENGTH: usize = 17;
+
+/// Shorten the label value to at most `max_length` bytes if it is longer than that.
+///
+/// The end of the value is replaced by a hash of the whole value, so that the same value is always
+/// shortened the same way (across calls, processes, and builds) and different values stay distinct.
+pub(crate) fn truncate_label_value(value: &'static str, max_length: usize) -> &'static str {
+    if value.len() <= max_length {
+        return value;
+    }
+    let mut end = max_length.saturating_sub(TRUNCATION_SUFFIX_LENGTH);
+    while !value.is_char_boundary(end) {
+        end -= 1;
+    }
+    let hash = fnv1a(FNV_OFFSET_BASIS, value.as_bytes());
+    intern(&format!("{}~{hash:016x}", &value[..end]))
+}
+
+/// The key of the given label in the exported metrics, with the `function` and `module` labels renamed
+/// as configured via [`AutometricsSettingsBuilder::label_key_overrides`](crate::settings::AutometricsSettingsBuilder::label_key_overrides).
+#[allow(dead_code)]
+pub(crate) fn label_key(key: &'static str) -> &'static str {
+    get_settings().label_key(key)
+}
+
+/// Implement `EncodeLabelSet` the same way as the derive macro, which uses the field names as the keys,
+/// except that the keys of the `function` and `module` labels can be renamed.
+///
+/// The [`CustomLabels`] are encoded with their own keys, after the other fields.
+#[cfg(prometheus_client)]
+macro_rules! impl_encode_label_set {
+    ($name:ident { $($field:ident),* $(,)? } $(custom $custom:ident)? $(flatten $flattened:ident)?) => {
+        impl EncodeLabelSet for $name {
+            fn encode(&self, mut encoder: LabelSetEncoder) -> Result<(), std::fmt::Error> {
+                $(encode_label(&mut encoder, label_key(stringify!($field)), &self.$field)?;)*
+                $(for (key, value) in self.$custom.iter() {
+                    encode_label(&mut encoder, key, &value)?;
+                })?
+                $(EncodeLabelSet::encode(&self.$flattened, encoder)?;)?
+                Ok(())
+            }
+        }
+    };
+}
+
+#[cfg(prometheus_client)]
+fn encode_label(
+    encoder: &mut LabelSetEncoder,
+    key: &'static str,
+    value: &impl EncodeLabelValue,
+) -> Result<(), std::fmt::Error> {
+    let mut label_encoder = encoder.encode_label();
+    let mut label_key_encoder = label_encoder.encode_label_key()?;
+    EncodeLabelKey::encode(&key, &mut label_key_encoder)?;
+    let mut label_value_encoder = label_key_encoder.encode_label_value()?;
+    EncodeLabelValue::encode(value, &mut label_value_encoder)?;
+    label_value_encoder.finish()
+}
+
 /// These are the labels used for the `build_info` metric.
-#[cfg_attr(
-    prometheus_client,
-    derive(EncodeLabelSet, Debug, Clone, PartialEq, Eq, Hash)
-)]
+///
+/// The build timestamp, compiler version, target, runtime, and OS labels are only added if they are known.
+#[cfg_attr(prometheus_client, derive(Debug, Clone, PartialEq, Eq, Hash))]
 pub struct BuildInfoLabels {
     pub(crate) branch: &'static str,
     pub(crate) commit: &'static str,
@@ -18,23 +121,89 @@ pub struct BuildInfoLabels {
     pub(crate) repo_url: &'static str,
     pub(crate) repo_provider: &'static str,
     pub(crate) autometrics_version: &'static str,
+    pub(crate) build_timestamp: Option<&'static str>,
+    pub(crate) rustc_version: Option<&'static str>,
+    pub(crate) target: Option<&'static str>,
+    pub(crate) runtime: Option<&'static str>,
+    pub(crate) os: Option<&'static str>,
+}
+
+#[cfg(prometheus_client)]
+impl EncodeLabelSet for BuildInfoLabels {
+    fn encode(&self, mut encoder: LabelSetEncoder) -> Result<(), std::fmt::Error> {
+        for (key, value) in [
+            ("branch", self.branch),
+            ("commit", self.commit),
+            ("version", self.version),
+            ("service_name", self.service_name),
+            ("repo_url", self.repo_url),
+            ("repo_provider", self.repo_provider),
+            ("autometrics_version", self.autometrics_version),
+        ] {
+            encode_label(&mut encoder, key, &value)?;
+        }
+        for (key, value) in self.metadata() {
+            if let Some(value) = value {
+                encode_label(&mut encoder, key, &value)?;
+            }
+        }
+        Ok(())
+    }
 }
 
 impl BuildInfoLabels {
-    pub fn new(version: &'static str, commit: &'static str, branch: &'static str) -> Self {
+    pub fn new(
+        settings: &'static AutometricsSettings,
+        version: &'static str,
+        commit: &'static str,
+        branch: &'static str,
+    ) -> Self {
+        // The build info set in the settings takes precedence over the compile-time values
+        let build_info = &settings.build_info;
         Self {
-            version,
-            commit,
-            branch,
-            service_name: &get_settings().service_name,
-            repo_url: &get_settings().repo_url,
-            repo_provider: &get_settings().repo_provider,
+            version: build_info.version.as_deref().unwrap_or(version),
+            commit: build_info.commit.as_deref().unwrap_or(commit),
+            branch: build_info.branch.as_deref().unwrap_or(branch),
+            service_name: &settings.service_name,
+            repo_url: &settings.repo_url,
+            repo_provider: &settings.repo_provider,
             autometrics_version: AUTOMETRICS_SPEC_TARGET,
+            build_timestamp: build_info.build_timestamp.as_deref(),
+            rustc_version: build_info.rustc_version.as_deref(),
+            target: build_info.target.as_deref(),
+            runtime: settings.runtime_build_info.then(runtime).flatten(),
+            os: settings.runtime_build_info.then_some(std::env::consts::OS),
         }
     }
 
+    /// Set the build timestamp, compiler version, and target read from the compile-time environment variables,
+    /// unless they are set in the settings.
+    pub fn with_toolchain(
+        mut self,
+        build_timestamp: Option<&'static str>,
+        rustc_version: Option<&'static str>,
+        target: Option<&'static str>,
+    ) -> Self {
+        self.build_timestamp = self.build_timestamp.or(build_timestamp);
+        self.rustc_version = self.rustc_version.or(rustc_version);
+        self.target = self.target.or(target);
+        self
+    }
+
+    /// The optional labels, with the keys used in the Prometheus format
+    #[allow(dead_code)]
+    pub(crate) fn metadata(&self) -> [(&'static str, Option<&'static str>); 5] {
+        [
+            (BUILD_TIMESTAMP_KEY_PROMETHEUS, self.build_timestamp),
+            (RUSTC_VERSION_KEY_PROMETHEUS, self.rustc_version),
+            (TARGET_KEY, self.target),
+            (RUNTIME_KEY, self.runtime),
+            (OS_KEY, self.os),
+        ]
+    }
+
     pub fn to_vec(&self) -> Vec<Label> {
-        vec![
+        let mut labels = vec![
             (COMMIT_KEY, self.commit),
             (VERSION_KEY, self.version),
             (BRANCH_KEY, self.branch),
@@ -42,15 +211,42 @@ impl BuildInfoLabels {
             (REPO_URL_KEY, self.repo_url),
             (REPO_PROVIDER_KEY, self.repo_provider),
             (AUTOMETRICS_VERSION_KEY, self.autometrics_version),
-        ]
+        ];
+        for (key, value) in [
+            (BUILD_TIMESTAMP_KEY, self.build_timestamp),
+            (RUSTC_VERSION_KEY, self.rustc_version),
+            (TARGET_KEY, self.target),
+            (RUNTIME_KEY, self.runtime),
+            (OS_KEY, self.os),
+        ] {
+            if let Some(value) = value {
+                labels.push((key, value));
+            }
+        }
+        labels.extend(global_labels());
+
+        labels
     }
 }
 
+/// The async runtime that the first instrumented function was called on, if it is known.
+///
+/// This is only looked up once, so that the `build_info` metric keeps a single series.
+fn runtime() -> Option<&'static str> {
+    use once_cell::sync::OnceCell;
+
+    static RUNTIME: OnceCell<Option<&'static str>> = OnceCell::new();
+    *RUNTIME.get_or_init(|| {
+        #[cfg(feature = "tokio")]
+        if tokio::runtime::Handle::try_current().is_ok() {
+            return Some("tokio");
+        }
+        None
+    })
+}
+
 /// These are the labels used for the `function.calls` metric.
-#[cfg_attr(
-    prometheus_client,
-    derive(EncodeLabelSet, Debug, Clone, PartialEq, Eq, Hash)
-)]
+#[cfg_attr(prometheus_client, derive(Debug, Clone, PartialEq, Eq, Hash))]
 pub struct CounterLabels {
     pub(crate) function: &'static str,
     pub(crate) module: &'static str,
@@ -62,8 +258,32 @@ pub struct CounterLabels {
     pub(crate) error: Option<&'static str>,
     pub(crate) objective_name: Option<&'static str>,
     pub(crate) objective_percentile: Option<ObjectivePercentile>,
+    pub(crate) recursive: Option<&'static str>,
+    pub(crate) error_type: Option<&'static str>,
+    pub(crate) type_params: Option<&'static str>,
+    pub(crate) panic: Option<&'static str>,
+    pub(crate) custom_labels: CustomLabels,
+    pub(crate) call_site: CallSiteRef,
 }
 
+#[cfg(prometheus_client)]
+impl_encode_label_set!(CounterLabels {
+    function,
+    module,
+    service_name,
+    caller_function,
+    caller_module,
+    result,
+    ok,
+    error,
+    objective_name,
+    objective_percentile,
+    recursive,
+    error_type,
+    type_params,
+    panic,
+} custom custom_labels);
+
 #[cfg_attr(prometheus_client, derive(Debug, Clone, PartialEq, Eq, Hash))]
 pub(crate) enum ResultLabel {
     Ok,
@@ -91,6 +311,7 @@ impl EncodeLabelValue for ResultLabel {
 
 impl CounterLabels {
     pub fn new(
+        settings: &'static AutometricsSettings,
         function: &'static str,
         module: &'static str,
         caller_function: &'static str,
@@ -117,19 +338,84 @@ impl CounterLabels {
             (None, None, None)
         };
         Self {
-            function,
-            module,
-            service_name: &get_settings().service_name,
-            caller_function,
-            caller_module,
+            function: settings.label_value(function),
+            module: settings.module_label_value(module),
+            service_name: &settings.service_name,
+            caller_function: settings.label_value(caller_function),
+            caller_module: settings.module_label_value(caller_module),
             objective_name,
             objective_percentile,
             result,
             ok,
             error,
+            recursive: None,
+            error_type: None,
+            type_params: None,
+            panic: None,
+            custom_labels: CustomLabels::default(),
+            call_site: CallSiteRef(None),
         }
     }
 
+    /// Add the `recursive="true"` label if the function was called by itself.
+    pub fn with_recursive(mut self, recursive: bool) -> Self {
+        self.recursive = recursive.then_some("true");
+        self
+    }
+
+    /// Add the `error_type` label with the name of the error variant or type.
+    pub fn with_error_type(mut self, error_type: Option<&'static str>) -> Self {
+        self.error_type = error_type;
+        self
+    }
+
+    /// Add the `type_params` label with the type parameters of a generic function.
+    pub fn with_type_params(mut self, type_params: &'static str) -> Self {
+        self.type_params = Some(get_settings().label_value(type_params));
+        self
+    }
+
+    /// Add a label set with the `label(key = "...", value = ...)` argument of the `autometrics` macro.
+    pub fn with_label(mut self, key: &'static str, value: &'static str) -> Self {
+        self.custom_labels
+            .push(key, get_settings().label_value(value));
+        self
+    }
+
+    /// Add the `previous_function` label with the `alias` argument of the `autometrics` macro,
+    /// if the previous names are reported with [`AliasMode::Label`].
+    pub fn with_alias(mut self, alias: &'static str) -> Self {
+        let settings = get_settings();
+        if settings.alias_mode == AliasMode::Label {
+            self.custom_labels
+                .push(PREVIOUS_FUNCTION_KEY, settings.label_value(alias));
+        }
+        self
+    }
+
+    /// Use the class of the error's root cause as the `error` label, if the call is counted as an error.
+    pub fn with_error_class(mut self, error_class: Option<&'static str>) -> Self {
+        if matches!(self.result, Some(ResultLabel::Error)) && error_class.is_some() {
+            self.error = error_class;
+        }
+        self
+    }
+
+    /// Keep the handles to the series in the given call site, instead of looking them up on every call.
+    pub fn with_call_site(mut self, call_site: &'static CallSite) -> Self {
+        self.call_site = CallSiteRef(Some(call_site));
+        self
+    }
+
+    /// Record the call as an error with the `panic="true"` label, because the function panicked.
+    pub fn with_panic(mut self) -> Self {
+        self.result = Some(ResultLabel::Error);
+        self.ok = None;
+        self.error = None;
+        self.panic = Some("true");
+        self
+    }
+
     pub fn to_vec(&self) -> Vec<Label> {
         let mut labels = vec![
             (FUNCTION_KEY, self.function),
@@ -153,16 +439,27 @@ impl CounterLabels {
         if let Some(objective_percentile) = &self.objective_percentile {
             labels.push((OBJECTIVE_PERCENTILE, objective_percentile.as_str()));
         }
+        if let Some(recursive) = self.recursive {
+            labels.push((RECURSIVE_KEY, recursive));
+        }
+        if let Some(error_type) = self.error_type {
+            labels.push((ERROR_TYPE_KEY, error_type));
+        }
+        if let Some(type_params) = self.type_params {
+            labels.push((TYPE_PARAMS_KEY, type_params));
+        }
+        if let Some(panic) = self.panic {
+            labels.push((PANIC_KEY, panic));
+        }
+        labels.extend(self.custom_labels.iter());
+        labels.extend(global_labels());
 
         labels
     }
 }
 
 /// These are the labels used for the `function.calls.duration` metric.
-#[cfg_attr(
-    prometheus_client,
-    derive(EncodeLabelSet, Debug, Clone, PartialEq, Eq, Hash)
-)]
+#[cfg_attr(prometheus_client, derive(Debug, Clone, PartialEq, Eq, Hash))]
 pub struct HistogramLabels {
     pub(crate) function: &'static str,
     pub(crate) module: &'static str,
@@ -170,13 +467,41 @@ pub struct HistogramLabels {
     pub(crate) objective_name: Option<&'static str>,
     pub(crate) objective_percentile: Option<ObjectivePercentile>,
     pub(crate) objective_latency_threshold: Option<ObjectiveLatency>,
+    pub(crate) type_params: Option<&'static str>,
+    pub(crate) cold: Option<&'static str>,
+    /// The result of the call, which is only a label for the functions with a composite objective
+    pub(crate) result: Option<&'static str>,
+    /// Whether the function has a composite objective, so the result is added when the call finishes
+    /// (this is not a label)
+    pub(crate) composite: bool,
+    pub(crate) custom_labels: CustomLabels,
+    pub(crate) buckets: HistogramBuckets,
+    pub(crate) call_site: CallSiteRef,
 }
 
+#[cfg(prometheus_client)]
+impl_encode_label_set!(HistogramLabels {
+    function,
+    module,
+    service_name,
+    objective_name,
+    objective_percentile,
+    objective_latency_threshold,
+    type_params,
+    cold,
+    result,
+} custom custom_labels flatten buckets);
+
 impl HistogramLabels {
-    pub fn new(function: &'static str, module: &'static str, objective: Option<Objective>) -> Self {
+    pub fn new(
+        settings: &'static AutometricsSettings,
+        function: &'static str,
+        module: &'static str,
+        objective: Option<Objective>,
+    ) -> Self {
         let (objective_name, objective_percentile, objective_latency_threshold) =
             if let Some(objective) = objective {
-                if let Some((latency, percentile)) = objective.latency {
+                if let Some((latency, percentile)) = objective.histogram_target() {
                     (Some(objective.name), Some(percentile), Some(latency))
                 } else {
                     (None, None, None)
@@ -186,12 +511,72 @@ impl HistogramLabels {
             };
 
         Self {
-            function,
-            module,
-            service_name: &get_settings().service_name,
+            function: settings.label_value(function),
+            module: settings.module_label_value(module),
+            service_name: &settings.service_name,
             objective_name,
             objective_percentile,
             objective_latency_threshold,
+            type_params: None,
+            cold: None,
+            result: None,
+            composite: objective.is_some_and(|objective| objective.composite.is_some()),
+            custom_labels: CustomLabels::default(),
+            buckets: HistogramBuckets(None),
+            call_site: CallSiteRef(None),
+        }
+    }
+
+    /// Use the given buckets instead of the ones from the settings for the function's histogram.
+    pub fn with_buckets(mut self, buckets: &'static [f64]) -> Self {
+        self.buckets = HistogramBuckets(Some(buckets));
+        self
+    }
+
+    /// Add the `type_params` label with the type parameters of a generic function.
+    pub fn with_type_params(mut self, type_params: &'static str) -> Self {
+        self.type_params = Some(get_settings().label_value(type_params));
+        self
+    }
+
+    /// Add a label set with the `label(key = "...", value = ...)` argument of the `autometrics` macro.
+    pub fn with_label(mut self, key: &'static str, value: &'static str) -> Self {
+        self.custom_labels
+            .push(key, get_settings().label_value(value));
+        self
+    }
+
+    /// Add the `previous_function` label with the `alias` argument of the `autometrics` macro,
+    /// if the previous names are reported with [`AliasMode::Label`].
+    pub fn with_alias(mut self, alias: &'static str) -> Self {
+        let settings = get_settings();
+        if settings.alias_mode == AliasMode::Label {
+            self.custom_labels
+                .push(PREVIOUS_FUNCTION_KEY, settings.label_value(alias));
+        }
+        self
+    }
+
+    /// Keep the handles to the series in the given call site, instead of looking them up on every call.
+    pub fn with_call_site(mut self, call_site: &'static CallSite) -> Self {
+        self.call_site = CallSiteRef(Some(call_site));
+        self
+    }
+
+    /// The same labels with the `cold="true"` label, for the first call of the function.
+    pub(crate) fn cold(&self) -> Self {
+        Self {
+            cold: Some("true"),
+            ..*self
+        }
+    }
+
+    /// Add the result of the call, for the functions with a composite objective
+    /// (functions that do not return a `Result` have no result)
+    pub(crate) fn with_result(&self, result: Option<&ResultLabel>) -> Self {
+        Self {
+            result: result.map(ResultLabel::as_str),
+            ..*self
         }
     }
 
@@ -211,31 +596,215 @@ impl HistogramLabels {
         if let Some(objective_latency_threshold) = &self.objective_latency_threshold {
             labels.push((
                 OBJECTIVE_LATENCY_THRESHOLD,
-                objective_latency_threshold.as_str(),
+                objective_latency_threshold.label_value(),
             ));
         }
+        if let Some(type_params) = self.type_params {
+            labels.push((TYPE_PARAMS_KEY, type_params));
+        }
+        if let Some(cold) = self.cold {
+            labels.push((COLD_KEY, cold));
+        }
+        if let Some(result) = self.result {
+            labels.push((RESULT_KEY, result));
+        }
+        labels.extend(self.custom_labels.iter());
+        labels.extend(global_labels());
 
         labels
     }
 }
 
+/// The maximum number of labels that a function can add with the `label` argument of the `autometrics` macro.
+///
+/// Each of these multiplies the number of series of the function, so only a couple are allowed.
+pub const MAX_CUSTOM_LABELS: usize = 2;
+
+/// The number of slots for custom labels, which has room for the `previous_function` label
+/// added by the `alias` argument on top of the ones set with the `label` argument.
+pub(crate) const CUSTOM_LABEL_SLOTS: usize = MAX_CUSTOM_LABELS + 1;
+
+/// The labels set with the `label(key = "...", value = ...)` argument of the `autometrics` macro.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub(crate) struct CustomLabels([Option<Label>; CUSTOM_LABEL_SLOTS]);
+
+impl CustomLabels {
+    /// Add the label in the first empty slot (the macro checks that there are enough of them)
+    fn push(&mut self, key: &'static str, value: &'static str) {
+        if let Some(slot) = self.0.iter_mut().find(|slot| slot.is_none()) {
+            *slot = Some((key, value));
+        }
+    }
+
+    #[cfg_attr(not(prometheus), allow(dead_code))]
+    pub(crate) fn is_empty(&self) -> bool {
+        self.0.iter().all(Option::is_none)
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = Label> + '_ {
+        self.0.iter().flatten().copied()
+    }
+
+    /// The keys of the labels, which identify the metrics they are recorded in for the `prometheus` backend
+    #[cfg_attr(not(prometheus), allow(dead_code))]
+    pub(crate) fn keys(&self) -> [Option<&'static str>; CUSTOM_LABEL_SLOTS] {
+        self.0.map(|label| label.map(|(key, _)| key))
+    }
+}
+
+/// A value for a label added with the `label(key = "...", value = ...)` argument
+/// of the [`autometrics`](crate::autometrics) macro.
+///
+/// Every distinct value creates a new set of time series, so only implement this for
+/// types with a small, fixed set of values, like enums without fields:
+/// ```rust
+/// use autometrics::LabelValue;
+///
+/// enum Region {
+///     Eu,
+///     Us,
+/// }
+///
+/// impl LabelValue for Region {
+///     fn label_value(&self) -> &'static str {
+///         match self {
+///             Region::Eu => "eu",
+///             Region::Us => "us",
+///         }
+///     }
+/// }
+/// ```
+pub trait LabelValue {
+    fn label_value(&self) -> &'static str;
+}
+
+impl LabelValue for &'static str {
+    fn label_value(&self) -> &'static str {
+        self
+    }
+}
+
+impl LabelValue for bool {
+    fn label_value(&self) -> &'static str {
+        if *self {
+            "true"
+        } else {
+            "false"
+        }
+    }
+}
+
+/// The buckets set with the `buckets` argument of the `autometrics` macro.
+///
+/// These only determine how the histogram is created, so they are not part of
+/// the identity of the time series and are not encoded as a label.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct HistogramBuckets(
+    #[cfg_attr(not(any(prometheus, prometheus_client)), allow(dead_code))]
+    pub(crate)  Option<&'static [f64]>,
+);
+
+impl PartialEq for HistogramBuckets {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for HistogramBuckets {}
+
+impl Hash for HistogramBuckets {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
+#[cfg(prometheus_client)]
+impl EncodeLabelSet for HistogramBuckets {
+    fn encode(&self, _encoder: LabelSetEncoder) -> Result<(), std::fmt::Error> {
+        Ok(())
+    }
+}
+
+/// The call site that recorded the labels, set by the `autometrics` macro.
+///
+/// Like the [`HistogramBuckets`], this is not part of the identity of the time series.
+#[derive(Clone, Copy)]
+pub(crate) struct CallSiteRef(
+    #[cfg_attr(not(prometheus), allow(dead_code))] pub(crate) Option<&'static CallSite>,
+);
+
+impl fmt::Debug for CallSiteRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(if self.0.is_some() { "Some(..)" } else { "None" })
+    }
+}
+
+impl PartialEq for CallSiteRef {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for CallSiteRef {}
+
+impl Hash for CallSiteRef {
+    fn hash<H: Hasher>(&self, _state: &mut H) {}
+}
+
 /// These are the labels used for the `function.calls.concurrent` metric.
-#[cfg_attr(
-    prometheus_client,
-    derive(EncodeLabelSet, Debug, Clone, PartialEq, Eq, Hash)
-)]
+#[derive(Clone)]
+#[cfg_attr(prometheus_client, derive(Debug, PartialEq, Eq, Hash))]
 pub struct GaugeLabels {
     pub(crate) function: &'static str,
     pub(crate) module: &'static str,
     pub(crate) service_name: &'static str,
+    /// The name of the Tokio task the call is running in, if it is known (only a label with `tokio-console`)
+    pub(crate) task: Option<&'static str>,
+    /// Whether the maximum number of concurrent calls is tracked too (this is not a label)
+    pub(crate) max_concurrency: bool,
+}
+
+#[cfg(all(prometheus_client, not(tokio_console)))]
+impl_encode_label_set!(GaugeLabels {
+    function,
+    module,
+    service_name,
+});
+
+#[cfg(all(prometheus_client, tokio_console))]
+impl EncodeLabelSet for GaugeLabels {
+    fn encode(&self, mut encoder: LabelSetEncoder) -> Result<(), std::fmt::Error> {
+        encode_label(&mut encoder, label_key(FUNCTION_KEY), &self.function)?;
+        encode_label(&mut encoder, label_key(MODULE_KEY), &self.module)?;
+        encode_label(&mut encoder, label_key("service_name"), &self.service_name)?;
+        if let Some(task) = self.task {
+            encode_label(&mut encoder, TASK_KEY, &task)?;
+        }
+        Ok(())
+    }
 }
 
 impl GaugeLabels {
-    pub fn new(function: &'static str, module: &'static str) -> Self {
+    pub fn new(
+        settings: &'static AutometricsSettings,
+        function: &'static str,
+        module: &'static str,
+    ) -> Self {
         Self {
-            function,
-            module,
-            service_name: &get_settings().service_name,
+            function: settings.label_value(function),
+            module: settings.module_label_value(module),
+            service_name: &settings.service_name,
+            #[cfg(tokio_console)]
+            task: crate::tokio_console::current_task_name().map(|task| settings.label_value(task)),
+            #[cfg(not(tokio_console))]
+            task: None,
+            max_concurrency: false,
+        }
+    }
+
+    /// Track the maximum number of concurrent calls too, for the `track_concurrency(max)` argument
+    pub fn with_max_concurrency(self) -> Self {
+        Self {
+            max_concurrency: true,
+            ..self
         }
     }
 
@@ -245,6 +814,258 @@ impl GaugeLabels {
             (MODULE_KEY, self.module),
             (SERVICE_NAME_KEY, self.service_name),
         ]
+        .into_iter()
+        .chain(self.task.map(|task| (TASK_KEY, task)))
+        .chain(global_labels())
+        .collect()
+    }
+}
+
+/// These are the labels used for the `function.call.edges.info` metric.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct CallEdgeLabels {
+    pub(crate) caller_function: &'static str,
+    pub(crate) caller_module: &'static str,
+    pub(crate) function: &'static str,
+    pub(crate) module: &'static str,
+    pub(crate) service_name: &'static str,
+}
+
+#[cfg(prometheus_client)]
+impl_encode_label_set!(CallEdgeLabels {
+    caller_function,
+    caller_module,
+    function,
+    module,
+    service_name,
+});
+
+impl From<&CounterLabels> for CallEdgeLabels {
+    fn from(counter_labels: &CounterLabels) -> Self {
+        Self {
+            caller_function: counter_labels.caller_function,
+            caller_module: counter_labels.caller_module,
+            function: counter_labels.function,
+            module: counter_labels.module,
+            service_name: counter_labels.service_name,
+        }
+    }
+}
+
+impl CallEdgeLabels {
+    #[allow(dead_code)]
+    pub(crate) fn to_vec(self) -> Vec<Label> {
+        vec![
+            (CALLER_FUNCTION_KEY, self.caller_function),
+            (CALLER_MODULE_KEY, self.caller_module),
+            (FUNCTION_KEY, self.function),
+            (MODULE_KEY, self.module),
+            (SERVICE_NAME_KEY, self.service_name),
+        ]
+        .into_iter()
+        .chain(global_labels())
+        .collect()
+    }
+}
+
+/// These are the labels used for the `autometrics.cardinality.warnings` metric.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct CardinalityWarningLabels {
+    pub(crate) function: &'static str,
+    pub(crate) module: &'static str,
+    pub(crate) label: &'static str,
+    pub(crate) service_name: &'static str,
+}
+
+#[cfg(prometheus_client)]
+impl_encode_label_set!(CardinalityWarningLabels {
+    function,
+    module,
+    label,
+    service_name,
+});
+
+impl CardinalityWarningLabels {
+    #[allow(dead_code)]
+    pub(crate) fn to_vec(self) -> Vec<Label> {
+        vec![
+            (FUNCTION_KEY, self.function),
+            (MODULE_KEY, self.module),
+            (LABEL_KEY, self.label),
+            (SERVICE_NAME_KEY, self.service_name),
+        ]
+        .into_iter()
+        .chain(global_labels())
+        .collect()
+    }
+}
+
+/// These are the labels used for the `autometrics_label_set_evictions_total` metric.
+#[cfg(prometheus_client)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct LabelSetEvictionLabels {
+    pub(crate) function: &'static str,
+    pub(crate) module: &'static str,
+    pub(crate) service_name: &'static str,
+}
+
+#[cfg(prometheus_client)]
+impl_encode_label_set!(LabelSetEvictionLabels {
+    function,
+    module,
+    service_name,
+});
+
+/// These are the labels used for the `function.renamed.info` metric.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct FunctionRenamedLabels {
+    pub(crate) function: &'static str,
+    pub(crate) module: &'static str,
+    pub(crate) previous_function: &'static str,
+    pub(crate) service_name: &'static str,
+}
+
+#[cfg(prometheus_client)]
+impl_encode_label_set!(FunctionRenamedLabels {
+    function,
+    module,
+    previous_function,
+    service_name,
+});
+
+impl FunctionRenamedLabels {
+    pub(crate) fn new(
+        settings: &'static AutometricsSettings,
+        function: &'static str,
+        module: &'static str,
+        previous_function: &'static str,
+    ) -> Self {
+        Self {
+            function: settings.label_value(function),
+            module: settings.module_label_value(module),
+            previous_function: settings.label_value(previous_function),
+            service_name: &settings.service_name,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn to_vec(self) -> Vec<Label> {
+        vec![
+            (FUNCTION_KEY, self.function),
+            (MODULE_KEY, self.module),
+            (PREVIOUS_FUNCTION_KEY, self.previous_function),
+            (SERVICE_NAME_KEY, self.service_name),
+        ]
+        .into_iter()
+        .chain(global_labels())
+        .collect()
+    }
+}
+
+/// These are the labels used for the `autometrics.lock.recoveries` metric.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct LockRecoveryLabels {
+    pub(crate) lock: &'static str,
+    pub(crate) service_name: &'static str,
+}
+
+#[cfg(prometheus_client)]
+impl_encode_label_set!(LockRecoveryLabels { lock, service_name });
+
+impl LockRecoveryLabels {
+    #[allow(dead_code)]
+    pub(crate) fn to_vec(self) -> Vec<Label> {
+        vec![(LOCK_KEY, self.lock), (SERVICE_NAME_KEY, self.service_name)]
+            .into_iter()
+            .chain(global_labels())
+            .collect()
+    }
+}
+
+/// These are the labels used for the `crate.calls` metric.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct CrateCallsLabels {
+    pub(crate) crate_name: &'static str,
+    pub(crate) result: &'static str,
+    pub(crate) service_name: &'static str,
+}
+
+// The `crate` key cannot be used as a field name
+#[cfg(prometheus_client)]
+impl EncodeLabelSet for CrateCallsLabels {
+    fn encode(&self, mut encoder: LabelSetEncoder) -> Result<(), std::fmt::Error> {
+        encode_label(&mut encoder, CRATE_KEY, &self.crate_name)?;
+        encode_label(&mut encoder, RESULT_KEY, &self.result)?;
+        encode_label(
+            &mut encoder,
+            SERVICE_NAME_KEY_PROMETHEUS,
+            &self.service_name,
+        )
+    }
+}
+
+impl CrateCallsLabels {
+    /// The crate is the first segment of the `module` label, which is the crate name
+    /// unless the module paths are transformed
+    pub(crate) fn new(counter_labels: &CounterLabels) -> Self {
+        Self {
+            crate_name: counter_labels.module.split("::").next().unwrap_or_default(),
+            result: counter_labels
+                .result
+                .as_ref()
+                .map_or("", ResultLabel::as_str),
+            service_name: counter_labels.service_name,
+        }
+    }
+
+    #[allow(dead_code)]
+    pub(crate) fn to_vec(self) -> Vec<Label> {
+        vec![
+            (CRATE_KEY, self.crate_name),
+            (RESULT_KEY, self.result),
+            (SERVICE_NAME_KEY, self.service_name),
+        ]
+        .into_iter()
+        .chain(global_labels())
+        .collect()
+    }
+}
+
+/// These are the labels used for the `kafka.consumer.lag` metric.
+#[cfg(feature = "rdkafka")]
+#[cfg_attr(
+    prometheus_client,
+    derive(EncodeLabelSet, Debug, Clone, PartialEq, Eq, Hash)
+)]
+pub struct ConsumerLagLabels {
+    pub(crate) topic: &'static str,
+    pub(crate) partition: &'static str,
+    pub(crate) service_name: &'static str,
+}
+
+#[cfg(feature = "rdkafka")]
+impl ConsumerLagLabels {
+    pub fn new(
+        settings: &'static AutometricsSettings,
+        topic: &'static str,
+        partition: &'static str,
+    ) -> Self {
+        Self {
+            topic,
+            partition,
+            service_name: &settings.service_name,
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<Label> {
+        vec![
+            (TOPIC_KEY, self.topic),
+            (PARTITION_KEY, self.partition),
+            (SERVICE_NAME_KEY, self.service_name),
+        ]
+        .into_iter()
+        .chain(global_labels())
+        .collect()
     }
 }
 
@@ -405,3 +1226,136 @@ macro_rules! get_result_labels_for_value {
         }
     }};
 }
+
+/// Return the class of the root cause of the error, if the value is a `Result` containing an error
+/// that implements [`std::error::Error`] (or a `Box<dyn Error>`) and an
+/// [`ErrorClassifier`](crate::error_classifier::ErrorClassifier) is configured.
+///
+/// Like [`get_result_labels_for_value`], this uses the autoref specialization trick through spez,
+/// so that it can be called on the return value of any function.
+///
+/// The macro is meant to be called with a reference as argument: `get_error_class_for_value(&return_value)`
+#[doc(hidden)]
+#[macro_export]
+macro_rules! get_error_class_for_value {
+    ($e:expr) => {{
+        $crate::__private::spez! {
+            for val = $e;
+
+            match<T, E> &::std::result::Result<T, E> where E: ::std::error::Error + 'static -> ::std::option::Option<&'static str> {
+                match val {
+                    Ok(_) => None,
+                    Err(err) => $crate::__private::classify_error(err),
+                }
+            }
+
+            match<T> &::std::result::Result<T, ::std::boxed::Box<dyn ::std::error::Error + Send + Sync>> -> ::std::option::Option<&'static str> {
+                match val {
+                    Ok(_) => None,
+                    Err(err) => $crate::__private::classify_error(&**err),
+                }
+            }
+
+            match<T> &::std::result::Result<T, ::std::boxed::Box<dyn ::std::error::Error>> -> ::std::option::Option<&'static str> {
+                match val {
+                    Ok(_) => None,
+                    Err(err) => $crate::__private::classify_error(&**err),
+                }
+            }
+
+            match<T> T -> ::std::option::Option<&'static str> {
+                None
+            }
+        }
+    }};
+}
+
+/// Return the value of the "error_type" label, if the value is a `Result` containing an error
+/// that implements [`std::error::Error`].
+///
+/// Like [`get_result_labels_for_value`], this uses the autoref specialization trick through spez,
+/// so that it can be called on the return value of any function.
+///
+/// The macro is meant to be called with a reference as argument: `get_error_type_for_value(&return_value)`
+#[doc(hidden)]
+#[macro_export]
+macro_rules! get_error_type_for_value {
+    ($e:expr) => {{
+        $crate::__private::spez! {
+            for val = $e;
+
+            match<T, E> &::std::result::Result<T, E> where E: ::std::error::Error -> ::std::option::Option<&'static str> {
+                match val {
+                    Ok(_) => None,
+                    Err(err) => Some($crate::__private::error_type(err)),
+                }
+            }
+
+            match<T> T -> ::std::option::Option<&'static str> {
+                None
+            }
+        }
+    }};
+}
+
+/// A type with a status code, which is used by the `ok_if_status` and `error_if_status` arguments
+/// of the [`autometrics`](crate::autometrics) macro.
+///
+/// This is implemented for HTTP responses and status codes, for `tonic` statuses and responses
+/// (with the `tonic` feature), and for `Result`s of two types that implement it, so that
+/// `Result<tonic::Response<T>, tonic::Status>` uses the status of either variant.
+/// Implement it for your own response types to use the same policy for them.
+pub trait ResultStatus {
+    /// The numeric status code, like `404` for an HTTP response or `5` (`NOT_FOUND`) for a gRPC status.
+    fn status_code(&self) -> u16;
+}
+
+impl<T: ResultStatus + ?Sized> ResultStatus for &T {
+    fn status_code(&self) -> u16 {
+        (**self).status_code()
+    }
+}
+
+impl ResultStatus for u16 {
+    fn status_code(&self) -> u16 {
+        *self
+    }
+}
+
+impl<T: ResultStatus, E: ResultStatus> ResultStatus for Result<T, E> {
+    fn status_code(&self) -> u16 {
+        match self {
+            Ok(ok) => ok.status_code(),
+            Err(err) => err.status_code(),
+        }
+    }
+}
+
+#[cfg(feature = "http")]
+impl ResultStatus for http::StatusCode {
+    fn status_code(&self) -> u16 {
+        self.as_u16()
+    }
+}
+
+#[cfg(feature = "http")]
+impl<B> ResultStatus for http::Response<B> {
+    fn status_code(&self) -> u16 {
+        self.status().as_u16()
+    }
+}
+
+#[cfg(feature = "tonic")]
+impl ResultStatus for tonic::Status {
+    fn status_code(&self) -> u16 {
+        self.code() as u16
+    }
+}
+
+/// Responses are only returned by successful calls, so they always have the status `OK`
+#[cfg(feature = "tonic")]
+impl<T> ResultStatus for tonic::Response<T> {
+    fn status_code(&self) -> u16 {
+        tonic::Code::Ok as u16
+    }
+}
@@ -5,22 +5,91 @@
 #![cfg_attr(docsrs, doc(cfg_hide(doc)))]
 #![doc = include_str!("README.md")]
 
+#[cfg(feature = "alerts")]
+pub mod alerts;
+#[cfg(feature = "async-graphql")]
+pub mod async_graphql;
+/// Moved to [`integrations::axum`].
+#[cfg(feature = "axum")]
+#[deprecated(note = "moved to `autometrics::integrations::axum`")]
+pub mod axum {
+    pub use crate::integrations::axum::RouterExt;
+    use crate::middleware::tower::{AutometricsLayer, MethodAndMatchedPathExtractor};
+
+    #[deprecated(note = "moved to `autometrics::integrations::axum::route_autometrics`")]
+    pub fn route_autometrics() -> AutometricsLayer<MethodAndMatchedPathExtractor> {
+        crate::integrations::axum::route_autometrics()
+    }
+}
+pub mod build;
+mod call_edges;
+mod capabilities;
+pub mod cardinality;
+mod concurrency;
+pub mod connection;
 mod constants;
+pub mod context;
+pub mod dashboards;
+pub mod error_classifier;
 #[cfg(any(
+    feature = "exemplars-custom",
     feature = "exemplars-tracing",
     feature = "exemplars-tracing-opentelemetry",
     feature = "exemplars-tracing-opentelemetry-0_25",
+    feature = "exemplars-testing",
 ))]
 pub mod exemplars;
+#[cfg(ffi)]
+pub mod ffi;
+pub mod instrument;
+#[cfg(any(feature = "axum", feature = "tonic"))]
+pub mod integrations;
+mod json;
 mod labels;
+mod lifecycle;
+#[cfg(feature = "tower")]
+pub mod middleware;
 pub mod objectives;
 #[cfg(feature = "otel-push-exporter")]
 pub mod otel_push_exporter;
+#[cfg(feature = "plugin")]
+pub mod plugin;
+mod poison;
 #[cfg(feature = "prometheus-exporter")]
 pub mod prometheus_exporter;
+pub mod queries;
+#[cfg(feature = "rdkafka")]
+pub mod rdkafka;
+pub mod registry;
+mod sampling;
+#[cfg(prometheus_client)]
+pub mod scope;
 pub mod settings;
+#[cfg(snapshots)]
+pub mod snapshot;
+#[cfg(span_events)]
+pub mod span_events;
+#[cfg(span_traces)]
+pub mod span_traces;
+#[cfg(stream)]
+pub mod stream;
+#[cfg(summaries)]
+pub mod summaries;
 mod task_local;
+#[cfg(prometheus_exporter)]
+pub mod testing;
+#[cfg(tokio_console)]
+pub mod tokio_console;
 mod tracker;
+#[cfg(wide_events)]
+pub mod wide_events;
+
+// The Prometheus registry used by the `prometheus` and `opentelemetry` backends comes from
+// a different version of the `prometheus` crate depending on the OpenTelemetry version
+#[cfg(any(prometheus, opentelemetry_0_24))]
+pub(crate) use prometheus as prometheus_crate;
+#[cfg(all(opentelemetry_0_33, not(prometheus)))]
+pub(crate) use prometheus_0_14 as prometheus_crate;
 
 /// A macro that makes it easy to instrument functions with the most useful metrics.
 ///
@@ -46,8 +115,23 @@ mod tracker;
 ///        // This method has metrics too!
 ///    }
 /// }
+///
+/// #[autometrics]
+/// trait Repository {
+///     // The default methods of traits are instrumented and identified
+///     // by the type implementing the trait, like `MyStruct::load`
+///     fn load(&self) -> Result<(), ()> {
+///         Ok(())
+///     }
+/// }
 /// ```
 ///
+/// ## Panics
+///
+/// If an instrumented function panics, the call is recorded with the `result="error"` and `panic="true"` labels,
+/// so that panics count towards the error rate and any objectives the function is part of.
+/// (This requires panics to unwind, which is the default.)
+///
 /// ## Optional Parameters
 ///
 /// ### `ok_if` and `error_if`
@@ -81,9 +165,78 @@ mod tracker;
 /// }
 /// ```
 ///
-/// Note that the function must be callable as `f(&T) -> bool`, where `T` is the return type
+/// The predicate can also be a method or a closure:
+/// ```rust
+/// # use autometrics::autometrics;
+/// # use http::{Request, Response};
+/// #[autometrics(ok_if = |res| res.status().is_success())]
+/// pub async fn my_handler(req: Request<()>) -> Response<()> {
+/// # Response::new(())
+///     // ...
+/// }
+///
+/// #[autometrics(error_if = Vec::is_empty)]
+/// pub fn list_users() -> Vec<String> {
+/// # Vec::new()
+///     // ...
+/// }
+/// ```
+///
+/// Note that the predicate must be callable as `f(&T) -> bool`, where `T` is the return type
 /// of the instrumented function.
 ///
+/// ### `ok_if_status` and `error_if_status`
+///
+/// Example:
+/// ```rust
+/// # use autometrics::autometrics;
+/// # use http::{Request, Response, StatusCode};
+/// // Client errors are not failures of the handler
+/// #[autometrics(ok_if_status < 500)]
+/// pub async fn get_user(req: Request<()>) -> Response<()> {
+/// # Response::new(())
+///     // ...
+/// }
+///
+/// #[autometrics(error_if_status in [500, 502, 503])]
+/// pub fn check_upstream() -> StatusCode {
+/// # StatusCode::OK
+///     // ...
+/// }
+/// ```
+///
+/// For return types that implement [`ResultStatus`], like HTTP responses and `tonic` statuses,
+/// the `result` label can be determined by comparing the status code with `<`, `<=`, `>`, `>=`, `==`, or `!=`,
+/// or by checking whether it is `in` a list of status codes.
+/// Functions that return `Result<tonic::Response<T>, tonic::Status>` use the gRPC status codes,
+/// so `error_if_status in [13, 14]` only counts `INTERNAL` and `UNAVAILABLE` statuses as errors.
+///
+/// ### `error_details`
+///
+/// Example:
+/// ```rust
+/// # use autometrics::autometrics;
+/// # #[derive(Debug)] pub enum DatabaseError { Timeout }
+/// # impl std::fmt::Display for DatabaseError {
+/// #     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result { write!(f, "timeout") }
+/// # }
+/// # impl std::error::Error for DatabaseError {}
+/// #[autometrics(error_details)]
+/// pub fn load_user() -> Result<(), DatabaseError> {
+///     Err(DatabaseError::Timeout)
+/// }
+/// ```
+///
+/// Pass this argument to add the `error_type` label when the function returns an `Err`
+/// containing a type that implements [`std::error::Error`] (here, `error_type="Timeout"`).
+///
+/// The label is the name of the error variant if the `Debug` representation of the error starts with one,
+/// like the ones produced by `#[derive(Debug)]`, and the name of the error type otherwise.
+/// This keeps the number of distinct values bounded, unlike the error messages.
+///
+/// To label errors by the cause that they wrap rather than by the wrapper, configure an
+/// [`ErrorClassifier`](crate::error_classifier::ErrorClassifier), which sets the `error` label of every function.
+///
 /// ### `track_concurrency`
 ///
 /// Example:
@@ -97,6 +250,296 @@ mod tracker;
 /// This may be most useful for top-level functions such as the main HTTP handler that
 /// passes requests off to other functions.
 ///
+/// If the function calls itself, only the top-level call is counted.
+///
+/// With `track_concurrency(max)`, the highest number of concurrent calls is also recorded in the
+/// `function_calls_concurrent_max` gauge. The [`prometheus_exporter`](crate::prometheus_exporter) resets it
+/// after every scrape, so it shows the peak since the previous scrape, including short bursts that
+/// the live gauge misses. This is useful for capacity planning:
+/// ```rust
+/// # use autometrics::autometrics;
+/// #[autometrics(track_concurrency(max))]
+/// pub fn handle_upload() { }
+/// ```
+///
+/// With the `tokio-console` feature, both gauges also have a `task` label with the name of the Tokio task
+/// that the calls are running in, which is set by the `tokio_console::TaskNameLayer`.
+///
+/// ### `recursive`
+///
+/// Example:
+/// ```rust
+/// # use autometrics::autometrics;
+/// #[autometrics(recursive = label)]
+/// pub fn factorial(n: u64) -> u64 {
+///     if n <= 1 { 1 } else { n * factorial(n - 1) }
+/// }
+/// ```
+///
+/// By default, the calls a recursive function makes to itself are reported with the function
+/// as its own caller. Use this argument to change how these calls are labeled:
+///
+/// - `recursive = skip_caller` leaves the `caller_function` and `caller_module` labels empty
+/// - `recursive = label` adds the `recursive="true"` label
+///
+/// ### `generic_params`
+///
+/// Example:
+/// ```rust
+/// # use autometrics::autometrics;
+/// # pub trait Job { fn run(&self); }
+/// #[autometrics(generic_params)]
+/// pub fn handle<T: Job>(job: T) {
+///     job.run()
+/// }
+/// ```
+///
+/// By default, all of the instantiations of a generic function are tracked together.
+/// Pass this argument to add the `type_params` label with the type parameters of each call
+/// (here, `type_params="EmailJob"`), so that the instantiations can be told apart.
+///
+/// The module paths are removed from the type names, so `alloc::vec::Vec<my_crate::Job>` becomes `Vec<Job>`.
+/// Functions with multiple type parameters are labeled with the tuple of their types (like `(String, u64)`).
+/// Only the type parameters declared on the function itself are included
+/// (not those of the surrounding impl block or `impl Trait` arguments).
+///
+/// Note that every type the function is used with creates a new set of time series.
+///
+/// ### `label`
+///
+/// Example:
+/// ```rust
+/// # use autometrics::autometrics;
+/// pub struct Consumer { shard: &'static str }
+///
+/// impl Consumer {
+///     #[autometrics(label(key = "shard", value = self.shard))]
+///     pub fn poll(&self) { }
+/// }
+/// ```
+///
+/// Add a label to the function's counter and histogram, with a value that is computed every time the
+/// function is called. The value is evaluated before the function body runs, so it can use the
+/// function's arguments (including `self`). It must be a `&'static str`, a `bool`, or a type that
+/// implements [`LabelValue`], like an enum with a small set of values.
+///
+/// A function can have up to two of these labels, and their keys cannot be the ones that
+/// autometrics already uses (like `function` or `result`).
+/// Note that every value creates a new set of time series, so only use this for labels with
+/// a small number of possible values, never for IDs or user input.
+///
+/// With the `prometheus` backend, the labels are not added to the histogram views
+/// or the `function_response_size_bytes` histogram.
+///
+/// ### `alias`
+///
+/// Example:
+/// ```rust
+/// # use autometrics::autometrics;
+/// // This function used to be called `get_user`
+/// #[autometrics(alias = "get_user")]
+/// pub fn load_user() { }
+/// ```
+///
+/// Keep track of the previous name of a renamed function, so that dashboards and alerts that use the old
+/// `function` label can be carried over. By default, the function reports a `function_renamed_info` series
+/// with the `function`, `module`, and `previous_function` labels. With
+/// [`AliasMode::Label`](crate::settings::AliasMode::Label), the `previous_function` label is added
+/// to the function's counter and histogram instead.
+///
+/// The alias is the value of the old `function` label, so it includes the type name for methods
+/// (like `Database::get_user`). It can only be set on individual functions, not on impl blocks or traits.
+/// Remove it once the transition period is over. To rewrite the old names in Prometheus instead,
+/// generate the relabeling rules with `autometrics generate-relabel-config`.
+///
+/// ### `buckets`
+///
+/// Example:
+/// ```rust
+/// # use autometrics::autometrics;
+/// # #[cfg(any(prometheus, prometheus_client, not(any(metrics, opentelemetry))))]
+/// #[autometrics(buckets = [0.001, 0.005, 0.01, 0.05])]
+/// pub fn lookup_cache() { }
+/// ```
+///
+/// Use these histogram buckets for the function's latency instead of the ones configured
+/// in the [`settings`](crate::settings). This is useful for latency-critical functions
+/// that need finer buckets than the rest of the application.
+///
+/// This is only supported by the `prometheus-client` and `prometheus` backends.
+/// The `opentelemetry` and `metrics` backends configure the buckets per metric rather than
+/// per function, so the argument fails to compile if they are the only enabled backends.
+/// If they are enabled alongside one of the supported backends, they use the buckets from the settings.
+///
+/// ### `fine_grained_latency`
+///
+/// Example:
+/// ```rust
+/// # use autometrics::autometrics;
+/// #[autometrics(fine_grained_latency)]
+/// pub fn decode_frame() { }
+/// ```
+///
+/// The default buckets start at 5 milliseconds, so they cannot tell apart the latencies of functions
+/// that take a few microseconds, such as in-memory caches or codecs. Pass this argument to use the
+/// [`FINE_GRAINED_LATENCY_BUCKETS`](crate::settings::FINE_GRAINED_LATENCY_BUCKETS),
+/// which go from 1 microsecond to 10 milliseconds, for the function's histogram.
+///
+/// The latencies are still recorded in seconds, so the metric names and queries stay the same.
+/// Latency objectives for these functions must use one of the thresholds covered by the buckets
+/// (`ObjectiveLatency::Ms5` or `ObjectiveLatency::Ms10`).
+///
+/// Like `buckets`, this is only supported by the `prometheus-client` and `prometheus` backends.
+/// With the `opentelemetry` and `metrics` backends, record the fine-grained latencies of all functions
+/// in an additional histogram instead, using
+/// [`histogram_view`](crate::settings::AutometricsSettingsBuilder::histogram_view)
+/// with the `FINE_GRAINED_LATENCY_BUCKETS`.
+///
+/// ### `sample_rate` and `skip_if`
+///
+/// Example:
+/// ```rust
+/// # use autometrics::autometrics;
+/// # fn is_health_check() -> bool { false }
+/// #[autometrics(sample_rate = 0.01)]
+/// pub fn parse_header() { }
+///
+/// #[autometrics(skip_if = is_health_check)]
+/// pub fn handle_request() { }
+/// ```
+///
+/// Recording the metrics of a call is cheap, but it can still add up for extremely hot functions.
+/// These arguments reduce the number of calls that are recorded:
+///
+/// - `sample_rate` records a random fraction of the calls (here, 1%).
+///   Each recorded call increments the `function.calls` counter by the inverse of the sample rate (here, 100),
+///   so the request and error rates stay accurate on average. The sample rate can be any expression
+///   that evaluates to an `f64` between 0 and 1
+/// - `skip_if` takes a function (or closure) with no arguments that returns a `bool`.
+///   It is called before the instrumented function runs, and if it returns `true`, the call is not recorded at all
+///
+/// Sampling trades accuracy for speed:
+/// - The counters are estimates, which are less accurate for functions that are rarely called
+///   or rarely return errors. Sample rates that are not the inverse of a whole number are
+///   rounded randomly, so they are only accurate on average
+/// - The latency histogram and the `track_concurrency` gauge only include the recorded calls.
+///   The latency percentiles are unaffected, but the number of calls in the histogram is
+///   lower than the actual number of calls
+///
+/// ### `track_size`
+///
+/// Example:
+/// ```rust
+/// # use autometrics::autometrics;
+/// pub struct Response {
+///     body: Vec<u8>,
+/// }
+///
+/// fn body_len(response: &Response) -> usize {
+///     response.body.len()
+/// }
+///
+/// #[autometrics(track_size = body_len)]
+/// pub fn download() -> Response {
+///     Response { body: Vec::new() }
+/// }
+/// ```
+///
+/// Pass a function (or closure) that takes a reference to the return value and returns its size
+/// in bytes, to record it in the `function_response_size_bytes` histogram, with the same labels
+/// as the latency histogram. Growing payloads are often a leading indicator of latency regressions,
+/// for example in gRPC or HTTP handlers.
+///
+/// The histogram buckets go from 64 bytes to 16 MiB. Functions that panic have no return value,
+/// so their size is not recorded. Sampled calls are recorded once, like their latency.
+///
+/// ### `span_events`
+///
+/// Example:
+/// ```rust
+/// # use autometrics::{autometrics, objectives::*};
+/// const API_SLO: Objective = Objective::new("api")
+///     .latency(ObjectiveLatency::Ms250, ObjectivePercentile::P99);
+///
+/// #[autometrics(objective = API_SLO, span_events)]
+/// pub fn get_user() { }
+/// ```
+///
+/// Attach an `autometrics.slow_call` event to the current `tracing` span when a call takes longer than
+/// the latency threshold of the function's objective, and an `autometrics.failed_call` event when it
+/// returns an error. See the [`span_events`](crate::span_events) module for the fields of the events.
+///
+/// This requires the `span-events` feature. Without it, the argument has no effect.
+///
+/// ### `count_only` and `inline_hint`
+///
+/// Example:
+/// ```rust
+/// # use autometrics::autometrics;
+/// # pub struct Point { x: f64 }
+/// impl Point {
+///     #[autometrics(count_only, inline_hint)]
+///     pub fn x(&self) -> f64 {
+///         self.x
+///     }
+/// }
+/// ```
+///
+/// For extremely hot accessors, even reading the clock to measure the latency can be noticeable.
+/// With `count_only`, the function's calls are only counted: the clock is never read and
+/// the latency histogram has no series for the function. The `function.calls` counter
+/// (including the `result` and `caller` labels) is recorded as usual, and calls that panic
+/// are counted with the `panic="true"` label. The calls are not observed by the call edges or
+/// the cardinality watchdog, and each call increments the counter directly (the calls are not
+/// batched per thread); with the `prometheus` backend, this uses the series handle kept by the call site.
+///
+/// The wrapper is not entirely branch-free: each call still loads the call site's settings
+/// (a single atomic load after the first call) and checks whether recording is paused.
+/// The build info is set when the call site first loads its settings rather than on every call,
+/// and a call that returns never checks whether the thread is panicking; the panic labels are
+/// only created by the guard that is dropped while unwinding. When the library is connected to
+/// a plugin host (with the `plugin` feature), the calls are counted by the host.
+///
+/// `inline_hint` adds `#[inline]` to the instrumented function, so that the wrapper around the body
+/// can be inlined into its callers across crates.
+///
+/// Because there is no latency to record, `count_only` cannot be combined with `track_concurrency`,
+/// `buckets`, `fine_grained_latency`, `recursive`, `sample_rate`, `skip_if`, `track_size`, or `span_events`, and the function
+/// should not be included in a latency objective.
+///
+/// ### `no_caller`
+///
+/// Example:
+/// ```rust
+/// # use autometrics::autometrics;
+/// #[autometrics(no_caller)]
+/// pub fn parse_header(header: &str) -> Option<&str> {
+///     header.split_once(':').map(|(_, value)| value.trim())
+/// }
+/// ```
+///
+/// By default, instrumented functions read the function that called them from a task-local variable
+/// for the `caller_function` and `caller_module` labels, and set themselves as the caller of the functions they call.
+/// With `no_caller`, the function skips both: its caller labels are left empty, and the functions
+/// it calls report the closest instrumented function above it as their caller.
+///
+/// Recursive calls cannot be detected without the caller, so `no_caller` cannot be combined with `recursive`.
+/// To disable caller tracking for every function, use
+/// [`AutometricsSettingsBuilder::disable_caller_tracking`](crate::settings::AutometricsSettingsBuilder::disable_caller_tracking).
+///
+/// ### `no_docs`
+///
+/// Example:
+/// ```rust
+/// # use autometrics::autometrics;
+/// #[autometrics(no_docs)]
+/// pub fn internal_helper() { }
+/// ```
+///
+/// Leave the Prometheus query links out of the function's documentation. To leave them out for
+/// every function, enable the `no-doc-links` feature or set the `AUTOMETRICS_DISABLE_DOCS`
+/// environment variable at compile time.
+///
 /// ### `objective`
 ///
 /// Example:
@@ -114,9 +557,32 @@ mod tracker;
 ///
 /// Include this function's metrics in the specified [`Objective`].
 ///
+/// The objective can also be defined inline, using the same fields as the
+/// objectives YAML file of the `objectives-yaml` feature:
+/// ```rust
+/// # use autometrics::autometrics;
+/// #[autometrics(objective(
+///     name = "api",
+///     success_rate = 99.9,
+///     latency(threshold = 0.25, percentile = 99),
+/// ))]
+/// pub fn handler() {
+///    // ...
+/// }
+/// ```
+///
+/// Values that do not match one of the [`ObjectivePercentile`] or [`ObjectiveLatency`] variants
+/// require the `custom-objective-percentile` or `custom-objective-latency` feature.
+///
 /// [`Objective`]: crate::objectives::Objective
+/// [`ObjectivePercentile`]: crate::objectives::ObjectivePercentile
+/// [`ObjectiveLatency`]: crate::objectives::ObjectiveLatency
 pub use autometrics_macros::autometrics;
 
+pub use capabilities::{capabilities, Backend, Capabilities, ExemplarsSource};
+pub use labels::{LabelValue, ResultStatus};
+pub use lifecycle::{flush, shutdown, FlushError};
+
 /// # Customize how types map to the Autometrics `result` label.
 ///
 /// The `ResultLabels` derive macro allows you to specify
@@ -162,6 +628,43 @@ pub use autometrics_macros::autometrics;
 /// failure from your handler that should trigger alerts and consume the "error
 /// budget" of the service.
 ///
+/// ## Computing the label from the inner value
+///
+/// Instead of a fixed result, `#[label(result_fn = path)]` calls the given function with a reference to
+/// the value inside of the variant, which must have a single field. The function returns `Some("ok")`,
+/// `Some("error")`, or `None` to infer the label from the context like a variant without the attribute.
+/// This also works for enums with generic parameters:
+///
+/// ```rust,ignore
+/// use autometrics::ResultLabels;
+///
+/// fn status_label(status: &u16) -> Option<&'static str> {
+///     Some(if *status >= 500 { "error" } else { "ok" })
+/// }
+///
+/// #[derive(ResultLabels)]
+/// pub enum Response<T> {
+///     #[label(result_fn = status_label)]
+///     Status(u16),
+///     #[label(result = "ok")]
+///     Body(T),
+/// }
+/// ```
+///
+/// ## Structs
+///
+/// The macro can also be derived for structs, with a single `#[label(result = "...")]` or
+/// `#[label(result_fn = path)]` attribute on the struct itself. The function is then called with
+/// a reference to the struct. Structs without the attribute are labelled from the context.
+///
+/// ```rust,ignore
+/// use autometrics::ResultLabels;
+///
+/// #[derive(ResultLabels)]
+/// #[label(result = "ok")]
+/// pub struct NotFound;
+/// ```
+///
 /// ## Per-function labelling
 ///
 /// The `ResultLabels` macro does _not_ have the granularity to behave
@@ -174,6 +677,34 @@ pub use autometrics_macros::autometrics;
 /// directives have priority over the ResultLabels annotations.
 pub use autometrics_macros::ResultLabels;
 
+/// # Write tests for the metrics of instrumented functions.
+///
+/// The `autometrics_test` attribute turns a function into a test that initializes the
+/// [`prometheus_exporter`](crate::prometheus_exporter) and passes it a [`TestMetrics`](crate::testing::TestMetrics),
+/// which only counts the calls made after the test started:
+///
+/// ```rust
+/// use autometrics::{autometrics, autometrics_test, testing::TestMetrics};
+///
+/// #[autometrics]
+/// async fn fetch_user(id: u64) -> Result<(), ()> {
+///     Ok(())
+/// }
+///
+/// #[autometrics_test]
+/// async fn records_calls(metrics: TestMetrics) {
+///     fetch_user(1).await.unwrap();
+///     assert_eq!(metrics.successes("fetch_user"), 1);
+/// }
+/// ```
+///
+/// The argument is optional. Async tests are run with the attribute of the async runtime
+/// (like `#[tokio::test]`) if it is added after `#[autometrics_test]`, or with a minimal executor otherwise.
+///
+/// See the [`testing`](crate::testing) module for details.
+#[cfg(prometheus_exporter)]
+pub use autometrics_macros::autometrics_test;
+
 /// Non-public API, used by the autometrics macro.
 // Note that this needs to be publicly exported (despite being called private)
 // because it is used by code generated by the autometrics macro.
@@ -184,17 +715,74 @@ pub use autometrics_macros::ResultLabels;
 // so you don't get any autocompletion or type checking.
 #[doc(hidden)]
 pub mod __private {
-    #[cfg(debug_assertions)]
     use crate::objectives::Objective;
+    use crate::poison;
     use crate::settings::get_settings;
     use crate::task_local::LocalKey;
-    use std::{cell::RefCell, thread_local};
+    use once_cell::sync::Lazy;
+    use std::collections::{HashMap, HashSet};
+    use std::future::Future;
+    use std::{any::type_name, cell::RefCell, fmt, sync::RwLock, thread_local};
 
     pub use crate::constants::*;
     pub use crate::labels::*;
-    pub use crate::tracker::{AutometricsTracker, TrackMetrics};
+    pub use crate::registry::FunctionId;
+    pub use crate::sampling::sample_weight;
+    pub use crate::settings::AutometricsSettings;
+    pub use crate::tracker::{
+        set_build_timestamp, AutometricsTracker, CallSite, CountGuard, PanicGuard, TrackMetrics,
+    };
+    pub use autometrics_macros::doc_queries;
+    pub use once_cell::sync::OnceCell;
     pub use spez::spez;
 
+    /// Implemented if one of the enabled backends can record the latencies of a function with
+    /// the buckets set with the `buckets` argument of the `autometrics` macro
+    #[diagnostic::on_unimplemented(
+        message = "the `buckets` argument is only supported by the `prometheus` and `prometheus-client` backends",
+        label = "the enabled backends cannot use per-function buckets",
+        note = "the `opentelemetry` and `metrics` backends configure the buckets per metric, so set them with views or in the recorder instead"
+    )]
+    pub trait PerFunctionBuckets {}
+
+    #[cfg(any(prometheus, prometheus_client, not(any(metrics, opentelemetry))))]
+    impl PerFunctionBuckets for () {}
+
+    /// Fails to compile if none of the enabled backends supports per-function buckets
+    pub const fn check_per_function_buckets<T: PerFunctionBuckets>() {}
+
+    /// Apply the `ok_if` or `error_if` predicate to the return value of an instrumented function
+    pub fn check_predicate<T: ?Sized>(value: &T, predicate: impl FnOnce(&T) -> bool) -> bool {
+        predicate(value)
+    }
+
+    /// Get the value of a label set with the `label(key = "...", value = ...)` argument
+    pub fn label_value<T: crate::LabelValue>(value: &T) -> &'static str {
+        value.label_value()
+    }
+
+    /// Measure the return value of an instrumented function with the `track_size` function
+    pub fn measure_size<T: ?Sized>(value: &T, size: impl FnOnce(&T) -> usize) -> usize {
+        size(value)
+    }
+
+    /// Classify the error returned by an instrumented function, for the `error` label
+    pub fn classify_error(error: &(dyn std::error::Error + 'static)) -> Option<&'static str> {
+        crate::error_classifier::classify(error)
+    }
+
+    /// The buckets used by the `fine_grained_latency` argument, in the configured duration unit
+    pub fn fine_grained_latency_buckets() -> &'static [f64] {
+        crate::settings::get_settings()
+            .duration_unit
+            .fine_grained_latency_buckets()
+    }
+
+    /// Load the settings, so that the generated code can cache them
+    pub fn settings() -> &'static AutometricsSettings {
+        get_settings()
+    }
+
     /// Track the current function's name and module
     #[derive(Clone, Copy)]
     pub struct CallerInfo {
@@ -202,6 +790,218 @@ pub mod __private {
         pub caller_module: &'static str,
     }
 
+    impl CallerInfo {
+        /// The caller labels of functions that are not called by another instrumented function,
+        /// or when caller tracking is disabled
+        pub const EMPTY: CallerInfo = CallerInfo {
+            caller_function: "",
+            caller_module: "",
+        };
+
+        /// The function that called the current function, unless caller tracking is disabled in the settings
+        pub fn current(settings: &AutometricsSettings) -> Self {
+            if settings.track_callers {
+                CALLER.get()
+            } else {
+                Self::EMPTY
+            }
+        }
+
+        /// Whether the caller is the given function, which means the function is calling itself
+        pub fn is_function(&self, function: &str, module: &str) -> bool {
+            self.caller_function == function && self.caller_module == module
+        }
+
+        /// Call the closure with this as the caller of the functions it calls,
+        /// unless caller tracking is disabled in the settings
+        pub fn sync_scope<R>(self, settings: &AutometricsSettings, f: impl FnOnce() -> R) -> R {
+            if settings.track_callers {
+                CALLER.sync_scope(self, f)
+            } else {
+                f()
+            }
+        }
+
+        /// Await the future with this as the caller of the functions it calls,
+        /// unless caller tracking is disabled in the settings
+        pub async fn scope<F: Future>(
+            self,
+            settings: &AutometricsSettings,
+            future: F,
+        ) -> F::Output {
+            if settings.track_callers {
+                CALLER.scope(self, future).await
+            } else {
+                future.await
+            }
+        }
+    }
+
+    /// The names of trait methods, keyed by the implementor's type name and the method name.
+    /// These are leaked once each so they can be used as `&'static str` labels.
+    static TRAIT_METHOD_NAMES: Lazy<RwLock<HashMap<(&'static str, &'static str), &'static str>>> =
+        Lazy::new(Default::default);
+
+    /// Get the name of a default trait method, identified by the type implementing
+    /// the trait in the same way as other methods (`Implementor::method`)
+    pub fn trait_method_name<T: ?Sized>(method: &'static str) -> &'static str {
+        let key = (type_name::<T>(), method);
+        if let Some(name) = poison::read(&TRAIT_METHOD_NAMES, "trait_method_names").get(&key) {
+            return name;
+        }
+
+        poison::write(&TRAIT_METHOD_NAMES, "trait_method_names")
+            .entry(key)
+            .or_insert_with(|| {
+                Box::leak(format!("{}::{}", short_type_name(key.0), method).into_boxed_str())
+            })
+    }
+
+    /// The values of the `type_params` label, keyed by the type name of the type parameters.
+    /// These are leaked once each so they can be used as `&'static str` labels.
+    static TYPE_PARAMS: Lazy<RwLock<HashMap<&'static str, &'static str>>> =
+        Lazy::new(Default::default);
+
+    /// Get the value of the `type_params` label for a generic function, which is the short name of
+    /// its type parameter (or the tuple of its type parameters, like `(String, Vec<u8>)`)
+    pub fn type_params<T: ?Sized>() -> &'static str {
+        let key = type_name::<T>();
+        if let Some(type_params) = poison::read(&TYPE_PARAMS, "type_params").get(key) {
+            return type_params;
+        }
+
+        poison::write(&TYPE_PARAMS, "type_params")
+            .entry(key)
+            .or_insert_with(|| Box::leak(short_type_name(key).into_boxed_str()))
+    }
+
+    /// The names used for the `error_type` label.
+    /// These are leaked once each so they can be used as `&'static str` labels.
+    static ERROR_TYPES: Lazy<RwLock<HashSet<&'static str>>> = Lazy::new(Default::default);
+
+    thread_local! {
+        /// The names that were already looked up on this thread, so that the global set
+        /// is only locked the first time a thread sees a name
+        static LOCAL_ERROR_TYPES: RefCell<HashSet<&'static str>> = RefCell::new(HashSet::new());
+        /// The short names of the error types that are not enums, keyed by their full type name
+        static ERROR_TYPE_NAMES: RefCell<HashMap<&'static str, &'static str>> = RefCell::new(HashMap::new());
+    }
+
+    /// Get the name of the error variant or type, for the `error_type` label.
+    ///
+    /// The variant name is taken from the `Debug` representation of the error when it starts with
+    /// an identifier, like the ones produced by `#[derive(Debug)]` (`Timeout`, `Io(..)` or `Parse { .. }`).
+    /// Otherwise, the name of the error type is used, so the number of distinct values stays bounded.
+    ///
+    /// Only the start of the `Debug` representation is formatted, up to the first character after the identifier.
+    pub fn error_type<E: std::error::Error>(error: &E) -> &'static str {
+        let mut variant = VariantName::default();
+        // Formatting is stopped with an error as soon as the variant name is known
+        let _ = fmt::Write::write_fmt(&mut variant, format_args!("{error:?}"));
+        if let Some(name) = variant.name() {
+            return intern_error_type(name);
+        }
+
+        let type_name = type_name::<E>();
+        if let Some(name) = ERROR_TYPE_NAMES.with(|names| names.borrow().get(type_name).copied()) {
+            return name;
+        }
+        let name = intern_error_type(&short_type_name(type_name));
+        ERROR_TYPE_NAMES.with(|names| names.borrow_mut().insert(type_name, name));
+        name
+    }
+
+    /// Get the `&'static str` for the name of an error type, leaking it the first time it is seen
+    fn intern_error_type(name: &str) -> &'static str {
+        if let Some(name) = LOCAL_ERROR_TYPES.with(|names| names.borrow().get(name).copied()) {
+            return name;
+        }
+
+        let interned = poison::read(&ERROR_TYPES, "error_types").get(name).copied();
+        let name = interned.unwrap_or_else(|| {
+            let mut error_types = poison::write(&ERROR_TYPES, "error_types");
+            match error_types.get(name) {
+                Some(name) => name,
+                None => {
+                    let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+                    error_types.insert(name);
+                    name
+                }
+            }
+        });
+        LOCAL_ERROR_TYPES.with(|names| names.borrow_mut().insert(name));
+        name
+    }
+
+    /// The longest variant name that is read from the `Debug` representation of an error.
+    /// Longer names are not variants produced by `#[derive(Debug)]` in practice, so the type name is used instead
+    const MAX_VARIANT_NAME_LEN: usize = 64;
+
+    /// Reads the identifier at the start of a `Debug` representation, along with the two characters after it
+    #[derive(Default)]
+    struct VariantName {
+        name: String,
+        /// The first two characters after the identifier, once it ended
+        rest: Option<(char, Option<char>)>,
+        too_long: bool,
+    }
+
+    impl VariantName {
+        /// The identifier, if it is followed by nothing, by a tuple, or by the fields of a struct variant
+        fn name(&self) -> Option<&str> {
+            let is_variant = !self.too_long
+                && self
+                    .name
+                    .starts_with(|c: char| c.is_alphabetic() || c == '_')
+                && matches!(self.rest, None | Some(('(', _)) | Some((' ', Some('{'))));
+            is_variant.then_some(self.name.as_str())
+        }
+    }
+
+    impl fmt::Write for VariantName {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            for c in s.chars() {
+                match &mut self.rest {
+                    None if c.is_alphanumeric() || c == '_' => {
+                        if self.name.len() + c.len_utf8() > MAX_VARIANT_NAME_LEN {
+                            self.too_long = true;
+                            return Err(fmt::Error);
+                        }
+                        self.name.push(c);
+                    }
+                    None => self.rest = Some((c, None)),
+                    Some((_, next)) => {
+                        *next = Some(c);
+                        return Err(fmt::Error);
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    /// Remove the module paths from a type name, so that `my_crate::db::Postgres<alloc::string::String>`
+    /// becomes `Postgres<String>`, like the type names used for the methods in impl blocks
+    fn short_type_name(type_name: &str) -> String {
+        let mut short = String::with_capacity(type_name.len());
+        let mut segment = String::new();
+        let mut chars = type_name.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c == ':' && chars.peek() == Some(&':') {
+                chars.next();
+                segment.clear();
+            } else if c.is_alphanumeric() || c == '_' {
+                segment.push(c);
+            } else {
+                short.push_str(&segment);
+                segment.clear();
+                short.push(c);
+            }
+        }
+        short.push_str(&segment);
+        short
+    }
+
     /// Task-local value used for tracking which function called the current function
     pub static CALLER: LocalKey<CallerInfo> = {
         // This does the same thing as the tokio::thread_local macro with the exception that
@@ -209,51 +1009,81 @@ pub mod __private {
         // The tokio macro does not allow you to get the value before setting it.
         // However, in our case, we want it to simply return empty strings rather than panicking.
         thread_local! {
-            static CALLER_KEY: RefCell<Option<CallerInfo>> = const { RefCell::new(Some(CallerInfo {
-                caller_function: "",
-                caller_module: "",
-            })) };
+            static CALLER_KEY: RefCell<Option<CallerInfo>> = const { RefCell::new(Some(CallerInfo::EMPTY)) };
         }
 
         LocalKey { inner: CALLER_KEY }
     };
 
     // Re-export linkme so that it can be used by the macro-generated code
-    #[cfg(debug_assertions)]
+    #[cfg(function_registry)]
     pub mod linkme {
         pub use linkme::*;
     }
 
-    /// In debug mode, we use linkme to collect all the function descriptions
-    /// so that we can initialize the counters to zero.
+    /// In debug mode (or with the `function-registry` feature), we use linkme to collect all
+    /// the function descriptions so that we can initialize the counters to zero.
     /// This exposes the details of instrumented functions to Prometheus
     /// before they are called for the first time.
-    #[cfg(debug_assertions)]
+    #[cfg(function_registry)]
     #[linkme::distributed_slice]
     pub static FUNCTION_DESCRIPTIONS: [FunctionDescription] = [..];
 
-    #[cfg(debug_assertions)]
     pub struct FunctionDescription {
-        pub name: &'static str,
-        pub module: &'static str,
-        pub objective: Option<Objective>,
+        pub id: FunctionId,
+        pub objective: fn() -> Option<Objective>,
+    }
+
+    /// The name of the crate that is being compiled, from the `CARGO_CRATE_NAME` environment variable
+    pub const fn crate_name(crate_name: Option<&'static str>) -> &'static str {
+        match crate_name {
+            Some(crate_name) => crate_name,
+            None => "",
+        }
+    }
+
+    /// Add an instrumented function to the [`registry`](crate::registry) the first time it is called
+    pub fn observe_function(id: &FunctionId) {
+        crate::registry::observe(id);
+    }
+
+    /// Report the previous name of a function with the `alias` argument, the first time it is called
+    pub fn register_alias(
+        settings: &'static AutometricsSettings,
+        function: &'static str,
+        module: &'static str,
+        alias: &'static str,
+    ) {
+        if settings.alias_mode == crate::settings::AliasMode::Info {
+            crate::tracker::set_function_renamed(&FunctionRenamedLabels::new(
+                settings, function, module, alias,
+            ));
+        }
+    }
+
+    /// Initialize the counter of a function passed to the [`register`](crate::register) macro
+    pub fn register_function(function: &FunctionDescription) {
+        crate::registry::observe(&function.id);
+        AutometricsTracker::intitialize_metrics(&[CounterLabels::from(function)]);
+        #[cfg(feature = "plugin")]
+        crate::plugin::register_function(function);
     }
 
-    #[cfg(debug_assertions)]
     impl From<&FunctionDescription> for CounterLabels {
         fn from(function: &FunctionDescription) -> Self {
-            let (objective_name, objective_percentile) = match &function.objective {
+            let (objective_name, objective_percentile) = match (function.objective)() {
                 Some(Objective {
                     name,
                     success_rate: Some(percentile),
                     ..
-                }) => (Some(*name), Some(*percentile)),
+                }) => (Some(name), Some(percentile)),
                 _ => (None, None),
             };
+            let settings = get_settings();
             CounterLabels {
-                function: function.name,
-                module: function.module,
-                service_name: &get_settings().service_name,
+                function: settings.label_value(function.id.name()),
+                module: settings.module_label_value(function.id.module()),
+                service_name: &settings.service_name,
                 caller_function: "",
                 caller_module: "",
                 result: Some(ResultLabel::Ok),
@@ -261,6 +1091,12 @@ pub mod __private {
                 error: None,
                 objective_name,
                 objective_percentile,
+                recursive: None,
+                error_type: None,
+                type_params: None,
+                panic: None,
+                custom_labels: Default::default(),
+                call_site: CallSiteRef(None),
             }
         }
     }
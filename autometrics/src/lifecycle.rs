@@ -0,0 +1,77 @@
+/// An error returned by [`flush`] or [`shutdown`].
+///
+/// Each variant is only available when the exporter it refers to is enabled.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum FlushError {
+    /// The OpenTelemetry push exporter could not export the metrics.
+    #[cfg(feature = "otel-push-exporter")]
+    #[error(transparent)]
+    OtelPushExporter(#[from] opentelemetry::metrics::MetricsError),
+
+    /// The metrics could not be pushed to the Prometheus Pushgateway.
+    #[cfg(push_gateway)]
+    #[error(transparent)]
+    PushGateway(#[from] crate::prometheus_exporter::PushError),
+}
+
+/// Export the metrics that have been collected so far, without waiting for the next export interval.
+///
+/// This flushes:
+/// - the meter providers started by the [`otel_push_exporter`](crate::otel_push_exporter)
+/// - the threads started by [`prometheus_exporter::push_to_gateway`](crate::prometheus_exporter::push_to_gateway)
+/// - the sink of the [`wide_events`](crate::wide_events)
+///
+/// Everything is flushed even if one of the exporters fails, and the first error is returned.
+/// Exemplars are attached to the metrics as they are recorded, so there is nothing else to flush.
+pub fn flush() -> Result<(), FlushError> {
+    #[allow(unused_mut)]
+    let mut result = Ok(());
+    #[cfg(feature = "otel-push-exporter")]
+    {
+        result = result.and(crate::otel_push_exporter::force_flush().map_err(FlushError::from));
+    }
+    #[cfg(push_gateway)]
+    {
+        result =
+            result.and(crate::prometheus_exporter::flush_push_gateways().map_err(FlushError::from));
+    }
+    #[cfg(wide_events)]
+    crate::wide_events::flush();
+    result
+}
+
+/// Export the metrics one last time and stop the background exporters.
+///
+/// Call this before the process exits (for example, at the end of `main` or of a short-lived job)
+/// so that the calls made since the last export interval are not lost. This shuts down the meter
+/// providers started by the [`otel_push_exporter`](crate::otel_push_exporter), stops the threads
+/// started by [`prometheus_exporter::push_to_gateway`](crate::prometheus_exporter::push_to_gateway)
+/// after their final push, and flushes the sink of the [`wide_events`](crate::wide_events).
+///
+/// Calling this more than once is harmless: the exporters that were already stopped are skipped.
+/// Like [`flush`], it returns the first error after trying every exporter.
+///
+/// ```rust,no_run
+/// // Initialize the exporters and run the job...
+///
+/// if let Err(err) = autometrics::shutdown() {
+///     eprintln!("failed to export the last metrics: {err}");
+/// }
+/// ```
+pub fn shutdown() -> Result<(), FlushError> {
+    #[allow(unused_mut)]
+    let mut result = Ok(());
+    #[cfg(feature = "otel-push-exporter")]
+    {
+        result = result.and(crate::otel_push_exporter::shutdown().map_err(FlushError::from));
+    }
+    #[cfg(push_gateway)]
+    {
+        result =
+            result.and(crate::prometheus_exporter::stop_push_gateways().map_err(FlushError::from));
+    }
+    #[cfg(wide_events)]
+    crate::wide_events::flush();
+    result
+}
@@ -0,0 +1,12 @@
+//! Middleware for instrumenting services without annotating every handler.
+
+/// Moved to [`integrations::tonic`](crate::integrations::tonic).
+#[cfg(feature = "tonic")]
+#[deprecated(note = "moved to `autometrics::integrations::tonic`")]
+pub mod tonic {
+    #[deprecated(note = "moved to `autometrics::integrations::tonic::AutometricsLayer`")]
+    pub type AutometricsLayer = crate::integrations::tonic::AutometricsLayer;
+    #[deprecated(note = "moved to `autometrics::integrations::tonic::AutometricsService`")]
+    pub type AutometricsService<S> = crate::integrations::tonic::AutometricsService<S>;
+}
+pub mod tower;
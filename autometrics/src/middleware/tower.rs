@@ -0,0 +1,247 @@
+//! Instrument the routes of any HTTP service built with [`tower`](https://crates.io/crates/tower),
+//! such as [`axum`](https://crates.io/crates/axum), [`hyper`](https://crates.io/crates/hyper),
+//! or [`tonic`](https://crates.io/crates/tonic) services.
+//!
+//! The [`AutometricsLayer`] tracks every request as a call to a function named after its route,
+//! in the module `http`. The route is determined by a [`RouteExtractor`], which can be any closure
+//! that returns the route of a request. Requests without a route are passed through without being tracked.
+//!
+//! Responses with a `5xx` status code and errors returned by the service are counted as errors.
+//!
+//! Handlers (and any other functions) instrumented with the `autometrics` macro
+//! are reported with the route as their caller.
+//!
+//! # Example
+//! ```rust
+//! use autometrics::middleware::tower::AutometricsLayer;
+//! use autometrics::objectives::{Objective, ObjectivePercentile};
+//! use http::{Request, Response};
+//! use std::convert::Infallible;
+//! use tower::{service_fn, ServiceBuilder};
+//!
+//! const API_SLO: Objective = Objective::new("api").success_rate(ObjectivePercentile::P99_9);
+//!
+//! let service = ServiceBuilder::new()
+//!     .layer(
+//!         // Use the first segment of the path as the route, to keep the number of routes bounded
+//!         AutometricsLayer::new(|request: &Request<String>| {
+//!             request.uri().path().split('/').nth(1).map(|segment| format!("/{segment}"))
+//!         })
+//!         .objective(API_SLO),
+//!     )
+//!     .service(service_fn(|_request: Request<String>| async {
+//!         Ok::<_, Infallible>(Response::new(String::from("Hello world!")))
+//!     }));
+//! ```
+//!
+//! With the `axum` feature, [`AutometricsLayer::matched_path`] uses the route that the request
+//! was matched to by the `axum` router (for example, `/users/:id`), and
+//! [`AutometricsLayer::method_and_matched_path`] prefixes it with the HTTP method (for example, `GET /users/:id`).
+//!
+//! The `function` label values need to be `&'static str`s, so each route is leaked once.
+//! The extractor must only return a bounded set of routes (for example, the route templates
+//! rather than the request paths), because every route also creates a new set of time series.
+
+use crate::__private::{
+    AutometricsTracker, CallerInfo, CounterLabels, HistogramLabels, TrackMetrics, ERROR_KEY, OK_KEY,
+};
+use crate::objectives::Objective;
+use crate::poison;
+use crate::settings::get_settings;
+use ::tower::{Layer, Service};
+use http::{Request, Response};
+use once_cell::sync::Lazy;
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::RwLock;
+use std::task::{Context, Poll};
+
+/// The value of the `module` label for the metrics of the routes
+const MODULE: &str = "http";
+
+static ROUTES: Lazy<RwLock<HashSet<&'static str>>> = Lazy::new(Default::default);
+
+/// Determines the route of a request, which is used as the `function` label.
+///
+/// This is implemented for closures that take a [`Request`] and return an `Option<String>`.
+pub trait RouteExtractor<B> {
+    /// The route of the request, or `None` if the request should not be tracked.
+    fn route<'a>(&self, request: &'a Request<B>) -> Option<Cow<'a, str>>;
+}
+
+impl<B, F> RouteExtractor<B> for F
+where
+    F: Fn(&Request<B>) -> Option<String>,
+{
+    fn route<'a>(&self, request: &'a Request<B>) -> Option<Cow<'a, str>> {
+        self(request).map(Cow::Owned)
+    }
+}
+
+/// Uses the route that the request was matched to by the `axum` router.
+///
+/// Note that the layer needs to be added to the router (or inside of it) for the route to be available.
+#[cfg(feature = "axum")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MatchedPathExtractor;
+
+#[cfg(feature = "axum")]
+impl<B> RouteExtractor<B> for MatchedPathExtractor {
+    fn route<'a>(&self, request: &'a Request<B>) -> Option<Cow<'a, str>> {
+        request
+            .extensions()
+            .get::<::axum::extract::MatchedPath>()
+            .map(|path| Cow::Borrowed(path.as_str()))
+    }
+}
+
+/// Uses the HTTP method and the route that the request was matched to by the `axum` router,
+/// separated by a space (like `GET /users/:id`).
+#[cfg(feature = "axum")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MethodAndMatchedPathExtractor;
+
+#[cfg(feature = "axum")]
+impl<B> RouteExtractor<B> for MethodAndMatchedPathExtractor {
+    fn route<'a>(&self, request: &'a Request<B>) -> Option<Cow<'a, str>> {
+        MatchedPathExtractor
+            .route(request)
+            .map(|path| Cow::Owned(format!("{} {path}", request.method())))
+    }
+}
+
+/// A [`Layer`] that tracks the request rate, error rate, and latency of every route of the wrapped service.
+///
+/// See the [module documentation](self) for details.
+#[derive(Clone, Debug)]
+pub struct AutometricsLayer<E> {
+    route_extractor: E,
+    objective: Option<Objective>,
+}
+
+impl<E> AutometricsLayer<E> {
+    /// Use the given extractor to determine the route of each request.
+    pub fn new(route_extractor: E) -> Self {
+        Self {
+            route_extractor,
+            objective: None,
+        }
+    }
+
+    /// Include the metrics of every route in the given [`Objective`].
+    pub fn objective(mut self, objective: Objective) -> Self {
+        self.objective = Some(objective);
+        self
+    }
+}
+
+#[cfg(feature = "axum")]
+impl AutometricsLayer<MatchedPathExtractor> {
+    /// Use the route that the request was matched to by the `axum` router (like `/users/:id`).
+    pub fn matched_path() -> Self {
+        Self::new(MatchedPathExtractor)
+    }
+}
+
+#[cfg(feature = "axum")]
+impl AutometricsLayer<MethodAndMatchedPathExtractor> {
+    /// Use the HTTP method and the route that the request was matched to by the `axum` router
+    /// (like `GET /users/:id`), so that the methods of a route are tracked separately.
+    pub fn method_and_matched_path() -> Self {
+        Self::new(MethodAndMatchedPathExtractor)
+    }
+}
+
+impl<S, E: Clone> Layer<S> for AutometricsLayer<E> {
+    type Service = AutometricsService<S, E>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AutometricsService {
+            inner,
+            route_extractor: self.route_extractor.clone(),
+            objective: self.objective,
+        }
+    }
+}
+
+/// The [`Service`] produced by the [`AutometricsLayer`].
+#[derive(Clone, Debug)]
+pub struct AutometricsService<S, E> {
+    inner: S,
+    route_extractor: E,
+    objective: Option<Objective>,
+}
+
+impl<S, E, ReqBody, ResBody> Service<Request<ReqBody>> for AutometricsService<S, E>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+    E: RouteExtractor<ReqBody>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        let Some(function) = self
+            .route_extractor
+            .route(&request)
+            .map(|route| intern_route(&route))
+        else {
+            return Box::pin(self.inner.call(request));
+        };
+
+        let objective = self.objective;
+        let tracker = AutometricsTracker::start(None);
+        let caller = CallerInfo {
+            caller_function: function,
+            caller_module: MODULE,
+        };
+        let settings = get_settings();
+        let future = caller.sync_scope(settings, || self.inner.call(request));
+
+        Box::pin(async move {
+            let response = caller.scope(settings, future).await;
+
+            let result = match &response {
+                Ok(response) if !response.status().is_server_error() => OK_KEY,
+                _ => ERROR_KEY,
+            };
+            let counter_labels = CounterLabels::new(
+                settings,
+                function,
+                MODULE,
+                "",
+                "",
+                Some((result, None)),
+                objective,
+            );
+            let histogram_labels = HistogramLabels::new(settings, function, MODULE, objective);
+            tracker.finish(&counter_labels, &histogram_labels);
+
+            response
+        })
+    }
+}
+
+pub(crate) fn intern_route(route: &str) -> &'static str {
+    if let Some(route) = poison::read(&ROUTES, "routes").get(route).copied() {
+        return route;
+    }
+
+    let mut routes = poison::write(&ROUTES, "routes");
+    match routes.get(route) {
+        Some(route) => route,
+        None => {
+            let route: &'static str = Box::leak(route.to_string().into_boxed_str());
+            routes.insert(route);
+            route
+        }
+    }
+}
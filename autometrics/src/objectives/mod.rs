@@ -0,0 +1,476 @@
+//! # Create alerts from your function-level metrics using Service-Level Objective (SLO) best practices.
+//!
+//! Autometrics makes it easy to add Prometheus alerts using Service-Level Objectives (SLOs) to a function or group of functions.
+//!
+//! This works using pre-defined [Prometheus alerting rules](https://github.com/autometrics-dev/autometrics-shared#prometheus-recording--alerting-rules), which can be loaded via the `rule_files` field in your Prometheus configuration. By default, most of the recording rules are dormant. They are enabled by specific metric labels that can be automatically attached by autometrics.
+//!
+//! To use autometrics SLOs and alerts, create one or multiple [`Objective`]s based on the function(s) success rate and/or latency, as shown below. The `Objective` can be passed as an argument to the `autometrics` macro to include the given function in that objective.
+//!
+//! Once you've added objectives to your code, you can use the [Autometrics Service-Level Objectives(SLO) Dashboard](https://github.com/autometrics-dev/autometrics-shared#dashboards) to visualize the current status of your objective(s).
+//!
+//! ## Example
+//!
+//! ```rust
+//! use autometrics::autometrics;
+//! use autometrics::objectives::{Objective, ObjectiveLatency, ObjectivePercentile};
+//!
+//! const API_SLO: Objective = Objective::new("api")
+//!     .success_rate(ObjectivePercentile::P99_9)
+//!     .latency(ObjectiveLatency::Ms250, ObjectivePercentile::P99);
+//!
+//! #[autometrics(objective = API_SLO)]
+//! pub fn api_handler() {
+//!   // ...
+//! }
+//! ```
+//!
+//! ## Sharing objectives across services
+//!
+//! With the `objectives-yaml` feature enabled, objectives can also be loaded from a YAML file
+//! using [`from_yaml_str`]. This is useful for keeping the objectives of multiple services,
+//! which may not all be written in Rust, in a single place.
+//!
+//! ## Listing the objectives
+//!
+//! [`registered_objectives`] lists the objectives that instrumented functions are part of,
+//! along with the functions in each. Deployment tooling can use this to check that a service
+//! defines the objectives it is expected to, for example by exposing them on an endpoint or
+//! checking them in a test:
+//!
+//! ```rust
+//! # use autometrics::{autometrics, objectives::*};
+//! # const API_SLO: Objective = Objective::new("api").success_rate(ObjectivePercentile::P99_9);
+//! # #[autometrics(objective = API_SLO)]
+//! # pub fn api_handler() {}
+//! assert!(registered_objectives()
+//!     .iter()
+//!     .any(|registered| registered.objective.name() == "api"));
+//! ```
+
+#[cfg(function_registry)]
+use crate::__private::FUNCTION_DESCRIPTIONS;
+#[cfg(function_registry)]
+use crate::registry::InstrumentedFunction;
+use crate::settings::{get_settings, DurationUnit};
+#[cfg(prometheus_client)]
+use prometheus_client::encoding::{EncodeLabelValue, LabelValueEncoder};
+
+#[cfg(objectives_yaml)]
+mod yaml;
+#[cfg(objectives_yaml)]
+pub use yaml::{from_yaml_str, ObjectiveSet, ObjectivesYamlError};
+
+/// A Service-Level Objective (SLO) for a function or group of functions.
+///
+/// The objective should be given a descriptive name and can represent
+/// a success rate and/or latency objective.
+///
+/// For details on SLOs, see [The Google SRE Book](https://sre.google/sre-book/service-level-objectives/).
+///
+/// ## Example
+/// ```rust
+/// # use autometrics::{autometrics, objectives::*};
+/// const API_SLO: Objective = Objective::new("api")
+///     .success_rate(ObjectivePercentile::P99_9)
+///     .latency(ObjectiveLatency::Ms250, ObjectivePercentile::P99);
+///
+/// #[autometrics(objective = API_SLO)]
+/// pub fn api_handler() {
+///    // ...
+/// }
+/// ```
+///
+/// ## How this works
+///
+/// When an objective is added to a function, Autometrics attaches additional labels to the generated metrics.
+///
+/// Specifically, [`success_rate`] objectives will add objective-related labels to the `function.calls` metric
+/// and [`latency`] and [`composite`] objectives will add labels to the `function.calls.duration` metric.
+///
+/// Autometrics comes with a set of Prometheus [recording rules](https://prometheus.io/docs/prometheus/latest/configuration/recording_rules/)
+/// and [alerting rules](https://prometheus.io/docs/prometheus/latest/configuration/alerting_rules/)
+/// that will fire alerts when the given objective is being violated.
+///
+/// By default, these recording rules will effectively lay dormaint.
+/// However, they are enabled when the special labels are present on certain metrics.
+///
+/// [`success_rate`]: Objective::success_rate
+/// [`latency`]: Objective::latency
+/// [`composite`]: Objective::composite
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Objective {
+    pub(crate) name: &'static str,
+    pub(crate) success_rate: Option<ObjectivePercentile>,
+    pub(crate) latency: Option<(ObjectiveLatency, ObjectivePercentile)>,
+    pub(crate) composite: Option<(ObjectiveLatency, ObjectivePercentile)>,
+}
+
+impl Objective {
+    /// Create a new objective with the given name.
+    ///
+    /// The name should be something descriptive of the function or group of functions it covers.
+    /// For example, if you have an objective covering all of the HTTP handlers in your API you might call it `"api"`.
+    pub const fn new(name: &'static str) -> Self {
+        Objective {
+            name,
+            success_rate: None,
+            latency: None,
+            composite: None,
+        }
+    }
+
+    /// Specify the success rate for this objective.
+    ///
+    /// This means that the function or group of functions that are part of this objective
+    /// should return an `Ok` result at least this percentage of the time.
+    ///
+    /// ## Metric Labels
+    ///
+    /// When a success rate objective is added to a function, the `function.calls` metric
+    /// will have these labels added:
+    /// - `objective.name` - the value of the name passed to the [`Objective::new`] function
+    /// - `objective.percentile` - the percentile provided here
+    pub const fn success_rate(mut self, percentile: ObjectivePercentile) -> Self {
+        self.success_rate = Some(percentile);
+        self
+    }
+
+    /// Specify the latency and percentile for this objective.
+    ///
+    /// This means that the function or group of functions that are part of this objective
+    /// should complete in less than the given latency at least this percentage of the time.
+    ///
+    /// ## Metric Labels
+    ///
+    /// When a latency objective is added to a function, the `function.calls.duration` metric,
+    /// which will appear in Prometheus as `function_calls_duration_bucket`, `function_calls_duration_count`,
+    /// and `function_calls_duration_sum`, will have these labels added:
+    /// - `objective.name` - the value of the name passed to the [`Objective::new`] function
+    /// - `objective.latency_threshold` - the latency threshold provided here
+    /// - `objective.percentile` - the percentile provided here
+    ///
+    /// This replaces the [`composite`](Objective::composite) objective, if there is one.
+    pub const fn latency(
+        mut self,
+        latency_threshold: ObjectiveLatency,
+        percentile: ObjectivePercentile,
+    ) -> Self {
+        self.latency = Some((latency_threshold, percentile));
+        self.composite = None;
+        self
+    }
+
+    /// Specify a composite objective, which combines the success rate and the latency into a single indicator.
+    ///
+    /// This means that the function or group of functions that are part of this objective
+    /// should both return an `Ok` result and complete in less than the given latency at least this percentage
+    /// of the time. Only the calls that are fast *and* successful are good events, so a slow error
+    /// is only counted once against the error budget.
+    ///
+    /// ## Metric Labels
+    ///
+    /// When a composite objective is added to a function, the `function.calls.duration` metric will have
+    /// the same labels as for a [`latency`](Objective::latency) objective, and a `result` label with the
+    /// result of each call, like the `function.calls` metric. The histogram has one set of objective labels,
+    /// so this replaces the latency objective, if there is one. The success rate objective is not affected.
+    ///
+    /// The recording and alerting rules for composite objectives are generated by the `alerts` feature
+    /// (they are not part of the pre-defined rules).
+    pub const fn composite(
+        mut self,
+        latency_threshold: ObjectiveLatency,
+        percentile: ObjectivePercentile,
+    ) -> Self {
+        self.composite = Some((latency_threshold, percentile));
+        self.latency = None;
+        self
+    }
+
+    /// The name of the objective.
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The percentile of the success rate objective, if there is one.
+    pub const fn success_rate_target(&self) -> Option<ObjectivePercentile> {
+        self.success_rate
+    }
+
+    /// The latency threshold and percentile of the latency objective, if there is one.
+    pub const fn latency_target(&self) -> Option<(ObjectiveLatency, ObjectivePercentile)> {
+        self.latency
+    }
+
+    /// The latency threshold and percentile of the composite objective, if there is one.
+    pub const fn composite_target(&self) -> Option<(ObjectiveLatency, ObjectivePercentile)> {
+        self.composite
+    }
+
+    /// The latency threshold and percentile that the `function.calls.duration` metric is labeled with,
+    /// from either the latency or the composite objective
+    pub(crate) const fn histogram_target(&self) -> Option<(ObjectiveLatency, ObjectivePercentile)> {
+        match self.latency {
+            Some(latency) => Some(latency),
+            None => self.composite,
+        }
+    }
+
+    /// Rebuild the objective that applies to a set of labels from their values,
+    /// for the labels that are received from plugins or snapshots.
+    ///
+    /// Returns `None` if the labels do not describe an objective that is supported by this library.
+    #[cfg(any(feature = "plugin", snapshots))]
+    pub(crate) fn from_labels(
+        name: Option<&'static str>,
+        percentile: Option<&'static str>,
+        latency_threshold: Option<&'static str>,
+    ) -> Option<Self> {
+        let objective = Objective::new(name?);
+        let percentile = percentile.and_then(ObjectivePercentile::from_label)?;
+        match latency_threshold.map(ObjectiveLatency::from_label) {
+            Some(Some(latency)) => Some(objective.latency(latency, percentile)),
+            Some(None) => None,
+            None => Some(objective.success_rate(percentile)),
+        }
+    }
+}
+
+/// An objective and the instrumented functions that are part of it.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+#[cfg(function_registry)]
+pub struct RegisteredObjective {
+    /// The objective.
+    pub objective: Objective,
+    /// The functions that are part of the objective, sorted by module and name.
+    pub functions: Vec<InstrumentedFunction>,
+}
+
+/// List all of the objectives that functions instrumented with the `autometrics` macro are part of,
+/// sorted by name, with the functions that are part of each.
+///
+/// Objectives that have the same name but different targets are listed separately,
+/// so the targets of an objective can be checked for consistency.
+/// Objectives that are only used by default trait methods, by the [`instrument`](crate::instrument) module,
+/// or by the middleware are not included, because those are not known until the functions are called.
+///
+/// This is available in debug builds, or in release builds with the `function-registry` feature.
+#[cfg(function_registry)]
+pub fn registered_objectives() -> Vec<RegisteredObjective> {
+    let mut registered: Vec<RegisteredObjective> = Vec::new();
+    for function in FUNCTION_DESCRIPTIONS {
+        let Some(objective) = (function.objective)() else {
+            continue;
+        };
+        let function = InstrumentedFunction::from(function);
+        match registered
+            .iter_mut()
+            .find(|registered| registered.objective == objective)
+        {
+            Some(registered) => registered.functions.push(function),
+            None => registered.push(RegisteredObjective {
+                objective,
+                functions: vec![function],
+            }),
+        }
+    }
+
+    registered.sort_by_key(|registered| registered.objective.name);
+    for registered in &mut registered {
+        registered
+            .functions
+            .sort_by_key(|function| (function.module, function.name));
+    }
+    registered
+}
+
+/// The percentage of requests that must meet the given criteria (success rate or latency).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ObjectivePercentile {
+    /// 90%
+    P90,
+    /// 95%
+    P95,
+    /// 99%
+    P99,
+    /// 99.9%
+    P99_9,
+    /// ⚠️ **Careful when using this option!**
+    ///
+    /// In order for this to work with the recording and alerting rules, you need to:
+    /// 1. generate a custom Sloth file using the [autometrics-cli](https://github.com/autometrics-dev/autometrics-rs/tree/main/autometrics-cli) that includes this objective
+    /// 2. use [Sloth](https://sloth.dev) to generate the Prometheus recording and alerting rules
+    /// 3. configure your Prometheus instance to use the generated rules
+    #[cfg(feature = "custom-objective-percentile")]
+    Custom(&'static str),
+}
+
+impl ObjectivePercentile {
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            ObjectivePercentile::P90 => "90",
+            ObjectivePercentile::P95 => "95",
+            ObjectivePercentile::P99 => "99",
+            ObjectivePercentile::P99_9 => "99.9",
+            #[cfg(feature = "custom-objective-percentile")]
+            ObjectivePercentile::Custom(custom) => custom,
+        }
+    }
+
+    /// The percentile with the given label value, which was produced by [`Self::as_str`]
+    #[cfg(any(feature = "plugin", snapshots))]
+    pub(crate) fn from_label(label: &'static str) -> Option<Self> {
+        match label {
+            "90" => Some(ObjectivePercentile::P90),
+            "95" => Some(ObjectivePercentile::P95),
+            "99" => Some(ObjectivePercentile::P99),
+            "99.9" => Some(ObjectivePercentile::P99_9),
+            #[cfg(feature = "custom-objective-percentile")]
+            custom => Some(ObjectivePercentile::Custom(custom)),
+            #[cfg(not(feature = "custom-objective-percentile"))]
+            _ => None,
+        }
+    }
+}
+
+#[cfg(prometheus_client)]
+impl EncodeLabelValue for ObjectivePercentile {
+    fn encode(&self, encoder: &mut LabelValueEncoder) -> Result<(), std::fmt::Error> {
+        self.as_str().encode(encoder)
+    }
+}
+
+/// The latency threshold, in milliseoncds, for a given objective.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ObjectiveLatency {
+    /// 5 milliseconds
+    Ms5,
+    /// 10 milliseconds
+    Ms10,
+    /// 25 milliseconds
+    Ms25,
+    /// 50 milliseconds
+    Ms50,
+    /// 75 milliseconds
+    Ms75,
+    /// 100 milliseconds
+    Ms100,
+    /// 150 milliseconds
+    Ms250,
+    /// 500 milliseconds
+    Ms500,
+    /// 750 milliseconds
+    Ms750,
+    /// 1 second
+    Ms1000,
+    /// 2.5 seconds
+    Ms2500,
+    /// 5 seconds
+    Ms5000,
+    /// 7.5 seconds
+    Ms7500,
+    /// 10 seconds
+    Ms10000,
+    /// ⚠️ **Careful when using this option!**
+    ///
+    /// First, the latency should be specified in seconds, not milliseconds.
+    /// For example, if you want to specify a latency of 200 milliseconds,
+    /// you would specify `ObjectiveLatency::Custom("0.2")`.
+    /// (If the latencies are recorded in milliseconds with
+    /// [`duration_unit`](crate::settings::AutometricsSettingsBuilder::duration_unit),
+    /// the latency should be specified in milliseconds instead, like `ObjectiveLatency::Custom("200")`.)
+    ///
+    /// Second, you must ensure that this value matches
+    /// one of the histogram buckets configured in the
+    /// [Autometrics settings](crate::settings::AutometricsSettingsBuilder::histogram_buckets)
+    /// or in your metrics exporter.
+    /// If it is not, the alerting rules will not work.
+    /// This is because the queries and recording rules compare this to
+    /// the value of the `le` label on the histogram buckets.
+    #[cfg(feature = "custom-objective-latency")]
+    Custom(&'static str),
+}
+
+impl ObjectiveLatency {
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match self {
+            ObjectiveLatency::Ms5 => "0.005",
+            ObjectiveLatency::Ms10 => "0.01",
+            ObjectiveLatency::Ms25 => "0.025",
+            ObjectiveLatency::Ms50 => "0.05",
+            ObjectiveLatency::Ms75 => "0.075",
+            ObjectiveLatency::Ms100 => "0.1",
+            ObjectiveLatency::Ms250 => "0.25",
+            ObjectiveLatency::Ms500 => "0.5",
+            ObjectiveLatency::Ms750 => "0.75",
+            ObjectiveLatency::Ms1000 => "1",
+            ObjectiveLatency::Ms2500 => "2.5",
+            ObjectiveLatency::Ms5000 => "5",
+            ObjectiveLatency::Ms7500 => "7.5",
+            ObjectiveLatency::Ms10000 => "10",
+            #[cfg(feature = "custom-objective-latency")]
+            ObjectiveLatency::Custom(custom) => custom,
+        }
+    }
+
+    /// The value of the `objective_latency_threshold` label, in the configured
+    /// [`DurationUnit`](crate::settings::DurationUnit)
+    pub(crate) fn label_value(&self) -> &'static str {
+        match get_settings().duration_unit {
+            DurationUnit::Seconds => self.as_str(),
+            DurationUnit::Milliseconds => match self {
+                ObjectiveLatency::Ms5 => "5",
+                ObjectiveLatency::Ms10 => "10",
+                ObjectiveLatency::Ms25 => "25",
+                ObjectiveLatency::Ms50 => "50",
+                ObjectiveLatency::Ms75 => "75",
+                ObjectiveLatency::Ms100 => "100",
+                ObjectiveLatency::Ms250 => "250",
+                ObjectiveLatency::Ms500 => "500",
+                ObjectiveLatency::Ms750 => "750",
+                ObjectiveLatency::Ms1000 => "1000",
+                ObjectiveLatency::Ms2500 => "2500",
+                ObjectiveLatency::Ms5000 => "5000",
+                ObjectiveLatency::Ms7500 => "7500",
+                ObjectiveLatency::Ms10000 => "10000",
+                // Custom thresholds are given in the configured unit
+                #[cfg(feature = "custom-objective-latency")]
+                ObjectiveLatency::Custom(custom) => custom,
+            },
+        }
+    }
+
+    /// The latency with the given label value, which was produced by [`Self::as_str`]
+    #[cfg(any(feature = "plugin", snapshots))]
+    pub(crate) fn from_label(label: &'static str) -> Option<Self> {
+        match label {
+            "0.005" => Some(ObjectiveLatency::Ms5),
+            "0.01" => Some(ObjectiveLatency::Ms10),
+            "0.025" => Some(ObjectiveLatency::Ms25),
+            "0.05" => Some(ObjectiveLatency::Ms50),
+            "0.075" => Some(ObjectiveLatency::Ms75),
+            "0.1" => Some(ObjectiveLatency::Ms100),
+            "0.25" => Some(ObjectiveLatency::Ms250),
+            "0.5" => Some(ObjectiveLatency::Ms500),
+            "0.75" => Some(ObjectiveLatency::Ms750),
+            "1" => Some(ObjectiveLatency::Ms1000),
+            "2.5" => Some(ObjectiveLatency::Ms2500),
+            "5" => Some(ObjectiveLatency::Ms5000),
+            "7.5" => Some(ObjectiveLatency::Ms7500),
+            "10" => Some(ObjectiveLatency::Ms10000),
+            #[cfg(feature = "custom-objective-latency")]
+            custom => Some(ObjectiveLatency::Custom(custom)),
+            #[cfg(not(feature = "custom-objective-latency"))]
+            _ => None,
+        }
+    }
+}
+
+#[cfg(prometheus_client)]
+impl EncodeLabelValue for ObjectiveLatency {
+    fn encode(&self, encoder: &mut LabelValueEncoder) -> Result<(), std::fmt::Error> {
+        self.label_value().encode(encoder)
+    }
+}
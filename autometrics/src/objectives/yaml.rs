@@ -0,0 +1,270 @@
+//! Load objectives from a YAML file that can be shared across services.
+//!
+//! The file should have the following format:
+//!
+//! ```yaml
+//! objectives:
+//!   - name: api
+//!     # The percentage of calls that should succeed
+//!     success_rate: 99.9
+//!     latency:
+//!       # The latency threshold, in seconds
+//!       threshold: 0.25
+//!       # The percentage of calls that should be faster than the threshold
+//!       percentile: 99
+//! ```
+//!
+//! A `composite` objective has the same format as the `latency` objective, and counts the calls that are
+//! both fast and successful (see [`Objective::composite`]). An objective can only have one of the two.
+
+use super::{Objective, ObjectiveLatency, ObjectivePercentile};
+use serde::Deserialize;
+use serde_yaml::Value;
+use std::collections::HashMap;
+use thiserror::Error;
+
+const PERCENTILES: [ObjectivePercentile; 4] = [
+    ObjectivePercentile::P90,
+    ObjectivePercentile::P95,
+    ObjectivePercentile::P99,
+    ObjectivePercentile::P99_9,
+];
+
+const LATENCIES: [ObjectiveLatency; 14] = [
+    ObjectiveLatency::Ms5,
+    ObjectiveLatency::Ms10,
+    ObjectiveLatency::Ms25,
+    ObjectiveLatency::Ms50,
+    ObjectiveLatency::Ms75,
+    ObjectiveLatency::Ms100,
+    ObjectiveLatency::Ms250,
+    ObjectiveLatency::Ms500,
+    ObjectiveLatency::Ms750,
+    ObjectiveLatency::Ms1000,
+    ObjectiveLatency::Ms2500,
+    ObjectiveLatency::Ms5000,
+    ObjectiveLatency::Ms7500,
+    ObjectiveLatency::Ms10000,
+];
+
+#[derive(Deserialize)]
+struct ObjectivesFile {
+    objectives: Vec<ObjectiveDefinition>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ObjectiveDefinition {
+    name: String,
+    success_rate: Option<Value>,
+    latency: Option<LatencyDefinition>,
+    composite: Option<LatencyDefinition>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct LatencyDefinition {
+    threshold: Value,
+    percentile: Value,
+}
+
+#[derive(Debug, Error)]
+pub enum ObjectivesYamlError {
+    #[error(transparent)]
+    Parse(#[from] serde_yaml::Error),
+
+    #[error("Objective `{0}` is defined more than once")]
+    DuplicateObjective(String),
+
+    #[error("Unsupported percentile `{value}` in objective `{objective}` (enable the `custom-objective-percentile` feature to use custom values)")]
+    UnsupportedPercentile { objective: String, value: String },
+
+    #[error("Unsupported latency threshold `{value}` in objective `{objective}` (enable the `custom-objective-latency` feature to use custom values)")]
+    UnsupportedLatency { objective: String, value: String },
+
+    #[error("Objective `{0}` has both a latency and a composite target")]
+    LatencyAndComposite(String),
+
+    #[error("Objective `{0}` is not defined")]
+    UnknownObjective(String),
+}
+
+/// A set of [`Objective`]s loaded from a YAML file.
+///
+/// Because the objectives are loaded at runtime, this is meant to be stored in a lazily-initialized static.
+/// The names used by the instrumented functions can be checked with [`require`](Self::require) when the set is loaded,
+/// so that a missing objective is reported with the other errors in the file:
+///
+/// ```rust
+/// use autometrics::{autometrics, objectives::{self, ObjectiveSet}};
+/// use once_cell::sync::Lazy;
+///
+/// static OBJECTIVES: Lazy<ObjectiveSet> = Lazy::new(|| {
+///     objectives::from_yaml_str("
+/// objectives:
+///   - name: api
+///     success_rate: 99.9
+/// ")
+///     .and_then(|objectives| objectives.require(&["api"]))
+///     .expect("Invalid objectives file")
+/// });
+///
+/// #[autometrics(objective = OBJECTIVES.objective("api"))]
+/// pub fn api_handler() {
+///    // ...
+/// }
+/// ```
+///
+/// The objective expression is only evaluated on the first call of each instrumented function.
+#[derive(Clone, Debug, Default)]
+pub struct ObjectiveSet {
+    objectives: Vec<Objective>,
+    by_name: HashMap<&'static str, usize>,
+}
+
+impl ObjectiveSet {
+    /// Get the objective with the given name, if it is defined.
+    pub fn get(&self, name: &str) -> Option<Objective> {
+        self.by_name.get(name).map(|&index| self.objectives[index])
+    }
+
+    /// Get the objective with the given name, or an error if it is not defined.
+    pub fn try_objective(&self, name: &str) -> Result<Objective, ObjectivesYamlError> {
+        self.get(name)
+            .ok_or_else(|| ObjectivesYamlError::UnknownObjective(name.to_string()))
+    }
+
+    /// Get the objective with the given name.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no objective with the given name is defined. Use [`require`](Self::require)
+    /// to check the names when the set is loaded, or [`try_objective`](Self::try_objective)
+    /// to handle a missing objective.
+    pub fn objective(&self, name: &str) -> Objective {
+        self.get(name)
+            .unwrap_or_else(|| panic!("Objective `{name}` is not defined"))
+    }
+
+    /// Check that all of the given objectives are defined, returning the set if they are.
+    pub fn require(self, names: &[&str]) -> Result<Self, ObjectivesYamlError> {
+        for name in names {
+            self.try_objective(name)?;
+        }
+        Ok(self)
+    }
+
+    /// Iterate over all of the objectives in the set.
+    pub fn iter(&self) -> impl Iterator<Item = &Objective> {
+        self.objectives.iter()
+    }
+}
+
+/// Load a set of objectives from a YAML string.
+///
+/// This makes it possible to share a single file defining the Service-Level Objectives
+/// between multiple services, regardless of the language they are written in.
+/// The file can be embedded into the binary using `include_str!("slos.yaml")`.
+///
+/// See the [`ObjectiveSet`] for how to use the objectives with the `autometrics` macro
+/// and the [module-level documentation](self) for the format of the file.
+pub fn from_yaml_str(yaml: &str) -> Result<ObjectiveSet, ObjectivesYamlError> {
+    let file: ObjectivesFile = serde_yaml::from_str(yaml)?;
+
+    let mut set = ObjectiveSet::default();
+    for definition in file.objectives {
+        if set.get(&definition.name).is_some() {
+            return Err(ObjectivesYamlError::DuplicateObjective(definition.name));
+        }
+
+        let success_rate = definition
+            .success_rate
+            .map(|value| parse_percentile(&definition.name, &value))
+            .transpose()?;
+        if definition.latency.is_some() && definition.composite.is_some() {
+            return Err(ObjectivesYamlError::LatencyAndComposite(definition.name));
+        }
+        let parse_target = |latency: LatencyDefinition| {
+            Ok::<_, ObjectivesYamlError>((
+                parse_latency(&definition.name, &latency.threshold)?,
+                parse_percentile(&definition.name, &latency.percentile)?,
+            ))
+        };
+        let latency = definition.latency.map(parse_target).transpose()?;
+        let composite = definition.composite.map(parse_target).transpose()?;
+
+        // The objectives are loaded once and live for the rest of the program,
+        // so they can be leaked to get the `&'static str` used in the metric labels
+        let name: &'static str = Box::leak(definition.name.into_boxed_str());
+        set.by_name.insert(name, set.objectives.len());
+        set.objectives.push(Objective {
+            name,
+            success_rate,
+            latency,
+            composite,
+        });
+    }
+
+    Ok(set)
+}
+
+fn parse_percentile(
+    objective: &str,
+    value: &Value,
+) -> Result<ObjectivePercentile, ObjectivesYamlError> {
+    let value = value_to_string(value);
+    if let Some(percentile) = PERCENTILES
+        .into_iter()
+        .find(|percentile| same_number(percentile.as_str(), &value))
+    {
+        return Ok(percentile);
+    }
+
+    #[cfg(custom_objective_percentile)]
+    if value.parse::<f64>().is_ok() {
+        return Ok(ObjectivePercentile::Custom(Box::leak(
+            value.into_boxed_str(),
+        )));
+    }
+
+    Err(ObjectivesYamlError::UnsupportedPercentile {
+        objective: objective.to_string(),
+        value,
+    })
+}
+
+fn parse_latency(objective: &str, value: &Value) -> Result<ObjectiveLatency, ObjectivesYamlError> {
+    let value = value_to_string(value);
+    if let Some(latency) = LATENCIES
+        .into_iter()
+        .find(|latency| same_number(latency.as_str(), &value))
+    {
+        return Ok(latency);
+    }
+
+    #[cfg(custom_objective_latency)]
+    if value.parse::<f64>().is_ok() {
+        return Ok(ObjectiveLatency::Custom(Box::leak(value.into_boxed_str())));
+    }
+
+    Err(ObjectivesYamlError::UnsupportedLatency {
+        objective: objective.to_string(),
+        value,
+    })
+}
+
+/// Numbers may be written either as YAML numbers or as strings
+fn value_to_string(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.trim().to_string(),
+        Value::Number(number) => number.to_string(),
+        other => serde_yaml::to_string(other)
+            .unwrap_or_default()
+            .trim()
+            .to_string(),
+    }
+}
+
+fn same_number(a: &str, b: &str) -> bool {
+    matches!((a.parse::<f64>(), b.parse::<f64>()), (Ok(a), Ok(b)) if a == b)
+}
@@ -1,40 +1,114 @@
+// The OTLP exporter still uses the OpenTelemetry 0.24 SDK, so it would not see the metrics
+// recorded by the newer backend
+#[cfg(all(not(doc), opentelemetry_0_33))]
+compile_error!(
+    "The otel-push-exporter feature cannot be used with the opentelemetry-0_33 backend yet"
+);
+
+#[cfg(not(any(
+    otel_push_exporter_tokio,
+    otel_push_exporter_tokio_current_thread,
+    otel_push_exporter_async_std
+)))]
+compile_error!("select your runtime (`otel-push-exporter-tokio`, `otel-push-exporter-tokio-current-thread` or `otel-push-exporter-async-std`) for the autometrics push exporter or use the custom push exporter if none fit");
+
+// The documentation is built with all of the features
+#[cfg(all(
+    not(doc),
+    any(
+        all(otel_push_exporter_tokio, otel_push_exporter_tokio_current_thread),
+        all(otel_push_exporter_tokio, otel_push_exporter_async_std),
+        all(otel_push_exporter_tokio_current_thread, otel_push_exporter_async_std)
+    )
+))]
+compile_error!("only one runtime (`otel-push-exporter-tokio`, `otel-push-exporter-tokio-current-thread` or `otel-push-exporter-async-std`) can be selected for the autometrics push exporter");
+
+use crate::poison;
 use opentelemetry::metrics::MetricsError;
-use opentelemetry_otlp::{ExportConfig, Protocol, WithExportConfig};
-use opentelemetry_otlp::{OtlpMetricPipeline, OTEL_EXPORTER_OTLP_TIMEOUT_DEFAULT};
-use opentelemetry_sdk::metrics::SdkMeterProvider;
+use opentelemetry_otlp::OTEL_EXPORTER_OTLP_TIMEOUT_DEFAULT;
+#[cfg(otel_push_exporter_transport)]
+use opentelemetry_otlp::{ExportConfig, MetricsExporterBuilder, Protocol, WithExportConfig};
+pub use opentelemetry_sdk::metrics::data::Temporality;
+#[cfg(any(test, otel_push_exporter_transport))]
+use opentelemetry_sdk::metrics::reader::DefaultAggregationSelector;
+use opentelemetry_sdk::metrics::reader::{
+    AggregationSelector, DefaultTemporalitySelector, TemporalitySelector,
+};
+#[cfg(any(test, otel_push_exporter_transport))]
+use opentelemetry_sdk::metrics::Aggregation;
+use opentelemetry_sdk::metrics::{InstrumentKind, SdkMeterProvider};
+use opentelemetry_sdk::Resource;
 use std::ops::Deref;
+#[cfg(otel_push_exporter_transport)]
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
 use std::time::Duration;
 
 /// Newtype struct holding a [`SdkMeterProvider`] with a custom `Drop` implementation to automatically clean up itself
-#[repr(transparent)]
 #[must_use = "Assign this to a unused variable instead: `let _meter = ...` (NOT `let _ = ...`), as else it will be dropped immediately - which will cause it to be shut down"]
-pub struct OtelMeterProvider(SdkMeterProvider);
+pub struct OtelMeterProvider {
+    provider: SdkMeterProvider,
+    /// Identifies this provider in [`METER_PROVIDERS`]
+    id: u64,
+}
+
+impl OtelMeterProvider {
+    #[cfg(otel_push_exporter_transport)]
+    fn register(provider: SdkMeterProvider) -> Self {
+        static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        poison::lock(&METER_PROVIDERS, "otel_meter_providers").push((id, provider.clone()));
+        OtelMeterProvider { provider, id }
+    }
+}
 
 impl Deref for OtelMeterProvider {
     type Target = SdkMeterProvider;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.provider
     }
 }
 
 impl Drop for OtelMeterProvider {
     fn drop(&mut self) {
+        poison::lock(&METER_PROVIDERS, "otel_meter_providers").retain(|(id, _)| *id != self.id);
         // this will only error if `.shutdown` gets called multiple times
-        let _ = self.0.shutdown();
+        let _ = self.provider.shutdown();
     }
 }
 
+/// The meter providers that have not been dropped yet, which are flushed by [`crate::flush`]
+/// and shut down by [`crate::shutdown`]
+static METER_PROVIDERS: Mutex<Vec<(u64, SdkMeterProvider)>> = Mutex::new(Vec::new());
+
+/// Export the metrics of all running meter providers now
+pub(crate) fn force_flush() -> Result<(), MetricsError> {
+    let providers = poison::lock(&METER_PROVIDERS, "otel_meter_providers").clone();
+    providers
+        .iter()
+        .map(|(_, provider)| provider.force_flush())
+        .fold(Ok(()), Result::and)
+}
+
+/// Export the metrics of all running meter providers one last time and shut them down
+pub(crate) fn shutdown() -> Result<(), MetricsError> {
+    let providers = std::mem::take(&mut *poison::lock(&METER_PROVIDERS, "otel_meter_providers"));
+    providers
+        .iter()
+        .map(|(_, provider)| provider.shutdown())
+        .fold(Ok(()), Result::and)
+}
+
 /// Initialize the OpenTelemetry push exporter using HTTP transport.
 ///
 /// # Interval and timeout
 /// This function uses the environment variables `OTEL_METRIC_EXPORT_TIMEOUT` and `OTEL_METRIC_EXPORT_INTERVAL`
 /// to configure the timeout and interval respectively. If you want to customize those
-/// from within code, consider using [`init_http_with_timeout_period`].
+/// from within code, consider using [`init_http_with_timeout_period`] or the [`builder`].
 #[cfg(feature = "otel-push-exporter-http")]
 pub fn init_http(url: impl Into<String>) -> Result<OtelMeterProvider, MetricsError> {
-    let (timeout, period) = timeout_and_period_from_env_or_default();
-    init_http_with_timeout_period(url, timeout, period)
+    builder().init_http(url)
 }
 
 /// Initialize the OpenTelemetry push exporter using HTTP transport with customized `timeout` and `period`.
@@ -44,20 +118,10 @@ pub fn init_http_with_timeout_period(
     timeout: Duration,
     period: Duration,
 ) -> Result<OtelMeterProvider, MetricsError> {
-    runtime()
-        .with_exporter(
-            opentelemetry_otlp::new_exporter()
-                .http()
-                .with_export_config(ExportConfig {
-                    endpoint: url.into(),
-                    protocol: Protocol::HttpBinary,
-                    timeout,
-                    ..Default::default()
-                }),
-        )
+    builder()
+        .with_timeout(timeout)
         .with_period(period)
-        .build()
-        .map(OtelMeterProvider)
+        .init_http(url)
 }
 
 /// Initialize the OpenTelemetry push exporter using gRPC transport.
@@ -65,11 +129,10 @@ pub fn init_http_with_timeout_period(
 /// # Interval and timeout
 /// This function uses the environment variables `OTEL_METRIC_EXPORT_TIMEOUT` and `OTEL_METRIC_EXPORT_INTERVAL`
 /// to configure the timeout and interval respectively. If you want to customize those
-/// from within code, consider using [`init_grpc_with_timeout_period`].
+/// from within code, consider using [`init_grpc_with_timeout_period`] or the [`builder`].
 #[cfg(feature = "otel-push-exporter-grpc")]
 pub fn init_grpc(url: impl Into<String>) -> Result<OtelMeterProvider, MetricsError> {
-    let (timeout, period) = timeout_and_period_from_env_or_default();
-    init_grpc_with_timeout_period(url, timeout, period)
+    builder().init_grpc(url)
 }
 
 /// Initialize the OpenTelemetry push exporter using gRPC transport with customized `timeout` and `period`.
@@ -79,20 +142,201 @@ pub fn init_grpc_with_timeout_period(
     timeout: Duration,
     period: Duration,
 ) -> Result<OtelMeterProvider, MetricsError> {
-    runtime()
-        .with_exporter(
-            opentelemetry_otlp::new_exporter()
-                .tonic()
-                .with_export_config(ExportConfig {
-                    endpoint: url.into(),
-                    protocol: Protocol::Grpc,
-                    timeout,
-                    ..Default::default()
-                }),
-        )
+    builder()
+        .with_timeout(timeout)
         .with_period(period)
-        .build()
-        .map(OtelMeterProvider)
+        .init_grpc(url)
+}
+
+/// Configure the OpenTelemetry push exporter.
+///
+/// ```rust,no_run
+/// use autometrics::otel_push_exporter::{self, Temporality};
+/// use opentelemetry::KeyValue;
+/// use opentelemetry_sdk::Resource;
+///
+/// # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+/// let _meter_provider = otel_push_exporter::builder()
+///     .with_temporality(Temporality::Delta)
+///     .with_resource(Resource::new([KeyValue::new("deployment.environment", "production")]))
+///     .init_http("http://localhost:4318/v1/metrics")?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn builder() -> OtelPushExporterBuilder {
+    let (timeout, period) = timeout_and_period_from_env_or_default();
+    OtelPushExporterBuilder {
+        timeout,
+        period,
+        temporality_selector: None,
+        aggregation_selector: None,
+        resource: None,
+    }
+}
+
+/// Builder for the OpenTelemetry push exporter, which is created with [`builder`].
+///
+/// Any options that are not set use the defaults of the OpenTelemetry pipeline.
+#[must_use = "The exporter is only started when `init_http` or `init_grpc` is called"]
+pub struct OtelPushExporterBuilder {
+    timeout: Duration,
+    period: Duration,
+    temporality_selector: Option<Box<dyn TemporalitySelector>>,
+    aggregation_selector: Option<Box<dyn AggregationSelector>>,
+    resource: Option<Resource>,
+}
+
+impl OtelPushExporterBuilder {
+    /// Set the timeout for each export.
+    ///
+    /// This defaults to the `OTEL_METRIC_EXPORT_TIMEOUT` environment variable (in seconds) or 10 seconds.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the interval between exports.
+    ///
+    /// This defaults to the `OTEL_METRIC_EXPORT_INTERVAL` environment variable (in seconds) or 60 seconds.
+    pub fn with_period(mut self, period: Duration) -> Self {
+        self.period = period;
+        self
+    }
+
+    /// Export the metrics with the given temporality.
+    ///
+    /// With [`Temporality::Delta`], the counters and histograms are exported as the change since the
+    /// previous export, while the concurrency gauge stays cumulative (like the OTLP exporter's
+    /// `delta` temporality preference). The default is [`Temporality::Cumulative`].
+    pub fn with_temporality(self, temporality: Temporality) -> Self {
+        match temporality {
+            Temporality::Delta => self.with_temporality_selector(DeltaTemporalitySelector),
+            _ => self.with_temporality_selector(DefaultTemporalitySelector::new()),
+        }
+    }
+
+    /// Select the temporality for each kind of instrument.
+    pub fn with_temporality_selector(
+        mut self,
+        temporality_selector: impl TemporalitySelector + 'static,
+    ) -> Self {
+        self.temporality_selector = Some(Box::new(temporality_selector));
+        self
+    }
+
+    /// Select the aggregation for each kind of instrument, for example to export the latencies
+    /// as [`Aggregation::Base2ExponentialHistogram`](opentelemetry_sdk::metrics::Aggregation::Base2ExponentialHistogram).
+    pub fn with_aggregation_selector(
+        mut self,
+        aggregation_selector: impl AggregationSelector + 'static,
+    ) -> Self {
+        self.aggregation_selector = Some(Box::new(aggregation_selector));
+        self
+    }
+
+    /// Attach the given resource attributes to the exported metrics.
+    pub fn with_resource(mut self, resource: Resource) -> Self {
+        self.resource = Some(resource);
+        self
+    }
+
+    /// Initialize the OpenTelemetry push exporter using HTTP transport.
+    #[cfg(feature = "otel-push-exporter-http")]
+    pub fn init_http(self, url: impl Into<String>) -> Result<OtelMeterProvider, MetricsError> {
+        let exporter = opentelemetry_otlp::new_exporter()
+            .http()
+            .with_export_config(ExportConfig {
+                endpoint: url.into(),
+                protocol: Protocol::HttpBinary,
+                timeout: self.timeout,
+            });
+        self.build(exporter)
+    }
+
+    /// Initialize the OpenTelemetry push exporter using gRPC transport.
+    #[cfg(feature = "otel-push-exporter-grpc")]
+    pub fn init_grpc(self, url: impl Into<String>) -> Result<OtelMeterProvider, MetricsError> {
+        let exporter = opentelemetry_otlp::new_exporter()
+            .tonic()
+            .with_export_config(ExportConfig {
+                endpoint: url.into(),
+                protocol: Protocol::Grpc,
+                timeout: self.timeout,
+            });
+        self.build(exporter)
+    }
+
+    /// The temporality and aggregation selectors that are passed to the pipeline, which are the defaults
+    /// of the OpenTelemetry pipeline unless they were set
+    #[cfg(any(test, otel_push_exporter_transport))]
+    fn into_selectors(self) -> (impl TemporalitySelector, impl AggregationSelector) {
+        (
+            BoxedSelector(
+                self.temporality_selector
+                    .unwrap_or_else(|| Box::new(DefaultTemporalitySelector::new())),
+            ),
+            BoxedSelector(
+                self.aggregation_selector
+                    .unwrap_or_else(|| Box::new(DefaultAggregationSelector::new())),
+            ),
+        )
+    }
+
+    #[cfg(otel_push_exporter_transport)]
+    fn build(
+        mut self,
+        exporter: impl Into<MetricsExporterBuilder>,
+    ) -> Result<OtelMeterProvider, MetricsError> {
+        let period = self.period;
+        let resource = self.resource.take();
+        let (temporality_selector, aggregation_selector) = self.into_selectors();
+        let mut pipeline = runtime()
+            .with_exporter(exporter)
+            .with_period(period)
+            .with_temporality_selector(temporality_selector)
+            .with_aggregation_selector(aggregation_selector);
+        if let Some(resource) = resource {
+            pipeline = pipeline.with_resource(resource);
+        }
+        pipeline.build().map(OtelMeterProvider::register)
+    }
+}
+
+/// The same selector as the OTLP exporter's `delta` temporality preference,
+/// which keeps the up-down counters cumulative
+struct DeltaTemporalitySelector;
+
+impl TemporalitySelector for DeltaTemporalitySelector {
+    fn temporality(&self, kind: InstrumentKind) -> Temporality {
+        match kind {
+            InstrumentKind::Counter
+            | InstrumentKind::Histogram
+            | InstrumentKind::ObservableCounter
+            | InstrumentKind::Gauge
+            | InstrumentKind::ObservableGauge => Temporality::Delta,
+            InstrumentKind::UpDownCounter | InstrumentKind::ObservableUpDownCounter => {
+                Temporality::Cumulative
+            }
+        }
+    }
+}
+
+/// Passes the boxed selectors from the builder to the pipeline, which takes them by value
+#[cfg(any(test, otel_push_exporter_transport))]
+struct BoxedSelector<T: ?Sized>(Box<T>);
+
+#[cfg(any(test, otel_push_exporter_transport))]
+impl TemporalitySelector for BoxedSelector<dyn TemporalitySelector> {
+    fn temporality(&self, kind: InstrumentKind) -> Temporality {
+        self.0.temporality(kind)
+    }
+}
+
+#[cfg(any(test, otel_push_exporter_transport))]
+impl AggregationSelector for BoxedSelector<dyn AggregationSelector> {
+    fn aggregation(&self, kind: InstrumentKind) -> Aggregation {
+        self.0.aggregation(kind)
+    }
 }
 
 /// returns timeout and period from their respective environment variables
@@ -118,51 +362,151 @@ fn timeout_and_period_from_env_or_default() -> (Duration, Duration) {
     (timeout, period)
 }
 
-#[cfg(all(
-    feature = "otel-push-exporter-tokio",
-    not(any(
-        feature = "otel-push-exporter-tokio-current-thread",
-        feature = "otel-push-exporter-async-std"
-    ))
-))]
-fn runtime(
-) -> OtlpMetricPipeline<opentelemetry_sdk::runtime::Tokio, opentelemetry_otlp::NoExporterConfig> {
-    return opentelemetry_otlp::new_pipeline().metrics(opentelemetry_sdk::runtime::Tokio);
+#[cfg(all(otel_push_exporter_tokio, otel_push_exporter_transport))]
+fn runtime() -> opentelemetry_otlp::OtlpMetricPipeline<
+    opentelemetry_sdk::runtime::Tokio,
+    opentelemetry_otlp::NoExporterConfig,
+> {
+    opentelemetry_otlp::new_pipeline().metrics(opentelemetry_sdk::runtime::Tokio)
 }
 
 #[cfg(all(
-    feature = "otel-push-exporter-tokio-current-thread",
-    not(any(
-        feature = "otel-push-exporter-tokio",
-        feature = "otel-push-exporter-async-std"
-    ))
+    otel_push_exporter_tokio_current_thread,
+    not(otel_push_exporter_tokio),
+    otel_push_exporter_transport
 ))]
-fn runtime() -> OtlpMetricPipeline<
+fn runtime() -> opentelemetry_otlp::OtlpMetricPipeline<
     opentelemetry_sdk::runtime::TokioCurrentThread,
     opentelemetry_otlp::NoExporterConfig,
 > {
-    return opentelemetry_otlp::new_pipeline()
-        .metrics(opentelemetry_sdk::runtime::TokioCurrentThread);
+    opentelemetry_otlp::new_pipeline().metrics(opentelemetry_sdk::runtime::TokioCurrentThread)
 }
 
 #[cfg(all(
-    feature = "otel-push-exporter-async-std",
-    not(any(
-        feature = "otel-push-exporter-tokio",
-        feature = "otel-push-exporter-tokio-current-thread"
-    ))
+    otel_push_exporter_async_std,
+    not(any(otel_push_exporter_tokio, otel_push_exporter_tokio_current_thread)),
+    otel_push_exporter_transport
 ))]
-fn runtime(
-) -> OtlpMetricPipeline<opentelemetry_sdk::runtime::AsyncStd, opentelemetry_otlp::NoExporterConfig>
-{
-    return opentelemetry_otlp::new_pipeline().metrics(opentelemetry_sdk::runtime::AsyncStd);
+fn runtime() -> opentelemetry_otlp::OtlpMetricPipeline<
+    opentelemetry_sdk::runtime::AsyncStd,
+    opentelemetry_otlp::NoExporterConfig,
+> {
+    opentelemetry_otlp::new_pipeline().metrics(opentelemetry_sdk::runtime::AsyncStd)
 }
 
-#[cfg(not(any(
-    feature = "otel-push-exporter-tokio",
-    feature = "otel-push-exporter-tokio-current-thread",
-    feature = "otel-push-exporter-async-std"
-)))]
-fn runtime() -> ! {
-    compile_error!("select your runtime (`otel-push-exporter-tokio`, `otel-push-exporter-tokio-current-thread` or `otel-push-exporter-async-std`) for the autometrics push exporter or use the custom push exporter if none fit")
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The builder reads the environment, so the tests that change it must not run at the same time
+    static ENV: Mutex<()> = Mutex::new(());
+
+    const INSTRUMENT_KINDS: [InstrumentKind; 7] = [
+        InstrumentKind::Counter,
+        InstrumentKind::UpDownCounter,
+        InstrumentKind::Histogram,
+        InstrumentKind::Gauge,
+        InstrumentKind::ObservableCounter,
+        InstrumentKind::ObservableUpDownCounter,
+        InstrumentKind::ObservableGauge,
+    ];
+
+    #[test]
+    fn builder_defaults() {
+        let _env = ENV.lock().unwrap_or_else(|err| err.into_inner());
+        std::env::remove_var("OTEL_METRIC_EXPORT_TIMEOUT");
+        std::env::remove_var("OTEL_METRIC_EXPORT_INTERVAL");
+        let builder = super::builder();
+        assert_eq!(builder.timeout, Duration::from_secs(10));
+        assert_eq!(builder.period, Duration::from_secs(60));
+
+        // The selectors are the defaults of the OpenTelemetry pipeline
+        let (temporality, aggregation) = builder.into_selectors();
+        for kind in INSTRUMENT_KINDS {
+            assert_eq!(
+                temporality.temporality(kind),
+                DefaultTemporalitySelector::new().temporality(kind)
+            );
+            assert_eq!(
+                aggregation.aggregation(kind),
+                DefaultAggregationSelector::new().aggregation(kind)
+            );
+        }
+
+        std::env::set_var("OTEL_METRIC_EXPORT_TIMEOUT", "5");
+        std::env::set_var("OTEL_METRIC_EXPORT_INTERVAL", "15");
+        let builder = super::builder();
+        assert_eq!(builder.timeout, Duration::from_secs(5));
+        assert_eq!(builder.period, Duration::from_secs(15));
+
+        // Invalid values are ignored
+        std::env::set_var("OTEL_METRIC_EXPORT_TIMEOUT", "5s");
+        let builder = super::builder().with_period(Duration::from_secs(1));
+        assert_eq!(builder.timeout, Duration::from_secs(10));
+        assert_eq!(builder.period, Duration::from_secs(1));
+
+        std::env::remove_var("OTEL_METRIC_EXPORT_TIMEOUT");
+        std::env::remove_var("OTEL_METRIC_EXPORT_INTERVAL");
+    }
+
+    #[test]
+    fn delta_temporality_keeps_up_down_counters_cumulative() {
+        let _env = ENV.lock().unwrap_or_else(|err| err.into_inner());
+        let (temporality, _) = builder()
+            .with_temporality(Temporality::Delta)
+            .into_selectors();
+        for kind in INSTRUMENT_KINDS {
+            let expected = match kind {
+                InstrumentKind::UpDownCounter | InstrumentKind::ObservableUpDownCounter => {
+                    Temporality::Cumulative
+                }
+                _ => Temporality::Delta,
+            };
+            assert_eq!(temporality.temporality(kind), expected, "{kind:?}");
+        }
+
+        let (temporality, _) = builder()
+            .with_temporality(Temporality::Cumulative)
+            .into_selectors();
+        for kind in INSTRUMENT_KINDS {
+            assert_eq!(temporality.temporality(kind), Temporality::Cumulative);
+        }
+    }
+
+    #[test]
+    fn custom_selectors_are_passed_to_the_pipeline() {
+        struct AlwaysDelta;
+
+        impl TemporalitySelector for AlwaysDelta {
+            fn temporality(&self, _kind: InstrumentKind) -> Temporality {
+                Temporality::Delta
+            }
+        }
+
+        struct ExponentialHistograms;
+
+        impl AggregationSelector for ExponentialHistograms {
+            fn aggregation(&self, _kind: InstrumentKind) -> Aggregation {
+                Aggregation::Base2ExponentialHistogram {
+                    max_size: 160,
+                    max_scale: 20,
+                    record_min_max: true,
+                }
+            }
+        }
+
+        let _env = ENV.lock().unwrap_or_else(|err| err.into_inner());
+        let (temporality, aggregation) = builder()
+            .with_temporality_selector(AlwaysDelta)
+            .with_aggregation_selector(ExponentialHistograms)
+            .into_selectors();
+        assert_eq!(
+            temporality.temporality(InstrumentKind::UpDownCounter),
+            Temporality::Delta
+        );
+        assert_eq!(
+            aggregation.aggregation(InstrumentKind::Histogram),
+            ExponentialHistograms.aggregation(InstrumentKind::Histogram)
+        );
+    }
 }
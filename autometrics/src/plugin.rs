@@ -0,0 +1,447 @@
+//! Record the metrics of plugins that are loaded as dynamic libraries in the application that loads them.
+//!
+//! A plugin built as a `cdylib` contains its own copy of autometrics, with its own registry
+//! and its own list of instrumented functions. The metrics recorded by the plugin would therefore
+//! not show up in the metrics exported by the application (the "host").
+//!
+//! To keep a single set of metrics, the host passes the interface returned by [`host`] to each plugin
+//! when it is loaded, and the plugin calls [`connect`] with it. From then on, every call to a function
+//! instrumented in the plugin is recorded by the host, using the host's settings (such as the service name)
+//! and exporter. In debug builds (or with the `function-registry` feature), the plugin's functions are
+//! also registered with the host so that their counters are initialized to zero.
+//!
+//! Both sides must be built with the `plugin` feature. They do not need to use the same version of
+//! autometrics, as long as both use the same [`ABI_VERSION`].
+//!
+//! # Example
+//!
+//! In the plugin:
+//! ```rust
+//! use autometrics::plugin::{self, PluginHost};
+//!
+//! #[no_mangle]
+//! pub unsafe extern "C" fn plugin_init(host: *const PluginHost) -> bool {
+//!     plugin::connect(host).is_ok()
+//! }
+//! ```
+//!
+//! In the host, for example using [`libloading`](https://crates.io/crates/libloading):
+//! ```rust,ignore
+//! use autometrics::plugin::{self, PluginHost};
+//!
+//! let library = unsafe { libloading::Library::new("libmy_plugin.so")? };
+//! let plugin_init: libloading::Symbol<unsafe extern "C" fn(*const PluginHost) -> bool> =
+//!     unsafe { library.get(b"plugin_init")? };
+//! unsafe { plugin_init(plugin::host()) };
+//! ```
+//!
+//! # Limitations
+//!
+//! - The custom histogram buckets of the plugin's functions are not used, the histograms use the
+//!   buckets configured in the host.
+//! - The build info, consumer lag, and exemplars of the plugin are not recorded by the host.
+//! - Calls from the host into the plugin (and vice versa) are not reported with a `caller_function`,
+//!   because each side keeps track of the current function separately.
+//!
+//! All of the label values that the plugin sends are copied by the host, so the plugin can be unloaded
+//! safely. Its metrics remain in the host's registry until they are removed.
+
+#[cfg(function_registry)]
+use crate::__private::FUNCTION_DESCRIPTIONS;
+use crate::__private::{AutometricsTracker, FunctionDescription, TrackMetrics};
+use crate::constants::{ERROR_KEY, OK_KEY};
+use crate::labels::{intern, CounterLabels, GaugeLabels, HistogramLabels, ResultLabel};
+use crate::objectives::{Objective, ObjectiveLatency, ObjectivePercentile};
+use crate::settings::get_settings;
+use once_cell::sync::OnceCell;
+use std::ffi::c_void;
+use std::fmt;
+use thiserror::Error;
+
+/// The version of the interface between the host and its plugins.
+///
+/// This is incremented whenever the data passed between them changes.
+pub const ABI_VERSION: u32 = 2;
+
+/// The host that this library is connected to, if it is loaded as a plugin
+static HOST: OnceCell<&'static PluginHost> = OnceCell::new();
+
+static HOST_INTERFACE: PluginHost = PluginHost {
+    abi_version: ABI_VERSION,
+    register_function: host_register_function,
+    start: host_start,
+    finish: host_finish,
+    count: host_count,
+};
+
+#[derive(Debug, Error)]
+pub enum PluginError {
+    #[error("The host pointer is null")]
+    NullHost,
+    #[error("The host uses version {host} of the plugin interface, but the plugin uses version {plugin}")]
+    IncompatibleVersion { host: u32, plugin: u32 },
+    #[error("The plugin is already connected to a host")]
+    AlreadyConnected,
+}
+
+/// The interface that plugins use to record their metrics in the host.
+///
+/// Get it with [`host`] and pass a pointer to it to [`connect`] in the plugin.
+#[repr(C)]
+pub struct PluginHost {
+    abi_version: u32,
+    register_function: extern "C" fn(function: &FfiFunction),
+    start: extern "C" fn(concurrency: Option<&FfiFunction>) -> *mut c_void,
+    finish: extern "C" fn(tracker: *mut c_void, call: Option<&FfiCall>),
+    count: extern "C" fn(call: &FfiCall),
+}
+
+impl fmt::Debug for PluginHost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PluginHost")
+            .field("abi_version", &self.abi_version)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The interface to pass to the plugins, so that they record their metrics in this application.
+pub fn host() -> &'static PluginHost {
+    &HOST_INTERFACE
+}
+
+/// Record the metrics of this library in the host that loaded it.
+///
+/// This should be called once, when the plugin is loaded and before any instrumented functions are called.
+///
+/// # Safety
+///
+/// `host` must be the pointer returned by [`host`] in the application that loads this plugin,
+/// and the plugin must be unloaded before that application exits.
+pub unsafe fn connect(host: *const PluginHost) -> Result<(), PluginError> {
+    if host.is_null() {
+        return Err(PluginError::NullHost);
+    }
+    // Only read the version before using the rest of the interface, whose layout may be different
+    let abi_version = std::ptr::addr_of!((*host).abi_version).read();
+    if abi_version != ABI_VERSION {
+        return Err(PluginError::IncompatibleVersion {
+            host: abi_version,
+            plugin: ABI_VERSION,
+        });
+    }
+
+    let host: &'static PluginHost = &*host;
+    HOST.set(host).map_err(|_| PluginError::AlreadyConnected)?;
+
+    #[cfg(function_registry)]
+    for function in FUNCTION_DESCRIPTIONS {
+        register_with_host(host, function);
+    }
+
+    Ok(())
+}
+
+/// Initialize the counter of the function in the host, if this library is connected to one
+pub(crate) fn register_function(function: &FunctionDescription) {
+    if let Some(host) = HOST.get() {
+        register_with_host(host, function);
+    }
+}
+
+fn register_with_host(host: &PluginHost, function: &FunctionDescription) {
+    let objective = (function.objective)();
+    (host.register_function)(&FfiFunction::new(
+        function.id.name(),
+        function.id.module(),
+        objective.and_then(|objective| objective.success_rate.map(|_| objective.name)),
+        objective.and_then(|objective| objective.success_rate),
+        None,
+    ));
+}
+
+/// Whether this library was connected to a host with [`connect`].
+pub fn is_connected() -> bool {
+    HOST.get().is_some()
+}
+
+/// A call that is being recorded by the host
+pub(crate) struct HostTracker {
+    host: &'static PluginHost,
+    tracker: *mut c_void,
+}
+
+// The tracker is only accessed by the host through the functions of the interface
+unsafe impl Send for HostTracker {}
+unsafe impl Sync for HostTracker {}
+
+impl HostTracker {
+    /// Start recording the call in the host, if this library is connected to one
+    pub(crate) fn start(gauge_labels: Option<&GaugeLabels>) -> Option<Self> {
+        let host = *HOST.get()?;
+        let concurrency = gauge_labels
+            .map(|labels| FfiFunction::new(labels.function, labels.module, None, None, None));
+        let tracker = (host.start)(concurrency.as_ref());
+        Some(Self { host, tracker })
+    }
+
+    pub(crate) fn finish(
+        mut self,
+        counter_labels: &CounterLabels,
+        histogram_labels: &HistogramLabels,
+        weight: u64,
+    ) {
+        let call = FfiCall::new(counter_labels, histogram_labels, weight);
+        let tracker = std::mem::replace(&mut self.tracker, std::ptr::null_mut());
+        (self.host.finish)(tracker, Some(&call));
+    }
+
+    /// Count a call of a function with the `count_only` argument in the host,
+    /// returning whether this library is connected to one
+    pub(crate) fn count(counter_labels: &CounterLabels) -> bool {
+        let Some(host) = HOST.get() else {
+            return false;
+        };
+        (host.count)(&FfiCall::counted(counter_labels));
+        true
+    }
+}
+
+impl Drop for HostTracker {
+    /// Release the host's tracker if the call did not finish (for example, because it panicked)
+    fn drop(&mut self) {
+        if !self.tracker.is_null() {
+            (self.host.finish)(self.tracker, None);
+        }
+    }
+}
+
+/// A string that is passed between the host and the plugin. Empty strings stand for missing values.
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct FfiStr {
+    ptr: *const u8,
+    len: usize,
+}
+
+impl FfiStr {
+    fn new(value: &str) -> Self {
+        Self {
+            ptr: value.as_ptr(),
+            len: value.len(),
+        }
+    }
+
+    fn from_option(value: Option<&str>) -> Self {
+        Self::new(value.unwrap_or_default())
+    }
+
+    /// Copy the string into the host, so it outlives the plugin
+    fn intern(self) -> &'static str {
+        if self.len == 0 {
+            return "";
+        }
+        // SAFETY: the string was created from a `&str` that lives until the end of the call
+        let value = unsafe {
+            std::str::from_utf8_unchecked(std::slice::from_raw_parts(self.ptr, self.len))
+        };
+        intern(value)
+    }
+
+    fn intern_option(self) -> Option<&'static str> {
+        Some(self.intern()).filter(|value| !value.is_empty())
+    }
+}
+
+/// A function, with the objective that applies to the labels that are being recorded
+#[repr(C)]
+struct FfiFunction {
+    function: FfiStr,
+    module: FfiStr,
+    objective_name: FfiStr,
+    objective_percentile: FfiStr,
+    objective_latency_threshold: FfiStr,
+}
+
+impl FfiFunction {
+    fn new(
+        function: &str,
+        module: &str,
+        objective_name: Option<&str>,
+        objective_percentile: Option<ObjectivePercentile>,
+        objective_latency_threshold: Option<ObjectiveLatency>,
+    ) -> Self {
+        Self {
+            function: FfiStr::new(function),
+            module: FfiStr::new(module),
+            objective_name: FfiStr::from_option(objective_name),
+            objective_percentile: FfiStr::from_option(
+                objective_percentile
+                    .as_ref()
+                    .map(ObjectivePercentile::as_str),
+            ),
+            objective_latency_threshold: FfiStr::from_option(
+                objective_latency_threshold
+                    .as_ref()
+                    .map(ObjectiveLatency::as_str),
+            ),
+        }
+    }
+
+    /// Rebuild the objective, without the parts that are not supported by the host
+    fn objective(&self) -> Option<Objective> {
+        Objective::from_labels(
+            self.objective_name.intern_option(),
+            self.objective_percentile.intern_option(),
+            self.objective_latency_threshold.intern_option(),
+        )
+    }
+}
+
+/// The labels of a finished call
+#[repr(C)]
+struct FfiCall {
+    counter: FfiFunction,
+    histogram: FfiFunction,
+    caller_function: FfiStr,
+    caller_module: FfiStr,
+    result: FfiStr,
+    return_value_type: FfiStr,
+    recursive: bool,
+    error_type: FfiStr,
+    type_params: FfiStr,
+    panic: bool,
+    weight: u64,
+}
+
+impl FfiCall {
+    fn new(
+        counter_labels: &CounterLabels,
+        histogram_labels: &HistogramLabels,
+        weight: u64,
+    ) -> Self {
+        Self::with_histogram(
+            counter_labels,
+            FfiFunction::new(
+                histogram_labels.function,
+                histogram_labels.module,
+                histogram_labels.objective_name,
+                histogram_labels.objective_percentile,
+                histogram_labels.objective_latency_threshold,
+            ),
+            weight,
+        )
+    }
+
+    /// A call that was only counted, which has no histogram labels
+    fn counted(counter_labels: &CounterLabels) -> Self {
+        Self::with_histogram(
+            counter_labels,
+            FfiFunction::new(
+                counter_labels.function,
+                counter_labels.module,
+                None,
+                None,
+                None,
+            ),
+            1,
+        )
+    }
+
+    fn with_histogram(counter_labels: &CounterLabels, histogram: FfiFunction, weight: u64) -> Self {
+        Self {
+            counter: FfiFunction::new(
+                counter_labels.function,
+                counter_labels.module,
+                counter_labels.objective_name,
+                counter_labels.objective_percentile,
+                None,
+            ),
+            histogram,
+            caller_function: FfiStr::new(counter_labels.caller_function),
+            caller_module: FfiStr::new(counter_labels.caller_module),
+            result: FfiStr::from_option(counter_labels.result.as_ref().map(ResultLabel::as_str)),
+            return_value_type: FfiStr::from_option(counter_labels.ok.or(counter_labels.error)),
+            recursive: counter_labels.recursive.is_some(),
+            error_type: FfiStr::from_option(counter_labels.error_type),
+            type_params: FfiStr::from_option(counter_labels.type_params),
+            panic: counter_labels.panic.is_some(),
+            weight,
+        }
+    }
+
+    fn to_labels(&self) -> (CounterLabels, HistogramLabels) {
+        let settings = get_settings();
+        let function = self.counter.function.intern();
+        let module = self.counter.module.intern();
+        let result = match self.result.intern() {
+            OK_KEY => Some(OK_KEY),
+            ERROR_KEY => Some(ERROR_KEY),
+            _ => None,
+        };
+        let type_params = self.type_params.intern_option();
+
+        let mut counter_labels = CounterLabels::new(
+            settings,
+            function,
+            module,
+            self.caller_function.intern(),
+            self.caller_module.intern(),
+            result.map(|result| (result, self.return_value_type.intern_option())),
+            self.counter.objective(),
+        )
+        .with_recursive(self.recursive)
+        .with_error_type(self.error_type.intern_option());
+        let mut histogram_labels =
+            HistogramLabels::new(settings, function, module, self.histogram.objective());
+        if let Some(type_params) = type_params {
+            counter_labels = counter_labels.with_type_params(type_params);
+            histogram_labels = histogram_labels.with_type_params(type_params);
+        }
+        if self.panic {
+            counter_labels = counter_labels.with_panic();
+        }
+
+        (counter_labels, histogram_labels)
+    }
+}
+
+extern "C" fn host_register_function(function: &FfiFunction) {
+    AutometricsTracker::intitialize_metrics(&[CounterLabels::new(
+        get_settings(),
+        function.function.intern(),
+        function.module.intern(),
+        "",
+        "",
+        Some((OK_KEY, None)),
+        function.objective(),
+    )]);
+}
+
+extern "C" fn host_start(concurrency: Option<&FfiFunction>) -> *mut c_void {
+    let gauge_labels = concurrency.map(|function| {
+        GaugeLabels::new(
+            get_settings(),
+            function.function.intern(),
+            function.module.intern(),
+        )
+    });
+    let tracker = AutometricsTracker::start_local(gauge_labels.as_ref());
+    Box::into_raw(Box::new(tracker)).cast()
+}
+
+extern "C" fn host_finish(tracker: *mut c_void, call: Option<&FfiCall>) {
+    if tracker.is_null() {
+        return;
+    }
+    // SAFETY: the tracker was created by `host_start` and the plugin only finishes it once
+    let tracker = unsafe { Box::from_raw(tracker.cast::<AutometricsTracker>()) };
+    if let Some(call) = call {
+        let (counter_labels, histogram_labels) = call.to_labels();
+        tracker.finish_weighted(&counter_labels, &histogram_labels, call.weight);
+    }
+}
+
+extern "C" fn host_count(call: &FfiCall) {
+    let (counter_labels, _) = call.to_labels();
+    AutometricsTracker::count_local(&counter_labels);
+}
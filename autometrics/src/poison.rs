@@ -0,0 +1,37 @@
+//! Recovering from locks that were poisoned by a panic in another thread.
+//!
+//! The locks used while recording metrics only hold caches and bookkeeping that stay consistent
+//! even if a panic happens while they are held, so one bad call should not make every other
+//! instrumented call panic. Instead, the poison is cleared and the recovery is counted in the
+//! `autometrics_lock_recoveries_total` metric.
+
+use crate::labels::LockRecoveryLabels;
+use crate::settings::get_settings;
+use std::sync::{LockResult, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// Acquire the read lock, recovering it if it was poisoned
+pub(crate) fn read<'a, T>(lock: &'a RwLock<T>, name: &'static str) -> RwLockReadGuard<'a, T> {
+    recover(lock.read(), || lock.clear_poison(), name)
+}
+
+/// Acquire the write lock, recovering it if it was poisoned
+pub(crate) fn write<'a, T>(lock: &'a RwLock<T>, name: &'static str) -> RwLockWriteGuard<'a, T> {
+    recover(lock.write(), || lock.clear_poison(), name)
+}
+
+/// Acquire the mutex, recovering it if it was poisoned
+pub(crate) fn lock<'a, T>(mutex: &'a Mutex<T>, name: &'static str) -> MutexGuard<'a, T> {
+    recover(mutex.lock(), || mutex.clear_poison(), name)
+}
+
+fn recover<G>(result: LockResult<G>, clear_poison: impl FnOnce(), name: &'static str) -> G {
+    result.unwrap_or_else(|err| {
+        // Clearing the poison means that each panic is only counted once
+        clear_poison();
+        crate::tracker::record_lock_recovery(&LockRecoveryLabels {
+            lock: name,
+            service_name: &get_settings().service_name,
+        });
+        err.into_inner()
+    })
+}
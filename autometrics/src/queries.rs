@@ -0,0 +1,427 @@
+//! Build the PromQL queries for the metrics of instrumented functions, along with links
+//! to them in the Prometheus expression browser.
+//!
+//! These are the same queries that the `autometrics` macro adds to the documentation of each function,
+//! but the links built here use the URL from the [`settings`](crate::settings)
+//! (see [`AutometricsSettingsBuilder::prometheus_url`](crate::settings::AutometricsSettingsBuilder::prometheus_url)),
+//! so the same binary points to the right Prometheus in every environment.
+//!
+//! The queries also use the metric name prefix, duration unit, metric naming, and label keys from the settings.
+//!
+//! # Documentation links
+//!
+//! The links in the rustdoc of the instrumented functions do **not** follow the runtime setting.
+//! The documentation is generated when the crate is compiled, so its links always use the `PROMETHEUS_URL`
+//! compile-time environment variable (or `http://localhost:9090`), and neither
+//! [`AutometricsSettingsBuilder::prometheus_url`](crate::settings::AutometricsSettingsBuilder::prometheus_url)
+//! nor the `PROMETHEUS_URL` runtime environment variable change them. Use [`graph_url`] wherever a link
+//! has to point to the Prometheus of the running service.
+//!
+//! # Example
+//! ```rust
+//! use autometrics::queries;
+//! use autometrics::settings::AutometricsSettings;
+//!
+//! AutometricsSettings::builder()
+//!     .prometheus_url("https://prometheus.example.com")
+//!     .init();
+//!
+//! let queries = queries::function("create_user");
+//! let url = queries::graph_url(&queries.error_ratio());
+//! assert!(url.starts_with("https://prometheus.example.com/graph?g0.expr="));
+//! ```
+//!
+//! # Objectives
+//!
+//! [`objective`] builds the queries for the error budget of an [`Objective`]: the ratio of the calls that
+//! missed its target, the rate at which the error budget is burned, and the multi-window, multi-burn-rate
+//! alert conditions. These are the same queries that the rules generated with the `alerts` feature use,
+//! so dashboards built from them match the alerts.
+//!
+//! ```rust
+//! use autometrics::objectives::{Objective, ObjectivePercentile};
+//! use autometrics::queries::{self, Sli};
+//!
+//! const API_SLO: Objective = Objective::new("api").success_rate(ObjectivePercentile::P99_9);
+//!
+//! let burn_rate = queries::objective(API_SLO).burn_rate(Sli::SuccessRate, "1h").unwrap();
+//! assert!(burn_rate.ends_with(" / 0.001"));
+//! ```
+
+use crate::constants::{COUNTER_NAME_PROMETHEUS, FUNCTION_KEY, MODULE_KEY};
+use crate::objectives::{Objective, ObjectivePercentile};
+use crate::settings::get_settings;
+
+/// The URL of the Prometheus instance that is used if none is configured
+pub(crate) const DEFAULT_PROMETHEUS_URL: &str = "http://localhost:9090";
+
+/// The queries for the metrics of a single function.
+///
+/// Created with [`function`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FunctionQueries {
+    function: String,
+}
+
+/// The queries for the metrics of the function with the given name
+/// (as it appears in the `function` label, like `Database::load`).
+pub fn function(name: impl Into<String>) -> FunctionQueries {
+    FunctionQueries {
+        function: name.into(),
+    }
+}
+
+impl FunctionQueries {
+    /// The rate of calls to the function per second, averaged over 5 minute windows.
+    pub fn request_rate(&self) -> String {
+        request_rate_query(&function_key(), &self.function)
+    }
+
+    /// The ratio of calls to the function that return errors, averaged over 5 minute windows.
+    pub fn error_ratio(&self) -> String {
+        error_ratio_query(&function_key(), &self.function)
+    }
+
+    /// The 95th and 99th percentile latencies of the function.
+    pub fn latency(&self) -> String {
+        latency_query(&function_key(), &self.function)
+    }
+
+    /// The latency of the function at the given quantile (between 0 and 1, like `0.99`).
+    pub fn latency_quantile(&self, quantile: f64) -> String {
+        format!(
+            "histogram_quantile({quantile}, {})",
+            latency_buckets_query(&function_key(), &self.function)
+        )
+    }
+
+    /// The number of concurrent calls to the function, if it is instrumented with `track_concurrency`.
+    pub fn concurrent_calls(&self) -> String {
+        let prefix = metric_name_prefix();
+        format!(
+            "sum by ({}) ({prefix}function_calls_concurrent{{{}=\"{}\"}} {})",
+            group_by_labels(),
+            function_key(),
+            self.function,
+            add_build_info_labels(&prefix)
+        )
+    }
+
+    /// The rate of calls to the functions called by this function.
+    pub fn callee_request_rate(&self) -> String {
+        request_rate_query("caller_function", &self.function)
+    }
+
+    /// The ratio of calls to the functions called by this function that return errors.
+    pub fn callee_error_ratio(&self) -> String {
+        error_ratio_query("caller_function", &self.function)
+    }
+}
+
+/// The service level indicators that an [`Objective`] can set a target for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Sli {
+    /// The ratio of calls that do not return errors, set with [`Objective::success_rate`].
+    SuccessRate,
+    /// The ratio of calls that are faster than the latency threshold, set with [`Objective::latency`].
+    Latency,
+    /// The ratio of calls that are both successful and faster than the latency threshold,
+    /// set with [`Objective::composite`].
+    Composite,
+}
+
+impl Sli {
+    /// The value of the `category` label of the recording rules for this SLI
+    #[cfg(feature = "alerts")]
+    pub(crate) const fn category(&self) -> &'static str {
+        match self {
+            Sli::SuccessRate => "success-rate",
+            Sli::Latency => "latency",
+            Sli::Composite => "composite",
+        }
+    }
+}
+
+/// The queries for the error budget of an objective.
+///
+/// Created with [`objective`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ObjectiveQueries {
+    objective: Objective,
+}
+
+/// The queries for the error budget of the given objective, over all of the functions that use it.
+pub fn objective(objective: Objective) -> ObjectiveQueries {
+    ObjectiveQueries { objective }
+}
+
+impl ObjectiveQueries {
+    /// The ratio of the calls that did not meet the target of the objective for the given SLI
+    /// over the window (like `"1h"`), or `None` if the objective does not have a target for it.
+    pub fn error_ratio(&self, sli: Sli, window: &str) -> Option<String> {
+        let percentile = self.percentile(sli)?;
+        Some(slo_error_ratio_query(
+            sli,
+            self.objective.name,
+            percentile.as_str(),
+            window,
+        ))
+    }
+
+    /// How fast the error budget of the objective for the given SLI is spent over the window,
+    /// or `None` if the objective does not have a target for it.
+    ///
+    /// A burn rate of 1 uses up exactly the whole error budget over the period of the objective,
+    /// and a burn rate of 14.4 uses up 2% of a 30 day budget in an hour.
+    pub fn burn_rate(&self, sli: Sli, window: &str) -> Option<String> {
+        let percentile = self.percentile(sli)?.as_str();
+        let error_budget = error_budget(percentile)?;
+        Some(format!(
+            "({}) / {}",
+            slo_error_ratio_query(sli, self.objective.name, percentile, window),
+            format_ratio(error_budget)
+        ))
+    }
+
+    /// The conditions of the [multi-window, multi-burn-rate alerts](https://sre.google/workbook/alerting-on-slos/#6-multiwindow-multi-burn-rate-alerts)
+    /// for the given SLI, or an empty list if the objective does not have a target for it.
+    ///
+    /// These are the same alerts as the ones generated by `alerts::generate_rules` with the `alerts` feature,
+    /// but they are computed from the metrics directly instead of from the recording rules:
+    ///
+    /// - a `page` alert if 2% of the monthly error budget is spent in 1 hour, or 5% in 6 hours
+    /// - a `ticket` alert if 10% of the monthly error budget is spent in 1 day, or 10% in 3 days
+    pub fn burn_rate_alerts(&self, sli: Sli) -> Vec<BurnRateAlert> {
+        let Some(percentile) = self.percentile(sli).map(|percentile| percentile.as_str()) else {
+            return Vec::new();
+        };
+        let Some(error_budget) = error_budget(percentile) else {
+            return Vec::new();
+        };
+        BURN_RATE_ALERTS
+            .iter()
+            .map(|(severity, windows)| BurnRateAlert {
+                severity,
+                query: burn_rate_condition(windows, error_budget, |window| {
+                    format!(
+                        "({})",
+                        slo_error_ratio_query(sli, self.objective.name, percentile, window)
+                    )
+                }),
+            })
+            .collect()
+    }
+
+    fn percentile(&self, sli: Sli) -> Option<ObjectivePercentile> {
+        match sli {
+            Sli::SuccessRate => self.objective.success_rate,
+            Sli::Latency => self.objective.latency.map(|(_, percentile)| percentile),
+            Sli::Composite => self.objective.composite.map(|(_, percentile)| percentile),
+        }
+    }
+}
+
+/// The condition of a burn rate alert, returned by [`ObjectiveQueries::burn_rate_alerts`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BurnRateAlert {
+    /// The severity of the alert, either `page` or `ticket`
+    pub severity: &'static str,
+    /// The query that returns a result while the alert is firing
+    pub query: String,
+}
+
+/// A long window, a short window, and the burn rate that must be exceeded in both to trigger an alert
+pub(crate) type BurnRateWindows = (&'static str, &'static str, f64);
+
+/// The severity of the burn rate alerts and the windows that trigger them
+pub(crate) const BURN_RATE_ALERTS: [(&str, [BurnRateWindows; 2]); 2] = [
+    ("page", [("1h", "5m", 14.4), ("6h", "30m", 6.0)]),
+    ("ticket", [("1d", "2h", 3.0), ("3d", "6h", 1.0)]),
+];
+
+/// The link to the graph of the given query in the Prometheus expression browser,
+/// using the URL from the settings.
+pub fn graph_url(query: &str) -> String {
+    let mut url = get_settings().prometheus_url.clone();
+    if !url.ends_with('/') {
+        url.push('/');
+    }
+    url.push_str("graph?g0.expr=");
+    url.push_str(&percent_encode(query));
+    // Go straight to the graph tab
+    url.push_str("&g0.tab=0");
+    url
+}
+
+/// Encode every character except ASCII letters and digits, like the links in the documentation
+fn percent_encode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len() * 3);
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{byte:02X}"));
+        }
+    }
+    encoded
+}
+
+fn metric_name_prefix() -> String {
+    match get_settings().metric_name_prefix {
+        Some(prefix) => format!("{prefix}_"),
+        None => String::new(),
+    }
+}
+
+fn function_key() -> String {
+    get_settings().label_key(FUNCTION_KEY).to_string()
+}
+
+/// The labels that the queries are aggregated by
+fn group_by_labels() -> String {
+    let settings = get_settings();
+    format!(
+        "{}, {}, service_name, commit, version",
+        settings.label_key(FUNCTION_KEY),
+        settings.label_key(MODULE_KEY)
+    )
+}
+
+fn add_build_info_labels(prefix: &str) -> String {
+    format!(
+        "* on (instance, job) group_left(version, commit) last_over_time({prefix}build_info[1s])"
+    )
+}
+
+fn request_rate_query(label_key: &str, label_value: &str) -> String {
+    let prefix = metric_name_prefix();
+    format!(
+        "sum by ({}) (rate({{__name__=~\"{prefix}function_calls(_count)?(_total)?\",{label_key}=\"{label_value}\"}}[5m]) {})",
+        group_by_labels(),
+        add_build_info_labels(&prefix)
+    )
+}
+
+fn error_ratio_query(label_key: &str, label_value: &str) -> String {
+    let prefix = metric_name_prefix();
+    format!(
+        "(sum by ({}) (rate({{__name__=~\"{prefix}function_calls(_count)?(_total)?\",{label_key}=\"{label_value}\",result=\"error\"}}[5m]) {}))
+/
+({})",
+        group_by_labels(),
+        add_build_info_labels(&prefix),
+        request_rate_query(label_key, label_value)
+    )
+}
+
+/// The buckets of the latency histogram, with or without the unit in its name
+/// (like the queries in the documentation, some exporters leave it out)
+fn latency_buckets_query(label_key: &str, label_value: &str) -> String {
+    let settings = get_settings();
+    let prefix = metric_name_prefix();
+    let histogram_name = settings
+        .metric_naming
+        .prometheus_histogram_name()
+        .trim_end_matches("_seconds");
+    let duration_unit = settings.duration_unit.prometheus_suffix();
+    format!(
+        "sum by (le, {}) (rate({{__name__=~\"{prefix}{histogram_name}(_{duration_unit})?_bucket\",{label_key}=\"{label_value}\"}}[5m]) {})",
+        group_by_labels(),
+        add_build_info_labels(&prefix)
+    )
+}
+
+fn latency_query(label_key: &str, label_value: &str) -> String {
+    let latency = latency_buckets_query(label_key, label_value);
+    format!(
+        "label_replace(histogram_quantile(0.99, {latency}), \"percentile_latency\", \"99\", \"\", \"\")
+or
+label_replace(histogram_quantile(0.95, {latency}), \"percentile_latency\", \"95\", \"\", \"\")"
+    )
+}
+
+/// The ratio of the calls of all of the functions that use the objective that did not meet its target,
+/// over the given window
+pub(crate) fn slo_error_ratio_query(
+    sli: Sli,
+    name: &str,
+    percentile: &str,
+    window: &str,
+) -> String {
+    let settings = get_settings();
+    let objective = format!(
+        r#"objective_name="{}",objective_percentile="{percentile}""#,
+        escape(name)
+    );
+    let by = "sum by (objective_name, objective_percentile, service_name)";
+    match sli {
+        Sli::SuccessRate => {
+            let counter = settings.prometheus_metric_name(COUNTER_NAME_PROMETHEUS);
+            format!(
+                r#"{by} (rate({counter}{{{objective},result="error"}}[{window}])) / {by} (rate({counter}{{{objective}}}[{window}]))"#
+            )
+        }
+        Sli::Latency | Sli::Composite => {
+            let histogram = settings.prometheus_histogram_name();
+            // The first calls are excluded from the latency objectives if they are labeled as cold starts
+            let objective = if settings.cold_start_label {
+                format!(r#"{objective},cold!="true""#)
+            } else {
+                objective
+            };
+            // Composite objectives only count the fast calls that did not return errors as good
+            let good = if sli == Sli::Composite {
+                format!(r#"{objective},result!="error""#)
+            } else {
+                objective.clone()
+            };
+            // The calls that are faster than the latency threshold are counted in the bucket
+            // whose upper bound is the threshold
+            format!(
+                r#"1 - ({by} (label_join(rate({histogram}_bucket{{{good}}}[{window}]), "autometrics_check_label_equality", "", "objective_latency_threshold") and label_join(rate({histogram}_bucket{{{good}}}[{window}]), "autometrics_check_label_equality", "", "le")) / {by} (rate({histogram}_count{{{objective}}}[{window}])))"#
+            )
+        }
+    }
+}
+
+/// The condition that either pair of windows exceeds its burn rate, given the query of the error ratio over a window
+pub(crate) fn burn_rate_condition(
+    windows: &[BurnRateWindows],
+    error_budget: f64,
+    error_ratio: impl Fn(&str) -> String,
+) -> String {
+    windows
+        .iter()
+        .map(|(long, short, burn_rate)| {
+            let threshold = format_ratio(burn_rate * error_budget);
+            format!(
+                "({} > {threshold} and {} > {threshold})",
+                error_ratio(long),
+                error_ratio(short)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" or ")
+}
+
+/// The ratio of the calls that may miss an objective with the given percentile (like `"99.9"`)
+pub(crate) fn error_budget(percentile: &str) -> Option<f64> {
+    let percentile = percentile.parse::<f64>().ok()?;
+    Some(1.0 - percentile / 100.0)
+}
+
+/// Format the ratio without the floating point noise (like `0.014400000000000001`)
+pub(crate) fn format_ratio(ratio: f64) -> String {
+    let ratio = format!("{ratio:.10}");
+    ratio
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+/// Escape a value for a PromQL string or a double-quoted YAML string, which use the same escape sequences
+/// for backslashes and quotes
+pub(crate) fn escape(value: &str) -> String {
+    value.replace('\\', r"\\").replace('"', "\\\"")
+}
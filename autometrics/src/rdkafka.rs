@@ -0,0 +1,219 @@
+//! Instrument [`rdkafka`](https://crates.io/crates/rdkafka) consumers and producers.
+//!
+//! Kafka topics are tracked like functions, using the topic name as the `function` label:
+//!
+//! - [`process`] and [`process_async`] wrap the handler of each consumed message.
+//!   They track the request rate, error rate, and latency of the handler in the module `rdkafka::consumer`.
+//!   Handlers that return an error are counted as errors.
+//! - [`send`] wraps [`FutureProducer::send`] and tracks the produced messages and the time until they are
+//!   delivered in the module `rdkafka::producer`. Messages that could not be delivered are counted as errors.
+//! - [`record_lag`] sets the `kafka_consumer_lag` gauge to the number of messages the consumer is behind
+//!   the end of each of its partitions.
+//!
+//! # Example
+//! ```rust,no_run
+//! use autometrics::rdkafka::{process_async, record_lag};
+//! use rdkafka::consumer::{Consumer, StreamConsumer};
+//! use rdkafka::{ClientConfig, Message};
+//! use std::time::Duration;
+//!
+//! async fn handle_order(payload: &[u8]) -> Result<(), std::str::Utf8Error> {
+//!     let order = std::str::from_utf8(payload)?;
+//!     // ...
+//!     Ok(())
+//! }
+//!
+//! # async fn run() -> Result<(), Box<dyn std::error::Error>> {
+//! let consumer: StreamConsumer = ClientConfig::new()
+//!     .set("bootstrap.servers", "localhost:9092")
+//!     .set("group.id", "orders")
+//!     .create()?;
+//! consumer.subscribe(&["orders"])?;
+//!
+//! loop {
+//!     let message = consumer.recv().await?;
+//!     // Tracked as the function "orders" in the module "rdkafka::consumer"
+//!     let result = process_async(&message, |message| {
+//!         handle_order(message.payload().unwrap_or_default())
+//!     })
+//!     .await;
+//!     if let Err(err) = result {
+//!         eprintln!("Failed to handle order: {err}");
+//!     }
+//!
+//!     // The lag is fetched from the brokers, so this should not be called for every message
+//!     if message.offset() % 1000 == 0 {
+//!         record_lag(&consumer, Duration::from_secs(1))?;
+//!     }
+//! }
+//! # }
+//! ```
+//!
+//! Functions instrumented with the `autometrics` macro that are called by the handler
+//! are reported with the topic as their caller.
+
+use crate::__private::{
+    AutometricsTracker, CallerInfo, ConsumerLagLabels, CounterLabels, HistogramLabels,
+    TrackMetrics, ERROR_KEY, OK_KEY,
+};
+use crate::poison;
+use crate::settings::get_settings;
+use ::rdkafka::consumer::{Consumer, ConsumerContext};
+use ::rdkafka::error::KafkaResult;
+use ::rdkafka::message::{Message, ToBytes};
+use ::rdkafka::producer::{FutureProducer, FutureRecord};
+use ::rdkafka::util::{AsyncRuntime, Timeout};
+use ::rdkafka::{ClientContext, Offset};
+use once_cell::sync::Lazy;
+use std::{collections::HashSet, future::Future, sync::RwLock};
+
+pub use ::rdkafka::producer::future_producer::OwnedDeliveryResult;
+
+/// The value of the `module` label for the metrics of the message handlers
+const CONSUMER_MODULE: &str = "rdkafka::consumer";
+/// The value of the `module` label for the metrics of the produced messages
+const PRODUCER_MODULE: &str = "rdkafka::producer";
+
+/// The label values need to be `&'static str`s, so the topic names and partitions are leaked once each.
+/// This is bounded by the number of topics and partitions the application uses.
+static NAMES: Lazy<RwLock<HashSet<&'static str>>> = Lazy::new(Default::default);
+
+/// Handle a consumed message, tracking the handler as a function named after the message's topic.
+pub fn process<'a, M, F, T, E>(message: &'a M, handler: F) -> Result<T, E>
+where
+    M: Message,
+    F: FnOnce(&'a M) -> Result<T, E>,
+{
+    let topic = intern(message.topic());
+    let caller = CallerInfo::current(get_settings());
+
+    let tracker = AutometricsTracker::start(None);
+    let callee = CallerInfo {
+        caller_function: topic,
+        caller_module: CONSUMER_MODULE,
+    };
+    let result = callee.sync_scope(get_settings(), || handler(message));
+
+    finish(tracker, topic, CONSUMER_MODULE, caller, result.is_ok());
+    result
+}
+
+/// Handle a consumed message with an async handler, tracking the handler as a function named after the message's topic.
+pub async fn process_async<'a, M, F, Fut, T, E>(message: &'a M, handler: F) -> Result<T, E>
+where
+    M: Message,
+    F: FnOnce(&'a M) -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+{
+    let topic = intern(message.topic());
+    let caller = CallerInfo::current(get_settings());
+
+    let tracker = AutometricsTracker::start(None);
+    let callee = CallerInfo {
+        caller_function: topic,
+        caller_module: CONSUMER_MODULE,
+    };
+    let result = callee.scope(get_settings(), handler(message)).await;
+
+    finish(tracker, topic, CONSUMER_MODULE, caller, result.is_ok());
+    result
+}
+
+/// Send a message with the [`FutureProducer`], tracking it as a call to a function named after the topic.
+///
+/// The latency includes the time until the message is delivered (or fails to be delivered).
+pub async fn send<C, R, K, P, T>(
+    producer: &FutureProducer<C, R>,
+    record: FutureRecord<'_, K, P>,
+    queue_timeout: T,
+) -> OwnedDeliveryResult
+where
+    C: ClientContext + 'static,
+    R: AsyncRuntime,
+    K: ToBytes + ?Sized,
+    P: ToBytes + ?Sized,
+    T: Into<Timeout>,
+{
+    let topic = intern(record.topic);
+    let caller = CallerInfo::current(get_settings());
+
+    let tracker = AutometricsTracker::start(None);
+    let result = producer.send(record, queue_timeout).await;
+
+    finish(tracker, topic, PRODUCER_MODULE, caller, result.is_ok());
+    result
+}
+
+/// Set the `kafka_consumer_lag` gauge for each of the partitions assigned to the consumer.
+///
+/// The lag is the difference between the high watermark of the partition and the consumer's position.
+/// The watermarks are fetched from the brokers, so this should be called periodically rather than
+/// for every message.
+pub fn record_lag<C, Ctx>(consumer: &C, timeout: impl Into<Timeout> + Copy) -> KafkaResult<()>
+where
+    C: Consumer<Ctx>,
+    Ctx: ConsumerContext,
+{
+    for partition in consumer.position()?.elements() {
+        let (low, high) =
+            consumer.fetch_watermarks(partition.topic(), partition.partition(), timeout)?;
+        // Partitions that have not been consumed from yet are behind by all of their messages
+        let lag = match partition.offset() {
+            Offset::Offset(offset) => high - offset,
+            _ => high - low,
+        };
+        set_lag(partition.topic(), partition.partition(), lag.max(0));
+    }
+    Ok(())
+}
+
+/// Set the `kafka_consumer_lag` gauge for the given partition.
+///
+/// This can be used if the lag is already known, for example from the statistics
+/// reported to the [`ClientContext`].
+pub fn set_lag(topic: &str, partition: i32, lag: i64) {
+    let labels = ConsumerLagLabels::new(
+        get_settings(),
+        intern(topic),
+        intern(&partition.to_string()),
+    );
+    AutometricsTracker::set_consumer_lag(&labels, lag);
+}
+
+fn finish(
+    tracker: AutometricsTracker,
+    topic: &'static str,
+    module: &'static str,
+    caller: CallerInfo,
+    is_ok: bool,
+) {
+    let result = if is_ok { OK_KEY } else { ERROR_KEY };
+    let settings = get_settings();
+    let counter_labels = CounterLabels::new(
+        settings,
+        topic,
+        module,
+        caller.caller_function,
+        caller.caller_module,
+        Some((result, None)),
+        None,
+    );
+    let histogram_labels = HistogramLabels::new(settings, topic, module, None);
+    tracker.finish(&counter_labels, &histogram_labels);
+}
+
+fn intern(name: &str) -> &'static str {
+    if let Some(name) = poison::read(&NAMES, "names").get(name).copied() {
+        return name;
+    }
+
+    let mut names = poison::write(&NAMES, "names");
+    match names.get(name) {
+        Some(name) => name,
+        None => {
+            let name: &'static str = Box::leak(name.to_string().into_boxed_str());
+            names.insert(name);
+            name
+        }
+    }
+}
@@ -0,0 +1,359 @@
+//! Inspect the instrumented functions and manage the series that Autometrics has created in the metrics registry.
+//!
+//! # Instrumented functions
+//!
+//! [`instrumented_functions`] lists all of the functions instrumented with the `autometrics` macro
+//! (except for default trait methods), whether or not they have been called. This can be used to build
+//! introspection endpoints or to initialize the metrics of a custom exporter.
+//!
+//! The functions are only collected in debug builds, unless the `function-registry` feature is enabled.
+//!
+//! # Function identifiers
+//!
+//! Every instrumented function is identified by a [`FunctionId`], which is created at compile time
+//! from the function's name, module, and crate. The functions are added to the registry the first time
+//! they are called (and when they are passed to [`register!`](crate::register)), so [`functions`] lists
+//! the functions that have been used in release builds as well. [`collisions`] finds the functions
+//! from different crates that share a name, whose series are combined by queries that only select
+//! the `function` label.
+//!
+//! # Registering functions in release builds
+//!
+//! The counters of the instrumented functions are initialized to zero when the metrics are exported,
+//! so that alerts can be defined for functions that have never been called. Like the list of instrumented
+//! functions, this only happens in debug builds or with the `function-registry` feature.
+//!
+//! Critical functions can be registered individually with the [`register!`](crate::register) macro instead,
+//! which initializes their counters in release builds as well.
+//!
+//! # Removing series
+//!
+//! Series are created the first time an instrumented function is called with a given set of labels
+//! and are normally kept for the lifetime of the process. In long-running services that load and unload
+//! code dynamically (for example, plugins), the series of functions that are no longer used can be removed
+//! with [`remove_function`] so that they are not exported anymore.
+//!
+//! This is only available with the `prometheus-client` and `prometheus` backends,
+//! because the `opentelemetry` and `metrics` libraries do not support removing series.
+
+#[cfg(function_registry)]
+use crate::__private::{FunctionDescription, FUNCTION_DESCRIPTIONS};
+#[cfg(function_registry)]
+use crate::objectives::Objective;
+use crate::poison;
+use once_cell::sync::Lazy;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::RwLock;
+
+/// Remove all of the series of the function with the given name (in any module) from the registry.
+///
+/// This removes the function's counter, histogram, and concurrency gauge series,
+/// as well as its in-process [`summaries`](crate::summaries) if those are enabled.
+/// Returns the number of series that were removed.
+///
+/// The function should not be called anymore after it is removed.
+/// If it is, it will be tracked again from zero, and a call that is in progress
+/// while the series are removed may leave its concurrency gauge at `-1`.
+///
+/// ```rust
+/// use autometrics::{autometrics, registry};
+///
+/// #[autometrics]
+/// fn plugin_handler() {}
+///
+/// plugin_handler();
+///
+/// // Once the plugin has been unloaded
+/// let removed = registry::remove_function("plugin_handler");
+/// assert!(removed > 0);
+/// ```
+#[cfg(any(prometheus, prometheus_client))]
+pub fn remove_function(function: &str) -> usize {
+    #[cfg(summaries)]
+    crate::summaries::remove_function(function);
+    crate::concurrency::remove_function(function);
+
+    // The series are removed from every backend that supports it
+    let mut removed = 0;
+    #[cfg(prometheus)]
+    {
+        removed += crate::tracker::prometheus::remove_function(function);
+    }
+    #[cfg(prometheus_client)]
+    {
+        removed += crate::tracker::prometheus_client::remove_function(function);
+    }
+    removed
+}
+
+/// The functions that were called or registered, keyed by their precomputed hash
+static FUNCTIONS: Lazy<RwLock<HashSet<FunctionId>>> = Lazy::new(Default::default);
+
+/// Identifies an instrumented function by its name, module, and crate.
+///
+/// The identifiers are created at compile time for each instrumented function, including the hash
+/// that is used to look them up, so that they can be compared and hashed without reading the strings.
+/// Default trait methods do not have an identifier, because their name depends on the implementor.
+#[derive(Clone, Copy, Debug)]
+pub struct FunctionId {
+    name: &'static str,
+    module: &'static str,
+    crate_name: &'static str,
+    hash: u64,
+}
+
+impl FunctionId {
+    /// Create the identifier of a function, hashing its name, module, and crate.
+    pub const fn new(name: &'static str, module: &'static str, crate_name: &'static str) -> Self {
+        let hash = fnv1a(FNV_OFFSET_BASIS, name.as_bytes());
+        let hash = fnv1a(hash, module.as_bytes());
+        let hash = fnv1a(hash, crate_name.as_bytes());
+        Self {
+            name,
+            module,
+            crate_name,
+            hash,
+        }
+    }
+
+    /// The name of the function (`Type::method` for methods).
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// The module path of the function.
+    pub const fn module(&self) -> &'static str {
+        self.module
+    }
+
+    /// The name of the crate that the function is defined in
+    /// (empty if the crate was not compiled by Cargo).
+    pub const fn crate_name(&self) -> &'static str {
+        self.crate_name
+    }
+
+    /// The 64-bit FNV-1a hash of the name, module, and crate.
+    pub const fn hash(&self) -> u64 {
+        self.hash
+    }
+}
+
+pub(crate) const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Hash the bytes, followed by a separator so that the fields cannot run into each other
+pub(crate) const fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+        i += 1;
+    }
+    hash ^= 0xff;
+    hash.wrapping_mul(FNV_PRIME)
+}
+
+impl PartialEq for FunctionId {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash
+            && self.name == other.name
+            && self.module == other.module
+            && self.crate_name == other.crate_name
+    }
+}
+
+impl Eq for FunctionId {}
+
+impl Hash for FunctionId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+impl fmt::Display for FunctionId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}::{}", self.module, self.name)
+    }
+}
+
+/// Add a function to the registry, unless it is already known.
+pub(crate) fn observe(id: &FunctionId) {
+    if poison::read(&FUNCTIONS, "function_ids").contains(id) {
+        return;
+    }
+    poison::write(&FUNCTIONS, "function_ids").insert(*id);
+}
+
+/// List the functions that have been called or registered with [`register!`](crate::register),
+/// as well as all of the [`instrumented_functions`] in debug builds or with the `function-registry` feature.
+///
+/// Every function is only listed once, sorted by module and name.
+///
+/// ```rust
+/// use autometrics::{autometrics, registry};
+///
+/// #[autometrics]
+/// fn get_user() {}
+///
+/// get_user();
+/// assert!(registry::functions().iter().any(|id| id.name() == "get_user"));
+/// ```
+pub fn functions() -> Vec<FunctionId> {
+    #[allow(unused_mut)]
+    let mut functions = poison::read(&FUNCTIONS, "function_ids").clone();
+    #[cfg(function_registry)]
+    functions.extend(FUNCTION_DESCRIPTIONS.iter().map(|function| function.id));
+
+    let mut functions: Vec<FunctionId> = functions.into_iter().collect();
+    functions.sort_by_key(|id| (id.module, id.name, id.crate_name));
+    functions
+}
+
+/// Find the functions from different crates that have the same name.
+///
+/// Their series can be told apart by the `module` label, but they are combined by queries that
+/// only select the `function` label, such as those of the links in the generated documentation.
+/// Each group contains the functions that share a name, sorted like [`functions`].
+pub fn collisions() -> Vec<Vec<FunctionId>> {
+    let mut by_name: HashMap<&'static str, Vec<FunctionId>> = HashMap::new();
+    for id in functions() {
+        by_name.entry(id.name).or_default().push(id);
+    }
+
+    let mut collisions: Vec<Vec<FunctionId>> = by_name
+        .into_values()
+        .filter(|ids| ids.iter().any(|id| id.crate_name != ids[0].crate_name))
+        .collect();
+    collisions.sort_by_key(|ids| ids[0].name);
+    collisions
+}
+
+/// A function instrumented with the `autometrics` macro.
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+#[cfg(function_registry)]
+pub struct InstrumentedFunction {
+    /// The name of the function (`Type::method` for methods).
+    pub name: &'static str,
+    /// The module path of the function.
+    pub module: &'static str,
+    /// The objective that the function is part of, if any.
+    pub objective: Option<Objective>,
+    /// The identifier of the function, which also includes its crate.
+    pub id: FunctionId,
+}
+
+#[cfg(function_registry)]
+impl From<&FunctionDescription> for InstrumentedFunction {
+    fn from(function: &FunctionDescription) -> Self {
+        InstrumentedFunction {
+            name: function.id.name,
+            module: function.id.module,
+            objective: (function.objective)(),
+            id: function.id,
+        }
+    }
+}
+
+/// List all of the functions instrumented with the `autometrics` macro, including those that have not been called yet.
+///
+/// Default trait methods are not included, because their implementors are not known
+/// until they are called.
+///
+/// This is available in debug builds, or in release builds with the `function-registry` feature.
+///
+/// ```rust
+/// use autometrics::{autometrics, registry};
+///
+/// #[autometrics]
+/// fn get_user() {}
+///
+/// assert!(registry::instrumented_functions().any(|function| function.name == "get_user"));
+/// ```
+#[cfg(function_registry)]
+pub fn instrumented_functions() -> impl Iterator<Item = InstrumentedFunction> {
+    FUNCTION_DESCRIPTIONS.iter().map(InstrumentedFunction::from)
+}
+
+/// Initialize the counter of an instrumented function to zero, even in release builds.
+///
+/// The function is given as a path relative to the current module (or starting with `crate::`),
+/// and it is labelled with the module that the path points to. Methods are given by their label instead,
+/// as a string in the form `"Type::method"`, and are labelled with the current module.
+/// If the function is part of an objective, it must be passed as well so that the series match
+/// the ones that are recorded when the function is called.
+///
+/// This should be called after the [`settings`](crate::settings) are initialized, if they are customized.
+///
+/// ```rust
+/// use autometrics::{autometrics, objectives::*};
+///
+/// const API_SLO: Objective = Objective::new("api")
+///     .success_rate(ObjectivePercentile::P99_9);
+///
+/// mod jobs {
+///     #[autometrics::autometrics]
+///     pub fn rotate_keys() {}
+/// }
+///
+/// struct Payments;
+///
+/// #[autometrics(objective = API_SLO)]
+/// fn checkout() {}
+///
+/// #[autometrics]
+/// impl Payments {
+///     fn refund() {}
+/// }
+///
+/// autometrics::register!(checkout, objective = API_SLO);
+/// autometrics::register!(jobs::rotate_keys);
+/// autometrics::register!("Payments::refund");
+/// ```
+#[macro_export]
+// `crate` is only matched in paths given to the macro, which are relative to the caller's crate
+#[allow(clippy::crate_in_macro_def)]
+macro_rules! register {
+    (@module [$($module:tt)*] $segment:ident :: $($function:tt)+) => {
+        $crate::register!(@module [$($module)*, "::", ::core::stringify!($segment)] $($function)+)
+    };
+    (@module [$($module:tt)*] $function:ident $(, objective = $objective:expr)? $(,)?) => {
+        $crate::register!(@function ::core::stringify!($function), ::core::concat!($($module)*) $(, $objective)?)
+    };
+    (@module [$($module:tt)*] $($rest:tt)*) => {
+        ::core::compile_error!("expected the path of an instrumented function, optionally followed by `, objective = ...`")
+    };
+    (@function $name:expr, $module:expr) => {
+        $crate::__private::register_function(&$crate::__private::FunctionDescription {
+            id: $crate::register!(@id $name, $module),
+            objective: || ::core::option::Option::None,
+        })
+    };
+    (@function $name:expr, $module:expr, $objective:expr) => {
+        $crate::__private::register_function(&$crate::__private::FunctionDescription {
+            id: $crate::register!(@id $name, $module),
+            objective: || ::core::option::Option::Some($objective),
+        })
+    };
+    (@id $name:expr, $module:expr) => {
+        $crate::registry::FunctionId::new(
+            $name,
+            $module,
+            $crate::__private::crate_name(::core::option_env!("CARGO_CRATE_NAME")),
+        )
+    };
+    ($name:literal $(, objective = $objective:expr)? $(,)?) => {
+        $crate::register!(@function $name, ::core::module_path!() $(, $objective)?)
+    };
+    (crate :: $($function:tt)+) => {
+        $crate::register!(@module [::core::env!("CARGO_CRATE_NAME")] $($function)+)
+    };
+    (self :: $($function:tt)+) => {
+        $crate::register!(@module [::core::module_path!()] $($function)+)
+    };
+    ($($function:tt)+) => {
+        $crate::register!(@module [::core::module_path!()] $($function)+)
+    };
+}
@@ -0,0 +1,45 @@
+//! Randomly sample the calls to instrumented functions.
+
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+/// A random number between 0 and 1, from a per-thread xorshift generator
+/// (the sampling does not need to be cryptographically secure)
+pub(crate) fn random_fraction() -> f64 {
+    thread_local! {
+        static STATE: Cell<u64> = Cell::new(RandomState::new().build_hasher().finish() | 1);
+    }
+
+    STATE.with(|state| {
+        let mut x = state.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        state.set(x);
+        // Use the upper 53 bits, which is the precision of an f64
+        (x >> 11) as f64 / (1u64 << 53) as f64
+    })
+}
+
+/// Decide whether to record a call to a function with the given sample rate.
+///
+/// Returns the number of calls that the recorded call stands for, or 0 if it is not recorded.
+/// The inverse of the sample rate is rounded up or down at random so that the weights
+/// are correct on average, even if it is not a whole number.
+pub fn sample_weight(sample_rate: f64) -> u64 {
+    if sample_rate >= 1.0 {
+        return 1;
+    }
+    if sample_rate.is_nan() || sample_rate <= 0.0 || random_fraction() >= sample_rate {
+        return 0;
+    }
+
+    let weight = 1.0 / sample_rate;
+    let whole = weight.floor();
+    if random_fraction() < weight - whole {
+        whole as u64 + 1
+    } else {
+        whole as u64
+    }
+}
@@ -0,0 +1,144 @@
+//! Record the metrics of some calls in an isolated registry instead of the global one.
+//!
+//! The settings and the metrics registry are global, so tests that run in parallel see each other's calls,
+//! and the tenants of a multi-tenant process share the same series. A [`ScopedRegistry`] has its own
+//! [`Registry`] and metric families. The calls to instrumented functions that are made within one of its
+//! scopes are only recorded in that registry, and the calls made outside of any scope are recorded
+//! in the global registry as usual.
+//!
+//! The scope is tracked per task (or per thread), like the [`context`](crate::context) of the caller,
+//! so it is not inherited by spawned tasks and threads unless they are run in the scope too.
+//!
+//! # Example
+//! ```rust
+//! use autometrics::autometrics;
+//! use autometrics::scope::ScopedRegistry;
+//! use prometheus_client::registry::Registry;
+//!
+//! #[autometrics]
+//! fn create_order() -> Result<(), ()> {
+//!     Ok(())
+//! }
+//!
+//! let scoped = ScopedRegistry::new(Registry::default());
+//! scoped.sync_scope(|| create_order().ok());
+//!
+//! let metrics = scoped.encode_to_string().unwrap();
+//! assert!(metrics.contains(r#"function="create_order""#));
+//! ```
+//!
+//! Async code is run in the scope with [`ScopedRegistry::scope`]:
+//! ```rust
+//! # use autometrics::autometrics;
+//! # use autometrics::scope::ScopedRegistry;
+//! # use prometheus_client::registry::Registry;
+//! # #[autometrics]
+//! # async fn fetch_orders() {}
+//! # async fn example() {
+//! let scoped = ScopedRegistry::new(Registry::default());
+//! scoped.scope(fetch_orders()).await;
+//! # }
+//! ```
+//!
+//! The scoped registries use the global [`settings`](crate::settings), so initialize the settings before
+//! creating them. They do not have the `build_info` metric or the series that are initialized to zero,
+//! their series are not removed by [`registry::remove_function`](crate::registry::remove_function),
+//! and they do not count towards the
+//! [`max_label_sets`](crate::settings::AutometricsSettingsBuilder::max_label_sets).
+//!
+//! This is only supported with the `prometheus-client` backend.
+
+use crate::settings::get_settings;
+use crate::task_local::LocalKey;
+use crate::tracker::prometheus_client::{initialize_registry, Metrics};
+use prometheus_client::encoding::text::encode;
+use prometheus_client::registry::Registry;
+use std::cell::RefCell;
+use std::fmt;
+use std::future::Future;
+use std::sync::Arc;
+
+/// The scoped registry of the current task, if it is running in a scope
+static SCOPE: LocalKey<ScopedRegistry> = {
+    thread_local! {
+        static SCOPE_KEY: RefCell<Option<ScopedRegistry>> = const { RefCell::new(None) };
+    }
+
+    LocalKey { inner: SCOPE_KEY }
+};
+
+/// A metrics registry that the calls made within its scopes are recorded in, instead of the global registry.
+///
+/// Cloning it is cheap, and the clones share the same registry.
+#[derive(Clone)]
+pub struct ScopedRegistry(Arc<Inner>);
+
+struct Inner {
+    registry: Registry,
+    metrics: Metrics,
+}
+
+impl ScopedRegistry {
+    /// Register the Autometrics metrics in the given registry, using the global settings.
+    pub fn new(registry: Registry) -> Self {
+        let settings = get_settings();
+        let (registry, metrics) = initialize_registry(
+            registry,
+            &settings.global_labels,
+            settings.metric_name_prefix,
+            settings.metric_naming,
+            &settings.histogram_views,
+            settings.duration_unit,
+            settings.call_edges.is_some(),
+            settings.cardinality_watchdog.is_some(),
+            settings.max_label_sets.is_some(),
+            settings.crate_rollups,
+        );
+        Self(Arc::new(Inner { registry, metrics }))
+    }
+
+    /// Access the [`Registry`], for example to encode it with the functionality of the [`prometheus_client`] crate.
+    pub fn registry(&self) -> &Registry {
+        &self.0.registry
+    }
+
+    /// Encode the metrics in the registry in the Prometheus text format.
+    pub fn encode_to_string(&self) -> Result<String, fmt::Error> {
+        let mut output = String::new();
+        encode(&mut output, &self.0.registry)?;
+        Ok(output)
+    }
+
+    /// Remove the function call counters and latency histograms from the registry,
+    /// so that it only contains the calls made from now on.
+    pub fn reset(&self) {
+        self.0.metrics.reset();
+    }
+
+    /// Run the given future in this scope, so that the instrumented functions it calls are recorded in this registry.
+    pub fn scope<F: Future>(&self, future: F) -> impl Future<Output = F::Output> {
+        SCOPE.scope(self.clone(), future)
+    }
+
+    /// Run the given closure in this scope, so that the instrumented functions it calls are recorded in this registry.
+    pub fn sync_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        SCOPE.sync_scope(self.clone(), f)
+    }
+}
+
+impl fmt::Debug for ScopedRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScopedRegistry").finish_non_exhaustive()
+    }
+}
+
+/// Record in the metrics of the scoped registry of the current task,
+/// returning `None` if the current task is not running in a scope
+pub(crate) fn with_scoped_metrics<R>(f: impl FnOnce(&Metrics) -> R) -> Option<R> {
+    SCOPE.try_with(|scoped| f(&scoped.0.metrics)).ok()
+}
+
+/// Whether the current task is running in the scope of a scoped registry
+pub(crate) fn is_active() -> bool {
+    with_scoped_metrics(|_| ()).is_some()
+}
@@ -10,17 +10,261 @@
 //!
 //! See [`AutometricsSettingsBuilder`] for more details on the available options.
 
+use crate::cardinality::CardinalityWatchdog;
+use crate::constants::{
+    FUNCTION_KEY, HISTOGRAM_NAME, HISTOGRAM_NAME_PROMETHEUS, HISTOGRAM_NAME_SEMCONV,
+    HISTOGRAM_NAME_SEMCONV_PROMETHEUS, MODULE_KEY,
+};
+use crate::error_classifier::ErrorClassifier;
+#[cfg(exemplars_custom)]
+use crate::exemplars::ExemplarProvider;
+use crate::labels::truncate_label_value;
 #[cfg(prometheus_exporter)]
 use crate::prometheus_exporter::{self, ExporterInitializationError};
-use once_cell::sync::OnceCell;
-use std::env;
+#[cfg(span_traces)]
+use crate::span_traces::SpanTraceSink;
+pub use crate::tracker::{Clock, DefaultClock};
+#[cfg(wide_events)]
+use crate::wide_events::WideEvents;
+use once_cell::sync::{Lazy, OnceCell};
+use std::collections::HashMap;
+use std::sync::RwLock;
+#[cfg(any(
+    metrics,
+    opentelemetry,
+    prometheus,
+    prometheus_client,
+    snapshots,
+    stream
+))]
+use std::time::Duration;
+use std::{env, fmt};
 use thiserror::Error;
 
 pub(crate) static AUTOMETRICS_SETTINGS: OnceCell<AutometricsSettings> = OnceCell::new();
-#[cfg(any(prometheus_exporter, prometheus, prometheus_client))]
+/// The transformed module paths, which are leaked once so they can be used as label values
+static TRANSFORMED_MODULES: Lazy<RwLock<HashMap<&'static str, &'static str>>> =
+    Lazy::new(Default::default);
+#[cfg(any(prometheus_exporter, prometheus, prometheus_client, snapshots, stream))]
 const DEFAULT_HISTOGRAM_BUCKETS: [f64; 14] = [
     0.005, 0.01, 0.025, 0.05, 0.075, 0.1, 0.25, 0.5, 0.75, 1.0, 2.5, 5.0, 7.5, 10.0,
 ];
+/// The default buckets in milliseconds, written out so that the bucket bounds match the latency thresholds exactly
+#[cfg(any(prometheus_exporter, prometheus, prometheus_client, snapshots, stream))]
+const DEFAULT_HISTOGRAM_BUCKETS_MILLISECONDS: [f64; 14] = [
+    5.0, 10.0, 25.0, 50.0, 75.0, 100.0, 250.0, 500.0, 750.0, 1000.0, 2500.0, 5000.0, 7500.0,
+    10000.0,
+];
+/// The buckets of the `function.response.size` histogram, from 64 bytes to 16 MiB
+#[cfg(any(prometheus_exporter, prometheus, prometheus_client))]
+pub(crate) const RESPONSE_SIZE_BUCKETS: [f64; 10] = [
+    64.0, 256.0, 1024.0, 4096.0, 16384.0, 65536.0, 262144.0, 1048576.0, 4194304.0, 16777216.0,
+];
+
+/// The shortest allowed [`AutometricsSettingsBuilder::max_label_value_length`], which leaves room
+/// for the beginning of the value in front of the hash
+const MIN_LABEL_VALUE_LENGTH: usize = 32;
+
+/// Histogram buckets, represented in seconds, for functions that usually take between 1 microsecond and 10 milliseconds.
+///
+/// These are used by the `fine_grained_latency` argument of the [`autometrics`](crate::autometrics) macro.
+/// They can also be used for all functions with [`AutometricsSettingsBuilder::histogram_buckets`],
+/// or in an additional histogram with [`AutometricsSettingsBuilder::histogram_view`].
+pub const FINE_GRAINED_LATENCY_BUCKETS: [f64; 15] = [
+    0.000_001,
+    0.000_002_5,
+    0.000_005,
+    0.000_01,
+    0.000_025,
+    0.000_05,
+    0.000_075,
+    0.000_1,
+    0.000_25,
+    0.000_5,
+    0.000_75,
+    0.001,
+    0.002_5,
+    0.005,
+    0.01,
+];
+
+/// The [`FINE_GRAINED_LATENCY_BUCKETS`] in milliseconds
+const FINE_GRAINED_LATENCY_BUCKETS_MILLISECONDS: [f64; 15] = [
+    0.001, 0.002_5, 0.005, 0.01, 0.025, 0.05, 0.075, 0.1, 0.25, 0.5, 0.75, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// The unit in which the function latencies are recorded.
+///
+/// See [`AutometricsSettingsBuilder::duration_unit`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DurationUnit {
+    /// Record the latencies in seconds, in the `function_calls_duration_seconds` histogram.
+    #[default]
+    Seconds,
+    /// Record the latencies in milliseconds, in the `function_calls_duration_milliseconds` histogram.
+    Milliseconds,
+}
+
+impl DurationUnit {
+    /// The suffix of the histogram names in Prometheus
+    pub(crate) const fn prometheus_suffix(self) -> &'static str {
+        match self {
+            DurationUnit::Seconds => "seconds",
+            DurationUnit::Milliseconds => "milliseconds",
+        }
+    }
+
+    /// The unit of the histograms in OpenTelemetry, which uses the UCUM notation
+    #[cfg(opentelemetry)]
+    pub(crate) const fn otel_unit(self) -> &'static str {
+        match self {
+            DurationUnit::Seconds => "s",
+            DurationUnit::Milliseconds => "ms",
+        }
+    }
+
+    /// The duration as a number of this unit
+    #[cfg(any(
+        metrics,
+        opentelemetry,
+        prometheus,
+        prometheus_client,
+        snapshots,
+        stream
+    ))]
+    pub(crate) fn convert(self, duration: Duration) -> f64 {
+        match self {
+            DurationUnit::Seconds => duration.as_secs_f64(),
+            DurationUnit::Milliseconds => duration.as_secs_f64() * 1000.0,
+        }
+    }
+
+    /// The duration of a number of this unit
+    #[cfg(stream)]
+    pub(crate) fn to_duration(self, value: f64) -> Duration {
+        match self {
+            DurationUnit::Seconds => Duration::from_secs_f64(value.max(0.0)),
+            DurationUnit::Milliseconds => Duration::from_secs_f64(value.max(0.0) / 1000.0),
+        }
+    }
+
+    /// The histogram buckets that are used unless others are configured, in this unit
+    #[cfg(any(prometheus_exporter, prometheus, prometheus_client, snapshots, stream))]
+    pub(crate) const fn default_histogram_buckets(self) -> &'static [f64] {
+        match self {
+            DurationUnit::Seconds => &DEFAULT_HISTOGRAM_BUCKETS,
+            DurationUnit::Milliseconds => &DEFAULT_HISTOGRAM_BUCKETS_MILLISECONDS,
+        }
+    }
+
+    /// The name of a Prometheus histogram that ends with `_seconds`, with the suffix of this unit
+    pub(crate) fn histogram_name(self, name: &str) -> String {
+        format!(
+            "{}_{}",
+            name.trim_end_matches("_seconds"),
+            self.prometheus_suffix()
+        )
+    }
+
+    /// The buckets used by the `fine_grained_latency` argument of the `autometrics` macro
+    pub(crate) fn fine_grained_latency_buckets(self) -> &'static [f64] {
+        match self {
+            DurationUnit::Seconds => &FINE_GRAINED_LATENCY_BUCKETS,
+            DurationUnit::Milliseconds => &FINE_GRAINED_LATENCY_BUCKETS_MILLISECONDS,
+        }
+    }
+}
+
+/// The naming convention of the metrics.
+///
+/// See [`AutometricsSettingsBuilder::metric_naming`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum MetricNaming {
+    /// Use the names from the Autometrics spec, like the `function_calls_duration_seconds` histogram.
+    #[default]
+    Prometheus,
+    /// Follow the OpenTelemetry semantic conventions, which name the latency histogram `function.duration`
+    /// (or `function_duration_seconds` when exported to Prometheus).
+    OpenTelemetry,
+}
+
+impl MetricNaming {
+    /// The OpenTelemetry-style name of the latency histogram
+    pub(crate) const fn histogram_name(self) -> &'static str {
+        match self {
+            MetricNaming::Prometheus => HISTOGRAM_NAME,
+            MetricNaming::OpenTelemetry => HISTOGRAM_NAME_SEMCONV,
+        }
+    }
+
+    /// The Prometheus-style name of the latency histogram, in seconds
+    pub(crate) const fn prometheus_histogram_name(self) -> &'static str {
+        match self {
+            MetricNaming::Prometheus => HISTOGRAM_NAME_PROMETHEUS,
+            MetricNaming::OpenTelemetry => HISTOGRAM_NAME_SEMCONV_PROMETHEUS,
+        }
+    }
+}
+
+/// How the previous name of a function renamed with the `alias` argument of the `autometrics` macro is reported.
+///
+/// See [`AutometricsSettingsBuilder::alias_mode`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum AliasMode {
+    /// Report the previous name in a separate `function_renamed_info` series,
+    /// which has the `function`, `module`, and `previous_function` labels and is always set to 1.
+    #[default]
+    Info,
+    /// Add the `previous_function` label to the function's `function_calls_total`
+    /// and `function_calls_duration_seconds` series.
+    Label,
+}
+
+/// The OpenTelemetry instrument that records the number of concurrent calls in the `function.calls.concurrent` metric.
+///
+/// See [`AutometricsSettingsBuilder::concurrency_instrument`].
+#[cfg(opentelemetry)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ConcurrencyInstrument {
+    /// Add and subtract the calls that start and finish with an `UpDownCounter`.
+    #[default]
+    UpDownCounter,
+    /// Record the current number of concurrent calls with a synchronous `Gauge`.
+    ///
+    /// Some OTLP backends render up-down counters as monotonic sums or rates, which makes the number
+    /// of concurrent calls hard to read. A gauge is exported with the absolute number instead.
+    Gauge,
+}
+
+/// The build metadata reported in the `build_info` metric.
+///
+/// Any fields that are not set fall back to the compile-time environment variables
+/// (`AUTOMETRICS_VERSION`, `AUTOMETRICS_COMMIT`, `AUTOMETRICS_BRANCH`, `AUTOMETRICS_BUILD_TIMESTAMP`,
+/// `AUTOMETRICS_RUSTC_VERSION`, `AUTOMETRICS_TARGET`, or the `CARGO_PKG_VERSION` and `VERGEN_*` variables).
+/// See [`AutometricsSettingsBuilder::build_info`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BuildInfo {
+    /// The version of the service, which is reported in the `version` label
+    pub version: Option<String>,
+    /// The commit SHA the service was built from, which is reported in the `commit` label
+    pub commit: Option<String>,
+    /// The branch the service was built from, which is reported in the `branch` label
+    pub branch: Option<String>,
+    /// The URL of the repository, which is reported in the `repository.url` label
+    pub repo_url: Option<String>,
+    /// The provider of the repository (such as `github`), which is reported in the `repository.provider` label
+    pub repo_provider: Option<String>,
+    /// When the service was built, which is reported in the `build.timestamp` label if it is known
+    pub build_timestamp: Option<String>,
+    /// The version of the Rust compiler the service was built with, which is reported in the `rustc.version` label if it is known
+    pub rustc_version: Option<String>,
+    /// The target triple the service was built for, which is reported in the `target` label if it is known
+    pub target: Option<String>,
+}
 
 /// Load the settings configured by the user or use the defaults.
 ///
@@ -33,15 +277,67 @@ pub(crate) fn get_settings() -> &'static AutometricsSettings {
 pub struct AutometricsSettings {
     #[cfg(any(prometheus_exporter, prometheus, prometheus_client))]
     pub(crate) histogram_buckets: Vec<f64>,
+    pub(crate) duration_unit: DurationUnit,
+    pub(crate) metric_naming: MetricNaming,
     pub(crate) service_name: String,
     pub(crate) repo_url: String,
     pub(crate) repo_provider: String,
+    /// The URL of the Prometheus instance used for the links built by the `queries` module
+    pub(crate) prometheus_url: String,
+    /// The version, commit, and branch set at runtime, which take precedence over the compile-time values
+    pub(crate) build_info: BuildInfo,
+    /// Whether the `build_info` metric has the `runtime` and `os` labels
+    pub(crate) runtime_build_info: bool,
+    pub(crate) global_labels: Vec<(String, String)>,
+    pub(crate) metric_name_prefix: Option<&'static str>,
+    /// The keys used for the `function` and `module` labels
+    pub(crate) function_label_key: String,
+    pub(crate) module_label_key: String,
+    pub(crate) module_path_transform: Option<ModulePathTransform>,
+    pub(crate) max_label_value_length: Option<usize>,
+    // The prometheus-client backend registers the views while the settings are being built
+    #[cfg_attr(not(any(metrics, opentelemetry, prometheus)), allow(dead_code))]
+    pub(crate) histogram_views: Vec<HistogramView>,
+    /// The maximum number of call edges to export, if the `function_call_edges_info` metric is enabled
+    pub(crate) call_edges: Option<usize>,
+    pub(crate) cardinality_watchdog: Option<CardinalityWatchdog>,
+    /// The maximum number of label sets of the counter and histogram, if the least recently used ones are evicted
+    #[cfg(prometheus_client)]
+    pub(crate) max_label_sets: Option<usize>,
+    /// Whether the calls are also counted per crate in the `crate_calls_total` metric
+    pub(crate) crate_rollups: bool,
+    /// The length of the windows of the [`stream`](crate::stream) of deltas
+    #[cfg(stream)]
+    pub(crate) stream_interval: Duration,
+    pub(crate) error_classifier: Option<ErrorClassifier>,
+    /// Whether the first call of each function is recorded with the `cold="true"` label
+    pub(crate) cold_start_label: bool,
+    /// How the previous names of functions with the `alias` argument are reported
+    pub(crate) alias_mode: AliasMode,
+    /// Whether instrumented functions report their callers in the `caller_function` and `caller_module` labels
+    pub(crate) track_callers: bool,
+    pub(crate) clock: BoxedClock,
+    #[cfg(opentelemetry)]
+    pub(crate) concurrency_instrument: ConcurrencyInstrument,
+    /// Whether the `prometheus_exporter` leaves the global `metrics` recorder that is already installed in place
+    #[cfg(metrics)]
+    pub(crate) use_existing_metrics_recorder: bool,
+    #[cfg(wide_events)]
+    pub(crate) wide_events: Option<WideEvents>,
+    #[cfg(span_traces)]
+    pub(crate) span_traces: Option<BoxedSpanTraceSink>,
+    #[cfg(exemplars_custom)]
+    pub(crate) exemplar_provider: Option<BoxedExemplarProvider>,
     #[cfg(any(prometheus, opentelemetry))]
-    pub(crate) prometheus_registry: prometheus::Registry,
+    pub(crate) prometheus_registry: crate::prometheus_crate::Registry,
     #[cfg(prometheus_client)]
     pub(crate) prometheus_client_registry: prometheus_client::registry::Registry,
     #[cfg(prometheus_client)]
     pub(crate) prometheus_client_metrics: crate::tracker::prometheus_client::Metrics,
+    #[cfg(prometheus_client)]
+    pub(crate) prometheus_client_shadow_registry: Option<prometheus_client::registry::Registry>,
+    #[cfg(prometheus_client)]
+    pub(crate) prometheus_client_shadow_metrics: Option<crate::tracker::prometheus_client::Metrics>,
 }
 
 impl AutometricsSettings {
@@ -57,7 +353,7 @@ impl AutometricsSettings {
     /// [`Registry`]: prometheus::Registry
     /// [`prometheus_exporter`]: crate::prometheus_exporter
     #[cfg(any(prometheus, opentelemetry))]
-    pub fn prometheus_registry(&self) -> &prometheus::Registry {
+    pub fn prometheus_registry(&self) -> &crate::prometheus_crate::Registry {
         &self.prometheus_registry
     }
 
@@ -72,6 +368,101 @@ impl AutometricsSettings {
     pub fn prometheus_client_registry(&self) -> &prometheus_client::registry::Registry {
         &self.prometheus_client_registry
     }
+
+    /// Access the shadow [`Registry`] configured with [`AutometricsSettingsBuilder::shadow_registry`], if any.
+    ///
+    /// [`Registry`]: prometheus_client::registry::Registry
+    #[cfg(prometheus_client)]
+    pub fn shadow_registry(&self) -> Option<&prometheus_client::registry::Registry> {
+        self.prometheus_client_shadow_registry.as_ref()
+    }
+
+    /// Remove the function call counters and latency histograms from the shadow registry,
+    /// so that it only contains the calls made from now on.
+    ///
+    /// The concurrency gauges and the other metrics are kept.
+    #[cfg(prometheus_client)]
+    pub fn reset_shadow_registry(&self) {
+        if let Some(metrics) = &self.prometheus_client_shadow_metrics {
+            metrics.reset();
+        }
+    }
+
+    /// Add the configured prefix to a Prometheus-style metric name (like `function_calls_total`).
+    #[allow(dead_code)]
+    pub(crate) fn prometheus_metric_name(&self, name: &str) -> String {
+        prefixed_metric_name(self.metric_name_prefix, '_', name)
+    }
+
+    /// The Prometheus-style name of the latency histogram, with the configured prefix and duration unit.
+    #[allow(dead_code)]
+    pub(crate) fn prometheus_histogram_name(&self) -> String {
+        self.prometheus_metric_name(
+            &self
+                .duration_unit
+                .histogram_name(self.metric_naming.prometheus_histogram_name()),
+        )
+    }
+
+    /// The OpenTelemetry-style name of the latency histogram, with the configured prefix.
+    #[allow(dead_code)]
+    pub(crate) fn otel_histogram_name(&self) -> String {
+        self.otel_metric_name(self.metric_naming.histogram_name())
+    }
+
+    /// Add the configured prefix to an OpenTelemetry-style metric name (like `function.calls`).
+    #[allow(dead_code)]
+    pub(crate) fn otel_metric_name(&self, name: &str) -> String {
+        prefixed_metric_name(self.metric_name_prefix, '.', name)
+    }
+
+    /// The key of the given label in the exported metrics, which is only different
+    /// for the `function` and `module` labels if they were renamed.
+    pub(crate) fn label_key(&'static self, key: &'static str) -> &'static str {
+        match key {
+            FUNCTION_KEY => &self.function_label_key,
+            MODULE_KEY => &self.module_label_key,
+            key => key,
+        }
+    }
+
+    /// The value of the `module` (or `caller_module`) label for the given module path.
+    pub(crate) fn module_label_value(&self, module: &'static str) -> &'static str {
+        let Some(transform) = &self.module_path_transform else {
+            return self.label_value(module);
+        };
+        // Calls that do not have a caller have an empty caller module
+        if module.is_empty() {
+            return module;
+        }
+        if let Some(transformed) = TRANSFORMED_MODULES
+            .read()
+            .unwrap_or_else(|err| err.into_inner())
+            .get(module)
+        {
+            return transformed;
+        }
+        TRANSFORMED_MODULES
+            .write()
+            .unwrap_or_else(|err| err.into_inner())
+            .entry(module)
+            .or_insert_with(|| self.label_value(Box::leak((transform.0)(module).into_boxed_str())))
+    }
+
+    /// The label value, shortened if it is longer than the [`max_label_value_length`](AutometricsSettingsBuilder::max_label_value_length).
+    pub(crate) fn label_value(&self, value: &'static str) -> &'static str {
+        match self.max_label_value_length {
+            Some(max_length) => truncate_label_value(value, max_length),
+            None => value,
+        }
+    }
+}
+
+pub(crate) fn prefixed_metric_name(prefix: Option<&str>, separator: char, name: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{prefix}{separator}{name}"),
+        None => name.to_string(),
+    }
 }
 
 #[derive(Debug, Default)]
@@ -79,16 +470,53 @@ pub struct AutometricsSettingsBuilder {
     pub(crate) service_name: Option<String>,
     pub(crate) repo_url: Option<String>,
     pub(crate) repo_provider: Option<String>,
+    pub(crate) prometheus_url: Option<String>,
+    pub(crate) build_info: BuildInfo,
+    pub(crate) runtime_build_info: bool,
+    pub(crate) global_labels: Vec<(String, String)>,
+    pub(crate) metric_name_prefix: Option<&'static str>,
+    pub(crate) label_key_overrides: Vec<(String, String)>,
+    pub(crate) module_path_transform: Option<ModulePathTransform>,
+    pub(crate) max_label_value_length: Option<usize>,
+    pub(crate) histogram_views: Vec<HistogramView>,
+    pub(crate) call_edges: Option<usize>,
+    pub(crate) cardinality_watchdog: Option<CardinalityWatchdog>,
+    #[cfg(prometheus_client)]
+    pub(crate) max_label_sets: Option<usize>,
+    pub(crate) crate_rollups: bool,
+    #[cfg(stream)]
+    pub(crate) stream_interval: Option<Duration>,
+    pub(crate) error_classifier: Option<ErrorClassifier>,
+    pub(crate) cold_start_label: bool,
+    pub(crate) alias_mode: AliasMode,
+    pub(crate) disable_caller_tracking: bool,
+    pub(crate) clock: Option<BoxedClock>,
+    #[cfg(opentelemetry)]
+    pub(crate) concurrency_instrument: ConcurrencyInstrument,
+    /// Whether the `prometheus_exporter` leaves the global `metrics` recorder that is already installed in place
+    #[cfg(metrics)]
+    pub(crate) use_existing_metrics_recorder: bool,
+    #[cfg(wide_events)]
+    pub(crate) wide_events: Option<WideEvents>,
+    #[cfg(span_traces)]
+    pub(crate) span_traces: Option<BoxedSpanTraceSink>,
+    #[cfg(exemplars_custom)]
+    pub(crate) exemplar_provider: Option<BoxedExemplarProvider>,
     #[cfg(any(prometheus_exporter, prometheus, prometheus_client))]
     pub(crate) histogram_buckets: Option<Vec<f64>>,
+    pub(crate) duration_unit: DurationUnit,
+    pub(crate) metric_naming: MetricNaming,
     #[cfg(any(prometheus, opentelemetry))]
-    pub(crate) prometheus_registry: Option<prometheus::Registry>,
+    pub(crate) prometheus_registry: Option<crate::prometheus_crate::Registry>,
     #[cfg(prometheus_client)]
     pub(crate) prometheus_client_registry: Option<prometheus_client::registry::Registry>,
+    #[cfg(prometheus_client)]
+    pub(crate) shadow_registry: Option<prometheus_client::registry::Registry>,
 }
 
 impl AutometricsSettingsBuilder {
-    /// Set the buckets, represented in seconds, used for the function latency histograms.
+    /// Set the buckets, represented in the [`duration_unit`](Self::duration_unit) (seconds by default),
+    /// used for the function latency histograms.
     ///
     /// If this is not set, the buckets recommended by the [OpenTelemetry specification] are used.
     ///
@@ -99,6 +527,89 @@ impl AutometricsSettingsBuilder {
         self
     }
 
+    /// Record the function latencies in an additional histogram with the given buckets, represented in
+    /// the [`duration_unit`](Self::duration_unit) (seconds by default).
+    ///
+    /// The histogram has the same labels as the `function.calls.duration` histogram and is named
+    /// `function.calls.duration.<name>` (or `function_calls_duration_<name>_seconds` when exported to Prometheus).
+    /// This is useful if you need different buckets for different purposes, for example coarse buckets for
+    /// long-window SLOs, because the latency is only measured once and recorded in all of the histograms.
+    ///
+    /// The name should only contain ASCII letters, digits, and underscores.
+    ///
+    /// ```rust
+    /// # use autometrics::settings::AutometricsSettings;
+    /// AutometricsSettings::builder()
+    ///     .histogram_view("coarse", [1.0, 5.0, 30.0, 60.0])
+    ///     .init();
+    /// ```
+    ///
+    /// Note that the buckets are only applied by the [`prometheus_exporter`](crate::prometheus_exporter) when using the `opentelemetry` or `metrics` backends.
+    /// If you configure those libraries yourself, you will need to set the buckets for the additional histograms as well.
+    pub fn histogram_view(mut self, name: impl Into<String>, buckets: impl Into<Vec<f64>>) -> Self {
+        // The full names depend on the naming convention and the unit, so they are set in `build`
+        self.histogram_views.push(HistogramView {
+            name: name.into(),
+            prometheus_name: String::new(),
+            buckets: buckets.into(),
+        });
+        self
+    }
+
+    /// Record the function latencies in the given unit instead of seconds.
+    ///
+    /// This is useful if your organization standardizes on milliseconds:
+    ///
+    /// ```rust
+    /// # use autometrics::settings::{AutometricsSettings, DurationUnit};
+    /// AutometricsSettings::builder()
+    ///     .duration_unit(DurationUnit::Milliseconds)
+    ///     .init();
+    /// ```
+    ///
+    /// The unit changes, consistently across the backends:
+    /// - the name of the latency histogram (and its [views](Self::histogram_view)), which becomes
+    ///   `function_calls_duration_milliseconds` when exported to Prometheus
+    /// - the values recorded in the histogram
+    /// - the unit of the [`histogram_buckets`](Self::histogram_buckets), the buckets of the views,
+    ///   and the `buckets` argument of the `autometrics` macro. The default buckets and the
+    ///   [`FINE_GRAINED_LATENCY_BUCKETS`] are converted automatically
+    /// - the values of the `objective_latency_threshold` label, so that they still match the buckets.
+    ///   `ObjectiveLatency::Custom` thresholds are also interpreted in this unit
+    ///
+    /// The queries that the `autometrics` macro adds to the function documentation are generated at compile time,
+    /// so they only use the unit if it is also set in the `AUTOMETRICS_DURATION_UNIT` compile-time environment variable
+    /// (like `AUTOMETRICS_DURATION_UNIT=milliseconds`).
+    pub fn duration_unit(mut self, duration_unit: DurationUnit) -> Self {
+        self.duration_unit = duration_unit;
+        self
+    }
+
+    /// Name the metrics according to the given convention.
+    ///
+    /// Use [`MetricNaming::OpenTelemetry`] if your observability stack expects the names
+    /// from the OpenTelemetry semantic conventions:
+    ///
+    /// ```rust
+    /// # use autometrics::settings::{AutometricsSettings, MetricNaming};
+    /// AutometricsSettings::builder()
+    ///     .metric_naming(MetricNaming::OpenTelemetry)
+    ///     .init();
+    /// ```
+    ///
+    /// This renames the latency histogram (and its [views](Self::histogram_view)) from `function.calls.duration`
+    /// to `function.duration`, which becomes `function_duration_seconds` when exported to Prometheus.
+    /// The names of the other metrics already follow the conventions.
+    ///
+    /// The queries that the `autometrics` macro adds to the function documentation are generated at compile time,
+    /// so they only use these names if the convention is also set in the `AUTOMETRICS_METRIC_NAMING` compile-time
+    /// environment variable (like `AUTOMETRICS_METRIC_NAMING=opentelemetry`). The SLO file generated with
+    /// `autometrics generate-sloth-file` needs the matching `--metric-naming` argument.
+    pub fn metric_naming(mut self, metric_naming: MetricNaming) -> Self {
+        self.metric_naming = metric_naming;
+        self
+    }
+
     /// All metrics produced by Autometrics have a label called `service.name`
     /// (or `service_name` when exported to Prometheus) attached to
     /// identify the logical service they are part of.
@@ -125,6 +636,435 @@ impl AutometricsSettingsBuilder {
         self
     }
 
+    /// The URL of the Prometheus instance that scrapes the metrics, which is used for the links built by
+    /// the [`queries`](crate::queries) module.
+    ///
+    /// The priority for where the URL is loaded from is:
+    /// 1. This method
+    /// 2. `PROMETHEUS_URL` (at runtime)
+    /// 3. `http://localhost:9090`
+    ///
+    /// The links that the `autometrics` macro adds to the function documentation are generated at compile time,
+    /// so they use the `PROMETHEUS_URL` compile-time environment variable instead.
+    pub fn prometheus_url(mut self, prometheus_url: impl Into<String>) -> Self {
+        self.prometheus_url = Some(prometheus_url.into());
+        self
+    }
+
+    /// Set the build metadata reported in the `build_info` metric at runtime.
+    ///
+    /// By default, the version, commit, and branch are read from compile-time environment variables.
+    /// This is useful for binaries that embed their build metadata in another way,
+    /// for example by reading it from a build info file when they start:
+    ///
+    /// ```rust
+    /// # use autometrics::settings::{AutometricsSettings, BuildInfo};
+    /// AutometricsSettings::builder()
+    ///     .build_info(BuildInfo {
+    ///         version: Some("1.4.2".to_string()),
+    ///         commit: Some("7f3c2a1".to_string()),
+    ///         branch: Some("main".to_string()),
+    ///         ..Default::default()
+    ///     })
+    ///     .init();
+    /// ```
+    ///
+    /// Any fields that are not set keep their default values. The `repo_url` and `repo_provider`
+    /// are only used if they are not also set with [`repo_url`](Self::repo_url) and
+    /// [`repo_provider`](Self::repo_provider).
+    pub fn build_info(mut self, build_info: BuildInfo) -> Self {
+        self.build_info = build_info;
+        self
+    }
+
+    /// Add the `runtime` and `os` labels to the `build_info` metric.
+    ///
+    /// The `runtime` label is `tokio` if the first instrumented function is called within a Tokio runtime
+    /// (which requires the `tokio` feature), and the `os` label is the operating system the service runs on,
+    /// such as `linux`. Together with the `rustc.version` and `target` labels, this makes it possible to correlate
+    /// latency regressions with compiler and runtime upgrades.
+    ///
+    /// ```rust
+    /// # use autometrics::settings::AutometricsSettings;
+    /// AutometricsSettings::builder()
+    ///     .runtime_build_info(true)
+    ///     .init();
+    /// ```
+    pub fn runtime_build_info(mut self, enabled: bool) -> Self {
+        self.runtime_build_info = enabled;
+        self
+    }
+
+    /// Attach the given labels to every metric produced by Autometrics.
+    ///
+    /// This is useful for adding deployment metadata, such as the region or tenant,
+    /// to the function metrics and `build_info`:
+    ///
+    /// ```rust
+    /// # use autometrics::settings::AutometricsSettings;
+    /// AutometricsSettings::builder()
+    ///     .global_labels(&[("region", "eu-west-1"), ("tenant", "acme")])
+    ///     .init();
+    /// ```
+    ///
+    /// When exported to Prometheus, any dots in the label keys are replaced with underscores
+    /// (like `service.name` becomes `service_name`).
+    /// The keys should not be the same as any of the labels that Autometrics already uses.
+    pub fn global_labels(mut self, global_labels: &[(&str, &str)]) -> Self {
+        self.global_labels = global_labels
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        self
+    }
+
+    /// Add a prefix to the names of all of the metrics produced by Autometrics.
+    ///
+    /// The prefix is separated from the metric names with an underscore
+    /// (or a dot for the OpenTelemetry metric names), so this produces metrics
+    /// like `payments_function_calls_total`:
+    ///
+    /// ```rust
+    /// # use autometrics::settings::AutometricsSettings;
+    /// AutometricsSettings::builder()
+    ///     .metric_name_prefix("payments")
+    ///     .init();
+    /// ```
+    ///
+    /// The prefix should only contain ASCII letters, digits, and underscores.
+    ///
+    /// The queries that the `autometrics` macro adds to the function documentation are generated at compile time,
+    /// so they only use the prefix if it is also set in the `AUTOMETRICS_METRIC_NAME_PREFIX` compile-time environment variable.
+    pub fn metric_name_prefix(mut self, prefix: &'static str) -> Self {
+        self.metric_name_prefix = Some(prefix);
+        self
+    }
+
+    /// Rename the `function` and `module` labels of all of the metrics produced by Autometrics.
+    ///
+    /// This is useful if your metrics need to follow a naming convention:
+    ///
+    /// ```rust
+    /// # use autometrics::settings::AutometricsSettings;
+    /// AutometricsSettings::builder()
+    ///     .label_key_overrides(&[("function", "fn"), ("module", "mod")])
+    ///     .init();
+    /// ```
+    ///
+    /// Only the `function` and `module` labels can be renamed, and [`try_init`](Self::try_init) returns an error
+    /// for any other key. The new keys should only contain ASCII letters, digits, and underscores.
+    ///
+    /// The queries that the `autometrics` macro adds to the function documentation are generated at compile time,
+    /// so they only use the new keys if they are also set in the `AUTOMETRICS_LABEL_KEY_OVERRIDES` compile-time
+    /// environment variable (like `AUTOMETRICS_LABEL_KEY_OVERRIDES=function=fn,module=mod`).
+    pub fn label_key_overrides(mut self, overrides: &[(&str, &str)]) -> Self {
+        self.label_key_overrides = overrides
+            .iter()
+            .map(|(key, new_key)| (key.to_string(), new_key.to_string()))
+            .collect();
+        self
+    }
+
+    /// Transform the module paths before they are used as the values of the `module` and `caller_module` labels.
+    ///
+    /// The function is called once for each module path. For example, to remove the name of the crate:
+    ///
+    /// ```rust
+    /// # use autometrics::settings::AutometricsSettings;
+    /// AutometricsSettings::builder()
+    ///     .module_path_transform(|module| match module.split_once("::") {
+    ///         Some((_crate_name, path)) => path.to_string(),
+    ///         None => module.to_string(),
+    ///     })
+    ///     .init();
+    /// ```
+    pub fn module_path_transform(
+        mut self,
+        transform: impl Fn(&str) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.module_path_transform = Some(ModulePathTransform(Box::new(transform)));
+        self
+    }
+
+    /// Shorten the values of the `function`, `module`, `caller_function`, `caller_module`,
+    /// and `type_params` labels that are longer than the given number of bytes.
+    ///
+    /// This keeps deeply nested module paths, or the names of functions in generated code,
+    /// within the label length limits of the backend that stores the metrics:
+    ///
+    /// ```rust
+    /// # use autometrics::settings::AutometricsSettings;
+    /// AutometricsSettings::builder()
+    ///     .max_label_value_length(128)
+    ///     .init();
+    /// ```
+    ///
+    /// The end of a value that is too long is replaced by `~` and a 16-digit hex hash of the whole value,
+    /// so each value is always shortened the same way and the time series of different functions stay separate.
+    /// The module paths are shortened after the [`module_path_transform`](Self::module_path_transform) is applied.
+    ///
+    /// The length must be at least 32 bytes, or [`try_init`](Self::try_init) returns an error.
+    pub fn max_label_value_length(mut self, max_length: usize) -> Self {
+        self.max_label_value_length = Some(max_length);
+        self
+    }
+
+    /// Export the `function_call_edges_info` metric, which has a series with the value 1
+    /// for every pair of functions where one was seen calling the other.
+    ///
+    /// This makes it possible to build a map of the functions of a service from a single scrape,
+    /// without going through all of the series of the `function_calls_total` counter.
+    /// At most `max_edges` pairs are kept. When more are seen, the pairs that were seen
+    /// least recently are removed.
+    ///
+    /// ```rust
+    /// # use autometrics::settings::AutometricsSettings;
+    /// AutometricsSettings::builder()
+    ///     .call_edges_info(1000)
+    ///     .init();
+    /// ```
+    ///
+    /// The `metrics` backend cannot remove series, so the pairs that are removed are set to 0.
+    pub fn call_edges_info(mut self, max_edges: usize) -> Self {
+        self.call_edges = Some(max_edges);
+        self
+    }
+
+    /// Warn when an instrumented function creates more series than the configured threshold.
+    ///
+    /// See the [`cardinality`](crate::cardinality) module for details.
+    pub fn cardinality_watchdog(mut self, cardinality_watchdog: CardinalityWatchdog) -> Self {
+        self.cardinality_watchdog = Some(cardinality_watchdog);
+        self
+    }
+
+    /// Keep at most `max_label_sets` label sets in each of the `function_calls_total` counter
+    /// and the `function_calls_duration_seconds` histogram.
+    ///
+    /// In long-running processes, labels with many short-lived values (like the callers of a function
+    /// or the variants of its errors) make these metrics grow forever. Once the limit is reached, the label
+    /// set that was recorded least recently is removed every time a new one is recorded, and counted in the
+    /// `autometrics_label_set_evictions_total` metric. If the label set is recorded again later,
+    /// its series starts over from zero, which Prometheus handles like a counter reset.
+    ///
+    /// ```rust
+    /// # use autometrics::settings::AutometricsSettings;
+    /// AutometricsSettings::builder()
+    ///     .max_label_sets(10_000)
+    ///     .init();
+    /// ```
+    ///
+    /// This is only supported by the `prometheus-client` backend. At least one label set is always kept.
+    #[cfg(prometheus_client)]
+    pub fn max_label_sets(mut self, max_label_sets: usize) -> Self {
+        self.max_label_sets = Some(max_label_sets.max(1));
+        self
+    }
+
+    /// Also count the calls to instrumented functions per crate, in the `crate_calls_total` metric
+    /// (`crate.calls` with OpenTelemetry) with only the `crate`, `result`, and `service_name` labels.
+    ///
+    /// This gives a small number of series for monitoring many services or functions at a glance,
+    /// when the details of each function are not needed:
+    ///
+    /// ```promql
+    /// sum by (crate) (rate(crate_calls_total{result="error"}[5m]))
+    ///   / sum by (crate) (rate(crate_calls_total[5m]))
+    /// ```
+    ///
+    /// The crate is the first segment of the `module` label, so it is not the crate name if the module paths are
+    /// changed with [`module_path_transform`](Self::module_path_transform). This is disabled by default.
+    pub fn crate_rollups(mut self, enabled: bool) -> Self {
+        self.crate_rollups = enabled;
+        self
+    }
+
+    /// Set the length of the windows over which the calls are aggregated in the [`stream`](crate::stream) of deltas.
+    ///
+    /// This defaults to one second.
+    ///
+    /// ```rust
+    /// # use autometrics::settings::AutometricsSettings;
+    /// # use std::time::Duration;
+    /// AutometricsSettings::builder()
+    ///     .stream_interval(Duration::from_millis(250))
+    ///     .init();
+    /// ```
+    #[cfg(stream)]
+    pub fn stream_interval(mut self, interval: Duration) -> Self {
+        self.stream_interval = Some(interval.max(Duration::from_millis(1)));
+        self
+    }
+
+    /// Use the class of the root cause of the errors returned by instrumented functions as the `error` label.
+    ///
+    /// See the [`error_classifier`](crate::error_classifier) module for details.
+    pub fn error_classifier(mut self, error_classifier: ErrorClassifier) -> Self {
+        self.error_classifier = Some(error_classifier);
+        self
+    }
+
+    /// Record the latency of the first call of each function with the `cold="true"` label.
+    ///
+    /// The first call often includes the time to initialize lazy statics, connection pools, and caches,
+    /// so in services with little traffic it can dominate the highest latency percentiles.
+    /// With this label, the first calls are kept in separate series of the `function_calls_duration_seconds`
+    /// histogram, and the alerting rules generated with the `alerts` feature exclude them from the latency objectives.
+    /// To exclude them from other queries, add `cold!="true"` to the label selector.
+    ///
+    /// The calls are still counted in the `function_calls_total` counter.
+    ///
+    /// ```rust
+    /// # use autometrics::settings::AutometricsSettings;
+    /// AutometricsSettings::builder()
+    ///     .cold_start_label(true)
+    ///     .init();
+    /// ```
+    pub fn cold_start_label(mut self, enabled: bool) -> Self {
+        self.cold_start_label = enabled;
+        self
+    }
+
+    /// Choose how the previous names of functions are reported, for functions
+    /// renamed with the `alias = "old_name"` argument of the `autometrics` macro.
+    ///
+    /// By default, each renamed function reports a `function_renamed_info` series that maps its new name
+    /// to the old one, which can be joined with the function's metrics. With [`AliasMode::Label`],
+    /// the old name is added to the function's own series in the `previous_function` label instead,
+    /// which does not need a join but creates new series for the transition period.
+    ///
+    /// ```rust
+    /// # use autometrics::settings::{AliasMode, AutometricsSettings};
+    /// AutometricsSettings::builder()
+    ///     .alias_mode(AliasMode::Label)
+    ///     .init();
+    /// ```
+    ///
+    /// To rename the old series to the new name in Prometheus instead, generate relabeling rules
+    /// with `autometrics generate-relabel-config`.
+    pub fn alias_mode(mut self, alias_mode: AliasMode) -> Self {
+        self.alias_mode = alias_mode;
+        self
+    }
+
+    /// Stop tracking which function called each instrumented function.
+    ///
+    /// By default, every instrumented function stores its name in a task-local variable while it runs,
+    /// so that the functions it calls can report it in their `caller_function` and `caller_module` labels.
+    /// If you do not use these labels, disabling this removes the task-local accesses from every call.
+    /// The caller labels are then left empty, and recursive calls are no longer detected
+    /// (for the `recursive` argument and the `track_concurrency` gauge).
+    ///
+    /// To only disable this for specific functions, use the `no_caller` argument of the macro instead.
+    ///
+    /// ```rust
+    /// # use autometrics::settings::AutometricsSettings;
+    /// AutometricsSettings::builder()
+    ///     .disable_caller_tracking()
+    ///     .init();
+    /// ```
+    pub fn disable_caller_tracking(mut self) -> Self {
+        self.disable_caller_tracking = true;
+        self
+    }
+
+    /// Measure the latencies with the given clock instead of the [`DefaultClock`].
+    ///
+    /// This can be a closure that returns the time elapsed since a fixed point in time, for runtimes
+    /// that provide their own timer (the default clock uses `performance.now()` on `wasm32-unknown-unknown`).
+    ///
+    /// ```rust
+    /// # use autometrics::settings::AutometricsSettings;
+    /// use std::time::Instant;
+    ///
+    /// let origin = Instant::now();
+    /// AutometricsSettings::builder()
+    ///     .clock(move || origin.elapsed())
+    ///     .init();
+    /// ```
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Some(BoxedClock(Box::new(clock)));
+        self
+    }
+
+    /// Choose the OpenTelemetry instrument that records the number of concurrent calls.
+    ///
+    /// By default, the calls that start and finish are added to and subtracted from an `UpDownCounter`.
+    /// With [`ConcurrencyInstrument::Gauge`], the current number of concurrent calls is recorded with
+    /// a synchronous gauge instead, for OTLP backends that do not render up-down counters correctly:
+    ///
+    /// ```rust
+    /// use autometrics::settings::{AutometricsSettings, ConcurrencyInstrument};
+    ///
+    /// AutometricsSettings::builder()
+    ///     .concurrency_instrument(ConcurrencyInstrument::Gauge)
+    ///     .init();
+    /// ```
+    ///
+    /// This is only available with the `opentelemetry` backends.
+    #[cfg(opentelemetry)]
+    pub fn concurrency_instrument(mut self, instrument: ConcurrencyInstrument) -> Self {
+        self.concurrency_instrument = instrument;
+        self
+    }
+
+    /// Record the metrics with the `metrics` recorder that the application has already installed,
+    /// such as a StatsD exporter, instead of the recorder of the [`prometheus_exporter`](crate::prometheus_exporter).
+    ///
+    /// The `metrics` backend always records to the global recorder. By default, initializing the Prometheus exporter
+    /// installs its own recorder, which fails if the application has already installed one. With this setting,
+    /// the exporter leaves the existing recorder in place, so the autometrics metrics become part of your
+    /// existing pipeline:
+    ///
+    /// ```rust,ignore
+    /// use autometrics::settings::AutometricsSettings;
+    ///
+    /// metrics::set_global_recorder(my_statsd_recorder).unwrap();
+    ///
+    /// AutometricsSettings::builder()
+    ///     .use_existing_metrics_recorder()
+    ///     .init();
+    /// ```
+    ///
+    /// The recorder should be installed before the first instrumented function is called,
+    /// because the metric descriptions are only sent to the recorder once.
+    /// The output of [`prometheus_exporter::encode_to_string`](crate::prometheus_exporter::encode_to_string) does not
+    /// include the autometrics metrics in this mode, since they are exported by your recorder instead.
+    ///
+    /// This is only available with the `metrics` backend.
+    #[cfg(metrics)]
+    pub fn use_existing_metrics_recorder(mut self) -> Self {
+        self.use_existing_metrics_recorder = true;
+        self
+    }
+
+    /// Attach the exemplars returned by the given provider to the metrics.
+    ///
+    /// See the [`exemplars`](crate::exemplars) module for details.
+    #[cfg(exemplars_custom)]
+    pub fn exemplar_provider(mut self, provider: impl ExemplarProvider + 'static) -> Self {
+        self.exemplar_provider = Some(BoxedExemplarProvider(Box::new(provider)));
+        self
+    }
+
+    /// Emit a structured event for every call to an instrumented function, in addition to the metrics.
+    ///
+    /// See the [`wide_events`](crate::wide_events) module for the available sinks and sampling options.
+    #[cfg(wide_events)]
+    pub fn wide_events(mut self, wide_events: WideEvents) -> Self {
+        self.wide_events = Some(wide_events);
+        self
+    }
+
+    /// Capture the [`SpanTrace`](crate::span_traces::SpanTrace) of the calls that return errors and pass them to the sink.
+    ///
+    /// See the [`span_traces`](crate::span_traces) module for details.
+    #[cfg(span_traces)]
+    pub fn span_traces(mut self, sink: impl SpanTraceSink + 'static) -> Self {
+        self.span_traces = Some(BoxedSpanTraceSink(Box::new(sink)));
+        self
+    }
+
     /// Configure the [`prometheus::Registry`] that will be used to collect metrics when using
     /// either the `prometheus` or `opentelemetry` backends. If none is set, it will use
     /// the [`prometheus::default_registry`].
@@ -135,8 +1075,11 @@ impl AutometricsSettingsBuilder {
     /// If you are not using the provided [`prometheus_exporter`] to export metrics and want to encode
     /// the metrics from the `Registry`, you can simply `clone` the `Registry` before passing it in here
     /// and use the original one for encoding.
+    ///
+    /// Note that the `opentelemetry-0_33` backend uses version 0.14 of the `prometheus` crate,
+    /// so the `Registry` needs to come from that version.
     #[cfg(any(prometheus, opentelemetry))]
-    pub fn prometheus_registry(mut self, registry: prometheus::Registry) -> Self {
+    pub fn prometheus_registry(mut self, registry: crate::prometheus_crate::Registry) -> Self {
         self.prometheus_registry = Some(registry);
         self
     }
@@ -156,6 +1099,39 @@ impl AutometricsSettingsBuilder {
         self
     }
 
+    /// Record every call in a secondary "shadow" [`prometheus_client::registry::Registry`] too.
+    ///
+    /// The shadow registry receives the same recordings as the main one, but it can be reset with
+    /// [`AutometricsSettings::reset_shadow_registry`] without affecting the metrics scraped by Prometheus.
+    /// This is useful for canary verification tooling that compares the calls made during a test window.
+    /// It is not exported by the [`prometheus_exporter`]; access it via [`AutometricsSettings::shadow_registry`] instead:
+    ///
+    /// ```rust
+    /// use autometrics::{autometrics, settings::AutometricsSettings};
+    /// use prometheus_client::{encoding::text::encode, registry::Registry};
+    ///
+    /// #[autometrics]
+    /// fn checkout() {}
+    ///
+    /// let settings = AutometricsSettings::builder()
+    ///     .shadow_registry(Registry::default())
+    ///     .init();
+    ///
+    /// settings.reset_shadow_registry();
+    /// checkout();
+    ///
+    /// let mut metrics = String::new();
+    /// encode(&mut metrics, settings.shadow_registry().unwrap()).unwrap();
+    /// assert!(metrics.contains(r#"function="checkout""#));
+    /// ```
+    ///
+    /// This is only available with the `prometheus-client` backend.
+    #[cfg(prometheus_client)]
+    pub fn shadow_registry(mut self, registry: prometheus_client::registry::Registry) -> Self {
+        self.shadow_registry = Some(registry);
+        self
+    }
+
     /// Set the global settings for Autometrics. This returns an error if the
     /// settings have already been initialized.
     ///
@@ -164,6 +1140,25 @@ impl AutometricsSettingsBuilder {
     ///
     /// If the Prometheus exporter is enabled, this will also initialize it.
     pub fn try_init(self) -> Result<&'static AutometricsSettings, SettingsInitializationError> {
+        if let Some((key, _)) = self
+            .label_key_overrides
+            .iter()
+            .find(|(key, _)| key != FUNCTION_KEY && key != MODULE_KEY)
+        {
+            return Err(SettingsInitializationError::UnsupportedLabelKeyOverride(
+                key.clone(),
+            ));
+        }
+
+        if let Some(max_length) = self
+            .max_label_value_length
+            .filter(|max_length| *max_length < MIN_LABEL_VALUE_LENGTH)
+        {
+            return Err(SettingsInitializationError::LabelValueLengthTooShort(
+                max_length,
+            ));
+        }
+
         let settings = self.build();
 
         let settings = AUTOMETRICS_SETTINGS
@@ -190,16 +1185,73 @@ impl AutometricsSettingsBuilder {
         self.try_init().unwrap()
     }
 
-    fn build(self) -> AutometricsSettings {
+    fn build(mut self) -> AutometricsSettings {
+        for view in &mut self.histogram_views {
+            let view_name = std::mem::take(&mut view.name);
+            view.name = format!("{}.{view_name}", self.metric_naming.histogram_name());
+            view.prometheus_name = self.duration_unit.histogram_name(&format!(
+                "{}_{view_name}",
+                self.metric_naming
+                    .prometheus_histogram_name()
+                    .trim_end_matches("_seconds")
+            ));
+        }
+        if let Some(prefix) = self.metric_name_prefix {
+            for view in &mut self.histogram_views {
+                view.name = prefixed_metric_name(Some(prefix), '.', &view.name);
+                view.prometheus_name =
+                    prefixed_metric_name(Some(prefix), '_', &view.prometheus_name);
+            }
+        }
+
         #[cfg(prometheus_client)]
         let (prometheus_client_registry, prometheus_client_metrics) =
             crate::tracker::prometheus_client::initialize_registry(
                 self.prometheus_client_registry
                     .unwrap_or_else(<prometheus_client::registry::Registry>::default),
+                &self.global_labels,
+                self.metric_name_prefix,
+                self.metric_naming,
+                &self.histogram_views,
+                self.duration_unit,
+                self.call_edges.is_some(),
+                self.cardinality_watchdog.is_some(),
+                self.max_label_sets.is_some(),
+                self.crate_rollups,
             );
 
+        #[cfg(prometheus_client)]
+        let (prometheus_client_shadow_registry, prometheus_client_shadow_metrics) = self
+            .shadow_registry
+            .map(|registry| {
+                crate::tracker::prometheus_client::initialize_registry(
+                    registry,
+                    &self.global_labels,
+                    self.metric_name_prefix,
+                    self.metric_naming,
+                    &self.histogram_views,
+                    self.duration_unit,
+                    self.call_edges.is_some(),
+                    self.cardinality_watchdog.is_some(),
+                    self.max_label_sets.is_some(),
+                    self.crate_rollups,
+                )
+            })
+            .unzip();
+
+        let label_key = |key: &str| {
+            self.label_key_overrides
+                .iter()
+                .find(|(from, _)| from == key)
+                .map_or_else(|| key.to_string(), |(_, to)| to.clone())
+        };
+        let function_label_key = label_key(FUNCTION_KEY);
+        let module_label_key = label_key(MODULE_KEY);
+
+        let mut build_info = self.build_info;
         let repo_url = self
             .repo_url
+            .or_else(|| build_info.repo_url.take())
             .or_else(|| env::var("AUTOMETRICS_REPOSITORY_URL").ok())
             .unwrap_or_else(|| env!("CARGO_PKG_REPOSITORY").to_string());
 
@@ -207,7 +1259,9 @@ impl AutometricsSettingsBuilder {
             #[cfg(any(prometheus_exporter, prometheus, prometheus_client))]
             histogram_buckets: self
                 .histogram_buckets
-                .unwrap_or_else(|| DEFAULT_HISTOGRAM_BUCKETS.to_vec()),
+                .unwrap_or_else(|| self.duration_unit.default_histogram_buckets().to_vec()),
+            duration_unit: self.duration_unit,
+            metric_naming: self.metric_naming,
             service_name: self
                 .service_name
                 .or_else(|| env::var("AUTOMETRICS_SERVICE_NAME").ok())
@@ -215,6 +1269,7 @@ impl AutometricsSettingsBuilder {
                 .unwrap_or_else(|| env!("CARGO_PKG_NAME").to_string()),
             repo_provider: self
                 .repo_provider
+                .or_else(|| build_info.repo_provider.take())
                 .or_else(|| env::var("AUTOMETRICS_REPOSITORY_PROVIDER").ok())
                 .or_else(|| {
                     AutometricsSettingsBuilder::determinate_repo_provider_from_url(Some(&repo_url))
@@ -222,14 +1277,55 @@ impl AutometricsSettingsBuilder {
                 })
                 .unwrap_or_default(),
             repo_url,
+            prometheus_url: self
+                .prometheus_url
+                .or_else(|| env::var("PROMETHEUS_URL").ok())
+                .unwrap_or_else(|| crate::queries::DEFAULT_PROMETHEUS_URL.to_string()),
+            build_info,
+            runtime_build_info: self.runtime_build_info,
+            global_labels: self.global_labels,
+            metric_name_prefix: self.metric_name_prefix,
+            function_label_key,
+            module_label_key,
+            module_path_transform: self.module_path_transform,
+            max_label_value_length: self.max_label_value_length,
+            histogram_views: self.histogram_views,
+            call_edges: self.call_edges,
+            cardinality_watchdog: self.cardinality_watchdog,
+            #[cfg(prometheus_client)]
+            max_label_sets: self.max_label_sets,
+            crate_rollups: self.crate_rollups,
+            #[cfg(stream)]
+            stream_interval: self.stream_interval.unwrap_or(Duration::from_secs(1)),
+            error_classifier: self.error_classifier,
+            cold_start_label: self.cold_start_label,
+            alias_mode: self.alias_mode,
+            track_callers: !self.disable_caller_tracking,
+            clock: self
+                .clock
+                .unwrap_or_else(|| BoxedClock(Box::new(DefaultClock))),
+            #[cfg(opentelemetry)]
+            concurrency_instrument: self.concurrency_instrument,
+            #[cfg(metrics)]
+            use_existing_metrics_recorder: self.use_existing_metrics_recorder,
+            #[cfg(wide_events)]
+            wide_events: self.wide_events,
+            #[cfg(span_traces)]
+            span_traces: self.span_traces,
+            #[cfg(exemplars_custom)]
+            exemplar_provider: self.exemplar_provider,
             #[cfg(prometheus_client)]
             prometheus_client_registry,
             #[cfg(prometheus_client)]
             prometheus_client_metrics,
+            #[cfg(prometheus_client)]
+            prometheus_client_shadow_registry,
+            #[cfg(prometheus_client)]
+            prometheus_client_shadow_metrics,
             #[cfg(any(prometheus, opentelemetry))]
             prometheus_registry: self
                 .prometheus_registry
-                .unwrap_or_else(|| prometheus::default_registry().clone()),
+                .unwrap_or_else(|| crate::prometheus_crate::default_registry().clone()),
         }
     }
 
@@ -250,11 +1346,75 @@ impl AutometricsSettingsBuilder {
     }
 }
 
+/// An additional histogram that records the function latencies with different buckets.
+///
+/// See [`AutometricsSettingsBuilder::histogram_view`].
+#[derive(Clone, Debug)]
+pub(crate) struct HistogramView {
+    #[cfg_attr(not(opentelemetry), allow(dead_code))]
+    pub(crate) name: String,
+    #[cfg_attr(not(any(metrics, prometheus, prometheus_client)), allow(dead_code))]
+    pub(crate) prometheus_name: String,
+    #[cfg_attr(
+        not(any(prometheus_exporter, prometheus, prometheus_client)),
+        allow(dead_code)
+    )]
+    pub(crate) buckets: Vec<f64>,
+}
+
+/// The function set with [`AutometricsSettingsBuilder::module_path_transform`].
+pub(crate) struct ModulePathTransform(Box<dyn Fn(&str) -> String + Send + Sync>);
+
+impl fmt::Debug for ModulePathTransform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ModulePathTransform").finish_non_exhaustive()
+    }
+}
+
+/// The clock set with [`AutometricsSettingsBuilder::clock`].
+pub(crate) struct BoxedClock(pub(crate) Box<dyn Clock>);
+
+impl fmt::Debug for BoxedClock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Clock").finish_non_exhaustive()
+    }
+}
+
+/// The provider set with [`AutometricsSettingsBuilder::exemplar_provider`].
+#[cfg(exemplars_custom)]
+pub(crate) struct BoxedExemplarProvider(pub(crate) Box<dyn ExemplarProvider>);
+
+#[cfg(exemplars_custom)]
+impl fmt::Debug for BoxedExemplarProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ExemplarProvider").finish_non_exhaustive()
+    }
+}
+
+/// The sink set with [`AutometricsSettingsBuilder::span_traces`].
+#[cfg(span_traces)]
+pub(crate) struct BoxedSpanTraceSink(pub(crate) Box<dyn SpanTraceSink>);
+
+#[cfg(span_traces)]
+impl fmt::Debug for BoxedSpanTraceSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SpanTraceSink").finish_non_exhaustive()
+    }
+}
+
 #[derive(Debug, Error)]
 pub enum SettingsInitializationError {
-    #[error("Autometrics settings have already been initialized")]
+    #[error("Autometrics settings have already been initialized (the default settings are initialized when an instrumented function is first called or the Prometheus exporter is initialized)")]
     AlreadyInitialized,
 
+    #[error("Only the function and module labels can be renamed, not {0:?}")]
+    UnsupportedLabelKeyOverride(String),
+
+    #[error(
+        "The maximum label value length must be at least {MIN_LABEL_VALUE_LENGTH} bytes, not {0}"
+    )]
+    LabelValueLengthTooShort(usize),
+
     #[cfg(prometheus_exporter)]
     #[error(transparent)]
     PrometheusExporter(#[from] ExporterInitializationError),
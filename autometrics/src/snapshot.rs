@@ -0,0 +1,352 @@
+//! Hand off the metrics recorded in one process to another process, in a compact binary format.
+//!
+//! This is useful for applications that fork worker processes (such as pre-fork servers or job runners),
+//! where only one process serves the metrics. Each worker periodically [`take`]s a snapshot of the calls it
+//! recorded since the previous one and sends the [encoded](Snapshot::to_bytes) snapshot to the process that
+//! exports the metrics (for example, over a pipe), which [`merge`]s it into its own metrics. The snapshots
+//! are aggregated per set of labels, so their size depends on the number of series rather than the number
+//! of calls, and they are compressed with zstd.
+//!
+//! ```rust
+//! use autometrics::{autometrics, snapshot::{self, Snapshot}};
+//!
+//! #[autometrics]
+//! fn handle_job() {}
+//!
+//! // In the worker
+//! handle_job();
+//! let bytes = snapshot::take().to_bytes()?;
+//!
+//! // In the process that exports the metrics
+//! snapshot::merge(&Snapshot::from_bytes(&bytes)?);
+//! # Ok::<(), autometrics::snapshot::SnapshotError>(())
+//! ```
+//!
+//! The calls that a worker inherits from its parent when it is forked are included in its first snapshot,
+//! so the worker should call [`take`] right after it is forked and drop the result.
+//!
+//! # Versioning
+//!
+//! The encoded snapshots start with the [`FORMAT_VERSION`], and [`Snapshot::from_bytes`] rejects the snapshots
+//! that were encoded with a different version. The processes that exchange snapshots do not need to use the
+//! same version of autometrics, as long as they use the same format version.
+//!
+//! # Limitations
+//!
+//! - The latencies are merged with the precision of the histogram buckets of the process that recorded them:
+//!   the calls in each bucket are merged as calls that took the average latency of that bucket.
+//! - The calls are merged with the settings of the process that merges them (such as the service name),
+//!   and the concurrent calls, response sizes, and exemplars of the other process are not included.
+//! - Merging the calls of functions with the `count_only` argument takes time proportional to the
+//!   number of calls, because the backends only count them one at a time.
+//!
+//! This requires the `snapshots` feature.
+
+use crate::constants::{ERROR_KEY, OK_KEY};
+use crate::labels::{intern, CounterLabels, HistogramLabels, ResultLabel};
+use crate::objectives::{Objective, ObjectiveLatency, ObjectivePercentile};
+use crate::poison;
+use crate::settings::get_settings;
+use crate::tracker::AutometricsTracker;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use thiserror::Error;
+
+/// The version of the binary format of the snapshots.
+///
+/// This is incremented whenever the encoded data changes.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// The bytes that every encoded snapshot starts with, followed by the format version
+const MAGIC: &[u8; 4] = b"AMSS";
+
+/// The calls recorded since the last snapshot was taken
+static RECORDED: Lazy<Mutex<HashMap<CallLabels, Calls>>> = Lazy::new(Default::default);
+
+#[derive(Debug, Error)]
+pub enum SnapshotError {
+    #[error("The data is not an autometrics snapshot")]
+    NotASnapshot,
+    #[error("The snapshot uses version {version} of the format, but this library only supports version {supported}")]
+    UnsupportedVersion { version: u32, supported: u32 },
+    #[error("Failed to compress or decompress the snapshot")]
+    Compression(#[from] std::io::Error),
+    #[error("Failed to encode or decode the snapshot")]
+    Encoding(#[from] bincode::Error),
+}
+
+/// The calls to instrumented functions that were recorded in a process, aggregated by their labels.
+///
+/// Take one with [`take`] and merge it into the metrics of another process with [`merge`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    series: Vec<Series>,
+}
+
+impl Snapshot {
+    /// Whether the snapshot does not contain any calls.
+    pub fn is_empty(&self) -> bool {
+        self.series.is_empty()
+    }
+
+    /// The number of calls in the snapshot, counting sampled calls as multiple calls.
+    pub fn calls(&self) -> u64 {
+        self.series.iter().map(|series| series.calls.calls).sum()
+    }
+
+    /// Encode the snapshot in the compressed binary format.
+    pub fn to_bytes(&self) -> Result<Vec<u8>, SnapshotError> {
+        let data = bincode::serialize(self)?;
+        let mut bytes = Vec::with_capacity(MAGIC.len() + 4 + data.len() / 2);
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+        zstd::stream::copy_encode(data.as_slice(), &mut bytes, zstd::DEFAULT_COMPRESSION_LEVEL)?;
+        Ok(bytes)
+    }
+
+    /// Decode a snapshot that was encoded with [`to_bytes`](Self::to_bytes).
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let bytes = bytes
+            .strip_prefix(MAGIC.as_slice())
+            .ok_or(SnapshotError::NotASnapshot)?;
+        if bytes.len() < 4 {
+            return Err(SnapshotError::NotASnapshot);
+        }
+        let (version, data) = bytes.split_at(4);
+        let version = u32::from_le_bytes(version.try_into().expect("the version has 4 bytes"));
+        if version != FORMAT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion {
+                version,
+                supported: FORMAT_VERSION,
+            });
+        }
+
+        let data = zstd::stream::decode_all(data)?;
+        Ok(bincode::deserialize(&data)?)
+    }
+}
+
+/// Take the calls that were recorded in this process since the last snapshot was taken.
+pub fn take() -> Snapshot {
+    let recorded = std::mem::take(&mut *poison::lock(&RECORDED, "snapshot"));
+    let series = recorded
+        .into_iter()
+        .map(|(labels, mut calls)| {
+            calls.latencies.retain(|bucket| bucket.count > 0);
+            Series { labels, calls }
+        })
+        .collect();
+    Snapshot { series }
+}
+
+/// Record the calls in the snapshot in the metrics of this process, so that they are exported with its own calls.
+///
+/// The calls are recorded in the metrics backend only: they are not included in the snapshots
+/// taken in this process, and they are not emitted as wide events or span events.
+pub fn merge(snapshot: &Snapshot) {
+    for series in &snapshot.series {
+        let (counter_labels, histogram_labels) = series.labels.to_labels();
+        let Calls { calls, latencies } = &series.calls;
+
+        // The calls that were not timed (because they were sampled or only counted)
+        // are added to the counter with the first timed call
+        let timed_calls: u64 = latencies.iter().map(|bucket| bucket.count).sum();
+        let mut extra_calls = calls.saturating_sub(timed_calls);
+        for bucket in latencies.iter().filter(|bucket| bucket.count > 0) {
+            let elapsed = Duration::from_secs_f64(bucket.sum_seconds / bucket.count as f64);
+            AutometricsTracker::record_replayed(
+                &counter_labels,
+                &histogram_labels,
+                1 + std::mem::take(&mut extra_calls),
+                elapsed,
+            );
+            for _ in 1..bucket.count {
+                AutometricsTracker::record_replayed(&counter_labels, &histogram_labels, 1, elapsed);
+            }
+        }
+        AutometricsTracker::count_replayed(&counter_labels, extra_calls);
+    }
+}
+
+/// Record a finished call, with its labels and latency unless it was only counted
+pub(crate) fn record(
+    counter_labels: &CounterLabels,
+    timed: Option<(&HistogramLabels, Duration)>,
+    weight: u64,
+) {
+    let settings = get_settings();
+    let labels = CallLabels::new(counter_labels, timed.map(|(labels, _)| labels));
+    let mut recorded = poison::lock(&RECORDED, "snapshot");
+    let calls = recorded.entry(labels).or_default();
+    calls.calls += weight;
+
+    if let Some((histogram_labels, elapsed)) = timed {
+        // Without an exporter, the latencies are grouped by the default buckets
+        #[cfg(any(prometheus_exporter, prometheus, prometheus_client))]
+        let default_buckets = settings.histogram_buckets.as_slice();
+        #[cfg(not(any(prometheus_exporter, prometheus, prometheus_client)))]
+        let default_buckets = settings.duration_unit.default_histogram_buckets();
+        let buckets = histogram_labels.buckets.0.unwrap_or(default_buckets);
+        let value = settings.duration_unit.convert(elapsed);
+        let index = buckets
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(buckets.len());
+        if calls.latencies.len() <= index {
+            calls
+                .latencies
+                .resize(buckets.len() + 1, LatencyBucket::default());
+        }
+        calls.latencies[index].count += 1;
+        calls.latencies[index].sum_seconds += elapsed.as_secs_f64();
+    }
+}
+
+/// The calls with the same labels
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Series {
+    labels: CallLabels,
+    calls: Calls,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+struct Calls {
+    /// The number of calls, counting sampled calls as multiple calls
+    calls: u64,
+    /// The latencies of the calls that were timed, grouped by histogram bucket
+    latencies: Vec<LatencyBucket>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+struct LatencyBucket {
+    count: u64,
+    sum_seconds: f64,
+}
+
+/// The labels of a call. The values are borrowed while the calls are recorded,
+/// and owned once they are decoded from a snapshot.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+struct CallLabels {
+    function: Cow<'static, str>,
+    module: Cow<'static, str>,
+    caller_function: Cow<'static, str>,
+    caller_module: Cow<'static, str>,
+    result: Option<Cow<'static, str>>,
+    return_value_type: Option<Cow<'static, str>>,
+    counter_objective: Option<ObjectiveLabels>,
+    histogram_objective: Option<ObjectiveLabels>,
+    recursive: bool,
+    error_type: Option<Cow<'static, str>>,
+    type_params: Option<Cow<'static, str>>,
+    panic: bool,
+    cold: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+struct ObjectiveLabels {
+    name: Cow<'static, str>,
+    percentile: Cow<'static, str>,
+    latency_threshold: Option<Cow<'static, str>>,
+}
+
+impl ObjectiveLabels {
+    fn new(
+        name: Option<&'static str>,
+        percentile: Option<ObjectivePercentile>,
+        latency_threshold: Option<ObjectiveLatency>,
+    ) -> Option<Self> {
+        Some(Self {
+            name: Cow::Borrowed(name?),
+            percentile: Cow::Borrowed(percentile?.as_str()),
+            latency_threshold: latency_threshold.map(|latency| Cow::Borrowed(latency.as_str())),
+        })
+    }
+
+    fn objective(&self) -> Option<Objective> {
+        Objective::from_labels(
+            Some(intern(&self.name)),
+            Some(intern(&self.percentile)),
+            self.latency_threshold.as_deref().map(intern),
+        )
+    }
+}
+
+impl CallLabels {
+    fn new(counter_labels: &CounterLabels, histogram_labels: Option<&HistogramLabels>) -> Self {
+        let borrowed = |value: Option<&'static str>| value.map(Cow::Borrowed);
+        Self {
+            function: Cow::Borrowed(counter_labels.function),
+            module: Cow::Borrowed(counter_labels.module),
+            caller_function: Cow::Borrowed(counter_labels.caller_function),
+            caller_module: Cow::Borrowed(counter_labels.caller_module),
+            result: borrowed(counter_labels.result.as_ref().map(ResultLabel::as_str)),
+            return_value_type: borrowed(counter_labels.ok.or(counter_labels.error)),
+            counter_objective: ObjectiveLabels::new(
+                counter_labels.objective_name,
+                counter_labels.objective_percentile,
+                None,
+            ),
+            histogram_objective: histogram_labels.and_then(|labels| {
+                ObjectiveLabels::new(
+                    labels.objective_name,
+                    labels.objective_percentile,
+                    labels.objective_latency_threshold,
+                )
+            }),
+            recursive: counter_labels.recursive.is_some(),
+            error_type: borrowed(counter_labels.error_type),
+            type_params: borrowed(counter_labels.type_params),
+            panic: counter_labels.panic.is_some(),
+            cold: histogram_labels.is_some_and(|labels| labels.cold.is_some()),
+        }
+    }
+
+    fn to_labels(&self) -> (CounterLabels, HistogramLabels) {
+        let settings = get_settings();
+        let function = intern(&self.function);
+        let module = intern(&self.module);
+        let result = match self.result.as_deref() {
+            Some(OK_KEY) => Some(OK_KEY),
+            Some(ERROR_KEY) => Some(ERROR_KEY),
+            _ => None,
+        };
+
+        let mut counter_labels = CounterLabels::new(
+            settings,
+            function,
+            module,
+            intern(&self.caller_function),
+            intern(&self.caller_module),
+            result.map(|result| (result, self.return_value_type.as_deref().map(intern))),
+            self.counter_objective
+                .as_ref()
+                .and_then(ObjectiveLabels::objective),
+        )
+        .with_recursive(self.recursive)
+        .with_error_type(self.error_type.as_deref().map(intern));
+        let mut histogram_labels = HistogramLabels::new(
+            settings,
+            function,
+            module,
+            self.histogram_objective
+                .as_ref()
+                .and_then(ObjectiveLabels::objective),
+        );
+        if let Some(type_params) = self.type_params.as_deref().map(intern) {
+            counter_labels = counter_labels.with_type_params(type_params);
+            histogram_labels = histogram_labels.with_type_params(type_params);
+        }
+        if self.panic {
+            counter_labels = counter_labels.with_panic();
+        }
+        if self.cold {
+            histogram_labels = histogram_labels.cold();
+        }
+
+        (counter_labels, histogram_labels)
+    }
+}
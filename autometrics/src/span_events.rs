@@ -0,0 +1,80 @@
+//! Attach events to the current span when an instrumented call is slow or fails.
+//!
+//! Functions instrumented with the `span_events` argument emit a [`tracing`](https://crates.io/crates/tracing)
+//! event inside the span that is current when the call finishes:
+//!
+//! - `autometrics.slow_call` if the call took longer than the latency threshold of the function's
+//!   [objective](crate::objectives), with the `duration_seconds` and `threshold_seconds` fields
+//! - `autometrics.failed_call` if the call returned an error (or panicked), with the `error_type`
+//!   field if the function uses the `error_details` argument
+//!
+//! Both events also have the `function` and `module` fields, and use the target `autometrics::span_events`.
+//! With [`tracing-opentelemetry`](https://crates.io/crates/tracing-opentelemetry), they are exported
+//! as events of the OpenTelemetry span, so slow and failed calls stand out when looking at a trace.
+//!
+//! ```rust
+//! use autometrics::{autometrics, objectives::*};
+//!
+//! const API_SLO: Objective = Objective::new("api")
+//!     .latency(ObjectiveLatency::Ms250, ObjectivePercentile::P99);
+//!
+//! #[autometrics(objective = API_SLO, span_events)]
+//! fn get_user() -> Result<(), std::io::Error> {
+//!     Ok(())
+//! }
+//! ```
+//!
+//! This requires the `span-events` feature. Without it, the `span_events` argument has no effect.
+
+use crate::labels::{CounterLabels, HistogramLabels, ResultLabel};
+use crate::settings::{get_settings, DurationUnit};
+use std::time::Duration;
+use tracing::Level;
+
+/// Emit the events for a call that finished, if it was slow or failed
+pub(crate) fn emit(
+    counter_labels: &CounterLabels,
+    histogram_labels: &HistogramLabels,
+    duration: Duration,
+) {
+    if let Some(threshold) = latency_threshold_seconds(histogram_labels) {
+        if duration.as_secs_f64() > threshold {
+            tracing::event!(
+                name: "autometrics.slow_call",
+                target: "autometrics::span_events",
+                Level::WARN,
+                function = counter_labels.function,
+                module = counter_labels.module,
+                duration_seconds = duration.as_secs_f64(),
+                threshold_seconds = threshold,
+                "autometrics.slow_call"
+            );
+        }
+    }
+
+    if matches!(counter_labels.result, Some(ResultLabel::Error)) || counter_labels.panic.is_some() {
+        tracing::event!(
+            name: "autometrics.failed_call",
+            target: "autometrics::span_events",
+            Level::WARN,
+            function = counter_labels.function,
+            module = counter_labels.module,
+            error_type = counter_labels.error_type,
+            "autometrics.failed_call"
+        );
+    }
+}
+
+/// The latency threshold of the function's objective, converted from the configured duration unit to seconds
+fn latency_threshold_seconds(histogram_labels: &HistogramLabels) -> Option<f64> {
+    let threshold: f64 = histogram_labels
+        .objective_latency_threshold
+        .as_ref()?
+        .label_value()
+        .parse()
+        .ok()?;
+    Some(match get_settings().duration_unit {
+        DurationUnit::Seconds => threshold,
+        DurationUnit::Milliseconds => threshold / 1000.0,
+    })
+}
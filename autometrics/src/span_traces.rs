@@ -0,0 +1,140 @@
+//! Capture the [`SpanTrace`] of the calls to instrumented functions that return errors.
+//!
+//! The metrics tell which function is failing or violating its objective, but not why. When a function
+//! returns an error (or panics), Autometrics can capture the [`tracing`](https://crates.io/crates/tracing)
+//! spans that were active during the call with [`tracing-error`](https://crates.io/crates/tracing-error),
+//! and pass them to a [`SpanTraceSink`]. This ties the failing function to the request or job it was handling,
+//! without sending every trace to a tracing backend.
+//!
+//! [`LatestSpanTraces`] keeps the span trace of the most recent error of each function, which can be shown
+//! on a debug page, for example. You can also implement [`SpanTraceSink`] (or pass a closure) to log the span traces
+//! or send them elsewhere.
+//!
+//! # Example
+//! ```rust
+//! use autometrics::autometrics;
+//! use autometrics::settings::AutometricsSettings;
+//! use autometrics::span_traces::LatestSpanTraces;
+//! use std::sync::Arc;
+//! use tracing_error::ErrorLayer;
+//! use tracing_subscriber::prelude::*;
+//!
+//! #[autometrics]
+//! #[tracing::instrument]
+//! fn load_order(id: u32) -> Result<(), &'static str> {
+//!     Err("not found")
+//! }
+//!
+//! // The span traces are only captured if the subscriber has the `ErrorLayer`
+//! tracing_subscriber::registry().with(ErrorLayer::default()).init();
+//!
+//! let span_traces = Arc::new(LatestSpanTraces::default());
+//! AutometricsSettings::builder()
+//!     .span_traces(span_traces.clone())
+//!     .init();
+//!
+//! load_order(7).ok();
+//!
+//! let error = span_traces.get("load_order").unwrap();
+//! assert!(error.span_trace.to_string().contains("load_order"));
+//! ```
+//!
+//! This requires the `span-traces` feature.
+
+use crate::labels::{CounterLabels, ResultLabel};
+use crate::poison;
+use crate::settings::get_settings;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+pub use tracing_error::SpanTrace;
+
+/// The spans that were active when a call to an instrumented function returned an error.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct ErrorSpanTrace {
+    pub function: &'static str,
+    pub module: &'static str,
+    /// The error variant or type, if the function uses the `error_details` argument
+    pub error_type: Option<&'static str>,
+    /// Whether the function panicked rather than returning an error
+    pub panic: bool,
+    pub span_trace: SpanTrace,
+}
+
+/// Receives the span traces of the calls that returned errors.
+///
+/// This is called synchronously when each call finishes, so it should not block.
+pub trait SpanTraceSink: Send + Sync {
+    fn record(&self, error: ErrorSpanTrace);
+}
+
+impl<F> SpanTraceSink for F
+where
+    F: Fn(ErrorSpanTrace) + Send + Sync,
+{
+    fn record(&self, error: ErrorSpanTrace) {
+        self(error)
+    }
+}
+
+impl<S: SpanTraceSink + ?Sized> SpanTraceSink for Arc<S> {
+    fn record(&self, error: ErrorSpanTrace) {
+        (**self).record(error)
+    }
+}
+
+/// Keeps the span trace of the most recent error of each function, by function name.
+///
+/// Share it with the settings in an [`Arc`] to look up the span traces later.
+#[derive(Debug, Default)]
+pub struct LatestSpanTraces {
+    errors: RwLock<HashMap<&'static str, ErrorSpanTrace>>,
+}
+
+impl LatestSpanTraces {
+    /// The span trace of the most recent error of the function with the given name, if it returned any.
+    pub fn get(&self, function: &str) -> Option<ErrorSpanTrace> {
+        poison::read(&self.errors, "span_traces")
+            .get(function)
+            .cloned()
+    }
+
+    /// The span traces of the most recent error of every function that returned any, sorted by function name.
+    pub fn all(&self) -> Vec<ErrorSpanTrace> {
+        let mut errors: Vec<ErrorSpanTrace> = poison::read(&self.errors, "span_traces")
+            .values()
+            .cloned()
+            .collect();
+        errors.sort_by_key(|error| (error.function, error.module));
+        errors
+    }
+
+    /// Forget the span traces of all functions.
+    pub fn clear(&self) {
+        poison::write(&self.errors, "span_traces").clear();
+    }
+}
+
+impl SpanTraceSink for LatestSpanTraces {
+    fn record(&self, error: ErrorSpanTrace) {
+        poison::write(&self.errors, "span_traces").insert(error.function, error);
+    }
+}
+
+/// Capture the span trace of a finished call if it returned an error and a sink is configured
+pub(crate) fn capture(counter_labels: &CounterLabels) {
+    if !matches!(counter_labels.result, Some(ResultLabel::Error)) {
+        return;
+    }
+    let Some(sink) = &get_settings().span_traces else {
+        return;
+    };
+
+    sink.0.record(ErrorSpanTrace {
+        function: counter_labels.function,
+        module: counter_labels.module,
+        error_type: counter_labels.error_type,
+        panic: counter_labels.panic.is_some(),
+        span_trace: SpanTrace::capture(),
+    });
+}
@@ -0,0 +1,301 @@
+//! Subscribe to the calls of instrumented functions as an async stream of deltas, for real-time consumers.
+//!
+//! Exporting the metrics to Prometheus works well for dashboards and alerts, but real-time UIs and
+//! adaptive control loops (such as load shedders or autoscalers) need the latest numbers without polling
+//! the `/metrics` endpoint and diffing the counters. While there are subscribers, Autometrics aggregates
+//! the calls of each function over a short window, and sends the [`Deltas`] to every subscriber at the end
+//! of each window: the number of calls and errors, and a digest of the latencies.
+//!
+//! ```rust
+//! use autometrics::{autometrics, stream};
+//! use futures_core::Stream;
+//! use std::future::poll_fn;
+//! use std::pin::pin;
+//!
+//! #[autometrics]
+//! fn handler() -> Result<(), ()> {
+//!     Ok(())
+//! }
+//!
+//! # #[tokio::main(flavor = "current_thread", start_paused = true)]
+//! # async fn main() {
+//! let mut deltas = pin!(stream::subscribe());
+//! handler().ok();
+//!
+//! let deltas = poll_fn(|cx| deltas.as_mut().poll_next(cx)).await.unwrap();
+//! let handler = deltas.function("handler", module_path!()).unwrap();
+//! assert_eq!(handler.calls, 1);
+//! assert_eq!(handler.errors, 0);
+//! # }
+//! ```
+//!
+//! The length of the windows is set with
+//! [`AutometricsSettingsBuilder::stream_interval`](crate::settings::AutometricsSettingsBuilder::stream_interval)
+//! and defaults to one second. The calls are only aggregated while there are subscribers, and the calls that
+//! happen before the first subscriber subscribes are not included.
+//!
+//! This requires the `stream` feature, and [`subscribe`] must be called within a Tokio runtime with the time driver enabled.
+
+use crate::labels::{CounterLabels, HistogramLabels, ResultLabel};
+use crate::poison;
+use crate::settings::get_settings;
+use futures_core::Stream;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// The number of deltas that are buffered for a subscriber that does not keep up.
+///
+/// The deltas of later windows are dropped for that subscriber until it catches up.
+const SUBSCRIBER_BUFFER: usize = 64;
+
+/// Whether there are subscribers, so that the calls are only aggregated if they are consumed
+static ACTIVE: AtomicBool = AtomicBool::new(false);
+
+static STATE: Lazy<Mutex<State>> = Lazy::new(Default::default);
+
+#[derive(Default)]
+struct State {
+    /// The calls of each function (by name and module) in the current window
+    window: HashMap<(&'static str, &'static str), FunctionWindow>,
+    subscribers: Vec<mpsc::Sender<Deltas>>,
+    /// Whether the task that sends the deltas at the end of each window is running
+    flushing: bool,
+}
+
+struct FunctionWindow {
+    calls: u64,
+    errors: u64,
+    latency: LatencyDigest,
+}
+
+/// The calls of all of the instrumented functions during one window.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct Deltas {
+    /// The length of the window
+    pub interval: Duration,
+    /// The functions that were called during the window, sorted by module and name
+    pub functions: Vec<FunctionDelta>,
+}
+
+impl Deltas {
+    /// The calls of the function with the given name and module, if it was called during the window.
+    pub fn function(&self, function: &str, module: &str) -> Option<&FunctionDelta> {
+        self.functions
+            .iter()
+            .find(|delta| delta.function == function && delta.module == module)
+    }
+}
+
+/// The calls of a single function during one window.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct FunctionDelta {
+    pub function: &'static str,
+    pub module: &'static str,
+    /// The number of calls, counting sampled calls as multiple calls
+    pub calls: u64,
+    /// The number of calls that returned an error (or panicked), counting sampled calls as multiple calls
+    pub errors: u64,
+    /// The latencies of the calls that were timed
+    pub latency: LatencyDigest,
+}
+
+/// The latencies of the calls to a function during one window, grouped by the histogram buckets of the function.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct LatencyDigest {
+    /// The number of calls that were timed
+    pub count: u64,
+    pub sum: Duration,
+    pub min: Option<Duration>,
+    pub max: Option<Duration>,
+    /// The upper bounds of the buckets, in the [`DurationUnit`](crate::settings::DurationUnit) from the settings
+    pub bucket_bounds: &'static [f64],
+    /// The number of calls in each bucket (not cumulative), followed by the number of calls above the last bound
+    pub bucket_counts: Vec<u64>,
+}
+
+impl LatencyDigest {
+    fn new(bucket_bounds: &'static [f64]) -> Self {
+        Self {
+            count: 0,
+            sum: Duration::ZERO,
+            min: None,
+            max: None,
+            bucket_bounds,
+            bucket_counts: vec![0; bucket_bounds.len() + 1],
+        }
+    }
+
+    fn record(&mut self, elapsed: Duration) {
+        let value = get_settings().duration_unit.convert(elapsed);
+        let index = self
+            .bucket_bounds
+            .iter()
+            .position(|bound| value <= *bound)
+            .unwrap_or(self.bucket_bounds.len());
+        self.bucket_counts[index] += 1;
+        self.count += 1;
+        self.sum += elapsed;
+        self.min = Some(self.min.map_or(elapsed, |min| min.min(elapsed)));
+        self.max = Some(self.max.map_or(elapsed, |max| max.max(elapsed)));
+    }
+
+    /// The average latency, if any calls were timed.
+    pub fn mean(&self) -> Option<Duration> {
+        let count = u32::try_from(self.count).ok().filter(|count| *count > 0)?;
+        Some(self.sum / count)
+    }
+
+    /// Estimate the given quantile (between 0 and 1) of the latencies, if any calls were timed.
+    ///
+    /// Like Prometheus' `histogram_quantile`, this assumes that the latencies are evenly distributed
+    /// within each bucket. The estimate is kept between the minimum and maximum latency.
+    pub fn quantile(&self, quantile: f64) -> Option<Duration> {
+        let (min, max) = (self.min?, self.max?);
+        let unit = get_settings().duration_unit;
+        let rank = quantile.clamp(0.0, 1.0) * self.count as f64;
+
+        let mut below = 0;
+        for (index, count) in self.bucket_counts.iter().enumerate() {
+            if *count == 0 || ((below + count) as f64) < rank {
+                below += count;
+                continue;
+            }
+            // The calls above the last bound are only known to be at most as slow as the slowest one
+            let Some(upper) = self.bucket_bounds.get(index) else {
+                return Some(max);
+            };
+            let lower = index
+                .checked_sub(1)
+                .map_or(0.0, |index| self.bucket_bounds[index]);
+            let fraction = (rank - below as f64) / *count as f64;
+            let value = unit.to_duration(lower + (upper - lower) * fraction);
+            return Some(value.clamp(min, max));
+        }
+        Some(max)
+    }
+}
+
+/// Subscribe to the deltas of the calls to all instrumented functions, which are sent at the end of each window.
+///
+/// The stream ends if the task that sends the deltas stops, for example because the Tokio runtime shut down.
+///
+/// # Panics
+///
+/// This panics if it is not called within a Tokio runtime.
+pub fn subscribe() -> DeltaStream {
+    let (sender, receiver) = mpsc::channel(SUBSCRIBER_BUFFER);
+
+    let mut state = poison::lock(&STATE, "stream");
+    state.subscribers.push(sender);
+    ACTIVE.store(true, Ordering::Relaxed);
+    if !state.flushing {
+        state.flushing = true;
+        tokio::spawn(flush_windows(get_settings().stream_interval));
+    }
+
+    DeltaStream { receiver }
+}
+
+/// The deltas returned by [`subscribe`].
+#[derive(Debug)]
+pub struct DeltaStream {
+    receiver: mpsc::Receiver<Deltas>,
+}
+
+impl Stream for DeltaStream {
+    type Item = Deltas;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Deltas>> {
+        self.receiver.poll_recv(cx)
+    }
+}
+
+/// Send the calls in the window to the subscribers at the end of each window,
+/// until all of the subscribers are gone
+async fn flush_windows(interval: Duration) {
+    let mut ticks = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+    ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+    loop {
+        ticks.tick().await;
+
+        let mut state = poison::lock(&STATE, "stream");
+        let mut functions: Vec<FunctionDelta> = state
+            .window
+            .drain()
+            .map(|((function, module), window)| FunctionDelta {
+                function,
+                module,
+                calls: window.calls,
+                errors: window.errors,
+                latency: window.latency,
+            })
+            .collect();
+        functions.sort_by_key(|delta| (delta.module, delta.function));
+        let deltas = Deltas {
+            interval,
+            functions,
+        };
+
+        // The subscribers that are full miss this window, and the ones that were dropped are removed
+        state.subscribers.retain(|subscriber| {
+            !matches!(
+                subscriber.try_send(deltas.clone()),
+                Err(mpsc::error::TrySendError::Closed(_))
+            )
+        });
+        if state.subscribers.is_empty() {
+            ACTIVE.store(false, Ordering::Relaxed);
+            state.flushing = false;
+            return;
+        }
+    }
+}
+
+/// Add a finished call to the current window, with its latency unless it was only counted
+pub(crate) fn record(
+    counter_labels: &CounterLabels,
+    timed: Option<(&HistogramLabels, Duration)>,
+    weight: u64,
+) {
+    if !ACTIVE.load(Ordering::Relaxed) {
+        return;
+    }
+
+    let mut state = poison::lock(&STATE, "stream");
+    let window = state
+        .window
+        .entry((counter_labels.function, counter_labels.module))
+        .or_insert_with(|| {
+            let settings = get_settings();
+            #[cfg(any(prometheus_exporter, prometheus, prometheus_client))]
+            let default_buckets = settings.histogram_buckets.as_slice();
+            #[cfg(not(any(prometheus_exporter, prometheus, prometheus_client)))]
+            let default_buckets = settings.duration_unit.default_histogram_buckets();
+            let buckets = timed
+                .and_then(|(histogram_labels, _)| histogram_labels.buckets.0)
+                .unwrap_or(default_buckets);
+            FunctionWindow {
+                calls: 0,
+                errors: 0,
+                latency: LatencyDigest::new(buckets),
+            }
+        });
+
+    window.calls += weight;
+    if matches!(counter_labels.result, Some(ResultLabel::Error)) {
+        window.errors += weight;
+    }
+    if let Some((_, elapsed)) = timed {
+        window.latency.record(elapsed);
+    }
+}
@@ -0,0 +1,164 @@
+//! Keep in-process summaries of the function latencies for accurate quantiles.
+//!
+//! The latency histograms that are exported to Prometheus can only estimate quantiles within the
+//! precision of their buckets. When one of the following features is enabled, Autometrics also records
+//! the duration of every call to an instrumented function in a summary that is kept in memory,
+//! so the quantiles can be computed accurately at runtime:
+//!
+//! - `summaries-metrics-util` - a [`metrics_util::storage::Summary`], which is the DDSketch-based summary
+//!   used by the `metrics` ecosystem. The values are recorded in seconds.
+//! - `summaries-hdrhistogram` - an [`hdrhistogram::Histogram`], which is the representation used by the
+//!   [`tracing-timing`](https://crates.io/crates/tracing-timing) crate. The values are recorded in nanoseconds,
+//!   like in `tracing-timing`, so the histograms can be merged using [`Histogram::add`](hdrhistogram::Histogram::add).
+//!
+//! The summaries are kept per function, independently of the other labels, and are not affected by the
+//! histogram buckets configured in the [`settings`](crate::settings) or the `autometrics` macro.
+//!
+//! # Example
+//! ```rust
+//! use autometrics::{autometrics, summaries};
+//!
+//! #[autometrics]
+//! pub fn handler() {
+//!    // ...
+//! }
+//!
+//! handler();
+//!
+//! let p99 = summaries::quantile("handler", module_path!(), 0.99);
+//! assert!(p99.is_some());
+//! ```
+
+use crate::labels::HistogramLabels;
+use crate::poison;
+#[cfg(summaries_hdrhistogram)]
+use hdrhistogram::Histogram;
+#[cfg(summaries_metrics_util)]
+use metrics_util::storage::Summary;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use std::time::Duration;
+
+/// The function and module names
+type FunctionKey = (&'static str, &'static str);
+
+/// The summaries of each function
+static SUMMARIES: Lazy<RwLock<HashMap<FunctionKey, Mutex<FunctionSummaries>>>> =
+    Lazy::new(Default::default);
+
+struct FunctionSummaries {
+    #[cfg(summaries_metrics_util)]
+    summary: Summary,
+    #[cfg(summaries_hdrhistogram)]
+    hdr_histogram: Histogram<u64>,
+}
+
+impl FunctionSummaries {
+    fn new() -> Self {
+        Self {
+            #[cfg(summaries_metrics_util)]
+            summary: Summary::with_defaults(),
+            // Three significant figures is the same precision that `tracing-timing` uses by default
+            #[cfg(summaries_hdrhistogram)]
+            hdr_histogram: Histogram::new(3).expect("Invalid HDR histogram precision"),
+        }
+    }
+
+    fn record(&mut self, duration: Duration) {
+        #[cfg(summaries_metrics_util)]
+        self.summary.add(duration.as_secs_f64());
+        #[cfg(summaries_hdrhistogram)]
+        {
+            let nanos = u64::try_from(duration.as_nanos()).unwrap_or(u64::MAX);
+            // The histogram resizes automatically, so this only fails for values that are too large to be represented
+            if self.hdr_histogram.record(nanos).is_err() {
+                self.hdr_histogram.saturating_record(nanos);
+            }
+        }
+    }
+
+    fn quantile(&self, quantile: f64) -> Option<f64> {
+        #[cfg(summaries_metrics_util)]
+        let value = self.summary.quantile(quantile);
+        #[cfg(not(summaries_metrics_util))]
+        let value = (!self.hdr_histogram.is_empty())
+            .then(|| self.hdr_histogram.value_at_quantile(quantile) as f64 / 1e9);
+        value
+    }
+}
+
+/// Record the duration of a call to the function with the given labels
+pub(crate) fn record(histogram_labels: &HistogramLabels, duration: Duration) {
+    let key = (histogram_labels.function, histogram_labels.module);
+
+    let summaries = poison::read(&SUMMARIES, "summaries");
+    if let Some(function_summaries) = summaries.get(&key) {
+        poison::lock(function_summaries, "summaries").record(duration);
+        return;
+    }
+    drop(summaries);
+
+    poison::write(&SUMMARIES, "summaries")
+        .entry(key)
+        .or_insert_with(|| Mutex::new(FunctionSummaries::new()))
+        .get_mut()
+        .unwrap_or_else(|err| err.into_inner())
+        .record(duration);
+}
+
+fn with_function_summaries<T>(
+    function: &str,
+    module: &str,
+    f: impl FnOnce(&FunctionSummaries) -> T,
+) -> Option<T> {
+    let summaries = poison::read(&SUMMARIES, "summaries");
+    let function_summaries = summaries.get(&(function, module))?;
+    let function_summaries = poison::lock(function_summaries, "summaries");
+    Some(f(&function_summaries))
+}
+
+/// Get the given quantile (between 0 and 1) of the latency of the function, in seconds.
+///
+/// Returns `None` if the function has not been called yet.
+/// This uses the [`Summary`](metrics_util::storage::Summary) if the `summaries-metrics-util` feature is enabled
+/// and the [`Histogram`](hdrhistogram::Histogram) otherwise.
+pub fn quantile(function: &str, module: &str, quantile: f64) -> Option<f64> {
+    with_function_summaries(function, module, |summaries| summaries.quantile(quantile)).flatten()
+}
+
+/// Get a copy of the [`Summary`] of the latency of the function, in seconds.
+///
+/// Returns `None` if the function has not been called yet.
+#[cfg(summaries_metrics_util)]
+pub fn summary(function: &str, module: &str) -> Option<Summary> {
+    with_function_summaries(function, module, |summaries| summaries.summary.clone())
+}
+
+/// Get a copy of the [`Histogram`] of the latency of the function, in nanoseconds.
+///
+/// Returns `None` if the function has not been called yet.
+#[cfg(summaries_hdrhistogram)]
+pub fn hdr_histogram(function: &str, module: &str) -> Option<Histogram<u64>> {
+    with_function_summaries(function, module, |summaries| {
+        summaries.hdr_histogram.clone()
+    })
+}
+
+/// List the functions and modules that have summaries.
+pub fn functions() -> Vec<(&'static str, &'static str)> {
+    poison::read(&SUMMARIES, "summaries")
+        .keys()
+        .copied()
+        .collect()
+}
+
+/// Clear all of the summaries.
+pub fn reset() {
+    poison::write(&SUMMARIES, "summaries").clear();
+}
+
+/// Remove the summaries of the function with the given name (in any module)
+pub(crate) fn remove_function(function: &str) {
+    poison::write(&SUMMARIES, "summaries").retain(|(name, _), _| *name != function);
+}
@@ -0,0 +1,339 @@
+//! Helpers for testing that functions are instrumented the way you expect.
+//!
+//! The [`autometrics_test`](crate::autometrics_test) attribute turns a function into a test that
+//! initializes the [`prometheus_exporter`](crate::prometheus_exporter) and passes a [`TestMetrics`] to it.
+//! The helpers of [`TestMetrics`] only count what was recorded after the test started,
+//! so the tests do not see the calls made by earlier tests.
+//!
+//! # Example
+//! ```rust
+//! use autometrics::{autometrics, autometrics_test, testing::TestMetrics};
+//!
+//! #[autometrics]
+//! fn get_user(id: u64) -> Result<String, String> {
+//!     if id == 0 {
+//!         Err("not found".to_string())
+//!     } else {
+//!         Ok("Alice".to_string())
+//!     }
+//! }
+//!
+//! #[autometrics_test]
+//! fn counts_errors(metrics: TestMetrics) {
+//!     get_user(1).ok();
+//!     get_user(0).ok();
+//!
+//!     assert_eq!(metrics.calls("get_user"), 2);
+//!     assert_eq!(metrics.errors("get_user"), 1);
+//! }
+//! ```
+//!
+//! Async tests are run with the attribute of your runtime (for example, `#[tokio::test]`) if there is one,
+//! and otherwise with a minimal executor that polls the test on the current thread:
+//! ```rust,ignore
+//! #[autometrics_test]
+//! #[tokio::test]
+//! async fn counts_async_calls(metrics: TestMetrics) {
+//!     fetch_user(1).await;
+//!     assert_eq!(metrics.calls("fetch_user"), 1);
+//! }
+//! ```
+//!
+//! The [`assert_counter!`](crate::assert_counter) macro checks the increase of the function call counter
+//! for any combination of labels, and shows the matching series if the assertion fails:
+//! ```rust
+//! # use autometrics::{autometrics, assert_counter, testing::TestMetrics};
+//! # #[autometrics]
+//! # fn get_user(id: u64) -> Result<(), ()> { if id == 0 { Err(()) } else { Ok(()) } }
+//! let mut metrics = TestMetrics::start();
+//! get_user(0).ok();
+//! get_user(0).ok();
+//! assert_counter!(metrics, function = "get_user", result = "error", value = 2);
+//!
+//! // Only count the calls made after this point
+//! metrics.reset();
+//! assert_counter!(metrics, function = "get_user", value = 0);
+//! ```
+//!
+//! The metrics are global, so tests that run in parallel can see each other's calls.
+//! Make assertions about functions that are only called by one test, run the tests with `--test-threads=1`,
+//! or record the calls of each test in its own [`ScopedRegistry`](crate::scope::ScopedRegistry)
+//! (with the `prometheus-client` backend).
+//!
+//! # Snapshot tests
+//!
+//! To compare the whole `/metrics` output to a golden file, enable
+//! [`prometheus_exporter::set_stable_output`], which sorts the metric families, series, and labels,
+//! and use a fixed [`Clock`](crate::settings::Clock) so that the latencies are the same in every run.
+
+use crate::constants::*;
+use crate::labels::label_key;
+use crate::prometheus_exporter;
+use crate::settings::get_settings;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+use std::thread::{self, Thread};
+
+/// The metrics recorded since the test started.
+///
+/// This is created by the [`autometrics_test`](crate::autometrics_test) attribute, or with [`TestMetrics::start`].
+#[derive(Debug)]
+pub struct TestMetrics {
+    baseline: Vec<Sample>,
+}
+
+impl TestMetrics {
+    /// Initialize the exporter (if it is not already initialized) and remember the current values of the metrics.
+    pub fn start() -> Self {
+        prometheus_exporter::try_init().ok();
+        Self {
+            baseline: samples(),
+        }
+    }
+
+    /// Forget the metrics recorded so far, so that the helpers only count what is recorded after this.
+    pub fn reset(&mut self) {
+        self.baseline = samples();
+    }
+
+    /// The number of calls to the function with the given name.
+    pub fn calls(&self, function: &str) -> u64 {
+        self.counter(&[(label_key(FUNCTION_KEY), function)])
+    }
+
+    /// The number of calls to the function that returned an error (or panicked).
+    pub fn errors(&self, function: &str) -> u64 {
+        self.counter(&[(label_key(FUNCTION_KEY), function), (RESULT_KEY, ERROR_KEY)])
+    }
+
+    /// The number of calls to the function that returned successfully.
+    pub fn successes(&self, function: &str) -> u64 {
+        self.counter(&[(label_key(FUNCTION_KEY), function), (RESULT_KEY, OK_KEY)])
+    }
+
+    /// The number of calls to the function that were made by the given caller.
+    pub fn calls_from(&self, function: &str, caller_function: &str) -> u64 {
+        self.counter(&[
+            (label_key(FUNCTION_KEY), function),
+            (CALLER_FUNCTION_PROMETHEUS, caller_function),
+        ])
+    }
+
+    /// The number of calls to the function whose duration was recorded in the `function_calls_duration_seconds` histogram.
+    pub fn timed_calls(&self, function: &str) -> u64 {
+        let name = get_settings().prometheus_histogram_name();
+        self.value(
+            &format!("{name}_count"),
+            &[(label_key(FUNCTION_KEY), function)],
+        ) as u64
+    }
+
+    /// The increase of the given metric since the test started, summed over all of the series
+    /// that have the given labels.
+    ///
+    /// The name is the full name of the series as it appears in the Prometheus text format,
+    /// such as `function_calls_total` or `function_calls_duration_seconds_count`.
+    pub fn value(&self, name: &str, labels: &[(&str, &str)]) -> f64 {
+        let total = |samples: &[Sample]| -> f64 {
+            samples
+                .iter()
+                .filter(|sample| sample.matches(name, labels))
+                .map(|sample| sample.value)
+                .sum()
+        };
+        total(&samples()) - total(&self.baseline)
+    }
+
+    /// Assert that the function call counter increased by the expected value since the test started,
+    /// summed over all of the series that have the given labels.
+    ///
+    /// The `function` and `module` keys are replaced with their new keys if they were renamed in the settings.
+    /// If the assertion fails, the message lists the series of the counter that have the given `function` label.
+    ///
+    /// The [`assert_counter!`](crate::assert_counter) macro is a shorthand for this.
+    #[track_caller]
+    pub fn assert_counter(&self, labels: &[(&'static str, &str)], expected: u64) {
+        let labels: Vec<(&str, &str)> = labels
+            .iter()
+            .map(|(key, value)| (label_key(key), *value))
+            .collect();
+        let actual = self.counter(&labels);
+        if actual == expected {
+            return;
+        }
+
+        let name = get_settings().prometheus_metric_name(COUNTER_NAME_PROMETHEUS);
+        let selector = labels
+            .iter()
+            .map(|(key, value)| format!("{key}={value:?}"))
+            .collect::<Vec<_>>()
+            .join(",");
+        let function: Vec<(&str, &str)> = labels
+            .iter()
+            .filter(|(key, _)| *key == label_key(FUNCTION_KEY))
+            .copied()
+            .collect();
+        let series: Vec<String> = samples()
+            .iter()
+            .filter(|sample| sample.matches(&name, &function))
+            .map(Sample::to_string)
+            .collect();
+        panic!(
+            "expected {name}{{{selector}}} to increase by {expected} since the test started, but it increased by {actual}\n\
+             series:\n  {}",
+            series.join("\n  ")
+        );
+    }
+
+    fn counter(&self, labels: &[(&str, &str)]) -> u64 {
+        let name = get_settings().prometheus_metric_name(COUNTER_NAME_PROMETHEUS);
+        self.value(&name, labels) as u64
+    }
+}
+
+/// Assert that the function call counter increased by the expected value since the test started,
+/// summed over all of the series that have the given labels.
+///
+/// This is a shorthand for [`TestMetrics::assert_counter`](crate::testing::TestMetrics::assert_counter).
+/// The labels are written as `key = value`, followed by the expected value.
+///
+/// # Example
+/// ```rust
+/// use autometrics::{autometrics, assert_counter, testing::TestMetrics};
+///
+/// #[autometrics]
+/// fn delete_user(id: u64) -> Result<(), &'static str> {
+///     Err("not allowed")
+/// }
+///
+/// let metrics = TestMetrics::start();
+/// delete_user(1).ok();
+/// assert_counter!(metrics, function = "delete_user", result = "error", value = 1);
+/// ```
+#[macro_export]
+macro_rules! assert_counter {
+    ($metrics:expr, $($rest:tt)+) => {
+        $crate::__assert_counter!(@labels $metrics, [] $($rest)+)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __assert_counter {
+    (@labels $metrics:expr, [$($labels:tt)*] value = $expected:expr $(,)?) => {
+        $metrics.assert_counter(&[$($labels)*], $expected)
+    };
+    (@labels $metrics:expr, [$($labels:tt)*] $key:ident = $value:expr, $($rest:tt)+) => {
+        $crate::__assert_counter!(@labels $metrics, [$($labels)* (::core::stringify!($key), $value),] $($rest)+)
+    };
+}
+
+/// Run a future to completion on the current thread.
+///
+/// This is used by the [`autometrics_test`](crate::autometrics_test) attribute for async tests without
+/// a runtime attribute, so it does not support futures that rely on a runtime's timers or I/O.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    let mut future = pin!(future);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+/// A series in the Prometheus text format
+#[derive(Debug)]
+struct Sample {
+    name: String,
+    labels: HashMap<String, String>,
+    value: f64,
+}
+
+impl fmt::Display for Sample {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut labels: Vec<_> = self.labels.iter().collect();
+        labels.sort();
+        let labels = labels
+            .into_iter()
+            .map(|(key, value)| format!("{key}={value:?}"))
+            .collect::<Vec<_>>()
+            .join(",");
+        write!(f, "{}{{{labels}}} {}", self.name, self.value)
+    }
+}
+
+impl Sample {
+    fn matches(&self, name: &str, labels: &[(&str, &str)]) -> bool {
+        self.name == name
+            && labels
+                .iter()
+                .all(|(key, value)| self.labels.get(*key).map(String::as_str) == Some(*value))
+    }
+
+    /// Parse a line like `function_calls_total{function="foo",module="bar"} 1`,
+    /// ignoring the exemplar and timestamp that may follow the value
+    fn parse(line: &str) -> Option<Self> {
+        if line.starts_with('#') {
+            return None;
+        }
+        let name_end = line.find(['{', ' '])?;
+        let name = line[..name_end].to_string();
+        let mut labels = HashMap::new();
+        let mut rest = &line[name_end..];
+
+        if let Some(mut label_set) = rest.strip_prefix('{') {
+            loop {
+                label_set = label_set.trim_start_matches(',');
+                if let Some(after) = label_set.strip_prefix('}') {
+                    rest = after;
+                    break;
+                }
+                let (key, after_key) = label_set.split_once("=\"")?;
+                let mut value = String::new();
+                let mut chars = after_key.char_indices();
+                let end = loop {
+                    match chars.next()? {
+                        (_, '\\') => match chars.next()?.1 {
+                            'n' => value.push('\n'),
+                            escaped => value.push(escaped),
+                        },
+                        (index, '"') => break index,
+                        (_, c) => value.push(c),
+                    }
+                };
+                labels.insert(key.to_string(), value);
+                label_set = &after_key[end + 1..];
+            }
+        }
+
+        let value = rest.split_whitespace().next()?.parse().ok()?;
+        Some(Self {
+            name,
+            labels,
+            value,
+        })
+    }
+}
+
+fn samples() -> Vec<Sample> {
+    prometheus_exporter::encode_to_string()
+        .unwrap_or_default()
+        .lines()
+        .filter_map(Sample::parse)
+        .collect()
+}
@@ -0,0 +1,118 @@
+//! Add the names of the Tokio tasks to the concurrency gauges.
+//!
+//! When Tokio is built with `--cfg tokio_unstable` and the `tracing` feature (as it needs to be for
+//! [tokio-console](https://github.com/tokio-rs/console)), every spawned task runs in a `runtime.spawn` span
+//! that has the name the task was given with [`tokio::task::Builder::name`]. The [`TaskNameLayer`] remembers
+//! these names, and the `function.calls.concurrent` and `function.calls.concurrent.max` gauges of the functions
+//! that [track their concurrency](crate::autometrics#track_concurrency) get a `task` label with the name of the
+//! task that the call is running in. A task that looks stuck in tokio-console can then be matched with
+//! the functions it is in the middle of calling, and the other way around.
+//!
+//! The calls that are not made in a named task do not have the `task` label, and the other metrics are not
+//! affected. Every distinct task name creates new series of the gauges, so only give the same name
+//! to the tasks that do the same work (for example, `"worker"` rather than `"worker-{id}"`).
+//!
+//! # Example
+//!
+//! ```rust
+//! use autometrics::tokio_console::TaskNameLayer;
+//! use tracing_subscriber::prelude::*;
+//!
+//! fn main() {
+//!     tracing_subscriber::registry()
+//!         // .with(console_subscriber::spawn())
+//!         .with(TaskNameLayer::new())
+//!         .init();
+//! }
+//! ```
+//!
+//! [`tokio::task::Builder::name`]: https://docs.rs/tokio/latest/tokio/task/struct.Builder.html#method.name
+
+use crate::labels::intern;
+use tracing::field::{Field, Visit};
+use tracing::{span::Attributes, Id, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::registry::{LookupSpan, Registry};
+
+/// The name of the spans that Tokio runs the spawned tasks in
+const SPAWN_SPAN_NAME: &str = "runtime.spawn";
+/// The field of the spawn span with the name of the task
+const TASK_NAME_FIELD: &str = "task.name";
+
+/// The name of a task, stored in the extensions of its spawn span
+struct TaskName(&'static str);
+
+/// Get the name of the task that the current span is running in
+pub(crate) fn current_task_name() -> Option<&'static str> {
+    let span = tracing::span::Span::current();
+
+    span.with_subscriber(|(id, sub)| {
+        sub.downcast_ref::<Registry>()
+            .and_then(|reg| reg.span(id))
+            .and_then(|span| {
+                span.scope()
+                    .find_map(|span| span.extensions().get::<TaskName>().map(|name| name.0))
+            })
+    })
+    .flatten()
+}
+
+/// A [`tracing_subscriber::Layer`] that records the names of the Tokio tasks,
+/// so that they can be added to the concurrency gauges.
+///
+/// # Example
+/// ```rust
+/// use autometrics::tokio_console::TaskNameLayer;
+/// use tracing_subscriber::prelude::*;
+///
+/// fn main() {
+///     tracing_subscriber::registry()
+///         .with(TaskNameLayer::new())
+///         .init();
+/// }
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct TaskNameLayer {
+    _private: (),
+}
+
+impl TaskNameLayer {
+    /// Create a new [`TaskNameLayer`].
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<S: Subscriber + for<'lookup> LookupSpan<'lookup>> Layer<S> for TaskNameLayer {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != SPAWN_SPAN_NAME {
+            return;
+        }
+
+        let mut visitor = TaskNameVisitor(None);
+        attrs.values().record(&mut visitor);
+
+        // Tokio records an empty name for the tasks that were not given one
+        if let Some(name) = visitor.0.filter(|name| !name.is_empty()) {
+            if let Some(span) = ctx.span(id) {
+                span.extensions_mut().insert(TaskName(intern(&name)));
+            }
+        }
+    }
+}
+
+struct TaskNameVisitor(Option<String>);
+
+impl Visit for TaskNameVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == TASK_NAME_FIELD {
+            self.0 = Some(format!("{:?}", value));
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == TASK_NAME_FIELD {
+            self.0 = Some(value.to_string());
+        }
+    }
+}
@@ -1,33 +1,225 @@
-#[cfg(debug_assertions)]
-use crate::__private::FunctionDescription;
 use crate::constants::*;
-use crate::labels::{BuildInfoLabels, CounterLabels, GaugeLabels, HistogramLabels};
-use crate::tracker::TrackMetrics;
-use metrics::{
-    describe_counter, describe_gauge, describe_histogram, register_counter, register_gauge,
-    register_histogram, Gauge, Unit,
+#[cfg(feature = "rdkafka")]
+use crate::labels::ConsumerLagLabels;
+use crate::labels::{
+    global_labels, label_key, BuildInfoLabels, CallEdgeLabels, CardinalityWarningLabels,
+    CounterLabels, CrateCallsLabels, FunctionRenamedLabels, GaugeLabels, HistogramLabels, Label,
+    LockRecoveryLabels,
 };
-use std::{sync::Once, time::Instant};
+use crate::settings::{get_settings, DurationUnit};
+use crate::tracker::{build_age_seconds, Timestamp, TrackMetrics};
+use metrics::{with_recorder, Counter, Gauge, Histogram, Key, KeyName, Level, Metadata, Unit};
+use once_cell::sync::Lazy;
+use std::sync::Once;
 
 static DESCRIBE_METRICS: Once = Once::new();
 static SET_BUILD_INFO: Once = Once::new();
 
+/// The metadata attached to all of the metrics, which some exporters use for filtering
+static METADATA: Metadata<'static> =
+    Metadata::new(module_path!(), Level::INFO, Some(module_path!()));
+
+/// The metric names with the prefix configured in the settings
+struct MetricNames {
+    counter: String,
+    histogram: String,
+    response_size: String,
+    gauge: String,
+    max_concurrency: String,
+    build_info: String,
+    build_age: String,
+    call_edges: String,
+    cardinality_warnings: String,
+    lock_recoveries: String,
+    function_renamed: String,
+    crate_calls: String,
+    #[cfg(feature = "rdkafka")]
+    consumer_lag: String,
+}
+
+static METRIC_NAMES: Lazy<MetricNames> = Lazy::new(|| {
+    let settings = get_settings();
+    MetricNames {
+        counter: settings.prometheus_metric_name(COUNTER_NAME_PROMETHEUS),
+        histogram: settings.prometheus_histogram_name(),
+        response_size: settings.prometheus_metric_name(RESPONSE_SIZE_NAME_PROMETHEUS),
+        gauge: settings.prometheus_metric_name(GAUGE_NAME_PROMETHEUS),
+        max_concurrency: settings.prometheus_metric_name(MAX_CONCURRENCY_NAME_PROMETHEUS),
+        build_info: settings.prometheus_metric_name(BUILD_INFO_NAME),
+        build_age: settings.prometheus_metric_name(BUILD_AGE_NAME_PROMETHEUS),
+        call_edges: settings.prometheus_metric_name(CALL_EDGES_NAME_PROMETHEUS),
+        cardinality_warnings: settings.prometheus_metric_name(CARDINALITY_WARNINGS_NAME_PROMETHEUS),
+        lock_recoveries: settings.prometheus_metric_name(LOCK_RECOVERIES_NAME_PROMETHEUS),
+        function_renamed: settings.prometheus_metric_name(FUNCTION_RENAMED_NAME_PROMETHEUS),
+        crate_calls: settings.prometheus_metric_name(CRATE_CALLS_NAME_PROMETHEUS),
+        #[cfg(feature = "rdkafka")]
+        consumer_lag: settings.prometheus_metric_name(CONSUMER_LAG_NAME_PROMETHEUS),
+    }
+});
+
 fn describe_metrics() {
     DESCRIBE_METRICS.call_once(|| {
-        describe_counter!(COUNTER_NAME_PROMETHEUS, COUNTER_DESCRIPTION);
-        describe_histogram!(
-            HISTOGRAM_NAME_PROMETHEUS,
-            Unit::Seconds,
-            HISTOGRAM_DESCRIPTION
-        );
-        describe_gauge!(GAUGE_NAME_PROMETHEUS, GAUGE_DESCRIPTION);
-        describe_gauge!(BUILD_INFO_NAME, BUILD_INFO_DESCRIPTION);
+        with_recorder(|recorder| {
+            let names = &*METRIC_NAMES;
+            recorder.describe_counter(name(&names.counter), None, COUNTER_DESCRIPTION.into());
+            let duration_unit = match get_settings().duration_unit {
+                DurationUnit::Seconds => Unit::Seconds,
+                DurationUnit::Milliseconds => Unit::Milliseconds,
+            };
+            recorder.describe_histogram(
+                name(&names.histogram),
+                Some(duration_unit),
+                HISTOGRAM_DESCRIPTION.into(),
+            );
+            for view in &get_settings().histogram_views {
+                recorder.describe_histogram(
+                    name(&view.prometheus_name),
+                    Some(duration_unit),
+                    HISTOGRAM_DESCRIPTION.into(),
+                );
+            }
+            recorder.describe_histogram(
+                name(&names.response_size),
+                Some(Unit::Bytes),
+                RESPONSE_SIZE_DESCRIPTION.into(),
+            );
+            recorder.describe_gauge(name(&names.gauge), None, GAUGE_DESCRIPTION.into());
+            recorder.describe_gauge(
+                name(&names.max_concurrency),
+                None,
+                MAX_CONCURRENCY_DESCRIPTION.into(),
+            );
+            recorder.describe_gauge(name(&names.build_info), None, BUILD_INFO_DESCRIPTION.into());
+            recorder.describe_gauge(
+                name(&names.build_age),
+                Some(Unit::Seconds),
+                BUILD_AGE_DESCRIPTION.into(),
+            );
+            recorder.describe_gauge(name(&names.call_edges), None, CALL_EDGES_DESCRIPTION.into());
+            recorder.describe_counter(
+                name(&names.cardinality_warnings),
+                None,
+                CARDINALITY_WARNINGS_DESCRIPTION.into(),
+            );
+            recorder.describe_counter(
+                name(&names.lock_recoveries),
+                None,
+                LOCK_RECOVERIES_DESCRIPTION.into(),
+            );
+            recorder.describe_gauge(
+                name(&names.function_renamed),
+                None,
+                FUNCTION_RENAMED_DESCRIPTION.into(),
+            );
+            recorder.describe_counter(
+                name(&names.crate_calls),
+                None,
+                CRATE_CALLS_DESCRIPTION.into(),
+            );
+            #[cfg(feature = "rdkafka")]
+            recorder.describe_gauge(
+                name(&names.consumer_lag),
+                None,
+                CONSUMER_LAG_DESCRIPTION.into(),
+            );
+        });
     });
 }
 
+/// The metric names live as long as the settings, so they can be used without copying them
+fn name(name: &'static str) -> KeyName {
+    KeyName::from(name)
+}
+
+/// Create the key of a series without copying the names or the label values,
+/// which all live as long as the program
+fn key(name: &'static str, labels: impl IntoIterator<Item = Label>) -> Key {
+    Key::from_parts(
+        name,
+        labels
+            .into_iter()
+            .map(|(key, value)| metrics::Label::from_static_parts(label_key(key), value))
+            .collect::<Vec<_>>(),
+    )
+}
+
+fn counter(name: &'static str, labels: impl IntoIterator<Item = Label>) -> Counter {
+    with_recorder(|recorder| recorder.register_counter(&key(name, labels), &METADATA))
+}
+
+fn gauge(name: &'static str, labels: impl IntoIterator<Item = Label>) -> Gauge {
+    with_recorder(|recorder| recorder.register_gauge(&key(name, labels), &METADATA))
+}
+
+fn histogram(name: &'static str, labels: impl IntoIterator<Item = Label>) -> Histogram {
+    with_recorder(|recorder| recorder.register_histogram(&key(name, labels), &METADATA))
+}
+
+pub(crate) fn record_cardinality_warning(labels: &CardinalityWarningLabels) {
+    describe_metrics();
+    counter(&METRIC_NAMES.cardinality_warnings, labels.to_vec()).increment(1);
+}
+
+pub(crate) fn record_crate_calls(labels: &CrateCallsLabels, weight: u64) {
+    describe_metrics();
+    counter(&METRIC_NAMES.crate_calls, labels.to_vec()).increment(weight);
+}
+
+pub(crate) fn record_lock_recovery(labels: &LockRecoveryLabels) {
+    describe_metrics();
+    counter(&METRIC_NAMES.lock_recoveries, labels.to_vec()).increment(1);
+}
+
+pub(crate) fn record_response_size(histogram_labels: &HistogramLabels, size: usize) {
+    describe_metrics();
+    histogram(&METRIC_NAMES.response_size, histogram_labels.to_vec()).record(size as f64);
+}
+
+pub(crate) fn set_max_concurrency(gauge_labels: &GaugeLabels, max: i64) {
+    describe_metrics();
+    gauge(&METRIC_NAMES.max_concurrency, gauge_labels.to_array()).set(max as f64);
+}
+
+/// Set the gauge for the call edge that was added, and reset the one that was evicted.
+///
+/// The `metrics` crate does not support removing series, so evicted edges are set to 0.
+pub(crate) fn update_call_edges(added: &CallEdgeLabels, evicted: Option<&CallEdgeLabels>) {
+    describe_metrics();
+    gauge(&METRIC_NAMES.call_edges, added.to_vec()).set(1.0);
+    if let Some(evicted) = evicted {
+        gauge(&METRIC_NAMES.call_edges, evicted.to_vec()).set(0.0);
+    }
+}
+
+pub(crate) fn set_function_renamed(labels: &FunctionRenamedLabels) {
+    describe_metrics();
+    gauge(&METRIC_NAMES.function_renamed, labels.to_vec()).set(1.0);
+}
+
+/// Update the build age gauge.
+///
+/// The `metrics` crate does not support computing values when the metrics are rendered,
+/// so this is called by the [`prometheus_exporter`](crate::prometheus_exporter) before encoding the metrics.
+pub(crate) fn update_build_age() {
+    if let Some(build_age) = build_age_seconds() {
+        gauge(&METRIC_NAMES.build_age, global_labels()).set(build_age);
+    }
+}
+
 pub struct MetricsTracker {
     gauge: Option<Gauge>,
-    start: Instant,
+    start: Timestamp,
+}
+
+#[cfg(replayed_calls)]
+impl MetricsTracker {
+    /// Replay a call that was measured in another process, without counting it as a concurrent call
+    pub(crate) fn replayed(elapsed: std::time::Duration) -> Self {
+        Self {
+            gauge: None,
+            start: Timestamp::Elapsed(elapsed),
+        }
+    }
 }
 
 impl TrackMetrics for MetricsTracker {
@@ -35,7 +227,7 @@ impl TrackMetrics for MetricsTracker {
         describe_metrics();
 
         let gauge = if let Some(gauge_labels) = gauge_labels {
-            let gauge = register_gauge!(GAUGE_NAME, &gauge_labels.to_array());
+            let gauge = gauge(&METRIC_NAMES.gauge, gauge_labels.to_array());
             gauge.increment(1.0);
             Some(gauge)
         } else {
@@ -44,30 +236,51 @@ impl TrackMetrics for MetricsTracker {
 
         Self {
             gauge,
-            start: Instant::now(),
+            start: Timestamp::now(),
         }
     }
 
-    fn finish(self, counter_labels: &CounterLabels, histogram_labels: &HistogramLabels) {
-        let duration = self.start.elapsed().as_secs_f64();
-        register_counter!(COUNTER_NAME_PROMETHEUS, &counter_labels.to_vec()).increment(1);
-        register_histogram!(HISTOGRAM_NAME_PROMETHEUS, &histogram_labels.to_vec()).record(duration);
+    fn finish_weighted(
+        self,
+        counter_labels: &CounterLabels,
+        histogram_labels: &HistogramLabels,
+        weight: u64,
+    ) {
+        let duration = get_settings().duration_unit.convert(self.start.elapsed());
+        counter(&METRIC_NAMES.counter, counter_labels.to_vec()).increment(weight);
+        // The buckets are configured per metric name by the exporter,
+        // so the buckets set with the `buckets` argument of the macro are only used by the other backends
+        let histogram_labels = histogram_labels.to_vec();
+        histogram(&METRIC_NAMES.histogram, histogram_labels.iter().copied()).record(duration);
+        for view in &get_settings().histogram_views {
+            histogram(&view.prometheus_name, histogram_labels.iter().copied()).record(duration);
+        }
         if let Some(gauge) = self.gauge {
             gauge.decrement(1.0);
         }
     }
 
+    fn count(counter_labels: &CounterLabels) {
+        describe_metrics();
+        counter(&METRIC_NAMES.counter, counter_labels.to_vec()).increment(1);
+    }
+
+    #[cfg(feature = "rdkafka")]
+    fn set_consumer_lag(consumer_lag_labels: &ConsumerLagLabels, lag: i64) {
+        describe_metrics();
+        gauge(&METRIC_NAMES.consumer_lag, consumer_lag_labels.to_vec()).set(lag as f64);
+    }
+
     fn set_build_info(build_info_labels: &BuildInfoLabels) {
         SET_BUILD_INFO.call_once(|| {
-            register_gauge!(BUILD_INFO_NAME, &build_info_labels.to_vec()).set(1.0);
+            gauge(&METRIC_NAMES.build_info, build_info_labels.to_vec()).set(1.0);
+            update_build_age();
         });
     }
 
-    #[cfg(debug_assertions)]
-    fn intitialize_metrics(function_descriptions: &[FunctionDescription]) {
-        for function in function_descriptions {
-            let labels = &CounterLabels::from(function).to_vec();
-            register_counter!(COUNTER_NAME, labels).increment(0);
+    fn intitialize_metrics(counter_labels: &[CounterLabels]) {
+        for counter_labels in counter_labels {
+            counter(&METRIC_NAMES.counter, counter_labels.to_vec()).increment(0);
         }
     }
 }
@@ -1,13 +1,30 @@
-#[cfg(debug_assertions)]
-use crate::__private::FunctionDescription;
+#[cfg(function_registry)]
+use crate::__private::FUNCTION_DESCRIPTIONS;
+use crate::concurrency::ConcurrencyGuard;
+#[cfg(feature = "rdkafka")]
+use crate::labels::ConsumerLagLabels;
 use crate::labels::{BuildInfoLabels, CounterLabels, GaugeLabels, HistogramLabels};
+use crate::objectives::Objective;
+#[cfg(feature = "plugin")]
+use crate::plugin::HostTracker;
+use crate::poison;
+use crate::settings::get_settings;
+use once_cell::sync::{Lazy, OnceCell};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+use std::time::Duration;
+#[cfg(not(wasm32_unknown))]
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+#[cfg(wasm32_unknown)]
+use wasm_bindgen::prelude::*;
 
 #[cfg(metrics)]
-mod metrics;
+pub(crate) mod metrics;
 #[cfg(opentelemetry)]
 mod opentelemetry;
 #[cfg(prometheus)]
-mod prometheus;
+pub(crate) mod prometheus;
 #[cfg(prometheus_client)]
 pub(crate) mod prometheus_client;
 
@@ -20,25 +37,378 @@ pub use self::prometheus::PrometheusTracker;
 #[cfg(prometheus_client)]
 pub use self::prometheus_client::PrometheusClientTracker;
 
-#[cfg(all(
-    not(doc),
-    any(
-        all(metrics, any(opentelemetry, prometheus, prometheus_client)),
-        all(opentelemetry, any(prometheus, prometheus_client)),
-        all(prometheus, prometheus_client)
-    )
-))]
-compile_error!("Only one of the metrics, opentelemetry, prometheus, or prometheus-client features can be enabled at a time");
+#[cfg(all(not(doc), opentelemetry_0_24, feature = "opentelemetry-0_33"))]
+compile_error!(
+    "Only one of the opentelemetry-0_24 or opentelemetry-0_33 features can be enabled at a time"
+);
+
+/// Whether recording metrics is currently paused.
+///
+/// This is controlled via the [`ExporterHandle`](crate::prometheus_exporter::ExporterHandle).
+static PAUSED: AtomicBool = AtomicBool::new(false);
+
+#[allow(dead_code)]
+pub(crate) fn set_paused(paused: bool) {
+    PAUSED.store(paused, Ordering::Relaxed);
+}
+
+#[allow(dead_code)]
+pub(crate) fn is_paused() -> bool {
+    PAUSED.load(Ordering::Relaxed)
+}
+
+/// The series recorded by a single call site of an instrumented function.
+///
+/// The `autometrics` macro creates one of these as a static for each instrumented function,
+/// so that the backends can keep handles to the function's series instead of looking them up
+/// by their labels on every call. Only the `prometheus` backend uses these so far.
+///
+/// It also keeps the function's objective, which may be loaded at runtime, so that it is only resolved once.
+#[derive(Debug)]
+pub struct CallSite {
+    #[cfg(prometheus)]
+    pub(crate) prometheus: prometheus::CallSiteSeries,
+    objective: OnceCell<Option<Objective>>,
+}
+
+impl CallSite {
+    #[allow(clippy::new_without_default)]
+    pub const fn new() -> Self {
+        Self {
+            #[cfg(prometheus)]
+            prometheus: prometheus::CallSiteSeries::new(),
+            objective: OnceCell::new(),
+        }
+    }
+
+    /// The objective of the function, which is resolved on the first call
+    pub fn objective(&self, objective: impl FnOnce() -> Option<Objective>) -> Option<Objective> {
+        *self.objective.get_or_init(objective)
+    }
+}
+
+/// A monotonic clock for measuring how long the calls to instrumented functions take.
+///
+/// By default, the latencies are measured with [`std::time::Instant`], except on `wasm32-unknown-unknown`
+/// (for example, in Cloudflare Workers), where `Instant::now()` panics and the JavaScript
+/// `performance.now()` is used instead. A different clock can be set with
+/// [`AutometricsSettingsBuilder::clock`](crate::settings::AutometricsSettingsBuilder::clock).
+///
+/// This is implemented for closures that return the current time.
+pub trait Clock: Send + Sync {
+    /// The time elapsed since an arbitrary point in time, which must never go backwards.
+    fn now(&self) -> Duration;
+}
+
+impl<F> Clock for F
+where
+    F: Fn() -> Duration + Send + Sync,
+{
+    fn now(&self) -> Duration {
+        self()
+    }
+}
+
+/// The clock that is used unless another one is set in the settings.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DefaultClock;
+
+impl Clock for DefaultClock {
+    #[cfg(not(wasm32_unknown))]
+    fn now(&self) -> Duration {
+        static ORIGIN: Lazy<Instant> = Lazy::new(Instant::now);
+        ORIGIN.elapsed()
+    }
+
+    #[cfg(wasm32_unknown)]
+    fn now(&self) -> Duration {
+        // Both clocks return milliseconds, and `performance` is missing in some runtimes
+        let millis = performance_now().unwrap_or_else(|_| date_now());
+        Duration::from_secs_f64(millis / 1000.0)
+    }
+}
+
+#[cfg(wasm32_unknown)]
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(catch, js_namespace = performance, js_name = now)]
+    fn performance_now() -> Result<f64, JsValue>;
+
+    #[wasm_bindgen(js_namespace = Date, js_name = now)]
+    fn date_now() -> f64;
+}
+
+/// A point in time read from the clock in the settings, for measuring the latency of a call
+#[derive(Clone, Copy, Debug)]
+#[allow(dead_code)]
+pub(crate) enum Timestamp {
+    Started(Duration),
+    /// The call was measured elsewhere (for example, in another process) and took this long
+    #[cfg(replayed_calls)]
+    Elapsed(Duration),
+}
+
+#[allow(dead_code)]
+impl Timestamp {
+    pub(crate) fn now() -> Self {
+        Self::Started(get_settings().clock.0.now())
+    }
+
+    pub(crate) fn elapsed(&self) -> Duration {
+        match self {
+            Self::Started(start) => get_settings().clock.0.now().saturating_sub(*start),
+            #[cfg(replayed_calls)]
+            Self::Elapsed(elapsed) => *elapsed,
+        }
+    }
+}
+
+/// The Unix timestamp (in seconds) of when the instrumented crate was built.
+///
+/// This is set from the `AUTOMETRICS_BUILD_TIMESTAMP` compile-time environment variable
+/// the first time a function instrumented in a crate that sets it is called.
+static BUILD_TIMESTAMP: OnceCell<u64> = OnceCell::new();
+
+/// Store the build timestamp passed in by the code generated by the autometrics macro.
+///
+/// The functions of crates that are built without a timestamp do not prevent another crate from setting it.
+pub fn set_build_timestamp(timestamp: Option<&'static str>) {
+    if let Some(timestamp) = timestamp.and_then(|timestamp| timestamp.trim().parse().ok()) {
+        let _ = BUILD_TIMESTAMP.set(timestamp);
+    }
+}
+
+/// The number of seconds elapsed since the instrumented crate was built,
+/// or `None` if the build timestamp is unknown.
+#[allow(dead_code)]
+pub(crate) fn build_age_seconds() -> Option<f64> {
+    let build_timestamp = *BUILD_TIMESTAMP.get()?;
+    #[cfg(not(wasm32_unknown))]
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_secs_f64();
+    #[cfg(wasm32_unknown)]
+    let now = date_now() / 1000.0;
+    Some((now - build_timestamp as f64).max(0.0))
+}
+
+/// The names of the backends that the metrics are recorded with
+#[cfg(debug_metrics)]
+const BACKENDS: &[&str] = &[
+    #[cfg(metrics)]
+    "metrics",
+    #[cfg(opentelemetry)]
+    "opentelemetry",
+    #[cfg(prometheus)]
+    "prometheus",
+    #[cfg(prometheus_client)]
+    "prometheus-client",
+];
+
+/// Log the labels of a call that was recorded, to help find out why metrics do not show up as expected
+/// (calls that are only counted do not have histogram labels)
+#[cfg(debug_metrics)]
+fn log_recorded_call(
+    counter_labels: &CounterLabels,
+    histogram_labels: Option<&HistogramLabels>,
+    weight: u64,
+) {
+    tracing::debug!(
+        target: "autometrics::debug_metrics",
+        backends = %BACKENDS.join(","),
+        function = counter_labels.function,
+        module = counter_labels.module,
+        weight,
+        counter_labels = %format_labels(counter_labels.to_vec()),
+        histogram_labels = %histogram_labels
+            .map(|labels| format_labels(labels.to_vec()))
+            .unwrap_or_default(),
+        "recorded function call"
+    );
+}
+
+/// Format the labels like a Prometheus label set (`{function="foo",module="bar"}`),
+/// with the `function` and `module` keys renamed as configured in the settings
+#[cfg(debug_metrics)]
+fn format_labels(labels: Vec<crate::labels::Label>) -> String {
+    let labels: Vec<String> = labels
+        .into_iter()
+        .map(|(key, value)| format!("{}={value:?}", crate::labels::label_key(key)))
+        .collect();
+    format!("{{{}}}", labels.join(","))
+}
+
+/// Register the `function_call_edges_info` metric, once the first call edge is observed.
+pub(crate) fn register_call_edges() {
+    #[cfg(opentelemetry)]
+    opentelemetry::register_call_edges();
+    #[cfg(prometheus)]
+    prometheus::register_call_edges();
+}
+
+/// Update the `function_call_edges_info` metric for backends that cannot compute it
+/// from the current call edges when the metrics are collected.
+#[allow(unused_variables)]
+pub(crate) fn update_call_edges(
+    added: &crate::labels::CallEdgeLabels,
+    evicted: Option<&crate::labels::CallEdgeLabels>,
+) {
+    #[cfg(metrics)]
+    metrics::update_call_edges(added, evicted);
+}
+
+/// The functions that were already called, if the first calls are labeled as cold starts
+static CALLED_FUNCTIONS: Lazy<RwLock<HashSet<(&'static str, &'static str)>>> =
+    Lazy::new(Default::default);
+
+/// Whether this is the first call of the function, if the `cold="true"` label is enabled
+fn is_cold_start(histogram_labels: &HistogramLabels) -> bool {
+    if !get_settings().cold_start_label {
+        return false;
+    }
+
+    let function = (histogram_labels.function, histogram_labels.module);
+    if poison::read(&CALLED_FUNCTIONS, "cold_starts").contains(&function) {
+        return false;
+    }
+    poison::write(&CALLED_FUNCTIONS, "cold_starts").insert(function)
+}
+
+/// Count a warning from the [`cardinality`](crate::cardinality) watchdog.
+#[allow(unused_variables)]
+pub(crate) fn record_cardinality_warning(labels: &crate::labels::CardinalityWarningLabels) {
+    #[cfg(metrics)]
+    metrics::record_cardinality_warning(labels);
+    #[cfg(opentelemetry)]
+    opentelemetry::record_cardinality_warning(labels);
+    #[cfg(prometheus)]
+    prometheus::record_cardinality_warning(labels);
+    #[cfg(prometheus_client)]
+    prometheus_client::record_cardinality_warning(labels);
+}
+
+/// Count the calls in the `crate_calls_total` metric, if the per-crate rollups are enabled.
+#[allow(unused_variables)]
+fn record_crate_calls(counter_labels: &CounterLabels, weight: u64) {
+    if !get_settings().crate_rollups {
+        return;
+    }
+
+    let labels = crate::labels::CrateCallsLabels::new(counter_labels);
+    #[cfg(metrics)]
+    metrics::record_crate_calls(&labels, weight);
+    #[cfg(opentelemetry)]
+    opentelemetry::record_crate_calls(&labels, weight);
+    #[cfg(prometheus)]
+    prometheus::record_crate_calls(&labels, weight);
+    #[cfg(prometheus_client)]
+    prometheus_client::record_crate_calls(&labels, weight);
+}
+
+/// Count a lock that was recovered after it was poisoned, see [`poison`](crate::poison).
+#[allow(unused_variables)]
+pub(crate) fn record_lock_recovery(labels: &crate::labels::LockRecoveryLabels) {
+    #[cfg(metrics)]
+    metrics::record_lock_recovery(labels);
+    #[cfg(opentelemetry)]
+    opentelemetry::record_lock_recovery(labels);
+    #[cfg(prometheus)]
+    prometheus::record_lock_recovery(labels);
+    #[cfg(prometheus_client)]
+    prometheus_client::record_lock_recovery(labels);
+}
+
+/// Set the `function.renamed.info` metric for a function with the `alias` argument.
+#[allow(unused_variables)]
+pub(crate) fn set_function_renamed(labels: &crate::labels::FunctionRenamedLabels) {
+    #[cfg(metrics)]
+    metrics::set_function_renamed(labels);
+    #[cfg(opentelemetry)]
+    opentelemetry::set_function_renamed(labels);
+    #[cfg(prometheus)]
+    prometheus::set_function_renamed(labels);
+    #[cfg(prometheus_client)]
+    prometheus_client::set_function_renamed(labels);
+}
+
+/// Record the size of a value returned by a function with the `track_size` argument.
+#[allow(unused_variables)]
+fn record_response_size(histogram_labels: &HistogramLabels, size: usize) {
+    #[cfg(metrics)]
+    metrics::record_response_size(histogram_labels, size);
+    #[cfg(opentelemetry)]
+    opentelemetry::record_response_size(histogram_labels, size);
+    #[cfg(prometheus)]
+    prometheus::record_response_size(histogram_labels, size);
+    #[cfg(prometheus_client)]
+    prometheus_client::record_response_size(histogram_labels, size);
+}
+
+/// Set the gauge of the maximum number of concurrent calls, see [`concurrency`](crate::concurrency).
+#[allow(unused_variables)]
+pub(crate) fn set_max_concurrency(gauge_labels: &GaugeLabels, max: i64) {
+    #[cfg(metrics)]
+    metrics::set_max_concurrency(gauge_labels, max);
+    #[cfg(opentelemetry)]
+    opentelemetry::set_max_concurrency(gauge_labels, max);
+    #[cfg(prometheus)]
+    prometheus::set_max_concurrency(gauge_labels, max);
+    #[cfg(prometheus_client)]
+    prometheus_client::set_max_concurrency(gauge_labels, max);
+}
+
+/// Set the counters of all of the instrumented functions to zero.
+#[cfg(function_registry)]
+#[allow(dead_code)]
+pub(crate) fn initialize_function_descriptions() {
+    let counter_labels: Vec<CounterLabels> = FUNCTION_DESCRIPTIONS
+        .iter()
+        .map(CounterLabels::from)
+        .collect();
+    AutometricsTracker::intitialize_metrics(&counter_labels);
+}
 
 pub trait TrackMetrics {
     fn set_build_info(build_info_labels: &BuildInfoLabels);
     fn start(gauge_labels: Option<&GaugeLabels>) -> Self;
-    fn finish(self, counter_labels: &CounterLabels, histogram_labels: &HistogramLabels);
-    #[cfg(debug_assertions)]
-    fn intitialize_metrics(function_descriptions: &[FunctionDescription]);
+    fn finish(self, counter_labels: &CounterLabels, histogram_labels: &HistogramLabels)
+    where
+        Self: Sized,
+    {
+        self.finish_weighted(counter_labels, histogram_labels, 1);
+    }
+    /// Record the call as `weight` calls in the counter, for functions whose calls are sampled
+    fn finish_weighted(
+        self,
+        counter_labels: &CounterLabels,
+        histogram_labels: &HistogramLabels,
+        weight: u64,
+    );
+    /// Count a call without timing it, for functions with the `count_only` argument
+    fn count(counter_labels: &CounterLabels);
+    #[cfg(feature = "rdkafka")]
+    fn set_consumer_lag(consumer_lag_labels: &ConsumerLagLabels, lag: i64);
+    fn intitialize_metrics(counter_labels: &[CounterLabels]);
 }
 
 pub struct AutometricsTracker {
+    /// Calls that start while recording is paused are not recorded at all
+    recording: bool,
+    /// The number of calls that this call is counted as, if the calls are sampled
+    weight: u64,
+    #[cfg(any(summaries, wide_events, span_events, snapshots, stream))]
+    start: Timestamp,
+    /// Whether events are attached to the current span if the call is slow or fails
+    #[cfg(span_events)]
+    span_events: bool,
+    /// Set for functions with `track_concurrency(max)`, and only held so that the call
+    /// counts towards the number of concurrent calls until the tracker is dropped
+    #[allow(dead_code)]
+    max_concurrency: Option<ConcurrencyGuard>,
+    /// Set if this library is a plugin whose calls are recorded by the host
+    #[cfg(feature = "plugin")]
+    host: Option<HostTracker>,
     #[cfg(metrics)]
     metrics_tracker: MetricsTracker,
     #[cfg(opentelemetry)]
@@ -62,46 +432,374 @@ impl TrackMetrics for AutometricsTracker {
         PrometheusClientTracker::set_build_info(build_info_labels);
     }
 
-    #[allow(unused_variables)]
     fn start(gauge_labels: Option<&GaugeLabels>) -> Self {
-        Self {
-            #[cfg(metrics)]
-            metrics_tracker: MetricsTracker::start(gauge_labels),
-            #[cfg(opentelemetry)]
-            opentelemetry_tracker: OpenTelemetryTracker::start(gauge_labels),
-            #[cfg(prometheus)]
-            prometheus_tracker: PrometheusTracker::start(gauge_labels),
-            #[cfg(prometheus_client)]
-            prometheus_client_tracker: PrometheusClientTracker::start(gauge_labels),
+        #[cfg(feature = "plugin")]
+        if let Some(host) = HostTracker::start(gauge_labels) {
+            let mut tracker = Self::start_local(None);
+            tracker.host = Some(host);
+            return tracker;
         }
+
+        Self::start_local(gauge_labels)
     }
 
     #[allow(unused_variables)]
     fn finish(self, counter_labels: &CounterLabels, histogram_labels: &HistogramLabels) {
+        let weight = self.weight;
+        self.finish_weighted(counter_labels, histogram_labels, weight);
+    }
+
+    #[allow(unused_variables)]
+    fn finish_weighted(
+        self,
+        counter_labels: &CounterLabels,
+        histogram_labels: &HistogramLabels,
+        weight: u64,
+    ) {
+        if weight == 0 {
+            return;
+        }
+
+        #[cfg(feature = "plugin")]
+        if let Some(host) = self.host {
+            host.finish(counter_labels, histogram_labels, weight);
+            return;
+        }
+
+        if !self.recording {
+            return;
+        }
+
+        crate::call_edges::observe(counter_labels);
+        crate::cardinality::observe(counter_labels);
+
+        let cold_labels;
+        let histogram_labels = if is_cold_start(histogram_labels) {
+            cold_labels = histogram_labels.cold();
+            &cold_labels
+        } else {
+            histogram_labels
+        };
+        // The calls of functions with a composite objective are split by result in the histogram,
+        // so that the calls that are both fast and successful can be counted
+        let result_labels;
+        let histogram_labels = if histogram_labels.composite {
+            result_labels = histogram_labels.with_result(counter_labels.result.as_ref());
+            &result_labels
+        } else {
+            histogram_labels
+        };
+
         #[cfg(metrics)]
         self.metrics_tracker
-            .finish(counter_labels, histogram_labels);
+            .finish_weighted(counter_labels, histogram_labels, weight);
         #[cfg(opentelemetry)]
         self.opentelemetry_tracker
-            .finish(counter_labels, histogram_labels);
+            .finish_weighted(counter_labels, histogram_labels, weight);
         #[cfg(prometheus)]
         self.prometheus_tracker
-            .finish(counter_labels, histogram_labels);
+            .finish_weighted(counter_labels, histogram_labels, weight);
         #[cfg(prometheus_client)]
         self.prometheus_client_tracker
-            .finish(counter_labels, histogram_labels);
+            .finish_weighted(counter_labels, histogram_labels, weight);
+        record_crate_calls(counter_labels, weight);
+        #[cfg(summaries)]
+        crate::summaries::record(histogram_labels, self.start.elapsed());
+        #[cfg(snapshots)]
+        crate::snapshot::record(
+            counter_labels,
+            Some((histogram_labels, self.start.elapsed())),
+            weight,
+        );
+        #[cfg(span_traces)]
+        crate::span_traces::capture(counter_labels);
+        #[cfg(stream)]
+        crate::stream::record(
+            counter_labels,
+            Some((histogram_labels, self.start.elapsed())),
+            weight,
+        );
+        #[cfg(wide_events)]
+        crate::wide_events::emit(counter_labels, self.start.elapsed());
+        #[cfg(span_events)]
+        if self.span_events {
+            crate::span_events::emit(counter_labels, histogram_labels, self.start.elapsed());
+        }
+        #[cfg(debug_metrics)]
+        log_recorded_call(counter_labels, Some(histogram_labels), weight);
+    }
+
+    /// Unlike [`finish_weighted`](TrackMetrics::finish_weighted), the call is not observed
+    /// for the call edges or the cardinality watchdog, which would take a lock on every call
+    /// of the (usually very hot) functions that are only counted.
+    fn count(counter_labels: &CounterLabels) {
+        #[cfg(feature = "plugin")]
+        if HostTracker::count(counter_labels) {
+            return;
+        }
+
+        Self::count_local(counter_labels);
+    }
+
+    #[cfg(feature = "rdkafka")]
+    #[allow(unused_variables)]
+    fn set_consumer_lag(consumer_lag_labels: &ConsumerLagLabels, lag: i64) {
+        #[cfg(metrics)]
+        MetricsTracker::set_consumer_lag(consumer_lag_labels, lag);
+        #[cfg(opentelemetry)]
+        OpenTelemetryTracker::set_consumer_lag(consumer_lag_labels, lag);
+        #[cfg(prometheus)]
+        PrometheusTracker::set_consumer_lag(consumer_lag_labels, lag);
+        #[cfg(prometheus_client)]
+        PrometheusClientTracker::set_consumer_lag(consumer_lag_labels, lag);
+    }
+
+    #[allow(unused_variables)]
+    fn intitialize_metrics(counter_labels: &[CounterLabels]) {
+        #[cfg(metrics)]
+        MetricsTracker::intitialize_metrics(counter_labels);
+        #[cfg(opentelemetry)]
+        OpenTelemetryTracker::intitialize_metrics(counter_labels);
+        #[cfg(prometheus)]
+        PrometheusTracker::intitialize_metrics(counter_labels);
+        #[cfg(prometheus_client)]
+        PrometheusClientTracker::intitialize_metrics(counter_labels);
+    }
+}
+
+impl AutometricsTracker {
+    /// Record `weight` calls that were measured in another process and took `elapsed`, for merging snapshots,
+    /// or that were measured by non-Rust code and recorded through the C interface.
+    ///
+    /// Unlike [`finish_weighted`](TrackMetrics::finish_weighted), this only records the metrics
+    /// in the backend, so the calls are not observed again (for example, as wide events).
+    #[cfg(replayed_calls)]
+    #[allow(unused_variables)]
+    pub(crate) fn record_replayed(
+        counter_labels: &CounterLabels,
+        histogram_labels: &HistogramLabels,
+        weight: u64,
+        elapsed: Duration,
+    ) {
+        #[cfg(metrics)]
+        MetricsTracker::replayed(elapsed).finish_weighted(counter_labels, histogram_labels, weight);
+        #[cfg(opentelemetry)]
+        OpenTelemetryTracker::replayed(elapsed).finish_weighted(
+            counter_labels,
+            histogram_labels,
+            weight,
+        );
+        #[cfg(prometheus)]
+        PrometheusTracker::replayed(elapsed).finish_weighted(
+            counter_labels,
+            histogram_labels,
+            weight,
+        );
+        #[cfg(prometheus_client)]
+        PrometheusClientTracker::replayed(elapsed).finish_weighted(
+            counter_labels,
+            histogram_labels,
+            weight,
+        );
+        record_crate_calls(counter_labels, weight);
+    }
+
+    /// Record `count` calls that were only counted in another process, for merging snapshots
+    #[cfg(snapshots)]
+    #[allow(unused_variables)]
+    pub(crate) fn count_replayed(counter_labels: &CounterLabels, count: u64) {
+        for _ in 0..count {
+            #[cfg(metrics)]
+            MetricsTracker::count(counter_labels);
+            #[cfg(opentelemetry)]
+            OpenTelemetryTracker::count(counter_labels);
+            #[cfg(prometheus)]
+            PrometheusTracker::count(counter_labels);
+            #[cfg(prometheus_client)]
+            PrometheusClientTracker::count(counter_labels);
+        }
+        record_crate_calls(counter_labels, count);
     }
 
-    #[cfg(debug_assertions)]
+    /// Count the call in this library, even if it is connected to a plugin host
     #[allow(unused_variables)]
-    fn intitialize_metrics(function_descriptions: &[FunctionDescription]) {
+    pub(crate) fn count_local(counter_labels: &CounterLabels) {
+        if is_paused() {
+            return;
+        }
+
         #[cfg(metrics)]
-        MetricsTracker::intitialize_metrics(function_descriptions);
+        MetricsTracker::count(counter_labels);
         #[cfg(opentelemetry)]
-        OpenTelemetryTracker::intitialize_metrics(function_descriptions);
+        OpenTelemetryTracker::count(counter_labels);
         #[cfg(prometheus)]
-        PrometheusTracker::intitialize_metrics(function_descriptions);
+        PrometheusTracker::count(counter_labels);
         #[cfg(prometheus_client)]
-        PrometheusClientTracker::intitialize_metrics(function_descriptions);
+        PrometheusClientTracker::count(counter_labels);
+        record_crate_calls(counter_labels, 1);
+        #[cfg(snapshots)]
+        crate::snapshot::record(counter_labels, None, 1);
+        #[cfg(span_traces)]
+        crate::span_traces::capture(counter_labels);
+        #[cfg(stream)]
+        crate::stream::record(counter_labels, None, 1);
+        #[cfg(debug_metrics)]
+        log_recorded_call(counter_labels, None, 1);
+    }
+
+    /// Start recording the call in this library, even if it is connected to a plugin host
+    #[allow(unused_variables)]
+    pub(crate) fn start_local(gauge_labels: Option<&GaugeLabels>) -> Self {
+        let recording = !is_paused();
+        let gauge_labels = if recording { gauge_labels } else { None };
+
+        Self {
+            recording,
+            weight: 1,
+            #[cfg(any(summaries, wide_events, span_events, snapshots, stream))]
+            start: Timestamp::now(),
+            #[cfg(span_events)]
+            span_events: false,
+            max_concurrency: gauge_labels
+                .filter(|gauge_labels| gauge_labels.max_concurrency)
+                .map(crate::concurrency::start),
+            #[cfg(feature = "plugin")]
+            host: None,
+            #[cfg(metrics)]
+            metrics_tracker: MetricsTracker::start(gauge_labels),
+            #[cfg(opentelemetry)]
+            opentelemetry_tracker: OpenTelemetryTracker::start(gauge_labels),
+            #[cfg(prometheus)]
+            prometheus_tracker: PrometheusTracker::start(gauge_labels),
+            #[cfg(prometheus_client)]
+            prometheus_client_tracker: PrometheusClientTracker::start(gauge_labels),
+        }
+    }
+
+    /// Record the size of the value returned by a function with the `track_size` argument.
+    ///
+    /// Sampled calls are only recorded once, like their latency. The sizes are not recorded
+    /// if this library is a plugin whose calls are recorded by the host.
+    pub fn record_size(&self, histogram_labels: &HistogramLabels, size: usize) {
+        #[cfg(feature = "plugin")]
+        if self.host.is_some() {
+            return;
+        }
+
+        if self.recording && self.weight > 0 {
+            record_response_size(histogram_labels, size);
+        }
+    }
+
+    /// Attach events to the current span if the call is slow or fails, for functions with the `span_events` argument.
+    ///
+    /// This has no effect unless the `span-events` feature is enabled.
+    #[allow(unused_mut)]
+    pub fn with_span_events(mut self) -> Self {
+        #[cfg(span_events)]
+        {
+            self.span_events = true;
+        }
+        self
+    }
+
+    /// Start recording a call to a function whose calls are sampled or skipped.
+    ///
+    /// The call is counted as `weight` calls, or is not recorded at all if the weight is 0.
+    pub fn start_sampled(gauge_labels: Option<&GaugeLabels>, weight: u64) -> Self {
+        if weight == 0 {
+            let mut tracker = Self::start_local(None);
+            tracker.recording = false;
+            return tracker;
+        }
+
+        let mut tracker = Self::start(gauge_labels);
+        tracker.weight = weight;
+        tracker
+    }
+}
+
+/// Finishes the call when the instrumented function returns, or records it as an error
+/// with the `panic="true"` label if the function panics.
+///
+/// The labels for the panic are only created if the function panics.
+pub struct PanicGuard<F: FnOnce() -> (CounterLabels, HistogramLabels)> {
+    tracker: Option<AutometricsTracker>,
+    panic_labels: Option<F>,
+}
+
+impl<F: FnOnce() -> (CounterLabels, HistogramLabels)> PanicGuard<F> {
+    pub fn new(tracker: AutometricsTracker, panic_labels: F) -> Self {
+        Self {
+            tracker: Some(tracker),
+            panic_labels: Some(panic_labels),
+        }
+    }
+
+    /// Record the size of the value returned by the function, see [`AutometricsTracker::record_size`].
+    pub fn record_size(&self, histogram_labels: &HistogramLabels, size: usize) {
+        if let Some(tracker) = &self.tracker {
+            tracker.record_size(histogram_labels, size);
+        }
+    }
+
+    /// Record the call after the function returned.
+    pub fn finish(mut self, counter_labels: &CounterLabels, histogram_labels: &HistogramLabels) {
+        if let Some(tracker) = self.tracker.take() {
+            tracker.finish(counter_labels, histogram_labels);
+        }
+    }
+}
+
+impl<F: FnOnce() -> (CounterLabels, HistogramLabels)> Drop for PanicGuard<F> {
+    fn drop(&mut self) {
+        // The guard is also dropped without being finished if an async function is cancelled,
+        // which is not recorded
+        if !std::thread::panicking() {
+            return;
+        }
+        if let (Some(tracker), Some(panic_labels)) = (self.tracker.take(), self.panic_labels.take())
+        {
+            let (counter_labels, histogram_labels) = panic_labels();
+            tracker.finish(&counter_labels.with_panic(), &histogram_labels);
+        }
+    }
+}
+
+/// Counts the call when a function with the `count_only` argument returns, or counts it
+/// as an error with the `panic="true"` label if the function panics.
+///
+/// The labels for the panic are only created if the function panics.
+pub struct CountGuard<F: FnOnce() -> CounterLabels> {
+    panic_labels: Option<F>,
+}
+
+impl<F: FnOnce() -> CounterLabels> CountGuard<F> {
+    pub fn new(panic_labels: F) -> Self {
+        Self {
+            panic_labels: Some(panic_labels),
+        }
+    }
+
+    /// Count the call after the function returned.
+    ///
+    /// The guard is forgotten rather than dropped, so a call that returns does not check for a panic.
+    pub fn finish(mut self, counter_labels: &CounterLabels) {
+        self.panic_labels = None;
+        std::mem::forget(self);
+        AutometricsTracker::count(counter_labels);
+    }
+}
+
+impl<F: FnOnce() -> CounterLabels> Drop for CountGuard<F> {
+    fn drop(&mut self) {
+        // The guard is also dropped without being finished if an async function is cancelled,
+        // which is not counted
+        if !std::thread::panicking() {
+            return;
+        }
+        if let Some(panic_labels) = self.panic_labels.take() {
+            AutometricsTracker::count(&panic_labels().with_panic());
+        }
     }
 }
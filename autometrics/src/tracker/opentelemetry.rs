@@ -1,100 +1,326 @@
-#[cfg(debug_assertions)]
-use crate::__private::FunctionDescription;
-use crate::labels::{BuildInfoLabels, CounterLabels, GaugeLabels, HistogramLabels, Label};
-use crate::{constants::*, tracker::TrackMetrics};
-use once_cell::sync::Lazy;
-use opentelemetry::metrics::{Counter, Histogram, UpDownCounter};
-use opentelemetry::{global, KeyValue};
-use std::{sync::Once, time::Instant};
+use crate::constants::*;
+#[cfg(feature = "rdkafka")]
+use crate::labels::ConsumerLagLabels;
+use crate::labels::{
+    global_labels, label_key, BuildInfoLabels, CardinalityWarningLabels, CounterLabels,
+    CrateCallsLabels, FunctionRenamedLabels, GaugeLabels, HistogramLabels, Label,
+    LockRecoveryLabels,
+};
+use crate::poison;
+use crate::settings::{get_settings, ConcurrencyInstrument};
+use crate::tracker::{build_age_seconds, Timestamp, TrackMetrics};
+use once_cell::sync::{Lazy, OnceCell};
+use otel::metrics::{Counter, Gauge, Histogram, ObservableGauge, UpDownCounter};
+use otel::{global, KeyValue};
+use std::collections::HashMap;
+use std::sync::{Mutex, Once};
+
+#[cfg(opentelemetry_0_24)]
+use opentelemetry as otel;
+#[cfg(opentelemetry_0_33)]
+use opentelemetry_0_33 as otel;
 
 static SET_BUILD_INFO: Once = Once::new();
 const METER_NAME: &str = "autometrics";
 static COUNTER: Lazy<Counter<u64>> = Lazy::new(|| {
     global::meter(METER_NAME)
-        .u64_counter(COUNTER_NAME)
+        .u64_counter(metric_name(COUNTER_NAME))
         .with_description(COUNTER_DESCRIPTION)
-        .init()
+        .build()
 });
 static HISTOGRAM: Lazy<Histogram<f64>> = Lazy::new(|| {
-    // Note that the unit needs to be written as "s" rather than "seconds"
+    // Note that the unit needs to be written as "s" (or "ms") rather than "seconds"
     // or it will not be included in the metric name
     // https://github.com/open-telemetry/opentelemetry-rust/issues/1173
     global::meter(METER_NAME)
-        .f64_histogram(HISTOGRAM_NAME)
-        .with_unit("s")
+        .f64_histogram(get_settings().otel_histogram_name())
+        .with_unit(get_settings().duration_unit.otel_unit())
         .with_description(HISTOGRAM_DESCRIPTION)
-        .init()
+        .build()
+});
+static HISTOGRAM_VIEWS: Lazy<Vec<Histogram<f64>>> = Lazy::new(|| {
+    get_settings()
+        .histogram_views
+        .iter()
+        .map(|view| {
+            global::meter(METER_NAME)
+                .f64_histogram(view.name.clone())
+                .with_unit(get_settings().duration_unit.otel_unit())
+                .with_description(HISTOGRAM_DESCRIPTION)
+                .build()
+        })
+        .collect()
+});
+static RESPONSE_SIZE: Lazy<Histogram<u64>> = Lazy::new(|| {
+    global::meter(METER_NAME)
+        .u64_histogram(metric_name(RESPONSE_SIZE_NAME))
+        .with_unit("By")
+        .with_description(RESPONSE_SIZE_DESCRIPTION)
+        .build()
 });
+static BUILD_AGE: OnceCell<ObservableGauge<f64>> = OnceCell::new();
+static CALL_EDGES: OnceCell<ObservableGauge<u64>> = OnceCell::new();
 static GAUGE: Lazy<UpDownCounter<i64>> = Lazy::new(|| {
     global::meter(METER_NAME)
-        .i64_up_down_counter(GAUGE_NAME)
+        .i64_up_down_counter(metric_name(GAUGE_NAME))
+        .with_description(GAUGE_DESCRIPTION)
+        .build()
+});
+/// The number of concurrent calls, if it is recorded with [`ConcurrencyInstrument::Gauge`]
+static CONCURRENCY_GAUGE: Lazy<Gauge<i64>> = Lazy::new(|| {
+    global::meter(METER_NAME)
+        .i64_gauge(metric_name(GAUGE_NAME))
         .with_description(GAUGE_DESCRIPTION)
-        .init()
+        .build()
+});
+/// The current number of concurrent calls per function, which the synchronous gauge needs to record absolute values
+static CONCURRENT_CALLS: Lazy<Mutex<HashMap<ConcurrencyKey, i64>>> = Lazy::new(Default::default);
+static MAX_CONCURRENCY: Lazy<Gauge<i64>> = Lazy::new(|| {
+    global::meter(METER_NAME)
+        .i64_gauge(metric_name(MAX_CONCURRENCY_NAME))
+        .with_description(MAX_CONCURRENCY_DESCRIPTION)
+        .build()
+});
+static CARDINALITY_WARNINGS: Lazy<Counter<u64>> = Lazy::new(|| {
+    global::meter(METER_NAME)
+        .u64_counter(metric_name(CARDINALITY_WARNINGS_NAME))
+        .with_description(CARDINALITY_WARNINGS_DESCRIPTION)
+        .build()
+});
+static LOCK_RECOVERIES: Lazy<Counter<u64>> = Lazy::new(|| {
+    global::meter(METER_NAME)
+        .u64_counter(metric_name(LOCK_RECOVERIES_NAME))
+        .with_description(LOCK_RECOVERIES_DESCRIPTION)
+        .build()
+});
+static CRATE_CALLS: Lazy<Counter<u64>> = Lazy::new(|| {
+    global::meter(METER_NAME)
+        .u64_counter(metric_name(CRATE_CALLS_NAME))
+        .with_description(CRATE_CALLS_DESCRIPTION)
+        .build()
 });
+static FUNCTION_RENAMED: Lazy<Gauge<i64>> = Lazy::new(|| {
+    global::meter(METER_NAME)
+        .i64_gauge(metric_name(FUNCTION_RENAMED_NAME))
+        .with_description(FUNCTION_RENAMED_DESCRIPTION)
+        .build()
+});
+
+#[cfg(feature = "rdkafka")]
+static CONSUMER_LAG: Lazy<Gauge<i64>> = Lazy::new(|| {
+    global::meter(METER_NAME)
+        .i64_gauge(metric_name(CONSUMER_LAG_NAME))
+        .with_description(CONSUMER_LAG_DESCRIPTION)
+        .build()
+});
+
+/// The function, module, service name, and task name of the concurrent calls
+type ConcurrencyKey = (
+    &'static str,
+    &'static str,
+    &'static str,
+    Option<&'static str>,
+);
 
 /// Tracks the number of function calls, concurrent calls, and latency
 pub struct OpenTelemetryTracker {
-    gauge_labels: Option<Vec<KeyValue>>,
-    start: Instant,
+    gauge_labels: Option<(ConcurrencyKey, Vec<KeyValue>)>,
+    start: Timestamp,
+}
+
+#[cfg(replayed_calls)]
+impl OpenTelemetryTracker {
+    /// Replay a call that was measured in another process, without counting it as a concurrent call
+    pub(crate) fn replayed(elapsed: std::time::Duration) -> Self {
+        Self {
+            gauge_labels: None,
+            start: Timestamp::Elapsed(elapsed),
+        }
+    }
 }
 
 impl TrackMetrics for OpenTelemetryTracker {
     fn start(gauge_labels: Option<&GaugeLabels>) -> Self {
         let gauge_labels = if let Some(gauge_labels) = gauge_labels {
+            let key = (
+                gauge_labels.function,
+                gauge_labels.module,
+                gauge_labels.service_name,
+                gauge_labels.task,
+            );
             let gauge_labels = to_key_values(gauge_labels.to_array());
             // Increase the number of concurrent requests
-            GAUGE.add(1, &gauge_labels);
-            Some(gauge_labels)
+            add_concurrent_calls(key, &gauge_labels, 1);
+            Some((key, gauge_labels))
         } else {
             None
         };
 
         Self {
             gauge_labels,
-            start: Instant::now(),
+            start: Timestamp::now(),
         }
     }
 
-    fn finish<'a>(self, counter_labels: &CounterLabels, histogram_labels: &HistogramLabels) {
-        let duration = self.start.elapsed().as_secs_f64();
+    fn finish_weighted(
+        self,
+        counter_labels: &CounterLabels,
+        histogram_labels: &HistogramLabels,
+        weight: u64,
+    ) {
+        let duration = get_settings().duration_unit.convert(self.start.elapsed());
 
         // Track the function calls
         let counter_labels = to_key_values(counter_labels.to_vec());
-        COUNTER.add(1, &counter_labels);
+        COUNTER.add(weight, &counter_labels);
 
         // Track the latency
+        // (the buckets are configured per instrument with views when the meter provider is built,
+        // so the buckets set with the `buckets` argument of the macro are only used by the other backends)
         let histogram_labels = to_key_values(histogram_labels.to_vec());
         HISTOGRAM.record(duration, &histogram_labels);
+        for histogram in HISTOGRAM_VIEWS.iter() {
+            histogram.record(duration, &histogram_labels);
+        }
 
         // Decrease the number of concurrent requests
-        if let Some(gauge_labels) = self.gauge_labels {
-            GAUGE.add(-1, &gauge_labels);
+        if let Some((key, gauge_labels)) = self.gauge_labels {
+            add_concurrent_calls(key, &gauge_labels, -1);
         }
     }
 
+    fn count(counter_labels: &CounterLabels) {
+        COUNTER.add(1, &to_key_values(counter_labels.to_vec()));
+    }
+
+    #[cfg(feature = "rdkafka")]
+    fn set_consumer_lag(consumer_lag_labels: &ConsumerLagLabels, lag: i64) {
+        CONSUMER_LAG.record(lag, &to_key_values(consumer_lag_labels.to_vec()));
+    }
+
     fn set_build_info(build_info_labels: &BuildInfoLabels) {
         SET_BUILD_INFO.call_once(|| {
             let build_info_labels = to_key_values(build_info_labels.to_vec());
             let build_info = global::meter(METER_NAME)
-                .f64_up_down_counter(BUILD_INFO_NAME)
+                .f64_up_down_counter(metric_name(BUILD_INFO_NAME))
                 .with_description(BUILD_INFO_DESCRIPTION)
-                .init();
+                .build();
             build_info.add(1.0, &build_info_labels);
+
+            // The build age is computed whenever the metrics are collected
+            BUILD_AGE.get_or_init(|| {
+                global::meter(METER_NAME)
+                    .f64_observable_gauge(metric_name(BUILD_AGE_NAME))
+                    .with_unit("s")
+                    .with_description(BUILD_AGE_DESCRIPTION)
+                    .with_callback(|observer| {
+                        if let Some(build_age) = build_age_seconds() {
+                            observer.observe(build_age, &to_key_values(global_labels()));
+                        }
+                    })
+                    .build()
+            });
         });
     }
 
-    #[cfg(debug_assertions)]
-    fn intitialize_metrics(function_descriptions: &[FunctionDescription]) {
-        for function in function_descriptions {
-            let labels = &to_key_values(CounterLabels::from(function).to_vec());
+    fn intitialize_metrics(counter_labels: &[CounterLabels]) {
+        for counter_labels in counter_labels {
+            let labels = &to_key_values(counter_labels.to_vec());
             COUNTER.add(0, labels);
         }
     }
 }
 
+/// Change the number of concurrent calls with the configured instrument
+fn add_concurrent_calls(key: ConcurrencyKey, gauge_labels: &[KeyValue], delta: i64) {
+    match get_settings().concurrency_instrument {
+        ConcurrencyInstrument::UpDownCounter => GAUGE.add(delta, gauge_labels),
+        ConcurrencyInstrument::Gauge => {
+            // Record the value while holding the lock, so that concurrent calls cannot record an outdated value last
+            let mut calls = poison::lock(&CONCURRENT_CALLS, "otel_concurrent_calls");
+            let count = calls.entry(key).or_insert(0);
+            *count += delta;
+            CONCURRENCY_GAUGE.record(*count, gauge_labels);
+        }
+    }
+}
+
+pub(crate) fn record_cardinality_warning(labels: &CardinalityWarningLabels) {
+    CARDINALITY_WARNINGS.add(1, &to_key_values(labels.to_vec()));
+}
+
+pub(crate) fn record_crate_calls(labels: &CrateCallsLabels, weight: u64) {
+    CRATE_CALLS.add(weight, &to_key_values(labels.to_vec()));
+}
+
+pub(crate) fn record_lock_recovery(labels: &LockRecoveryLabels) {
+    LOCK_RECOVERIES.add(1, &to_key_values(labels.to_vec()));
+}
+
+pub(crate) fn set_function_renamed(labels: &FunctionRenamedLabels) {
+    FUNCTION_RENAMED.record(1, &to_key_values(labels.to_vec()));
+}
+
+pub(crate) fn record_response_size(histogram_labels: &HistogramLabels, size: usize) {
+    RESPONSE_SIZE.record(size as u64, &to_key_values(histogram_labels.to_vec()));
+}
+
+pub(crate) fn set_max_concurrency(gauge_labels: &GaugeLabels, max: i64) {
+    MAX_CONCURRENCY.record(max, &to_key_values(gauge_labels.to_array()));
+}
+
+/// Register the observable gauge for the `function.call.edges.info` metric,
+/// which reports the call edges that are known whenever the metrics are collected
+pub(crate) fn register_call_edges() {
+    CALL_EDGES.get_or_init(|| {
+        global::meter(METER_NAME)
+            .u64_observable_gauge(metric_name(CALL_EDGES_NAME))
+            .with_description(CALL_EDGES_DESCRIPTION)
+            .with_callback(|observer| {
+                for edge in crate::call_edges::edges() {
+                    observer.observe(1, &to_key_values(edge.to_vec()));
+                }
+            })
+            .build()
+    });
+}
+
+/// The name of the metric with the prefix configured in the settings
+fn metric_name(name: &str) -> String {
+    get_settings().otel_metric_name(name)
+}
+
 fn to_key_values(labels: impl IntoIterator<Item = Label>) -> Vec<KeyValue> {
     labels
         .into_iter()
-        .map(|(k, v)| KeyValue::new(k, v))
+        .map(|(k, v)| KeyValue::new(label_key(k), v))
         .collect()
 }
+
+/// Compatibility shim for `opentelemetry` 0.24, where the instrument builders
+/// are finished with `init` instead of `build`
+#[cfg(opentelemetry_0_24)]
+trait BuildInstrument<T> {
+    fn build(self) -> T;
+}
+
+#[cfg(opentelemetry_0_24)]
+impl<'a, T> BuildInstrument<T> for otel::metrics::InstrumentBuilder<'a, T>
+where
+    T: TryFrom<Self, Error = otel::metrics::MetricsError>,
+{
+    fn build(self) -> T {
+        self.init()
+    }
+}
+
+#[cfg(opentelemetry_0_24)]
+impl<'a, I, M> BuildInstrument<I> for otel::metrics::AsyncInstrumentBuilder<'a, I, M>
+where
+    I: TryFrom<Self, Error = otel::metrics::MetricsError>,
+    I: otel::metrics::AsyncInstrument<M>,
+{
+    fn build(self) -> I {
+        self.init()
+    }
+}
@@ -1,74 +1,202 @@
-#[cfg(debug_assertions)]
-use crate::__private::FunctionDescription;
-use crate::labels::{BuildInfoLabels, CounterLabels, GaugeLabels, HistogramLabels, ResultLabel};
-use crate::{constants::*, settings::get_settings, tracker::TrackMetrics};
-use once_cell::sync::Lazy;
-use prometheus::core::{AtomicI64, GenericGauge};
+use crate::constants::*;
+#[cfg(feature = "rdkafka")]
+use crate::labels::ConsumerLagLabels;
+use crate::labels::{
+    global_labels, label_key, BuildInfoLabels, CardinalityWarningLabels, CounterLabels,
+    CrateCallsLabels, CustomLabels, FunctionRenamedLabels, GaugeLabels, HistogramLabels,
+    LockRecoveryLabels, ResultLabel, CUSTOM_LABEL_SLOTS,
+};
+use crate::poison;
+use crate::settings::{get_settings, RESPONSE_SIZE_BUCKETS};
+use crate::tracker::{build_age_seconds, Timestamp, TrackMetrics};
+use once_cell::sync::{Lazy, OnceCell};
+use prometheus::core::{AtomicI64, Collector, Desc, GenericGauge, MetricVec, MetricVecBuilder};
+use prometheus::proto::MetricFamily;
 use prometheus::{
-    histogram_opts, register_histogram_vec_with_registry, register_int_counter_vec_with_registry,
-    register_int_gauge_vec_with_registry, HistogramVec, IntCounterVec, IntGaugeVec,
+    histogram_opts, opts, register_histogram_vec_with_registry,
+    register_int_counter_vec_with_registry, register_int_gauge_vec_with_registry, Gauge, Histogram,
+    HistogramVec, IntCounter, IntCounterVec, IntGaugeVec,
+};
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU64, Ordering},
+    sync::{Once, RwLock},
 };
-use std::{sync::Once, time::Instant};
 
 static SET_BUILD_INFO: Once = Once::new();
 
+// The keys of the `function` and `module` labels can be renamed in the settings
+static COUNTER_KEYS: Lazy<[&str; 14]> = Lazy::new(|| {
+    [
+        label_key(FUNCTION_KEY),
+        label_key(MODULE_KEY),
+        SERVICE_NAME_KEY_PROMETHEUS,
+        CALLER_FUNCTION_PROMETHEUS,
+        CALLER_MODULE_PROMETHEUS,
+        RESULT_KEY,
+        OK_KEY,
+        ERROR_KEY,
+        OBJECTIVE_NAME_PROMETHEUS,
+        OBJECTIVE_PERCENTILE_PROMETHEUS,
+        RECURSIVE_KEY,
+        ERROR_TYPE_PROMETHEUS,
+        TYPE_PARAMS_KEY,
+        PANIC_KEY,
+    ]
+});
+#[cfg(not(tokio_console))]
+static GAUGE_KEYS: Lazy<[&str; 3]> = Lazy::new(|| {
+    [
+        label_key(FUNCTION_KEY),
+        label_key(MODULE_KEY),
+        SERVICE_NAME_KEY_PROMETHEUS,
+    ]
+});
+#[cfg(tokio_console)]
+static GAUGE_KEYS: Lazy<[&str; 4]> = Lazy::new(|| {
+    [
+        label_key(FUNCTION_KEY),
+        label_key(MODULE_KEY),
+        SERVICE_NAME_KEY_PROMETHEUS,
+        TASK_KEY,
+    ]
+});
+static HISTOGRAM_KEYS: Lazy<[&str; 9]> = Lazy::new(|| {
+    [
+        label_key(FUNCTION_KEY),
+        label_key(MODULE_KEY),
+        SERVICE_NAME_KEY_PROMETHEUS,
+        OBJECTIVE_NAME_PROMETHEUS,
+        OBJECTIVE_PERCENTILE_PROMETHEUS,
+        OBJECTIVE_LATENCY_THRESHOLD_PROMETHEUS,
+        TYPE_PARAMS_KEY,
+        COLD_KEY,
+        RESULT_KEY,
+    ]
+});
+
 static COUNTER: Lazy<IntCounterVec> = Lazy::new(|| {
     register_int_counter_vec_with_registry!(
-        COUNTER_NAME_PROMETHEUS,
-        COUNTER_DESCRIPTION,
-        &[
-            FUNCTION_KEY,
-            MODULE_KEY,
-            SERVICE_NAME_KEY_PROMETHEUS,
-            CALLER_FUNCTION_PROMETHEUS,
-            CALLER_MODULE_PROMETHEUS,
-            RESULT_KEY,
-            OK_KEY,
-            ERROR_KEY,
-            OBJECTIVE_NAME_PROMETHEUS,
-            OBJECTIVE_PERCENTILE_PROMETHEUS,
-        ],
+        opts!(metric_name(COUNTER_NAME_PROMETHEUS), COUNTER_DESCRIPTION)
+            .const_labels(const_labels()),
+        &*COUNTER_KEYS,
         get_settings().prometheus_registry.clone()
     )
     .expect("Failed to register function_calls_count_total counter")
 });
 static HISTOGRAM: Lazy<HistogramVec> = Lazy::new(|| {
     let opts = histogram_opts!(
-        HISTOGRAM_NAME_PROMETHEUS,
+        get_settings().prometheus_histogram_name(),
         HISTOGRAM_DESCRIPTION,
         // The Prometheus crate uses different histogram buckets by default
         // (and these are configured when creating a histogram rather than
         // when configuring the registry or exporter, like in the other crates)
         // so we need to pass these in here
         get_settings().histogram_buckets.clone()
-    );
+    )
+    .const_labels(const_labels());
     register_histogram_vec_with_registry!(
         opts,
-        &[
-            FUNCTION_KEY,
-            MODULE_KEY,
-            SERVICE_NAME_KEY_PROMETHEUS,
-            OBJECTIVE_NAME_PROMETHEUS,
-            OBJECTIVE_PERCENTILE_PROMETHEUS,
-            OBJECTIVE_LATENCY_THRESHOLD_PROMETHEUS
-        ],
+        &*HISTOGRAM_KEYS,
         get_settings().prometheus_registry.clone()
     )
     .expect("Failed to register function_calls_duration histogram")
 });
+static HISTOGRAM_VIEWS: Lazy<Vec<HistogramVec>> = Lazy::new(|| {
+    get_settings()
+        .histogram_views
+        .iter()
+        .map(|view| {
+            let opts = histogram_opts!(
+                view.prometheus_name.as_str(),
+                HISTOGRAM_DESCRIPTION,
+                view.buckets.clone()
+            )
+            .const_labels(const_labels());
+            register_histogram_vec_with_registry!(
+                opts,
+                &*HISTOGRAM_KEYS,
+                get_settings().prometheus_registry.clone()
+            )
+            .expect("Failed to register function_calls_duration histogram view")
+        })
+        .collect()
+});
+static RESPONSE_SIZE: Lazy<HistogramVec> = Lazy::new(|| {
+    let opts = histogram_opts!(
+        metric_name(RESPONSE_SIZE_NAME_PROMETHEUS),
+        RESPONSE_SIZE_DESCRIPTION,
+        RESPONSE_SIZE_BUCKETS.to_vec()
+    )
+    .const_labels(const_labels());
+    register_histogram_vec_with_registry!(
+        opts,
+        &*HISTOGRAM_KEYS,
+        get_settings().prometheus_registry.clone()
+    )
+    .expect("Failed to register function_response_size histogram")
+});
+type CustomBucketsHistogram = (&'static [f64], HistogramVec);
+
+/// The histograms of the functions that set their own buckets with the `autometrics` macro.
+///
+/// The Prometheus crate configures the buckets per histogram, so there is one for each set of buckets.
+/// These are not registered individually (because they share the same name as the main histogram)
+/// but are gathered into the same metric family by the [`SharedFamiliesCollector`].
+static CUSTOM_BUCKETS_HISTOGRAMS: Lazy<RwLock<Vec<CustomBucketsHistogram>>> = Lazy::new(|| {
+    Lazy::force(&SHARED_FAMILIES_COLLECTOR);
+    RwLock::default()
+});
+type CustomLabelKeys = [Option<&'static str>; CUSTOM_LABEL_SLOTS];
+type CustomLabelsCounter = (CustomLabelKeys, IntCounterVec);
+type CustomLabelsHistogram = ((CustomLabelKeys, Option<&'static [f64]>), HistogramVec);
+
+/// The counters and histograms of the functions that add their own labels with the `label`
+/// argument of the `autometrics` macro (or the `previous_function` label of the `alias` argument).
+///
+/// The Prometheus crate needs all of the label keys when creating a metric, so there is one for each
+/// set of keys (and, for the histograms, each set of buckets). Like the histograms with custom buckets,
+/// these are gathered into the same metric families as the main metrics by the [`SharedFamiliesCollector`].
+static CUSTOM_LABELS_COUNTERS: Lazy<RwLock<Vec<CustomLabelsCounter>>> = Lazy::new(|| {
+    Lazy::force(&SHARED_FAMILIES_COLLECTOR);
+    RwLock::default()
+});
+static CUSTOM_LABELS_HISTOGRAMS: Lazy<RwLock<Vec<CustomLabelsHistogram>>> = Lazy::new(|| {
+    Lazy::force(&SHARED_FAMILIES_COLLECTOR);
+    RwLock::default()
+});
+
+/// The registry identifies a collector by its descriptions, so the collector of the metrics
+/// that share the main metrics' names (which has none) can only be registered once
+static SHARED_FAMILIES_COLLECTOR: Lazy<()> = Lazy::new(|| {
+    get_settings()
+        .prometheus_registry
+        .register(Box::new(SharedFamiliesCollector))
+        .expect("Failed to register the metrics with custom buckets and labels");
+});
 static GAUGE: Lazy<IntGaugeVec> = Lazy::new(|| {
     register_int_gauge_vec_with_registry!(
-        GAUGE_NAME_PROMETHEUS,
-        GAUGE_DESCRIPTION,
-        &[FUNCTION_KEY, MODULE_KEY, SERVICE_NAME_KEY_PROMETHEUS],
+        opts!(metric_name(GAUGE_NAME_PROMETHEUS), GAUGE_DESCRIPTION).const_labels(const_labels()),
+        &*GAUGE_KEYS,
         get_settings().prometheus_registry.clone()
     )
     .expect("Failed to register function_calls_concurrent gauge")
 });
+static MAX_CONCURRENCY: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec_with_registry!(
+        opts!(
+            metric_name(MAX_CONCURRENCY_NAME_PROMETHEUS),
+            MAX_CONCURRENCY_DESCRIPTION
+        )
+        .const_labels(const_labels()),
+        &*GAUGE_KEYS,
+        get_settings().prometheus_registry.clone()
+    )
+    .expect("Failed to register function_calls_concurrent_max gauge")
+});
 static BUILD_INFO: Lazy<IntGaugeVec> = Lazy::new(|| {
     register_int_gauge_vec_with_registry!(
-        BUILD_INFO_NAME,
-        BUILD_INFO_DESCRIPTION,
+        opts!(metric_name(BUILD_INFO_NAME), BUILD_INFO_DESCRIPTION).const_labels(const_labels()),
         &[
             COMMIT_KEY,
             VERSION_KEY,
@@ -77,25 +205,636 @@ static BUILD_INFO: Lazy<IntGaugeVec> = Lazy::new(|| {
             REPO_URL_KEY_PROMETHEUS,
             REPO_PROVIDER_KEY_PROMETHEUS,
             AUTOMETRICS_VERSION_KEY_PROMETHEUS,
+            BUILD_TIMESTAMP_KEY_PROMETHEUS,
+            RUSTC_VERSION_KEY_PROMETHEUS,
+            TARGET_KEY,
+            RUNTIME_KEY,
+            OS_KEY,
         ],
         get_settings().prometheus_registry.clone()
     )
     .expect("Failed to register build_info counter")
 });
+static CARDINALITY_WARNINGS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec_with_registry!(
+        opts!(
+            metric_name(CARDINALITY_WARNINGS_NAME_PROMETHEUS),
+            CARDINALITY_WARNINGS_DESCRIPTION
+        )
+        .const_labels(const_labels()),
+        &[
+            label_key(FUNCTION_KEY),
+            label_key(MODULE_KEY),
+            LABEL_KEY,
+            SERVICE_NAME_KEY_PROMETHEUS
+        ],
+        get_settings().prometheus_registry.clone()
+    )
+    .expect("Failed to register autometrics_cardinality_warnings_total counter")
+});
+static CRATE_CALLS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec_with_registry!(
+        opts!(
+            metric_name(CRATE_CALLS_NAME_PROMETHEUS),
+            CRATE_CALLS_DESCRIPTION
+        )
+        .const_labels(const_labels()),
+        &[CRATE_KEY, RESULT_KEY, SERVICE_NAME_KEY_PROMETHEUS],
+        get_settings().prometheus_registry.clone()
+    )
+    .expect("Failed to register crate_calls_total counter")
+});
+static LOCK_RECOVERIES: Lazy<IntCounterVec> = Lazy::new(|| {
+    register_int_counter_vec_with_registry!(
+        opts!(
+            metric_name(LOCK_RECOVERIES_NAME_PROMETHEUS),
+            LOCK_RECOVERIES_DESCRIPTION
+        )
+        .const_labels(const_labels()),
+        &[LOCK_KEY, SERVICE_NAME_KEY_PROMETHEUS],
+        get_settings().prometheus_registry.clone()
+    )
+    .expect("Failed to register autometrics_lock_recoveries_total counter")
+});
+static FUNCTION_RENAMED: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec_with_registry!(
+        opts!(
+            metric_name(FUNCTION_RENAMED_NAME_PROMETHEUS),
+            FUNCTION_RENAMED_DESCRIPTION
+        )
+        .const_labels(const_labels()),
+        &[
+            label_key(FUNCTION_KEY),
+            label_key(MODULE_KEY),
+            PREVIOUS_FUNCTION_KEY,
+            SERVICE_NAME_KEY_PROMETHEUS
+        ],
+        get_settings().prometheus_registry.clone()
+    )
+    .expect("Failed to register function_renamed_info gauge")
+});
+#[cfg(feature = "rdkafka")]
+static CONSUMER_LAG: Lazy<IntGaugeVec> = Lazy::new(|| {
+    register_int_gauge_vec_with_registry!(
+        opts!(
+            metric_name(CONSUMER_LAG_NAME_PROMETHEUS),
+            CONSUMER_LAG_DESCRIPTION
+        )
+        .const_labels(const_labels()),
+        &[TOPIC_KEY, PARTITION_KEY, SERVICE_NAME_KEY_PROMETHEUS],
+        get_settings().prometheus_registry.clone()
+    )
+    .expect("Failed to register kafka_consumer_lag gauge")
+});
+
+/// The name of the metric with the prefix configured in the settings
+fn metric_name(name: &str) -> String {
+    get_settings().prometheus_metric_name(name)
+}
+
+/// The global labels are attached to the metrics as constant labels.
+///
+/// Prometheus label names cannot contain dots, so those are replaced with underscores.
+fn const_labels() -> HashMap<String, String> {
+    global_labels()
+        .map(|(key, value)| (key.replace('.', "_"), value.to_string()))
+        .collect()
+}
+
+/// Collector that computes the time elapsed since the build whenever the registry is gathered
+struct BuildAgeCollector {
+    gauge: Gauge,
+}
+
+impl BuildAgeCollector {
+    fn register() {
+        let collector = BuildAgeCollector {
+            gauge: Gauge::with_opts(
+                opts!(
+                    metric_name(BUILD_AGE_NAME_PROMETHEUS),
+                    BUILD_AGE_DESCRIPTION
+                )
+                .const_labels(const_labels()),
+            )
+            .expect("Failed to create build_age_seconds gauge"),
+        };
+        get_settings()
+            .prometheus_registry
+            .register(Box::new(collector))
+            .expect("Failed to register build_age_seconds gauge");
+    }
+}
+
+impl Collector for BuildAgeCollector {
+    fn desc(&self) -> Vec<&Desc> {
+        self.gauge.desc()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        match build_age_seconds() {
+            Some(build_age) => {
+                self.gauge.set(build_age);
+                self.gauge.collect()
+            }
+            None => Vec::new(),
+        }
+    }
+}
+
+pub(crate) fn record_cardinality_warning(labels: &CardinalityWarningLabels) {
+    CARDINALITY_WARNINGS
+        .with_label_values(&[
+            labels.function,
+            labels.module,
+            labels.label,
+            labels.service_name,
+        ])
+        .inc();
+}
+
+pub(crate) fn record_crate_calls(labels: &CrateCallsLabels, weight: u64) {
+    CRATE_CALLS
+        .with_label_values(&[labels.crate_name, labels.result, labels.service_name])
+        .inc_by(weight);
+}
+
+pub(crate) fn record_lock_recovery(labels: &LockRecoveryLabels) {
+    LOCK_RECOVERIES
+        .with_label_values(&[labels.lock, labels.service_name])
+        .inc();
+}
+
+pub(crate) fn set_function_renamed(labels: &FunctionRenamedLabels) {
+    FUNCTION_RENAMED
+        .with_label_values(&[
+            labels.function,
+            labels.module,
+            labels.previous_function,
+            labels.service_name,
+        ])
+        .set(1);
+}
+
+pub(crate) fn record_response_size(histogram_labels: &HistogramLabels, size: usize) {
+    RESPONSE_SIZE
+        .with_label_values(&histogram_labels_to_prometheus_array(histogram_labels))
+        .observe(size as f64);
+}
+
+pub(crate) fn set_max_concurrency(gauge_labels: &GaugeLabels, max: i64) {
+    MAX_CONCURRENCY
+        .with_label_values(&gauge_label_values(gauge_labels))
+        .set(max);
+}
+
+/// The values of the [`GAUGE_KEYS`], where the calls that are not made in a named task have an empty `task` label
+#[cfg(not(tokio_console))]
+fn gauge_label_values(gauge_labels: &GaugeLabels) -> [&'static str; 3] {
+    [
+        gauge_labels.function,
+        gauge_labels.module,
+        gauge_labels.service_name,
+    ]
+}
+
+/// The values of the [`GAUGE_KEYS`], where the calls that are not made in a named task have an empty `task` label
+#[cfg(tokio_console)]
+fn gauge_label_values(gauge_labels: &GaugeLabels) -> [&'static str; 4] {
+    [
+        gauge_labels.function,
+        gauge_labels.module,
+        gauge_labels.service_name,
+        gauge_labels.task.unwrap_or_default(),
+    ]
+}
+
+/// Register the collector for the `function_call_edges_info` metric
+pub(crate) fn register_call_edges() {
+    let collector = CallEdgesCollector {
+        gauge: IntGaugeVec::new(
+            opts!(
+                metric_name(CALL_EDGES_NAME_PROMETHEUS),
+                CALL_EDGES_DESCRIPTION
+            )
+            .const_labels(const_labels()),
+            &[
+                CALLER_FUNCTION_PROMETHEUS,
+                CALLER_MODULE_PROMETHEUS,
+                label_key(FUNCTION_KEY),
+                label_key(MODULE_KEY),
+                SERVICE_NAME_KEY_PROMETHEUS,
+            ],
+        )
+        .expect("Failed to create function_call_edges_info gauge"),
+    };
+    get_settings()
+        .prometheus_registry
+        .register(Box::new(collector))
+        .expect("Failed to register function_call_edges_info gauge");
+}
+
+/// Collector that sets a series for each of the call edges that are known when the registry is gathered
+struct CallEdgesCollector {
+    gauge: IntGaugeVec,
+}
+
+impl Collector for CallEdgesCollector {
+    fn desc(&self) -> Vec<&Desc> {
+        self.gauge.desc()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        // Remove the series of the edges that were evicted since the last collection
+        self.gauge.reset();
+        for edge in crate::call_edges::edges() {
+            self.gauge
+                .with_label_values(&[
+                    edge.caller_function,
+                    edge.caller_module,
+                    edge.function,
+                    edge.module,
+                    edge.service_name,
+                ])
+                .set(1);
+        }
+        self.gauge.collect()
+    }
+}
+
+/// Get or create the histogram for the given buckets
+fn custom_buckets_histogram(buckets: &'static [f64]) -> HistogramVec {
+    let histograms = poison::read(&CUSTOM_BUCKETS_HISTOGRAMS, "custom_buckets_histograms");
+    if let Some((_, histogram)) = histograms.iter().find(|(b, _)| *b == buckets) {
+        return histogram.clone();
+    }
+    drop(histograms);
+
+    let mut histograms = poison::write(&CUSTOM_BUCKETS_HISTOGRAMS, "custom_buckets_histograms");
+    if let Some((_, histogram)) = histograms.iter().find(|(b, _)| *b == buckets) {
+        return histogram.clone();
+    }
+    let opts = histogram_opts!(
+        get_settings().prometheus_histogram_name(),
+        HISTOGRAM_DESCRIPTION,
+        buckets.to_vec()
+    )
+    .const_labels(const_labels());
+    let histogram = HistogramVec::new(opts, &*HISTOGRAM_KEYS)
+        .expect("Failed to create function_calls_duration histogram with custom buckets");
+    histograms.push((buckets, histogram.clone()));
+    histogram
+}
+
+/// The keys of the metric followed by the keys of the custom labels
+fn custom_label_keys(keys: &[&'static str], custom_keys: CustomLabelKeys) -> Vec<&'static str> {
+    keys.iter()
+        .copied()
+        .chain(custom_keys.into_iter().flatten())
+        .collect()
+}
+
+/// The label values of the metric followed by the values of the custom labels
+fn custom_label_values(values: &[&'static str], custom_labels: &CustomLabels) -> Vec<&'static str> {
+    values
+        .iter()
+        .copied()
+        .chain(custom_labels.iter().map(|(_, value)| value))
+        .collect()
+}
+
+/// Find the metric for the given key in the list, or create and add it
+fn get_or_create<K: PartialEq, M: Clone>(
+    metrics: &RwLock<Vec<(K, M)>>,
+    key: K,
+    create: impl FnOnce() -> M,
+) -> M {
+    let existing = poison::read(metrics, "custom_labels_metrics");
+    if let Some((_, metric)) = existing.iter().find(|(k, _)| *k == key) {
+        return metric.clone();
+    }
+    drop(existing);
+
+    let mut metrics = poison::write(metrics, "custom_labels_metrics");
+    if let Some((_, metric)) = metrics.iter().find(|(k, _)| *k == key) {
+        return metric.clone();
+    }
+    let metric = create();
+    metrics.push((key, metric.clone()));
+    metric
+}
+
+/// Get or create the counter with the keys of the given custom labels
+fn custom_labels_counter(custom_labels: &CustomLabels) -> IntCounterVec {
+    let custom_keys = custom_labels.keys();
+    get_or_create(&CUSTOM_LABELS_COUNTERS, custom_keys, || {
+        IntCounterVec::new(
+            opts!(metric_name(COUNTER_NAME_PROMETHEUS), COUNTER_DESCRIPTION)
+                .const_labels(const_labels()),
+            &custom_label_keys(&*COUNTER_KEYS, custom_keys),
+        )
+        .expect("Failed to create function_calls_count_total counter with custom labels")
+    })
+}
+
+/// Get or create the histogram with the keys of the given custom labels and the given buckets
+fn custom_labels_histogram(
+    custom_labels: &CustomLabels,
+    buckets: Option<&'static [f64]>,
+) -> HistogramVec {
+    let custom_keys = custom_labels.keys();
+    get_or_create(&CUSTOM_LABELS_HISTOGRAMS, (custom_keys, buckets), || {
+        let buckets =
+            buckets.map_or_else(|| get_settings().histogram_buckets.clone(), <[f64]>::to_vec);
+        let opts = histogram_opts!(
+            get_settings().prometheus_histogram_name(),
+            HISTOGRAM_DESCRIPTION,
+            buckets
+        )
+        .const_labels(const_labels());
+        HistogramVec::new(opts, &custom_label_keys(&*HISTOGRAM_KEYS, custom_keys))
+            .expect("Failed to create function_calls_duration histogram with custom labels")
+    })
+}
+
+/// Collector that gathers the histograms with custom buckets and the counters and histograms with custom labels
+struct SharedFamiliesCollector;
+
+impl Collector for SharedFamiliesCollector {
+    fn desc(&self) -> Vec<&Desc> {
+        // The descriptions are the same as the main metrics', which are already registered
+        Vec::new()
+    }
+
+    fn collect(&self) -> Vec<MetricFamily> {
+        let mut families = Vec::new();
+        if let Some(histograms) = Lazy::get(&CUSTOM_BUCKETS_HISTOGRAMS) {
+            for (_, histogram) in poison::read(histograms, "custom_buckets_histograms").iter() {
+                families.extend(histogram.collect());
+            }
+        }
+        if let Some(counters) = Lazy::get(&CUSTOM_LABELS_COUNTERS) {
+            for (_, counter) in poison::read(counters, "custom_labels_metrics").iter() {
+                families.extend(counter.collect());
+            }
+        }
+        if let Some(histograms) = Lazy::get(&CUSTOM_LABELS_HISTOGRAMS) {
+            for (_, histogram) in poison::read(histograms, "custom_labels_metrics").iter() {
+                families.extend(histogram.collect());
+            }
+        }
+        families
+    }
+}
+
+/// Remove all of the series of the function with the given name from the metrics,
+/// returning the number of series that were removed
+pub(crate) fn remove_function(function: &str) -> usize {
+    // Metrics that have not been created yet do not have any series
+    let mut removed = 0;
+    if let Some(counter) = Lazy::get(&COUNTER) {
+        removed += remove_function_series(counter, &*COUNTER_KEYS, function);
+    }
+    if let Some(histogram) = Lazy::get(&HISTOGRAM) {
+        removed += remove_function_series(histogram, &*HISTOGRAM_KEYS, function);
+    }
+    if let Some(histogram) = Lazy::get(&RESPONSE_SIZE) {
+        removed += remove_function_series(histogram, &*HISTOGRAM_KEYS, function);
+    }
+    if let Some(histograms) = Lazy::get(&HISTOGRAM_VIEWS) {
+        for histogram in histograms {
+            removed += remove_function_series(histogram, &*HISTOGRAM_KEYS, function);
+        }
+    }
+    if let Some(histograms) = Lazy::get(&CUSTOM_BUCKETS_HISTOGRAMS) {
+        for (_, histogram) in poison::read(histograms, "custom_buckets_histograms").iter() {
+            removed += remove_function_series(histogram, &*HISTOGRAM_KEYS, function);
+        }
+    }
+    if let Some(counters) = Lazy::get(&CUSTOM_LABELS_COUNTERS) {
+        for (custom_keys, counter) in poison::read(counters, "custom_labels_metrics").iter() {
+            let keys = custom_label_keys(&*COUNTER_KEYS, *custom_keys);
+            removed += remove_function_series(counter, &keys, function);
+        }
+    }
+    if let Some(histograms) = Lazy::get(&CUSTOM_LABELS_HISTOGRAMS) {
+        for ((custom_keys, _), histogram) in
+            poison::read(histograms, "custom_labels_metrics").iter()
+        {
+            let keys = custom_label_keys(&*HISTOGRAM_KEYS, *custom_keys);
+            removed += remove_function_series(histogram, &keys, function);
+        }
+    }
+    if let Some(gauge) = Lazy::get(&GAUGE) {
+        removed += remove_function_series(gauge, &*GAUGE_KEYS, function);
+    }
+    if let Some(gauge) = Lazy::get(&MAX_CONCURRENCY) {
+        removed += remove_function_series(gauge, &*GAUGE_KEYS, function);
+    }
+    if let Some(gauge) = Lazy::get(&FUNCTION_RENAMED) {
+        let keys = [
+            label_key(FUNCTION_KEY),
+            label_key(MODULE_KEY),
+            PREVIOUS_FUNCTION_KEY,
+            SERVICE_NAME_KEY_PROMETHEUS,
+        ];
+        removed += remove_function_series(gauge, &keys, function);
+    }
+    // The handles kept by the call sites of the function may point to series that were just removed
+    if let Some(call_sites) = Lazy::get(&CALL_SITES) {
+        let call_sites = poison::read(call_sites, "call_sites");
+        for call_site in call_sites.get(function).into_iter().flatten() {
+            call_site.generation.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    removed
+}
+
+/// The Prometheus crate can only remove series by their exact label values,
+/// so this collects the series to find the ones of the function
+fn remove_function_series<T: MetricVecBuilder>(
+    metric: &MetricVec<T>,
+    keys: &[&str],
+    function: &str,
+) -> usize {
+    let mut removed = 0;
+    for family in metric.collect() {
+        for series in family.get_metric() {
+            let labels: HashMap<&str, &str> = keys
+                .iter()
+                .map(|key| {
+                    let value = series
+                        .get_label()
+                        .iter()
+                        .find(|label| label.get_name() == *key)
+                        .map(|label| label.get_value())
+                        .unwrap_or_default();
+                    (*key, value)
+                })
+                .collect();
+            if labels.get(label_key(FUNCTION_KEY)) == Some(&function)
+                && metric.remove(&labels).is_ok()
+            {
+                removed += 1;
+            }
+        }
+    }
+    removed
+}
+
+/// The call sites that keep handles to the series of each function, so that removing the series
+/// of a function only invalidates the handles of its own call sites
+static CALL_SITES: Lazy<RwLock<HashMap<&'static str, Vec<&'static CallSiteSeries>>>> =
+    Lazy::new(Default::default);
+
+/// The number of label combinations (for example, `result="ok"` and `result="error"`)
+/// that each call site keeps handles for. The others are looked up by their labels.
+const CALL_SITE_COUNTERS: usize = 4;
+const CALL_SITE_HISTOGRAMS: usize = 2;
+
+/// A handle to a series, along with the label values that it was created with
+/// and the generation of the call site when it was created
+#[derive(Debug)]
+struct CachedSeries<M, const N: usize> {
+    generation: u64,
+    labels: [&'static str; N],
+    metric: M,
+}
+
+type CachedSlot<M, const N: usize> = OnceCell<CachedSeries<M, N>>;
+
+/// Handles to the series of the `function.calls` counter and `function.calls.duration`
+/// histogram that were recorded by a call site.
+///
+/// `with_label_values` hashes all of the label values and takes a lock on every call,
+/// while these handles are found by comparing the (usually identical) `&'static str`s.
+/// Each slot is filled once, with the first label combination that does not have one yet.
+///
+/// The slots of the handles that are invalidated by removing the series of the function are not
+/// reclaimed, because the handles may still be in use by calls in progress. A call site whose
+/// function is removed and called again keeps recording with the remaining slots, so after its function
+/// has been removed twice, it falls back to looking up the series by their labels on every call.
+#[derive(Debug)]
+pub(crate) struct CallSiteSeries {
+    /// Incremented when the series of the call site's function are removed,
+    /// which invalidates the handles that were created before
+    generation: AtomicU64,
+    counters: [CachedSlot<IntCounter, 14>; CALL_SITE_COUNTERS],
+    histograms: [CachedSlot<Histogram, 9>; CALL_SITE_HISTOGRAMS],
+}
+
+impl CallSiteSeries {
+    pub(crate) const fn new() -> Self {
+        Self {
+            generation: AtomicU64::new(0),
+            counters: [const { OnceCell::new() }; CALL_SITE_COUNTERS],
+            histograms: [const { OnceCell::new() }; CALL_SITE_HISTOGRAMS],
+        }
+    }
+
+    fn counter(&'static self, labels: [&'static str; 14]) -> Option<&'static IntCounter> {
+        // The function is the first label
+        self.cached_series(&self.counters, labels[0], labels, || {
+            COUNTER.with_label_values(&labels)
+        })
+    }
+
+    fn histogram(&'static self, labels: [&'static str; 9]) -> Option<&'static Histogram> {
+        self.cached_series(&self.histograms, labels[0], labels, || {
+            HISTOGRAM.with_label_values(&labels)
+        })
+    }
+
+    /// Find the handle to the series with the given labels, or create one in the first empty slot.
+    ///
+    /// Returns `None` if all of the slots are taken by other label combinations or by handles
+    /// that were created before the series of the function were removed.
+    fn cached_series<M, const N: usize>(
+        &'static self,
+        slots: &'static [CachedSlot<M, N>],
+        function: &'static str,
+        labels: [&'static str; N],
+        create: impl FnOnce() -> M,
+    ) -> Option<&'static M> {
+        let generation = self.generation.load(Ordering::Relaxed);
+        let mut create = Some(create);
+        for slot in slots {
+            let cached = match slot.get() {
+                Some(cached) => cached,
+                None => {
+                    // The call site is registered before the handle is created, so that removing
+                    // the series in the meantime invalidates it
+                    self.register(function);
+                    slot.get_or_init(|| CachedSeries {
+                        generation: self.generation.load(Ordering::Relaxed),
+                        labels,
+                        metric: (create.take().expect("the series is only created once"))(),
+                    })
+                }
+            };
+            if cached.generation == generation && same_labels(&cached.labels, &labels) {
+                return Some(&cached.metric);
+            }
+        }
+        None
+    }
+
+    fn register(&'static self, function: &'static str) {
+        let mut call_sites = poison::write(&CALL_SITES, "call_sites");
+        let call_sites = call_sites.entry(function).or_default();
+        if !call_sites
+            .iter()
+            .any(|call_site| std::ptr::eq(*call_site, self))
+        {
+            call_sites.push(self);
+        }
+    }
+}
+
+/// The label values are usually the same `&'static str`s on every call,
+/// so this compares their lengths and addresses before comparing their contents
+fn same_labels<const N: usize>(a: &[&'static str; N], b: &[&'static str; N]) -> bool {
+    a.iter().zip(b).all(|(a, b)| {
+        a.len() == b.len() && (a.is_empty() || std::ptr::eq(a.as_ptr(), b.as_ptr()) || a == b)
+    })
+}
+
+/// Increment the `function.calls` counter, using the handle kept by the call site if there is one
+fn increment_counter(counter_labels: &CounterLabels, weight: u64) {
+    let call_site = counter_labels.call_site.0;
+    let custom_labels = counter_labels.custom_labels;
+    let counter_labels = counter_labels_to_prometheus_vec(counter_labels);
+    if !custom_labels.is_empty() {
+        custom_labels_counter(&custom_labels)
+            .with_label_values(&custom_label_values(&counter_labels, &custom_labels))
+            .inc_by(weight);
+        return;
+    }
+    match call_site.and_then(|call_site| call_site.prometheus.counter(counter_labels)) {
+        Some(counter) => counter.inc_by(weight),
+        None => COUNTER.with_label_values(&counter_labels).inc_by(weight),
+    }
+}
 
 pub struct PrometheusTracker {
-    start: Instant,
+    start: Timestamp,
     gauge: Option<GenericGauge<AtomicI64>>,
 }
 
+#[cfg(replayed_calls)]
+impl PrometheusTracker {
+    /// Replay a call that was measured in another process, without counting it as a concurrent call
+    pub(crate) fn replayed(elapsed: std::time::Duration) -> Self {
+        Self {
+            start: Timestamp::Elapsed(elapsed),
+            gauge: None,
+        }
+    }
+}
+
 impl TrackMetrics for PrometheusTracker {
     fn start(gauge_labels: Option<&GaugeLabels>) -> Self {
         let gauge = if let Some(gauge_labels) = gauge_labels {
-            let gauge = GAUGE.with_label_values(&[
-                gauge_labels.function,
-                gauge_labels.module,
-                gauge_labels.service_name,
-            ]);
+            let gauge = GAUGE.with_label_values(&gauge_label_values(gauge_labels));
             gauge.inc();
             Some(gauge)
         } else {
@@ -103,68 +842,99 @@ impl TrackMetrics for PrometheusTracker {
         };
 
         Self {
-            start: Instant::now(),
+            start: Timestamp::now(),
             gauge,
         }
     }
 
-    fn finish(self, counter_labels: &CounterLabels, histogram_labels: &HistogramLabels) {
-        let duration = self.start.elapsed().as_secs_f64();
+    fn finish_weighted(
+        self,
+        counter_labels: &CounterLabels,
+        histogram_labels: &HistogramLabels,
+        weight: u64,
+    ) {
+        let duration = get_settings().duration_unit.convert(self.start.elapsed());
 
-        let counter_labels = counter_labels_to_prometheus_vec(counter_labels);
-        COUNTER.with_label_values(&counter_labels).inc();
+        increment_counter(counter_labels, weight);
 
-        HISTOGRAM
-            .with_label_values(&[
-                histogram_labels.function,
-                histogram_labels.module,
-                histogram_labels.service_name,
-                histogram_labels.objective_name.unwrap_or_default(),
-                histogram_labels
-                    .objective_percentile
-                    .as_ref()
-                    .map(|p| p.as_str())
-                    .unwrap_or_default(),
-                histogram_labels
-                    .objective_latency_threshold
-                    .as_ref()
-                    .map(|p| p.as_str())
-                    .unwrap_or_default(),
-            ])
-            .observe(duration);
+        let buckets = histogram_labels.buckets.0;
+        let call_site = histogram_labels.call_site.0;
+        let custom_labels = histogram_labels.custom_labels;
+        let histogram_labels = histogram_labels_to_prometheus_array(histogram_labels);
+        match buckets {
+            _ if !custom_labels.is_empty() => custom_labels_histogram(&custom_labels, buckets)
+                .with_label_values(&custom_label_values(&histogram_labels, &custom_labels))
+                .observe(duration),
+            Some(buckets) => custom_buckets_histogram(buckets)
+                .with_label_values(&histogram_labels)
+                .observe(duration),
+            None => match call_site
+                .and_then(|call_site| call_site.prometheus.histogram(histogram_labels))
+            {
+                Some(histogram) => histogram.observe(duration),
+                None => HISTOGRAM
+                    .with_label_values(&histogram_labels)
+                    .observe(duration),
+            },
+        }
+        for histogram in HISTOGRAM_VIEWS.iter() {
+            histogram
+                .with_label_values(&histogram_labels)
+                .observe(duration);
+        }
 
         if let Some(gauge) = self.gauge {
             gauge.dec();
         }
     }
 
+    fn count(counter_labels: &CounterLabels) {
+        increment_counter(counter_labels, 1);
+    }
+
+    #[cfg(feature = "rdkafka")]
+    fn set_consumer_lag(consumer_lag_labels: &ConsumerLagLabels, lag: i64) {
+        CONSUMER_LAG
+            .with_label_values(&[
+                consumer_lag_labels.topic,
+                consumer_lag_labels.partition,
+                consumer_lag_labels.service_name,
+            ])
+            .set(lag);
+    }
+
     fn set_build_info(build_info_labels: &BuildInfoLabels) {
         SET_BUILD_INFO.call_once(|| {
-            BUILD_INFO
-                .with_label_values(&[
-                    build_info_labels.commit,
-                    build_info_labels.version,
-                    build_info_labels.branch,
-                    build_info_labels.service_name,
-                    build_info_labels.repo_url,
-                    build_info_labels.repo_provider,
-                    build_info_labels.autometrics_version,
-                ])
-                .set(1);
+            // The labels of a metric are fixed with this library, so the unknown ones are left empty
+            let mut labels = vec![
+                build_info_labels.commit,
+                build_info_labels.version,
+                build_info_labels.branch,
+                build_info_labels.service_name,
+                build_info_labels.repo_url,
+                build_info_labels.repo_provider,
+                build_info_labels.autometrics_version,
+            ];
+            labels.extend(
+                build_info_labels
+                    .metadata()
+                    .map(|(_, value)| value.unwrap_or_default()),
+            );
+            BUILD_INFO.with_label_values(&labels).set(1);
+            BuildAgeCollector::register();
         });
     }
 
-    #[cfg(debug_assertions)]
-    fn intitialize_metrics(function_descriptions: &[FunctionDescription]) {
-        for function in function_descriptions {
-            let labels = counter_labels_to_prometheus_vec(&CounterLabels::from(function));
+    fn intitialize_metrics(counter_labels: &[CounterLabels]) {
+        for counter_labels in counter_labels {
+            let labels = counter_labels_to_prometheus_vec(counter_labels);
             COUNTER.with_label_values(&labels).inc_by(0);
         }
     }
 }
 
 /// Put the label values in the same order as the keys in the counter definition
-fn counter_labels_to_prometheus_vec(counter_labels: &CounterLabels) -> [&'static str; 10] {
+fn counter_labels_to_prometheus_vec(counter_labels: &CounterLabels) -> [&'static str; 14] {
     [
         counter_labels.function,
         counter_labels.module,
@@ -184,5 +954,32 @@ fn counter_labels_to_prometheus_vec(counter_labels: &CounterLabels) -> [&'static
             .as_ref()
             .map(|p| p.as_str())
             .unwrap_or_default(),
+        counter_labels.recursive.unwrap_or_default(),
+        counter_labels.error_type.unwrap_or_default(),
+        counter_labels.type_params.unwrap_or_default(),
+        counter_labels.panic.unwrap_or_default(),
+    ]
+}
+
+/// Put the label values in the same order as the keys in the histogram definition
+fn histogram_labels_to_prometheus_array(histogram_labels: &HistogramLabels) -> [&'static str; 9] {
+    [
+        histogram_labels.function,
+        histogram_labels.module,
+        histogram_labels.service_name,
+        histogram_labels.objective_name.unwrap_or_default(),
+        histogram_labels
+            .objective_percentile
+            .as_ref()
+            .map(|p| p.as_str())
+            .unwrap_or_default(),
+        histogram_labels
+            .objective_latency_threshold
+            .as_ref()
+            .map(|p| p.label_value())
+            .unwrap_or_default(),
+        histogram_labels.type_params.unwrap_or_default(),
+        histogram_labels.cold.unwrap_or_default(),
+        histogram_labels.result.unwrap_or_default(),
     ]
 }
@@ -1,14 +1,30 @@
-use super::TrackMetrics;
-#[cfg(debug_assertions)]
-use crate::__private::FunctionDescription;
+use super::{build_age_seconds, Timestamp, TrackMetrics};
+use crate::constants::*;
 #[cfg(exemplars)]
-use crate::exemplars::get_exemplar;
-use crate::labels::{BuildInfoLabels, CounterLabels, GaugeLabels, HistogramLabels};
-use crate::{constants::*, settings::get_settings};
-use once_cell::sync::Lazy;
-use prometheus_client::metrics::{family::Family, gauge::Gauge};
+use crate::exemplars::take_exemplar;
+#[cfg(feature = "rdkafka")]
+use crate::labels::ConsumerLagLabels;
+use crate::labels::{
+    BuildInfoLabels, CardinalityWarningLabels, CounterLabels, CrateCallsLabels,
+    FunctionRenamedLabels, GaugeLabels, HistogramLabels, LabelSetEvictionLabels,
+    LockRecoveryLabels,
+};
+use crate::poison;
+use crate::settings::{
+    get_settings, prefixed_metric_name, DurationUnit, HistogramView, MetricNaming,
+    RESPONSE_SIZE_BUCKETS,
+};
+use once_cell::sync::{Lazy, OnceCell};
+use prometheus_client::encoding::{EncodeMetric, MetricEncoder};
+use prometheus_client::metrics::family::{Family, MetricConstructor};
+use prometheus_client::metrics::histogram::Histogram;
+use prometheus_client::metrics::{counter::Counter, gauge::Gauge, MetricType, TypedMetric};
 use prometheus_client::registry::{Registry, Unit};
-use std::time::Instant;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
 
 #[cfg(exemplars)]
 type CounterType =
@@ -23,104 +39,719 @@ type HistogramType =
 type HistogramType = prometheus_client::metrics::histogram::Histogram;
 
 static METRICS: Lazy<&Metrics> = Lazy::new(|| &get_settings().prometheus_client_metrics);
+static SHADOW_METRICS: Lazy<Option<&Metrics>> =
+    Lazy::new(|| get_settings().prometheus_client_shadow_metrics.as_ref());
+
+/// Record in the metrics of the registry, and in those of the shadow registry if there is one,
+/// unless the current task is running in the scope of a [`ScopedRegistry`](crate::scope::ScopedRegistry)
+fn record(mut record: impl FnMut(&Metrics)) {
+    if crate::scope::with_scoped_metrics(&mut record).is_some() {
+        return;
+    }
+    record(&METRICS);
+    if let Some(shadow_metrics) = *SHADOW_METRICS {
+        record(shadow_metrics);
+    }
+}
+
+/// Histogram buckets that override the ones from the settings for newly created histograms
+static HISTOGRAM_BUCKETS_OVERRIDE: RwLock<Option<Vec<f64>>> = RwLock::new(None);
+
+/// The label sets of the series of each function.
+///
+/// The metric families cannot be iterated, so these are needed to remove the series of a function.
+static SERIES: Lazy<RwLock<HashMap<&'static str, FunctionSeries>>> = Lazy::new(Default::default);
+
+#[derive(Default)]
+struct FunctionSeries {
+    counter: HashSet<CounterLabels>,
+    histogram: HashSet<HistogramLabels>,
+    gauge: HashSet<GaugeLabels>,
+}
+
+/// Record the label sets of a function's series, unless they are already known
+fn track_series(
+    function: &'static str,
+    is_tracked: impl Fn(&FunctionSeries) -> bool,
+    track: impl FnOnce(&mut FunctionSeries),
+) {
+    // The series of the scoped registries cannot be removed by function
+    if crate::scope::is_active() {
+        return;
+    }
+    let series = poison::read(&SERIES, "series");
+    if series.get(function).is_some_and(is_tracked) {
+        return;
+    }
+    drop(series);
+
+    track(
+        poison::write(&SERIES, "series")
+            .entry(function)
+            .or_default(),
+    );
+}
+
+/// The label sets of the counter and the histogram, along with the tick at which each was last recorded,
+/// if their number is bounded with [`max_label_sets`](crate::settings::AutometricsSettingsBuilder::max_label_sets)
+static COUNTER_LABEL_SETS: Lazy<RwLock<HashMap<CounterLabels, AtomicU64>>> =
+    Lazy::new(Default::default);
+static HISTOGRAM_LABEL_SETS: Lazy<RwLock<HashMap<HistogramLabels, AtomicU64>>> =
+    Lazy::new(Default::default);
+
+/// Incremented for every recorded label set, so that the ones recorded least recently can be evicted
+static TICK: AtomicU64 = AtomicU64::new(0);
+
+/// Mark the label set as recorded, returning the label set that was evicted to make room for it, if any
+fn touch_label_set<T: Clone + Eq + Hash>(
+    label_sets: &RwLock<HashMap<T, AtomicU64>>,
+    labels: &T,
+    max_label_sets: usize,
+) -> Option<T> {
+    let tick = TICK.fetch_add(1, Ordering::Relaxed);
+
+    let known = poison::read(label_sets, "label_sets");
+    if let Some(last_recorded) = known.get(labels) {
+        last_recorded.store(tick, Ordering::Relaxed);
+        return None;
+    }
+    drop(known);
+
+    let mut label_sets = poison::write(label_sets, "label_sets");
+    if label_sets.contains_key(labels) {
+        return None;
+    }
+
+    // Finding the label set to evict is linear in the number of label sets, but this only happens
+    // when a new label set is recorded once the limit is reached
+    let mut evicted = None;
+    if label_sets.len() >= max_label_sets {
+        evicted = label_sets
+            .iter()
+            .min_by_key(|(_, last_recorded)| last_recorded.load(Ordering::Relaxed))
+            .map(|(labels, _)| labels.clone());
+        if let Some(evicted) = &evicted {
+            label_sets.remove(evicted);
+        }
+    }
+    label_sets.insert(labels.clone(), AtomicU64::new(tick));
+    evicted
+}
+
+/// Remove the series of the label sets that were recorded least recently,
+/// if the number of label sets is bounded and the limit is reached
+fn evict_label_sets(counter_labels: &CounterLabels, histogram_labels: Option<&HistogramLabels>) {
+    let Some(max_label_sets) = get_settings().max_label_sets else {
+        return;
+    };
+    if crate::scope::is_active() {
+        return;
+    }
+
+    if let Some(evicted) = touch_label_set(&COUNTER_LABEL_SETS, counter_labels, max_label_sets) {
+        record(|metrics| {
+            metrics.counter.remove(&evicted);
+        });
+        if let Some(series) = poison::write(&SERIES, "series").get_mut(evicted.function) {
+            series.counter.remove(&evicted);
+        }
+        record_label_set_eviction(evicted.function, evicted.module, evicted.service_name);
+    }
+
+    let Some(histogram_labels) = histogram_labels else {
+        return;
+    };
+    if let Some(evicted) = touch_label_set(&HISTOGRAM_LABEL_SETS, histogram_labels, max_label_sets)
+    {
+        record(|metrics| {
+            metrics.histogram.remove(&evicted);
+            for histogram in &metrics.histogram_views {
+                histogram.remove(&evicted);
+            }
+            metrics.response_size.remove(&evicted);
+        });
+        if let Some(series) = poison::write(&SERIES, "series").get_mut(evicted.function) {
+            series.histogram.remove(&evicted);
+        }
+        record_label_set_eviction(evicted.function, evicted.module, evicted.service_name);
+    }
+}
+
+fn record_label_set_eviction(
+    function: &'static str,
+    module: &'static str,
+    service_name: &'static str,
+) {
+    let labels = LabelSetEvictionLabels {
+        function,
+        module,
+        service_name,
+    };
+    record(|metrics| {
+        metrics.label_set_evictions.get_or_create(&labels).inc();
+    });
+}
+
+/// Remove all of the series of the function with the given name from the metric families,
+/// returning the number of series that were removed
+pub(crate) fn remove_function(function: &str) -> usize {
+    // The removed label sets no longer count towards the limit
+    if let Some(label_sets) = Lazy::get(&COUNTER_LABEL_SETS) {
+        poison::write(label_sets, "label_sets").retain(|labels, _| labels.function != function);
+    }
+    if let Some(label_sets) = Lazy::get(&HISTOGRAM_LABEL_SETS) {
+        poison::write(label_sets, "label_sets").retain(|labels, _| labels.function != function);
+    }
+
+    let Some(series) = poison::write(&SERIES, "series").remove(function) else {
+        return 0;
+    };
+
+    if let Some(shadow_metrics) = *SHADOW_METRICS {
+        shadow_metrics.remove_series(&series);
+    }
+    METRICS.remove_series(&series)
+}
+
+pub(crate) fn record_cardinality_warning(labels: &CardinalityWarningLabels) {
+    record(|metrics| {
+        metrics.cardinality_warnings.get_or_create(labels).inc();
+    });
+}
+
+pub(crate) fn record_crate_calls(labels: &CrateCallsLabels, weight: u64) {
+    record(|metrics| {
+        metrics.crate_calls.get_or_create(labels).inc_by(weight);
+    });
+}
+
+pub(crate) fn record_lock_recovery(labels: &LockRecoveryLabels) {
+    record(|metrics| {
+        metrics.lock_recoveries.get_or_create(labels).inc();
+    });
+}
+
+pub(crate) fn set_function_renamed(labels: &FunctionRenamedLabels) {
+    record(|metrics| {
+        metrics.function_renamed.get_or_create(labels).set(1);
+    });
+}
+
+pub(crate) fn record_response_size(histogram_labels: &HistogramLabels, size: usize) {
+    record(|metrics| {
+        metrics
+            .response_size
+            .get_or_create(histogram_labels)
+            .observe(size as f64);
+    });
+    track_series(
+        histogram_labels.function,
+        |series| series.histogram.contains(histogram_labels),
+        |series| {
+            series.histogram.insert(histogram_labels.clone());
+        },
+    );
+}
+
+pub(crate) fn set_max_concurrency(gauge_labels: &GaugeLabels, max: i64) {
+    record(|metrics| {
+        metrics.max_concurrency.get_or_create(gauge_labels).set(max);
+    });
+}
+
+/// Use the given buckets for all histograms created from now on.
+///
+/// Histograms that were already created keep their existing buckets.
+#[cfg(prometheus_exporter)]
+pub(crate) fn set_histogram_buckets(histogram_buckets: Vec<f64>) {
+    *poison::write(&HISTOGRAM_BUCKETS_OVERRIDE, "histogram_buckets") = Some(histogram_buckets);
+}
+
+/// The buckets of the histograms that are created from now on, unless the call site sets its own
+#[cfg(all(prometheus_exporter, exemplars))]
+pub(crate) fn histogram_buckets() -> Vec<f64> {
+    match &*poison::read(&HISTOGRAM_BUCKETS_OVERRIDE, "histogram_buckets") {
+        Some(histogram_buckets) => histogram_buckets.clone(),
+        None => get_settings().histogram_buckets.clone(),
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn initialize_registry(
+    mut registry: Registry,
+    global_labels: &[(String, String)],
+    metric_name_prefix: Option<&str>,
+    metric_naming: MetricNaming,
+    histogram_views: &[HistogramView],
+    duration_unit: DurationUnit,
+    call_edges: bool,
+    cardinality_watchdog: bool,
+    bounded_label_sets: bool,
+    crate_rollups: bool,
+) -> (Registry, Metrics) {
+    // The names of the histogram views already include the prefix
+    let name = |name: &str| prefixed_metric_name(metric_name_prefix, '_', name);
+
+    // Attach the global labels to all of the metrics registered below
+    // (Prometheus label names cannot contain dots, so those are replaced with underscores)
+    let sub_registry =
+        registry.sub_registry_with_labels(global_labels.iter().map(|(key, value)| {
+            (
+                Cow::Owned(key.replace('.', "_")),
+                Cow::Owned(value.to_string()),
+            )
+        }));
 
-pub(crate) fn initialize_registry(mut registry: Registry) -> (Registry, Metrics) {
     let counter = Family::<CounterLabels, CounterType>::default();
-    registry.register(
+    sub_registry.register(
         // Remove the _total suffix from the counter name
         // because the library adds it automatically
-        COUNTER_NAME_PROMETHEUS.replace("_total", ""),
+        name(&COUNTER_NAME_PROMETHEUS.replace("_total", "")),
         COUNTER_DESCRIPTION,
         counter.clone(),
     );
 
-    let histogram = Family::<HistogramLabels, HistogramType>::new_with_constructor(|| {
-        HistogramType::new(get_settings().histogram_buckets.iter().copied())
-    });
-    registry.register_with_unit(
-        // This also adds the _seconds suffix to the histogram name automatically
-        HISTOGRAM_NAME_PROMETHEUS.replace("_seconds", ""),
+    // This adds the unit suffix (like _seconds) to the histogram names automatically
+    let duration_unit_suffix = format!("_{}", duration_unit.prometheus_suffix());
+    let duration_unit = || match duration_unit {
+        DurationUnit::Seconds => Unit::Seconds,
+        unit => Unit::Other(unit.prometheus_suffix().to_string()),
+    };
+
+    let histogram = Family::<HistogramLabels, FunctionHistogram>::default();
+    sub_registry.register_with_unit(
+        name(
+            &metric_naming
+                .prometheus_histogram_name()
+                .replace("_seconds", ""),
+        ),
         HISTOGRAM_DESCRIPTION,
-        Unit::Seconds,
+        duration_unit(),
         histogram.clone(),
     );
 
+    let histogram_views = histogram_views
+        .iter()
+        .map(|view| {
+            let histogram =
+                Family::<HistogramLabels, HistogramType, ViewBuckets>::new_with_constructor(
+                    ViewBuckets(view.buckets.clone()),
+                );
+            sub_registry.register_with_unit(
+                view.prometheus_name
+                    .trim_end_matches(&duration_unit_suffix)
+                    .to_string(),
+                HISTOGRAM_DESCRIPTION,
+                duration_unit(),
+                histogram.clone(),
+            );
+            histogram
+        })
+        .collect();
+
+    let response_size =
+        Family::<HistogramLabels, Histogram, ResponseSizeBuckets>::new_with_constructor(|| {
+            Histogram::new(RESPONSE_SIZE_BUCKETS.into_iter())
+        });
+    sub_registry.register_with_unit(
+        // This adds the _bytes suffix to the histogram name automatically
+        name(&RESPONSE_SIZE_NAME_PROMETHEUS.replace("_bytes", "")),
+        RESPONSE_SIZE_DESCRIPTION,
+        Unit::Bytes,
+        response_size.clone(),
+    );
+
     let gauge = Family::<GaugeLabels, Gauge>::default();
-    registry.register(GAUGE_NAME_PROMETHEUS, GAUGE_DESCRIPTION, gauge.clone());
+    sub_registry.register(
+        name(GAUGE_NAME_PROMETHEUS),
+        GAUGE_DESCRIPTION,
+        gauge.clone(),
+    );
+
+    let max_concurrency = Family::<GaugeLabels, Gauge>::default();
+    sub_registry.register(
+        name(MAX_CONCURRENCY_NAME_PROMETHEUS),
+        MAX_CONCURRENCY_DESCRIPTION,
+        max_concurrency.clone(),
+    );
 
     let build_info = Family::<BuildInfoLabels, Gauge>::default();
-    registry.register(BUILD_INFO_NAME, BUILD_INFO_DESCRIPTION, build_info.clone());
+    sub_registry.register(
+        name(BUILD_INFO_NAME),
+        BUILD_INFO_DESCRIPTION,
+        build_info.clone(),
+    );
+
+    sub_registry.register_with_unit(
+        // This also adds the _seconds suffix to the gauge name automatically
+        name(&BUILD_AGE_NAME_PROMETHEUS.replace("_seconds", "")),
+        BUILD_AGE_DESCRIPTION,
+        Unit::Seconds,
+        BuildAge,
+    );
+
+    if call_edges {
+        sub_registry.register(
+            // The library adds the _info suffix automatically
+            name(&CALL_EDGES_NAME_PROMETHEUS.replace("_info", "")),
+            CALL_EDGES_DESCRIPTION,
+            CallEdges,
+        );
+    }
+
+    let cardinality_warnings = Family::<CardinalityWarningLabels, Counter>::default();
+    if cardinality_watchdog {
+        sub_registry.register(
+            // The library adds the _total suffix automatically
+            name(&CARDINALITY_WARNINGS_NAME_PROMETHEUS.replace("_total", "")),
+            CARDINALITY_WARNINGS_DESCRIPTION,
+            cardinality_warnings.clone(),
+        );
+    }
+
+    let label_set_evictions = Family::<LabelSetEvictionLabels, Counter>::default();
+    if bounded_label_sets {
+        sub_registry.register(
+            // The library adds the _total suffix automatically
+            name(&LABEL_SET_EVICTIONS_NAME_PROMETHEUS.replace("_total", "")),
+            LABEL_SET_EVICTIONS_DESCRIPTION,
+            label_set_evictions.clone(),
+        );
+    }
+
+    let crate_calls = Family::<CrateCallsLabels, Counter>::default();
+    if crate_rollups {
+        sub_registry.register(
+            // The library adds the _total suffix automatically
+            name(&CRATE_CALLS_NAME_PROMETHEUS.replace("_total", "")),
+            CRATE_CALLS_DESCRIPTION,
+            crate_calls.clone(),
+        );
+    }
+
+    let lock_recoveries = Family::<LockRecoveryLabels, Counter>::default();
+    sub_registry.register(
+        // The library adds the _total suffix automatically
+        name(&LOCK_RECOVERIES_NAME_PROMETHEUS.replace("_total", "")),
+        LOCK_RECOVERIES_DESCRIPTION,
+        lock_recoveries.clone(),
+    );
+
+    let function_renamed = Family::<FunctionRenamedLabels, Gauge>::default();
+    sub_registry.register(
+        name(FUNCTION_RENAMED_NAME_PROMETHEUS),
+        FUNCTION_RENAMED_DESCRIPTION,
+        function_renamed.clone(),
+    );
+
+    #[cfg(feature = "rdkafka")]
+    let consumer_lag = Family::<ConsumerLagLabels, Gauge>::default();
+    #[cfg(feature = "rdkafka")]
+    sub_registry.register(
+        name(CONSUMER_LAG_NAME_PROMETHEUS),
+        CONSUMER_LAG_DESCRIPTION,
+        consumer_lag.clone(),
+    );
 
     (
         registry,
         Metrics {
             counter,
             histogram,
+            histogram_views,
+            response_size,
             gauge,
+            max_concurrency,
             build_info,
+            cardinality_warnings,
+            label_set_evictions,
+            crate_calls,
+            lock_recoveries,
+            function_renamed,
+            #[cfg(feature = "rdkafka")]
+            consumer_lag,
         },
     )
 }
 
 pub(crate) struct Metrics {
     counter: Family<CounterLabels, CounterType>,
-    histogram: Family<HistogramLabels, HistogramType>,
+    histogram: Family<HistogramLabels, FunctionHistogram>,
+    histogram_views: Vec<Family<HistogramLabels, HistogramType, ViewBuckets>>,
+    response_size: Family<HistogramLabels, Histogram, ResponseSizeBuckets>,
     gauge: Family<GaugeLabels, Gauge>,
+    max_concurrency: Family<GaugeLabels, Gauge>,
     build_info: Family<BuildInfoLabels, Gauge>,
+    cardinality_warnings: Family<CardinalityWarningLabels, Counter>,
+    label_set_evictions: Family<LabelSetEvictionLabels, Counter>,
+    crate_calls: Family<CrateCallsLabels, Counter>,
+    lock_recoveries: Family<LockRecoveryLabels, Counter>,
+    function_renamed: Family<FunctionRenamedLabels, Gauge>,
+    #[cfg(feature = "rdkafka")]
+    consumer_lag: Family<ConsumerLagLabels, Gauge>,
+}
+
+impl Metrics {
+    /// Remove the series of a function, returning the number of series that were removed
+    fn remove_series(&self, series: &FunctionSeries) -> usize {
+        let mut removed = 0;
+        for counter_labels in &series.counter {
+            removed += usize::from(self.counter.remove(counter_labels));
+        }
+        for histogram_labels in &series.histogram {
+            removed += usize::from(self.histogram.remove(histogram_labels));
+            for histogram in &self.histogram_views {
+                removed += usize::from(histogram.remove(histogram_labels));
+            }
+            removed += usize::from(self.response_size.remove(histogram_labels));
+        }
+        for gauge_labels in &series.gauge {
+            removed += usize::from(self.gauge.remove(gauge_labels));
+            removed += usize::from(self.max_concurrency.remove(gauge_labels));
+        }
+        removed
+    }
+
+    /// Remove the series of the function call counters (and their per-crate rollups),
+    /// latency histograms, and response size histograms
+    pub(crate) fn reset(&self) {
+        self.counter.clear();
+        self.crate_calls.clear();
+        self.histogram.clear();
+        for histogram in &self.histogram_views {
+            histogram.clear();
+        }
+        self.response_size.clear();
+    }
+}
+
+/// Creates the histograms of the `function.response.size` metric
+type ResponseSizeBuckets = fn() -> Histogram;
+
+/// Histogram that is created when it is first observed, so that it can use
+/// the buckets that were set for the function with the `autometrics` macro
+#[derive(Debug, Default)]
+struct FunctionHistogram(OnceCell<HistogramType>);
+
+impl FunctionHistogram {
+    fn get_or_init(&self, histogram_labels: &HistogramLabels) -> &HistogramType {
+        self.0.get_or_init(|| {
+            if let Some(buckets) = histogram_labels.buckets.0 {
+                return HistogramType::new(buckets.iter().copied());
+            }
+
+            match &*poison::read(&HISTOGRAM_BUCKETS_OVERRIDE, "histogram_buckets") {
+                Some(histogram_buckets) => HistogramType::new(histogram_buckets.iter().copied()),
+                None => HistogramType::new(get_settings().histogram_buckets.iter().copied()),
+            }
+        })
+    }
+}
+
+impl TypedMetric for FunctionHistogram {
+    const TYPE: MetricType = MetricType::Histogram;
+}
+
+impl EncodeMetric for FunctionHistogram {
+    fn encode(&self, encoder: MetricEncoder) -> Result<(), std::fmt::Error> {
+        match self.0.get() {
+            Some(histogram) => histogram.encode(encoder),
+            None => Ok(()),
+        }
+    }
+
+    fn metric_type(&self) -> MetricType {
+        MetricType::Histogram
+    }
+}
+
+/// Creates the histograms for the additional histogram views, using the view's buckets
+#[derive(Clone, Debug)]
+struct ViewBuckets(Vec<f64>);
+
+impl MetricConstructor<HistogramType> for ViewBuckets {
+    fn new_metric(&self) -> HistogramType {
+        HistogramType::new(self.0.iter().copied())
+    }
+}
+
+/// Gauge that computes the time elapsed since the build whenever the registry is encoded
+#[derive(Debug)]
+struct BuildAge;
+
+impl EncodeMetric for BuildAge {
+    fn encode(&self, mut encoder: MetricEncoder) -> Result<(), std::fmt::Error> {
+        match build_age_seconds() {
+            Some(build_age) => encoder.encode_gauge(&build_age),
+            None => Ok(()),
+        }
+    }
+
+    fn metric_type(&self) -> MetricType {
+        MetricType::Gauge
+    }
+}
+
+/// Info metric with a series for each of the call edges that are known when the registry is encoded
+#[derive(Debug)]
+struct CallEdges;
+
+impl EncodeMetric for CallEdges {
+    fn encode(&self, mut encoder: MetricEncoder) -> Result<(), std::fmt::Error> {
+        for edge in crate::call_edges::edges() {
+            encoder.encode_info(&edge)?;
+        }
+        Ok(())
+    }
+
+    fn metric_type(&self) -> MetricType {
+        MetricType::Info
+    }
 }
 
 pub struct PrometheusClientTracker {
     gauge_labels: Option<GaugeLabels>,
-    start_time: Instant,
+    start_time: Timestamp,
+}
+
+#[cfg(replayed_calls)]
+impl PrometheusClientTracker {
+    /// Replay a call that was measured in another process, without counting it as a concurrent call
+    pub(crate) fn replayed(elapsed: std::time::Duration) -> Self {
+        Self {
+            gauge_labels: None,
+            start_time: Timestamp::Elapsed(elapsed),
+        }
+    }
 }
 
 impl TrackMetrics for PrometheusClientTracker {
     fn set_build_info(build_info_labels: &BuildInfoLabels) {
-        METRICS.build_info.get_or_create(build_info_labels).set(1);
+        record(|metrics| {
+            metrics.build_info.get_or_create(build_info_labels).set(1);
+        });
     }
 
     fn start(gauge_labels: Option<&GaugeLabels>) -> Self {
         if let Some(gauge_labels) = gauge_labels {
-            METRICS.gauge.get_or_create(gauge_labels).inc();
+            record(|metrics| {
+                metrics.gauge.get_or_create(gauge_labels).inc();
+            });
+            track_series(
+                gauge_labels.function,
+                |series| series.gauge.contains(gauge_labels),
+                |series| {
+                    series.gauge.insert(gauge_labels.clone());
+                },
+            );
         }
         Self {
             gauge_labels: gauge_labels.cloned(),
-            start_time: Instant::now(),
+            start_time: Timestamp::now(),
         }
     }
 
-    fn finish(self, counter_labels: &CounterLabels, histogram_labels: &HistogramLabels) {
+    fn finish_weighted(
+        self,
+        counter_labels: &CounterLabels,
+        histogram_labels: &HistogramLabels,
+        weight: u64,
+    ) {
         #[cfg(exemplars)]
-        let exemplar = get_exemplar().map(|exemplar| exemplar.into_iter().collect::<Vec<_>>());
+        let exemplar = take_exemplar().map(|exemplar| exemplar.into_iter().collect::<Vec<_>>());
+
+        let duration = get_settings()
+            .duration_unit
+            .convert(self.start_time.elapsed());
+        record(|metrics| {
+            metrics.counter.get_or_create(counter_labels).inc_by(
+                weight,
+                #[cfg(exemplars)]
+                exemplar.clone(),
+            );
 
-        METRICS.counter.get_or_create(counter_labels).inc_by(
-            1,
-            #[cfg(exemplars)]
-            exemplar.clone(),
+            for histogram in &metrics.histogram_views {
+                histogram.get_or_create(histogram_labels).observe(
+                    duration,
+                    #[cfg(exemplars)]
+                    exemplar.clone(),
+                );
+            }
+            metrics
+                .histogram
+                .get_or_create(histogram_labels)
+                .get_or_init(histogram_labels)
+                .observe(
+                    duration,
+                    #[cfg(exemplars)]
+                    exemplar.clone(),
+                );
+
+            if let Some(gauge_labels) = &self.gauge_labels {
+                metrics.gauge.get_or_create(gauge_labels).dec();
+            }
+        });
+
+        track_series(
+            counter_labels.function,
+            |series| {
+                series.counter.contains(counter_labels)
+                    && series.histogram.contains(histogram_labels)
+            },
+            |series| {
+                series.counter.insert(counter_labels.clone());
+                series.histogram.insert(histogram_labels.clone());
+            },
         );
+        evict_label_sets(counter_labels, Some(histogram_labels));
+    }
 
-        METRICS.histogram.get_or_create(histogram_labels).observe(
-            self.start_time.elapsed().as_secs_f64(),
-            #[cfg(exemplars)]
-            exemplar,
+    fn count(counter_labels: &CounterLabels) {
+        #[cfg(exemplars)]
+        let exemplar = take_exemplar().map(|exemplar| exemplar.into_iter().collect::<Vec<_>>());
+        record(|metrics| {
+            metrics.counter.get_or_create(counter_labels).inc_by(
+                1,
+                #[cfg(exemplars)]
+                exemplar.clone(),
+            );
+        });
+        track_series(
+            counter_labels.function,
+            |series| series.counter.contains(counter_labels),
+            |series| {
+                series.counter.insert(counter_labels.clone());
+            },
         );
+        evict_label_sets(counter_labels, None);
+    }
 
-        if let Some(gauge_labels) = &self.gauge_labels {
-            METRICS.gauge.get_or_create(gauge_labels).dec();
-        }
+    #[cfg(feature = "rdkafka")]
+    fn set_consumer_lag(consumer_lag_labels: &ConsumerLagLabels, lag: i64) {
+        record(|metrics| {
+            metrics
+                .consumer_lag
+                .get_or_create(consumer_lag_labels)
+                .set(lag);
+        });
     }
 
-    #[cfg(debug_assertions)]
-    fn intitialize_metrics(function_descriptions: &[FunctionDescription]) {
-        for function in function_descriptions {
-            METRICS
-                .counter
-                .get_or_create(&CounterLabels::from(function))
-                .inc_by(
+    fn intitialize_metrics(counter_labels: &[CounterLabels]) {
+        for counter_labels in counter_labels {
+            record(|metrics| {
+                metrics.counter.get_or_create(counter_labels).inc_by(
                     0,
                     #[cfg(exemplars)]
                     None,
                 );
+            });
+            track_series(
+                counter_labels.function,
+                |series| series.counter.contains(counter_labels),
+                |series| {
+                    series.counter.insert(counter_labels.clone());
+                },
+            );
         }
     }
 }
@@ -0,0 +1,262 @@
+//! Emit a structured event for every call to an instrumented function ("wide events").
+//!
+//! The metrics aggregate the calls to each function, which keeps them cheap to store and query.
+//! Wide events complement them with one record per call, so individual calls can be inspected
+//! and sliced by any of their attributes. The events use the same names as the metric labels.
+//!
+//! Events are passed to an [`EventSink`]. Autometrics provides the following sinks:
+//!
+//! - [`TracingSink`] emits a [`tracing`](https://crates.io/crates/tracing) event.
+//!   These can be exported as OpenTelemetry logs with the
+//!   [`opentelemetry-appender-tracing`](https://crates.io/crates/opentelemetry-appender-tracing) crate.
+//! - [`JsonLinesSink`] writes each event as a line of JSON, for example to a file.
+//!
+//! You can also implement [`EventSink`] to send the events anywhere else.
+//!
+//! # Example
+//! ```rust
+//! use autometrics::settings::AutometricsSettings;
+//! use autometrics::wide_events::{TracingSink, WideEvents};
+//!
+//! AutometricsSettings::builder()
+//!     .wide_events(
+//!         WideEvents::new(TracingSink)
+//!             // Only emit 10% of the events for successful calls
+//!             .sample_rate(0.1)
+//!             .always_emit_errors(true),
+//!     )
+//!     .init();
+//! ```
+//!
+//! # Trace IDs
+//!
+//! When one of the [`exemplars`](crate::exemplars) features is enabled, the `trace_id` of the
+//! current span is added to the events.
+
+#[cfg(exemplars)]
+use crate::exemplars::get_exemplar;
+use crate::json::JsonObject;
+use crate::labels::{CounterLabels, ResultLabel};
+use crate::poison;
+use crate::sampling::random_fraction;
+use crate::settings::get_settings;
+use std::fmt;
+use std::io::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// A structured record of a single call to an instrumented function.
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub struct CallEvent {
+    pub function: &'static str,
+    pub module: &'static str,
+    pub service_name: &'static str,
+    pub caller_function: &'static str,
+    pub caller_module: &'static str,
+    pub duration: Duration,
+    /// `"ok"` or `"error"` if the function returns a `Result`
+    pub result: Option<&'static str>,
+    /// The error variant or type, if the function uses the `error_details` argument
+    pub error_type: Option<&'static str>,
+    pub objective_name: Option<&'static str>,
+    pub trace_id: Option<String>,
+}
+
+impl CallEvent {
+    fn new(counter_labels: &CounterLabels, duration: Duration) -> Self {
+        Self {
+            function: counter_labels.function,
+            module: counter_labels.module,
+            service_name: counter_labels.service_name,
+            caller_function: counter_labels.caller_function,
+            caller_module: counter_labels.caller_module,
+            duration,
+            result: counter_labels.result.as_ref().map(|result| match result {
+                ResultLabel::Ok => "ok",
+                ResultLabel::Error => "error",
+            }),
+            error_type: counter_labels.error_type,
+            objective_name: counter_labels.objective_name,
+            #[cfg(exemplars)]
+            trace_id: get_exemplar().and_then(|mut exemplar| exemplar.remove("trace_id")),
+            #[cfg(not(exemplars))]
+            trace_id: None,
+        }
+    }
+
+    fn is_error(&self) -> bool {
+        self.result == Some("error")
+    }
+}
+
+impl fmt::Display for CallEvent {
+    /// Format the event as a single line of JSON.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut json = JsonObject::new(f)?;
+        json.field("function", self.function)?;
+        json.field("module", self.module)?;
+        json.field("service_name", self.service_name)?;
+        json.field("caller_function", self.caller_function)?;
+        json.field("caller_module", self.caller_module)?;
+        json.raw_field("duration_seconds", self.duration.as_secs_f64())?;
+        if let Some(result) = self.result {
+            json.field("result", result)?;
+        }
+        if let Some(error_type) = self.error_type {
+            json.field("error_type", error_type)?;
+        }
+        if let Some(objective_name) = self.objective_name {
+            json.field("objective_name", objective_name)?;
+        }
+        if let Some(trace_id) = &self.trace_id {
+            json.field("trace_id", trace_id)?;
+        }
+        json.finish()
+    }
+}
+
+/// Receives the events for the calls to instrumented functions.
+///
+/// This is called synchronously when each call finishes, so it should not block.
+pub trait EventSink: Send + Sync {
+    fn emit(&self, event: &CallEvent);
+
+    /// Write out any events that are buffered by the sink.
+    ///
+    /// This is called by [`autometrics::flush`](crate::flush) and [`autometrics::shutdown`](crate::shutdown).
+    fn flush(&self) {}
+}
+
+impl<F> EventSink for F
+where
+    F: Fn(&CallEvent) + Send + Sync,
+{
+    fn emit(&self, event: &CallEvent) {
+        self(event)
+    }
+}
+
+/// Emits each call as a [`tracing`](https://crates.io/crates/tracing) event with the target `autometrics::wide_events`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TracingSink;
+
+impl EventSink for TracingSink {
+    fn emit(&self, event: &CallEvent) {
+        tracing::info!(
+            target: "autometrics::wide_events",
+            function = event.function,
+            module = event.module,
+            service_name = event.service_name,
+            caller_function = event.caller_function,
+            caller_module = event.caller_module,
+            duration_seconds = event.duration.as_secs_f64(),
+            result = event.result,
+            error_type = event.error_type,
+            objective_name = event.objective_name,
+            trace_id = event.trace_id.as_deref(),
+        );
+    }
+}
+
+/// Writes each call as a line of JSON to the given writer, such as a [`File`](std::fs::File).
+///
+/// Errors while writing are ignored.
+#[derive(Debug)]
+pub struct JsonLinesSink<W> {
+    writer: Mutex<W>,
+}
+
+impl<W: Write> JsonLinesSink<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+        }
+    }
+
+    /// Get the writer back, for example to flush it.
+    pub fn into_inner(self) -> W {
+        self.writer
+            .into_inner()
+            .unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+impl<W: Write + Send> EventSink for JsonLinesSink<W> {
+    fn emit(&self, event: &CallEvent) {
+        let mut writer = poison::lock(&self.writer, "wide_events");
+        writeln!(writer, "{event}").ok();
+    }
+
+    fn flush(&self) {
+        poison::lock(&self.writer, "wide_events").flush().ok();
+    }
+}
+
+/// The configuration of the wide events, which is set with
+/// [`AutometricsSettingsBuilder::wide_events`](crate::settings::AutometricsSettingsBuilder::wide_events).
+pub struct WideEvents {
+    sink: Box<dyn EventSink>,
+    sample_rate: f64,
+    always_emit_errors: bool,
+}
+
+impl WideEvents {
+    /// Emit the events to the given sink.
+    pub fn new(sink: impl EventSink + 'static) -> Self {
+        Self {
+            sink: Box::new(sink),
+            sample_rate: 1.0,
+            always_emit_errors: false,
+        }
+    }
+
+    /// Only emit the given fraction (between 0 and 1) of the events. The default is to emit all of them.
+    pub fn sample_rate(mut self, sample_rate: f64) -> Self {
+        self.sample_rate = sample_rate.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Emit the events for calls that return errors regardless of the sample rate.
+    pub fn always_emit_errors(mut self, always_emit_errors: bool) -> Self {
+        self.always_emit_errors = always_emit_errors;
+        self
+    }
+
+    fn is_sampled(&self, event: &CallEvent) -> bool {
+        (self.always_emit_errors && event.is_error())
+            || self.sample_rate >= 1.0
+            || random_fraction() < self.sample_rate
+    }
+}
+
+impl fmt::Debug for WideEvents {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WideEvents")
+            .field("sample_rate", &self.sample_rate)
+            .field("always_emit_errors", &self.always_emit_errors)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Emit the event for a finished call, if wide events are enabled and the call is sampled
+pub(crate) fn emit(counter_labels: &CounterLabels, duration: Duration) {
+    let Some(wide_events) = &get_settings().wide_events else {
+        return;
+    };
+    if wide_events.sample_rate <= 0.0 && !wide_events.always_emit_errors {
+        return;
+    }
+
+    let event = CallEvent::new(counter_labels, duration);
+    if wide_events.is_sampled(&event) {
+        wide_events.sink.emit(&event);
+    }
+}
+
+/// Flush the sink of the wide events, if they are enabled
+pub(crate) fn flush() {
+    if let Some(wide_events) = &get_settings().wide_events {
+        wide_events.sink.flush();
+    }
+}
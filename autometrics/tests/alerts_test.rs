@@ -0,0 +1,91 @@
+#![cfg(feature = "alerts")]
+use autometrics::{alerts, autometrics, objectives::*};
+
+const API_SLO: Objective = Objective::new("api")
+    .success_rate(ObjectivePercentile::P99_9)
+    .latency(ObjectiveLatency::Ms250, ObjectivePercentile::P99);
+
+const BATCH_SLO: Objective = Objective::new("batch").success_rate(ObjectivePercentile::P95);
+
+#[autometrics(objective = API_SLO)]
+fn api_handler() {}
+
+#[autometrics(objective = API_SLO)]
+fn other_api_handler() {}
+
+#[test]
+fn generate_rules() {
+    api_handler();
+    other_api_handler();
+    alerts::register_objective(BATCH_SLO);
+    alerts::register_objective(API_SLO);
+
+    let rules = alerts::generate_rules();
+    assert!(rules.starts_with("groups:\n"));
+
+    // One group per objective name, category, and percentile
+    let groups: Vec<_> = rules
+        .lines()
+        .filter(|line| line.starts_with("  - name: "))
+        .collect();
+    assert_eq!(
+        groups,
+        [
+            r#"  - name: "autometrics-api-latency-99""#,
+            r#"  - name: "autometrics-api-success-rate-99_9""#,
+            r#"  - name: "autometrics-batch-success-rate-95""#,
+        ]
+    );
+
+    assert!(rules.contains(
+        r#"sum by (objective_name, objective_percentile, service_name) (rate(function_calls_total{objective_name="batch",objective_percentile="95",result="error"}[5m]))"#
+    ));
+    assert!(rules.contains(r#"function_calls_duration_seconds_bucket{objective_name="api",objective_percentile="99"}[3d]"#));
+
+    // 14.4 times the error budget of 0.1%
+    assert!(rules.contains(
+        r#"autometrics:slo_errors:ratio_rate1h{category="success-rate",objective_name="api",objective_percentile="99.9"} > 0.0144 and"#
+    ));
+    assert!(rules.contains("severity: page"));
+    assert!(rules.contains("severity: ticket"));
+}
+
+#[autometrics]
+fn caller_graph_handler() {
+    caller_graph_callee();
+}
+
+#[autometrics]
+fn caller_graph_callee() {}
+
+#[test]
+fn generate_caller_graph_rules() {
+    caller_graph_handler();
+
+    let rules = alerts::generate_caller_graph_rules();
+    assert!(rules.starts_with("groups:\n  - name: \"autometrics-caller-graph\"\n"));
+    assert!(rules.contains("      - record: autometrics:caller_graph:calls_rate5m\n"));
+    assert!(rules.contains("      - record: autometrics:caller_graph:error_ratio_rate1d\n"));
+    assert!(rules.contains(
+        "sum by (caller_function, caller_module, function, module, service_name) (rate(function_calls_total{"
+    ));
+
+    // Only the instrumented functions are included
+    let selector = rules
+        .lines()
+        .find_map(|line| line.split_once(r#"function=~""#))
+        .map(|(_, rest)| rest.split_once('"').unwrap().0)
+        .unwrap();
+    let functions: Vec<&str> = selector.split('|').collect();
+    assert!(functions.contains(&"caller_graph_handler"));
+    assert!(functions.contains(&"caller_graph_callee"));
+    assert!(rules.contains(&format!(
+        r#"rate(function_calls_total{{function=~"{selector}",result="error"}}[1h])"#
+    )));
+
+    // The combined file has the groups of both
+    let all_rules = alerts::generate_all_rules();
+    assert!(all_rules.contains(r#"  - name: "autometrics-api-success-rate-99_9""#));
+    assert!(all_rules.contains(r#"  - name: "autometrics-caller-graph""#));
+    assert_eq!(all_rules.matches("groups:").count(), 1);
+}
@@ -0,0 +1,32 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::settings::{AliasMode, AutometricsSettings};
+use autometrics::{autometrics, prometheus_exporter};
+
+/// This function used to be called `get_user`
+#[autometrics(alias = "get_user")]
+fn load_user() {}
+
+#[test]
+fn adds_previous_function_label() {
+    AutometricsSettings::builder()
+        .alias_mode(AliasMode::Label)
+        .init();
+    prometheus_exporter::try_init().ok();
+
+    load_user();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    for metric in [
+        "function_calls_total{",
+        "function_calls_duration_seconds_count{",
+    ] {
+        assert!(
+            metrics.lines().any(|line| line.starts_with(metric)
+                && line.contains(r#"function="load_user""#)
+                && line.contains(r#"previous_function="get_user""#)),
+            "{metrics}"
+        );
+    }
+    assert!(!metrics.contains("function_renamed_info{"), "{metrics}");
+}
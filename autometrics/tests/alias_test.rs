@@ -0,0 +1,33 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::{autometrics, prometheus_exporter};
+
+/// This function used to be called `get_user`
+#[autometrics(alias = "get_user")]
+fn load_user() {}
+
+#[test]
+fn reports_previous_function_name() {
+    prometheus_exporter::try_init().ok();
+
+    load_user();
+    load_user();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    let renamed_info: Vec<_> = metrics
+        .lines()
+        .filter(|line| line.starts_with("function_renamed_info{"))
+        .collect();
+    assert_eq!(renamed_info.len(), 1, "{metrics}");
+    assert!(renamed_info[0].contains(r#"function="load_user""#));
+    assert!(renamed_info[0].contains(r#"previous_function="get_user""#));
+    assert!(renamed_info[0].ends_with(" 1"));
+
+    // The function's own series are not changed
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="load_user""#)
+            && !line.contains("previous_function")
+            && line.ends_with(" 2")
+    }));
+}
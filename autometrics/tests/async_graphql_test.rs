@@ -0,0 +1,82 @@
+#![cfg(all(feature = "async-graphql", prometheus_exporter))]
+use async_graphql::{EmptyMutation, EmptySubscription, Object, Schema, SimpleObject};
+use autometrics::{async_graphql::Autometrics, autometrics, objectives::*, prometheus_exporter};
+
+const API_SLO: Objective = Objective::new("graphql").success_rate(ObjectivePercentile::P99);
+
+#[autometrics]
+fn load_user_name(id: u64) -> String {
+    format!("User {id}")
+}
+
+#[derive(SimpleObject)]
+struct User {
+    name: String,
+}
+
+struct Query;
+
+#[Object]
+impl Query {
+    async fn user(&self, id: u64) -> User {
+        User {
+            name: load_user_name(id),
+        }
+    }
+
+    async fn fail(&self) -> async_graphql::Result<bool> {
+        Err("failed".into())
+    }
+}
+
+#[tokio::test]
+async fn resolvers() {
+    prometheus_exporter::try_init().ok();
+
+    let schema = Schema::build(Query, EmptyMutation, EmptySubscription)
+        .extension(Autometrics::new().objective(API_SLO))
+        .finish();
+
+    for query in [
+        "{ user(id: 1) { name } }",
+        "{ user(id: 2) { name } }",
+        "{ fail }",
+        "{ __schema { queryType { name } } }",
+    ] {
+        schema.execute(query).await;
+    }
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="Query.user""#)
+            && line.contains(r#"module="async_graphql""#)
+            && line.contains(r#"result="ok""#)
+            && line.contains(r#"objective_name="graphql""#)
+            && line.ends_with("} 2")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="Query.fail""#)
+            && line.contains(r#"result="error""#)
+            && line.ends_with("} 1")
+    }));
+    // Nested resolvers and instrumented functions are reported as being called by the resolver
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="User.name""#)
+            && line.contains(r#"caller_function="Query.user""#)
+            && line.ends_with("} 2")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="load_user_name""#)
+            && line.contains(r#"caller_function="Query.user""#)
+            && line.ends_with("} 2")
+    }));
+    assert!(!metrics.contains("__schema"));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_duration_seconds_bucket{")
+            && line.contains(r#"function="Query.user""#)
+    }));
+}
@@ -0,0 +1,110 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::{assert_counter, autometrics, autometrics_test, testing::TestMetrics};
+
+#[autometrics]
+fn lookup(id: u64) -> Result<u64, String> {
+    if id == 0 {
+        Err("not found".to_string())
+    } else {
+        Ok(id)
+    }
+}
+
+#[autometrics]
+fn validate(id: u64) -> Result<u64, String> {
+    Ok(id)
+}
+
+#[autometrics]
+async fn fetch(id: u64) -> Result<u64, String> {
+    validate(id)
+}
+
+#[autometrics]
+fn remove(id: u64) -> Result<u64, String> {
+    Err(format!("cannot remove {id}"))
+}
+
+#[autometrics]
+async fn load(id: u64) -> Result<u64, String> {
+    Ok(id)
+}
+
+#[autometrics_test]
+fn sync_test(metrics: TestMetrics) {
+    lookup(1).unwrap();
+    lookup(0).unwrap_err();
+
+    assert_eq!(metrics.calls("lookup"), 2);
+    assert_eq!(metrics.successes("lookup"), 1);
+    assert_eq!(metrics.errors("lookup"), 1);
+    assert_eq!(metrics.timed_calls("lookup"), 2);
+}
+
+#[autometrics_test]
+async fn async_test_without_runtime(metrics: TestMetrics) {
+    fetch(2).await.unwrap();
+
+    assert_eq!(metrics.calls("fetch"), 1);
+    assert_eq!(metrics.calls_from("validate", "fetch"), 1);
+}
+
+#[autometrics_test]
+#[tokio::test]
+async fn async_test_with_runtime(metrics: TestMetrics) {
+    tokio::task::yield_now().await;
+    load(3).await.unwrap();
+
+    assert_eq!(
+        metrics.value(
+            "function_calls_total",
+            &[("function", "load"), ("result", "ok")]
+        ),
+        1.0
+    );
+}
+
+#[autometrics_test]
+fn without_argument() -> Result<(), String> {
+    validate(4)?;
+    Ok(())
+}
+
+#[autometrics_test]
+fn assert_counter_macro(mut metrics: TestMetrics) {
+    remove(1).unwrap_err();
+    remove(2).unwrap_err();
+
+    assert_counter!(metrics, function = "remove", value = 2);
+    assert_counter!(
+        metrics,
+        function = "remove",
+        module = module_path!(),
+        result = "error",
+        value = 2,
+    );
+    assert_counter!(metrics, function = "remove", result = "ok", value = 0);
+
+    metrics.reset();
+    assert_counter!(metrics, function = "remove", value = 0);
+    remove(3).unwrap_err();
+    assert_counter!(metrics, function = "remove", value = 1);
+}
+
+#[test]
+fn assert_counter_failure_message() {
+    let metrics = TestMetrics::start();
+    lookup(5).unwrap();
+
+    let message = std::panic::catch_unwind(|| {
+        assert_counter!(metrics, function = "lookup", result = "error", value = 1);
+    })
+    .unwrap_err();
+    let message = message.downcast_ref::<String>().unwrap();
+    assert!(message.starts_with(
+        r#"expected function_calls_total{function="lookup",result="error"} to increase by 1"#
+    ));
+    assert!(message.contains(r#"function="lookup""#));
+    assert!(message.contains(r#"result="ok""#));
+}
@@ -0,0 +1,105 @@
+#![cfg(feature = "axum")]
+use autometrics::integrations::axum::{route_autometrics, RouterExt};
+use autometrics::objectives::{Objective, ObjectivePercentile};
+use autometrics::{autometrics, prometheus_exporter};
+use axum::{body::Body, http::Request, http::StatusCode, routing::get, Router};
+use tower::ServiceExt;
+
+#[autometrics]
+async fn axum_handler() -> &'static str {
+    "Hello world!"
+}
+
+async fn axum_failing_handler() -> StatusCode {
+    StatusCode::INTERNAL_SERVER_ERROR
+}
+
+#[tokio::test]
+async fn router_ext() {
+    prometheus_exporter::try_init().ok();
+
+    let app: Router = Router::new()
+        .route("/users/:id", get(axum_handler))
+        .route("/fail", get(axum_failing_handler))
+        .with_autometrics();
+
+    for uri in ["/users/1", "/users/2", "/fail"] {
+        app.clone()
+            .oneshot(Request::get(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+    }
+
+    let response = app
+        .oneshot(Request::get("/metrics").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    let metrics = String::from_utf8(body.to_vec()).unwrap();
+
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="/users/:id""#)
+            && line.contains(r#"module="http""#)
+            && line.contains(r#"result="ok""#)
+            && line.ends_with("} 2")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="/fail""#)
+            && line.contains(r#"result="error""#)
+            && line.ends_with("} 1")
+    }));
+    // The handler is reported as being called by the route
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="axum_handler""#)
+            && line.contains(r#"caller_function="/users/:id""#)
+            && line.ends_with("} 2")
+    }));
+    assert!(!metrics.contains(r#"function="/metrics""#));
+}
+
+const ROUTES_SLO: Objective = Objective::new("routes").success_rate(ObjectivePercentile::P99);
+
+#[tokio::test]
+async fn route_autometrics_by_method() {
+    prometheus_exporter::try_init().ok();
+
+    let app: Router = Router::new()
+        .route(
+            "/orders/:id",
+            get(|| async { "order" }).delete(axum_failing_handler),
+        )
+        .route_layer(route_autometrics().objective(ROUTES_SLO));
+
+    for request in [
+        Request::get("/orders/1"),
+        Request::get("/orders/2"),
+        Request::delete("/orders/1"),
+    ] {
+        app.clone()
+            .oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+    }
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="GET /orders/:id""#)
+            && line.contains(r#"module="http""#)
+            && line.contains(r#"objective_name="routes""#)
+            && line.contains(r#"result="ok""#)
+            && line.ends_with("} 2")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="DELETE /orders/:id""#)
+            && line.contains(r#"result="error""#)
+            && line.ends_with("} 1")
+    }));
+}
@@ -0,0 +1,55 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::settings::{AutometricsSettings, BuildInfo};
+use autometrics::{autometrics, build, prometheus_exporter};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[autometrics]
+fn function_just_to_initialize_build_info() {}
+
+#[test]
+fn build_timestamp_is_exported() {
+    // This would be set by `build::emit_build_timestamp` in the build script of the instrumented crate
+    let build_timestamp = build::build_timestamp() - 3600;
+    let build_timestamp: &'static str = Box::leak(build_timestamp.to_string().into_boxed_str());
+    // The functions of a crate built without a timestamp may be called first
+    autometrics::__private::set_build_timestamp(None);
+    autometrics::__private::set_build_timestamp(Some(build_timestamp));
+    AutometricsSettings::builder()
+        .build_info(BuildInfo {
+            build_timestamp: Some(build_timestamp.to_string()),
+            ..Default::default()
+        })
+        .init();
+
+    function_just_to_initialize_build_info();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(
+        metrics.lines().any(|line| line.starts_with("build_info{")
+            && line.contains(&format!(r#"build_timestamp="{build_timestamp}""#))),
+        "{metrics}"
+    );
+    assert!(
+        metrics.contains("# TYPE build_age_seconds gauge"),
+        "{metrics}"
+    );
+    let build_age: f64 = metrics
+        .lines()
+        .find(|line| line.starts_with("build_age_seconds"))
+        .and_then(|line| line.rsplit(' ').next())
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_else(|| panic!("No build_age_seconds gauge in {metrics}"));
+    assert!((3600.0..3660.0).contains(&build_age), "{build_age}");
+}
+
+#[test]
+fn build_timestamp_is_the_current_time() {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    if std::env::var_os("SOURCE_DATE_EPOCH").is_none() {
+        assert!(build::build_timestamp().abs_diff(now) < 60);
+    }
+}
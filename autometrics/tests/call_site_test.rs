@@ -0,0 +1,56 @@
+#![cfg(all(prometheus_exporter, prometheus))]
+
+use autometrics::__private::{
+    settings, AutometricsTracker, CallSite, CounterLabels, HistogramLabels, TrackMetrics,
+};
+use autometrics::{prometheus_exporter, registry};
+
+static KEPT_CALL_SITE: CallSite = CallSite::new();
+static REMOVED_CALL_SITE: CallSite = CallSite::new();
+
+fn record_call(function: &'static str, call_site: &'static CallSite) {
+    let settings = settings();
+    let counter_labels = CounterLabels::new(settings, function, module_path!(), "", "", None, None)
+        .with_call_site(call_site);
+    let histogram_labels =
+        HistogramLabels::new(settings, function, module_path!(), None).with_call_site(call_site);
+    AutometricsTracker::start(None).finish(&counter_labels, &histogram_labels);
+}
+
+fn calls(function: &str) -> Option<u64> {
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    metrics
+        .lines()
+        .find(|line| {
+            line.starts_with("function_calls_total{")
+                && line.contains(&format!(r#"function="{function}""#))
+        })
+        .and_then(|line| line.rsplit(' ').next()?.parse().ok())
+}
+
+#[test]
+fn removing_a_function_keeps_the_handles_of_other_call_sites() {
+    prometheus_exporter::try_init().ok();
+
+    record_call("call_site_kept", &KEPT_CALL_SITE);
+    record_call("call_site_removed", &REMOVED_CALL_SITE);
+
+    assert!(registry::remove_function("call_site_removed") > 0);
+    assert_eq!(calls("call_site_removed"), None);
+
+    record_call("call_site_kept", &KEPT_CALL_SITE);
+    // Removing the other function does not affect the series of this one
+    assert_eq!(calls("call_site_kept"), Some(2));
+
+    // The call site of the removed function records new series
+    record_call("call_site_removed", &REMOVED_CALL_SITE);
+    assert_eq!(calls("call_site_removed"), Some(1));
+
+    // Once all of its slots are used up, the call site looks up the series by their labels
+    for _ in 0..3 {
+        assert!(registry::remove_function("call_site_removed") > 0);
+        record_call("call_site_removed", &REMOVED_CALL_SITE);
+        record_call("call_site_removed", &REMOVED_CALL_SITE);
+        assert_eq!(calls("call_site_removed"), Some(2));
+    }
+}
@@ -0,0 +1,26 @@
+use autometrics::{capabilities, Backend};
+
+#[test]
+fn compiled_features() {
+    let capabilities = capabilities();
+
+    #[cfg(prometheus_client)]
+    assert_eq!(capabilities.backend, Some(Backend::PrometheusClient));
+    #[cfg(prometheus)]
+    assert_eq!(capabilities.backend, Some(Backend::Prometheus));
+    #[cfg(opentelemetry)]
+    assert_eq!(capabilities.backend, Some(Backend::OpenTelemetry));
+    #[cfg(metrics)]
+    assert_eq!(capabilities.backend, Some(Backend::Metrics));
+    #[cfg(not(any(metrics, opentelemetry, prometheus, prometheus_client)))]
+    assert_eq!(capabilities.backend, None);
+
+    assert_eq!(capabilities.exemplars.is_some(), cfg!(exemplars));
+    assert_eq!(capabilities.prometheus_exporter, cfg!(prometheus_exporter));
+    assert_eq!(
+        capabilities.per_function_buckets,
+        cfg!(any(prometheus, prometheus_client))
+    );
+    assert_eq!(capabilities.instrumented_functions, cfg!(function_registry));
+    assert_eq!(capabilities.tokio, cfg!(feature = "tokio"));
+}
@@ -8,6 +8,33 @@ fn harness() {
     t.pass("tests/compilation/result_labels/pass/*.rs");
     t.compile_fail("tests/compilation/result_labels/fail/*.rs");
 
+    // Test that async_trait methods are instrumented regardless of the order of the attributes
+    t.pass("tests/compilation/async_trait/pass/*.rs");
+
+    // Test that the return types of instrumented functions are handled correctly
+    t.pass("tests/compilation/return_types/pass/*.rs");
+
+    // Test generics, `impl Trait` arguments, unsafe and extern functions, and methods with every kind of receiver
+    t.pass("tests/compilation/signatures/pass/*.rs");
+
+    // Test that items other than functions, impl blocks, and traits are rejected
+    t.compile_fail("tests/compilation/items/fail/*.rs");
+
+    // Test that invalid histogram buckets are rejected
+    t.compile_fail("tests/compilation/buckets/fail/*.rs");
+
+    // Test that the histogram buckets can be combined with other arguments if a backend supports them,
+    // and that they are rejected if only the backends that configure the buckets per metric are enabled
+    #[cfg(any(prometheus, prometheus_client, not(any(metrics, opentelemetry))))]
+    t.pass("tests/compilation/buckets/pass/*.rs");
+    #[cfg(all(any(metrics, opentelemetry), not(any(prometheus, prometheus_client))))]
+    t.compile_fail("tests/compilation/buckets/unsupported_backend/*.rs");
+
+    // Test that the macro arguments can be combined, and that invalid or conflicting
+    // arguments are reported at the right location
+    t.pass("tests/compilation/args/pass/*.rs");
+    t.compile_fail("tests/compilation/args/fail/*.rs");
+
     // Test that compiler reports errors in the correct location
     t.compile_fail("tests/compilation/error_locus/fail/*.rs");
 }
@@ -0,0 +1,42 @@
+use autometrics::autometrics;
+
+#[autometrics(ok_if = Result::is_ok, error_if = Result::is_err)]
+fn ok_if_and_error_if() -> Result<(), ()> {
+    Ok(())
+}
+
+#[autometrics(buckets = [0.1, 1], fine_grained_latency)]
+fn buckets_and_fine_grained_latency() {}
+
+#[autometrics(count_only, buckets = [0.1, 1])]
+fn count_only_with_buckets() {}
+
+#[autometrics(count_only, track_size = |_: &()| 0)]
+fn count_only_with_track_size() {}
+
+#[autometrics(count_only, span_events)]
+fn count_only_with_span_events() {}
+
+#[autometrics(span_events, span_events)]
+fn duplicate_span_events() {}
+
+#[autometrics(objective = API_SLO, objective = API_SLO)]
+fn duplicate_objective() {}
+
+#[autometrics(no_caller, recursive = label)]
+fn no_caller_with_recursive() {}
+
+#[autometrics(sample_rate = "high")]
+fn sample_rate_not_a_number() {}
+
+fn main() {
+    ok_if_and_error_if().ok();
+    buckets_and_fine_grained_latency();
+    count_only_with_buckets();
+    count_only_with_track_size();
+    count_only_with_span_events();
+    duplicate_span_events();
+    duplicate_objective();
+    no_caller_with_recursive();
+    sample_rate_not_a_number();
+}
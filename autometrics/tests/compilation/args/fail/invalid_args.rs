@@ -0,0 +1,108 @@
+use autometrics::autometrics;
+
+#[autometrics(track_concurrency, track_concurrency)]
+fn duplicate_argument() {}
+
+#[autometrics(track_concurrency error_details)]
+fn missing_comma() {}
+
+#[autometrics(unknown_argument)]
+fn unknown_argument() {}
+
+#[autometrics(objective(success_rate = 99.9))]
+fn objective_without_name() {}
+
+#[autometrics(objective(name = "api"))]
+fn objective_without_targets() {}
+
+#[autometrics(objective(name = "api", success_rate = 101))]
+fn invalid_percentile() {}
+
+#[autometrics(objective(name = "api", latency(threshold = 0.25)))]
+fn latency_without_percentile() {}
+
+#[autometrics(objective(name = "api", success_rate = "fast"))]
+fn not_a_number() {}
+
+#[autometrics(sample_rate = 1.5)]
+fn invalid_sample_rate() {}
+
+#[autometrics(ok_if = true)]
+fn literal_predicate() -> bool {
+    true
+}
+
+#[autometrics(error_if = |a, b| a == b)]
+fn closure_with_two_arguments() -> bool {
+    true
+}
+
+#[autometrics(ok_if_status + 500)]
+fn status_without_comparison() -> u16 {
+    200
+}
+
+#[autometrics(error_if_status = 500)]
+fn status_with_equals_sign() -> u16 {
+    200
+}
+
+#[autometrics(count_only, track_concurrency)]
+fn count_only_with_concurrency() {}
+
+#[autometrics(count_only, sample_rate = 0.5)]
+fn count_only_with_sampling() {}
+
+#[autometrics(track_concurrency(min))]
+fn unknown_concurrency_mode() {}
+
+#[autometrics(label(key = "result", value = "cached"))]
+fn reserved_label_key() {}
+
+#[autometrics(label(key = "shard-id", value = "a"))]
+fn invalid_label_key() {}
+
+#[autometrics(label(key = "a", value = "1"), label(key = "a", value = "2"))]
+fn duplicate_label() {}
+
+#[autometrics(label(key = "a", value = "1"), label(key = "b", value = "2"), label(key = "c", value = "3"))]
+fn too_many_labels() {}
+
+#[autometrics(label(key = "shard", value = String::new()))]
+fn label_value_not_static() {}
+
+#[autometrics(alias = "unchanged_alias")]
+fn unchanged_alias() {}
+
+struct Database;
+
+#[autometrics(alias = "Database::get_user")]
+impl Database {
+    fn load_user(&self) {}
+}
+
+fn main() {
+    duplicate_argument();
+    missing_comma();
+    unknown_argument();
+    objective_without_name();
+    objective_without_targets();
+    invalid_percentile();
+    latency_without_percentile();
+    not_a_number();
+    invalid_sample_rate();
+    literal_predicate();
+    closure_with_two_arguments();
+    status_without_comparison();
+    status_with_equals_sign();
+    count_only_with_concurrency();
+    count_only_with_sampling();
+    unknown_concurrency_mode();
+    reserved_label_key();
+    invalid_label_key();
+    duplicate_label();
+    too_many_labels();
+    label_value_not_static();
+    unchanged_alias();
+    let _ = Database;
+}
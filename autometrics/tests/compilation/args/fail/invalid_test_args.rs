@@ -0,0 +1,9 @@
+use autometrics::autometrics_test;
+
+#[autometrics_test(isolated)]
+fn unknown_argument() {}
+
+#[autometrics_test]
+fn too_many_arguments(metrics: autometrics::testing::TestMetrics, other: u64) {}
+
+fn main() {}
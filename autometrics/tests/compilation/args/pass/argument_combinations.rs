@@ -0,0 +1,141 @@
+use autometrics::autometrics;
+use autometrics::objectives::{Objective, ObjectiveLatency, ObjectivePercentile};
+
+const API_SLO: Objective = Objective::new("api")
+    .success_rate(ObjectivePercentile::P99_9)
+    .latency(ObjectiveLatency::Ms250, ObjectivePercentile::P99);
+
+struct Response {
+    status: u16,
+    body: Vec<u8>,
+}
+
+impl autometrics::ResultStatus for Response {
+    fn status_code(&self) -> u16 {
+        self.status
+    }
+}
+
+#[autometrics(objective = API_SLO)]
+fn objective_constant() -> Result<(), ()> {
+    Ok(())
+}
+
+#[autometrics(objective(name = "api", success_rate = 99.9, latency(threshold = 0.25, percentile = 99)))]
+fn inline_objective() -> Result<(), ()> {
+    Ok(())
+}
+
+#[autometrics(track_concurrency(max))]
+fn concurrency() {}
+
+#[autometrics(track_concurrency, recursive = skip_caller, error_details)]
+fn recursion(n: u32) -> Result<u32, String> {
+    if n == 0 {
+        Ok(0)
+    } else {
+        recursion(n - 1)
+    }
+}
+
+#[autometrics(recursive = label)]
+fn recursion_label() {}
+
+#[autometrics(error_if = Option::is_none, fine_grained_latency)]
+fn error_if_with_fine_grained_latency() -> Option<u32> {
+    Some(1)
+}
+
+#[autometrics(ok_if_status < 400, track_size = |response: &Response| response.body.len())]
+fn status_and_size() -> Response {
+    Response {
+        status: 200,
+        body: Vec::new(),
+    }
+}
+
+#[autometrics(error_if_status in [500, 503], span_events, objective = API_SLO)]
+fn status_list_with_span_events() -> Response {
+    Response {
+        status: 503,
+        body: Vec::new(),
+    }
+}
+
+#[autometrics(sample_rate = 0.1, skip_if = || false)]
+fn sampled() {}
+
+#[autometrics(count_only, ok_if = Result::is_ok, objective = API_SLO, error_details)]
+fn count_only() -> Result<(), String> {
+    Ok(())
+}
+
+#[autometrics(generic_params, inline_hint)]
+fn generic_params<T: Default>() -> T {
+    T::default()
+}
+
+#[autometrics(no_caller, count_only)]
+fn no_caller_count_only() {}
+
+#[autometrics(no_caller, track_concurrency)]
+async fn no_caller_async() {}
+
+#[autometrics(no_docs, count_only)]
+fn no_docs_count_only() {}
+
+struct Service;
+
+struct Shard {
+    id: &'static str,
+}
+
+impl Shard {
+    #[autometrics(label(key = "shard", value = self.id), label(key = "cached", value = cached))]
+    fn consume(self, cached: bool) -> Result<(), ()> {
+        drop(self);
+        Ok(())
+    }
+
+    #[autometrics(label(key = "shard", value = self.id), count_only)]
+    async fn consume_async(self) {}
+}
+
+#[autometrics(struct_name = "Service", track_concurrency)]
+fn explicit_struct_name() {}
+
+#[autometrics(struct_name = "Service", alias = "Service::old_name", label(key = "shard", value = "a"))]
+fn renamed_method() {}
+
+#[autometrics(objective = API_SLO, track_concurrency)]
+impl Service {
+    fn method(&self) -> Result<(), ()> {
+        Ok(())
+    }
+
+    #[skip_autometrics]
+    fn skipped(&self) {}
+}
+
+fn main() {
+    objective_constant().ok();
+    inline_objective().ok();
+    concurrency();
+    recursion(2).ok();
+    recursion_label();
+    error_if_with_fine_grained_latency();
+    status_and_size();
+    status_list_with_span_events();
+    sampled();
+    count_only().ok();
+    generic_params::<u32>();
+    no_caller_count_only();
+    drop(no_caller_async());
+    no_docs_count_only();
+    explicit_struct_name();
+    renamed_method();
+    Shard { id: "a" }.consume(true).ok();
+    drop(Shard { id: "b" }.consume_async());
+    Service.method().ok();
+    Service.skipped();
+}
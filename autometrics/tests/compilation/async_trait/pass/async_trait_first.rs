@@ -0,0 +1,51 @@
+use async_trait::async_trait;
+use autometrics::autometrics;
+
+#[async_trait]
+#[autometrics]
+trait TestTrait {
+    async fn method() -> bool {
+        true
+    }
+
+    async fn self_method(&self) -> Result<&'static str, String>;
+}
+
+#[derive(Default)]
+struct TestStruct;
+
+#[async_trait]
+#[autometrics]
+impl TestTrait for TestStruct {
+    async fn self_method(&self) -> Result<&'static str, String> {
+        Ok("hello")
+    }
+}
+
+#[async_trait(?Send)]
+trait LocalTrait {
+    async fn local_method(&self) -> std::rc::Rc<u32>;
+}
+
+#[async_trait(?Send)]
+#[autometrics]
+impl LocalTrait for TestStruct {
+    async fn local_method(&self) -> std::rc::Rc<u32> {
+        std::rc::Rc::new(1)
+    }
+}
+
+fn assert_send<T: Send>(_: T) {}
+
+fn main() {
+    let ts = TestStruct::default();
+
+    assert_send(async move {
+        <TestStruct as TestTrait>::method().await;
+        ts.self_method().await.ok();
+    });
+
+    let _ = async move {
+        TestStruct.local_method().await;
+    };
+}
@@ -0,0 +1,33 @@
+use autometrics::autometrics;
+// The attribute is recognized by the shape of the generated code rather than by its name
+use async_trait::async_trait as boxed_async;
+
+#[boxed_async]
+trait TestTrait {
+    async fn method() -> bool;
+    async fn self_method(&self) -> Result<&'static str, String>;
+}
+
+#[derive(Default)]
+struct TestStruct;
+
+#[autometrics]
+#[boxed_async]
+impl TestTrait for TestStruct {
+    async fn method() -> bool {
+        true
+    }
+
+    async fn self_method(&self) -> Result<&'static str, String> {
+        Ok("hello")
+    }
+}
+
+fn main() {
+    let ts = TestStruct::default();
+
+    let _ = async move {
+        <TestStruct as TestTrait>::method().await;
+        ts.self_method().await.ok();
+    };
+}
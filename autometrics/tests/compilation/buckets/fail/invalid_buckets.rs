@@ -0,0 +1,20 @@
+use autometrics::autometrics;
+
+#[autometrics(buckets = [0.01, 0.005])]
+fn unordered_buckets() {}
+
+#[autometrics(buckets = [])]
+fn empty_buckets() {}
+
+#[autometrics(buckets = ["0.01"])]
+fn string_buckets() {}
+
+#[autometrics(buckets = [0.01], fine_grained_latency)]
+fn buckets_and_fine_grained_latency() {}
+
+fn main() {
+    unordered_buckets();
+    empty_buckets();
+    string_buckets();
+    buckets_and_fine_grained_latency();
+}
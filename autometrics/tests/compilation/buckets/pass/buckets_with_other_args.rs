@@ -0,0 +1,20 @@
+use autometrics::autometrics;
+
+#[autometrics(ok_if = |result: &Result<u16, ()>| result.is_ok(), buckets = [0.1, 0.5, 1])]
+fn ok_if_with_buckets() -> Result<u16, ()> {
+    Ok(200)
+}
+
+struct Cache;
+
+#[autometrics(buckets = [0.0001, 0.001])]
+impl Cache {
+    fn get(&self) -> Option<u32> {
+        None
+    }
+}
+
+fn main() {
+    ok_if_with_buckets().ok();
+    Cache.get();
+}
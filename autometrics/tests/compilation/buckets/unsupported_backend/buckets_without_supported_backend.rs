@@ -0,0 +1,8 @@
+use autometrics::autometrics;
+
+#[autometrics(buckets = [0.001, 0.005, 0.01])]
+fn lookup_cache() {}
+
+fn main() {
+    lookup_cache();
+}
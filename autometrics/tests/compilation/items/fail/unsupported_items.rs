@@ -0,0 +1,13 @@
+use autometrics::autometrics;
+
+#[autometrics]
+struct NotAFunction;
+
+#[autometrics]
+const fn const_function() -> u32 {
+    1
+}
+
+fn main() {
+    const_function();
+}
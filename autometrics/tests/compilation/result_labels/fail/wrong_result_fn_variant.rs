@@ -0,0 +1,17 @@
+// This test ensures that the macro fails with a readable
+// error when `result_fn` is used on a variant that does not
+// have exactly one field to pass to the function.
+use autometrics_macros::ResultLabels;
+
+fn classify(_: &u64) -> Option<&'static str> {
+    None
+}
+
+#[derive(ResultLabels)]
+enum MyError {
+    Empty,
+    #[label(result_fn = classify)]
+    ServerError(u64, u64),
+}
+
+fn main() {}
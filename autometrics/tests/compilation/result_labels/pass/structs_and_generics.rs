@@ -0,0 +1,82 @@
+//! This test uses interfaces not meant to be directly used.
+//!
+//! It checks the labels of structs, and of generic enums whose variants
+//! compute the label from their inner value with `result_fn`.
+use autometrics::get_result_labels_for_value;
+use autometrics_macros::ResultLabels;
+
+/// A struct is always labelled with the result set on the type.
+#[derive(ResultLabels)]
+#[label(result = "ok")]
+struct NotFound;
+
+/// Or with the result computed by the function from the whole value.
+#[derive(ResultLabels)]
+#[label(result_fn = Status::label)]
+struct Status(u16);
+
+impl Status {
+    fn label(&self) -> Option<&'static str> {
+        Some(if self.0 >= 500 { "error" } else { "ok" })
+    }
+}
+
+/// Structs without the attribute are labelled from the context.
+#[derive(ResultLabels)]
+struct Plain {
+    _value: u64,
+}
+
+fn classify_status(status: &u16) -> Option<&'static str> {
+    Status(*status).label()
+}
+
+fn classify_inner<T: AsRef<str>>(inner: &T) -> Option<&'static str> {
+    (inner.as_ref() == "retry").then_some("ok")
+}
+
+#[derive(ResultLabels)]
+enum Response<T: AsRef<str>, E> {
+    #[label(result_fn = classify_status)]
+    Http(u16),
+    #[label(result_fn = classify_inner)]
+    Message { text: T },
+    #[label(result = "error")]
+    Failed(E, E),
+    Other(T),
+}
+
+fn main() {
+    assert_eq!(get_result_labels_for_value!(&NotFound).unwrap().0, "ok");
+    let not_found: Result<(), NotFound> = Err(NotFound);
+    assert_eq!(get_result_labels_for_value!(&not_found).unwrap().0, "ok");
+
+    assert_eq!(get_result_labels_for_value!(&Status(503)).unwrap().0, "error");
+    assert_eq!(get_result_labels_for_value!(&Status(404)).unwrap().0, "ok");
+
+    assert_eq!(get_result_labels_for_value!(&Plain { _value: 1 }), None);
+    let plain: Result<(), Plain> = Err(Plain { _value: 1 });
+    assert_eq!(get_result_labels_for_value!(&plain).unwrap().0, "error");
+
+    let server_error: Response<String, ()> = Response::Http(500);
+    assert_eq!(
+        get_result_labels_for_value!(&server_error).unwrap().0,
+        "error"
+    );
+    let ok: Response<String, ()> = Response::Http(200);
+    assert_eq!(get_result_labels_for_value!(&ok).unwrap().0, "ok");
+
+    let retry: Result<(), Response<&str, ()>> = Err(Response::Message { text: "retry" });
+    assert_eq!(get_result_labels_for_value!(&retry).unwrap().0, "ok");
+    // Without an opinion from the function, the label comes from the context
+    let other_message: Result<(), Response<&str, ()>> = Err(Response::Message { text: "other" });
+    assert_eq!(
+        get_result_labels_for_value!(&other_message).unwrap().0,
+        "error"
+    );
+
+    let failed: Response<String, u8> = Response::Failed(1, 2);
+    assert_eq!(get_result_labels_for_value!(&failed).unwrap().0, "error");
+    let other: Response<String, u8> = Response::Other(String::new());
+    assert_eq!(get_result_labels_for_value!(&other), None);
+}
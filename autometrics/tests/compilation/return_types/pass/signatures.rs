@@ -0,0 +1,94 @@
+use autometrics::autometrics;
+use std::fmt::{Debug, Display};
+
+// `impl Trait` nested inside of other types
+#[autometrics]
+fn nested_impl_trait<'a>(words: &'a str) -> Result<Vec<impl Display + 'a>, String> {
+    Ok(words.split(' ').collect())
+}
+
+#[autometrics]
+fn tuple_with_impl_trait(word: &str) -> (impl Display + '_, usize) {
+    (word, word.len())
+}
+
+#[autometrics]
+fn reference_to_impl_trait<'a>(word: &'a String) -> &'a impl Display {
+    word
+}
+
+#[autometrics]
+fn array_of_impl_trait() -> [impl Display; 2] {
+    [1, 2]
+}
+
+// Elided lifetimes captured by the return type
+#[autometrics]
+fn elided_lifetime(word: &str) -> Option<impl AsRef<str> + '_> {
+    word.split(' ').next()
+}
+
+#[autometrics]
+fn boxed_iterator<'a, T>(items: &'a [T]) -> Box<dyn Iterator<Item = &'a T> + 'a>
+where
+    T: Debug,
+{
+    Box::new(items.iter())
+}
+
+// Qualified paths and absolute paths
+#[autometrics]
+fn qualified_path<I>(mut iter: I) -> <I as Iterator>::Item
+where
+    I: Iterator,
+    I::Item: Default,
+{
+    iter.next().unwrap_or_default()
+}
+
+#[autometrics]
+fn absolute_path(input: &str) -> ::std::result::Result<u32, ::std::num::ParseIntError> {
+    input.parse()
+}
+
+// The return type is needed to infer the type of the body
+#[autometrics]
+fn inferred_collect(items: &[u32]) -> Vec<u32> {
+    items.iter().map(|item| item * 2).collect()
+}
+
+#[autometrics]
+async fn async_nested_impl_trait<'a>(words: &'a str) -> Result<Option<impl Display + 'a>, String> {
+    Ok(words.split(' ').last())
+}
+
+struct Store {
+    values: Vec<String>,
+}
+
+#[autometrics]
+impl Store {
+    fn values(&self) -> impl Iterator<Item = &String> + '_ {
+        self.values.iter()
+    }
+
+    fn first(&self) -> Option<(&Self, impl Display + '_)> {
+        self.values.first().map(|value| (self, value))
+    }
+}
+
+fn main() {
+    nested_impl_trait("hello world").ok();
+    tuple_with_impl_trait("hello");
+    reference_to_impl_trait(&String::new());
+    array_of_impl_trait();
+    elided_lifetime("hello");
+    boxed_iterator(&[1, 2, 3]).count();
+    qualified_path(vec![1u32].into_iter());
+    absolute_path("1").ok();
+    inferred_collect(&[1, 2, 3]);
+    drop(async_nested_impl_trait("hello"));
+    let store = Store { values: Vec::new() };
+    store.values().count();
+    store.first();
+}
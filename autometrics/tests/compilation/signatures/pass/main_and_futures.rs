@@ -0,0 +1,62 @@
+use autometrics::autometrics;
+use std::future::{ready, Future};
+use std::pin::Pin;
+use std::process;
+
+// Futures that borrow from the arguments
+#[autometrics]
+fn borrowed_future<'a>(value: &'a str) -> impl Future<Output = &'a str> + Send + 'a {
+    async move { value }
+}
+
+// The instrumented future is boxed to keep it `Unpin`
+#[autometrics]
+fn unpin_future() -> impl Future<Output = u32> + Unpin {
+    ready(1)
+}
+
+// The output of the future does not need to be specified
+#[autometrics]
+fn unspecified_output() -> impl Future {
+    async {}
+}
+
+#[autometrics]
+fn boxed_future() -> Pin<Box<dyn Future<Output = Result<(), ()>> + Send>> {
+    Box::pin(async { Ok(()) })
+}
+
+// Functions that never return
+#[autometrics]
+fn exit(code: i32) -> ! {
+    process::exit(code)
+}
+
+#[autometrics(count_only)]
+fn abort() -> ! {
+    process::abort()
+}
+
+struct Client {
+    name: String,
+}
+
+#[autometrics]
+impl Client {
+    fn name(&self) -> impl Future<Output = &str> + '_ {
+        async move { self.name.as_str() }
+    }
+}
+
+#[autometrics]
+fn main() {
+    drop(borrowed_future("value"));
+    drop(unpin_future());
+    drop(unspecified_output());
+    drop(boxed_future());
+    drop(Client { name: String::new() }.name());
+    if false {
+        abort();
+    }
+    exit(0)
+}
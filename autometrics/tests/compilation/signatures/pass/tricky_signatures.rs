@@ -0,0 +1,122 @@
+use autometrics::autometrics;
+use std::fmt::Display;
+use std::future::Future;
+
+// Generics, const generics, and where clauses
+#[autometrics]
+fn generic<T: Clone, const N: usize>(items: [T; N]) -> Vec<T>
+where
+    T: Default,
+{
+    items.to_vec()
+}
+
+// `impl Trait` in argument position
+#[autometrics]
+fn impl_trait_argument(value: impl Display, values: impl IntoIterator<Item = u32>) -> String {
+    format!("{value}: {}", values.into_iter().sum::<u32>())
+}
+
+// Functions returning a future without being async
+#[autometrics]
+fn returns_future() -> impl Future<Output = u32> {
+    async { 1 }
+}
+
+#[autometrics]
+async fn async_generic<T: Send>(value: T) -> T {
+    value
+}
+
+#[autometrics]
+unsafe fn unsafe_function(pointer: *const u32) -> u32 {
+    *pointer
+}
+
+// Patterns and mutable bindings in the arguments
+#[autometrics]
+fn patterns((a, b): (u32, u32), mut c: u32, _: u32) -> u32 {
+    c += a + b;
+    c
+}
+
+// Other attributes are kept
+#[autometrics]
+#[must_use]
+#[allow(clippy::needless_return)]
+fn other_attributes() -> u32 {
+    return 1;
+}
+
+#[autometrics]
+pub(crate) extern "C" fn extern_abi() -> u32 {
+    1
+}
+
+struct Counter<T> {
+    value: T,
+}
+
+#[autometrics]
+impl<T: Copy + std::ops::Add<Output = T>> Counter<T> {
+    fn get(&self) -> T {
+        self.value
+    }
+
+    fn set(&mut self, value: T) -> &mut Self {
+        self.value = value;
+        self
+    }
+
+    fn into_value(self) -> T {
+        self.value
+    }
+
+    unsafe fn unchecked(&self) -> T {
+        self.value
+    }
+
+    async fn add(&self, other: T) -> T {
+        self.value + other
+    }
+
+    fn associated() -> Option<T> {
+        None
+    }
+}
+
+#[autometrics]
+trait Greeter {
+    fn name(&self) -> String;
+
+    fn greet(&self) -> String {
+        format!("Hello, {}", self.name())
+    }
+}
+
+struct World;
+
+impl Greeter for World {
+    fn name(&self) -> String {
+        "world".to_string()
+    }
+}
+
+fn main() {
+    generic([1, 2, 3]);
+    impl_trait_argument("sum", [1, 2]);
+    drop(returns_future());
+    drop(async_generic(1));
+    unsafe { unsafe_function(&1) };
+    patterns((1, 2), 3, 4);
+    let _ = other_attributes();
+    extern_abi();
+
+    let mut counter = Counter { value: 1 };
+    counter.set(2).get();
+    unsafe { counter.unchecked() };
+    drop(counter.add(1));
+    Counter::<u32>::associated();
+    counter.into_value();
+    World.greet();
+}
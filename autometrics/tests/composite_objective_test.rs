@@ -0,0 +1,124 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::objectives::{Objective, ObjectiveLatency, ObjectivePercentile};
+use autometrics::queries::{self, Sli};
+use autometrics::{autometrics, prometheus_exporter};
+
+const CHECKOUT_SLO: Objective = Objective::new("checkout")
+    .success_rate(ObjectivePercentile::P99_9)
+    .composite(ObjectiveLatency::Ms250, ObjectivePercentile::P99);
+
+const SEARCH_SLO: Objective =
+    Objective::new("search").latency(ObjectiveLatency::Ms250, ObjectivePercentile::P99);
+
+#[autometrics(objective = CHECKOUT_SLO)]
+fn checkout(cart: &[u32]) -> Result<(), &'static str> {
+    if cart.is_empty() {
+        Err("empty cart")
+    } else {
+        Ok(())
+    }
+}
+
+#[autometrics(objective = SEARCH_SLO)]
+fn search() -> Result<(), ()> {
+    Ok(())
+}
+
+/// The histogram count of the given function with the given labels
+fn count(metrics: &str, function: &str, labels: &[&str]) -> u64 {
+    metrics
+        .lines()
+        .filter(|line| {
+            line.starts_with("function_calls_duration_seconds_count{")
+                && line.contains(&format!(r#"function="{function}""#))
+                && labels.iter().all(|label| line.contains(label))
+        })
+        .filter_map(|line| line.rsplit(' ').next()?.parse::<u64>().ok())
+        .sum()
+}
+
+#[test]
+fn composite_objective_labels_histogram_with_result() {
+    prometheus_exporter::try_init().ok();
+
+    checkout(&[1, 2]).unwrap();
+    checkout(&[3]).unwrap();
+    checkout(&[]).unwrap_err();
+    search().unwrap();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    let objective = [
+        r#"objective_name="checkout""#,
+        r#"objective_percentile="99""#,
+        r#"objective_latency_threshold="0.25""#,
+    ];
+    assert_eq!(
+        count(
+            &metrics,
+            "checkout",
+            &[&objective[..], &[r#"result="ok""#]].concat()
+        ),
+        2
+    );
+    assert_eq!(
+        count(
+            &metrics,
+            "checkout",
+            &[&objective[..], &[r#"result="error""#]].concat()
+        ),
+        1
+    );
+
+    // The success rate objective still labels the counter
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="checkout""#)
+            && line.contains(r#"objective_percentile="99.9""#)
+    }));
+
+    // Latency objectives do not split the histogram by result
+    assert_eq!(
+        count(&metrics, "search", &[r#"objective_name="search""#]),
+        1
+    );
+    assert_eq!(count(&metrics, "search", &[r#"result="ok""#]), 0);
+}
+
+#[test]
+fn composite_objective_queries() {
+    assert_eq!(CHECKOUT_SLO.latency_target(), None);
+    let checkout = queries::objective(CHECKOUT_SLO);
+
+    // Only the fast calls that did not return errors are good events
+    let error_ratio = checkout.error_ratio(Sli::Composite, "5m").unwrap();
+    assert!(
+        error_ratio.contains(
+            r#"_bucket{objective_name="checkout",objective_percentile="99",result!="error"}[5m]"#
+        ),
+        "{error_ratio}"
+    );
+    assert!(
+        error_ratio.contains(r#"_count{objective_name="checkout",objective_percentile="99"}[5m]"#),
+        "{error_ratio}"
+    );
+    assert!(checkout
+        .burn_rate(Sli::Composite, "1h")
+        .unwrap()
+        .ends_with(" / 0.01"));
+    assert_eq!(checkout.error_ratio(Sli::Latency, "5m"), None);
+}
+
+#[cfg(feature = "alerts")]
+#[test]
+fn composite_objective_rules() {
+    autometrics::alerts::register_objective(CHECKOUT_SLO);
+    let rules = autometrics::alerts::generate_rules();
+
+    assert!(rules.contains(r#"  - name: "autometrics-checkout-composite-99""#));
+    assert!(rules.contains("category: composite"));
+    assert!(rules.contains(r#"result!="error""#));
+    assert!(rules.contains(r#"- alert: "High error rate or latency SLO - 99%""#));
+    // The success rate objective has its own rules
+    assert!(rules.contains(r#"  - name: "autometrics-checkout-success-rate-99_9""#));
+}
@@ -0,0 +1,108 @@
+#![cfg(prometheus_exporter)]
+use autometrics::connection::{Connection, Phase};
+use autometrics::{autometrics, prometheus_exporter};
+
+const SOCKET: Connection = Connection::new("socket", module_path!());
+
+#[autometrics]
+fn handle_message() {}
+
+/// The sum of the series of the given metric and function with all of the given labels
+fn value(metrics: &str, metric: &str, function: &str, labels: &[&str]) -> f64 {
+    metrics
+        .lines()
+        .filter(|line| {
+            line.starts_with(metric)
+                && line.contains(&format!(r#"function="{function}""#))
+                && labels.iter().all(|label| line.contains(label))
+        })
+        .filter_map(|line| line.rsplit(' ').next()?.parse::<f64>().ok())
+        .sum()
+}
+
+#[test]
+fn phases_are_tracked_separately() {
+    prometheus_exporter::try_init().ok();
+
+    let mut first = SOCKET.open();
+    first.enter(Phase::Active);
+    first.scope(handle_message);
+    let second = SOCKET.open();
+
+    // Both connections are open
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert_eq!(
+        value(&metrics, "function_calls_concurrent{", "socket", &[]),
+        2.0,
+        "{metrics}"
+    );
+    assert_eq!(
+        value(
+            &metrics,
+            "function_calls_duration_seconds_count{",
+            "socket.handshake",
+            &[]
+        ),
+        1.0
+    );
+
+    first.enter(Phase::Shutdown);
+    assert_eq!(first.phase(), Phase::Shutdown);
+    first.close(&Ok::<_, ()>(()));
+    second.close(&Err::<(), _>("handshake failed"));
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert_eq!(
+        value(&metrics, "function_calls_concurrent{", "socket", &[]),
+        0.0
+    );
+    for phase in ["socket.handshake", "socket.active", "socket.shutdown"] {
+        assert!(
+            value(
+                &metrics,
+                "function_calls_duration_seconds_count{",
+                phase,
+                &[]
+            ) > 0.0,
+            "{phase} was not recorded: {metrics}"
+        );
+    }
+    // The failed handshake is counted as an error, for both the phase and the connection
+    assert_eq!(
+        value(
+            &metrics,
+            "function_calls_total{",
+            "socket.handshake",
+            &[r#"result="error""#]
+        ),
+        1.0
+    );
+    assert_eq!(
+        value(
+            &metrics,
+            "function_calls_total{",
+            "socket",
+            &[r#"result="error""#]
+        ),
+        1.0
+    );
+    assert_eq!(
+        value(
+            &metrics,
+            "function_calls_total{",
+            "socket",
+            &[r#"result="ok""#]
+        ),
+        1.0
+    );
+    // The functions called during a phase are reported with the phase as their caller
+    assert_eq!(
+        value(
+            &metrics,
+            "function_calls_total{",
+            "handle_message",
+            &[r#"caller_function="socket.active""#]
+        ),
+        1.0
+    );
+}
@@ -0,0 +1,58 @@
+#![cfg(prometheus_exporter)]
+use autometrics::{autometrics, context, prometheus_exporter};
+
+#[autometrics]
+async fn spawning_fn() {
+    let context = context::current();
+    assert_eq!(context.function(), "spawning_fn");
+    assert_eq!(context.module(), module_path!());
+
+    tokio::spawn(context.scope(spawned_fn())).await.unwrap();
+    #[cfg(feature = "tokio")]
+    context::spawn(spawned_fn()).await.unwrap();
+    // Without the context, the caller is lost
+    tokio::spawn(spawned_fn()).await.unwrap();
+}
+
+#[autometrics]
+async fn spawned_fn() {}
+
+#[autometrics]
+fn thread_spawning_fn() {
+    let context = context::current();
+    std::thread::spawn(move || context.sync_scope(thread_fn))
+        .join()
+        .unwrap();
+}
+
+#[autometrics]
+fn thread_fn() {}
+
+/// The number of calls to the function from the given caller
+fn calls(metrics: &str, function: &str, caller_function: &str) -> u64 {
+    metrics
+        .lines()
+        .filter(|line| {
+            line.starts_with("function_calls_total{")
+                && line.contains(&format!(r#"function="{function}""#))
+                && line.contains(&format!(r#"caller_function="{caller_function}""#))
+        })
+        .map(|line| line.rsplit_once(' ').unwrap().1.parse::<u64>().unwrap())
+        .sum()
+}
+
+#[tokio::test]
+async fn propagate_caller() {
+    prometheus_exporter::try_init().ok();
+
+    assert_eq!(context::current(), Default::default());
+
+    spawning_fn().await;
+    thread_spawning_fn();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    let propagated = if cfg!(feature = "tokio") { 2 } else { 1 };
+    assert_eq!(calls(&metrics, "spawned_fn", "spawning_fn"), propagated);
+    assert_eq!(calls(&metrics, "spawned_fn", ""), 1);
+    assert_eq!(calls(&metrics, "thread_fn", "thread_spawning_fn"), 1);
+}
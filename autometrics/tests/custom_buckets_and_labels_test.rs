@@ -0,0 +1,50 @@
+#![cfg(all(prometheus_exporter, any(prometheus, prometheus_client)))]
+
+use autometrics::{autometrics, prometheus_exporter};
+
+#[autometrics(buckets = [0.0005, 0.5])]
+fn fetch_page() {}
+
+#[autometrics(label(key = "tier", value = tier))]
+fn render_page(tier: &'static str) {}
+
+#[autometrics(buckets = [0.001, 1.0], label(key = "tier", value = tier))]
+fn cache_page(tier: &'static str) {}
+
+#[test]
+fn custom_buckets_with_custom_labels() {
+    prometheus_exporter::try_init().ok();
+
+    // The metrics with custom buckets and custom labels are created in different orders
+    // for different functions, so both are registered whichever comes first
+    render_page("free");
+    fetch_page();
+    cache_page("paid");
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    let bucket = |function: &str, le: &str| {
+        metrics.lines().any(|line| {
+            line.starts_with("function_calls_duration_seconds_bucket{")
+                && line.contains(&format!(r#"function="{function}""#))
+                && line.contains(&format!(r#"le="{le}""#))
+        })
+    };
+    assert!(bucket("fetch_page", "0.0005"), "{metrics}");
+    assert!(bucket("cache_page", "0.001"), "{metrics}");
+    assert!(
+        metrics
+            .lines()
+            .any(|line| line.starts_with("function_calls_total{")
+                && line.contains(r#"function="render_page""#)
+                && line.contains(r#"tier="free""#)),
+        "{metrics}"
+    );
+    assert!(
+        metrics.lines().any(
+            |line| line.starts_with("function_calls_duration_seconds_bucket{")
+                && line.contains(r#"function="cache_page""#)
+                && line.contains(r#"tier="paid""#)
+        ),
+        "{metrics}"
+    );
+}
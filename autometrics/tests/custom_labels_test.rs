@@ -0,0 +1,100 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::{autometrics, prometheus_exporter, LabelValue};
+
+#[derive(Clone, Copy)]
+enum Region {
+    Eu,
+    Us,
+}
+
+impl LabelValue for Region {
+    fn label_value(&self) -> &'static str {
+        match self {
+            Region::Eu => "eu",
+            Region::Us => "us",
+        }
+    }
+}
+
+struct Consumer {
+    shard: &'static str,
+}
+
+impl Consumer {
+    // The labels are evaluated before `self` is moved into the body
+    #[autometrics(label(key = "shard", value = self.shard), label(key = "region", value = region))]
+    fn consume(self, region: Region) -> Result<(), ()> {
+        let Self { shard } = self;
+        assert!(!shard.is_empty());
+        Ok(())
+    }
+}
+
+#[autometrics(label(key = "cached", value = cached))]
+async fn lookup(cached: bool) {}
+
+#[tokio::test]
+async fn adds_custom_labels() {
+    prometheus_exporter::try_init().ok();
+
+    Consumer { shard: "shard_1" }.consume(Region::Eu).ok();
+    Consumer { shard: "shard_1" }.consume(Region::Eu).ok();
+    Consumer { shard: "shard_2" }.consume(Region::Us).ok();
+    lookup(true).await;
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    let series = |metric: &str, labels: &[&str]| {
+        metrics
+            .lines()
+            .filter(|line| line.starts_with(metric))
+            .filter(|line| labels.iter().all(|label| line.contains(label)))
+            .map(|line| line.rsplit(' ').next().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        series(
+            "function_calls_total{",
+            &[
+                r#"function="consume""#,
+                r#"shard="shard_1""#,
+                r#"region="eu""#
+            ]
+        ),
+        ["2"],
+        "{metrics}"
+    );
+    assert_eq!(
+        series(
+            "function_calls_total{",
+            &[
+                r#"function="consume""#,
+                r#"shard="shard_2""#,
+                r#"region="us""#
+            ]
+        ),
+        ["1"],
+        "{metrics}"
+    );
+    assert_eq!(
+        series(
+            "function_calls_duration_seconds_count{",
+            &[
+                r#"function="consume""#,
+                r#"shard="shard_1""#,
+                r#"region="eu""#
+            ]
+        ),
+        ["2"],
+        "{metrics}"
+    );
+    assert_eq!(
+        series(
+            "function_calls_total{",
+            &[r#"function="lookup""#, r#"cached="true""#]
+        ),
+        ["1"],
+        "{metrics}"
+    );
+}
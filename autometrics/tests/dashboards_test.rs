@@ -0,0 +1,88 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::dashboards::{self, Dashboard};
+use autometrics::objectives::{Objective, ObjectiveLatency, ObjectivePercentile};
+use autometrics::{autometrics, queries};
+
+const API_SLO: Objective = Objective::new("dashboards_api")
+    .success_rate(ObjectivePercentile::P99_9)
+    .latency(ObjectiveLatency::Ms250, ObjectivePercentile::P99);
+
+#[autometrics(objective = API_SLO)]
+fn create_dashboard() {}
+
+#[autometrics]
+fn delete_dashboard() {}
+
+/// The query as it is written in a JSON string
+fn json_string(query: &str) -> String {
+    format!(
+        "\"{}\"",
+        query
+            .replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('\n', "\\n")
+    )
+}
+
+#[test]
+fn dashboard_has_rows_for_objectives_and_functions() {
+    let dashboard = Dashboard::new("API \"v2\"")
+        .objective(API_SLO)
+        .function("create_dashboard")
+        .to_string();
+
+    assert!(
+        dashboard.starts_with(r#"{"title":"API \"v2\"","#),
+        "{dashboard}"
+    );
+    assert!(dashboard.contains(r#""uid":"${datasource}""#));
+
+    // The objective row comes first, with a panel for each of its targets
+    let objective_row = dashboard
+        .find(r#""title":"Objective: dashboards_api""#)
+        .unwrap();
+    let function_row = dashboard
+        .find(r#""title":"Function: create_dashboard""#)
+        .unwrap();
+    assert!(objective_row < function_row);
+    assert!(dashboard.contains(r#""title":"Success rate burn rate""#));
+    assert!(dashboard.contains(r#""title":"Latency burn rate""#));
+    assert!(!dashboard.contains(r#""title":"Success rate and latency burn rate""#));
+
+    let objective = queries::objective(API_SLO);
+    for window in ["1h", "6h", "1d", "3d"] {
+        let burn_rate = objective
+            .burn_rate(queries::Sli::SuccessRate, window)
+            .unwrap();
+        assert!(dashboard.contains(&json_string(&burn_rate)), "{dashboard}");
+    }
+
+    // The function row uses the same queries as the documentation
+    let function = queries::function("create_dashboard");
+    for query in [
+        function.request_rate(),
+        function.error_ratio(),
+        function.latency(),
+    ] {
+        assert!(dashboard.contains(&json_string(&query)), "{dashboard}");
+    }
+    assert!(dashboard.contains(r#""unit":"reqps""#));
+    assert!(dashboard.contains(r#""unit":"percentunit""#));
+    assert!(dashboard.contains(r#""unit":"s""#));
+
+    // The panels of a row are placed side by side below it
+    assert!(dashboard.contains(r#""gridPos":{"h":8,"w":8,"x":16,"y":10}"#));
+}
+
+#[test]
+fn dashboards_from_the_function_registry() {
+    let all = dashboards::instrumented_functions("All functions").to_string();
+    assert!(all.contains(r#""title":"Function: create_dashboard""#));
+    assert!(all.contains(r#""title":"Function: delete_dashboard""#));
+
+    let objective = dashboards::objective(API_SLO).to_string();
+    assert!(objective.starts_with(r#"{"title":"Objective: dashboards_api""#));
+    assert!(objective.contains(r#""title":"Function: create_dashboard""#));
+    assert!(!objective.contains(r#""title":"Function: delete_dashboard""#));
+}
@@ -0,0 +1,49 @@
+#![cfg(all(prometheus_exporter, debug_metrics))]
+
+use autometrics::autometrics;
+use std::io;
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[autometrics]
+fn logged_fn() -> Result<(), ()> {
+    Err(())
+}
+
+#[test]
+fn logs_recorded_calls() {
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+
+    tracing::subscriber::with_default(subscriber, || logged_fn().ok());
+
+    let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+    let line = logs
+        .lines()
+        .find(|line| line.contains("recorded function call"))
+        .unwrap_or_else(|| panic!("{logs}"));
+    assert!(line.contains("autometrics::debug_metrics"), "{line}");
+    assert!(line.contains(r#"function="logged_fn""#), "{line}");
+    assert!(line.contains(r#"module="debug_metrics_test""#), "{line}");
+    assert!(line.contains("weight=1"), "{line}");
+    assert!(line.contains(r#"result="error""#), "{line}");
+    assert!(line.contains("histogram_labels={"), "{line}");
+}
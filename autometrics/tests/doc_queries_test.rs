@@ -0,0 +1,21 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::__private::doc_queries;
+use autometrics::queries;
+
+/// The queries in the documentation are generated at compile time by the macro, and the ones
+/// in the `queries` module at runtime, so this checks that the two implementations stay in sync
+/// (with the default settings, which match the default compile-time environment variables)
+#[test]
+fn doc_queries_match_runtime_queries() {
+    let queries = queries::function("create_user");
+    let [request_rate, error_ratio, latency, concurrent_calls, callee_request_rate, callee_error_ratio] =
+        doc_queries!("create_user");
+
+    assert_eq!(request_rate, queries.request_rate());
+    assert_eq!(error_ratio, queries.error_ratio());
+    assert_eq!(latency, queries.latency());
+    assert_eq!(concurrent_calls, queries.concurrent_calls());
+    assert_eq!(callee_request_rate, queries.callee_request_rate());
+    assert_eq!(callee_error_ratio, queries.callee_error_ratio());
+}
@@ -0,0 +1,26 @@
+#![cfg(all(prometheus_exporter, feature = "tokio"))]
+
+use autometrics::{autometrics, prometheus_exporter};
+use http::header::CONTENT_TYPE;
+
+#[autometrics]
+fn render_page() {}
+
+#[tokio::test(flavor = "current_thread")]
+async fn encode_http_response_async_matches_the_blocking_response() {
+    prometheus_exporter::try_init().ok();
+    render_page();
+
+    let response = prometheus_exporter::encode_http_response_async().await;
+    let blocking = prometheus_exporter::encode_http_response();
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.headers().get(CONTENT_TYPE),
+        blocking.headers().get(CONTENT_TYPE)
+    );
+    assert!(response
+        .body()
+        .lines()
+        .any(|line| line.starts_with("function_calls_total{")
+            && line.contains(r#"function="render_page""#)));
+}
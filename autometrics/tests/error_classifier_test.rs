@@ -0,0 +1,85 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::error_classifier::ErrorClassifier;
+use autometrics::{autometrics, prometheus_exporter, settings::AutometricsSettings};
+use std::error::Error;
+use std::{fmt, io};
+
+#[derive(Debug)]
+enum ServiceError {
+    Internal(io::Error),
+    NotFound,
+}
+
+impl fmt::Display for ServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServiceError::Internal(_) => write!(f, "internal error"),
+            ServiceError::NotFound => write!(f, "not found"),
+        }
+    }
+}
+
+impl Error for ServiceError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ServiceError::Internal(err) => Some(err),
+            ServiceError::NotFound => None,
+        }
+    }
+}
+
+#[autometrics]
+fn read_config() -> Result<(), ServiceError> {
+    Err(ServiceError::Internal(io::Error::other("disk failure")))
+}
+
+#[autometrics]
+fn find_user() -> Result<(), ServiceError> {
+    Err(ServiceError::NotFound)
+}
+
+#[autometrics]
+fn parse_port() -> Result<u16, Box<dyn Error + Send + Sync>> {
+    Ok("port".parse::<u16>()?)
+}
+
+/// The value of the `error` label of the function's errors, if it is set
+fn error_label<'a>(metrics: &'a str, function: &str) -> Option<&'a str> {
+    let line = metrics.lines().find(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(&format!(r#"function="{function}""#))
+            && line.contains(r#"result="error""#)
+    })?;
+    let (_, rest) = line.split_once(r#",error=""#)?;
+    rest.split('"').next().filter(|error| !error.is_empty())
+}
+
+#[test]
+fn classifies_root_causes() {
+    AutometricsSettings::builder()
+        .error_classifier(
+            ErrorClassifier::new()
+                .class::<io::Error>("io")
+                .class::<std::num::ParseIntError>("parse"),
+        )
+        .init();
+
+    read_config().ok();
+    find_user().ok();
+    parse_port().ok();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert_eq!(
+        error_label(&metrics, "read_config"),
+        Some("io"),
+        "{metrics}"
+    );
+    assert_eq!(
+        error_label(&metrics, "parse_port"),
+        Some("parse"),
+        "{metrics}"
+    );
+    // Errors that are not recognized keep the same labels
+    assert_eq!(error_label(&metrics, "find_user"), None, "{metrics}");
+}
@@ -0,0 +1,48 @@
+#![cfg(all(prometheus_exporter, exemplars_custom))]
+
+use autometrics::settings::AutometricsSettings;
+use autometrics::{autometrics, prometheus_exporter};
+use std::cell::RefCell;
+
+thread_local! {
+    static REQUEST_ID: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+#[autometrics]
+fn handle_request() {}
+
+#[autometrics]
+fn background_job() {}
+
+#[test]
+fn custom_exemplar_provider() {
+    AutometricsSettings::builder()
+        .exemplar_provider(|| {
+            REQUEST_ID.with(|id| id.borrow().clone().map(|id| vec![("trace_id", id)]))
+        })
+        .init();
+
+    REQUEST_ID.with(|id| *id.borrow_mut() = Some("request-1234".to_string()));
+    handle_request();
+    REQUEST_ID.with(|id| *id.borrow_mut() = None);
+    background_job();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(
+        metrics
+            .lines()
+            .any(|line| line.starts_with("function_calls_total{")
+                && line.contains(r#"function="handle_request""#)
+                && line.ends_with(r#"} 1 # {trace_id="request-1234"} 1.0"#)),
+        "{metrics}"
+    );
+    // Calls without a request ID do not have an exemplar
+    assert!(
+        metrics
+            .lines()
+            .any(|line| line.starts_with("function_calls_total{")
+                && line.contains(r#"function="background_job""#)
+                && line.ends_with("} 1")),
+        "{metrics}"
+    );
+}
@@ -0,0 +1,76 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::prometheus_exporter::{self, ExemplarsConfigurationError};
+
+#[cfg(all(prometheus_client, not(exemplars)))]
+#[test]
+fn exemplars_disabled() {
+    prometheus_exporter::try_init().ok();
+    assert_eq!(
+        prometheus_exporter::openmetrics_with_exemplars_guard(),
+        Err(ExemplarsConfigurationError::ExemplarsDisabled)
+    );
+}
+
+#[cfg(not(prometheus_client))]
+#[test]
+fn unsupported_backend() {
+    prometheus_exporter::try_init().ok();
+    let err = prometheus_exporter::openmetrics_with_exemplars_guard().unwrap_err();
+    assert!(matches!(
+        err,
+        ExemplarsConfigurationError::UnsupportedBackend(_)
+    ));
+    assert!(err.to_string().contains("prometheus-client-0_22"));
+}
+
+#[cfg(all(
+    exemplars_custom,
+    not(any(exemplars_tracing, exemplars_tracing_opentelemetry, exemplars_testing))
+))]
+#[test]
+fn exemplar_provider_missing() {
+    prometheus_exporter::try_init().ok();
+    assert_eq!(
+        prometheus_exporter::openmetrics_with_exemplars_guard(),
+        Err(ExemplarsConfigurationError::NoExemplarProvider)
+    );
+}
+
+#[cfg(any(exemplars_tracing, exemplars_tracing_opentelemetry, exemplars_testing))]
+#[test]
+fn histogram_buckets() {
+    let handle = prometheus_exporter::init();
+    let content_type = prometheus_exporter::openmetrics_with_exemplars_guard().unwrap();
+    assert!(content_type.starts_with("application/openmetrics-text"));
+    assert_eq!(
+        prometheus_exporter::encode_http_response().headers()["content-type"],
+        content_type
+    );
+
+    handle.set_histogram_buckets([0.5, 0.1]).unwrap();
+    assert_eq!(
+        prometheus_exporter::openmetrics_with_exemplars_guard(),
+        Err(ExemplarsConfigurationError::InvalidHistogramBuckets {
+            histogram: "function_calls_duration_seconds".to_string(),
+            buckets: vec![0.5, 0.1],
+        })
+    );
+
+    handle.set_histogram_buckets([0.1, f64::NAN]).unwrap();
+    assert!(matches!(
+        prometheus_exporter::openmetrics_with_exemplars_guard(),
+        Err(ExemplarsConfigurationError::InvalidHistogramBuckets { .. })
+    ));
+
+    handle.set_histogram_buckets(Vec::new()).unwrap();
+    assert_eq!(
+        prometheus_exporter::openmetrics_with_exemplars_guard(),
+        Err(ExemplarsConfigurationError::NoHistogramBuckets {
+            histogram: "function_calls_duration_seconds".to_string(),
+        })
+    );
+
+    handle.set_histogram_buckets([0.1, 0.5, 1.0]).unwrap();
+    assert!(prometheus_exporter::openmetrics_with_exemplars_guard().is_ok());
+}
@@ -0,0 +1,60 @@
+#![cfg(all(prometheus_exporter, exemplars_testing))]
+
+use autometrics::{autometrics, exemplars, prometheus_exporter};
+
+#[autometrics]
+fn lookup() -> Result<(), ()> {
+    Ok(())
+}
+
+#[test]
+fn override_for_next_recordings() {
+    prometheus_exporter::init();
+    let lines_with_exemplar = || {
+        let metrics = prometheus_exporter::encode_to_string().unwrap();
+        metrics
+            .lines()
+            .filter(|line| line.contains(r#"function="lookup""#))
+            // The order of the exemplar labels is not specified
+            .filter(|line| {
+                line.split_once(" # ").map_or(false, |(_, exemplar)| {
+                    exemplar.contains(r#"trace_id="0af7651916cd43dd8448eb211c80319c""#)
+                        && exemplar.contains(r#"span_id="b7ad6b7169203331""#)
+                })
+            })
+            .map(|line| line.split(' ').next().unwrap().to_string())
+            .collect::<Vec<_>>()
+    };
+
+    exemplars::set_override(
+        [
+            ("trace_id", "0af7651916cd43dd8448eb211c80319c"),
+            ("span_id", "b7ad6b7169203331"),
+        ],
+        2,
+    );
+    lookup().ok();
+    lookup().ok();
+
+    // Both the counter and the histogram carry the exemplar
+    let lines = lines_with_exemplar();
+    assert!(lines
+        .iter()
+        .any(|name| name.starts_with("function_calls_total{")));
+    assert!(lines
+        .iter()
+        .any(|name| name.starts_with("function_calls_duration_seconds_bucket{")));
+
+    // The override is used up, so the next recording replaces the counter's exemplar
+    lookup().ok();
+    let lines = lines_with_exemplar();
+    assert!(!lines
+        .iter()
+        .any(|name| name.starts_with("function_calls_total{")));
+
+    exemplars::set_override([("trace_id", "1")], 5);
+    exemplars::clear_override();
+    lookup().ok();
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(!metrics.contains(r#"trace_id="1""#));
+}
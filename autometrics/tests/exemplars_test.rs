@@ -1,4 +1,7 @@
-#![cfg(all(prometheus_exporter, exemplars))]
+#![cfg(all(
+    prometheus_exporter,
+    any(exemplars_tracing, exemplars_tracing_opentelemetry)
+))]
 
 use autometrics::{autometrics, prometheus_exporter};
 
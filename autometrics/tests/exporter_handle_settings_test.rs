@@ -0,0 +1,76 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::autometrics;
+use autometrics::prometheus_exporter::{self, ExporterHandle};
+
+fn handle() -> ExporterHandle {
+    prometheus_exporter::try_init().ok();
+    prometheus_exporter::handle().unwrap()
+}
+
+#[cfg(function_registry)]
+#[test]
+fn initialize_metrics_with_the_function_registry() {
+    #[autometrics]
+    fn never_called_fn() {}
+
+    handle().initialize_metrics();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(
+        metrics
+            .lines()
+            .any(|line| line.starts_with("function_calls_total{")
+                && line.contains(r#"function="never_called_fn""#)
+                && line.ends_with("} 0")),
+        "{metrics}"
+    );
+}
+
+// Release builds without the `function-registry` feature cannot find the functions that were not called
+#[cfg(not(function_registry))]
+#[test]
+fn initialize_metrics_without_the_function_registry() {
+    #[autometrics]
+    fn never_called_fn() {}
+
+    handle().initialize_metrics();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(
+        !metrics.contains(r#"function="never_called_fn""#),
+        "{metrics}"
+    );
+}
+
+#[cfg(all(prometheus_client, not(any(prometheus, metrics, opentelemetry))))]
+#[test]
+fn set_histogram_buckets() {
+    #[autometrics]
+    fn rebucketed_fn() {}
+
+    handle().set_histogram_buckets([0.25, 2.5]).unwrap();
+    rebucketed_fn();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(
+        metrics.lines().any(
+            |line| line.starts_with("function_calls_duration_seconds_bucket{")
+                && line.contains(r#"function="rebucketed_fn""#)
+                && line.contains(r#"le="0.25""#)
+        ),
+        "{metrics}"
+    );
+}
+
+#[cfg(any(prometheus, metrics, opentelemetry))]
+#[test]
+fn set_histogram_buckets_with_an_unsupported_backend() {
+    use autometrics::prometheus_exporter::SetHistogramBucketsError;
+
+    let result = handle().set_histogram_buckets([0.25, 2.5]);
+    assert!(
+        matches!(result, Err(SetHistogramBucketsError::UnsupportedBackend(_))),
+        "{result:?}"
+    );
+}
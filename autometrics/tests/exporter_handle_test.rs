@@ -0,0 +1,30 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::{autometrics, prometheus_exporter};
+
+#[test]
+fn pause_and_resume() {
+    let handle = prometheus_exporter::init();
+    assert!(prometheus_exporter::handle().is_some());
+
+    #[autometrics]
+    fn paused_fn() -> &'static str {
+        "Hello world!"
+    }
+
+    handle.pause();
+    assert!(handle.is_paused());
+    paused_fn();
+    paused_fn();
+
+    handle.resume();
+    assert!(!handle.is_paused());
+    paused_fn();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="paused_fn""#)
+            && line.ends_with("} 1")
+    }));
+}
@@ -0,0 +1,31 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::prometheus_exporter::{self, EncodingError};
+use autometrics::{autometrics, settings::AutometricsSettings};
+
+#[autometrics]
+fn initialized_fn() {}
+
+#[test]
+fn encode_before_settings() {
+    // Encoding the metrics before the settings are initialized does not initialize the default settings
+    assert_eq!(prometheus_exporter::encode_to_string().unwrap(), "");
+
+    prometheus_exporter::set_strict(true);
+    assert!(matches!(
+        prometheus_exporter::encode_to_string(),
+        Err(EncodingError::NotInitialized)
+    ));
+
+    AutometricsSettings::builder()
+        .service_name("initialized_later")
+        .init();
+    initialized_fn();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="initialized_fn""#)
+            && line.contains(r#"service_name="initialized_later""#)
+    }));
+}
@@ -0,0 +1,66 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::autometrics;
+use autometrics::prometheus_exporter::{self, RegistryHandle};
+use prometheus_client::metrics::counter::Counter;
+use std::sync::Arc;
+
+#[autometrics]
+fn merged_fn() {}
+
+#[test]
+fn merges_registries() {
+    prometheus_exporter::try_init().ok();
+    merged_fn();
+
+    let legacy_registry = prometheus::Registry::new();
+    let legacy_requests =
+        prometheus::IntCounter::new("legacy_requests_total", "Legacy requests").unwrap();
+    legacy_registry
+        .register(Box::new(legacy_requests.clone()))
+        .unwrap();
+    legacy_requests.inc_by(3);
+
+    let mut client_registry = <prometheus_client::registry::Registry>::default();
+    let client_requests = Counter::<u64>::default();
+    client_registry.register(
+        "client_requests",
+        "Client requests",
+        client_requests.clone(),
+    );
+    client_requests.inc();
+    // This has the same name as the counter of the autometrics metrics, so it is not exported
+    let conflicting = Counter::<u64>::default();
+    client_registry.register("function_calls", "Conflicting counter", conflicting.clone());
+    conflicting.inc_by(42);
+
+    prometheus_exporter::merge_with(&[
+        RegistryHandle::from(legacy_registry),
+        RegistryHandle::from(Arc::new(client_registry)),
+    ]);
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.contains("legacy_requests_total 3"), "{metrics}");
+    assert!(metrics.contains("client_requests_total 1"), "{metrics}");
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{") && line.contains(r#"function="merged_fn""#)
+    }));
+    assert!(!metrics.contains("Conflicting counter"), "{metrics}");
+    assert!(!metrics.contains("function_calls_total 42"), "{metrics}");
+    assert_eq!(
+        metrics
+            .lines()
+            .filter(|line| line.starts_with("# TYPE function_calls"))
+            .filter(|line| line.contains("counter"))
+            .count(),
+        1,
+        "{metrics}"
+    );
+    // The OpenMetrics terminator is only added once, at the end
+    assert!(metrics.matches("# EOF").count() <= 1);
+    if metrics.contains("# EOF") {
+        assert!(metrics.ends_with("# EOF\n"));
+    }
+
+    prometheus_exporter::merge_with(&[]);
+}
@@ -0,0 +1,103 @@
+#![cfg(exporter_server)]
+
+use autometrics::{autometrics, prometheus_exporter};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::{sleep, Duration};
+
+const ADDR: &str = "127.0.0.1:39464";
+
+#[autometrics]
+fn served_fn() {}
+
+/// Make a request to the server and return the response, including the status line and headers
+async fn get(path: &str) -> String {
+    let stream = loop {
+        // The server is started in another task, so it may not be listening yet
+        match TcpStream::connect(ADDR).await {
+            Ok(stream) => break stream,
+            Err(_) => sleep(Duration::from_millis(10)).await,
+        }
+    };
+    request(stream, path).await
+}
+
+/// Make a request on the given connection and return the response
+async fn request(mut stream: impl AsyncRead + AsyncWrite + Unpin, path: &str) -> String {
+    stream
+        .write_all(
+            format!("GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+                .as_bytes(),
+        )
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+#[tokio::test]
+async fn serves_metrics_health_and_functions() {
+    prometheus_exporter::try_init().ok();
+    tokio::spawn(prometheus_exporter::serve(ADDR));
+
+    served_fn();
+
+    let metrics = get("/metrics").await;
+    assert!(metrics.starts_with("HTTP/1.1 200 OK"), "{metrics}");
+    assert!(
+        metrics.lines().any(|line| {
+            line.starts_with("function_calls_total{") && line.contains(r#"function="served_fn""#)
+        }),
+        "{metrics}"
+    );
+
+    let health = get("/health").await;
+    assert!(health.starts_with("HTTP/1.1 200 OK"), "{health}");
+    assert!(health.ends_with("OK"), "{health}");
+
+    // The tests are built in debug mode, so the function catalog is available
+    let functions = get("/autometrics/functions").await;
+    assert!(functions.starts_with("HTTP/1.1 200 OK"), "{functions}");
+    assert!(functions.contains("application/json"), "{functions}");
+    assert!(
+        functions.contains(r#""function":"served_fn""#),
+        "{functions}"
+    );
+
+    let not_found = get("/unknown").await;
+    assert!(
+        not_found.starts_with("HTTP/1.1 404 Not Found"),
+        "{not_found}"
+    );
+}
+
+#[tokio::test]
+async fn serves_on_bound_listener() {
+    prometheus_exporter::try_init().ok();
+    // Let the OS pick a free port
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(prometheus_exporter::serve_with_listener(listener));
+
+    let health = request(TcpStream::connect(addr).await.unwrap(), "/health").await;
+    assert!(health.starts_with("HTTP/1.1 200 OK"), "{health}");
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn serves_on_unix_socket() {
+    use tokio::net::{UnixListener, UnixStream};
+
+    prometheus_exporter::try_init().ok();
+    let path = std::env::temp_dir().join(format!("autometrics-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let listener = UnixListener::bind(&path).unwrap();
+    tokio::spawn(prometheus_exporter::serve_with_listener(listener));
+
+    served_fn();
+    let metrics = request(UnixStream::connect(&path).await.unwrap(), "/metrics").await;
+    std::fs::remove_file(&path).unwrap();
+    assert!(metrics.starts_with("HTTP/1.1 200 OK"), "{metrics}");
+    assert!(metrics.contains(r#"function="served_fn""#), "{metrics}");
+}
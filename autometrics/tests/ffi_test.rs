@@ -0,0 +1,104 @@
+#![cfg(all(prometheus_exporter, ffi))]
+
+use autometrics::ffi::*;
+use autometrics::{autometrics, prometheus_exporter};
+use std::ptr;
+
+#[autometrics]
+fn handle_request() -> Result<(), ()> {
+    Ok(())
+}
+
+/// The value of the first series of the metric with the given labels
+fn value(metrics: &str, name: &str, labels: &[&str]) -> Option<u64> {
+    metrics
+        .lines()
+        .find(|line| {
+            line.starts_with(&format!("{name}{{"))
+                && labels.iter().all(|label| line.contains(label))
+        })
+        .and_then(|line| line.rsplit(' ').next()?.parse().ok())
+}
+
+#[test]
+fn records_calls_from_c() {
+    prometheus_exporter::init();
+    handle_request().unwrap();
+
+    unsafe {
+        assert!(autometrics_record_call(
+            c"decode_frame".as_ptr(),
+            c"codec".as_ptr(),
+            2_000_000,
+            AUTOMETRICS_OUTCOME_OK,
+        ));
+        assert!(autometrics_record_call(
+            c"decode_frame".as_ptr(),
+            c"codec".as_ptr(),
+            40_000_000,
+            AUTOMETRICS_OUTCOME_ERROR,
+        ));
+        assert!(autometrics_record_call(
+            c"flush".as_ptr(),
+            c"codec".as_ptr(),
+            1_000,
+            AUTOMETRICS_OUTCOME_NONE,
+        ));
+
+        // Invalid arguments are rejected
+        assert!(!autometrics_record_call(
+            ptr::null(),
+            c"codec".as_ptr(),
+            1_000,
+            AUTOMETRICS_OUTCOME_OK,
+        ));
+        assert!(!autometrics_record_call(
+            c"flush".as_ptr(),
+            c"codec".as_ptr(),
+            1_000,
+            42,
+        ));
+    }
+
+    // The calls are exported with the ones of the instrumented functions
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    let decode_frame = [r#"function="decode_frame""#, r#"module="codec""#];
+    assert_eq!(
+        value(
+            &metrics,
+            "function_calls_total",
+            &[&decode_frame[..], &[r#"result="ok""#]].concat()
+        ),
+        Some(1)
+    );
+    assert_eq!(
+        value(
+            &metrics,
+            "function_calls_total",
+            &[&decode_frame[..], &[r#"result="error""#]].concat()
+        ),
+        Some(1)
+    );
+    assert_eq!(
+        value(
+            &metrics,
+            "function_calls_duration_seconds_count",
+            &decode_frame
+        ),
+        Some(2)
+    );
+    // Only the call that took 2ms is faster than 5ms
+    assert_eq!(
+        value(
+            &metrics,
+            "function_calls_duration_seconds_bucket",
+            &[&decode_frame[..], &[r#"le="0.005""#]].concat()
+        ),
+        Some(1)
+    );
+    assert_eq!(
+        value(&metrics, "function_calls_total", &[r#"function="flush""#]),
+        Some(1)
+    );
+    assert!(metrics.contains(r#"function="handle_request""#));
+}
@@ -0,0 +1,60 @@
+use autometrics::autometrics;
+use autometrics::registry::{self, FunctionId};
+
+mod users {
+    #[autometrics::autometrics]
+    pub fn get() {}
+}
+
+mod orders {
+    #[autometrics::autometrics]
+    pub fn get() {}
+}
+
+struct Cart;
+
+#[autometrics]
+impl Cart {
+    fn checkout(&self) {}
+}
+
+#[test]
+fn called_functions_are_registered() {
+    users::get();
+    users::get();
+    orders::get();
+    Cart.checkout();
+
+    let functions = registry::functions();
+    let users_get = FunctionId::new("get", "function_id_test::users", "function_id_test");
+    assert_eq!(functions.iter().filter(|id| **id == users_get).count(), 1);
+    assert!(functions
+        .iter()
+        .any(|id| id.name() == "get" && id.module() == "function_id_test::orders"));
+    assert!(functions.iter().any(|id| {
+        id.name() == "Cart::checkout"
+            && id.module() == "function_id_test"
+            && id.crate_name() == "function_id_test"
+    }));
+
+    // Functions with the same name in the same crate are told apart by their module label
+    assert!(registry::collisions().is_empty());
+}
+
+#[test]
+fn ids_are_created_at_compile_time() {
+    const ID: FunctionId = FunctionId::new("get_user", "api::users", "api");
+
+    assert_eq!(ID, FunctionId::new("get_user", "api::users", "api"));
+    assert_eq!(
+        ID.hash(),
+        FunctionId::new("get_user", "api::users", "api").hash()
+    );
+    assert_ne!(ID, FunctionId::new("get_user", "api::users", "admin"));
+    // The fields are separated in the hash
+    assert_ne!(
+        FunctionId::new("ab", "c", "").hash(),
+        FunctionId::new("a", "bc", "").hash()
+    );
+    assert_eq!(ID.to_string(), "api::users::get_user");
+}
@@ -0,0 +1,50 @@
+#![cfg(function_registry)]
+
+use autometrics::objectives::{Objective, ObjectiveLatency, ObjectivePercentile};
+use autometrics::{autometrics, registry};
+
+const API_SLO: Objective = Objective::new("api")
+    .success_rate(ObjectivePercentile::P99)
+    .latency(ObjectiveLatency::Ms250, ObjectivePercentile::P95);
+
+#[autometrics(objective = API_SLO)]
+fn never_called() {}
+
+#[allow(dead_code)]
+struct Handler;
+
+#[autometrics]
+impl Handler {
+    fn handle(&self) {}
+}
+
+#[test]
+fn lists_functions_that_were_not_called() {
+    let functions: Vec<_> = registry::instrumented_functions().collect();
+
+    let function = functions
+        .iter()
+        .find(|function| function.name == "never_called")
+        .expect("never_called is missing");
+    assert_eq!(function.module, "function_registry_test");
+    assert!(function.objective.is_some());
+
+    let method = functions
+        .iter()
+        .find(|function| function.name == "Handler::handle")
+        .expect("Handler::handle is missing");
+    assert!(method.objective.is_none());
+}
+
+#[cfg(prometheus_exporter)]
+#[test]
+fn function_catalog_json() {
+    let catalog = autometrics::prometheus_exporter::encode_function_catalog_json();
+    assert!(catalog.starts_with('[') && catalog.ends_with(']'));
+    assert!(catalog.contains(
+        r#"{"function":"never_called","module":"function_registry_test","service_name":"autometrics","objective_name":"api","objective_success_rate":"99","objective_latency_threshold":"0.25","objective_latency_percentile":"95"}"#
+    ), "{catalog}");
+    assert!(catalog.contains(
+        r#"{"function":"Handler::handle","module":"function_registry_test","service_name":"autometrics"}"#
+    ), "{catalog}");
+}
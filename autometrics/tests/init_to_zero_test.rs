@@ -2,7 +2,7 @@
 
 use autometrics::{autometrics, prometheus_exporter};
 
-#[cfg(debug_assertions)]
+#[cfg(function_registry)]
 #[test]
 fn zero_metrics() {
     // This test is in its own file because there is a race condition when multiple tests
@@ -0,0 +1,183 @@
+#![cfg(prometheus_exporter)]
+use autometrics::instrument::{track_call, track_call_async, Function};
+use autometrics::{autometrics, objectives::*, prometheus_exporter};
+
+const API_SLO: Objective = Objective::new("manual").success_rate(ObjectivePercentile::P99);
+
+const MANUAL_FN: Function = Function::new("manual_fn", module_path!())
+    .objective(API_SLO)
+    .track_concurrency();
+
+#[autometrics(objective = API_SLO, track_concurrency)]
+fn macro_fn(fail: bool) -> Result<(), ()> {
+    manual_fn(fail)
+}
+
+fn manual_fn(fail: bool) -> Result<(), ()> {
+    MANUAL_FN.call(|| callee(fail))
+}
+
+#[autometrics]
+fn callee(fail: bool) -> Result<(), ()> {
+    if fail {
+        Err(())
+    } else {
+        Ok(())
+    }
+}
+
+/// The labels of the series for the given function, without the value
+fn series(metrics: &str, metric: &str, function: &str) -> Vec<String> {
+    metrics
+        .lines()
+        .filter(|line| {
+            line.starts_with(metric) && line.contains(&format!(r#"function="{function}""#))
+        })
+        .map(|line| line.rsplit_once(' ').unwrap().0.to_string())
+        .collect()
+}
+
+#[test]
+fn same_labels_as_macro() {
+    prometheus_exporter::try_init().ok();
+
+    macro_fn(false).ok();
+    macro_fn(true).ok();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    // The manually instrumented function is called by the macro-instrumented one
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="manual_fn""#)
+            && line.contains(r#"caller_function="macro_fn""#)
+            && line.contains(r#"objective_name="manual""#)
+            && line.contains(r#"result="error""#)
+            && line.ends_with("} 1")
+    }));
+    // And it is reported as the caller of the functions it calls
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="callee""#)
+            && line.contains(r#"caller_function="manual_fn""#)
+            && line.contains(r#"result="error""#)
+            && line.ends_with("} 1")
+    }));
+
+    for metric in [
+        "function_calls_duration_seconds_bucket{",
+        "function_calls_concurrent{",
+    ] {
+        let manual = series(&metrics, metric, "manual_fn");
+        assert!(!manual.is_empty(), "{metric} is missing");
+        assert_eq!(
+            manual,
+            series(&metrics, metric, "macro_fn")
+                .iter()
+                .map(|line| line.replace("macro_fn", "manual_fn"))
+                .collect::<Vec<_>>()
+        );
+    }
+}
+
+#[tokio::test]
+async fn track_calls() {
+    prometheus_exporter::try_init().ok();
+
+    track_call("manual_sync", module_path!(), || ());
+    let result: Result<(), &str> =
+        track_call_async("manual_async", module_path!(), async { Err("failed") }).await;
+    assert!(result.is_err());
+
+    let timer = Function::new("manual_timer", module_path!()).start();
+    let result = timer.scope(|| callee(false));
+    timer.finish(&result);
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="manual_sync""#)
+            // Return values other than results do not have a result label
+            && !line.contains(r#"result="ok""#)
+            && !line.contains(r#"result="error""#)
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="manual_async""#)
+            && line.contains(r#"result="error""#)
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="callee""#)
+            && line.contains(r#"caller_function="manual_timer""#)
+    }));
+}
+
+#[test]
+fn panic() {
+    prometheus_exporter::try_init().ok();
+
+    let result = std::panic::catch_unwind(|| {
+        track_call(
+            "panicking_manual_fn",
+            module_path!(),
+            || -> Result<(), ()> { panic!("Something went wrong") },
+        )
+    });
+    assert!(result.is_err());
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="panicking_manual_fn""#)
+            && line.contains(r#"result="error""#)
+            && line.contains(r#"panic="true""#)
+            && line.ends_with("} 1")
+    }));
+}
+
+#[autometrics]
+async fn spawn_jobs() -> Result<(), &'static str> {
+    let future = autometrics::wrap_future!("wrapped_future", async { Err("failed") });
+    let closure = autometrics::wrap_closure!("wrapped_closure", || callee(false));
+
+    // The wrapped jobs run on other tasks and threads, but are still called by this function
+    let result = tokio::spawn(future).await.unwrap();
+    std::thread::spawn(closure).join().unwrap().ok();
+    result
+}
+
+#[tokio::test]
+async fn wrapped_futures_and_closures() {
+    prometheus_exporter::try_init().ok();
+
+    assert!(spawn_jobs().await.is_err());
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="wrapped_future""#)
+            && line.contains(r#"module="instrument_test""#)
+            && line.contains(r#"caller_function="spawn_jobs""#)
+            && line.contains(r#"result="error""#)
+            && line.ends_with("} 1")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="wrapped_closure""#)
+            && line.contains(r#"caller_function="spawn_jobs""#)
+            && line.contains(r#"result="ok""#)
+            && line.ends_with("} 1")
+    }));
+    // Functions called by the wrapped closure are reported with it as their caller
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="callee""#)
+            && line.contains(r#"caller_function="wrapped_closure""#)
+    }));
+    assert!(!series(
+        &metrics,
+        "function_calls_duration_seconds_bucket{",
+        "wrapped_future"
+    )
+    .is_empty());
+}
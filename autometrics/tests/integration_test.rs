@@ -1,5 +1,7 @@
 #![cfg(prometheus_exporter)]
 use autometrics::{autometrics, prometheus_exporter};
+use std::future::Future;
+use std::time::Duration;
 
 #[test]
 fn single_function() {
@@ -71,6 +73,165 @@ fn impl_block() {
         && line.ends_with("} 1")));
 }
 
+#[test]
+fn trait_default_methods() {
+    prometheus_exporter::try_init().ok();
+
+    #[autometrics]
+    trait Repository {
+        fn name(&self) -> &'static str;
+
+        fn load(&self) -> Result<&'static str, ()> {
+            Ok(self.name())
+        }
+
+        #[skip_autometrics]
+        fn skipped(&self) {}
+    }
+
+    struct Postgres;
+    impl Repository for Postgres {
+        fn name(&self) -> &'static str {
+            "postgres"
+        }
+    }
+
+    struct InMemory<T>(T);
+    impl<T> Repository for InMemory<T> {
+        fn name(&self) -> &'static str {
+            "in-memory"
+        }
+    }
+
+    Postgres.load().unwrap();
+    Postgres.load().unwrap();
+    Postgres.skipped();
+    InMemory(String::new()).load().unwrap();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="Postgres::load""#)
+            && line.contains(r#"module="integration_test""#)
+            && line.contains(r#"result="ok""#)
+            && line.ends_with("} 2")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="InMemory<String>::load""#)
+            && line.ends_with("} 1")
+    }));
+    assert!(!metrics.contains("::skipped"));
+    assert!(!metrics.contains("::name"));
+}
+
+#[tokio::test]
+async fn async_trait_methods() {
+    prometheus_exporter::try_init().ok();
+
+    #[autometrics]
+    fn fetch_row() {}
+
+    #[async_trait::async_trait]
+    trait Database {
+        async fn query(&self) -> Result<(), ()>;
+    }
+
+    struct Postgres;
+
+    // The async_trait attribute is expanded before the autometrics one,
+    // so autometrics instruments the future returned by the desugared method
+    #[async_trait::async_trait]
+    #[autometrics]
+    impl Database for Postgres {
+        async fn query(&self) -> Result<(), ()> {
+            fetch_row();
+            Err(())
+        }
+    }
+
+    Postgres.query().await.unwrap_err();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="Postgres::query""#)
+            && line.contains(r#"result="error""#)
+            && line.ends_with("} 1")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="fetch_row""#)
+            && line.contains(r#"caller_function="Postgres::query""#)
+            && line.ends_with("} 1")
+    }));
+}
+
+#[tokio::test]
+async fn impl_future_return() {
+    prometheus_exporter::try_init().ok();
+
+    #[autometrics]
+    fn fetch_page() {}
+
+    // The metrics track the execution of the returned future rather than the call that creates it
+    #[autometrics]
+    fn load_page(delay: Duration) -> impl Future<Output = Result<(), ()>> + Send {
+        async move {
+            tokio::time::sleep(delay).await;
+            fetch_page();
+            Err(())
+        }
+    }
+
+    let future = load_page(Duration::from_millis(50));
+    future.await.unwrap_err();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="load_page""#)
+            && line.contains(r#"result="error""#)
+            && line.ends_with("} 1")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="fetch_page""#)
+            && line.contains(r#"caller_function="load_page""#)
+            && line.ends_with("} 1")
+    }));
+    let duration = metrics
+        .lines()
+        .find(|line| {
+            line.starts_with("function_calls_duration_seconds_sum{")
+                && line.contains(r#"function="load_page""#)
+        })
+        .and_then(|line| line.rsplit(' ').next()?.parse::<f64>().ok())
+        .unwrap();
+    assert!(duration >= 0.05, "{duration}");
+}
+
+#[test]
+fn never_returning_function() {
+    prometheus_exporter::try_init().ok();
+
+    #[autometrics]
+    fn abort_job() -> ! {
+        panic!("The job was aborted");
+    }
+
+    assert!(std::panic::catch_unwind(|| abort_job()).is_err());
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="abort_job""#)
+            && line.contains(r#"result="error""#)
+            && line.contains(r#"panic="true""#)
+            && line.ends_with("} 1")
+    }));
+}
+
 #[test]
 fn struct_name_autometrics_macro_attribute() {
     prometheus_exporter::try_init().ok();
@@ -166,6 +327,240 @@ fn error_if() {
     }));
 }
 
+#[test]
+fn predicate_closures_and_methods() {
+    prometheus_exporter::try_init().ok();
+
+    struct Response {
+        status: u16,
+    }
+
+    impl Response {
+        fn is_success(&self) -> bool {
+            (200..300).contains(&self.status)
+        }
+    }
+
+    #[autometrics(ok_if = |response| response.status < 400)]
+    fn ok_if_closure_fn(status: u16) -> Response {
+        Response { status }
+    }
+
+    #[autometrics(error_if = |values: &Vec<u32>| values.is_empty())]
+    fn error_if_closure_fn() -> Vec<u32> {
+        Vec::new()
+    }
+
+    #[autometrics(ok_if = Response::is_success)]
+    fn ok_if_method_fn() -> Response {
+        Response { status: 500 }
+    }
+
+    ok_if_closure_fn(302);
+    ok_if_closure_fn(404);
+    error_if_closure_fn();
+    ok_if_method_fn();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    for (function, result) in [
+        ("ok_if_closure_fn", "ok"),
+        ("ok_if_closure_fn", "error"),
+        ("error_if_closure_fn", "error"),
+        ("ok_if_method_fn", "error"),
+    ] {
+        assert!(
+            metrics.lines().any(|line| {
+                line.starts_with("function_calls_total{")
+                    && line.contains(&format!(r#"function="{function}""#))
+                    && line.contains(&format!(r#"result="{result}""#))
+                    && line.ends_with("} 1")
+            }),
+            "{function} is missing the {result} result"
+        );
+    }
+}
+
+#[test]
+fn status_predicates() {
+    prometheus_exporter::try_init().ok();
+
+    #[autometrics(ok_if_status < 500)]
+    fn ok_if_status_fn(status: u16) -> http::Response<()> {
+        let mut response = http::Response::new(());
+        *response.status_mut() = http::StatusCode::from_u16(status).unwrap();
+        response
+    }
+
+    #[autometrics(error_if_status in [502, 503])]
+    fn error_if_status_fn(status: u16) -> Result<u16, u16> {
+        if status < 400 {
+            Ok(status)
+        } else {
+            Err(status)
+        }
+    }
+
+    ok_if_status_fn(404);
+    ok_if_status_fn(500);
+    ok_if_status_fn(500);
+    error_if_status_fn(200).ok();
+    error_if_status_fn(404).ok();
+    error_if_status_fn(404).ok();
+    error_if_status_fn(503).ok();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    for (function, result, count) in [
+        ("ok_if_status_fn", "ok", 1),
+        ("ok_if_status_fn", "error", 2),
+        // A client error is not counted as an error, even though it is returned as an `Err`
+        ("error_if_status_fn", "ok", 3),
+        ("error_if_status_fn", "error", 1),
+    ] {
+        assert!(
+            metrics.lines().any(|line| {
+                line.starts_with("function_calls_total{")
+                    && line.contains(&format!(r#"function="{function}""#))
+                    && line.contains(&format!(r#"result="{result}""#))
+                    && line.ends_with(&format!("}} {count}"))
+            }),
+            "{function} is missing the {result} result"
+        );
+    }
+}
+
+#[test]
+fn error_details() {
+    prometheus_exporter::try_init().ok();
+
+    #[derive(Debug)]
+    enum DatabaseError {
+        Timeout,
+        Query(String),
+    }
+
+    impl std::fmt::Display for DatabaseError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                DatabaseError::Timeout => write!(f, "timeout"),
+                DatabaseError::Query(query) => write!(f, "query failed: {query}"),
+            }
+        }
+    }
+
+    impl std::error::Error for DatabaseError {}
+
+    /// An error whose Debug representation does not start with a variant name
+    struct OpaqueError;
+
+    impl std::fmt::Debug for OpaqueError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "connection reset by peer")
+        }
+    }
+
+    impl std::fmt::Display for OpaqueError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "connection reset by peer")
+        }
+    }
+
+    impl std::error::Error for OpaqueError {}
+
+    /// An error with a large Debug representation, of which only the variant name is formatted
+    struct PayloadError;
+
+    impl std::fmt::Debug for PayloadError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.write_str("Payload")?;
+            f.write_str("(")?;
+            f.write_str("[0, 0, 0")?;
+            unreachable!("the Debug representation is formatted past the variant name")
+        }
+    }
+
+    impl std::fmt::Display for PayloadError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "payload too large")
+        }
+    }
+
+    impl std::error::Error for PayloadError {}
+
+    #[autometrics(error_details)]
+    fn error_details_fn(n: u8) -> Result<(), DatabaseError> {
+        match n {
+            0 => Ok(()),
+            1 => Err(DatabaseError::Timeout),
+            _ => Err(DatabaseError::Query(format!("query {n}"))),
+        }
+    }
+
+    #[autometrics(error_details)]
+    fn opaque_error_fn() -> Result<(), OpaqueError> {
+        Err(OpaqueError)
+    }
+
+    #[autometrics(error_details)]
+    fn payload_error_fn() -> Result<(), PayloadError> {
+        Err(PayloadError)
+    }
+
+    // Errors that do not implement std::error::Error do not get the label
+    #[autometrics(error_details)]
+    fn string_error_fn() -> Result<(), String> {
+        Err("error".to_string())
+    }
+
+    error_details_fn(0).ok();
+    error_details_fn(1).ok();
+    error_details_fn(2).ok();
+    error_details_fn(3).ok();
+    opaque_error_fn().ok();
+    payload_error_fn().ok();
+    string_error_fn().ok();
+
+    // Depending on the backend, labels without a value are either empty or left out
+    let no_error_type =
+        |line: &str| !line.contains("error_type=") || line.contains(r#"error_type="""#);
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="error_details_fn""#)
+            && line.contains(r#"result="ok""#)
+            && no_error_type(line)
+            && line.ends_with("} 1")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="error_details_fn""#)
+            && line.contains(r#"error_type="Timeout""#)
+            && line.ends_with("} 1")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="error_details_fn""#)
+            && line.contains(r#"error_type="Query""#)
+            && line.ends_with("} 2")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="opaque_error_fn""#)
+            && line.contains(r#"error_type="OpaqueError""#)
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="payload_error_fn""#)
+            && line.contains(r#"error_type="Payload""#)
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="string_error_fn""#)
+            && line.contains(r#"result="error""#)
+            && no_error_type(line)
+    }));
+}
+
 #[test]
 fn caller_labels() {
     prometheus_exporter::try_init().ok();
@@ -195,6 +590,164 @@ fn caller_labels() {
     }));
 }
 
+#[test]
+fn no_caller() {
+    prometheus_exporter::try_init().ok();
+
+    #[autometrics]
+    fn no_caller_parent() {
+        no_caller_fn()
+    }
+
+    #[autometrics(no_caller)]
+    fn no_caller_fn() {
+        no_caller_child()
+    }
+
+    #[autometrics]
+    fn no_caller_child() {}
+
+    no_caller_parent();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="no_caller_fn""#)
+            && line.contains(r#"caller_function="""#)
+            && line.ends_with("} 1")
+    }));
+    // The function does not set itself as the caller of the functions it calls
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="no_caller_child""#)
+            && line.contains(r#"caller_function="no_caller_parent""#)
+            && line.ends_with("} 1")
+    }));
+}
+
+#[test]
+fn recursive_label() {
+    prometheus_exporter::try_init().ok();
+
+    #[autometrics(recursive = label)]
+    fn recursive_label_fn(n: u32) {
+        if n > 0 {
+            recursive_label_fn(n - 1)
+        }
+    }
+
+    recursive_label_fn(2);
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="recursive_label_fn""#)
+            && line.contains(r#"caller_function="recursive_label_fn""#)
+            && line.contains(r#"recursive="true""#)
+            && line.ends_with("} 2")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="recursive_label_fn""#)
+            && line.contains(r#"caller_function="""#)
+            && !line.contains(r#"recursive="true""#)
+            && line.ends_with("} 1")
+    }));
+}
+
+#[test]
+fn recursive_skip_caller() {
+    prometheus_exporter::try_init().ok();
+
+    #[autometrics(recursive = skip_caller)]
+    fn recursive_skip_caller_fn(n: u32) {
+        if n > 0 {
+            recursive_skip_caller_fn(n - 1)
+        }
+    }
+
+    recursive_skip_caller_fn(2);
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(!metrics.lines().any(|line| {
+        line.contains(r#"function="recursive_skip_caller_fn""#)
+            && line.contains(r#"caller_function="recursive_skip_caller_fn""#)
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="recursive_skip_caller_fn""#)
+            && line.ends_with("} 3")
+    }));
+}
+
+#[test]
+fn recursive_concurrency() {
+    prometheus_exporter::try_init().ok();
+
+    #[autometrics(track_concurrency)]
+    fn recursive_concurrency_fn(n: u32) -> String {
+        if n > 0 {
+            recursive_concurrency_fn(n - 1)
+        } else {
+            prometheus_exporter::encode_to_string().unwrap()
+        }
+    }
+
+    // Only the top-level call is counted, even though three calls are in progress
+    let metrics = recursive_concurrency_fn(2);
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_concurrent{")
+            && line.contains(r#"function="recursive_concurrency_fn""#)
+            && line.ends_with("} 1")
+    }));
+}
+
+// The other backends configure the histogram buckets per metric name
+#[cfg(any(prometheus, prometheus_client))]
+#[test]
+fn custom_buckets() {
+    prometheus_exporter::try_init().ok();
+
+    #[autometrics(buckets = [0.0001, 0.0005, 1])]
+    fn custom_buckets_fn() {}
+
+    #[autometrics]
+    fn default_buckets_fn() {}
+
+    #[autometrics(fine_grained_latency)]
+    fn fine_grained_fn() {}
+
+    custom_buckets_fn();
+    default_buckets_fn();
+    fine_grained_fn();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    let buckets = |function: &str| {
+        metrics
+            .lines()
+            .filter(|line| {
+                line.starts_with("function_calls_duration_seconds_bucket{")
+                    && line.contains(&format!("function=\"{function}\""))
+            })
+            .count()
+    };
+    // Including the +Inf bucket
+    assert_eq!(buckets("custom_buckets_fn"), 4);
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_duration_seconds_bucket{")
+            && line.contains(r#"function="custom_buckets_fn""#)
+            && line.contains(r#"le="0.0005""#)
+    }));
+    // The default buckets
+    assert_eq!(buckets("default_buckets_fn"), 15);
+    assert_eq!(buckets("fine_grained_fn"), 16);
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_duration_seconds_bucket{")
+            && line.contains(r#"function="fine_grained_fn""#)
+            && line.contains(r#"le="0.000001""#)
+    }));
+}
+
 #[test]
 fn build_info() {
     prometheus_exporter::try_init().ok();
@@ -213,3 +766,287 @@ fn build_info() {
         && line.contains(r#"service_name="autometrics""#)
         && line.ends_with("} 1")));
 }
+
+#[test]
+fn generic_params() {
+    prometheus_exporter::try_init().ok();
+
+    #[autometrics(generic_params)]
+    fn generic_fn<T: Default, U>(_value: U) -> T {
+        T::default()
+    }
+
+    generic_fn::<String, _>(1u8);
+    generic_fn::<String, _>(2u8);
+    generic_fn::<Vec<String>, _>(());
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="generic_fn""#)
+            && line.contains(r#"type_params="(String, u8)""#)
+            && line.ends_with("} 2")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_duration_seconds_count{")
+            && line.contains(r#"function="generic_fn""#)
+            && line.contains(r#"type_params="(Vec<String>, ())""#)
+            && line.ends_with("} 1")
+    }));
+}
+
+#[test]
+fn panic() {
+    prometheus_exporter::try_init().ok();
+
+    #[autometrics(track_concurrency)]
+    fn panicking_fn(panic: bool) -> Result<(), ()> {
+        if panic {
+            panic!("Something went wrong");
+        }
+        Ok(())
+    }
+
+    #[autometrics]
+    fn panicking_caller() {
+        panicking_fn(true).ok();
+    }
+
+    panicking_fn(false).ok();
+    assert!(std::panic::catch_unwind(|| panicking_fn(true)).is_err());
+    assert!(std::panic::catch_unwind(panicking_caller).is_err());
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="panicking_fn""#)
+            && line.contains(r#"caller_function="""#)
+            && line.contains(r#"result="error""#)
+            && line.contains(r#"panic="true""#)
+            && line.ends_with("} 1")
+    }));
+    // The caller is known even though the call panicked
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="panicking_fn""#)
+            && line.contains(r#"caller_function="panicking_caller""#)
+            && line.contains(r#"panic="true""#)
+            && line.ends_with("} 1")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="panicking_caller""#)
+            && line.contains(r#"panic="true""#)
+            && line.ends_with("} 1")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_duration_seconds_count{")
+            && line.contains(r#"function="panicking_fn""#)
+            && line.ends_with("} 3")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_concurrent{")
+            && line.contains(r#"function="panicking_fn""#)
+            && line.ends_with("} 0")
+    }));
+}
+
+#[test]
+fn sampling() {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    prometheus_exporter::try_init().ok();
+
+    static SKIP: AtomicBool = AtomicBool::new(false);
+
+    fn should_skip() -> bool {
+        SKIP.load(Ordering::Relaxed)
+    }
+
+    #[autometrics(skip_if = should_skip)]
+    fn skipped_fn() {}
+
+    #[autometrics(sample_rate = 0.25)]
+    fn sampled_fn() {}
+
+    skipped_fn();
+    SKIP.store(true, Ordering::Relaxed);
+    skipped_fn();
+    skipped_fn();
+
+    for _ in 0..1000 {
+        sampled_fn();
+    }
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    let value = |prefix: &str, function: &str| -> u64 {
+        metrics
+            .lines()
+            .filter(|line| {
+                line.starts_with(prefix) && line.contains(&format!(r#"function="{function}""#))
+            })
+            .map(|line| line.rsplit(' ').next().unwrap().parse::<u64>().unwrap())
+            .sum()
+    };
+
+    assert_eq!(value("function_calls_total{", "skipped_fn"), 1);
+    assert_eq!(
+        value("function_calls_duration_seconds_count{", "skipped_fn"),
+        1
+    );
+
+    // Every recorded call is counted as 4 calls, but only the recorded calls are in the histogram
+    let calls = value("function_calls_total{", "sampled_fn");
+    let recorded = value("function_calls_duration_seconds_count{", "sampled_fn");
+    assert!(recorded > 0 && recorded < 1000);
+    assert_eq!(calls, recorded * 4);
+}
+
+#[test]
+fn count_only() {
+    prometheus_exporter::try_init().ok();
+
+    struct Point {
+        x: f64,
+    }
+
+    impl Point {
+        #[autometrics(count_only, inline_hint)]
+        fn x(&self) -> f64 {
+            self.x
+        }
+    }
+
+    #[autometrics(count_only)]
+    fn count_only_fn(should_error: bool) -> Result<(), ()> {
+        if should_error {
+            Err(())
+        } else {
+            Ok(())
+        }
+    }
+
+    #[autometrics(count_only)]
+    fn count_only_panic() -> u32 {
+        panic!("Something went wrong");
+    }
+
+    let point = Point { x: 1.0 };
+    for _ in 0..3 {
+        assert_eq!(point.x(), 1.0);
+    }
+    count_only_fn(true).ok();
+    count_only_fn(false).ok();
+    assert!(std::panic::catch_unwind(count_only_panic).is_err());
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    let value = |prefix: &str, function: &str, result: &str| -> u64 {
+        metrics
+            .lines()
+            .filter(|line| {
+                line.starts_with(prefix)
+                    && line.contains(&format!(r#"function="{function}""#))
+                    && line.contains(result)
+            })
+            .map(|line| line.rsplit(' ').next().unwrap().parse::<u64>().unwrap())
+            .sum()
+    };
+
+    assert_eq!(value("function_calls_total{", "x", ""), 3);
+    assert_eq!(
+        value(
+            "function_calls_total{",
+            "count_only_fn",
+            r#"result="error""#
+        ),
+        1
+    );
+    assert_eq!(
+        value("function_calls_total{", "count_only_fn", r#"result="ok""#),
+        1
+    );
+    // Calls that panic are counted as errors, like the calls of the functions that are timed
+    assert_eq!(
+        value(
+            "function_calls_total{",
+            "count_only_panic",
+            r#"panic="true""#
+        ),
+        1
+    );
+    assert_eq!(
+        value(
+            "function_calls_total{",
+            "count_only_panic",
+            r#"result="error""#
+        ),
+        1
+    );
+    // The latency is never measured
+    assert_eq!(value("function_calls_duration_seconds_count{", "x", ""), 0);
+    assert_eq!(
+        value(
+            "function_calls_duration_seconds_count{",
+            "count_only_fn",
+            ""
+        ),
+        0
+    );
+}
+
+#[tokio::test]
+async fn track_size() {
+    prometheus_exporter::try_init().ok();
+
+    struct Response {
+        body: Vec<u8>,
+    }
+
+    fn body_len(response: &Response) -> usize {
+        response.body.len()
+    }
+
+    #[autometrics(track_size = body_len)]
+    fn download(len: usize) -> Response {
+        Response { body: vec![0; len] }
+    }
+
+    #[autometrics(track_size = |body| body.len())]
+    async fn download_async() -> String {
+        "x".repeat(100)
+    }
+
+    download(10);
+    download(1000);
+    download_async().await;
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    let value = |prefix: &str, function: &str| -> Option<f64> {
+        metrics
+            .lines()
+            .find(|line| {
+                line.starts_with(prefix) && line.contains(&format!(r#"function="{function}""#))
+            })
+            .map(|line| line.rsplit(' ').next().unwrap().parse().unwrap())
+    };
+
+    assert_eq!(
+        value("function_response_size_bytes_count{", "download"),
+        Some(2.0)
+    );
+    assert_eq!(
+        value("function_response_size_bytes_sum{", "download"),
+        Some(1010.0)
+    );
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_response_size_bytes_bucket{")
+            && line.contains(r#"function="download""#)
+            // prometheus-client formats the bound as 64.0
+            && (line.contains(r#"le="64""#) || line.contains(r#"le="64.0""#))
+            && line.ends_with(" 1")
+    }));
+    assert_eq!(
+        value("function_response_size_bytes_sum{", "download_async"),
+        Some(100.0)
+    );
+}
@@ -0,0 +1,66 @@
+#![cfg(all(prometheus_exporter, wide_events))]
+
+use autometrics::settings::AutometricsSettings;
+use autometrics::wide_events::{JsonLinesSink, WideEvents};
+use autometrics::{autometrics, prometheus_exporter};
+use std::io::{self, Write};
+use std::panic::catch_unwind;
+
+/// A writer that panics the first time it is used, which poisons the lock around it
+#[derive(Default)]
+struct PanicOnceWriter {
+    panicked: bool,
+    lines: usize,
+}
+
+impl Write for PanicOnceWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if !self.panicked {
+            self.panicked = true;
+            panic!("failed to write the event");
+        }
+        self.lines += buf.iter().filter(|byte| **byte == b'\n').count();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[autometrics]
+fn recorded_fn() {}
+
+#[test]
+fn recovers_poisoned_locks() {
+    AutometricsSettings::builder()
+        .wide_events(WideEvents::new(JsonLinesSink::new(
+            PanicOnceWriter::default(),
+        )))
+        .init();
+
+    // The first call panics while the lock around the writer is held
+    assert!(catch_unwind(recorded_fn).is_err());
+
+    // The following calls keep recording metrics instead of panicking
+    recorded_fn();
+    recorded_fn();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(
+        metrics.lines().any(
+            |line| line.starts_with("autometrics_lock_recoveries_total{")
+                && line.contains(r#"lock="wide_events""#)
+                && line.ends_with("} 1")
+        ),
+        "{metrics}"
+    );
+    assert!(
+        metrics
+            .lines()
+            .any(|line| line.starts_with("function_calls_total{")
+                && line.contains(r#"function="recorded_fn""#)
+                && line.ends_with("} 3")),
+        "{metrics}"
+    );
+}
@@ -0,0 +1,55 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::{autometrics, prometheus_exporter};
+use std::sync::Barrier;
+
+#[autometrics(track_concurrency(max))]
+fn upload(started: &Barrier, finish: &Barrier) {
+    started.wait();
+    finish.wait();
+}
+
+#[test]
+fn max_concurrency_since_last_scrape() {
+    // This test is in its own file because exporting the metrics resets the maximum,
+    // so other tests must not export them while it runs
+    prometheus_exporter::init();
+
+    let started = Barrier::new(4);
+    let finish = Barrier::new(4);
+    std::thread::scope(|scope| {
+        for _ in 0..3 {
+            scope.spawn(|| upload(&started, &finish));
+        }
+        started.wait();
+        finish.wait();
+    });
+
+    let gauge = |metrics: &str, name: &str| {
+        metrics
+            .lines()
+            .find(|line| {
+                line.starts_with(&format!("{name}{{")) && line.contains(r#"function="upload""#)
+            })
+            .and_then(|line| line.rsplit(' ').next())
+            .map(str::to_string)
+    };
+
+    // All three calls were in progress at the same time, but they have all finished
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert_eq!(
+        gauge(&metrics, "function_calls_concurrent").as_deref(),
+        Some("0")
+    );
+    assert_eq!(
+        gauge(&metrics, "function_calls_concurrent_max").as_deref(),
+        Some("3")
+    );
+
+    // The maximum is reset to the number of calls in progress after every scrape
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert_eq!(
+        gauge(&metrics, "function_calls_concurrent_max").as_deref(),
+        Some("0")
+    );
+}
@@ -0,0 +1,93 @@
+#![cfg(all(prometheus_exporter, metrics))]
+
+use autometrics::settings::AutometricsSettings;
+use autometrics::{autometrics, prometheus_exporter};
+use metrics::{
+    Counter, CounterFn, Gauge, Histogram, Key, KeyName, Metadata, Recorder, SharedString, Unit,
+};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// A recorder that only keeps the counters, standing in for an existing pipeline like a StatsD exporter
+#[derive(Clone, Default)]
+struct CountingRecorder {
+    counters: Arc<Mutex<HashMap<Key, Arc<AtomicU64>>>>,
+}
+
+impl CountingRecorder {
+    fn count(&self, name: &str, function: &str) -> u64 {
+        self.counters
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(key, _)| {
+                key.name() == name
+                    && key
+                        .labels()
+                        .any(|label| label.key() == "function" && label.value() == function)
+            })
+            .map(|(_, counter)| counter.load(Ordering::Relaxed))
+            .sum()
+    }
+}
+
+struct SharedCounter(Arc<AtomicU64>);
+
+impl CounterFn for SharedCounter {
+    fn increment(&self, value: u64) {
+        self.0.fetch_add(value, Ordering::Relaxed);
+    }
+
+    fn absolute(&self, value: u64) {
+        self.0.fetch_max(value, Ordering::Relaxed);
+    }
+}
+
+impl Recorder for CountingRecorder {
+    fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+    fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+    fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+        let counter = self
+            .counters
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_default()
+            .clone();
+        Counter::from_arc(Arc::new(SharedCounter(counter)))
+    }
+
+    fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+        Gauge::noop()
+    }
+
+    fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+        Histogram::noop()
+    }
+}
+
+#[autometrics]
+fn recorded_fn() {}
+
+#[test]
+fn records_to_existing_recorder() {
+    let recorder = CountingRecorder::default();
+    metrics::set_global_recorder(recorder.clone()).unwrap();
+
+    // This would fail if the exporter tried to install its own recorder
+    AutometricsSettings::builder()
+        .use_existing_metrics_recorder()
+        .init();
+
+    recorded_fn();
+    recorded_fn();
+
+    assert_eq!(recorder.count("function_calls_total", "recorded_fn"), 2);
+
+    // The metrics are exported by the existing recorder instead
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(!metrics.contains("recorded_fn"), "{metrics}");
+}
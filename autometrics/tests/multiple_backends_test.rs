@@ -0,0 +1,57 @@
+#![cfg(all(prometheus_exporter, prometheus_client, opentelemetry))]
+
+use autometrics::{autometrics, prometheus_exporter};
+
+#[autometrics]
+fn checkout() {}
+
+#[autometrics(buckets = [0.0002, 0.0004])]
+fn lookup_cart() {}
+
+#[test]
+fn records_with_every_backend() {
+    prometheus_exporter::try_init().ok();
+
+    checkout();
+    checkout();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+
+    // Both backends record the calls, but each metric family is only exported once
+    let types: Vec<&str> = metrics
+        .lines()
+        .filter(|line| {
+            *line == "# TYPE function_calls counter"
+                || *line == "# TYPE function_calls_total counter"
+        })
+        .collect();
+    assert_eq!(types.len(), 1, "{metrics}");
+
+    let calls: u64 = metrics
+        .lines()
+        .filter(|line| {
+            line.starts_with("function_calls_total{") && line.contains(r#"function="checkout""#)
+        })
+        .filter_map(|line| line.rsplit(' ').next()?.parse::<u64>().ok())
+        .sum();
+    assert_eq!(calls, 2, "{metrics}");
+}
+
+#[test]
+fn per_function_buckets_with_a_supported_backend() {
+    prometheus_exporter::try_init().ok();
+
+    lookup_cart();
+
+    // The `prometheus-client` backend uses the buckets of the function,
+    // while OpenTelemetry uses the buckets from its views
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(
+        metrics.lines().any(|line| {
+            line.starts_with("function_calls_duration_seconds_bucket{")
+                && line.contains(r#"function="lookup_cart""#)
+                && line.contains(r#"le="0.0002""#)
+        }),
+        "{metrics}"
+    );
+}
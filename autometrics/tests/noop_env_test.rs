@@ -0,0 +1,30 @@
+//! CI runs this test with `AUTOMETRICS_NOOP=0` and `AUTOMETRICS_NOOP=false`, which must not
+//! turn the instrumentation off, because only `1` and `true` do.
+#![cfg(all(not(feature = "noop"), prometheus_exporter))]
+
+use autometrics::{autometrics, prometheus_exporter};
+
+#[autometrics]
+fn still_instrumented() {}
+
+#[test]
+fn falsy_noop_env_keeps_instrumentation() {
+    let noop = option_env!("AUTOMETRICS_NOOP").unwrap_or_default();
+    assert!(
+        noop != "1" && !noop.eq_ignore_ascii_case("true"),
+        "run this test without turning on AUTOMETRICS_NOOP"
+    );
+
+    prometheus_exporter::try_init().ok();
+    still_instrumented();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(
+        metrics.lines().any(|line| {
+            line.starts_with("function_calls_total{")
+                && line.contains(r#"function="still_instrumented""#)
+                && line.ends_with(" 1")
+        }),
+        "{metrics}"
+    );
+}
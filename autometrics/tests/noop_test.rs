@@ -0,0 +1,48 @@
+#![cfg(all(feature = "noop", prometheus_exporter))]
+
+use autometrics::{autometrics, prometheus_exporter};
+
+#[autometrics(track_concurrency, label(key = "shard", value = "a"))]
+fn add(a: u32, b: u32) -> Result<u32, ()> {
+    Ok(a + b)
+}
+
+struct Database;
+
+#[autometrics]
+impl Database {
+    fn load(&self) -> u32 {
+        1
+    }
+
+    #[skip_autometrics]
+    fn skipped(&self) -> u32 {
+        2
+    }
+}
+
+#[autometrics]
+trait Store {
+    fn save(&self) -> bool {
+        true
+    }
+}
+
+impl Store for Database {}
+
+#[test]
+fn leaves_functions_unchanged() {
+    prometheus_exporter::try_init().ok();
+
+    assert_eq!(add(1, 2), Ok(3));
+    assert_eq!(Database.load() + Database.skipped(), 3);
+    assert!(Database.save());
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(
+        !metrics
+            .lines()
+            .any(|line| line.starts_with("function_calls")),
+        "{metrics}"
+    );
+}
@@ -86,3 +86,92 @@ fn combined_objective() {
             && line.ends_with("} 2")
     }));
 }
+
+#[cfg(prometheus_exporter)]
+#[test]
+fn inline_objective() {
+    prometheus_exporter::try_init().ok();
+
+    #[autometrics(
+        objective(
+            name = "inline",
+            success_rate = 99.9,
+            latency(threshold = "0.25", percentile = 99),
+        ),
+        track_concurrency
+    )]
+    fn inline_objective_fn() -> &'static str {
+        "Hello world!"
+    }
+
+    inline_objective_fn();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="inline_objective_fn""#)
+            && line.contains(r#"objective_name="inline""#)
+            && line.contains(r#"objective_percentile="99.9""#)
+            && line.ends_with("} 1")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_duration_seconds_bucket{")
+            && line.contains(r#"function="inline_objective_fn""#)
+            && line.contains(r#"objective_latency_threshold="0.25""#)
+            && line.contains(r#"objective_name="inline""#)
+            && line.contains(r#"objective_percentile="99""#)
+            && line.ends_with("} 1")
+    }));
+}
+
+#[test]
+fn registered_objectives_list_their_functions() {
+    const REGISTERED: Objective =
+        Objective::new("registered").success_rate(ObjectivePercentile::P99);
+    const REGISTERED_LATENCY: Objective =
+        Objective::new("registered").latency(ObjectiveLatency::Ms100, ObjectivePercentile::P95);
+
+    #[autometrics(objective = REGISTERED)]
+    fn first_registered_fn() {}
+
+    #[autometrics(objective = REGISTERED)]
+    fn second_registered_fn() {}
+
+    #[autometrics(objective = REGISTERED_LATENCY)]
+    fn latency_registered_fn() {}
+
+    // The functions do not need to be called to be listed
+    let registered: Vec<RegisteredObjective> = registered_objectives()
+        .into_iter()
+        .filter(|registered| registered.objective.name() == "registered")
+        .collect();
+    assert_eq!(registered.len(), 2, "{registered:?}");
+
+    let success_rate = registered
+        .iter()
+        .find(|registered| registered.objective == REGISTERED)
+        .unwrap();
+    assert_eq!(
+        success_rate.objective.success_rate_target(),
+        Some(ObjectivePercentile::P99)
+    );
+    assert_eq!(success_rate.objective.latency_target(), None);
+    let functions: Vec<&str> = success_rate
+        .functions
+        .iter()
+        .map(|function| function.name)
+        .collect();
+    assert_eq!(functions, ["first_registered_fn", "second_registered_fn"]);
+
+    let latency = registered
+        .iter()
+        .find(|registered| registered.objective == REGISTERED_LATENCY)
+        .unwrap();
+    assert_eq!(
+        latency.objective.latency_target(),
+        Some((ObjectiveLatency::Ms100, ObjectivePercentile::P95))
+    );
+    assert_eq!(latency.functions.len(), 1);
+    assert_eq!(latency.functions[0].name, "latency_registered_fn");
+    assert_eq!(latency.functions[0].module, "objectives_test");
+}
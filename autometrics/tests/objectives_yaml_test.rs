@@ -0,0 +1,144 @@
+#![cfg(all(prometheus_exporter, objectives_yaml))]
+use autometrics::{autometrics, objectives::*, prometheus_exporter};
+use once_cell::sync::Lazy;
+
+static OBJECTIVES: Lazy<ObjectiveSet> = Lazy::new(|| {
+    from_yaml_str(
+        r#"
+objectives:
+  - name: yaml_api
+    success_rate: 99.9
+    latency:
+      threshold: 0.25
+      percentile: 99
+"#,
+    )
+    .unwrap()
+});
+
+#[test]
+fn objective_from_yaml() {
+    prometheus_exporter::try_init().ok();
+
+    #[autometrics(objective = OBJECTIVES.objective("yaml_api"))]
+    fn yaml_objective_fn() -> &'static str {
+        "Hello world!"
+    }
+
+    yaml_objective_fn();
+    yaml_objective_fn();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics
+        .lines()
+        .any(|line| line.starts_with("function_calls_total{")
+            && line.contains(r#"function="yaml_objective_fn""#)
+            && line.contains(r#"objective_name="yaml_api""#)
+            && line.contains(r#"objective_percentile="99.9""#)
+            && line.ends_with("} 2")));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_duration_seconds_bucket{")
+            && line.contains(r#"function="yaml_objective_fn""#)
+            && line.contains(r#"objective_latency_threshold="0.25""#)
+            && line.contains(r#"objective_name="yaml_api""#)
+            && line.contains(r#"objective_percentile="99""#)
+            && line.ends_with("} 2")
+    }));
+}
+
+#[test]
+fn invalid_objectives() {
+    assert!(matches!(
+        from_yaml_str("objectives:\n  - name: a\n  - name: a\n"),
+        Err(ObjectivesYamlError::DuplicateObjective(name)) if name == "a"
+    ));
+
+    #[cfg(not(custom_objective_percentile))]
+    assert!(matches!(
+        from_yaml_str("objectives:\n  - name: a\n    success_rate: 42\n"),
+        Err(ObjectivesYamlError::UnsupportedPercentile { .. })
+    ));
+
+    assert!(matches!(
+        from_yaml_str(
+            "objectives:
+  - name: a
+    latency: { threshold: 0.25, percentile: 99 }
+    composite: { threshold: 0.25, percentile: 99 }
+"
+        ),
+        Err(ObjectivesYamlError::LatencyAndComposite(name)) if name == "a"
+    ));
+
+    assert!(from_yaml_str("objectives:\n  - name: a\n    unknown: 1\n").is_err());
+}
+
+#[test]
+fn composite_objective_from_yaml() {
+    let objectives = from_yaml_str(
+        "objectives:
+  - name: checkout
+    success_rate: 99.9
+    composite:
+      threshold: 0.5
+      percentile: 99
+",
+    )
+    .unwrap();
+
+    let checkout = objectives.objective("checkout");
+    assert_eq!(
+        checkout,
+        Objective::new("checkout")
+            .success_rate(ObjectivePercentile::P99_9)
+            .composite(ObjectiveLatency::Ms500, ObjectivePercentile::P99)
+    );
+    assert_eq!(checkout.latency_target(), None);
+}
+
+#[test]
+fn unknown_objectives() {
+    let objectives = from_yaml_str("objectives:\n  - name: a\n  - name: b\n").unwrap();
+    assert_eq!(objectives.try_objective("b").unwrap().name(), "b");
+    assert!(matches!(
+        objectives.try_objective("c"),
+        Err(ObjectivesYamlError::UnknownObjective(name)) if name == "c"
+    ));
+    assert_eq!(
+        objectives.iter().map(Objective::name).collect::<Vec<_>>(),
+        ["a", "b"]
+    );
+
+    // The names used by the instrumented functions can be checked when the set is loaded
+    assert!(objectives.clone().require(&["a", "b"]).is_ok());
+    assert!(matches!(
+        objectives.require(&["a", "c"]),
+        Err(ObjectivesYamlError::UnknownObjective(name)) if name == "c"
+    ));
+}
+
+#[test]
+fn objective_is_resolved_once() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static RESOLVED: AtomicUsize = AtomicUsize::new(0);
+
+    fn resolve_objective() -> Objective {
+        RESOLVED.fetch_add(1, Ordering::Relaxed);
+        OBJECTIVES.objective("yaml_api")
+    }
+
+    prometheus_exporter::try_init().ok();
+
+    #[autometrics(objective = resolve_objective())]
+    fn resolved_once_fn() -> Result<(), ()> {
+        Err(())
+    }
+
+    for _ in 0..3 {
+        resolved_once_fn().ok();
+    }
+
+    // The objective is resolved on the first call and kept by the call site
+    assert_eq!(RESOLVED.load(Ordering::Relaxed), 1);
+}
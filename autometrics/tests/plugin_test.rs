@@ -0,0 +1,83 @@
+#![cfg(all(feature = "plugin", prometheus_exporter))]
+use autometrics::plugin::{self, PluginError};
+use autometrics::{autometrics, objectives::*, prometheus_exporter};
+
+const PLUGIN_SLO: Objective = Objective::new("plugin")
+    .success_rate(ObjectivePercentile::P99)
+    .latency(ObjectiveLatency::Ms250, ObjectivePercentile::P95);
+
+#[autometrics(objective = PLUGIN_SLO, track_concurrency)]
+fn plugin_fn(fail: bool) -> Result<(), ()> {
+    if fail {
+        Err(())
+    } else {
+        Ok(())
+    }
+}
+
+#[autometrics(count_only)]
+fn counted_plugin_fn() -> Result<(), ()> {
+    Ok(())
+}
+
+#[test]
+fn record_through_host() {
+    prometheus_exporter::try_init().ok();
+
+    // The test binary acts as both the host and the plugin
+    unsafe { plugin::connect(plugin::host()) }.unwrap();
+    assert!(plugin::is_connected());
+    assert!(matches!(
+        unsafe { plugin::connect(plugin::host()) },
+        Err(PluginError::AlreadyConnected)
+    ));
+    assert!(matches!(
+        unsafe { plugin::connect(std::ptr::null()) },
+        Err(PluginError::NullHost)
+    ));
+
+    plugin_fn(false).ok();
+    plugin_fn(false).ok();
+    plugin_fn(true).ok();
+    for _ in 0..4 {
+        counted_plugin_fn().ok();
+    }
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="plugin_fn""#)
+            && line.contains(r#"objective_name="plugin""#)
+            && line.contains(r#"objective_percentile="99""#)
+            && line.contains(r#"result="ok""#)
+            && line.ends_with("} 2")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="plugin_fn""#)
+            && line.contains(r#"result="error""#)
+            && line.ends_with("} 1")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_duration_seconds_count{")
+            && line.contains(r#"function="plugin_fn""#)
+            && line.contains(r#"objective_latency_threshold="0.25""#)
+            && line.contains(r#"objective_percentile="95""#)
+            && line.ends_with("} 3")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_concurrent{")
+            && line.contains(r#"function="plugin_fn""#)
+            && line.ends_with("} 0")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="counted_plugin_fn""#)
+            && line.contains(r#"result="ok""#)
+            && line.ends_with("} 4")
+    }));
+    assert!(!metrics.lines().any(|line| {
+        line.starts_with("function_calls_duration_seconds")
+            && line.contains(r#"function="counted_plugin_fn""#)
+    }));
+}
@@ -0,0 +1,105 @@
+#![cfg(push_gateway)]
+
+use autometrics::{autometrics, prometheus_exporter};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+#[autometrics]
+fn process_batch() {}
+
+/// A request received by the fake Pushgateway
+struct PushRequest {
+    request_line: String,
+    authorization: Option<String>,
+    body: String,
+}
+
+/// Start a fake Pushgateway that accepts `count` requests and sends them to the returned channel
+fn fake_push_gateway(count: usize) -> (String, mpsc::Receiver<PushRequest>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming().take(count) {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+
+            let (mut authorization, mut content_length) = (None, 0);
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                let header = header.trim_end();
+                if header.is_empty() {
+                    break;
+                }
+                let (name, value) = header.split_once(": ").unwrap();
+                match name.to_ascii_lowercase().as_str() {
+                    "authorization" => authorization = Some(value.to_string()),
+                    "content-length" => content_length = value.parse().unwrap(),
+                    _ => {}
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .unwrap();
+            sender
+                .send(PushRequest {
+                    request_line: request_line.trim_end().to_string(),
+                    authorization,
+                    body: String::from_utf8(body).unwrap(),
+                })
+                .unwrap();
+        }
+    });
+    (format!("http://user:secret@{address}/"), receiver)
+}
+
+#[test]
+fn push_metrics_to_gateway() {
+    prometheus_exporter::try_init().ok();
+    process_batch();
+
+    let (url, requests) = fake_push_gateway(3);
+    prometheus_exporter::push_once(&url, "nightly_batch").unwrap();
+    let request = requests.recv().unwrap();
+    assert_eq!(
+        request.request_line,
+        "PUT /metrics/job/nightly_batch HTTP/1.1"
+    );
+    // base64("user:secret")
+    assert_eq!(
+        request.authorization.as_deref(),
+        Some("Basic dXNlcjpzZWNyZXQ=")
+    );
+    assert!(request
+        .body
+        .lines()
+        .any(|line| line.starts_with("function_calls_total{")
+            && line.contains(r#"function="process_batch""#)));
+
+    // Job names with a slash are base64-encoded
+    prometheus_exporter::push_once(&url, "batch/nightly").unwrap();
+    let request = requests.recv().unwrap();
+    assert_eq!(
+        request.request_line,
+        "PUT /metrics/job@base64/YmF0Y2gvbmlnaHRseQ== HTTP/1.1"
+    );
+
+    // Stopping the background pusher pushes the metrics one last time
+    let pusher =
+        prometheus_exporter::push_to_gateway(url, "nightly_batch", Duration::from_secs(3600));
+    pusher.stop().unwrap();
+    let request = requests.recv().unwrap();
+    assert_eq!(
+        request.request_line,
+        "PUT /metrics/job/nightly_batch HTTP/1.1"
+    );
+}
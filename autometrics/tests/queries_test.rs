@@ -0,0 +1,76 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::objectives::{Objective, ObjectiveLatency, ObjectivePercentile};
+use autometrics::queries::{self, Sli};
+use autometrics::settings::AutometricsSettings;
+
+#[test]
+fn builds_queries_and_links_from_settings() {
+    AutometricsSettings::builder()
+        .prometheus_url("https://prometheus.example.com/")
+        .metric_name_prefix("payments")
+        .label_key_overrides(&[("function", "fn")])
+        .init();
+
+    let queries = queries::function("create_user");
+
+    let request_rate = queries.request_rate();
+    assert!(
+        request_rate.contains(
+            r#"{__name__=~"payments_function_calls(_count)?(_total)?",fn="create_user"}"#
+        ),
+        "{request_rate}"
+    );
+    assert!(
+        request_rate.contains("last_over_time(payments_build_info[1s])"),
+        "{request_rate}"
+    );
+    assert!(queries.error_ratio().contains(r#"result="error""#));
+    assert!(queries
+        .latency()
+        .contains(r#"__name__=~"payments_function_calls_duration(_seconds)?_bucket""#));
+    assert!(queries
+        .callee_request_rate()
+        .contains(r#"caller_function="create_user""#));
+
+    assert!(queries
+        .latency_quantile(0.99)
+        .starts_with("histogram_quantile(0.99, sum by (le, fn, module"));
+
+    let api_slo = queries::objective(
+        Objective::new("api")
+            .success_rate(ObjectivePercentile::P99_9)
+            .latency(ObjectiveLatency::Ms250, ObjectivePercentile::P99),
+    );
+    let error_ratio = api_slo.error_ratio(Sli::SuccessRate, "1h").unwrap();
+    assert!(
+        error_ratio.contains(r#"rate(payments_function_calls_total{objective_name="api",objective_percentile="99.9",result="error"}[1h])"#),
+        "{error_ratio}"
+    );
+    // The error budget of a 99.9% objective is 0.1%
+    let burn_rate = api_slo.burn_rate(Sli::SuccessRate, "1h").unwrap();
+    assert_eq!(burn_rate, format!("({error_ratio}) / 0.001"));
+    assert!(api_slo
+        .burn_rate(Sli::Latency, "6h")
+        .unwrap()
+        .contains(r#"payments_function_calls_duration_seconds_count{objective_name="api",objective_percentile="99"}[6h]"#));
+
+    let alerts = api_slo.burn_rate_alerts(Sli::SuccessRate);
+    let severities: Vec<_> = alerts.iter().map(|alert| alert.severity).collect();
+    assert_eq!(severities, ["page", "ticket"]);
+    // 14.4 times the error budget over 1 hour and 5 minutes, or 6 times over 6 hours and 30 minutes
+    assert!(alerts[0].query.contains("[1h]))) > 0.0144 and ("));
+    assert!(alerts[0].query.contains("[30m]))) > 0.006)"));
+
+    // There are no queries for the SLIs that the objective has no target for
+    let batch_slo =
+        queries::objective(Objective::new("batch").success_rate(ObjectivePercentile::P95));
+    assert_eq!(batch_slo.error_ratio(Sli::Latency, "1h"), None);
+    assert!(batch_slo.burn_rate_alerts(Sli::Latency).is_empty());
+
+    let url = queries::graph_url("sum(up)");
+    assert_eq!(
+        url,
+        "https://prometheus.example.com/graph?g0.expr=sum%28up%29&g0.tab=0"
+    );
+}
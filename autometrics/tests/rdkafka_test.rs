@@ -0,0 +1,123 @@
+#![cfg(all(feature = "rdkafka", prometheus_exporter))]
+use autometrics::{autometrics, prometheus_exporter, rdkafka as kafka};
+use rdkafka::message::{OwnedMessage, Timestamp};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use rdkafka::{ClientConfig, Message};
+use std::time::Duration;
+
+fn message(topic: &str, payload: &str) -> OwnedMessage {
+    OwnedMessage::new(
+        Some(payload.as_bytes().to_vec()),
+        None,
+        topic.to_string(),
+        Timestamp::NotAvailable,
+        0,
+        0,
+        None,
+    )
+}
+
+#[autometrics]
+fn parse_order(payload: &[u8]) -> Result<u64, String> {
+    std::str::from_utf8(payload)
+        .map_err(|err| err.to_string())?
+        .parse()
+        .map_err(|err: std::num::ParseIntError| err.to_string())
+}
+
+#[tokio::test]
+async fn consumer() {
+    prometheus_exporter::try_init().ok();
+
+    for payload in ["1", "2", "invalid"] {
+        let message = message("orders", payload);
+        kafka::process(&message, |message| {
+            parse_order(message.payload().unwrap_or_default())
+        })
+        .ok();
+    }
+    let message = message("payments", "3");
+    kafka::process_async(&message, |message| async move {
+        parse_order(message.payload().unwrap_or_default())
+    })
+    .await
+    .unwrap();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="orders""#)
+            && line.contains(r#"module="rdkafka::consumer""#)
+            && line.contains(r#"result="ok""#)
+            && line.ends_with("} 2")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="orders""#)
+            && line.contains(r#"result="error""#)
+            && line.ends_with("} 1")
+    }));
+    // Instrumented functions are reported as being called by the topic's handler
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="parse_order""#)
+            && line.contains(r#"caller_function="payments""#)
+            && line.contains(r#"caller_module="rdkafka::consumer""#)
+            && line.ends_with("} 1")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_duration_seconds_bucket{")
+            && line.contains(r#"function="payments""#)
+    }));
+}
+
+#[tokio::test]
+async fn producer() {
+    prometheus_exporter::try_init().ok();
+
+    // Nothing is listening on this port, so the message fails to be delivered
+    let producer: FutureProducer = ClientConfig::new()
+        .set("bootstrap.servers", "localhost:1")
+        .set("message.timeout.ms", "100")
+        .create()
+        .unwrap();
+    let result = kafka::send(
+        &producer,
+        FutureRecord::<(), _>::to("shipments").payload("1"),
+        Duration::from_secs(1),
+    )
+    .await;
+    assert!(result.is_err());
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="shipments""#)
+            && line.contains(r#"module="rdkafka::producer""#)
+            && line.contains(r#"result="error""#)
+            && line.ends_with("} 1")
+    }));
+}
+
+#[test]
+fn consumer_lag() {
+    prometheus_exporter::try_init().ok();
+
+    kafka::set_lag("invoices", 0, 10);
+    kafka::set_lag("invoices", 1, 20);
+    kafka::set_lag("invoices", 1, 5);
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("kafka_consumer_lag{")
+            && line.contains(r#"topic="invoices""#)
+            && line.contains(r#"partition="0""#)
+            && line.ends_with("} 10")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("kafka_consumer_lag{")
+            && line.contains(r#"topic="invoices""#)
+            && line.contains(r#"partition="1""#)
+            && line.ends_with("} 5")
+    }));
+}
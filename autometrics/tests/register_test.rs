@@ -0,0 +1,50 @@
+#![cfg(feature = "prometheus-exporter")]
+
+use autometrics::{autometrics, objectives::*, prometheus_exporter};
+
+const API_SLO: Objective = Objective::new("register").success_rate(ObjectivePercentile::P99);
+
+mod jobs {
+    #[autometrics::autometrics]
+    pub fn rotate_keys() {}
+}
+
+struct Payments;
+
+#[autometrics]
+impl Payments {
+    #[allow(dead_code)]
+    fn refund() {}
+}
+
+#[autometrics(objective = API_SLO)]
+fn checkout() {}
+
+#[test]
+fn registered_functions_start_at_zero() {
+    // Like the init_to_zero test, this is in its own file so that no other test initializes the exporter
+    prometheus_exporter::init();
+
+    // The functions are not called, but their counters should still be exported in release builds
+    autometrics::register!(checkout, objective = API_SLO);
+    autometrics::register!(jobs::rotate_keys);
+    autometrics::register!(crate::jobs::rotate_keys);
+    autometrics::register!("Payments::refund");
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    let is_zero_series = |function: &str, module: &str| {
+        metrics.lines().any(|line| {
+            line.contains(&format!(r#"function="{function}""#))
+                && line.contains(&format!(r#"module="{module}""#))
+                && line.ends_with("} 0")
+        })
+    };
+
+    assert!(is_zero_series("rotate_keys", "register_test::jobs"));
+    assert!(is_zero_series("Payments::refund", "register_test"));
+    assert!(metrics.lines().any(|line| {
+        line.contains(r#"function="checkout""#)
+            && line.contains(r#"objective_name="register""#)
+            && line.ends_with("} 0")
+    }));
+}
@@ -0,0 +1,55 @@
+#![cfg(all(prometheus_exporter, any(prometheus, prometheus_client)))]
+use autometrics::{autometrics, prometheus_exporter, registry};
+
+#[autometrics(track_concurrency)]
+fn unloaded_fn(fail: bool) -> Result<(), ()> {
+    if fail {
+        Err(())
+    } else {
+        Ok(())
+    }
+}
+
+#[autometrics]
+fn kept_fn() {}
+
+fn has_series(metrics: &str, function: &str) -> bool {
+    metrics
+        .lines()
+        .any(|line| line.contains(&format!(r#"function="{function}""#)))
+}
+
+#[test]
+fn remove_function() {
+    prometheus_exporter::try_init().ok();
+
+    unloaded_fn(false).ok();
+    unloaded_fn(true).ok();
+    kept_fn();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(has_series(&metrics, "unloaded_fn"));
+
+    // The two counters, the histogram, and the concurrency gauge
+    assert!(registry::remove_function("unloaded_fn") >= 4);
+    assert_eq!(registry::remove_function("unloaded_fn"), 0);
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(!has_series(&metrics, "unloaded_fn"));
+    assert!(has_series(&metrics, "kept_fn"));
+
+    // The function is tracked from zero if it is called again
+    unloaded_fn(false).ok();
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="unloaded_fn""#)
+            && line.contains(r#"result="ok""#)
+            && line.ends_with("} 1")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_duration_seconds_count{")
+            && line.contains(r#"function="unloaded_fn""#)
+            && line.ends_with("} 1")
+    }));
+}
@@ -0,0 +1,90 @@
+#![cfg(all(prometheus_exporter, prometheus_client))]
+
+use autometrics::scope::ScopedRegistry;
+use autometrics::{autometrics, prometheus_exporter};
+use prometheus_client::registry::Registry;
+use std::thread;
+
+#[autometrics]
+fn handle(tenant: &str) -> Result<(), String> {
+    if tenant.is_empty() {
+        Err("unknown tenant".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+#[autometrics]
+async fn handle_async() {}
+
+/// The total of the function call counter for the given function and result
+fn calls(metrics: &str, function: &str, result: &str) -> u64 {
+    metrics
+        .lines()
+        .filter(|line| {
+            line.starts_with("function_calls_total{")
+                && line.contains(&format!(r#"function="{function}""#))
+                && line.contains(&format!(r#"result="{result}""#))
+        })
+        .map(|line| line.rsplit(' ').next().unwrap().parse::<u64>().unwrap())
+        .sum()
+}
+
+#[test]
+fn scoped_registries_are_isolated() {
+    prometheus_exporter::try_init().ok();
+    let first = ScopedRegistry::new(Registry::default());
+    let second = ScopedRegistry::new(Registry::default());
+
+    thread::scope(|threads| {
+        threads.spawn(|| {
+            first.sync_scope(|| {
+                handle("first").unwrap();
+                handle("").unwrap_err();
+            })
+        });
+        threads.spawn(|| {
+            second.sync_scope(|| {
+                for _ in 0..3 {
+                    handle("second").unwrap();
+                }
+            })
+        });
+    });
+    handle("global").unwrap();
+
+    let first_metrics = first.encode_to_string().unwrap();
+    assert_eq!(calls(&first_metrics, "handle", "ok"), 1);
+    assert_eq!(calls(&first_metrics, "handle", "error"), 1);
+    assert!(first_metrics.contains("function_calls_duration_seconds_bucket{"));
+
+    let second_metrics = second.encode_to_string().unwrap();
+    assert_eq!(calls(&second_metrics, "handle", "ok"), 3);
+    assert_eq!(calls(&second_metrics, "handle", "error"), 0);
+
+    // The calls made in the scopes are not recorded in the global registry
+    let global_metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert_eq!(calls(&global_metrics, "handle", "ok"), 1);
+    assert_eq!(calls(&global_metrics, "handle", "error"), 0);
+
+    first.reset();
+    assert_eq!(calls(&first.encode_to_string().unwrap(), "handle", "ok"), 0);
+}
+
+#[tokio::test]
+async fn async_scope() {
+    prometheus_exporter::try_init().ok();
+    let scoped = ScopedRegistry::new(Registry::default());
+
+    scoped
+        .scope(async {
+            tokio::task::yield_now().await;
+            handle_async().await;
+        })
+        .await;
+
+    let metrics = scoped.encode_to_string().unwrap();
+    assert_eq!(calls(&metrics, "handle_async", ""), 1);
+    let global_metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert_eq!(calls(&global_metrics, "handle_async", ""), 0);
+}
@@ -0,0 +1,38 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::settings::{AutometricsSettings, BuildInfo};
+use autometrics::{autometrics, prometheus_exporter};
+
+#[autometrics]
+fn function_just_to_initialize_build_info() {}
+
+#[test]
+fn build_info_from_settings() {
+    AutometricsSettings::builder()
+        .build_info(BuildInfo {
+            version: Some("1.4.2".to_string()),
+            commit: Some("7f3c2a1".to_string()),
+            repo_url: Some("https://github.com/example/service".to_string()),
+            rustc_version: Some("1.82.0".to_string()),
+            target: Some("x86_64-unknown-linux-gnu".to_string()),
+            ..Default::default()
+        })
+        .runtime_build_info(true)
+        .init();
+
+    function_just_to_initialize_build_info();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(
+        metrics.lines().any(|line| line.starts_with("build_info{")
+            && line.contains(r#"version="1.4.2""#)
+            && line.contains(r#"commit="7f3c2a1""#)
+            && line.contains(r#"branch="""#)
+            && line.contains(r#"="https://github.com/example/service""#)
+            && line.contains(r#"="github""#)
+            && line.contains(r#"rustc_version="1.82.0""#)
+            && line.contains(r#"target="x86_64-unknown-linux-gnu""#)
+            && line.contains(&format!(r#"os="{}""#, std::env::consts::OS))),
+        "{metrics}"
+    );
+}
@@ -0,0 +1,58 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::{autometrics, prometheus_exporter, settings::AutometricsSettings};
+
+#[autometrics]
+fn first() {}
+
+#[autometrics]
+fn second() {}
+
+#[autometrics]
+fn third() {}
+
+#[autometrics]
+fn caller(callees: &[fn()]) {
+    for callee in callees {
+        callee();
+    }
+}
+
+/// The edges that are currently known (the `metrics` backend sets evicted edges to 0)
+fn call_edges(metrics: &str) -> Vec<&str> {
+    metrics
+        .lines()
+        .filter(|line| line.starts_with("function_call_edges_info{") && line.ends_with(" 1"))
+        .collect()
+}
+
+fn has_edge(edges: &[&str], function: &str) -> bool {
+    edges.iter().any(|line| {
+        line.contains(r#"caller_function="caller""#)
+            && line.contains(&format!(r#"function="{function}""#))
+    })
+}
+
+#[test]
+fn call_edges_info() {
+    AutometricsSettings::builder().call_edges_info(2).init();
+
+    caller(&[first, second]);
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    let edges = call_edges(&metrics);
+    assert_eq!(edges.len(), 2, "{metrics}");
+    assert!(has_edge(&edges, "first"));
+    assert!(has_edge(&edges, "second"));
+    assert!(edges
+        .iter()
+        .all(|line| line.contains(r#"caller_module="settings_call_edges_test""#)));
+
+    // The edge that was seen least recently is evicted
+    caller(&[second, third]);
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    let edges = call_edges(&metrics);
+    assert_eq!(edges.len(), 2, "{metrics}");
+    assert!(!has_edge(&edges, "first"));
+    assert!(has_edge(&edges, "second"));
+    assert!(has_edge(&edges, "third"));
+}
@@ -0,0 +1,41 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::{autometrics, prometheus_exporter, settings::AutometricsSettings};
+
+#[autometrics]
+fn tracking_parent() {
+    tracking_child()
+}
+
+#[autometrics]
+fn tracking_child() {}
+
+#[autometrics(track_concurrency)]
+async fn tracking_async_parent() {
+    tracking_child()
+}
+
+#[tokio::test]
+async fn caller_labels_are_empty_when_caller_tracking_is_disabled() {
+    AutometricsSettings::builder()
+        .disable_caller_tracking()
+        .init();
+
+    tracking_parent();
+    tracking_async_parent().await;
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    let child_calls = || {
+        metrics.lines().filter(|line| {
+            line.starts_with("function_calls_total{")
+                && line.contains(r#"function="tracking_child""#)
+        })
+    };
+    assert!(
+        child_calls()
+            .all(|line| line.contains(r#"caller_function="""#)
+                && line.contains(r#"caller_module="""#)),
+        "{metrics}"
+    );
+    assert!(child_calls().any(|line| line.ends_with("} 2")), "{metrics}");
+}
@@ -0,0 +1,65 @@
+#![cfg(all(prometheus_exporter, feature = "tracing"))]
+
+use autometrics::cardinality::CardinalityWatchdog;
+use autometrics::{autometrics, settings::AutometricsSettings};
+use std::io;
+use std::sync::{Arc, Mutex};
+
+#[derive(Clone, Default)]
+struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+impl io::Write for CapturedLogs {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[autometrics]
+fn shared() {}
+
+#[autometrics]
+fn caller_a() {
+    shared()
+}
+
+#[autometrics]
+fn caller_b() {
+    shared()
+}
+
+#[test]
+fn logs_warnings_by_default() {
+    AutometricsSettings::builder()
+        .cardinality_watchdog(CardinalityWatchdog::new(1))
+        .init();
+
+    let logs = CapturedLogs::default();
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::WARN)
+        .with_ansi(false)
+        .with_writer(move || writer.clone())
+        .finish();
+
+    tracing::subscriber::with_default(subscriber, || {
+        caller_a();
+        caller_b();
+    });
+
+    let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+    let line = logs
+        .lines()
+        .find(|line| line.contains("autometrics high cardinality"))
+        .unwrap_or_else(|| panic!("{logs}"));
+    assert!(line.contains("WARN"), "{line}");
+    assert!(line.contains("autometrics::cardinality"), "{line}");
+    assert!(line.contains(r#"function="shared""#), "{line}");
+    assert!(line.contains(r#"label="caller_function""#), "{line}");
+    assert!(line.contains("series=2"), "{line}");
+    assert!(line.contains("threshold=1"), "{line}");
+}
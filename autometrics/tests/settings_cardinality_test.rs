@@ -0,0 +1,72 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::cardinality::{CardinalityWarning, CardinalityWatchdog};
+use autometrics::{autometrics, prometheus_exporter, settings::AutometricsSettings};
+use std::sync::Mutex;
+
+static WARNINGS: Mutex<Vec<CardinalityWarning>> = Mutex::new(Vec::new());
+
+#[autometrics]
+fn shared() {}
+
+#[autometrics]
+fn caller_a() {
+    shared()
+}
+
+#[autometrics]
+fn caller_b() {
+    shared()
+}
+
+#[autometrics]
+fn caller_c() {
+    shared()
+}
+
+#[autometrics]
+fn caller_d() {
+    shared()
+}
+
+#[test]
+fn warns_about_high_cardinality() {
+    AutometricsSettings::builder()
+        .cardinality_watchdog(
+            CardinalityWatchdog::new(3)
+                .on_warning(|warning| WARNINGS.lock().unwrap().push(warning.clone())),
+        )
+        .init();
+
+    for _ in 0..2 {
+        caller_a();
+        caller_b();
+        caller_c();
+    }
+    assert!(WARNINGS.lock().unwrap().is_empty());
+
+    caller_d();
+    let warnings = WARNINGS.lock().unwrap().clone();
+    assert_eq!(warnings.len(), 1);
+    let warning = &warnings[0];
+    assert_eq!(warning.function, "shared");
+    assert_eq!(warning.module, "settings_cardinality_test");
+    assert_eq!(warning.label, "caller_function");
+    assert_eq!(warning.label_values, 4);
+    assert_eq!(warning.series, 4);
+    assert_eq!(warning.threshold, 3);
+    assert!(warning.to_string().contains(
+        r#"function="shared" module="settings_cardinality_test" label="caller_function""#
+    ));
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(
+        metrics.lines().any(
+            |line| line.starts_with("autometrics_cardinality_warnings_total{")
+                && line.contains(r#"function="shared""#)
+                && line.contains(r#"label="caller_function""#)
+                && line.ends_with(" 1")
+        ),
+        "{metrics}"
+    );
+}
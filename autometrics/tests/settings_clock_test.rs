@@ -0,0 +1,33 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::{autometrics, prometheus_exporter, settings::AutometricsSettings};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// The current time of the fake clock, in milliseconds
+static NOW: AtomicU64 = AtomicU64::new(0);
+
+#[autometrics]
+fn slow_fn() {
+    NOW.fetch_add(1500, Ordering::SeqCst);
+}
+
+#[test]
+fn latencies_are_measured_with_the_clock() {
+    AutometricsSettings::builder()
+        .clock(|| Duration::from_millis(NOW.load(Ordering::SeqCst)))
+        .init();
+
+    slow_fn();
+    slow_fn();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    let sum = metrics
+        .lines()
+        .find(|line| {
+            line.starts_with("function_calls_duration_seconds_sum{")
+                && line.contains(r#"function="slow_fn""#)
+        })
+        .and_then(|line| line.rsplit(' ').next()?.parse::<f64>().ok());
+    assert_eq!(sum, Some(3.0), "{metrics}");
+}
@@ -0,0 +1,37 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::{autometrics, prometheus_exporter, settings::AutometricsSettings};
+
+#[autometrics]
+fn cold_start_fn() {}
+
+fn duration_count(metrics: &str, cold: bool) -> Option<&str> {
+    metrics
+        .lines()
+        .filter(|line| {
+            line.starts_with("function_calls_duration_seconds_count{")
+                && line.contains(r#"function="cold_start_fn""#)
+        })
+        .find(|line| line.contains(r#"cold="true""#) == cold)
+        .and_then(|line| line.rsplit(' ').next())
+}
+
+#[test]
+fn first_call_is_labeled_as_cold() {
+    AutometricsSettings::builder().cold_start_label(true).init();
+
+    for _ in 0..3 {
+        cold_start_fn();
+    }
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert_eq!(duration_count(&metrics, true), Some("1"), "{metrics}");
+    assert_eq!(duration_count(&metrics, false), Some("2"), "{metrics}");
+    // All of the calls are counted
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="cold_start_fn""#)
+            && !line.contains("cold=")
+            && line.ends_with("} 3")
+    }));
+}
@@ -0,0 +1,38 @@
+#![cfg(all(prometheus_exporter, opentelemetry))]
+
+use autometrics::settings::{AutometricsSettings, ConcurrencyInstrument};
+use autometrics::{autometrics, prometheus_exporter};
+
+#[autometrics(track_concurrency)]
+fn nested_concurrency(n: u32) -> String {
+    if n > 0 {
+        std::thread::scope(|scope| scope.spawn(|| nested_concurrency(n - 1)).join().unwrap())
+    } else {
+        prometheus_exporter::encode_to_string().unwrap()
+    }
+}
+
+fn concurrent_calls(metrics: &str) -> Option<&str> {
+    metrics
+        .lines()
+        .find(|line| {
+            line.starts_with("function_calls_concurrent{")
+                && line.contains(r#"function="nested_concurrency""#)
+        })
+        .and_then(|line| line.rsplit(' ').next())
+}
+
+#[test]
+fn concurrency_recorded_with_gauge() {
+    AutometricsSettings::builder()
+        .concurrency_instrument(ConcurrencyInstrument::Gauge)
+        .init();
+
+    // Each call runs on its own thread, so all three calls are counted
+    let metrics = nested_concurrency(2);
+    assert_eq!(concurrent_calls(&metrics), Some("3"));
+
+    // The gauge is set to the absolute number of calls when they finish
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert_eq!(concurrent_calls(&metrics), Some("0"));
+}
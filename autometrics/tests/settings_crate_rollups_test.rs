@@ -0,0 +1,47 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::{autometrics, prometheus_exporter, settings::AutometricsSettings};
+
+#[autometrics]
+fn create_user(name: &str) -> Result<(), ()> {
+    if name.is_empty() {
+        Err(())
+    } else {
+        Ok(())
+    }
+}
+
+#[autometrics]
+fn delete_user() -> Result<(), ()> {
+    Ok(())
+}
+
+#[test]
+fn counts_calls_per_crate() {
+    AutometricsSettings::builder().crate_rollups(true).init();
+
+    create_user("alice").unwrap();
+    create_user("").unwrap_err();
+    delete_user().unwrap();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    let calls = |result: &str| -> u64 {
+        metrics
+            .lines()
+            .filter(|line| {
+                line.starts_with("crate_calls_total{")
+                    && line.contains(r#"crate="settings_crate_rollups_test""#)
+                    && line.contains(&format!(r#"result="{result}""#))
+            })
+            .filter_map(|line| line.rsplit(' ').next()?.parse::<u64>().ok())
+            .sum()
+    };
+    assert_eq!(calls("ok"), 2);
+    assert_eq!(calls("error"), 1);
+
+    // The rollup does not have the function labels
+    assert!(metrics
+        .lines()
+        .filter(|line| line.starts_with("crate_calls_total{"))
+        .all(|line| !line.contains("function=")));
+}
@@ -1,4 +1,4 @@
-#![cfg(prometheus_exporter)]
+#![cfg(all(prometheus_exporter, not(metrics)))]
 
 use autometrics::{autometrics, prometheus_exporter, settings::AutometricsSettings};
 
@@ -94,8 +94,12 @@ fn custom_prometheus_registry() {
 #[cfg(opentelemetry)]
 #[test]
 fn custom_opentelemetry_registry() {
-    use opentelemetry::{global, KeyValue};
-    use prometheus::{Registry, TextEncoder};
+    use otel::{global, KeyValue};
+    use prometheus_crate::{Registry, TextEncoder};
+    #[cfg(opentelemetry_0_24)]
+    use {opentelemetry as otel, prometheus as prometheus_crate};
+    #[cfg(opentelemetry_0_33)]
+    use {opentelemetry_0_33 as otel, prometheus_0_14 as prometheus_crate};
 
     // OpenTelemetry uses the `prometheus` crate under the hood
     let registry = Registry::new();
@@ -104,7 +108,10 @@ fn custom_opentelemetry_registry() {
         .prometheus_registry(registry)
         .init();
 
+    #[cfg(opentelemetry_0_24)]
     let custom_metric = global::meter("foo").u64_counter("custom_metric").init();
+    #[cfg(opentelemetry_0_33)]
+    let custom_metric = global::meter("foo").u64_counter("custom_metric").build();
 
     #[autometrics]
     fn hello_world() -> &'static str {
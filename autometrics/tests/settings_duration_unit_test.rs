@@ -0,0 +1,59 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::settings::{AutometricsSettings, DurationUnit};
+use autometrics::{autometrics, objectives::*, prometheus_exporter};
+
+const API_SLO: Objective =
+    Objective::new("api").latency(ObjectiveLatency::Ms250, ObjectivePercentile::P99);
+
+#[autometrics(objective = API_SLO)]
+fn api_handler() {}
+
+#[autometrics(fine_grained_latency)]
+fn decode_frame() {}
+
+#[test]
+fn records_latencies_in_milliseconds() {
+    AutometricsSettings::builder()
+        .duration_unit(DurationUnit::Milliseconds)
+        .init();
+
+    api_handler();
+    decode_frame();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    let buckets: Vec<&str> = metrics
+        .lines()
+        .filter(|line| {
+            line.starts_with("function_calls_duration_milliseconds_bucket{")
+                && line.contains(r#"function="api_handler""#)
+        })
+        .collect();
+    assert!(!buckets.is_empty(), "{metrics}");
+    assert!(
+        !metrics.contains("function_calls_duration_seconds"),
+        "{metrics}"
+    );
+
+    // The default buckets and the latency threshold are both converted to milliseconds
+    assert!(buckets
+        .iter()
+        .all(|line| line.contains(r#"objective_latency_threshold="250""#)));
+    assert!(
+        buckets
+            .iter()
+            .any(|line| line.contains(r#"le="250""#) || line.contains(r#"le="250.0""#)),
+        "{metrics}"
+    );
+
+    // Only the backends that support the `buckets` argument use the fine-grained buckets
+    #[cfg(any(prometheus, prometheus_client))]
+    assert!(
+        metrics.lines().any(|line| {
+            line.starts_with("function_calls_duration_milliseconds_bucket{")
+                && line.contains(r#"function="decode_frame""#)
+                && line.contains(r#"le="0.001""#)
+        }),
+        "{metrics}"
+    );
+}
@@ -0,0 +1,33 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::{autometrics, prometheus_exporter, settings::AutometricsSettings};
+
+#[test]
+fn set_global_labels() {
+    #[autometrics(track_concurrency)]
+    fn global_labels_fn() -> String {
+        prometheus_exporter::encode_to_string().unwrap()
+    }
+
+    AutometricsSettings::builder()
+        .global_labels(&[("region", "eu-west-1"), ("deployment.tenant", "acme")])
+        .init();
+
+    // Encode the metrics while the function is running to include the concurrency gauge
+    global_labels_fn();
+    let metrics = global_labels_fn();
+
+    for metric in [
+        "function_calls_total{",
+        "function_calls_duration_seconds_bucket{",
+        "function_calls_concurrent{",
+        "build_info{",
+    ] {
+        assert!(
+            metrics.lines().any(|line| line.starts_with(metric)
+                && line.contains(r#"region="eu-west-1""#)
+                && line.contains(r#"deployment_tenant="acme""#)),
+            "{metric} is missing the global labels"
+        );
+    }
+}
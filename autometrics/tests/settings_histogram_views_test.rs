@@ -0,0 +1,35 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::{autometrics, prometheus_exporter, settings::AutometricsSettings};
+
+#[test]
+fn histogram_views() {
+    #[autometrics]
+    fn histogram_views_fn() -> &'static str {
+        "Hello world!"
+    }
+
+    AutometricsSettings::builder()
+        .histogram_view("coarse", [30.0, 60.0])
+        .init();
+
+    histogram_views_fn();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    // The call is recorded in both the default histogram and the additional view
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_duration_seconds_count{")
+            && line.contains(r#"function="histogram_views_fn""#)
+            && line.ends_with("} 1")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_duration_coarse_seconds_bucket{")
+            && line.contains(r#"function="histogram_views_fn""#)
+            && line.contains(r#"le="30"#)
+            && line.ends_with("} 1")
+    }));
+    assert!(!metrics.lines().any(|line| {
+        line.starts_with("function_calls_duration_coarse_seconds_bucket{")
+            && line.contains(r#"le="0.1""#)
+    }));
+}
@@ -0,0 +1,62 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::settings::{AutometricsSettings, SettingsInitializationError};
+use autometrics::{autometrics, prometheus_exporter};
+
+mod api {
+    use autometrics::autometrics;
+
+    #[autometrics]
+    pub fn get_order() {}
+}
+
+#[autometrics]
+fn handle_request() {
+    api::get_order();
+}
+
+#[test]
+fn renames_function_and_module_labels() {
+    assert!(matches!(
+        AutometricsSettings::builder()
+            .label_key_overrides(&[("caller_function", "caller")])
+            .try_init(),
+        Err(SettingsInitializationError::UnsupportedLabelKeyOverride(key)) if key == "caller_function"
+    ));
+
+    AutometricsSettings::builder()
+        .label_key_overrides(&[("function", "fn"), ("module", "mod")])
+        .module_path_transform(|module| match module.split_once("::") {
+            Some((_crate_name, path)) => path.to_string(),
+            None => module.to_string(),
+        })
+        .init();
+
+    handle_request();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    let series: Vec<&str> = metrics
+        .lines()
+        .filter(|line| line.contains(r#"fn="get_order""#))
+        .collect();
+    assert!(
+        series
+            .iter()
+            .any(|line| line.starts_with("function_calls_total{")
+                && line.contains(r#"mod="api""#)
+                && line.contains(r#"caller_function="handle_request""#)
+                && line.contains(r#"caller_module="settings_label_overrides_test""#)),
+        "{metrics}"
+    );
+    assert!(
+        series.iter().any(
+            |line| line.starts_with("function_calls_duration_seconds_count{")
+                && line.contains(r#"mod="api""#)
+        ),
+        "{metrics}"
+    );
+    for key in ["function", "module"] {
+        assert!(!metrics.contains(&format!("{{{key}=")), "{metrics}");
+        assert!(!metrics.contains(&format!(",{key}=")), "{metrics}");
+    }
+}
@@ -0,0 +1,58 @@
+#![cfg(all(prometheus_exporter, prometheus_client))]
+
+use autometrics::{autometrics, prometheus_exporter, settings::AutometricsSettings};
+
+#[autometrics]
+fn shared() {}
+
+#[autometrics]
+fn caller_a() {
+    shared()
+}
+
+#[autometrics]
+fn caller_b() {
+    shared()
+}
+
+#[test]
+fn evicts_least_recently_used_label_sets() {
+    AutometricsSettings::builder().max_label_sets(3).init();
+    // The counter is initialized to zero in debug builds, in series with different labels
+    let calls = |metrics: &str, function: &str, caller: &str| -> u64 {
+        metrics
+            .lines()
+            .filter(|line| {
+                line.starts_with("function_calls_total{")
+                    && line.contains(&format!(r#"function="{function}""#))
+                    && line.contains(&format!(r#"caller_function="{caller}""#))
+            })
+            .filter_map(|line| line.rsplit(' ').next()?.parse::<u64>().ok())
+            .sum()
+    };
+
+    caller_a();
+    caller_a();
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert_eq!(calls(&metrics, "shared", "caller_a"), 2);
+    assert_eq!(calls(&metrics, "caller_a", ""), 2);
+    assert!(!metrics.contains("autometrics_label_set_evictions_total{"));
+
+    // The series of shared called by caller_a was recorded least recently, so it makes room for caller_b
+    caller_b();
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert_eq!(calls(&metrics, "shared", "caller_a"), 0);
+    assert_eq!(calls(&metrics, "caller_a", ""), 2);
+    assert_eq!(calls(&metrics, "shared", "caller_b"), 1);
+    assert_eq!(calls(&metrics, "caller_b", ""), 1);
+    assert!(metrics.lines().any(
+        |line| line.starts_with("autometrics_label_set_evictions_total{")
+            && line.contains(r#"function="shared""#)
+            && line.ends_with(" 1")
+    ));
+
+    // An evicted label set starts again from zero when it is recorded again
+    caller_a();
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert_eq!(calls(&metrics, "shared", "caller_a"), 1);
+}
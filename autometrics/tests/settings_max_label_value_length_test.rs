@@ -0,0 +1,63 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::settings::{AutometricsSettings, SettingsInitializationError};
+use autometrics::{autometrics, prometheus_exporter};
+
+mod a_very_deeply_nested_module {
+    pub mod that_was_probably_generated_by_a_build_script {
+        use autometrics::autometrics;
+
+        #[autometrics]
+        pub fn first() {}
+
+        #[autometrics]
+        pub fn second() {}
+    }
+}
+
+#[autometrics]
+fn short() {}
+
+#[test]
+fn truncates_long_label_values() {
+    assert!(matches!(
+        AutometricsSettings::builder()
+            .max_label_value_length(16)
+            .try_init(),
+        Err(SettingsInitializationError::LabelValueLengthTooShort(16))
+    ));
+
+    AutometricsSettings::builder()
+        .max_label_value_length(40)
+        .init();
+
+    a_very_deeply_nested_module::that_was_probably_generated_by_a_build_script::first();
+    a_very_deeply_nested_module::that_was_probably_generated_by_a_build_script::second();
+    short();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    let module_label = |function: &str| {
+        let line = metrics
+            .lines()
+            .find(|line| {
+                line.starts_with("function_calls_total{")
+                    && line.contains(&format!(r#"function="{function}""#))
+            })
+            .unwrap_or_else(|| panic!("no series for {function}:\n{metrics}"));
+        let start = line.find(r#",module=""#).unwrap() + r#",module=""#.len();
+        let end = start + line[start..].find('"').unwrap();
+        line[start..end].to_string()
+    };
+
+    let first = module_label("first");
+    let second = module_label("second");
+    assert_eq!(first.len(), 40);
+    assert!(first.starts_with("settings_max_label_valu~"), "{first}");
+    // Both functions are in the same module, so it is shortened the same way
+    assert_eq!(first, second);
+
+    assert_eq!(
+        module_label("short"),
+        "settings_max_label_value_length_test"
+    );
+}
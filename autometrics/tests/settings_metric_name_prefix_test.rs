@@ -0,0 +1,40 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::{autometrics, prometheus_exporter, settings::AutometricsSettings};
+
+#[test]
+fn set_metric_name_prefix() {
+    #[autometrics(track_concurrency)]
+    fn prefixed_fn() -> String {
+        prometheus_exporter::encode_to_string().unwrap()
+    }
+
+    AutometricsSettings::builder()
+        .metric_name_prefix("payments")
+        .histogram_view("coarse", [1.0, 10.0])
+        .init();
+
+    // Encode the metrics while the function is running to include the concurrency gauge
+    prefixed_fn();
+    let metrics = prefixed_fn();
+
+    for metric in [
+        "payments_function_calls_total{",
+        "payments_function_calls_duration_seconds_bucket{",
+        "payments_function_calls_duration_coarse_seconds_bucket{",
+        "payments_function_calls_concurrent{",
+    ] {
+        assert!(
+            metrics
+                .lines()
+                .any(|line| line.starts_with(metric) && line.contains(r#"function="prefixed_fn""#)),
+            "{metric} is missing"
+        );
+    }
+    assert!(metrics
+        .lines()
+        .any(|line| line.starts_with("payments_build_info{")));
+    assert!(!metrics
+        .lines()
+        .any(|line| line.starts_with("function_calls") || line.starts_with("build_info")));
+}
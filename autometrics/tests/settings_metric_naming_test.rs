@@ -0,0 +1,44 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::settings::{AutometricsSettings, MetricNaming};
+use autometrics::{autometrics, prometheus_exporter};
+
+#[autometrics]
+fn semconv_fn() {}
+
+#[test]
+fn opentelemetry_metric_names() {
+    // The view is named after the histogram even though it is added before the naming convention is set
+    AutometricsSettings::builder()
+        .histogram_view("coarse", [30.0, 60.0])
+        .metric_naming(MetricNaming::OpenTelemetry)
+        .init();
+
+    semconv_fn();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(
+        metrics.lines().any(|line| {
+            line.starts_with("function_duration_seconds_count{")
+                && line.contains(r#"function="semconv_fn""#)
+                && line.ends_with("} 1")
+        }),
+        "{metrics}"
+    );
+    assert!(
+        metrics.lines().any(|line| {
+            line.starts_with("function_duration_coarse_seconds_bucket{")
+                && line.contains(r#"function="semconv_fn""#)
+        }),
+        "{metrics}"
+    );
+    assert!(!metrics.contains("function_calls_duration"), "{metrics}");
+
+    // The other metrics keep their names
+    assert!(
+        metrics.lines().any(|line| {
+            line.starts_with("function_calls_total{") && line.contains(r#"function="semconv_fn""#)
+        }),
+        "{metrics}"
+    );
+}
@@ -0,0 +1,48 @@
+#![cfg(all(prometheus_exporter, prometheus_client))]
+
+use autometrics::{autometrics, prometheus_exporter, settings::AutometricsSettings};
+use prometheus_client::encoding::text::encode;
+use prometheus_client::registry::Registry;
+
+#[autometrics]
+fn place_order() {}
+
+#[test]
+fn shadow_registry() {
+    let settings = AutometricsSettings::builder()
+        .shadow_registry(<Registry>::default())
+        .init();
+    // The counter is initialized to zero in debug builds, in a series with different labels
+    let calls = |metrics: &str| -> u64 {
+        metrics
+            .lines()
+            .filter(|line| {
+                line.starts_with("function_calls_total{")
+                    && line.contains(r#"function="place_order""#)
+            })
+            .filter_map(|line| line.rsplit(' ').next()?.parse::<u64>().ok())
+            .sum()
+    };
+    let encode_shadow = || {
+        let mut metrics = String::new();
+        encode(&mut metrics, settings.shadow_registry().unwrap()).unwrap();
+        metrics
+    };
+
+    place_order();
+    place_order();
+
+    // The shadow registry receives the same recordings as the main one
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert_eq!(calls(&metrics), 2);
+    assert_eq!(calls(&encode_shadow()), 2);
+
+    // Resetting the shadow registry does not affect the scraped metrics
+    settings.reset_shadow_registry();
+    assert_eq!(calls(&encode_shadow()), 0);
+
+    place_order();
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert_eq!(calls(&metrics), 3);
+    assert_eq!(calls(&encode_shadow()), 1);
+}
@@ -0,0 +1,75 @@
+#![cfg(push_gateway)]
+
+use autometrics::{autometrics, prometheus_exporter};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+#[autometrics]
+fn process_batch() {}
+
+/// Start a fake Pushgateway that sends the request line of each request it receives to the returned channel
+fn fake_push_gateway() -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+
+            let mut content_length = 0;
+            loop {
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                let header = header.trim_end();
+                if header.is_empty() {
+                    break;
+                }
+                let (name, value) = header.split_once(": ").unwrap();
+                if name.eq_ignore_ascii_case("content-length") {
+                    content_length = value.parse().unwrap();
+                }
+            }
+            reader.read_exact(&mut vec![0; content_length]).unwrap();
+
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\ncontent-length: 0\r\n\r\n")
+                .unwrap();
+            sender.send(request_line.trim_end().to_string()).unwrap();
+        }
+    });
+    (format!("http://{address}"), receiver)
+}
+
+#[test]
+fn flush_and_shutdown_push_the_metrics() {
+    prometheus_exporter::try_init().ok();
+    process_batch();
+
+    let (url, requests) = fake_push_gateway();
+    let pusher = prometheus_exporter::push_to_gateway(url, "batch", Duration::from_secs(3600));
+
+    // Flushing pushes the metrics without waiting for the interval, and keeps the pusher running
+    autometrics::flush().unwrap();
+    assert_eq!(
+        requests.recv_timeout(Duration::from_secs(5)).unwrap(),
+        "PUT /metrics/job/batch HTTP/1.1"
+    );
+
+    // Shutting down pushes the metrics one last time and stops the pusher
+    autometrics::shutdown().unwrap();
+    assert_eq!(
+        requests.recv_timeout(Duration::from_secs(5)).unwrap(),
+        "PUT /metrics/job/batch HTTP/1.1"
+    );
+
+    // Nothing is left to stop, so neither the second shutdown nor dropping the handle pushes again
+    autometrics::shutdown().unwrap();
+    drop(pusher);
+    assert!(requests.recv_timeout(Duration::from_millis(200)).is_err());
+}
@@ -0,0 +1,106 @@
+#![cfg(all(prometheus_exporter, snapshots))]
+
+use autometrics::snapshot::{self, Snapshot, SnapshotError, FORMAT_VERSION};
+use autometrics::{autometrics, prometheus_exporter};
+
+#[autometrics]
+fn handle_job(fail: bool) -> Result<(), ()> {
+    if fail {
+        Err(())
+    } else {
+        Ok(())
+    }
+}
+
+#[autometrics(count_only)]
+fn poll_queue() {}
+
+/// The sum of the series with the given labels
+fn value(metrics: &str, prefix: &str, function: &str, labels: &[&str]) -> f64 {
+    metrics
+        .lines()
+        .filter(|line| {
+            line.starts_with(prefix)
+                && line.contains(&format!(r#"function="{function}""#))
+                && labels.iter().all(|label| line.contains(label))
+        })
+        .filter_map(|line| line.rsplit(' ').next()?.parse::<f64>().ok())
+        .sum()
+}
+
+#[test]
+fn snapshots_are_merged_into_the_metrics() {
+    prometheus_exporter::try_init().ok();
+
+    handle_job(false).ok();
+    handle_job(false).ok();
+    handle_job(true).ok();
+    poll_queue();
+    poll_queue();
+
+    let bytes = snapshot::take().to_bytes().unwrap();
+    let snapshot = Snapshot::from_bytes(&bytes).unwrap();
+    assert_eq!(snapshot.calls(), 5);
+    // The calls are only included in one snapshot
+    assert!(snapshot::take().is_empty());
+
+    // Merging the calls into the same process counts them twice
+    snapshot::merge(&snapshot);
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert_eq!(
+        value(
+            &metrics,
+            "function_calls_total{",
+            "handle_job",
+            &[r#"result="ok""#]
+        ),
+        4.0,
+        "{metrics}"
+    );
+    assert_eq!(
+        value(
+            &metrics,
+            "function_calls_total{",
+            "handle_job",
+            &[r#"result="error""#]
+        ),
+        2.0
+    );
+    assert_eq!(
+        value(
+            &metrics,
+            "function_calls_duration_seconds_count{",
+            "handle_job",
+            &[]
+        ),
+        6.0
+    );
+    assert_eq!(
+        value(&metrics, "function_calls_total{", "poll_queue", &[]),
+        4.0
+    );
+
+    // The merged calls are not included in the snapshots of this process
+    assert!(snapshot::take().is_empty());
+}
+
+#[test]
+fn invalid_snapshots_are_rejected() {
+    assert!(matches!(
+        Snapshot::from_bytes(b"not a snapshot"),
+        Err(SnapshotError::NotASnapshot)
+    ));
+
+    let mut bytes = Snapshot::default().to_bytes().unwrap();
+    bytes[4..8].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+    assert!(matches!(
+        Snapshot::from_bytes(&bytes),
+        Err(SnapshotError::UnsupportedVersion { version, supported })
+            if version == FORMAT_VERSION + 1 && supported == FORMAT_VERSION
+    ));
+
+    assert_eq!(
+        Snapshot::from_bytes(&Snapshot::default().to_bytes().unwrap()).unwrap(),
+        Snapshot::default()
+    );
+}
@@ -0,0 +1,87 @@
+#![cfg(all(prometheus_exporter, span_events))]
+
+use autometrics::settings::AutometricsSettings;
+use autometrics::{autometrics, objectives::*};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+const API_SLO: Objective = Objective::new("span_events")
+    .success_rate(ObjectivePercentile::P99)
+    .latency(ObjectiveLatency::Ms250, ObjectivePercentile::P99);
+
+/// The current time of the fake clock, in milliseconds
+static NOW: AtomicU64 = AtomicU64::new(0);
+
+#[autometrics(objective = API_SLO, span_events)]
+fn handler(millis: u64, fail: bool) -> Result<(), ()> {
+    NOW.fetch_add(millis, Ordering::SeqCst);
+    if fail {
+        Err(())
+    } else {
+        Ok(())
+    }
+}
+
+#[autometrics(objective = API_SLO)]
+fn without_span_events() -> Result<(), ()> {
+    NOW.fetch_add(1000, Ordering::SeqCst);
+    Err(())
+}
+
+/// Records the name of each event, the span it is in, and its fields
+#[derive(Clone, Default)]
+struct RecordEvents(Arc<Mutex<Vec<String>>>);
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for RecordEvents {
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let span = ctx.event_span(event).map(|span| span.name()).unwrap_or("");
+        let mut fields = String::new();
+        event.record(&mut FieldsVisitor(&mut fields));
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("{} in {span}:{fields}", event.metadata().name()));
+    }
+}
+
+struct FieldsVisitor<'a>(&'a mut String);
+
+impl Visit for FieldsVisitor<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() != "message" {
+            self.0.push_str(&format!(" {}={value:?}", field.name()));
+        }
+    }
+}
+
+#[test]
+fn slow_and_failed_calls() {
+    AutometricsSettings::builder()
+        .clock(|| Duration::from_millis(NOW.load(Ordering::SeqCst)))
+        .init();
+
+    let events = RecordEvents::default();
+    let subscriber = tracing_subscriber::registry().with(events.clone());
+    tracing::subscriber::with_default(subscriber, || {
+        let _span = tracing::info_span!("request").entered();
+        handler(100, false).ok();
+        handler(500, false).ok();
+        handler(100, true).ok();
+        without_span_events().ok();
+    });
+
+    let events = events.0.lock().unwrap();
+    assert_eq!(
+        *events,
+        [
+            r#"autometrics.slow_call in request: function="handler" module="span_events_test" duration_seconds=0.5 threshold_seconds=0.25"#,
+            r#"autometrics.failed_call in request: function="handler" module="span_events_test""#,
+        ]
+    );
+}
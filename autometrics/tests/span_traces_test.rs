@@ -0,0 +1,49 @@
+#![cfg(all(prometheus_exporter, span_traces))]
+
+use autometrics::autometrics;
+use autometrics::settings::AutometricsSettings;
+use autometrics::span_traces::LatestSpanTraces;
+use std::sync::Arc;
+use tracing_error::ErrorLayer;
+use tracing_subscriber::prelude::*;
+
+#[autometrics]
+fn charge(amount: u32) -> Result<(), &'static str> {
+    if amount > 100 {
+        Err("declined")
+    } else {
+        Ok(())
+    }
+}
+
+#[autometrics]
+fn refund() -> Result<(), &'static str> {
+    Ok(())
+}
+
+#[test]
+fn captures_span_traces_of_errors() {
+    tracing_subscriber::registry()
+        .with(ErrorLayer::default())
+        .init();
+    let span_traces = Arc::new(LatestSpanTraces::default());
+    AutometricsSettings::builder()
+        .span_traces(span_traces.clone())
+        .init();
+
+    let span = tracing::info_span!("checkout", order_id = 42);
+    span.in_scope(|| {
+        charge(10).ok();
+        refund().ok();
+    });
+    assert!(span_traces.all().is_empty());
+
+    span.in_scope(|| charge(500).ok());
+    let error = span_traces.get("charge").unwrap();
+    assert_eq!(error.module, module_path!());
+    assert!(!error.panic);
+    let span_trace = error.span_trace.to_string();
+    assert!(span_trace.contains("checkout"), "{span_trace}");
+    assert!(span_trace.contains("order_id"), "{span_trace}");
+    assert!(span_traces.get("refund").is_none());
+}
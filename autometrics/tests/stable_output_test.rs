@@ -0,0 +1,79 @@
+#![cfg(prometheus_exporter)]
+
+use autometrics::autometrics;
+use autometrics::prometheus_exporter::{self, RegistryHandle};
+
+#[autometrics]
+fn second_fn() {}
+
+#[autometrics]
+fn first_fn() {}
+
+const UNSORTED: &str = r#"# HELP zebra_requests_total Requests
+# TYPE zebra_requests_total counter
+zebra_requests_total{path="/b",method="GET"} 2
+zebra_requests_total{path="/a",method="GET"} 1
+# TYPE aardvark_latency histogram
+aardvark_latency_bucket{le="10",route="x"} 3
+aardvark_latency_bucket{le="+Inf",route="x"} 3
+aardvark_latency_bucket{le="2.5",route="x"} 2
+aardvark_latency_sum{route="x"} 12.5
+aardvark_latency_count{route="x"} 3
+aardvark_latency_bucket{le="+Inf",route="a \"quoted\", value"} 1
+aardvark_latency_count{route="a \"quoted\", value"} 1
+"#;
+
+#[test]
+fn sorts_families_series_and_labels() {
+    prometheus_exporter::try_init().ok();
+    prometheus_exporter::set_stable_output(true);
+    second_fn();
+    first_fn();
+
+    prometheus_exporter::merge_with(&[RegistryHandle::from_fn(|| {
+        Ok::<_, std::fmt::Error>(UNSORTED.to_string())
+    })]);
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+
+    let lines: Vec<&str> = metrics
+        .lines()
+        .filter(|line| line.starts_with("aardvark") || line.starts_with("zebra"))
+        .collect();
+    assert_eq!(
+        lines,
+        [
+            r#"aardvark_latency_bucket{le="+Inf",route="a \"quoted\", value"} 1"#,
+            r#"aardvark_latency_count{route="a \"quoted\", value"} 1"#,
+            r#"aardvark_latency_bucket{le="2.5",route="x"} 2"#,
+            r#"aardvark_latency_bucket{le="10",route="x"} 3"#,
+            r#"aardvark_latency_bucket{le="+Inf",route="x"} 3"#,
+            r#"aardvark_latency_count{route="x"} 3"#,
+            r#"aardvark_latency_sum{route="x"} 12.5"#,
+            r#"zebra_requests_total{method="GET",path="/a"} 1"#,
+            r#"zebra_requests_total{method="GET",path="/b"} 2"#,
+        ],
+        "{metrics}"
+    );
+
+    // The families are sorted by name, and the series of the autometrics counter by their labels
+    let families: Vec<&str> = metrics
+        .lines()
+        .filter_map(|line| line.strip_prefix("# TYPE "))
+        .map(|line| line.split(' ').next().unwrap().trim_end_matches("_total"))
+        .collect();
+    let mut sorted_families = families.clone();
+    sorted_families.sort();
+    assert_eq!(families, sorted_families, "{metrics}");
+
+    let position = |function: &str| {
+        metrics
+            .lines()
+            .position(|line| {
+                line.starts_with("function_calls_total{")
+                    && line.contains(&format!(r#",function="{function}""#))
+            })
+            .unwrap_or_else(|| panic!("{metrics}"))
+    };
+    assert!(position("first_fn") < position("second_fn"), "{metrics}");
+}
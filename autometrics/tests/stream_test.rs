@@ -0,0 +1,59 @@
+#![cfg(stream)]
+
+use autometrics::autometrics;
+use autometrics::settings::AutometricsSettings;
+use autometrics::stream::{self, Deltas};
+use futures_core::Stream;
+use std::future::poll_fn;
+use std::pin::Pin;
+use std::time::Duration;
+
+#[autometrics]
+fn lookup(fail: bool) -> Result<(), ()> {
+    if fail {
+        Err(())
+    } else {
+        Ok(())
+    }
+}
+
+async fn next(deltas: &mut stream::DeltaStream) -> Deltas {
+    poll_fn(|cx| Pin::new(&mut *deltas).poll_next(cx))
+        .await
+        .expect("the stream ended")
+}
+
+#[tokio::test(start_paused = true)]
+async fn deltas_per_window() {
+    AutometricsSettings::builder()
+        .stream_interval(Duration::from_millis(100))
+        .init();
+
+    // Calls before the first subscriber are not included
+    lookup(false).ok();
+
+    let mut deltas = stream::subscribe();
+    lookup(false).ok();
+    lookup(false).ok();
+    lookup(true).ok();
+
+    let window = next(&mut deltas).await;
+    assert_eq!(window.interval, Duration::from_millis(100));
+    let lookup_delta = window.function("lookup", module_path!()).unwrap();
+    assert_eq!(lookup_delta.calls, 3);
+    assert_eq!(lookup_delta.errors, 1);
+    let latency = &lookup_delta.latency;
+    assert_eq!(latency.count, 3);
+    assert_eq!(latency.bucket_counts.iter().sum::<u64>(), 3);
+    let p99 = latency.quantile(0.99).unwrap();
+    assert!(latency.min.unwrap() <= p99 && p99 <= latency.max.unwrap());
+
+    // Each window only has the calls made since the previous one
+    lookup(true).ok();
+    let window = next(&mut deltas).await;
+    let lookup_delta = window.function("lookup", module_path!()).unwrap();
+    assert_eq!((lookup_delta.calls, lookup_delta.errors), (1, 1));
+
+    let window = next(&mut deltas).await;
+    assert!(window.functions.is_empty());
+}
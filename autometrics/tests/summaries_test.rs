@@ -0,0 +1,41 @@
+#![cfg(summaries)]
+use autometrics::{autometrics, summaries};
+use std::{thread::sleep, time::Duration};
+
+#[autometrics]
+fn sleep_10ms() {
+    sleep(Duration::from_millis(10));
+}
+
+#[autometrics]
+fn never_called() {}
+
+#[test]
+fn summaries() {
+    for _ in 0..5 {
+        sleep_10ms();
+    }
+
+    let median = summaries::quantile("sleep_10ms", module_path!(), 0.5).unwrap();
+    assert!((0.01..1.0).contains(&median), "median was {median}");
+    assert!(summaries::functions().contains(&("sleep_10ms", module_path!())));
+    assert_eq!(
+        summaries::quantile("never_called", module_path!(), 0.5),
+        None
+    );
+
+    #[cfg(summaries_metrics_util)]
+    {
+        let summary = summaries::summary("sleep_10ms", module_path!()).unwrap();
+        assert_eq!(summary.count(), 5);
+        assert!(summary.min() >= 0.01);
+    }
+
+    #[cfg(summaries_hdrhistogram)]
+    {
+        let histogram = summaries::hdr_histogram("sleep_10ms", module_path!()).unwrap();
+        assert_eq!(histogram.len(), 5);
+        // The values are recorded in nanoseconds
+        assert!(histogram.min() >= 10_000_000);
+    }
+}
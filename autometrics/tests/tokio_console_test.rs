@@ -0,0 +1,65 @@
+#![cfg(all(prometheus_exporter, tokio_console))]
+
+use autometrics::tokio_console::TaskNameLayer;
+use autometrics::{autometrics, prometheus_exporter};
+use tracing_subscriber::prelude::*;
+
+#[autometrics(track_concurrency(max))]
+fn poll_queue() {}
+
+/// The value of the gauge for the `poll_queue` function in the given task,
+/// or in no task if it is `None` (which the `prometheus` backend encodes as an empty `task` label)
+fn gauge(metrics: &str, name: &str, task: Option<&str>) -> Option<u64> {
+    metrics
+        .lines()
+        .filter(|line| {
+            line.starts_with(&format!("{name}{{")) && line.contains(r#"function="poll_queue""#)
+        })
+        .find(|line| match task {
+            Some(task) => line.contains(&format!(r#"task="{task}""#)),
+            None => !line.contains("task=") || line.contains(r#"task="""#),
+        })
+        .and_then(|line| line.rsplit(' ').next()?.parse().ok())
+}
+
+#[test]
+fn concurrency_gauges_have_task_names() {
+    prometheus_exporter::init();
+    let subscriber = tracing_subscriber::registry().with(TaskNameLayer::new());
+
+    tracing::subscriber::with_default(subscriber, || {
+        // This is the span that Tokio runs a task named with `tokio::task::Builder::name` in,
+        // when it is built with `--cfg tokio_unstable` for tokio-console
+        let task = tracing::trace_span!(target: "tokio::task", "runtime.spawn", task.name = %"queue-worker");
+        task.in_scope(|| {
+            let _request = tracing::info_span!("request").entered();
+            poll_queue();
+        });
+
+        // Unnamed tasks have an empty name
+        let unnamed = tracing::trace_span!(target: "tokio::task", "runtime.spawn", task.name = %"");
+        unnamed.in_scope(poll_queue);
+        poll_queue();
+    });
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert_eq!(
+        gauge(&metrics, "function_calls_concurrent", Some("queue-worker")),
+        Some(0)
+    );
+    assert_eq!(
+        gauge(
+            &metrics,
+            "function_calls_concurrent_max",
+            Some("queue-worker")
+        ),
+        Some(1)
+    );
+    assert_eq!(gauge(&metrics, "function_calls_concurrent", None), Some(0));
+
+    // The task name is not added to the other metrics
+    assert!(metrics
+        .lines()
+        .filter(|line| line.contains(r#"task="queue-worker""#))
+        .all(|line| line.starts_with("function_calls_concurrent")));
+}
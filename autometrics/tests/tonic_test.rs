@@ -0,0 +1,83 @@
+#![cfg(all(feature = "tonic", prometheus_exporter))]
+use autometrics::integrations::tonic::AutometricsLayer;
+use autometrics::{autometrics, objectives::*, prometheus_exporter};
+use http::{Request, Response};
+use std::convert::Infallible;
+use tonic::Code;
+use tower::{service_fn, ServiceBuilder, ServiceExt};
+
+const GRPC_SLO: Objective = Objective::new("grpc").success_rate(ObjectivePercentile::P99);
+
+#[autometrics]
+async fn get_order(id: &str) -> Code {
+    match id {
+        "missing" => Code::NotFound,
+        "fail" => Code::Internal,
+        _ => Code::Ok,
+    }
+}
+
+#[tokio::test]
+async fn layer() {
+    prometheus_exporter::try_init().ok();
+
+    let service = ServiceBuilder::new()
+        .layer(
+            AutometricsLayer::new()
+                .ok_codes([Code::NotFound])
+                .objective(GRPC_SLO),
+        )
+        .service(service_fn(|request: Request<String>| async move {
+            let code = if request.uri().path() == "/orders.Orders/GetOrder" {
+                get_order(request.body()).await
+            } else {
+                Code::Unimplemented
+            };
+            // Like tonic, only set the status in the headers if the call failed
+            let mut response = Response::builder();
+            if code != Code::Ok {
+                response = response.header("grpc-status", (code as i32).to_string());
+            }
+            Ok::<_, Infallible>(response.body(()).unwrap())
+        }));
+
+    for (path, id) in [
+        ("/orders.Orders/GetOrder", "1"),
+        ("/orders.Orders/GetOrder", "missing"),
+        ("/orders.Orders/GetOrder", "fail"),
+        ("/orders.Orders/DeleteOrder", "1"),
+        ("/not-grpc", "1"),
+    ] {
+        service
+            .clone()
+            .oneshot(Request::post(path).body(id.to_string()).unwrap())
+            .await
+            .unwrap();
+    }
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="GetOrder""#)
+            && line.contains(r#"module="orders.Orders""#)
+            && line.contains(r#"objective_name="grpc""#)
+            && line.contains(r#"result="ok""#)
+            && line.ends_with("} 2")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="GetOrder""#)
+            && line.contains(r#"result="error""#)
+            && line.ends_with("} 1")
+    }));
+    // The handler is reported as being called by the gRPC method
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="get_order""#)
+            && line.contains(r#"caller_function="GetOrder""#)
+            && line.contains(r#"caller_module="orders.Orders""#)
+            && line.ends_with("} 3")
+    }));
+    assert!(!metrics.contains("DeleteOrder"));
+    assert!(!metrics.contains("not-grpc"));
+}
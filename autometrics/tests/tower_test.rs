@@ -0,0 +1,96 @@
+#![cfg(all(feature = "tower", prometheus_exporter))]
+use autometrics::middleware::tower::AutometricsLayer;
+use autometrics::{autometrics, objectives::*, prometheus_exporter};
+use http::{Request, Response, StatusCode};
+use std::convert::Infallible;
+use tower::{service_fn, ServiceBuilder, ServiceExt};
+
+const API_SLO: Objective = Objective::new("tower").success_rate(ObjectivePercentile::P99);
+
+#[autometrics]
+async fn tower_handler(path: String) -> StatusCode {
+    if path == "/orders/fail" {
+        StatusCode::INTERNAL_SERVER_ERROR
+    } else {
+        StatusCode::OK
+    }
+}
+
+#[tokio::test]
+async fn layer() {
+    prometheus_exporter::try_init().ok();
+
+    let service = ServiceBuilder::new()
+        .layer(
+            AutometricsLayer::new(|request: &Request<()>| {
+                request
+                    .uri()
+                    .path()
+                    .starts_with("/orders/")
+                    .then(|| "/orders/:id".to_string())
+            })
+            .objective(API_SLO),
+        )
+        .service(service_fn(|request: Request<()>| async move {
+            let status = tower_handler(request.uri().path().to_string()).await;
+            let mut response = Response::new(());
+            *response.status_mut() = status;
+            Ok::<_, Infallible>(response)
+        }));
+
+    for uri in ["/orders/1", "/orders/2", "/orders/fail", "/untracked"] {
+        service
+            .clone()
+            .oneshot(Request::get(uri).body(()).unwrap())
+            .await
+            .unwrap();
+    }
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="/orders/:id""#)
+            && line.contains(r#"module="http""#)
+            && line.contains(r#"objective_name="tower""#)
+            && line.contains(r#"result="ok""#)
+            && line.ends_with("} 2")
+    }));
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="/orders/:id""#)
+            && line.contains(r#"result="error""#)
+            && line.ends_with("} 1")
+    }));
+    // The handler is reported as being called by the route
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="tower_handler""#)
+            && line.contains(r#"caller_function="/orders/:id""#)
+            && line.ends_with("} 3")
+    }));
+    assert!(!metrics.contains("untracked"));
+}
+
+#[cfg(feature = "axum")]
+#[tokio::test]
+async fn axum_matched_path() {
+    use axum::{body::Body, routing::get, Router};
+
+    prometheus_exporter::try_init().ok();
+
+    let app: Router = Router::new()
+        .route("/items/:id", get(|| async { "item" }))
+        .layer(AutometricsLayer::matched_path());
+
+    app.oneshot(Request::get("/items/1").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+
+    let metrics = prometheus_exporter::encode_to_string().unwrap();
+    assert!(metrics.lines().any(|line| {
+        line.starts_with("function_calls_total{")
+            && line.contains(r#"function="/items/:id""#)
+            && line.contains(r#"module="http""#)
+            && line.contains(r#"result="ok""#)
+    }));
+}
@@ -0,0 +1,59 @@
+#![cfg(all(prometheus_exporter, wide_events))]
+
+use autometrics::settings::AutometricsSettings;
+use autometrics::wide_events::{CallEvent, WideEvents};
+use autometrics::{autometrics, objectives::*};
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+
+const API_SLO: Objective = Objective::new("wide").success_rate(ObjectivePercentile::P99);
+
+static EVENTS: Lazy<Mutex<Vec<CallEvent>>> = Lazy::new(Default::default);
+
+#[autometrics(objective = API_SLO)]
+fn sampled_fn(fail: bool) -> Result<(), ()> {
+    if fail {
+        Err(())
+    } else {
+        Ok(())
+    }
+}
+
+#[autometrics]
+fn caller_fn() {
+    sampled_fn(false).ok();
+}
+
+#[test]
+fn emit_wide_events() {
+    AutometricsSettings::builder()
+        .service_name("wide_events")
+        .wide_events(
+            WideEvents::new(|event: &CallEvent| EVENTS.lock().unwrap().push(event.clone()))
+                // Only the errors are emitted
+                .sample_rate(0.0)
+                .always_emit_errors(true),
+        )
+        .init();
+
+    for _ in 0..10 {
+        caller_fn();
+    }
+    sampled_fn(true).ok();
+
+    let events = EVENTS.lock().unwrap();
+    assert_eq!(events.len(), 1);
+    let event = &events[0];
+    assert_eq!(event.function, "sampled_fn");
+    assert_eq!(event.module, module_path!());
+    assert_eq!(event.service_name, "wide_events");
+    assert_eq!(event.caller_function, "");
+    assert_eq!(event.result, Some("error"));
+    assert_eq!(event.objective_name, Some("wide"));
+    assert_eq!(event.trace_id, None);
+
+    let json = event.to_string();
+    assert!(json.starts_with(r#"{"function":"sampled_fn","module":"wide_events_test","#));
+    assert!(json.contains(r#""result":"error""#));
+    assert!(json.contains(r#""objective_name":"wide""#));
+}
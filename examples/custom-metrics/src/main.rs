@@ -14,7 +14,7 @@ pub fn get_index_handler() -> Result<String, ()> {
 pub fn function_with_custom_metrics_metric() {
     use metrics::counter;
 
-    counter!("custom_metrics_counter", 1, "foo" => "bar");
+    counter!("custom_metrics_counter", "foo" => "bar").increment(1);
 }
 
 // Run the example with `--features=opentelemetry` to use the `opentelemetry` crate to define additional metrics.
@@ -16,6 +16,8 @@ async fn main() -> Result<(), Box<dyn Error + Send + Sync + 'static>> {
     // would cause it to be dropped immediately and thus shut down.
     let _meter_provider = otel_push_exporter::init_http("http://0.0.0.0:4318")?;
     // or: otel_push_exporter::init_grpc("http://0.0.0.0:4317");
+    // or, to configure the temporality, resource, or aggregation:
+    // otel_push_exporter::builder().with_temporality(Temporality::Delta).init_http("http://0.0.0.0:4318");
 
     for _ in 0..100 {
         do_stuff().await;
@@ -0,0 +1,91 @@
+use autometrics::{
+    autometrics, exemplars::tracing::AutometricsExemplarExtractor, prometheus_exporter,
+};
+use autometrics_example_util::run_prometheus_with_args;
+use axum::{http::header::CONTENT_TYPE, response::Response, routing::get, Router};
+use std::error::Error;
+use std::net::Ipv4Addr;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tracing::instrument;
+use tracing_subscriber::{prelude::*, EnvFilter};
+use uuid::Uuid;
+
+const SERVER_CONFIG_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/prometheus-server.yml");
+const AGENT_CONFIG_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/prometheus-agent.yml");
+
+// Autometrics looks for a field called `trace_id` and attaches
+// that as an exemplar for the metrics it generates.
+#[autometrics]
+#[instrument(fields(trace_id = %Uuid::new_v4()))]
+async fn checkout() -> &'static str {
+    autometrics_example_util::sleep_random_duration().await;
+    "Thank you for your order!"
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    prometheus_exporter::init();
+    tracing_subscriber::fmt::fmt()
+        .finish()
+        .with(EnvFilter::from_default_env())
+        .with(AutometricsExemplarExtractor::from_fields(&["trace_id"]))
+        .init();
+
+    // Fail at startup instead of silently losing the exemplars if they cannot be scraped
+    let content_type = prometheus_exporter::openmetrics_with_exemplars_guard()?;
+
+    // The server stores the samples and exemplars that the agent writes to it
+    let _server = run_prometheus_with_args(&[
+        "--config.file",
+        SERVER_CONFIG_PATH,
+        "--storage.tsdb.path",
+        "data/prometheus-server",
+        "--web.enable-remote-write-receiver",
+        "--enable-feature=exemplar-storage",
+    ]);
+    // The agent scrapes this example and forwards everything to the server
+    // (use `--enable-feature=agent` instead of `--agent` with Prometheus 2)
+    let _agent = run_prometheus_with_args(&[
+        "--agent",
+        "--config.file",
+        AGENT_CONFIG_PATH,
+        "--storage.agent.path",
+        "data/prometheus-agent",
+        "--web.listen-address",
+        "localhost:9091",
+    ]);
+    tokio::spawn(generate_random_traffic());
+
+    let app = Router::new().route("/checkout", get(checkout)).route(
+        "/metrics",
+        // A custom handler must set the content type returned by the guard,
+        // because the Prometheus text format does not have exemplars
+        get(move || async move {
+            let metrics = prometheus_exporter::encode_to_string().unwrap_or_default();
+            Response::builder()
+                .header(CONTENT_TYPE, content_type)
+                .body(metrics)
+                .expect("Error building response")
+        }),
+    );
+
+    println!("\nVisit the following URL to see the latency of the checkout function along with the exemplars:");
+    println!("http://localhost:9090/graph?g0.expr=histogram_quantile(0.99%2C%20sum%20by%20(le)%20(rate(function_calls_duration_seconds_bucket%7Bfunction%3D%22checkout%22%7D%5B1m%5D)))&g0.tab=0&g0.show_exemplars=1&g0.range_input=15m");
+
+    let listener = TcpListener::bind((Ipv4Addr::from([127, 0, 0, 1]), 3000)).await?;
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+pub async fn generate_random_traffic() {
+    let client = reqwest::Client::new();
+    loop {
+        client
+            .get("http://localhost:3000/checkout")
+            .send()
+            .await
+            .ok();
+        tokio::time::sleep(Duration::from_millis(100)).await
+    }
+}
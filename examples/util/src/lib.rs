@@ -22,8 +22,24 @@ pub fn run_prometheus(enable_exemplars: bool) -> ChildGuard {
         args.push("--enable-feature=exemplar-storage");
     }
 
+    let child = spawn_prometheus(&args);
+    eprintln!("Running Prometheus on port 9090 (using config file: {PROMETHEUS_CONFIG_PATH})");
+    if enable_exemplars {
+        eprintln!("Exemplars are enabled (using the flag: --enable-feature=exemplar-storage)");
+    }
+    child
+}
+
+/// Run the `prometheus` binary with the given arguments, for examples that need their own config
+pub fn run_prometheus_with_args(args: &[&str]) -> ChildGuard {
+    let child = spawn_prometheus(args);
+    eprintln!("Running Prometheus with: {}", args.join(" "));
+    child
+}
+
+fn spawn_prometheus(args: &[&str]) -> ChildGuard {
     match Command::new("prometheus")
-        .args(&args)
+        .args(args)
         .stdout(Stdio::null())
         .stderr(Stdio::null())
         .spawn()
@@ -34,17 +50,7 @@ pub fn run_prometheus(enable_exemplars: bool) -> ChildGuard {
         Err(err) => {
             panic!("Failed to start prometheus: {}", err);
         }
-        Ok(child) => {
-            eprintln!(
-                "Running Prometheus on port 9090 (using config file: {PROMETHEUS_CONFIG_PATH})",
-            );
-            if enable_exemplars {
-                eprintln!(
-                    "Exemplars are enabled (using the flag: --enable-feature=exemplar-storage)"
-                );
-            }
-            ChildGuard(child)
-        }
+        Ok(child) => ChildGuard(child),
     }
 }
 